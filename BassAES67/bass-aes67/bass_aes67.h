@@ -45,17 +45,26 @@ extern "C" {
 // Clock settings
 #define BASS_CONFIG_AES67_CLOCK_MODE            0x20019  // Clock mode (see BASS_AES67_CLOCK_*)
 #define BASS_CONFIG_AES67_CLOCK_FALLBACK_TIMEOUT 0x2001A // Fallback timeout in seconds (0=disabled, default 5)
+#define BASS_CONFIG_AES67_PTP_INSTANCE          0x2001B  // bass_ptp instance to follow (BASS_PTP_CreateInstance handle, 0=default)
+#define BASS_CONFIG_AES67_NTP_SERVER            0x2001C  // NTP server "host[:port]" for BASS_AES67_CLOCK_NTP (string ptr, default pool.ntp.org)
+#define BASS_CONFIG_AES67_CLOCK_STREAM          0x2001D  // aes67 input stream (HSTREAM) for BASS_AES67_CLOCK_STREAM (0=none)
+#define BASS_CONFIG_AES67_CLOCK_SLEW_RATE       0x2001E  // Frequency slew limit across clock switches, PPM/s x 1000 (0=unlimited, default 1000)
+#define BASS_CONFIG_AES67_CLOCK_RETURN_DELAY    0x2001F  // Seconds the clock must stay locked before fallback ends (default 3)
 
 // Clock mode values (for BASS_CONFIG_AES67_CLOCK_MODE)
 #define BASS_AES67_CLOCK_PTP        0  // IEEE 1588v2 PTP (default)
 #define BASS_AES67_CLOCK_LIVEWIRE   1  // Axia Livewire Clock
 #define BASS_AES67_CLOCK_SYSTEM     2  // System clock (free-running, no sync)
+#define BASS_AES67_CLOCK_NTP        3  // System clock rate-matched to an NTP server (needs bass_ntp_clock)
+#define BASS_AES67_CLOCK_STREAM     4  // Recovered from an incoming stream (see BASS_CONFIG_AES67_CLOCK_STREAM)
 
 // Clock state values (for BASS_CONFIG_AES67_PTP_STATE)
 #define BASS_AES67_PTP_DISABLED     0  // Clock not running
 #define BASS_AES67_PTP_LISTENING    1  // Waiting for master
 #define BASS_AES67_PTP_UNCALIBRATED 2  // Syncing with master
 #define BASS_AES67_PTP_SLAVE        3  // Locked to master (or fallback active)
+#define BASS_AES67_PTP_MASTER       4  // Acting as PTP grandmaster
+#define BASS_AES67_PTP_HOLDOVER     5  // Master lost, running on last frequency
 
 // Clock control functions (for output-only mode without input streams)
 // Set BASS_CONFIG_AES67_INTERFACE, BASS_CONFIG_AES67_CLOCK_MODE, and
@@ -64,6 +73,35 @@ extern "C" {
 BOOL BASSDEF(BASS_AES67_ClockStart)();  // Start clock (returns TRUE on success)
 BOOL BASSDEF(BASS_AES67_ClockStop)();   // Stop clock (returns TRUE on success)
 
+// Clock events (BASS_AES67_ClockSetEventCallback), called on a thread of
+// their own with the clock mode (BASS_AES67_CLOCK_*) the event is about
+#define BASS_AES67_CLOCK_EVENT_LOCKED           1  // Clock locked
+#define BASS_AES67_CLOCK_EVENT_LOST             2  // Clock lost lock
+#define BASS_AES67_CLOCK_EVENT_FALLBACK_ENTERED 3  // System clock stands in (after the fallback timeout)
+#define BASS_AES67_CLOCK_EVENT_FALLBACK_EXITED  4  // Clock followed again (after the return delay)
+#define BASS_AES67_CLOCK_EVENT_GM_CHANGED       5  // PTP grandmaster changed
+typedef void (CLOCKEVENTPROC)(DWORD event, DWORD clock_mode, void* user);  // cdecl
+BOOL BASSDEF(BASS_AES67_ClockSetEventCallback)(CLOCKEVENTPROC* proc, void* user);  // proc NULL = stop events
+
+// Livewire source discovery (LWAP). Streams can be opened by channel number:
+// aes67://lw/<channel> uses the advertised multicast group, or 239.192.x.y
+// (x.y = channel number) if the channel hasn't been advertised yet.
+// Set BASS_CONFIG_AES67_INTERFACE before calling BASS_AES67_LivewireDiscoveryStart()
+
+BOOL BASSDEF(BASS_AES67_LivewireDiscoveryStart)();  // Start listening for advertisements
+BOOL BASSDEF(BASS_AES67_LivewireDiscoveryStop)();   // Stop listening
+// Discovered sources, one line per source: channel\tname\tsource IP\tstream type\tmulticast group
+// Returned string is valid until the next call
+const char* BASSDEF(BASS_AES67_LivewireGetSources)();
+
+// Livewire node: outputs offered with BASS_AES67_OutputSetLivewire() are
+// advertised as sources, and inputs opened with dst=N (e.g.
+// aes67://lw/4001?dst=1&name=Rec+1) can be routed by consoles via LWRP.
+// Set BASS_CONFIG_AES67_INTERFACE before calling BASS_AES67_LivewireNodeStart()
+
+BOOL BASSDEF(BASS_AES67_LivewireNodeStart)(DWORD port, const char* password);  // port 0 = 93, password NULL = any
+BOOL BASSDEF(BASS_AES67_LivewireNodeStop)();
+
 // =============================================================================
 // AES67 OUTPUT STREAM
 // =============================================================================
@@ -97,6 +135,14 @@ BOOL BASSDEF(BASS_AES67_OutputStop)(HAES67OUTPUT handle);
 BOOL BASSDEF(BASS_AES67_OutputGetStats)(HAES67OUTPUT handle, BASS_AES67_OUTPUT_STATS* stats);
 BOOL BASSDEF(BASS_AES67_OutputIsRunning)(HAES67OUTPUT handle);
 DWORD BASSDEF(BASS_AES67_OutputGetPPM)(HAES67OUTPUT handle);  // Returns PPM x 1000
+// Livewire stream formats (BASS_AES67_OutputSetLivewireFormat; inputs use &lw=standard|live|surround)
+#define BASS_AES67_LW_STANDARD      1  // Stereo, 240 samples (5 ms) per packet
+#define BASS_AES67_LW_LIVESTREAM    2  // Stereo, 12 samples (250 us) per packet
+#define BASS_AES67_LW_SURROUND      3  // 8 channels (5.1 + stereo downmix), 12 samples per packet
+BOOL BASSDEF(BASS_AES67_OutputSetLivewireFormat)(HAES67OUTPUT handle, DWORD format);  // Output must be stopped
+BOOL BASSDEF(BASS_AES67_OutputSetLivewire)(HAES67OUTPUT handle, DWORD channel, const char* name);  // channel 0 = withdraw
+// Own clock: "ptp:N", "lw", "sys", "ntp" or "stream:HANDLE" (NULL = global; inputs use &clock=)
+BOOL BASSDEF(BASS_AES67_OutputSetClock)(HAES67OUTPUT handle, const char* clock);  // Output must be stopped
 BOOL BASSDEF(BASS_AES67_OutputFree)(HAES67OUTPUT handle);
 
 #ifdef __cplusplus
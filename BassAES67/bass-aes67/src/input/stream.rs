@@ -4,13 +4,14 @@
 
 use std::ffi::c_void;
 use std::net::{UdpSocket, Ipv4Addr, SocketAddrV4};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 
 use ringbuf::{HeapRb, traits::{Producer, Consumer, Split, Observer}};
 
 use super::url::Aes67Url;
+use crate::clock_bindings::ClockLease;
 use super::rtp::{RtpPacket, convert_24bit_be_to_float};
 use crate::ffi::*;
 use crate::ffi::addon::AddonFunctions;
@@ -35,6 +36,25 @@ impl StreamStats {
     }
 }
 
+/// What the receiver thread takes over when the stream starts
+struct ReceiverParams {
+    socket: UdpSocket,
+    running: Arc<AtomicBool>,
+    ended: Arc<AtomicBool>,
+    stats: Arc<StreamStats>,
+    producer: ringbuf::HeapProd<f32>,
+    expected_pt: u8,
+    channels: u16,
+    sample_rate: u32,
+    /// Multicast group to be joined (0 = none), changed by routing
+    group: Arc<AtomicU32>,
+    interface: Ipv4Addr,
+    /// Group the socket joined when it was created
+    joined: Ipv4Addr,
+    /// Media clock key of the stream
+    clock_key: usize,
+}
+
 /// AES67 input stream with lock-free architecture
 pub struct Aes67Stream {
     /// Ring buffer consumer (audio callback reads from here)
@@ -49,6 +69,13 @@ pub struct Aes67Stream {
     pub handle: HSTREAM,
     /// Stream configuration
     config: Aes67Url,
+    /// Multicast group to receive (as u32, 0 = none); changed by Livewire
+    /// routing and picked up by the receiver thread
+    group: Arc<AtomicU32>,
+    /// Key identifying this stream as a media clock source
+    clock_key: usize,
+    /// Clock this stream follows (the process-wide clock unless the URL names one)
+    clock: ClockLease,
     /// Statistics (lock-free)
     stats: Arc<StreamStats>,
     /// Target buffer level in samples
@@ -97,6 +124,9 @@ impl Aes67Stream {
             ended: Arc::new(AtomicBool::new(false)),
             receiver_thread: None,
             handle: 0,
+            group: Arc::new(AtomicU32::new(u32::from(config.multicast_addr))),
+            clock_key: crate::media_clock::new_source_key(),
+            clock: ClockLease::global(),
             config,
             stats: Arc::new(StreamStats::new()),
             target_samples,
@@ -145,16 +175,22 @@ impl Aes67Stream {
         self.ended.store(false, Ordering::SeqCst);
         self.buffering.store(true, Ordering::Relaxed);
 
-        let running = self.running.clone();
-        let ended = self.ended.clone();
-        let stats = self.stats.clone();
-        let payload_type = self.config.payload_type;
-        let channels = self.config.channels;
-        let sample_rate = self.config.sample_rate;
+        let params = ReceiverParams {
+            socket,
+            running: self.running.clone(),
+            ended: self.ended.clone(),
+            stats: self.stats.clone(),
+            producer,
+            expected_pt: self.config.payload_type,
+            channels: self.config.channels,
+            sample_rate: self.config.sample_rate,
+            group: self.group.clone(),
+            interface: self.config.interface.unwrap_or(Ipv4Addr::UNSPECIFIED),
+            joined: self.config.multicast_addr,
+            clock_key: self.clock_key,
+        };
 
-        self.receiver_thread = Some(thread::spawn(move || {
-            Self::receiver_loop(socket, running, ended, stats, producer, payload_type, channels, sample_rate);
-        }));
+        self.receiver_thread = Some(thread::spawn(move || Self::receiver_loop(params)));
 
         Ok(())
     }
@@ -197,20 +233,42 @@ impl Aes67Stream {
 
     /// Receiver thread loop - reads packets and pushes samples to ring buffer.
     /// This is the ONLY thread that writes to the ring buffer (single producer).
-    fn receiver_loop(
-        socket: UdpSocket,
-        running: Arc<AtomicBool>,
-        ended: Arc<AtomicBool>,
-        stats: Arc<StreamStats>,
-        mut producer: ringbuf::HeapProd<f32>,
-        expected_pt: u8,
-        channels: u16,
-        sample_rate: u32,
-    ) {
+    fn receiver_loop(params: ReceiverParams) {
+        let ReceiverParams {
+            socket,
+            running,
+            ended,
+            stats,
+            mut producer,
+            expected_pt,
+            channels,
+            sample_rate,
+            group,
+            interface,
+            joined,
+            clock_key,
+        } = params;
         let mut buf = [0u8; 2048];
         let mut sample_buf = vec![0.0f32; 480 * channels as usize]; // Max samples per packet
+        let mut joined = Some(joined);
 
         while running.load(Ordering::SeqCst) {
+            // Retune when the destination was routed elsewhere
+            let wanted = match group.load(Ordering::SeqCst) {
+                0 => None,
+                a => Some(Ipv4Addr::from(a)),
+            };
+            if wanted != joined {
+                if let Some(old) = joined.take() {
+                    let _ = socket.leave_multicast_v4(&old, &interface);
+                }
+                if let Some(new) = wanted {
+                    if socket.join_multicast_v4(&new, &interface).is_ok() {
+                        joined = Some(new);
+                    }
+                }
+            }
+
             match socket.recv(&mut buf) {
                 Ok(len) => {
                     if len < 12 {
@@ -223,6 +281,7 @@ impl Aes67Stream {
                         }
 
                         stats.packets_received.fetch_add(1, Ordering::Relaxed);
+                        crate::media_clock::feed(clock_key, packet.header.timestamp);
 
                         // Convert to float samples
                         let sample_count = packet.sample_count(channels);
@@ -349,8 +408,8 @@ impl Aes67Stream {
         // Clock feedforward: match output's consumption rate when clock is locked
         // Output uses interval_factor = 1.0 - (ppm / 1e6), so it sends FASTER when ppm > 0
         // We need to consume FASTER too, so add ppm to our ratio
-        let clock_feedforward = if crate::clock_bindings::clock_is_locked() {
-            let ppm = crate::clock_bindings::clock_get_frequency_ppm();
+        let clock_feedforward = if self.clock.is_locked() {
+            let ppm = self.clock.frequency_ppm();
             ppm / 1_000_000.0
         } else {
             0.0  // No feedforward during clock calibration
@@ -415,6 +474,21 @@ impl Aes67Stream {
         &self.config
     }
 
+    /// Multicast group the receiver follows (shared with Livewire routing)
+    pub fn group(&self) -> Arc<AtomicU32> {
+        self.group.clone()
+    }
+
+    /// Key to select this stream as the media clock source
+    pub fn clock_key(&self) -> usize {
+        self.clock_key
+    }
+
+    /// Follow `clock` instead of the current clock (released on drop)
+    pub fn set_clock(&mut self, clock: ClockLease) {
+        self.clock = clock;
+    }
+
     /// Get buffer fill percentage (0-200, where 100 = at target level).
     pub fn buffer_fill_percent(&self) -> u32 {
         let level = self.consumer.occupied_len();
@@ -503,6 +577,10 @@ unsafe extern "system" fn addon_free(inst: *mut c_void) {
         let stream = inst as *mut Aes67Stream;
         // Unregister from stream registry before freeing
         crate::unregister_stream((*stream).handle);
+        crate::media_clock::release_source((*stream).clock_key);
+        if let Some(number) = (*stream).config.livewire_destination {
+            crate::livewire::node::unregister_destination(number, &(*stream).group);
+        }
         let _ = Box::from_raw(stream);
     }
 }
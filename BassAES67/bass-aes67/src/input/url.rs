@@ -1,9 +1,18 @@
 //! URL parser for aes67:// scheme.
 //! Parses URLs like: aes67://239.192.76.52:5004?iface=192.168.60.102&pt=96&jitter=10
+//! or, by Livewire channel number: aes67://lw/4001?iface=192.168.60.102
+//! Inputs can be offered to LWRP routing with dst=N (and name=TEXT).
+//! lw=live|standard|surround selects a Livewire stream format.
+//! clock=ptp:N|lw|sys|ntp|stream:HANDLE gives the stream its own clock
+//! reference instead of the process-wide clock.
 
 use std::net::Ipv4Addr;
 use std::str::FromStr;
 
+use crate::clock_bindings::ClockRef;
+use crate::livewire::lwap::LwStreamType;
+use crate::livewire::{self, LW_MAX_CHANNEL, LW_MIN_CHANNEL, LW_SAMPLE_RATE};
+
 /// Parsed AES67 URL with all stream parameters
 #[derive(Debug, Clone)]
 pub struct Aes67Url {
@@ -21,6 +30,17 @@ pub struct Aes67Url {
     pub channels: u16,
     /// Sample rate in Hz (default: 48000)
     pub sample_rate: u32,
+    /// Livewire channel number (aes67://lw/<channel> URLs)
+    pub livewire_channel: Option<u16>,
+    /// Livewire stream format (lw=live|standard|surround); sets the
+    /// channel count and sample rate of the format
+    pub livewire_stream: Option<LwStreamType>,
+    /// Livewire destination number for LWRP routing (dst=N)
+    pub livewire_destination: Option<u16>,
+    /// Livewire destination name (name=TEXT)
+    pub livewire_name: Option<String>,
+    /// Clock the stream follows (clock=...; default: the process-wide clock)
+    pub clock: ClockRef,
 }
 
 impl Default for Aes67Url {
@@ -33,6 +53,11 @@ impl Default for Aes67Url {
             jitter_ms: 10,
             channels: 2,
             sample_rate: 48000,
+            livewire_channel: None,
+            livewire_stream: None,
+            livewire_destination: None,
+            livewire_name: None,
+            clock: ClockRef::Global,
         }
     }
 }
@@ -40,6 +65,8 @@ impl Default for Aes67Url {
 impl Aes67Url {
     /// Parse an aes67:// URL string.
     /// Format: aes67://MULTICAST_IP:PORT?iface=IP&pt=N&jitter=MS&ch=N&rate=HZ
+    /// or aes67://lw/CHANNEL[:PORT]?... (multicast group from the Livewire
+    /// channel mapping; the stream resolves advertised groups at open time)
     pub fn parse(url: &str) -> Result<Self, String> {
         // Check scheme
         if !url.starts_with("aes67://") {
@@ -61,9 +88,23 @@ impl Aes67Url {
             None => (host_port, None),
         };
 
-        // Parse multicast address
-        result.multicast_addr = Ipv4Addr::from_str(host)
-            .map_err(|e| format!("Invalid multicast address '{}': {}", host, e))?;
+        // Parse Livewire channel or multicast address
+        if let Some(channel_str) = host.strip_prefix("lw/") {
+            let channel: u16 = channel_str
+                .parse()
+                .map_err(|e| format!("Invalid Livewire channel '{}': {}", channel_str, e))?;
+            if !(LW_MIN_CHANNEL..=LW_MAX_CHANNEL).contains(&channel) {
+                return Err(format!(
+                    "Livewire channel must be {}-{}",
+                    LW_MIN_CHANNEL, LW_MAX_CHANNEL
+                ));
+            }
+            result.livewire_channel = Some(channel);
+            result.multicast_addr = livewire::channel_to_multicast(channel);
+        } else {
+            result.multicast_addr = Ipv4Addr::from_str(host)
+                .map_err(|e| format!("Invalid multicast address '{}': {}", host, e))?;
+        }
 
         // Parse port if specified
         if let Some(port_str) = port_str {
@@ -106,6 +147,27 @@ impl Aes67Url {
                             .parse()
                             .map_err(|e| format!("Invalid sample rate '{}': {}", value, e))?;
                     }
+                    "lw" => {
+                        result.livewire_stream = Some(
+                            LwStreamType::parse(value)
+                                .ok_or_else(|| format!("Invalid Livewire stream type '{}'", value))?,
+                        );
+                    }
+                    "dst" => {
+                        let number: u16 = value
+                            .parse()
+                            .map_err(|e| format!("Invalid destination '{}': {}", value, e))?;
+                        if number == 0 {
+                            return Err("Destination number must be 1 or higher".to_string());
+                        }
+                        result.livewire_destination = Some(number);
+                    }
+                    "name" => {
+                        result.livewire_name = Some(value.replace('+', " "));
+                    }
+                    "clock" => {
+                        result.clock = value.parse()?;
+                    }
                     _ => {
                         // Ignore unknown parameters
                     }
@@ -113,8 +175,19 @@ impl Aes67Url {
             }
         }
 
+        if let Some(stream_type) = result.livewire_stream {
+            result.set_livewire_stream(stream_type);
+        }
+
         Ok(result)
     }
+
+    /// Use the channel layout and sample rate of a Livewire stream format
+    pub fn set_livewire_stream(&mut self, stream_type: LwStreamType) {
+        self.livewire_stream = Some(stream_type);
+        self.channels = stream_type.channels();
+        self.sample_rate = LW_SAMPLE_RATE;
+    }
 }
 
 #[cfg(test)]
@@ -138,4 +211,61 @@ mod tests {
         assert_eq!(url.payload_type, 96);
         assert_eq!(url.jitter_ms, 10);
     }
+
+    #[test]
+    fn test_parse_livewire_channel() {
+        let url = Aes67Url::parse("aes67://lw/4001?iface=192.168.60.102").unwrap();
+        assert_eq!(url.livewire_channel, Some(4001));
+        assert_eq!(url.multicast_addr, Ipv4Addr::new(239, 192, 15, 161));
+        assert_eq!(url.port, 5004);
+
+        let url = Aes67Url::parse("aes67://lw/1:5006").unwrap();
+        assert_eq!(url.multicast_addr, Ipv4Addr::new(239, 192, 0, 1));
+        assert_eq!(url.port, 5006);
+
+        assert!(Aes67Url::parse("aes67://lw/0").is_err());
+        assert!(Aes67Url::parse("aes67://lw/32768").is_err());
+        assert!(Aes67Url::parse("aes67://lw/abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_livewire_stream() {
+        let url = Aes67Url::parse("aes67://lw/4001?lw=surround&ch=2&rate=44100").unwrap();
+        assert_eq!(url.livewire_stream, Some(LwStreamType::Surround));
+        assert_eq!(url.channels, 8);
+        assert_eq!(url.sample_rate, 48000);
+
+        let url = Aes67Url::parse("aes67://lw/4001?lw=live").unwrap();
+        assert_eq!(url.livewire_stream, Some(LwStreamType::Livestream));
+        assert_eq!(url.channels, 2);
+
+        assert!(Aes67Url::parse("aes67://lw/4001?lw=mono").is_err());
+    }
+
+    #[test]
+    fn test_parse_livewire_destination() {
+        let url = Aes67Url::parse("aes67://lw/4001?dst=3&name=Rec+3").unwrap();
+        assert_eq!(url.livewire_destination, Some(3));
+        assert_eq!(url.livewire_name.as_deref(), Some("Rec 3"));
+
+        assert!(Aes67Url::parse("aes67://lw/4001?dst=0").is_err());
+        assert!(Aes67Url::parse("aes67://lw/4001?dst=x").is_err());
+    }
+
+    #[test]
+    fn test_parse_clock() {
+        let url = Aes67Url::parse("aes67://239.192.76.52:5004").unwrap();
+        assert_eq!(url.clock, ClockRef::Global);
+
+        let url = Aes67Url::parse("aes67://239.192.76.52:5004?clock=ptp:127").unwrap();
+        assert_eq!(url.clock, ClockRef::Ptp(127));
+
+        let url = Aes67Url::parse("aes67://lw/4001?clock=lw").unwrap();
+        assert_eq!(url.clock, ClockRef::Livewire);
+
+        let url = Aes67Url::parse("aes67://239.192.76.52:5004?clock=stream:65537").unwrap();
+        assert_eq!(url.clock, ClockRef::Stream(65537));
+
+        assert!(Aes67Url::parse("aes67://239.192.76.52:5004?clock=gps").is_err());
+    }
 }
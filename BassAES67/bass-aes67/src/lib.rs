@@ -11,8 +11,10 @@
 
 mod ffi;
 mod input;
+mod livewire;
 mod output;
 mod clock_bindings;
+use bass_clock::media as media_clock;
 
 // Re-export output module for external use
 pub use output::{Aes67OutputStream, Aes67OutputConfig, OutputStats};
@@ -29,6 +31,8 @@ use parking_lot::RwLock;
 
 use ffi::*;
 use input::{Aes67Stream, Aes67Url, ADDON_FUNCS, stream::stream_proc};
+use livewire::lwap::LwStreamType;
+use clock_bindings::{ClockLease, ClockRef};
 
 // Plugin version (matches BASS version format: 0xAABBCCDD)
 const VERSION: DWORD = 0x02040000;
@@ -63,13 +67,20 @@ pub const BASS_CONFIG_AES67_TARGET_PACKETS: DWORD = 0x20015; // Get target buffe
 pub const BASS_CONFIG_AES67_PACKET_TIME: DWORD = 0x20016; // Get detected packet time in microseconds
 pub const BASS_CONFIG_AES67_PTP_LOCKED: DWORD = 0x20017;  // PTP locked status (0/1)
 pub const BASS_CONFIG_AES67_PTP_FREQ: DWORD = 0x20018;    // PTP frequency in PPM × 1000
-pub const BASS_CONFIG_AES67_CLOCK_MODE: DWORD = 0x20019;  // Clock mode: 0=PTP, 1=Livewire, 2=System
+pub const BASS_CONFIG_AES67_CLOCK_MODE: DWORD = 0x20019;  // Clock mode: 0=PTP, 1=Livewire, 2=System, 3=NTP, 4=Stream
 pub const BASS_CONFIG_AES67_CLOCK_FALLBACK_TIMEOUT: DWORD = 0x2001A; // Fallback timeout in seconds (0=disabled)
+pub const BASS_CONFIG_AES67_PTP_INSTANCE: DWORD = 0x2001B; // bass_ptp instance handle to follow (0=default client)
+pub const BASS_CONFIG_AES67_NTP_SERVER: DWORD = 0x2001C; // NTP server "host[:port]" for NTP clock mode (string ptr)
+pub const BASS_CONFIG_AES67_CLOCK_STREAM: DWORD = 0x2001D; // aes67 input stream the Stream clock mode recovers from (0=none)
+pub const BASS_CONFIG_AES67_CLOCK_SLEW_RATE: DWORD = 0x2001E; // Clock frequency slew limit in PPM/s × 1000 (0=unlimited)
+pub const BASS_CONFIG_AES67_CLOCK_RETURN_DELAY: DWORD = 0x2001F; // Seconds the clock must stay locked before fallback ends
 
 // Clock mode values
 pub const BASS_AES67_CLOCK_PTP: DWORD = 0;
 pub const BASS_AES67_CLOCK_LIVEWIRE: DWORD = 1;
 pub const BASS_AES67_CLOCK_SYSTEM: DWORD = 2;
+pub const BASS_AES67_CLOCK_NTP: DWORD = 3;
+pub const BASS_AES67_CLOCK_STREAM: DWORD = 4;
 
 // Default configuration values
 static mut CONFIG_PT: DWORD = 96;
@@ -77,7 +88,9 @@ static mut CONFIG_INTERFACE: [u8; 64] = [0; 64];
 static mut CONFIG_JITTER_MS: DWORD = 10;
 static mut CONFIG_PTP_DOMAIN: DWORD = 0;
 static mut CONFIG_PTP_ENABLED: DWORD = 1; // Enabled by default
-static mut CONFIG_CLOCK_MODE: DWORD = 0;  // 0=PTP (default), 1=Livewire, 2=System
+static mut CONFIG_CLOCK_MODE: DWORD = 0;  // 0=PTP (default), 1=Livewire, 2=System, 3=NTP, 4=Stream
+static mut CONFIG_NTP_SERVER: [u8; 256] = [0; 256]; // Empty = pool.ntp.org
+static mut CONFIG_CLOCK_STREAM: DWORD = 0; // 0 = no media clock source
 static mut CONFIG_FALLBACK_TIMEOUT: DWORD = 5; // 5 seconds default fallback timeout
 
 // Wrapper for raw pointer to allow Send + Sync in HashMap.
@@ -113,6 +126,17 @@ fn get_any_stream() -> Option<*mut Aes67Stream> {
     STREAM_REGISTRY.read().values().next().map(|ptr| ptr.0)
 }
 
+/// Acquire the clock a stream refers to. `stream:<handle>` references
+/// resolve to the media clock of a registered input stream.
+fn acquire_clock(reference: ClockRef, interface: Option<Ipv4Addr>) -> Result<ClockLease, i32> {
+    ClockLease::acquire(reference, interface.unwrap_or(Ipv4Addr::UNSPECIFIED), |handle| {
+        STREAM_REGISTRY
+            .read()
+            .get(&handle)
+            .map(|ptr| unsafe { (*ptr.0).clock_key() })
+    })
+}
+
 /// Plugin format information - defines what formats this plugin handles
 /// For URL schemes, the exts field should contain the scheme (e.g., "aes67://")
 static PLUGIN_FORMATS: [BassPluginForm; 1] = [
@@ -404,7 +428,7 @@ unsafe extern "system" fn config_handler(option: DWORD, flags: DWORD, value: *mu
             TRUE
         }
         BASS_CONFIG_AES67_CLOCK_MODE => {
-            // Clock mode: 0=PTP, 1=Livewire, 2=System
+            // Clock mode: 0=PTP, 1=Livewire, 2=System, 3=NTP, 4=Stream
             if is_ptr {
                 return FALSE;
             }
@@ -431,6 +455,94 @@ unsafe extern "system" fn config_handler(option: DWORD, flags: DWORD, value: *mu
             }
             TRUE
         }
+        BASS_CONFIG_AES67_NTP_SERVER => {
+            if !is_ptr {
+                return FALSE;
+            }
+            if is_set {
+                let cstr = CStr::from_ptr(value as *const i8);
+                if let Ok(s) = cstr.to_str() {
+                    let bytes = s.as_bytes();
+                    let max_len = 255; // CONFIG_NTP_SERVER.len() - 1
+                    let len = bytes.len().min(max_len);
+                    let dst = ptr::addr_of_mut!(CONFIG_NTP_SERVER) as *mut u8;
+                    ptr::copy_nonoverlapping(bytes.as_ptr(), dst, len);
+                    *dst.add(len) = 0;
+                    clock_bindings::clock_set_ntp_server(std::str::from_utf8(&bytes[..len]).unwrap_or(""));
+                }
+            } else {
+                let server_ptr = ptr::addr_of!(CONFIG_NTP_SERVER) as *const u8;
+                *(value as *mut *const u8) = server_ptr;
+            }
+            TRUE
+        }
+        BASS_CONFIG_AES67_PTP_INSTANCE => {
+            // bass_ptp instance handle (from BASS_PTP_CreateInstance, 0=default client)
+            if is_ptr {
+                return FALSE;
+            }
+            let dvalue = value as *mut DWORD;
+            if is_set {
+                clock_bindings::clock_set_ptp_instance(*dvalue);
+            } else {
+                *dvalue = clock_bindings::clock_get_ptp_instance();
+            }
+            TRUE
+        }
+        BASS_CONFIG_AES67_CLOCK_SLEW_RATE => {
+            // Frequency slew limit across clock switches, PPM per second × 1000
+            if is_ptr {
+                return FALSE;
+            }
+            let dvalue = value as *mut DWORD;
+            if is_set {
+                clock_bindings::set_slew_rate(*dvalue as f64 / 1000.0);
+            } else {
+                *dvalue = (clock_bindings::get_slew_rate() * 1000.0).round() as DWORD;
+            }
+            TRUE
+        }
+        BASS_CONFIG_AES67_CLOCK_RETURN_DELAY => {
+            // Seconds the clock must stay locked before leaving fallback
+            if is_ptr {
+                return FALSE;
+            }
+            let dvalue = value as *mut DWORD;
+            if is_set {
+                clock_bindings::set_fallback_return_delay(*dvalue);
+            } else {
+                *dvalue = clock_bindings::get_fallback_return_delay();
+            }
+            TRUE
+        }
+        BASS_CONFIG_AES67_CLOCK_STREAM => {
+            // Input stream whose RTP timestamps drive the Stream clock mode
+            if is_ptr {
+                return FALSE;
+            }
+            let dvalue = value as *mut DWORD;
+            if is_set {
+                if *dvalue == 0 {
+                    media_clock::select_source(0, None);
+                } else {
+                    let registry = STREAM_REGISTRY.read();
+                    let stream = match registry.get(&*dvalue) {
+                        Some(ptr) => &*ptr.0,
+                        None => return FALSE,
+                    };
+                    media_clock::select_source(stream.clock_key(), Some(stream.config().sample_rate));
+                }
+                CONFIG_CLOCK_STREAM = *dvalue;
+            } else {
+                // Report 0 once the source stream has been freed
+                let registry = STREAM_REGISTRY.read();
+                *dvalue = match registry.get(&CONFIG_CLOCK_STREAM) {
+                    Some(ptr) if (*ptr.0).clock_key() == media_clock::source() => CONFIG_CLOCK_STREAM,
+                    _ => 0,
+                };
+            }
+            TRUE
+        }
         _ => FALSE,
     }
 }
@@ -476,6 +588,22 @@ unsafe extern "system" fn stream_create_url(
         }
     }
 
+    // Livewire channel: prefer the advertised multicast group (and stream
+    // format, unless lw= was given), and keep listening for advertisements
+    // for later opens
+    if let Some(channel) = config.livewire_channel {
+        if let Some(iface) = config.interface {
+            let _ = livewire::lwap::start_discovery(iface);
+        }
+        config.multicast_addr = livewire::resolve_channel(channel);
+        if config.livewire_stream.is_none() {
+            match livewire::lwap::lookup(channel).map(|s| s.stream_type) {
+                Some(LwStreamType::Unknown(_)) | None => {}
+                Some(t) => config.set_livewire_stream(t),
+            }
+        }
+    }
+
     // Use global PT config if not specified in URL
     if config.payload_type == 96 {
         config.payload_type = CONFIG_PT as u8;
@@ -486,6 +614,12 @@ unsafe extern "system" fn stream_create_url(
         config.jitter_ms = CONFIG_JITTER_MS;
     }
 
+    // Keep a few packets of a Livewire stream buffered (Standard streams
+    // arrive in 5 ms bursts)
+    if let Some(stream_type) = config.livewire_stream {
+        config.jitter_ms = config.jitter_ms.max(stream_type.min_jitter_ms());
+    }
+
     // Create the AES67 stream
     let mut stream = match Aes67Stream::new(config.clone()) {
         Ok(s) => Box::new(s),
@@ -495,6 +629,15 @@ unsafe extern "system" fn stream_create_url(
         }
     };
 
+    // Follow the stream's own clock if the URL names one
+    match acquire_clock(config.clock, config.interface) {
+        Ok(lease) => stream.set_clock(lease),
+        Err(_) => {
+            set_error(BASS_ERROR_NOTAVAIL);
+            return 0;
+        }
+    }
+
     // Start receiving packets
     if let Err(_) = stream.start() {
         set_error(BASS_ERROR_FILEOPEN);
@@ -503,7 +646,7 @@ unsafe extern "system" fn stream_create_url(
 
     // Start clock client if enabled, interface is configured, and clock not already running
     // (Clock may have been started via BASS_AES67_ClockStart)
-    if CONFIG_PTP_ENABLED != 0 && !clock_bindings::clock_is_running() {
+    if config.clock == ClockRef::Global && CONFIG_PTP_ENABLED != 0 && !clock_bindings::clock_is_running() {
         if let Some(iface) = config.interface {
             let mode = clock_bindings::ClockMode::from(CONFIG_CLOCK_MODE);
             let _ = clock_bindings::clock_start(iface, CONFIG_PTP_DOMAIN as u8, mode);
@@ -556,6 +699,15 @@ unsafe extern "system" fn stream_create_url(
     // Register stream for buffer level queries (supports multiple streams)
     register_stream(handle, stream_ptr);
 
+    // Offer the input to LWRP routing
+    if let Some(number) = config.livewire_destination {
+        let name = config
+            .livewire_name
+            .clone()
+            .unwrap_or_else(|| format!("BASS In {}", number));
+        livewire::node::register_destination(number, &name, config.channels, (*stream_ptr).group());
+    }
+
     handle
 }
 
@@ -644,6 +796,136 @@ pub unsafe extern "system" fn BASS_AES67_GetClockStats() -> *const i8 {
     CLOCK_STATS_BUFFER.as_ptr() as *const i8
 }
 
+/// Receive clock events (see BASS_AES67_CLOCK_EVENT_*) for logging and
+/// alarms. The callback runs on a thread of its own; NULL stops the events.
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_ClockSetEventCallback(
+    callback: Option<clock_bindings::ClockEventCallback>,
+    user: *mut c_void,
+) -> i32 {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return 0;
+    }
+    clock_bindings::clock_set_event_callback(callback, user);
+    1
+}
+
+// =============================================================================
+// LIVEWIRE DISCOVERY FFI
+// =============================================================================
+
+/// Start listening for Livewire source advertisements (LWAP)
+/// Requires BASS_CONFIG_AES67_INTERFACE to be set first via BASS_SetConfigPtr.
+/// Opening an aes67://lw/<channel> URL starts the listener as well.
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_LivewireDiscoveryStart() -> i32 {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return 0;
+    }
+
+    let iface_ptr = ptr::addr_of!(CONFIG_INTERFACE) as *const i8;
+    let iface = CStr::from_ptr(iface_ptr)
+        .to_str()
+        .ok()
+        .and_then(|s| Ipv4Addr::from_str(s).ok());
+
+    match iface.map(livewire::lwap::start_discovery) {
+        Some(Ok(())) => 1,
+        _ => 0,
+    }
+}
+
+/// Stop listening for Livewire source advertisements
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_LivewireDiscoveryStop() -> i32 {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return 0;
+    }
+    livewire::lwap::stop_discovery();
+    1
+}
+
+/// Get the discovered Livewire sources, one line per source with
+/// tab-separated channel, name, source IP, stream type and multicast group
+/// Returns pointer to null-terminated string, valid until next call
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_LivewireGetSources() -> *const i8 {
+    lazy_static! {
+        static ref SOURCES_BUFFER: parking_lot::Mutex<std::ffi::CString> =
+            parking_lot::Mutex::new(std::ffi::CString::default());
+    }
+
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return b"\0".as_ptr() as *const i8;
+    }
+
+    // Source names never contain NUL; strip defensively
+    let table = livewire::lwap::format_sources().replace('\0', "");
+    let mut buffer = SOURCES_BUFFER.lock();
+    *buffer = std::ffi::CString::new(table).unwrap_or_default();
+    buffer.as_ptr()
+}
+
+/// Make this process a Livewire node: advertise the outputs registered
+/// with BASS_AES67_OutputSetLivewire and start the LWRP server.
+/// Requires BASS_CONFIG_AES67_INTERFACE to be set first via BASS_SetConfigPtr.
+/// port: LWRP TCP port (0 = 93)
+/// password: LWRP login password (null or empty = any password)
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_LivewireNodeStart(port: DWORD, password: *const i8) -> i32 {
+    if !INITIALIZED.load(Ordering::SeqCst) || port > u16::MAX as DWORD {
+        return 0;
+    }
+
+    let iface_ptr = ptr::addr_of!(CONFIG_INTERFACE) as *const i8;
+    let iface = match CStr::from_ptr(iface_ptr)
+        .to_str()
+        .ok()
+        .and_then(|s| Ipv4Addr::from_str(s).ok())
+    {
+        Some(i) => i,
+        None => return 0,
+    };
+
+    let password = if password.is_null() {
+        None
+    } else {
+        CStr::from_ptr(password)
+            .to_str()
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    };
+    let port = if port == 0 { livewire::lwrp::LWRP_PORT } else { port as u16 };
+
+    if livewire::lwrp::start_server(iface, port, password).is_err() {
+        return 0;
+    }
+    match livewire::lwap::start_advertising(iface) {
+        Ok(()) => 1,
+        Err(_) => {
+            livewire::lwrp::stop_server();
+            0
+        }
+    }
+}
+
+/// Stop the LWRP server and source advertising
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_LivewireNodeStop() -> i32 {
+    if !INITIALIZED.load(Ordering::SeqCst) {
+        return 0;
+    }
+    livewire::lwrp::stop_server();
+    livewire::lwap::stop_advertising();
+    1
+}
+
 // =============================================================================
 // AES67 OUTPUT STREAM FFI
 // =============================================================================
@@ -794,6 +1076,108 @@ pub unsafe extern "system" fn BASS_AES67_OutputGetPPM(handle: *mut c_void) -> i3
     (stream.applied_ppm() * 1000.0) as i32
 }
 
+/// Send a Livewire stream format instead of the packet time and channel
+/// count given to BASS_AES67_OutputCreate. The output must be stopped, and
+/// the BASS source must have the format's channel count.
+/// format: BASS_AES67_LW_STANDARD (5 ms, stereo), BASS_AES67_LW_LIVESTREAM
+/// (250 us, stereo) or BASS_AES67_LW_SURROUND (250 us, 8 channels)
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_OutputSetLivewireFormat(handle: *mut c_void, format: DWORD) -> i32 {
+    if handle.is_null() || format > u16::MAX as DWORD {
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut Aes67OutputStream);
+    let stream_type = LwStreamType::from_fast(format as u16);
+    if stream.set_livewire_format(stream_type).is_err() {
+        return 0;
+    }
+    livewire::node::update_source_format(handle as usize, stream.config().channels, stream_type);
+    1
+}
+
+/// Offer the output to the Livewire network as source `channel` named
+/// `name` (advertised while the Livewire node runs)
+/// channel: Livewire channel number (1-32767, 0 = stop offering)
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_OutputSetLivewire(
+    handle: *mut c_void,
+    channel: DWORD,
+    name: *const i8,
+) -> i32 {
+    if handle.is_null() {
+        return 0;
+    }
+
+    let key = handle as usize;
+    if channel == 0 {
+        livewire::node::unregister_source(key);
+        return 1;
+    }
+    if channel > u16::MAX as DWORD {
+        return 0;
+    }
+
+    let stream = &*(handle as *mut Aes67OutputStream);
+    let config = stream.config();
+    let name = if name.is_null() {
+        format!("BASS Out {}", channel)
+    } else {
+        CStr::from_ptr(name).to_string_lossy().into_owned()
+    };
+
+    match livewire::node::register_source(
+        key,
+        channel as u16,
+        &name,
+        config.multicast_addr,
+        config.channels,
+        stream.livewire_format().unwrap_or(LwStreamType::Standard),
+    ) {
+        Ok(()) => 1,
+        Err(_) => 0,
+    }
+}
+
+/// Follow a clock of the output's own instead of the process-wide clock.
+/// The output must be stopped.
+/// clock: "ptp:N", "lw", "sys", "ntp" or "stream:HANDLE" (NULL or "" = global)
+/// Returns 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_AES67_OutputSetClock(handle: *mut c_void, clock: *const i8) -> i32 {
+    if handle.is_null() {
+        return 0;
+    }
+
+    let reference = if clock.is_null() {
+        ClockRef::Global
+    } else {
+        match CStr::from_ptr(clock).to_str() {
+            Ok("") => ClockRef::Global,
+            Ok(s) => match s.parse() {
+                Ok(r) => r,
+                Err(_) => return 0,
+            },
+            Err(_) => return 0,
+        }
+    };
+
+    let stream = &mut *(handle as *mut Aes67OutputStream);
+    if stream.is_running() {
+        return 0;
+    }
+    let lease = match acquire_clock(reference, stream.config().interface) {
+        Ok(lease) => lease,
+        Err(_) => return 0,
+    };
+    match stream.set_clock(lease) {
+        Ok(()) => 1,
+        Err(_) => 0,
+    }
+}
+
 /// Destroy the output stream and free resources
 /// Returns 1 on success, 0 on failure
 #[no_mangle]
@@ -802,6 +1186,8 @@ pub unsafe extern "system" fn BASS_AES67_OutputFree(handle: *mut c_void) -> i32
         return 0;
     }
 
+    livewire::node::unregister_source(handle as usize);
+
     // Take ownership and drop (stop() is called in Drop impl)
     let _ = Box::from_raw(handle as *mut Aes67OutputStream);
     1
@@ -0,0 +1,677 @@
+//! Livewire advertisement (LWAP) listener and advertiser.
+//!
+//! Livewire devices periodically multicast the sources they offer to
+//! 239.192.255.3:4001. Each advertisement is a 16-byte header followed by
+//! tagged items (4-character tag, 1-byte type, value). Sources are nested
+//! blocks tagged `S001`, `S002`, ... carrying the channel number (`PSID`),
+//! the source name (`PSNM`), the multicast group (`FSID`, 0 = standard
+//! group of the channel) and the stream type (`FAST`).
+//!
+//! Item types that aren't understood end the parse of a packet, so unknown
+//! extensions never produce bogus entries.
+//!
+//! The advertiser announces the sources registered with the node (see
+//! `node`) in the same format, every `ADVERTISE_INTERVAL` and right after
+//! a source is added, renamed or removed.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use super::node::{self, NodeSource};
+use super::{channel_to_multicast, LW_MAX_CHANNEL, LW_MIN_CHANNEL, LW_SAMPLE_RATE};
+
+/// LWAP multicast group
+pub const LWAP_MULTICAST: Ipv4Addr = Ipv4Addr::new(239, 192, 255, 3);
+/// LWAP UDP port
+pub const LWAP_PORT: u16 = 4001;
+
+/// Advertisement header magic
+const LWAP_MAGIC: [u8; 4] = [0x03, 0x00, 0x02, 0x07];
+/// Header size in bytes
+const LWAP_HEADER_SIZE: usize = 16;
+
+/// Sources not re-advertised for this long are dropped
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Interval between advertisements of our own sources
+const ADVERTISE_INTERVAL: Duration = Duration::from_secs(10);
+
+// Item value types
+const ITEM_U32: u8 = 0x01;
+const ITEM_STRING: u8 = 0x03;
+const ITEM_U64: u8 = 0x06;
+const ITEM_U16: u8 = 0x07;
+const ITEM_U8: u8 = 0x08;
+const ITEM_IP: u8 = 0x09;
+const ITEM_BLOCK: u8 = 0x13;
+
+/// Livewire stream type of a source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LwStreamType {
+    /// Standard stream (stereo, 5 ms packets)
+    Standard,
+    /// Livestream (stereo, 0.25 ms packets)
+    Livestream,
+    /// Surround stream (8 channels)
+    Surround,
+    /// Type value not known to us
+    Unknown(u16),
+}
+
+impl LwStreamType {
+    /// Stream type from its LWAP `FAST` value
+    pub fn from_fast(value: u16) -> Self {
+        match value {
+            1 => LwStreamType::Standard,
+            2 => LwStreamType::Livestream,
+            3 => LwStreamType::Surround,
+            v => LwStreamType::Unknown(v),
+        }
+    }
+
+    fn to_fast(self) -> u16 {
+        match self {
+            LwStreamType::Standard => 1,
+            LwStreamType::Livestream => 2,
+            LwStreamType::Surround => 3,
+            LwStreamType::Unknown(v) => v,
+        }
+    }
+
+    /// Parse a stream type name (as used by the `lw=` URL parameter)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "standard" | "std" => Some(LwStreamType::Standard),
+            "live" | "livestream" => Some(LwStreamType::Livestream),
+            "surround" => Some(LwStreamType::Surround),
+            _ => None,
+        }
+    }
+
+    /// Audio channels per stream. Surround streams carry 5.1 (L, R, C, LFE,
+    /// Ls, Rs) followed by a stereo downmix.
+    pub fn channels(&self) -> u16 {
+        match self {
+            LwStreamType::Surround => 8,
+            _ => 2,
+        }
+    }
+
+    /// Samples per channel in each packet (all formats are 24-bit at 48 kHz).
+    /// Surround uses Livestream framing so 8 channels fit in one datagram.
+    pub fn samples_per_packet(&self) -> u32 {
+        match self {
+            LwStreamType::Standard => 240,
+            _ => 12,
+        }
+    }
+
+    /// Packet time in microseconds
+    pub fn packet_time_us(&self) -> u32 {
+        self.samples_per_packet() * 1_000_000 / LW_SAMPLE_RATE
+    }
+
+    /// Smallest useful jitter buffer (three packets, at least 1 ms)
+    pub fn min_jitter_ms(&self) -> u32 {
+        (self.packet_time_us() * 3).div_ceil(1000).max(1)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LwStreamType::Standard => "standard",
+            LwStreamType::Livestream => "livestream",
+            LwStreamType::Surround => "surround",
+            LwStreamType::Unknown(_) => "unknown",
+        }
+    }
+}
+
+/// An advertised Livewire source
+#[derive(Debug, Clone, PartialEq)]
+pub struct LwSource {
+    /// Livewire channel number (1-32767)
+    pub channel: u16,
+    /// Source name as shown on the device
+    pub name: String,
+    /// IP address of the device offering the source
+    pub source_ip: Ipv4Addr,
+    pub stream_type: LwStreamType,
+    /// Multicast group the source is sent to
+    pub multicast_addr: Ipv4Addr,
+}
+
+/// Decoded item value
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Int(u64),
+    Ip(Ipv4Addr),
+    Text(String),
+    Block(Vec<Item>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    tag: [u8; 4],
+    value: Value,
+}
+
+impl Item {
+    fn int(&self) -> Option<u64> {
+        match self.value {
+            Value::Int(v) => Some(v),
+            Value::Ip(ip) => Some(u32::from(ip) as u64),
+            _ => None,
+        }
+    }
+}
+
+/// Parse up to `count` items (None = until the data ends); stops at the
+/// first malformed or unknown item
+fn parse_items(data: &mut &[u8], count: Option<usize>) -> Vec<Item> {
+    let mut items = Vec::new();
+    while count.is_none_or(|c| items.len() < c) && data.len() >= 5 {
+        let tag = [data[0], data[1], data[2], data[3]];
+        let item_type = data[4];
+        let rest = &data[5..];
+
+        let (value, used) = match item_type {
+            ITEM_U8 if !rest.is_empty() => (Value::Int(rest[0] as u64), 1),
+            ITEM_U16 if rest.len() >= 2 => (Value::Int(u16::from_be_bytes([rest[0], rest[1]]) as u64), 2),
+            ITEM_U32 if rest.len() >= 4 => (
+                Value::Int(u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as u64),
+                4,
+            ),
+            ITEM_IP if rest.len() >= 4 => (Value::Ip(Ipv4Addr::new(rest[0], rest[1], rest[2], rest[3])), 4),
+            ITEM_U64 if rest.len() >= 8 => {
+                let mut b = [0u8; 8];
+                b.copy_from_slice(&rest[..8]);
+                (Value::Int(u64::from_be_bytes(b)), 8)
+            }
+            ITEM_STRING if rest.len() >= 2 => {
+                let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+                if rest.len() < 2 + len {
+                    break;
+                }
+                let text = String::from_utf8_lossy(&rest[2..2 + len]);
+                (Value::Text(text.trim_end_matches('\0').to_string()), 2 + len)
+            }
+            ITEM_BLOCK if rest.len() >= 2 => {
+                let n = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+                let mut inner = &rest[2..];
+                let before = inner.len();
+                let block = parse_items(&mut inner, Some(n));
+                if block.len() < n {
+                    break;
+                }
+                (Value::Block(block), 2 + before - inner.len())
+            }
+            _ => break,
+        };
+
+        items.push(Item { tag, value });
+        *data = &rest[used..];
+    }
+    items
+}
+
+/// Extract the sources of an advertisement sent from `sender`
+pub fn parse_advertisement(data: &[u8], sender: Ipv4Addr) -> Vec<LwSource> {
+    if data.len() < LWAP_HEADER_SIZE || data[..4] != LWAP_MAGIC {
+        return Vec::new();
+    }
+
+    let mut body = &data[LWAP_HEADER_SIZE..];
+    let items = parse_items(&mut body, None);
+
+    // Devices announce their own address; prefer it over the packet source
+    let source_ip = items
+        .iter()
+        .find(|i| &i.tag == b"INIP")
+        .and_then(|i| match i.value {
+            Value::Ip(ip) => Some(ip),
+            _ => None,
+        })
+        .filter(|ip| !ip.is_unspecified())
+        .unwrap_or(sender);
+
+    items
+        .iter()
+        .filter(|i| i.tag[0] == b'S' && i.tag[1..].iter().all(u8::is_ascii_digit))
+        .filter_map(|i| match &i.value {
+            Value::Block(block) => parse_source(block, source_ip),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_source(block: &[Item], source_ip: Ipv4Addr) -> Option<LwSource> {
+    let find = |tag: &[u8; 4]| block.iter().find(|i| &i.tag == tag);
+
+    let channel = u16::try_from(find(b"PSID")?.int()?).ok()?;
+    if !(LW_MIN_CHANNEL..=LW_MAX_CHANNEL).contains(&channel) {
+        return None;
+    }
+    let name = match find(b"PSNM").map(|i| &i.value) {
+        Some(Value::Text(t)) => t.clone(),
+        _ => String::new(),
+    };
+    let multicast_addr = match find(b"FSID").and_then(Item::int) {
+        Some(v) if v != 0 => Ipv4Addr::from(v as u32),
+        _ => channel_to_multicast(channel),
+    };
+    let stream_type = find(b"FAST")
+        .and_then(Item::int)
+        .map(|v| LwStreamType::from_fast(v as u16))
+        .unwrap_or(LwStreamType::Standard);
+
+    Some(LwSource {
+        channel,
+        name,
+        source_ip,
+        stream_type,
+        multicast_addr,
+    })
+}
+
+/// Build an advertisement of `sources` offered by `device_ip`
+pub fn build_advertisement(device_ip: Ipv4Addr, sources: &[NodeSource]) -> Vec<u8> {
+    fn item(out: &mut Vec<u8>, tag: &[u8; 4], item_type: u8, value: &[u8]) {
+        out.extend_from_slice(tag);
+        out.push(item_type);
+        out.extend_from_slice(value);
+    }
+
+    let mut packet = LWAP_MAGIC.to_vec();
+    packet.resize(LWAP_HEADER_SIZE, 0);
+    item(&mut packet, b"INIP", ITEM_IP, &device_ip.octets());
+    item(&mut packet, b"NSRC", ITEM_U16, &(sources.len() as u16).to_be_bytes());
+
+    for (i, source) in sources.iter().enumerate() {
+        // Groups other than the standard one of the channel go into FSID
+        let fsid = if source.multicast_addr == channel_to_multicast(source.channel) {
+            [0; 4]
+        } else {
+            source.multicast_addr.octets()
+        };
+        let name = source.name.as_bytes();
+        let name = &name[..name.len().min(u16::MAX as usize)];
+
+        let mut block = 5u16.to_be_bytes().to_vec();
+        item(&mut block, b"PSID", ITEM_U32, &(source.channel as u32).to_be_bytes());
+        let mut text = (name.len() as u16).to_be_bytes().to_vec();
+        text.extend_from_slice(name);
+        item(&mut block, b"PSNM", ITEM_STRING, &text);
+        item(&mut block, b"FSID", ITEM_IP, &fsid);
+        item(&mut block, b"FAST", ITEM_U16, &source.stream_type.to_fast().to_be_bytes());
+        item(&mut block, b"NCHN", ITEM_U8, &[source.channels.min(u8::MAX as u16) as u8]);
+
+        let tag = format!("S{:03}", (i + 1) % 1000);
+        let tag: [u8; 4] = tag.as_bytes().try_into().unwrap_or(*b"S000");
+        item(&mut packet, &tag, ITEM_BLOCK, &block);
+    }
+    packet
+}
+
+// =============================================================================
+// Discovery listener
+// =============================================================================
+
+/// Running listener
+struct Listener {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    interface: Ipv4Addr,
+}
+
+lazy_static! {
+    /// Discovered sources by channel, with the time they were last advertised
+    static ref SOURCES: RwLock<HashMap<u16, (LwSource, Instant)>> = RwLock::new(HashMap::new());
+    static ref LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+    static ref ADVERTISER: Mutex<Option<Listener>> = Mutex::new(None);
+}
+
+/// Start listening for advertisements on `interface`. Does nothing if the
+/// listener already runs on that interface; restarts it on another one.
+pub fn start_discovery(interface: Ipv4Addr) -> Result<(), String> {
+    let mut listener = LISTENER.lock();
+    if let Some(l) = listener.as_ref() {
+        if l.interface == interface {
+            return Ok(());
+        }
+    }
+    if let Some(mut l) = listener.take() {
+        l.running.store(false, Ordering::SeqCst);
+        if let Some(t) = l.thread.take() {
+            let _ = t.join();
+        }
+    }
+
+    let socket = create_socket(interface)?;
+    let running = Arc::new(AtomicBool::new(true));
+    let running_clone = running.clone();
+    let thread = thread::Builder::new()
+        .name("lwap-listener".to_string())
+        .spawn(move || listener_thread(socket, running_clone))
+        .map_err(|e| format!("Failed to spawn LWAP thread: {}", e))?;
+
+    *listener = Some(Listener {
+        running,
+        thread: Some(thread),
+        interface,
+    });
+    Ok(())
+}
+
+/// Stop the listener (the discovered sources are kept)
+pub fn stop_discovery() {
+    if let Some(mut l) = LISTENER.lock().take() {
+        l.running.store(false, Ordering::SeqCst);
+        if let Some(t) = l.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+/// Advertised source for `channel`, if seen recently
+pub fn lookup(channel: u16) -> Option<LwSource> {
+    SOURCES
+        .read()
+        .get(&channel)
+        .filter(|(_, seen)| seen.elapsed() < SOURCE_TIMEOUT)
+        .map(|(s, _)| s.clone())
+}
+
+/// All recently advertised sources, by channel number
+pub fn sources() -> Vec<LwSource> {
+    let mut list: Vec<LwSource> = SOURCES
+        .read()
+        .values()
+        .filter(|(_, seen)| seen.elapsed() < SOURCE_TIMEOUT)
+        .map(|(s, _)| s.clone())
+        .collect();
+    list.sort_by_key(|s| s.channel);
+    list
+}
+
+/// Table of recently advertised sources, one tab-separated line per source:
+/// channel, name, source IP, stream type, multicast group
+pub fn format_sources() -> String {
+    sources()
+        .iter()
+        .map(|s| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                s.channel,
+                s.name,
+                s.source_ip,
+                s.stream_type.as_str(),
+                s.multicast_addr
+            )
+        })
+        .collect()
+}
+
+fn create_socket(interface: Ipv4Addr) -> Result<UdpSocket, String> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+        .map_err(|e| format!("Failed to create socket: {}", e))?;
+    socket
+        .set_reuse_address(true)
+        .map_err(|e| format!("Failed to set SO_REUSEADDR: {}", e))?;
+
+    let bind_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, LWAP_PORT);
+    socket
+        .bind(&bind_addr.into())
+        .map_err(|e| format!("Failed to bind to port {}: {}", LWAP_PORT, e))?;
+    socket
+        .join_multicast_v4(&LWAP_MULTICAST, &interface)
+        .map_err(|e| format!("Failed to join multicast group: {}", e))?;
+
+    let socket: UdpSocket = socket.into();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+    Ok(socket)
+}
+
+// =============================================================================
+// Advertiser
+// =============================================================================
+
+/// Start advertising the node's sources on `interface` (restarts on
+/// another interface)
+pub fn start_advertising(interface: Ipv4Addr) -> Result<(), String> {
+    let mut advertiser = ADVERTISER.lock();
+    if let Some(a) = advertiser.as_ref() {
+        if a.interface == interface {
+            return Ok(());
+        }
+    }
+    if let Some(mut a) = advertiser.take() {
+        a.running.store(false, Ordering::SeqCst);
+        if let Some(t) = a.thread.take() {
+            let _ = t.join();
+        }
+    }
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+        .map_err(|e| format!("Failed to create socket: {}", e))?;
+    socket
+        .set_multicast_if_v4(&interface)
+        .map_err(|e| format!("Failed to set multicast interface: {}", e))?;
+    let socket: UdpSocket = socket.into();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let running_clone = running.clone();
+    let thread = thread::Builder::new()
+        .name("lwap-advertiser".to_string())
+        .spawn(move || advertiser_thread(socket, interface, running_clone))
+        .map_err(|e| format!("Failed to spawn LWAP thread: {}", e))?;
+
+    *advertiser = Some(Listener {
+        running,
+        thread: Some(thread),
+        interface,
+    });
+    Ok(())
+}
+
+/// Stop advertising
+pub fn stop_advertising() {
+    if let Some(mut a) = ADVERTISER.lock().take() {
+        a.running.store(false, Ordering::SeqCst);
+        if let Some(t) = a.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+fn advertiser_thread(socket: UdpSocket, interface: Ipv4Addr, running: Arc<AtomicBool>) {
+    let dest = SocketAddrV4::new(LWAP_MULTICAST, LWAP_PORT);
+    let mut last_sent: Option<Instant> = None;
+
+    while running.load(Ordering::SeqCst) {
+        let changed = node::take_sources_changed();
+        if changed || last_sent.is_none_or(|t| t.elapsed() >= ADVERTISE_INTERVAL) {
+            let sources = node::with_node(|n| n.sources.clone());
+            if !sources.is_empty() {
+                let _ = socket.send_to(&build_advertisement(interface, &sources), dest);
+            }
+            last_sent = Some(Instant::now());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn listener_thread(socket: UdpSocket, running: Arc<AtomicBool>) {
+    let mut buf = [0u8; 9000];
+
+    while running.load(Ordering::SeqCst) {
+        let (len, src) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        let sender = match src {
+            SocketAddr::V4(a) => *a.ip(),
+            SocketAddr::V6(_) => continue,
+        };
+
+        let now = Instant::now();
+        let found = parse_advertisement(&buf[..len], sender);
+        if found.is_empty() {
+            continue;
+        }
+        let mut table = SOURCES.write();
+        for source in found {
+            table.insert(source.channel, (source, now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(out: &mut Vec<u8>, tag: &[u8; 4], item_type: u8, value: &[u8]) {
+        out.extend_from_slice(tag);
+        out.push(item_type);
+        out.extend_from_slice(value);
+    }
+
+    fn string(text: &str) -> Vec<u8> {
+        let mut v = (text.len() as u16).to_be_bytes().to_vec();
+        v.extend_from_slice(text.as_bytes());
+        v
+    }
+
+    fn source_block(tag: &[u8; 4], channel: u32, name: &str, fsid: [u8; 4], fast: u16) -> Vec<u8> {
+        let mut inner = Vec::new();
+        item(&mut inner, b"PSID", ITEM_U32, &channel.to_be_bytes());
+        item(&mut inner, b"PSNM", ITEM_STRING, &string(name));
+        item(&mut inner, b"FSID", ITEM_IP, &fsid);
+        item(&mut inner, b"FAST", ITEM_U16, &fast.to_be_bytes());
+        item(&mut inner, b"SHAB", ITEM_U8, &[1]);
+
+        let mut out = Vec::new();
+        let mut header = 5u16.to_be_bytes().to_vec();
+        header.extend_from_slice(&inner);
+        item(&mut out, tag, ITEM_BLOCK, &header);
+        out
+    }
+
+    fn advertisement(body: &[u8]) -> Vec<u8> {
+        let mut packet = LWAP_MAGIC.to_vec();
+        packet.resize(LWAP_HEADER_SIZE, 0);
+        packet.extend_from_slice(body);
+        packet
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        let mut body = Vec::new();
+        item(&mut body, b"NEST", ITEM_U16, &2u16.to_be_bytes());
+        item(&mut body, b"INIP", ITEM_IP, &[192, 168, 2, 20]);
+        item(&mut body, b"HWID", ITEM_U64, &[0; 8]);
+        body.extend(source_block(b"S001", 4001, "Studio A Mic", [0; 4], 1));
+        body.extend(source_block(b"S002", 4002, "Studio A PGM", [239, 192, 100, 1], 3));
+
+        let sources = parse_advertisement(&advertisement(&body), Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources[0],
+            LwSource {
+                channel: 4001,
+                name: "Studio A Mic".to_string(),
+                source_ip: Ipv4Addr::new(192, 168, 2, 20),
+                stream_type: LwStreamType::Standard,
+                multicast_addr: Ipv4Addr::new(239, 192, 15, 161),
+            }
+        );
+        assert_eq!(sources[1].multicast_addr, Ipv4Addr::new(239, 192, 100, 1));
+        assert_eq!(sources[1].stream_type, LwStreamType::Surround);
+    }
+
+    #[test]
+    fn test_unknown_item_stops_parsing() {
+        let mut body = source_block(b"S001", 10, "One", [0; 4], 2);
+        item(&mut body, b"XXXX", 0x7F, &[1, 2, 3]);
+        body.extend(source_block(b"S002", 11, "Two", [0; 4], 2));
+
+        let sources = parse_advertisement(&advertisement(&body), Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].channel, 10);
+        assert_eq!(sources[0].source_ip, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(sources[0].stream_type, LwStreamType::Livestream);
+    }
+
+    #[test]
+    fn test_advertisement_round_trip() {
+        let sources = vec![
+            NodeSource {
+                key: 1,
+                channel: 4001,
+                name: "BASS Out 1".to_string(),
+                multicast_addr: channel_to_multicast(4001),
+                channels: 2,
+                stream_type: LwStreamType::Standard,
+            },
+            NodeSource {
+                key: 2,
+                channel: 4002,
+                name: "BASS Out 2".to_string(),
+                multicast_addr: Ipv4Addr::new(239, 69, 1, 2),
+                channels: 2,
+                stream_type: LwStreamType::Livestream,
+            },
+        ];
+        let device = Ipv4Addr::new(192, 168, 2, 30);
+        let parsed = parse_advertisement(&build_advertisement(device, &sources), Ipv4Addr::LOCALHOST);
+
+        assert_eq!(parsed.len(), 2);
+        for (p, s) in parsed.iter().zip(&sources) {
+            assert_eq!(p.channel, s.channel);
+            assert_eq!(p.name, s.name);
+            assert_eq!(p.multicast_addr, s.multicast_addr);
+            assert_eq!(p.stream_type, s.stream_type);
+            assert_eq!(p.source_ip, device);
+        }
+    }
+
+    #[test]
+    fn test_stream_formats() {
+        assert_eq!(LwStreamType::parse("live"), Some(LwStreamType::Livestream));
+        assert_eq!(LwStreamType::parse("Standard"), Some(LwStreamType::Standard));
+        assert_eq!(LwStreamType::parse("5.1"), None);
+
+        assert_eq!(LwStreamType::Livestream.packet_time_us(), 250);
+        assert_eq!(LwStreamType::Standard.packet_time_us(), 5000);
+        assert_eq!(LwStreamType::Surround.channels(), 8);
+        assert_eq!(LwStreamType::Surround.samples_per_packet(), 12);
+
+        assert_eq!(LwStreamType::Livestream.min_jitter_ms(), 1);
+        assert_eq!(LwStreamType::Standard.min_jitter_ms(), 15);
+    }
+
+    #[test]
+    fn test_rejects_bad_packets() {
+        let body = source_block(b"S001", 10, "One", [0; 4], 1);
+        let mut packet = advertisement(&body);
+        packet[0] = 0xFF;
+        assert!(parse_advertisement(&packet, Ipv4Addr::LOCALHOST).is_empty());
+
+        // Truncated source block
+        let packet = advertisement(&body[..body.len() - 3]);
+        assert!(parse_advertisement(&packet, Ipv4Addr::LOCALHOST).is_empty());
+
+        // Channel out of range
+        let packet = advertisement(&source_block(b"S001", 40000, "Bad", [0; 4], 1));
+        assert!(parse_advertisement(&packet, Ipv4Addr::LOCALHOST).is_empty());
+    }
+}
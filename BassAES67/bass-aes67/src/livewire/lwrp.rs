@@ -0,0 +1,466 @@
+//! Livewire Routing Protocol (LWRP) server.
+//!
+//! LWRP is a line-based text protocol on TCP port 93 that Axia consoles and
+//! PathfinderPC use to inspect and control Livewire nodes. Each line is a
+//! command word, an optional source/destination number and `KEY:value`
+//! parameters (values with spaces are double-quoted). Supported commands:
+//!
+//! - `VER` - node version and source/destination counts
+//! - `LOGIN [password]` - unlock the commands that change the node
+//! - `SRC [n] [PSNM:"name"]` - list sources, or rename source `n`
+//! - `DST [n] [NAME:"name"] [ADDR:"channel or group"]` - list destinations,
+//!   or rename / route destination `n`
+//!
+//! Listing works without login; changes need a successful `LOGIN`.
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use super::node::{self, NodeDestination, NodeSource, NodeState, DEVICE_NAME};
+use super::channel_to_multicast;
+
+/// Standard LWRP TCP port
+pub const LWRP_PORT: u16 = 93;
+
+/// Protocol version reported by VER
+const LWRP_VERSION: &str = "1.4";
+
+/// Longest accepted command line
+const MAX_LINE: usize = 4096;
+
+// Error codes
+const ERR_LOGIN: u32 = 1000;
+const ERR_COMMAND: u32 = 1001;
+const ERR_PARAMETER: u32 = 1002;
+const ERR_NOT_FOUND: u32 = 1003;
+const ERR_PERMISSION: u32 = 1004;
+
+/// Per-connection state
+#[derive(Debug, Default)]
+pub struct Session {
+    /// Password required by LOGIN (None = any password is accepted)
+    pub password: Option<String>,
+    pub logged_in: bool,
+}
+
+impl Session {
+    pub fn new(password: Option<String>) -> Self {
+        Self {
+            password,
+            logged_in: false,
+        }
+    }
+}
+
+/// `KEY:value` parameters of a command, keys upper-cased
+type Params = Vec<(String, String)>;
+
+/// Split a command line into words and `KEY:value` parameters
+fn tokenize(line: &str) -> Result<(Vec<String>, Params), String> {
+    let mut words = Vec::new();
+    let mut params = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ':') {
+            token.push(c);
+        }
+        if chars.next_if_eq(&':').is_none() {
+            words.push(token);
+            continue;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => value.push(c),
+                    None => return Err(format!("Unterminated value for {}", token)),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        params.push((token.to_ascii_uppercase(), value));
+    }
+    Ok((words, params))
+}
+
+fn error(code: u32, message: &str) -> String {
+    format!("ERROR {} \"{}\"", code, message.replace('"', "'"))
+}
+
+fn src_line(number: usize, source: &NodeSource) -> String {
+    format!(
+        "SRC {} PSNM:\"{}\" RTPE:1 RTPA:\"{}\" NCHN:{}",
+        number,
+        source.name.replace('"', "'"),
+        source.multicast_addr,
+        source.channels
+    )
+}
+
+fn dst_line(dst: &NodeDestination) -> String {
+    // Standard groups are shown as their channel number, as consoles do
+    let addr = match dst.address() {
+        None => String::new(),
+        Some(a) => {
+            let [_, _, hi, lo] = a.octets();
+            let channel = u16::from_be_bytes([hi, lo]);
+            if channel_to_multicast(channel) == a && channel != 0 {
+                channel.to_string()
+            } else {
+                a.to_string()
+            }
+        }
+    };
+    format!(
+        "DST {} NAME:\"{}\" ADDR:\"{}\" NCHN:{}",
+        dst.number,
+        dst.name.replace('"', "'"),
+        addr,
+        dst.channels
+    )
+}
+
+/// Handle one command line and return the reply lines
+pub fn handle_command(state: &mut NodeState, session: &mut Session, line: &str) -> Vec<String> {
+    let (words, params) = match tokenize(line) {
+        Ok(t) => t,
+        Err(e) => return vec![error(ERR_COMMAND, &e)],
+    };
+    let Some(command) = words.first() else {
+        return Vec::new();
+    };
+    let command = command.to_ascii_uppercase();
+    // SRC/DST take a source/destination number, LOGIN a password
+    let number = match words.get(1).filter(|_| command != "LOGIN").map(|w| w.parse::<u16>()) {
+        None => None,
+        Some(Ok(n)) => Some(n),
+        Some(Err(_)) => return vec![error(ERR_PARAMETER, "Invalid number")],
+    };
+    if !params.is_empty() && !session.logged_in {
+        return vec![error(ERR_PERMISSION, "Login required")];
+    }
+
+    match command.as_str() {
+        "VER" => vec![format!(
+            "VER LWRP:{} DEVN:\"{}\" SYSV:{} NSRC:{} NDST:{} NGPI:0 NGPO:0",
+            LWRP_VERSION,
+            DEVICE_NAME,
+            env!("CARGO_PKG_VERSION"),
+            state.sources.len(),
+            state.destinations.len()
+        )],
+
+        "LOGIN" => {
+            let accepted = match &session.password {
+                None => true,
+                Some(p) => words.get(1) == Some(p),
+            };
+            session.logged_in = accepted;
+            if accepted {
+                Vec::new()
+            } else {
+                vec![error(ERR_LOGIN, "Invalid password")]
+            }
+        }
+
+        "SRC" => {
+            let Some(n) = number else {
+                return state
+                    .sources
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| src_line(i + 1, s))
+                    .collect();
+            };
+            let index = n as usize;
+            if index == 0 || index > state.sources.len() {
+                return vec![error(ERR_NOT_FOUND, "No such source")];
+            }
+            if let Some((key, _)) = params.iter().find(|(k, _)| k != "PSNM") {
+                return vec![error(ERR_PARAMETER, &format!("Unsupported parameter {}", key))];
+            }
+            if let Some((_, name)) = params.iter().find(|(k, _)| k == "PSNM") {
+                state.sources[index - 1].name = name.clone();
+                node::mark_sources_changed();
+            }
+            vec![src_line(index, &state.sources[index - 1])]
+        }
+
+        "DST" => {
+            let Some(n) = number else {
+                return state.destinations.iter().map(dst_line).collect();
+            };
+            // Validate everything before changing anything
+            let mut route = None;
+            for (key, value) in &params {
+                match key.as_str() {
+                    "NAME" => {}
+                    "ADDR" => match node::parse_address(value) {
+                        Ok(addr) => route = Some(addr),
+                        Err(e) => return vec![error(ERR_PARAMETER, &e)],
+                    },
+                    _ => return vec![error(ERR_PARAMETER, &format!("Unsupported parameter {}", key))],
+                }
+            }
+            let Some(dst) = state.destinations.iter_mut().find(|d| d.number == n) else {
+                return vec![error(ERR_NOT_FOUND, "No such destination")];
+            };
+            if let Some((_, name)) = params.iter().find(|(k, _)| k == "NAME") {
+                dst.name = name.clone();
+            }
+            if let Some(addr) = route {
+                dst.route(addr);
+            }
+            vec![dst_line(dst)]
+        }
+
+        _ => vec![error(ERR_COMMAND, "Unknown command")],
+    }
+}
+
+// =============================================================================
+// TCP server
+// =============================================================================
+
+struct Server {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+lazy_static! {
+    static ref SERVER: Mutex<Option<Server>> = Mutex::new(None);
+}
+
+/// Start the LWRP server on `interface:port` (restarts a running server)
+pub fn start_server(interface: Ipv4Addr, port: u16, password: Option<String>) -> Result<(), String> {
+    stop_server();
+
+    let listener = TcpListener::bind(SocketAddrV4::new(interface, port))
+        .map_err(|e| format!("Failed to bind LWRP port {}: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking: {}", e))?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let running_clone = running.clone();
+    let thread = thread::Builder::new()
+        .name("lwrp-server".to_string())
+        .spawn(move || server_thread(listener, password, running_clone))
+        .map_err(|e| format!("Failed to spawn LWRP thread: {}", e))?;
+
+    *SERVER.lock() = Some(Server {
+        running,
+        thread: Some(thread),
+    });
+    Ok(())
+}
+
+/// Stop the server and close all connections
+pub fn stop_server() {
+    if let Some(mut s) = SERVER.lock().take() {
+        s.running.store(false, Ordering::SeqCst);
+        if let Some(t) = s.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+fn server_thread(listener: TcpListener, password: Option<String>, running: Arc<AtomicBool>) {
+    let mut connections: Vec<JoinHandle<()>> = Vec::new();
+
+    while running.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let session = Session::new(password.clone());
+                let running = running.clone();
+                if let Ok(t) = thread::Builder::new()
+                    .name("lwrp-connection".to_string())
+                    .spawn(move || connection_thread(stream, session, running))
+                {
+                    connections.push(t);
+                }
+            }
+            // WouldBlock: nothing to accept yet
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+        connections.retain(|t| !t.is_finished());
+    }
+
+    for t in connections {
+        let _ = t.join();
+    }
+}
+
+fn connection_thread(stream: TcpStream, mut session: Session, running: Arc<AtomicBool>) {
+    if stream.set_nonblocking(false).is_err()
+        || stream.set_read_timeout(Some(Duration::from_millis(100))).is_err()
+    {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+
+    while running.load(Ordering::SeqCst) {
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) if !line.ends_with('\n') && line.len() < MAX_LINE => continue,
+            Ok(_) => {}
+            // Timeouts keep any partial line and try again
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(_) => break,
+        }
+
+        let replies = node::with_node(|n| handle_command(n, &mut session, &line));
+        line.clear();
+
+        let mut out = String::new();
+        for reply in replies {
+            out.push_str(&reply);
+            out.push('\n');
+        }
+        if !out.is_empty() && writer.write_all(out.as_bytes()).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::livewire::lwap::LwStreamType;
+    use std::sync::atomic::AtomicU32;
+
+    fn test_node() -> (NodeState, Arc<AtomicU32>) {
+        let mut state = NodeState::default();
+        state.set_source(NodeSource {
+            key: 1,
+            channel: 4001,
+            name: "Playout 1".to_string(),
+            multicast_addr: channel_to_multicast(4001),
+            channels: 2,
+            stream_type: LwStreamType::Standard,
+        });
+        let group = Arc::new(AtomicU32::new(0));
+        state.set_destination(1, "Rec 1".to_string(), 2, group.clone());
+        (state, group)
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let (words, params) = tokenize("DST 1 NAME:\"Studio A\" ADDR:4001\r\n").unwrap();
+        assert_eq!(words, vec!["DST", "1"]);
+        assert_eq!(
+            params,
+            vec![
+                ("NAME".to_string(), "Studio A".to_string()),
+                ("ADDR".to_string(), "4001".to_string())
+            ]
+        );
+        assert!(tokenize("SRC 1 PSNM:\"open").is_err());
+    }
+
+    #[test]
+    fn test_ver_and_listing() {
+        let (mut state, _) = test_node();
+        let mut session = Session::new(None);
+
+        let ver = handle_command(&mut state, &mut session, "VER");
+        assert_eq!(ver.len(), 1);
+        assert!(ver[0].starts_with("VER LWRP:1.4 DEVN:\"BASS AES67\""));
+        assert!(ver[0].contains("NSRC:1 NDST:1"));
+
+        assert_eq!(
+            handle_command(&mut state, &mut session, "SRC"),
+            vec!["SRC 1 PSNM:\"Playout 1\" RTPE:1 RTPA:\"239.192.15.161\" NCHN:2"]
+        );
+        assert_eq!(
+            handle_command(&mut state, &mut session, "DST"),
+            vec!["DST 1 NAME:\"Rec 1\" ADDR:\"\" NCHN:2"]
+        );
+    }
+
+    #[test]
+    fn test_login_required_for_changes() {
+        let (mut state, group) = test_node();
+        let mut session = Session::new(Some("secret".to_string()));
+
+        let reply = handle_command(&mut state, &mut session, "DST 1 ADDR:\"4002\"");
+        assert!(reply[0].starts_with("ERROR 1004"));
+        assert_eq!(group.load(Ordering::SeqCst), 0);
+
+        let reply = handle_command(&mut state, &mut session, "LOGIN wrong");
+        assert!(reply[0].starts_with("ERROR 1000"));
+        assert!(handle_command(&mut state, &mut session, "LOGIN secret").is_empty());
+        assert!(session.logged_in);
+    }
+
+    #[test]
+    fn test_rename_and_route() {
+        let (mut state, group) = test_node();
+        let mut session = Session::new(None);
+        handle_command(&mut state, &mut session, "LOGIN");
+
+        assert_eq!(
+            handle_command(&mut state, &mut session, "SRC 1 PSNM:\"News\""),
+            vec!["SRC 1 PSNM:\"News\" RTPE:1 RTPA:\"239.192.15.161\" NCHN:2"]
+        );
+        assert_eq!(state.sources[0].name, "News");
+
+        assert_eq!(
+            handle_command(&mut state, &mut session, "DST 1 NAME:\"Rec A\" ADDR:\"4002\""),
+            vec!["DST 1 NAME:\"Rec A\" ADDR:\"4002\" NCHN:2"]
+        );
+        assert_eq!(Ipv4Addr::from(group.load(Ordering::SeqCst)), channel_to_multicast(4002));
+
+        handle_command(&mut state, &mut session, "DST 1 ADDR:\"239.69.1.2\"");
+        assert_eq!(Ipv4Addr::from(group.load(Ordering::SeqCst)), Ipv4Addr::new(239, 69, 1, 2));
+
+        // Invalid address leaves the route alone
+        let reply = handle_command(&mut state, &mut session, "DST 1 ADDR:\"40000\"");
+        assert!(reply[0].starts_with("ERROR 1002"));
+        assert_eq!(Ipv4Addr::from(group.load(Ordering::SeqCst)), Ipv4Addr::new(239, 69, 1, 2));
+
+        assert!(handle_command(&mut state, &mut session, "DST 9 ADDR:\"1\"")[0].starts_with("ERROR 1003"));
+        assert!(handle_command(&mut state, &mut session, "FOO")[0].starts_with("ERROR 1001"));
+    }
+
+    #[test]
+    fn test_tcp_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            connection_thread(stream, Session::new(None), Arc::new(AtomicBool::new(true)));
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(b"VER\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(client.try_clone().unwrap()).read_line(&mut reply).unwrap();
+        assert!(reply.starts_with("VER LWRP:1.4"));
+        drop(client);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+//! Livewire interoperability.
+//! Maps Livewire channel numbers to multicast groups, discovers the
+//! sources advertised on the network (LWAP) and makes this process a
+//! Livewire node: outputs are advertised as sources, and inputs can be
+//! routed as destinations through the LWRP server.
+
+pub mod lwap;
+pub mod lwrp;
+pub mod node;
+
+use std::net::Ipv4Addr;
+
+/// Lowest Livewire channel number
+pub const LW_MIN_CHANNEL: u16 = 1;
+/// Highest Livewire channel number
+pub const LW_MAX_CHANNEL: u16 = 32767;
+/// Sample rate of all Livewire streams
+pub const LW_SAMPLE_RATE: u32 = 48000;
+
+/// Standard multicast group of a Livewire channel (239.192.x.y, where
+/// x.y is the channel number as a 16-bit big-endian value)
+pub fn channel_to_multicast(channel: u16) -> Ipv4Addr {
+    let [hi, lo] = channel.to_be_bytes();
+    Ipv4Addr::new(239, 192, hi, lo)
+}
+
+/// Multicast group to receive `channel` from: the advertised group if the
+/// channel has been seen by the discovery listener, else the standard one
+pub fn resolve_channel(channel: u16) -> Ipv4Addr {
+    lwap::lookup(channel)
+        .map(|s| s.multicast_addr)
+        .unwrap_or_else(|| channel_to_multicast(channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_mapping() {
+        assert_eq!(channel_to_multicast(1), Ipv4Addr::new(239, 192, 0, 1));
+        assert_eq!(channel_to_multicast(4001), Ipv4Addr::new(239, 192, 15, 161));
+    }
+}
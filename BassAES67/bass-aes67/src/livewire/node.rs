@@ -0,0 +1,214 @@
+//! Livewire node state.
+//!
+//! Holds what this process offers to the Livewire network: sources (BASS
+//! outputs with a channel number and name) and destinations (BASS inputs
+//! that can be routed to any channel). The LWAP advertiser announces the
+//! sources, and the LWRP server lists and changes both.
+//!
+//! Destinations are retuned through an atomic multicast group that the
+//! input's receiver thread watches, so routing never touches the audio path.
+
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use super::lwap::LwStreamType;
+use super::{channel_to_multicast, LW_MAX_CHANNEL, LW_MIN_CHANNEL};
+
+/// Device name reported by VER
+pub const DEVICE_NAME: &str = "BASS AES67";
+
+/// A source offered to the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeSource {
+    /// Registration key (e.g. the output handle)
+    pub key: usize,
+    /// Livewire channel number
+    pub channel: u16,
+    /// Source name (PSNM)
+    pub name: String,
+    /// Multicast group the audio is sent to
+    pub multicast_addr: Ipv4Addr,
+    /// Number of audio channels
+    pub channels: u16,
+    pub stream_type: LwStreamType,
+}
+
+/// A destination that can be routed to a channel
+#[derive(Debug, Clone)]
+pub struct NodeDestination {
+    /// Destination number (DST n)
+    pub number: u16,
+    /// Destination name (NAME)
+    pub name: String,
+    /// Number of audio channels
+    pub channels: u16,
+    /// Multicast group the input receives (as u32; 0 = unrouted)
+    group: Arc<AtomicU32>,
+}
+
+impl NodeDestination {
+    /// Multicast group the destination currently receives
+    pub fn address(&self) -> Option<Ipv4Addr> {
+        match self.group.load(Ordering::SeqCst) {
+            0 => None,
+            a => Some(Ipv4Addr::from(a)),
+        }
+    }
+
+    /// Route the destination to `addr` (None = unrouted)
+    pub fn route(&self, addr: Option<Ipv4Addr>) {
+        self.group.store(addr.map(u32::from).unwrap_or(0), Ordering::SeqCst);
+    }
+}
+
+/// Sources and destinations of this node
+#[derive(Debug, Default)]
+pub struct NodeState {
+    /// Sources in registration order (LWRP source n = index n-1)
+    pub sources: Vec<NodeSource>,
+    /// Destinations by number
+    pub destinations: Vec<NodeDestination>,
+}
+
+impl NodeState {
+    /// Add or replace the source registered under `key`
+    pub fn set_source(&mut self, source: NodeSource) {
+        match self.sources.iter_mut().find(|s| s.key == source.key) {
+            Some(s) => *s = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    pub fn remove_source(&mut self, key: usize) -> bool {
+        let before = self.sources.len();
+        self.sources.retain(|s| s.key != key);
+        self.sources.len() != before
+    }
+
+    /// Add or replace destination `number`
+    pub fn set_destination(&mut self, number: u16, name: String, channels: u16, group: Arc<AtomicU32>) {
+        let dst = NodeDestination {
+            number,
+            name,
+            channels,
+            group,
+        };
+        match self.destinations.iter_mut().find(|d| d.number == number) {
+            Some(d) => *d = dst,
+            None => {
+                self.destinations.push(dst);
+                self.destinations.sort_by_key(|d| d.number);
+            }
+        }
+    }
+
+    /// Remove destination `number` if it still belongs to `group`
+    pub fn remove_destination(&mut self, number: u16, group: &Arc<AtomicU32>) {
+        self.destinations
+            .retain(|d| d.number != number || !Arc::ptr_eq(&d.group, group));
+    }
+}
+
+lazy_static! {
+    static ref NODE: Mutex<NodeState> = Mutex::new(NodeState::default());
+}
+
+/// Set when the sources changed (the advertiser sends a fresh advertisement)
+static SOURCES_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Access the node state
+pub fn with_node<T>(f: impl FnOnce(&mut NodeState) -> T) -> T {
+    f(&mut NODE.lock())
+}
+
+/// Flag the sources as changed
+pub fn mark_sources_changed() {
+    SOURCES_CHANGED.store(true, Ordering::SeqCst);
+}
+
+/// Take the changed flag
+pub fn take_sources_changed() -> bool {
+    SOURCES_CHANGED.swap(false, Ordering::SeqCst)
+}
+
+/// Offer an output to the network as Livewire `channel`
+pub fn register_source(
+    key: usize,
+    channel: u16,
+    name: &str,
+    multicast_addr: Ipv4Addr,
+    channels: u16,
+    stream_type: LwStreamType,
+) -> Result<(), String> {
+    if !(LW_MIN_CHANNEL..=LW_MAX_CHANNEL).contains(&channel) {
+        return Err(format!("Livewire channel must be {}-{}", LW_MIN_CHANNEL, LW_MAX_CHANNEL));
+    }
+    with_node(|n| {
+        n.set_source(NodeSource {
+            key,
+            channel,
+            name: name.to_string(),
+            multicast_addr,
+            channels,
+            stream_type,
+        })
+    });
+    mark_sources_changed();
+    Ok(())
+}
+
+/// Update the format of a registered source (does nothing if `key` isn't
+/// registered)
+pub fn update_source_format(key: usize, channels: u16, stream_type: LwStreamType) {
+    let updated = with_node(|n| match n.sources.iter_mut().find(|s| s.key == key) {
+        Some(s) => {
+            s.channels = channels;
+            s.stream_type = stream_type;
+            true
+        }
+        None => false,
+    });
+    if updated {
+        mark_sources_changed();
+    }
+}
+
+/// Withdraw a source
+pub fn unregister_source(key: usize) {
+    if with_node(|n| n.remove_source(key)) {
+        mark_sources_changed();
+    }
+}
+
+/// Make an input routable as destination `number`
+pub fn register_destination(number: u16, name: &str, channels: u16, group: Arc<AtomicU32>) {
+    with_node(|n| n.set_destination(number, name.to_string(), channels, group));
+}
+
+/// Withdraw a destination (only if `group` still owns the number)
+pub fn unregister_destination(number: u16, group: &Arc<AtomicU32>) {
+    with_node(|n| n.remove_destination(number, group));
+}
+
+/// Parse an LWRP address: a channel number or a dotted multicast group
+/// (empty or "0" = unrouted)
+pub fn parse_address(value: &str) -> Result<Option<Ipv4Addr>, String> {
+    let value = value.trim();
+    if value.is_empty() || value == "0" {
+        return Ok(None);
+    }
+    if let Ok(channel) = value.parse::<u16>() {
+        if (LW_MIN_CHANNEL..=LW_MAX_CHANNEL).contains(&channel) {
+            return Ok(Some(channel_to_multicast(channel)));
+        }
+        return Err(format!("Invalid channel {}", channel));
+    }
+    value
+        .parse::<Ipv4Addr>()
+        .map(Some)
+        .map_err(|_| format!("Invalid address {}", value))
+}
@@ -3,6 +3,10 @@
 //!
 //! Single-thread design: transmitter thread reads from BASS and sends packets
 //! at precise PTP-synchronized intervals. No Mutex in the audio path.
+//!
+//! Outputs set to a Livewire stream format send that format's packet size
+//! and channel layout, paced by the Livewire clock when it is running.
+//! An output given its own clock reference follows that clock instead.
 
 use std::ffi::c_void;
 use std::net::{UdpSocket, Ipv4Addr, SocketAddrV4};
@@ -15,7 +19,9 @@ use socket2::{Socket, Domain, Type, Protocol, SockAddr};
 
 use super::rtp::RtpPacketBuilder;
 use crate::ffi::DWORD;
-use crate::clock_bindings::{init_clock_bindings, clock_get_frequency_ppm};
+use crate::clock_bindings::{init_clock_bindings, lw_get_frequency_ppm, ClockLease};
+use crate::livewire::lwap::LwStreamType;
+use crate::livewire::LW_SAMPLE_RATE;
 
 // FFI import for BASS_ChannelGetData
 #[link(name = "bass")]
@@ -91,6 +97,24 @@ pub struct OutputStats {
     pub underruns: u64,
 }
 
+/// What the transmitter thread takes over when the stream starts
+struct TransmitterParams {
+    running: Arc<AtomicBool>,
+    stats: Arc<AtomicStats>,
+    current_ppm_x1000: Arc<AtomicI64>,
+    socket: UdpSocket,
+    dest_addr: SocketAddrV4,
+    source_channel: DWORD,
+    samples_per_packet: usize,
+    channels: u16,
+    /// Nominal packet interval
+    interval_us: u64,
+    payload_type: u8,
+    /// Livewire format: follow the Livewire clock when it runs
+    livewire_clock: bool,
+    clock: Arc<ClockLease>,
+}
+
 /// AES67 output stream - lock-free design
 /// Reads PCM from a BASS channel and transmits via RTP multicast
 pub struct Aes67OutputStream {
@@ -108,6 +132,10 @@ pub struct Aes67OutputStream {
     source_channel: DWORD,
     /// Samples per packet
     samples_per_packet: usize,
+    /// Livewire stream format (None = plain AES67 as configured)
+    livewire_stream: Option<LwStreamType>,
+    /// Clock the transmitter follows
+    clock: Arc<ClockLease>,
 }
 
 impl Aes67OutputStream {
@@ -130,9 +158,44 @@ impl Aes67OutputStream {
             config,
             source_channel,
             samples_per_packet,
+            livewire_stream: None,
+            clock: Arc::new(ClockLease::global()),
         })
     }
 
+    /// Send a Livewire stream format: 48 kHz 24-bit with the format's
+    /// channel count and packet size. The BASS source must have that many
+    /// channels. Only possible while stopped.
+    pub fn set_livewire_format(&mut self, stream_type: LwStreamType) -> Result<(), String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stream is running".to_string());
+        }
+        if let LwStreamType::Unknown(v) = stream_type {
+            return Err(format!("Unknown Livewire stream type {}", v));
+        }
+
+        self.config.channels = stream_type.channels();
+        self.config.sample_rate = LW_SAMPLE_RATE;
+        self.config.packet_time_us = stream_type.packet_time_us();
+        self.samples_per_packet = stream_type.samples_per_packet() as usize;
+        self.livewire_stream = Some(stream_type);
+        Ok(())
+    }
+
+    /// Livewire stream format, if one is set
+    pub fn livewire_format(&self) -> Option<LwStreamType> {
+        self.livewire_stream
+    }
+
+    /// Follow `clock` instead of the current clock. Only possible while stopped.
+    pub fn set_clock(&mut self, clock: ClockLease) -> Result<(), String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stream is running".to_string());
+        }
+        self.clock = Arc::new(clock);
+        Ok(())
+    }
+
     /// Create and configure the multicast UDP socket
     fn create_multicast_socket(config: &Aes67OutputConfig) -> Result<UdpSocket, String> {
         let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
@@ -188,30 +251,23 @@ impl Aes67OutputStream {
         self.running.store(true, Ordering::SeqCst);
 
         // Clone shared state for thread
-        let running = self.running.clone();
-        let stats = self.stats.clone();
-        let current_ppm_x1000 = self.current_ppm_x1000.clone();
-        let source_channel = self.source_channel;
-        let samples_per_packet = self.samples_per_packet;
-        let channels = self.config.channels;
-        let interval_us = self.config.packet_time_us as u64;
-        let payload_type = self.config.payload_type;
+        let params = TransmitterParams {
+            running: self.running.clone(),
+            stats: self.stats.clone(),
+            current_ppm_x1000: self.current_ppm_x1000.clone(),
+            socket,
+            dest_addr,
+            source_channel: self.source_channel,
+            samples_per_packet: self.samples_per_packet,
+            channels: self.config.channels,
+            interval_us: self.config.packet_time_us as u64,
+            payload_type: self.config.payload_type,
+            livewire_clock: self.livewire_stream.is_some(),
+            clock: self.clock.clone(),
+        };
 
         // Spawn transmitter thread
-        let tx = thread::spawn(move || {
-            Self::transmitter_loop(
-                running,
-                stats,
-                current_ppm_x1000,
-                socket,
-                dest_addr,
-                source_channel,
-                samples_per_packet,
-                channels,
-                interval_us,
-                payload_type,
-            );
-        });
+        let tx = thread::spawn(move || Self::transmitter_loop(params));
 
         self.tx_thread = Some(tx);
         Ok(())
@@ -227,18 +283,22 @@ impl Aes67OutputStream {
     }
 
     /// Transmitter thread - reads from BASS and sends packets at precise intervals
-    fn transmitter_loop(
-        running: Arc<AtomicBool>,
-        stats: Arc<AtomicStats>,
-        current_ppm_x1000: Arc<AtomicI64>,
-        socket: UdpSocket,
-        dest_addr: SocketAddrV4,
-        source_channel: DWORD,
-        samples_per_packet: usize,
-        channels: u16,
-        interval_us: u64,
-        payload_type: u8,
-    ) {
+    fn transmitter_loop(params: TransmitterParams) {
+        let TransmitterParams {
+            running,
+            stats,
+            current_ppm_x1000,
+            socket,
+            dest_addr,
+            source_channel,
+            samples_per_packet,
+            channels,
+            interval_us,
+            payload_type,
+            livewire_clock,
+            clock,
+        } = params;
+
         // Set thread priority high for better timing (Windows)
         #[cfg(windows)]
         {
@@ -279,7 +339,13 @@ impl Aes67OutputStream {
             ppm_update_counter += 1;
             if ppm_update_counter >= 100 {
                 ppm_update_counter = 0;
-                current_ppm = clock_get_frequency_ppm();
+                // Livewire formats on the process-wide clock follow the
+                // Livewire clock when it runs
+                current_ppm = if clock.is_global() && livewire_clock {
+                    lw_get_frequency_ppm().unwrap_or_else(|| clock.frequency_ppm())
+                } else {
+                    clock.frequency_ppm()
+                };
                 current_ppm_x1000.store((current_ppm * 1000.0) as i64, Ordering::Relaxed);
             }
 
@@ -370,6 +436,11 @@ impl Aes67OutputStream {
         self.stats.send_errors.load(Ordering::Relaxed)
     }
 
+    /// Get the stream configuration
+    pub fn config(&self) -> &Aes67OutputConfig {
+        &self.config
+    }
+
     /// Check if stream is running
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
@@ -0,0 +1,120 @@
+//! Clock events for applications to log and alarm on.
+//!
+//! Events are detected where the clock state is read (see
+//! [`crate::clock_is_locked`]), which happens on audio threads. They are
+//! queued there and delivered to the application's callback on a thread of
+//! their own, which also polls the active clock so events arrive even while
+//! no stream is running.
+
+use std::ffi::c_void;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::registry;
+use crate::shared;
+use crate::ClockMode;
+
+/// Clock event callback: event (`ClockEvent`), clock mode (`ClockMode`) and
+/// the user pointer given with the callback
+pub type ClockEventCallback = unsafe extern "C" fn(u32, u32, *mut c_void);
+
+/// What happened to the process-wide clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ClockEvent {
+    /// The active clock locked
+    Locked = 1,
+    /// The active clock lost lock
+    Lost = 2,
+    /// The system clock stands in for the active clock
+    FallbackEntered = 3,
+    /// The active clock is followed again after a fallback
+    FallbackExited = 4,
+    /// The active clock follows a different master (PTP grandmaster)
+    GrandmasterChanged = 5,
+}
+
+/// How often the dispatcher polls the active clock
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Dispatcher {
+    events: Sender<(ClockEvent, ClockMode)>,
+}
+
+/// Dispatcher of the registered callback (None = no callback)
+static DISPATCHER: Mutex<Option<Dispatcher>> = Mutex::new(None);
+
+/// Raw pointers are only handed back to the application's callback
+struct UserData(*mut c_void);
+unsafe impl Send for UserData {}
+
+/// Deliver clock events to `callback`, or stop delivering them (None).
+/// The callback runs on a thread of its own, one event at a time.
+// `user` is only handed back to the callback
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn clock_set_event_callback(callback: Option<ClockEventCallback>, user: *mut c_void) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.set_event_callback)(callback, user) };
+    }
+    let mut dispatcher = DISPATCHER.lock().unwrap_or_else(|e| e.into_inner());
+
+    // Dropping the old sender ends the old dispatcher thread
+    *dispatcher = callback.map(|callback| {
+        let (events, received) = mpsc::channel::<(ClockEvent, ClockMode)>();
+        let user = UserData(user);
+        thread::spawn(move || {
+            let user = user;
+            loop {
+                match received.recv_timeout(POLL_INTERVAL) {
+                    Ok((event, mode)) => unsafe { callback(event as u32, mode as u32, user.0) },
+                    Err(RecvTimeoutError::Timeout) => registry::poll_events(),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        Dispatcher { events }
+    });
+}
+
+/// Queue `event` for the callback, if one is registered
+pub(crate) fn emit(event: ClockEvent, mode: ClockMode) {
+    let dispatcher = DISPATCHER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(dispatcher) = dispatcher.as_ref() {
+        let _ = dispatcher.events.send((event, mode));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Instant;
+
+    static RECEIVED: AtomicU32 = AtomicU32::new(0);
+
+    // Other tests may start clocks the dispatcher polls; only record ours
+    unsafe extern "C" fn record(event: u32, mode: u32, user: *mut c_void) {
+        if mode == ClockMode::Ptp as u32 && user as usize == 42 {
+            RECEIVED.store(event, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_events_reach_callback() {
+        clock_set_event_callback(Some(record), 42 as *mut c_void);
+        emit(ClockEvent::GrandmasterChanged, ClockMode::Ptp);
+
+        let start = Instant::now();
+        while RECEIVED.load(Ordering::SeqCst) == 0 && start.elapsed() < Duration::from_secs(2) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(RECEIVED.load(Ordering::SeqCst), ClockEvent::GrandmasterChanged as u32);
+
+        clock_set_event_callback(None, std::ptr::null_mut());
+        emit(ClockEvent::Lost, ClockMode::Ptp);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(RECEIVED.load(Ordering::SeqCst), ClockEvent::GrandmasterChanged as u32);
+    }
+}
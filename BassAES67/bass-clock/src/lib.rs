@@ -0,0 +1,480 @@
+//! Shared clock layer for the BASS AES67 plugins.
+//!
+//! bass-aes67 and bass-rtp both follow a network clock: PTP (IEEE 1588v2),
+//! the Axia Livewire clock, NTP, a free-running system clock, or the sender
+//! of a received stream. This crate gives them one implementation of that
+//! layer:
+//!
+//! - [`ClockSource`]: the trait every clock implements
+//! - a registry with one instance of each clock, the active clock, and the
+//!   automatic fallback to the system clock when the active clock loses lock
+//! - the unified `clock_*` API the plugins call from their audio threads
+//! - frequency slewing across clock switches, and clock events (lock,
+//!   fallback, grandmaster changes) delivered to an application callback
+//! - [`ClockLease`]: a clock reference of a single stream ([`ClockRef`]),
+//!   started on demand and shared by reference count
+//!
+//! The PTP, Livewire, System and NTP clocks live in their own crates. By
+//! default their libraries (bass_ptp, bass_livewire_clock, bass_system_clock,
+//! bass_ntp_clock) are loaded at runtime from the plugin's directory, so all
+//! plugins in a process share one running client per clock. The `static-*`
+//! features link a clock into the plugin instead; its C API (`BASS_PTP_*`,
+//! ...) is then exported by the plugin itself.
+//!
+//! The state of this layer (registry, active clock, stream clocks, events)
+//! is per binary. Built as a library of its own, bass_clock exports the API
+//! as `BASS_CLOCK_*`, and plugins that find it in their directory forward to
+//! it, so the process has one copy (see `shared`). Without it, or with any
+//! `static-*` feature, each plugin binary keeps its own.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::net::Ipv4Addr;
+
+mod events;
+mod library;
+pub mod media;
+mod reference;
+mod registry;
+mod shared;
+mod source;
+mod stream;
+
+pub use events::{clock_set_event_callback, ClockEvent, ClockEventCallback};
+pub use library::{clock_get_ptp_instance, clock_set_ntp_server, clock_set_ptp_instance};
+pub use reference::{ClockLease, ClockRef};
+pub use registry::*;
+pub use source::ClockSource;
+
+// ============================================================================
+// Clock Mode Selection
+// ============================================================================
+
+/// Clock synchronization mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ClockMode {
+    /// IEEE 1588v2 PTP (default)
+    Ptp = 0,
+    /// Axia Livewire Clock
+    Livewire = 1,
+    /// System clock (free-running, no sync)
+    System = 2,
+    /// System clock rate-matched to an NTP server
+    Ntp = 3,
+    /// Recovered from the RTP timestamps of a designated input stream
+    Stream = 4,
+}
+
+impl From<u32> for ClockMode {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => ClockMode::Livewire,
+            2 => ClockMode::System,
+            3 => ClockMode::Ntp,
+            4 => ClockMode::Stream,
+            _ => ClockMode::Ptp,
+        }
+    }
+}
+
+// ============================================================================
+// Error codes (same values as the clock libraries)
+// ============================================================================
+
+pub const CLOCK_OK: i32 = 0;
+pub const CLOCK_ERROR_ALREADY: i32 = 1;
+pub const CLOCK_ERROR_NOT_INIT: i32 = 2;
+pub const CLOCK_ERROR_SOCKET: i32 = 3;
+pub const CLOCK_ERROR_INVALID: i32 = 4;
+
+// ============================================================================
+// Clock state values (same for all clocks)
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum ClockState {
+    #[default]
+    Disabled = 0,
+    Listening = 1,
+    Uncalibrated = 2,
+    Slave = 3,
+    Master = 4,
+    /// PTP only: grandmaster lost, running on the last frequency estimate
+    Holdover = 5,
+}
+
+impl From<u8> for ClockState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ClockState::Disabled,
+            1 => ClockState::Listening,
+            2 => ClockState::Uncalibrated,
+            3 => ClockState::Slave,
+            4 => ClockState::Master,
+            5 => ClockState::Holdover,
+            _ => ClockState::Disabled,
+        }
+    }
+}
+
+/// Timer callback type
+pub type ClockTimerCallback = unsafe extern "C" fn(*mut c_void);
+
+// ============================================================================
+// C API (bass_clock library)
+// ============================================================================
+//
+// Plugins forward the public API to these when they load bass_clock (see
+// `shared`). Each calls the function of the same name on this binary's state.
+
+/// Parse a C string argument
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/// Copy `s` to a caller's buffer like the clock libraries' GetStatsString
+unsafe fn write_string(s: &str, buffer: *mut c_char, buffer_size: i32) -> i32 {
+    if buffer.is_null() || buffer_size <= 0 {
+        return 0;
+    }
+    let bytes = s.as_bytes();
+    let copy_len = bytes.len().min((buffer_size - 1) as usize);
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, copy_len);
+    *buffer.add(copy_len) = 0;
+    copy_len as i32
+}
+
+fn to_code(result: Result<(), i32>) -> i32 {
+    result.err().unwrap_or(CLOCK_OK)
+}
+
+/// RTP clock rate argument (0 = detect)
+fn clock_rate(rate: u32) -> Option<u32> {
+    (rate != 0).then_some(rate)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_Init() -> i32 {
+    init_clock_bindings() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_IsAvailable(mode: u32) -> i32 {
+    clock_source(ClockMode::from(mode)).is_some() as i32
+}
+
+/// Running clocks as a bit mask (bit n = ClockMode n)
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetRunningClocks() -> u32 {
+    running_clocks().iter().fold(0, |mask, &mode| mask | 1 << mode as u32)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetActiveClock() -> u32 {
+    get_active_clock() as u32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_IsFallbackActive() -> i32 {
+    is_fallback_active() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_SetFallbackTimeout(seconds: u32) {
+    set_fallback_timeout(seconds);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetFallbackTimeout() -> u32 {
+    get_fallback_timeout()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_SetFallbackReturnDelay(seconds: u32) {
+    set_fallback_return_delay(seconds);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetFallbackReturnDelay() -> u32 {
+    get_fallback_return_delay()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_SetSlewRate(ppm_per_sec: f64) {
+    set_slew_rate(ppm_per_sec);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetSlewRate() -> f64 {
+    get_slew_rate()
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_Start(interface_ip: *const c_char, domain: u8, mode: u32) -> i32 {
+    match c_str(interface_ip).and_then(|s| s.parse::<Ipv4Addr>().ok()) {
+        Some(interface) => to_code(clock_start(interface, domain, ClockMode::from(mode))),
+        None => CLOCK_ERROR_INVALID,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_Stop() {
+    clock_stop();
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_ForceStop() {
+    clock_force_stop();
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_IsRunning() -> i32 {
+    clock_is_running() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetOffset() -> i64 {
+    clock_get_offset()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetFrequencyPPM() -> f64 {
+    clock_get_frequency_ppm()
+}
+
+/// CLOCK_ERROR_NOT_INIT if the Livewire clock isn't running
+///
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_LwGetFrequencyPPM(ppm: *mut f64) -> i32 {
+    if ppm.is_null() {
+        return CLOCK_ERROR_INVALID;
+    }
+    match lw_get_frequency_ppm() {
+        Some(value) => {
+            *ppm = value;
+            CLOCK_OK
+        }
+        None => CLOCK_ERROR_NOT_INIT,
+    }
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_GetStatsString(buffer: *mut c_char, buffer_size: i32) -> i32 {
+    write_string(&clock_get_stats_string(), buffer, buffer_size)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetState() -> u8 {
+    clock_get_state() as u8
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_IsLocked() -> i32 {
+    clock_is_locked() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetVersion() -> u32 {
+    clock_get_version()
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_TimerStart(
+    interval_ms: u32,
+    callback: Option<ClockTimerCallback>,
+    user: *mut c_void,
+) -> i32 {
+    to_code(clock_timer_start(interval_ms, callback, user))
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerStop() {
+    clock_timer_stop();
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerIsRunning() -> i32 {
+    clock_timer_is_running() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerSetInterval(interval_ms: u32) -> i32 {
+    to_code(clock_timer_set_interval(interval_ms))
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerGetInterval() -> u32 {
+    clock_timer_get_interval()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerSetPLL(enabled: i32) {
+    clock_timer_set_pll(enabled != 0);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_TimerIsPLLEnabled() -> i32 {
+    clock_timer_is_pll_enabled() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_SetPtpInstance(handle: u32) {
+    clock_set_ptp_instance(handle);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_GetPtpInstance() -> u32 {
+    clock_get_ptp_instance()
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_SetNtpServer(server: *const c_char) {
+    clock_set_ntp_server(c_str(server).unwrap_or(""));
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_SetEventCallback(callback: Option<ClockEventCallback>, user: *mut c_void) {
+    clock_set_event_callback(callback, user);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaNewSourceKey() -> usize {
+    media::new_source_key()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaSelectSource(key: usize, rate: u32) {
+    media::select_source(key, clock_rate(rate));
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaGetSource() -> usize {
+    media::source()
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaFollow(key: usize, rate: u32) {
+    media::follow(key, clock_rate(rate));
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaUnfollow(key: usize) {
+    media::unfollow(key);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaReleaseSource(key: usize) {
+    media::release_source(key);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaFeed(key: usize, timestamp: u32) {
+    media::feed(key, timestamp);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaGetFrequencyPPM(key: usize) -> f64 {
+    media::frequency_ppm(key)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaIsLocked(key: usize) -> i32 {
+    media::is_locked(key) as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaGetState(key: usize) -> u8 {
+    media::state(key)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_MediaGetOffset(key: usize) -> i64 {
+    media::offset_ns(key)
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_MediaGetStatsString(key: usize, buffer: *mut c_char, buffer_size: i32) -> i32 {
+    write_string(&media::stats_string(key), buffer, buffer_size)
+}
+
+/// Acquire a clock lease for `reference` ("ptp:1", "lw", "stream:42" ...).
+/// `stream_key` is the media clock key of a stream reference (0 otherwise).
+/// The lease handle is written to `handle`.
+///
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_LeaseAcquire(
+    reference: *const c_char,
+    interface_ip: *const c_char,
+    stream_key: usize,
+    handle: *mut u32,
+) -> i32 {
+    let reference = c_str(reference).and_then(|s| s.parse::<ClockRef>().ok());
+    let interface = c_str(interface_ip).and_then(|s| s.parse::<Ipv4Addr>().ok());
+    let (Some(reference), Some(interface)) = (reference, interface) else {
+        return CLOCK_ERROR_INVALID;
+    };
+    if handle.is_null() {
+        return CLOCK_ERROR_INVALID;
+    }
+    match reference::hold(reference, interface, stream_key) {
+        Ok(h) => {
+            *handle = h;
+            CLOCK_OK
+        }
+        Err(err) => err,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_LeaseRelease(handle: u32) {
+    reference::release_held(handle);
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_LeaseIsLocked(handle: u32) -> i32 {
+    reference::with_held(handle, |lease| lease.is_locked()).unwrap_or(false) as i32
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_LeaseGetFrequencyPPM(handle: u32) -> f64 {
+    reference::with_held(handle, |lease| lease.frequency_ppm()).unwrap_or(0.0)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_LeaseGetOffset(handle: u32) -> i64 {
+    reference::with_held(handle, |lease| lease.offset_ns()).unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn BASS_CLOCK_LeaseGetState(handle: u32) -> u8 {
+    reference::with_held(handle, |lease| lease.state()).unwrap_or_default() as u8
+}
+
+/// # Safety
+/// Pointer arguments must be null or valid for the call.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_CLOCK_LeaseGetStatsString(handle: u32, buffer: *mut c_char, buffer_size: i32) -> i32 {
+    let stats = reference::with_held(handle, |lease| lease.stats_string()).unwrap_or_default();
+    write_string(&stats, buffer, buffer_size)
+}
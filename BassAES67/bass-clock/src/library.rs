@@ -0,0 +1,845 @@
+//! Clocks implemented by the clock libraries: bass_ptp, bass_livewire_clock,
+//! bass_system_clock and bass_ntp_clock.
+//!
+//! All four export the same C API under their own prefix (`BASS_PTP_Start`,
+//! `BASS_LW_Start`, ...); only the arguments of `Start` differ. A library is
+//! either loaded at runtime or, with its `static-*` feature, linked into the
+//! plugin. Both fill the same function table.
+
+use std::ffi::{c_char, c_void, CString};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use crate::shared;
+use crate::source::ClockSource;
+use crate::{ClockMode, ClockState, ClockTimerCallback, CLOCK_ERROR_INVALID, CLOCK_ERROR_NOT_INIT, CLOCK_OK};
+
+// ============================================================================
+// Function pointer types
+// ============================================================================
+
+type StartPtpFn = unsafe extern "C" fn(*const c_char, u8) -> i32;
+type StartInterfaceFn = unsafe extern "C" fn(*const c_char) -> i32;
+type StartNtpFn = unsafe extern "C" fn(*const c_char, *const c_char) -> i32;
+type StatusFn = unsafe extern "C" fn() -> i32;
+type GetOffsetFn = unsafe extern "C" fn() -> i64;
+type GetFrequencyPpmFn = unsafe extern "C" fn() -> f64;
+type GetStatsStringFn = unsafe extern "C" fn(*mut c_char, i32) -> i32;
+type GetU32Fn = unsafe extern "C" fn() -> u32;
+type GetStateFn = unsafe extern "C" fn() -> u8;
+type TimerStartFn = unsafe extern "C" fn(u32, Option<ClockTimerCallback>, *mut c_void) -> i32;
+type SetU32Fn = unsafe extern "C" fn(u32) -> i32;
+type SetFlagFn = unsafe extern "C" fn(i32) -> i32;
+
+// PTP instance getters (first argument is the instance handle)
+type InstanceGetOffsetFn = unsafe extern "C" fn(u32) -> i64;
+type InstanceGetFrequencyPpmFn = unsafe extern "C" fn(u32) -> f64;
+type InstanceGetStateFn = unsafe extern "C" fn(u32) -> u8;
+type InstanceIsLockedFn = unsafe extern "C" fn(u32) -> i32;
+type InstanceGetStatsStringFn = unsafe extern "C" fn(u32, *mut c_char, i32) -> i32;
+type CreateInstanceFn = unsafe extern "C" fn(*const PtpConfig) -> u32;
+type DestroyInstanceFn = unsafe extern "C" fn(u32) -> i32;
+type GetGrandmasterIdFn = unsafe extern "C" fn(u32) -> u64;
+
+/// bass_ptp's BASS_PTP_CONFIG; zero fields select the library defaults,
+/// except the log intervals where 0 means 1 second
+#[repr(C)]
+struct PtpConfig {
+    struct_size: u32,
+    interface_ip: *const c_char,
+    domain: u8,
+    transport: u8,
+    master_enabled: u8,
+    priority1: u8,
+    priority2: u8,
+    clock_class: u8,
+    log_sync_interval: i8,
+    log_announce_interval: i8,
+    log_delay_req_interval: i8,
+    unicast_duration: u32,
+    unicast_masters: *const c_char,
+    delay_mechanism: u8,
+    profile: u8,
+}
+
+/// `Start` is the one function whose arguments differ between libraries
+#[derive(Clone, Copy)]
+enum Start {
+    /// bass_ptp: interface, domain
+    Ptp(StartPtpFn),
+    /// bass_livewire_clock, bass_system_clock: interface
+    Interface(StartInterfaceFn),
+    /// bass_ntp_clock: interface, server
+    Ntp(StartNtpFn),
+}
+
+struct Functions {
+    start: Start,
+    force_stop: StatusFn,
+    is_running: StatusFn,
+    get_offset: GetOffsetFn,
+    get_frequency_ppm: GetFrequencyPpmFn,
+    get_stats_string: GetStatsStringFn,
+    get_version: GetU32Fn,
+    get_state: GetStateFn,
+    is_locked: StatusFn,
+    timer_start: TimerStartFn,
+    timer_stop: StatusFn,
+    timer_is_running: StatusFn,
+    timer_set_interval: SetU32Fn,
+    timer_get_interval: GetU32Fn,
+    timer_set_pll: SetFlagFn,
+    timer_is_pll_enabled: StatusFn,
+}
+
+/// Optional: only present in bass_ptp builds with instance support
+#[derive(Default)]
+struct InstanceFunctions {
+    create: Option<CreateInstanceFn>,
+    destroy: Option<DestroyInstanceFn>,
+    get_offset: Option<InstanceGetOffsetFn>,
+    get_frequency_ppm: Option<InstanceGetFrequencyPpmFn>,
+    get_state: Option<InstanceGetStateFn>,
+    is_locked: Option<InstanceIsLockedFn>,
+    get_stats_string: Option<InstanceGetStatsStringFn>,
+    /// Newer bass_ptp only; also reads the default client (handle 0)
+    get_grandmaster_id: Option<GetGrandmasterIdFn>,
+}
+
+/// The instance functions needed to run a client instance of our own
+#[derive(Clone, Copy)]
+struct InstanceApi {
+    create: CreateInstanceFn,
+    destroy: DestroyInstanceFn,
+    get_offset: InstanceGetOffsetFn,
+    get_frequency_ppm: InstanceGetFrequencyPpmFn,
+    get_state: InstanceGetStateFn,
+    is_locked: InstanceIsLockedFn,
+    get_stats_string: InstanceGetStatsStringFn,
+}
+
+impl InstanceFunctions {
+    fn api(&self) -> Option<InstanceApi> {
+        Some(InstanceApi {
+            create: self.create?,
+            destroy: self.destroy?,
+            get_offset: self.get_offset?,
+            get_frequency_ppm: self.get_frequency_ppm?,
+            get_state: self.get_state?,
+            is_locked: self.is_locked?,
+            get_stats_string: self.get_stats_string?,
+        })
+    }
+}
+
+// ============================================================================
+// Settings read when the clocks start or are queried
+// ============================================================================
+
+/// Selected bass_ptp instance handle (0 = default client started by clock_start)
+static PTP_INSTANCE: AtomicU32 = AtomicU32::new(0);
+
+/// Select which bass_ptp instance the PTP getters read.
+///
+/// The instance is created by the application (BASS_PTP_CreateInstance);
+/// 0 selects the default client.
+pub fn clock_set_ptp_instance(handle: u32) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.set_ptp_instance)(handle) };
+    }
+    PTP_INSTANCE.store(handle, Ordering::Relaxed);
+}
+
+/// Get the selected bass_ptp instance handle
+pub fn clock_get_ptp_instance() -> u32 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_ptp_instance)() };
+    }
+    PTP_INSTANCE.load(Ordering::Relaxed)
+}
+
+/// NTP server for the NTP clock mode ("host[:port]", empty = library default)
+static NTP_SERVER: Mutex<String> = Mutex::new(String::new());
+
+/// Set the NTP server used by the next clock start in NTP mode
+pub fn clock_set_ntp_server(server: &str) {
+    if let Some(library) = shared::library() {
+        let server = CString::new(server.trim()).unwrap_or_default();
+        return unsafe { (library.set_ntp_server)(server.as_ptr()) };
+    }
+    *NTP_SERVER.lock().unwrap_or_else(|e| e.into_inner()) = server.trim().to_string();
+}
+
+// ============================================================================
+// Library clock
+// ============================================================================
+
+/// Where a library's file and symbols come from
+struct LibrarySpec {
+    mode: ClockMode,
+    name: &'static str,
+    /// Symbol prefix ("BASS_PTP_")
+    prefix: &'static str,
+    /// File name without platform prefix/extension ("bass_ptp")
+    file: &'static str,
+}
+
+const LIBRARIES: [LibrarySpec; 4] = [
+    LibrarySpec { mode: ClockMode::Ptp, name: "PTP", prefix: "BASS_PTP_", file: "bass_ptp" },
+    LibrarySpec { mode: ClockMode::Livewire, name: "Livewire", prefix: "BASS_LW_", file: "bass_livewire_clock" },
+    LibrarySpec { mode: ClockMode::System, name: "System", prefix: "BASS_SYS_", file: "bass_system_clock" },
+    LibrarySpec { mode: ClockMode::Ntp, name: "NTP", prefix: "BASS_NTP_", file: "bass_ntp_clock" },
+];
+
+/// A clock backed by one of the clock libraries
+pub(crate) struct LibraryClock {
+    mode: ClockMode,
+    name: &'static str,
+    functions: Functions,
+    instance: InstanceFunctions,
+    /// Library handle, kept loaded for the process lifetime (null when linked)
+    _handle: *mut c_void,
+}
+
+// The function table only holds plain function pointers
+unsafe impl Send for LibraryClock {}
+unsafe impl Sync for LibraryClock {}
+
+/// Get the clock library for `mode`: linked in if its `static-*` feature is
+/// enabled, otherwise loaded from the plugin's directory or the library path.
+pub(crate) fn load(mode: ClockMode) -> Option<LibraryClock> {
+    if let Some(clock) = linked(mode) {
+        return Some(clock);
+    }
+    let spec = LIBRARIES.iter().find(|s| s.mode == mode)?;
+    let handle = loader::open(spec.file);
+    if handle.is_null() {
+        return None;
+    }
+    let clock = LibraryClock::from_library(spec, handle);
+    if clock.is_none() {
+        loader::close(handle);
+    }
+    clock
+}
+
+impl LibraryClock {
+    /// Resolve the function table from a loaded library. Returns None if a
+    /// required symbol is missing.
+    fn from_library(spec: &LibrarySpec, handle: *mut c_void) -> Option<Self> {
+        let symbol = |name: &str| loader::symbol(handle, &format!("{}{}", spec.prefix, name));
+
+        macro_rules! load_fn {
+            ($name:expr) => {{
+                let ptr = symbol($name);
+                if ptr.is_null() {
+                    return None;
+                }
+                unsafe { std::mem::transmute_copy::<*mut c_void, _>(&ptr) }
+            }};
+        }
+
+        macro_rules! load_opt_fn {
+            ($name:expr) => {{
+                let ptr = symbol($name);
+                if ptr.is_null() {
+                    None
+                } else {
+                    Some(unsafe { std::mem::transmute_copy::<*mut c_void, _>(&ptr) })
+                }
+            }};
+        }
+
+        let start = match spec.mode {
+            ClockMode::Ptp => Start::Ptp(load_fn!("Start")),
+            ClockMode::Ntp => Start::Ntp(load_fn!("Start")),
+            _ => Start::Interface(load_fn!("Start")),
+        };
+
+        let functions = Functions {
+            start,
+            force_stop: load_fn!("ForceStop"),
+            is_running: load_fn!("IsRunning"),
+            get_offset: load_fn!("GetOffset"),
+            get_frequency_ppm: load_fn!("GetFrequencyPPM"),
+            get_stats_string: load_fn!("GetStatsString"),
+            get_version: load_fn!("GetVersion"),
+            get_state: load_fn!("GetState"),
+            is_locked: load_fn!("IsLocked"),
+            timer_start: load_fn!("TimerStart"),
+            timer_stop: load_fn!("TimerStop"),
+            timer_is_running: load_fn!("TimerIsRunning"),
+            timer_set_interval: load_fn!("TimerSetInterval"),
+            timer_get_interval: load_fn!("TimerGetInterval"),
+            timer_set_pll: load_fn!("TimerSetPLL"),
+            timer_is_pll_enabled: load_fn!("TimerIsPLLEnabled"),
+        };
+
+        let instance = if spec.mode == ClockMode::Ptp {
+            InstanceFunctions {
+                create: load_opt_fn!("CreateInstance"),
+                destroy: load_opt_fn!("DestroyInstance"),
+                get_offset: load_opt_fn!("InstanceGetOffset"),
+                get_frequency_ppm: load_opt_fn!("InstanceGetFrequencyPPM"),
+                get_state: load_opt_fn!("InstanceGetState"),
+                is_locked: load_opt_fn!("InstanceIsLocked"),
+                get_stats_string: load_opt_fn!("InstanceGetStatsString"),
+                get_grandmaster_id: load_opt_fn!("GetGrandmasterId"),
+            }
+        } else {
+            InstanceFunctions::default()
+        };
+
+        Some(Self {
+            mode: spec.mode,
+            name: spec.name,
+            functions,
+            instance,
+            _handle: handle,
+        })
+    }
+
+    /// Selected PTP instance, if this library can read it
+    fn selected_instance<F>(&self, f: Option<F>) -> Option<(u32, F)> {
+        match (clock_get_ptp_instance(), f) {
+            (0, _) | (_, None) => None,
+            (handle, Some(f)) => Some((handle, f)),
+        }
+    }
+}
+
+impl ClockSource for LibraryClock {
+    fn mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn start(&self, interface: Ipv4Addr, domain: u8) -> Result<(), i32> {
+        let ip_str = CString::new(interface.to_string()).map_err(|_| CLOCK_ERROR_NOT_INIT)?;
+        let result = match self.functions.start {
+            Start::Ptp(f) => unsafe { f(ip_str.as_ptr(), domain) },
+            Start::Interface(f) => unsafe { f(ip_str.as_ptr()) },
+            Start::Ntp(f) => {
+                let server = NTP_SERVER.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                let server = CString::new(server).map_err(|_| CLOCK_ERROR_INVALID)?;
+                unsafe { f(ip_str.as_ptr(), server.as_ptr()) }
+            }
+        };
+        if result == CLOCK_OK {
+            Ok(())
+        } else {
+            Err(result)
+        }
+    }
+
+    fn force_stop(&self) {
+        unsafe { (self.functions.force_stop)(); }
+    }
+
+    fn is_running(&self) -> bool {
+        unsafe { (self.functions.is_running)() != 0 }
+    }
+
+    // Getters for the selected PTP instance fall back to the default client
+    // when no instance is selected or the library has no instance support
+
+    fn offset_ns(&self) -> i64 {
+        match self.selected_instance(self.instance.get_offset) {
+            Some((handle, f)) => unsafe { f(handle) },
+            None => unsafe { (self.functions.get_offset)() },
+        }
+    }
+
+    fn frequency_ppm(&self) -> f64 {
+        match self.selected_instance(self.instance.get_frequency_ppm) {
+            Some((handle, f)) => unsafe { f(handle) },
+            None => unsafe { (self.functions.get_frequency_ppm)() },
+        }
+    }
+
+    fn state(&self) -> ClockState {
+        let state = match self.selected_instance(self.instance.get_state) {
+            Some((handle, f)) => unsafe { f(handle) },
+            None => unsafe { (self.functions.get_state)() },
+        };
+        ClockState::from(state)
+    }
+
+    /// Holdover keeps the PTP timing usable; bass_ptp leaves holdover (and
+    /// reports unlocked) once its holdover timeout has passed
+    fn is_locked(&self) -> bool {
+        let locked = match self.selected_instance(self.instance.is_locked) {
+            Some((handle, f)) => unsafe { f(handle) },
+            None => unsafe { (self.functions.is_locked)() },
+        };
+        locked != 0 || self.state() == ClockState::Holdover
+    }
+
+    fn stats_string(&self) -> String {
+        read_stats(|buffer, size| match self.selected_instance(self.instance.get_stats_string) {
+            Some((handle, f)) => unsafe { f(handle, buffer, size) },
+            None => unsafe { (self.functions.get_stats_string)(buffer, size) },
+        })
+    }
+
+    fn version(&self) -> u32 {
+        unsafe { (self.functions.get_version)() }
+    }
+
+    fn grandmaster_id(&self) -> u64 {
+        match self.instance.get_grandmaster_id {
+            Some(f) => unsafe { f(clock_get_ptp_instance()) },
+            None => 0,
+        }
+    }
+
+    fn create_instance(&self, interface: Ipv4Addr, domain: u8) -> Option<Box<dyn ClockSource>> {
+        let api = self.instance.api()?;
+        let ip_str = CString::new(interface.to_string()).ok()?;
+        let config = PtpConfig {
+            struct_size: std::mem::size_of::<PtpConfig>() as u32,
+            interface_ip: ip_str.as_ptr(),
+            domain,
+            transport: 0,
+            master_enabled: 0,
+            priority1: 0,
+            priority2: 0,
+            clock_class: 0,
+            // AES67 rates, spelled out for libraries without the 127 = default sentinel
+            log_sync_interval: -3,
+            log_announce_interval: 1,
+            log_delay_req_interval: 0,
+            unicast_duration: 0,
+            unicast_masters: std::ptr::null(),
+            delay_mechanism: 0,
+            profile: 0,
+        };
+        let handle = unsafe { (api.create)(&config) };
+        if handle == 0 {
+            return None;
+        }
+        Some(Box::new(PtpInstance {
+            handle: AtomicU32::new(handle),
+            api,
+            get_grandmaster_id: self.instance.get_grandmaster_id,
+            version: self.version(),
+        }))
+    }
+
+    fn has_timer(&self) -> bool {
+        true
+    }
+
+    fn timer_start(&self, interval_ms: u32, callback: Option<ClockTimerCallback>, user: *mut c_void) -> i32 {
+        unsafe { (self.functions.timer_start)(interval_ms, callback, user) }
+    }
+
+    fn timer_stop(&self) {
+        unsafe { (self.functions.timer_stop)(); }
+    }
+
+    fn timer_is_running(&self) -> bool {
+        unsafe { (self.functions.timer_is_running)() != 0 }
+    }
+
+    fn timer_set_interval(&self, interval_ms: u32) -> i32 {
+        unsafe { (self.functions.timer_set_interval)(interval_ms) }
+    }
+
+    fn timer_get_interval(&self) -> u32 {
+        unsafe { (self.functions.timer_get_interval)() }
+    }
+
+    fn timer_set_pll(&self, enabled: bool) {
+        unsafe { (self.functions.timer_set_pll)(if enabled { 1 } else { 0 }); }
+    }
+
+    fn timer_is_pll_enabled(&self) -> bool {
+        unsafe { (self.functions.timer_is_pll_enabled)() != 0 }
+    }
+}
+
+/// Read a stats string through a `GetStatsString`-style function
+pub(crate) fn read_stats(get: impl FnOnce(*mut c_char, i32) -> i32) -> String {
+    let mut buffer = vec![0 as c_char; 256];
+    let len = get(buffer.as_mut_ptr(), buffer.len() as i32);
+    if len > 0 {
+        let bytes: Vec<u8> = buffer[..len as usize].iter().map(|&b| b as u8).collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        String::from("Clock: Error getting stats")
+    }
+}
+
+// ============================================================================
+// PTP client instances
+// ============================================================================
+
+/// A bass_ptp client instance of its own, for streams that follow a PTP
+/// domain other than the process-wide one
+struct PtpInstance {
+    /// Instance handle (0 once stopped)
+    handle: AtomicU32,
+    api: InstanceApi,
+    get_grandmaster_id: Option<GetGrandmasterIdFn>,
+    version: u32,
+}
+
+impl PtpInstance {
+    fn handle(&self) -> u32 {
+        self.handle.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for PtpInstance {
+    fn drop(&mut self) {
+        self.force_stop();
+    }
+}
+
+impl ClockSource for PtpInstance {
+    fn mode(&self) -> ClockMode {
+        ClockMode::Ptp
+    }
+
+    fn name(&self) -> &'static str {
+        "PTP"
+    }
+
+    /// The instance runs from creation until it is stopped
+    fn start(&self, _interface: Ipv4Addr, _domain: u8) -> Result<(), i32> {
+        if self.is_running() {
+            Ok(())
+        } else {
+            Err(CLOCK_ERROR_NOT_INIT)
+        }
+    }
+
+    fn force_stop(&self) {
+        let handle = self.handle.swap(0, Ordering::AcqRel);
+        if handle != 0 {
+            unsafe { (self.api.destroy)(handle); }
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.handle() != 0
+    }
+
+    fn offset_ns(&self) -> i64 {
+        unsafe { (self.api.get_offset)(self.handle()) }
+    }
+
+    fn frequency_ppm(&self) -> f64 {
+        unsafe { (self.api.get_frequency_ppm)(self.handle()) }
+    }
+
+    fn state(&self) -> ClockState {
+        ClockState::from(unsafe { (self.api.get_state)(self.handle()) })
+    }
+
+    fn is_locked(&self) -> bool {
+        let locked = unsafe { (self.api.is_locked)(self.handle()) };
+        locked != 0 || self.state() == ClockState::Holdover
+    }
+
+    fn stats_string(&self) -> String {
+        read_stats(|buffer, size| unsafe { (self.api.get_stats_string)(self.handle(), buffer, size) })
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn grandmaster_id(&self) -> u64 {
+        match (self.handle(), self.get_grandmaster_id) {
+            (0, _) | (_, None) => 0,
+            (handle, Some(f)) => unsafe { f(handle) },
+        }
+    }
+}
+
+// The instance functions are plain function pointers
+unsafe impl Send for PtpInstance {}
+unsafe impl Sync for PtpInstance {}
+
+// ============================================================================
+// Statically linked libraries
+// ============================================================================
+
+/// Function table of a clock library linked into the plugin. The C API
+/// names are spelled out because each crate exports them under its prefix.
+#[cfg(any(feature = "static-ptp", feature = "static-livewire", feature = "static-system", feature = "static-ntp"))]
+macro_rules! linked_functions {
+    ($start:expr, $lib:ident, $force_stop:ident, $is_running:ident, $get_offset:ident,
+     $get_frequency_ppm:ident, $get_stats_string:ident, $get_version:ident, $get_state:ident,
+     $is_locked:ident, $timer_start:ident, $timer_stop:ident, $timer_is_running:ident,
+     $timer_set_interval:ident, $timer_get_interval:ident, $timer_set_pll:ident,
+     $timer_is_pll_enabled:ident) => {
+        Functions {
+            start: $start,
+            force_stop: $lib::$force_stop,
+            is_running: $lib::$is_running,
+            get_offset: $lib::$get_offset,
+            get_frequency_ppm: $lib::$get_frequency_ppm,
+            get_stats_string: $lib::$get_stats_string,
+            get_version: $lib::$get_version,
+            get_state: $lib::$get_state,
+            is_locked: $lib::$is_locked,
+            timer_start: $lib::$timer_start,
+            timer_stop: $lib::$timer_stop,
+            timer_is_running: $lib::$timer_is_running,
+            timer_set_interval: $lib::$timer_set_interval,
+            timer_get_interval: $lib::$timer_get_interval,
+            timer_set_pll: $lib::$timer_set_pll,
+            timer_is_pll_enabled: $lib::$timer_is_pll_enabled,
+        }
+    };
+}
+
+/// The clock for `mode` if its library is linked in
+#[allow(unreachable_code)]
+fn linked(mode: ClockMode) -> Option<LibraryClock> {
+    let (functions, instance): (Functions, InstanceFunctions) = match mode {
+        #[cfg(feature = "static-ptp")]
+        ClockMode::Ptp => (
+            linked_functions!(
+                Start::Ptp(bass_ptp::BASS_PTP_Start), bass_ptp,
+                BASS_PTP_ForceStop, BASS_PTP_IsRunning, BASS_PTP_GetOffset,
+                BASS_PTP_GetFrequencyPPM, BASS_PTP_GetStatsString, BASS_PTP_GetVersion,
+                BASS_PTP_GetState, BASS_PTP_IsLocked, BASS_PTP_TimerStart, BASS_PTP_TimerStop,
+                BASS_PTP_TimerIsRunning, BASS_PTP_TimerSetInterval, BASS_PTP_TimerGetInterval,
+                BASS_PTP_TimerSetPLL, BASS_PTP_TimerIsPLLEnabled
+            ),
+            InstanceFunctions {
+                // Same layout as PtpConfig, only the pointer type differs
+                create: Some(unsafe {
+                    std::mem::transmute::<
+                        unsafe extern "C" fn(*const bass_ptp::BASS_PTP_CONFIG) -> u32,
+                        CreateInstanceFn,
+                    >(bass_ptp::BASS_PTP_CreateInstance)
+                }),
+                destroy: Some(bass_ptp::BASS_PTP_DestroyInstance),
+                get_offset: Some(bass_ptp::BASS_PTP_InstanceGetOffset),
+                get_frequency_ppm: Some(bass_ptp::BASS_PTP_InstanceGetFrequencyPPM),
+                get_state: Some(bass_ptp::BASS_PTP_InstanceGetState),
+                is_locked: Some(bass_ptp::BASS_PTP_InstanceIsLocked),
+                get_stats_string: Some(bass_ptp::BASS_PTP_InstanceGetStatsString),
+                get_grandmaster_id: Some(bass_ptp::BASS_PTP_GetGrandmasterId),
+            },
+        ),
+        #[cfg(feature = "static-livewire")]
+        ClockMode::Livewire => (
+            linked_functions!(
+                Start::Interface(bass_livewire_clock::BASS_LW_Start), bass_livewire_clock,
+                BASS_LW_ForceStop, BASS_LW_IsRunning, BASS_LW_GetOffset,
+                BASS_LW_GetFrequencyPPM, BASS_LW_GetStatsString, BASS_LW_GetVersion,
+                BASS_LW_GetState, BASS_LW_IsLocked, BASS_LW_TimerStart, BASS_LW_TimerStop,
+                BASS_LW_TimerIsRunning, BASS_LW_TimerSetInterval, BASS_LW_TimerGetInterval,
+                BASS_LW_TimerSetPLL, BASS_LW_TimerIsPLLEnabled
+            ),
+            InstanceFunctions::default(),
+        ),
+        #[cfg(feature = "static-system")]
+        ClockMode::System => (
+            linked_functions!(
+                Start::Interface(bass_system_clock::BASS_SYS_Start), bass_system_clock,
+                BASS_SYS_ForceStop, BASS_SYS_IsRunning, BASS_SYS_GetOffset,
+                BASS_SYS_GetFrequencyPPM, BASS_SYS_GetStatsString, BASS_SYS_GetVersion,
+                BASS_SYS_GetState, BASS_SYS_IsLocked, BASS_SYS_TimerStart, BASS_SYS_TimerStop,
+                BASS_SYS_TimerIsRunning, BASS_SYS_TimerSetInterval, BASS_SYS_TimerGetInterval,
+                BASS_SYS_TimerSetPLL, BASS_SYS_TimerIsPLLEnabled
+            ),
+            InstanceFunctions::default(),
+        ),
+        #[cfg(feature = "static-ntp")]
+        ClockMode::Ntp => (
+            linked_functions!(
+                Start::Ntp(bass_ntp_clock::BASS_NTP_Start), bass_ntp_clock,
+                BASS_NTP_ForceStop, BASS_NTP_IsRunning, BASS_NTP_GetOffset,
+                BASS_NTP_GetFrequencyPPM, BASS_NTP_GetStatsString, BASS_NTP_GetVersion,
+                BASS_NTP_GetState, BASS_NTP_IsLocked, BASS_NTP_TimerStart, BASS_NTP_TimerStop,
+                BASS_NTP_TimerIsRunning, BASS_NTP_TimerSetInterval, BASS_NTP_TimerGetInterval,
+                BASS_NTP_TimerSetPLL, BASS_NTP_TimerIsPLLEnabled
+            ),
+            InstanceFunctions::default(),
+        ),
+        _ => return None,
+    };
+
+    let spec = LIBRARIES.iter().find(|s| s.mode == mode)?;
+    Some(LibraryClock {
+        mode: spec.mode,
+        name: spec.name,
+        functions,
+        instance,
+        _handle: std::ptr::null_mut(),
+    })
+}
+
+// ============================================================================
+// Windows-specific loading
+// ============================================================================
+
+#[cfg(windows)]
+pub(crate) mod loader {
+    use std::ffi::{c_void, CString};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LoadLibraryW(lpLibFileName: *const u16) -> *mut c_void;
+        fn FreeLibrary(hLibModule: *mut c_void) -> i32;
+        fn GetProcAddress(hModule: *mut c_void, lpProcName: *const i8) -> *mut c_void;
+        fn GetModuleHandleExW(dwFlags: u32, lpModuleName: *const u16, phModule: *mut *mut c_void) -> i32;
+        fn GetModuleFileNameW(hModule: *mut c_void, lpFilename: *mut u16, nSize: u32) -> u32;
+    }
+
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+
+    fn to_wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Directory of the plugin DLL this crate is linked into
+    fn get_dll_directory() -> Option<String> {
+        unsafe {
+            let mut module = std::ptr::null_mut();
+            let address = get_dll_directory as *const u16;
+            let flags = GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT;
+            if GetModuleHandleExW(flags, address, &mut module) == 0 {
+                return None;
+            }
+
+            let mut path = vec![0u16; 260];
+            let len = GetModuleFileNameW(module, path.as_mut_ptr(), path.len() as u32);
+            if len == 0 {
+                return None;
+            }
+
+            let path_str = String::from_utf16_lossy(&path[..len as usize]);
+            let path = std::path::Path::new(&path_str);
+            path.parent().map(|p| p.to_string_lossy().into_owned())
+        }
+    }
+
+    pub fn open(file: &str) -> *mut c_void {
+        let dll_name = format!("{}.dll", file);
+        unsafe {
+            let dll_path = if let Some(dir) = get_dll_directory() {
+                format!("{}\\{}", dir, dll_name)
+            } else {
+                dll_name.clone()
+            };
+
+            let handle = LoadLibraryW(to_wide(&dll_path).as_ptr());
+            if handle.is_null() {
+                LoadLibraryW(to_wide(&dll_name).as_ptr())
+            } else {
+                handle
+            }
+        }
+    }
+
+    pub fn symbol(handle: *mut c_void, name: &str) -> *mut c_void {
+        match CString::new(name) {
+            Ok(c_name) => unsafe { GetProcAddress(handle, c_name.as_ptr()) },
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
+    pub fn close(handle: *mut c_void) {
+        unsafe { FreeLibrary(handle); }
+    }
+}
+
+// ============================================================================
+// Linux/macOS loading
+// ============================================================================
+
+#[cfg(not(windows))]
+pub(crate) mod loader {
+    use std::ffi::{c_void, CString};
+    use std::path::Path;
+
+    // dlopen flags
+    const RTLD_NOW: i32 = 2;
+    const RTLD_LOCAL: i32 = 0;
+
+    extern "C" {
+        fn dlopen(filename: *const i8, flags: i32) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const i8) -> *mut c_void;
+        fn dlclose(handle: *mut c_void) -> i32;
+    }
+
+    /// Try to find the directory containing `lib_name`
+    fn get_lib_directory(lib_name: &str) -> Option<String> {
+        // Check if the library is in the current directory
+        if Path::new(".").join(lib_name).exists() {
+            return Some(".".to_string());
+        }
+
+        // Check executable directory and parent directories
+        // This handles running from target/release/examples/ where libs are in target/release/
+        if let Ok(exe_path) = std::env::current_exe() {
+            // Check executable's directory
+            if let Some(dir) = exe_path.parent() {
+                if dir.join(lib_name).exists() {
+                    return Some(dir.to_string_lossy().into_owned());
+                }
+
+                // Check parent directory (for examples/ subdirectory case)
+                if let Some(parent) = dir.parent() {
+                    if parent.join(lib_name).exists() {
+                        return Some(parent.to_string_lossy().into_owned());
+                    }
+                }
+            }
+        }
+
+        // Also check target/release relative to current working directory
+        if Path::new("target/release").join(lib_name).exists() {
+            return Some("target/release".to_string());
+        }
+
+        None
+    }
+
+    pub fn open(file: &str) -> *mut c_void {
+        let lib_name = format!("lib{}.so", file);
+        unsafe {
+            // Try with directory prefix first (same directory as the plugin)
+            if let Some(dir) = get_lib_directory(&lib_name) {
+                let full_path = format!("{}/{}", dir, lib_name);
+                if let Ok(c_path) = CString::new(full_path) {
+                    let handle = dlopen(c_path.as_ptr(), RTLD_NOW | RTLD_LOCAL);
+                    if !handle.is_null() {
+                        return handle;
+                    }
+                }
+            }
+
+            // Fall back to letting dlopen search standard paths (LD_LIBRARY_PATH, etc.)
+            let c_name = match CString::new(lib_name) {
+                Ok(s) => s,
+                Err(_) => return std::ptr::null_mut(),
+            };
+            dlopen(c_name.as_ptr(), RTLD_NOW | RTLD_LOCAL)
+        }
+    }
+
+    pub fn symbol(handle: *mut c_void, name: &str) -> *mut c_void {
+        match CString::new(name) {
+            Ok(c_name) => unsafe { dlsym(handle, c_name.as_ptr()) },
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
+    pub fn close(handle: *mut c_void) {
+        unsafe { dlclose(handle); }
+    }
+}
@@ -0,0 +1,523 @@
+//! Media clock recovery: use the sender of an incoming stream as the clock.
+//!
+//! Without a shared PTP or Livewire clock, the best reference for audio
+//! leaving this process is often the sender of a stream it receives. The
+//! stream's RTP timestamps advance at the sender's sample rate, so comparing
+//! them with local arrival times gives the sender's rate against the local
+//! clock.
+//!
+//! Network jitter only ever delays packets, so each one-second window keeps
+//! its least-delayed packet and a line is fitted through those points. The
+//! slope is the frequency; its standard error decides lock. A timestamp jump
+//! (sender restart, new SSRC) restarts the fit and keeps the last frequency.
+//!
+//! One stream at a time is the process-wide source, which the registry
+//! exposes as the Stream clock like any other. Streams can also follow another
+//! stream directly (`follow`). Receiver threads feed every packet through
+//! `feed`, which keeps an estimator for each stream someone follows. With
+//! the bass_clock library loaded the tracked streams live there, so a stream
+//! received by one plugin can clock another.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::library::read_stats;
+use crate::shared;
+
+/// Length of a filter window (ns)
+const WINDOW_NS: i64 = 1_000_000_000;
+
+/// Windows used for the fit
+const FIT_WINDOWS: usize = 60;
+
+/// Windows needed before a frequency is reported
+const MIN_WINDOWS: usize = 4;
+
+/// Standard error of the frequency estimate below which the clock is locked (ppm)
+const LOCK_STD_ERROR_PPM: f64 = 1.0;
+
+/// Timestamp jump against local time treated as a sender restart (ns)
+const STEP_THRESHOLD_NS: i64 = 100_000_000;
+
+/// Without packets for this long the source counts as lost (ns)
+const SOURCE_TIMEOUT_NS: i64 = 1_000_000_000;
+
+/// Largest frequency reported (ppm)
+const MAX_FREQUENCY_PPM: f64 = 1000.0;
+
+/// RTP clock rates a measured rate is snapped to when the rate isn't known
+const STANDARD_RATES: [u32; 10] = [8000, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 90000, 96000];
+
+/// A measured rate within this fraction of a standard rate is snapped to it
+const SNAP_TOLERANCE: f64 = 0.01;
+
+/// Snap a measured RTP clock rate to the nearest standard rate
+fn snap_rate(measured: f64) -> u32 {
+    STANDARD_RATES
+        .iter()
+        .copied()
+        .find(|&r| (measured / r as f64 - 1.0).abs() < SNAP_TOLERANCE)
+        .unwrap_or(measured.round() as u32)
+}
+
+/// Rate estimator fed with (RTP timestamp, local arrival time) pairs
+#[derive(Debug)]
+pub struct MediaClockEstimator {
+    /// RTP clock rate (None = detect from the first window)
+    clock_rate: Option<u32>,
+    last_ts: Option<u32>,
+    /// Unwrapped timestamp
+    ext_ts: i64,
+    /// Local time and unwrapped timestamp of the first packet
+    base: Option<(i64, i64)>,
+    /// Start of the current window (ns since base)
+    window_start: i64,
+    /// Least-delayed packet of the current window: (x, y)
+    window_best: Option<(i64, i64)>,
+    /// Fit points: x = local ns since base, y = media ns - local ns
+    points: VecDeque<(i64, i64)>,
+    /// Slope and its standard error (ns per ns)
+    fit: Option<(f64, f64)>,
+    frequency_ppm: f64,
+    last_arrival_ns: Option<i64>,
+    packets: u64,
+    restarts: u64,
+}
+
+impl MediaClockEstimator {
+    pub fn new(clock_rate: Option<u32>) -> Self {
+        Self {
+            clock_rate: clock_rate.filter(|&r| r > 0),
+            last_ts: None,
+            ext_ts: 0,
+            base: None,
+            window_start: 0,
+            window_best: None,
+            points: VecDeque::with_capacity(FIT_WINDOWS),
+            fit: None,
+            frequency_ppm: 0.0,
+            last_arrival_ns: None,
+            packets: 0,
+            restarts: 0,
+        }
+    }
+
+    /// Feed one packet's RTP timestamp and local arrival time (ns)
+    pub fn update(&mut self, timestamp: u32, now_ns: i64) {
+        self.packets += 1;
+        self.last_arrival_ns = Some(now_ns);
+
+        if let Some(last) = self.last_ts {
+            self.ext_ts += timestamp.wrapping_sub(last) as i32 as i64;
+        }
+        self.last_ts = Some(timestamp);
+
+        let (base_ns, base_ts) = *self.base.get_or_insert((now_ns, self.ext_ts));
+        let x = now_ns - base_ns;
+        let ticks = self.ext_ts - base_ts;
+
+        let rate = match self.clock_rate {
+            Some(r) => r,
+            None => {
+                if x < WINDOW_NS || ticks <= 0 {
+                    return;
+                }
+                let r = snap_rate(ticks as f64 * 1e9 / x as f64);
+                self.clock_rate = Some(r);
+                self.window_start = x;
+                r
+            }
+        };
+        let y = (ticks as i128 * 1_000_000_000 / rate as i128) as i64 - x;
+
+        // A jump far from the last point means the sender restarted
+        let reference = self.window_best.or_else(|| self.points.back().copied());
+        if let Some((_, ref_y)) = reference {
+            if (y - ref_y).abs() > STEP_THRESHOLD_NS {
+                self.restart(now_ns);
+                return;
+            }
+        }
+
+        if self.window_best.is_none_or(|(_, best_y)| y > best_y) {
+            self.window_best = Some((x, y));
+        }
+
+        if x - self.window_start >= WINDOW_NS {
+            if let Some(best) = self.window_best.take() {
+                if self.points.len() >= FIT_WINDOWS {
+                    self.points.pop_front();
+                }
+                self.points.push_back(best);
+                self.refit();
+            }
+            self.window_start = x;
+        }
+    }
+
+    /// Start over from the next packet, keeping the frequency
+    fn restart(&mut self, now_ns: i64) {
+        self.restarts += 1;
+        self.base = Some((now_ns, self.ext_ts));
+        self.window_start = 0;
+        self.window_best = None;
+        self.points.clear();
+        self.fit = None;
+    }
+
+    fn refit(&mut self) {
+        let n = self.points.len();
+        if n < MIN_WINDOWS {
+            return;
+        }
+        let nf = n as f64;
+        let mean_x = self.points.iter().map(|p| p.0 as f64).sum::<f64>() / nf;
+        let mean_y = self.points.iter().map(|p| p.1 as f64).sum::<f64>() / nf;
+        let sxx: f64 = self.points.iter().map(|p| (p.0 as f64 - mean_x).powi(2)).sum();
+        if sxx <= 0.0 {
+            return;
+        }
+        let sxy: f64 = self
+            .points
+            .iter()
+            .map(|p| (p.0 as f64 - mean_x) * (p.1 as f64 - mean_y))
+            .sum();
+        let slope = sxy / sxx;
+        let residuals: f64 = self
+            .points
+            .iter()
+            .map(|p| (p.1 as f64 - mean_y - slope * (p.0 as f64 - mean_x)).powi(2))
+            .sum();
+        let slope_error = (residuals / (nf - 2.0)).sqrt() / sxx.sqrt();
+
+        self.fit = Some((slope, slope_error));
+        self.frequency_ppm = (slope * 1e6).clamp(-MAX_FREQUENCY_PPM, MAX_FREQUENCY_PPM);
+    }
+
+    /// Whether packets arrived recently
+    fn is_receiving(&self, now_ns: i64) -> bool {
+        self.last_arrival_ns
+            .is_some_and(|t| now_ns - t < SOURCE_TIMEOUT_NS)
+    }
+
+    /// Frequency adjustment in ppm: positive when the sender runs faster
+    /// than the local clock
+    pub fn frequency_ppm(&self) -> f64 {
+        self.frequency_ppm
+    }
+
+    /// Standard error of the frequency estimate (ppm), once there is one
+    pub fn frequency_error_ppm(&self) -> Option<f64> {
+        self.fit.map(|(_, e)| e * 1e6)
+    }
+
+    /// Locked: receiving and the frequency estimate is within 1 ppm
+    pub fn is_locked(&self, now_ns: i64) -> bool {
+        self.is_receiving(now_ns) && self.frequency_error_ppm().is_some_and(|e| e < LOCK_STD_ERROR_PPM)
+    }
+
+    /// Clock state (ClockState values: 1=Listening, 2=Uncalibrated, 3=Slave)
+    pub fn state(&self, now_ns: i64) -> u8 {
+        if !self.is_receiving(now_ns) {
+            1
+        } else if self.fit.is_none() {
+            2
+        } else {
+            3
+        }
+    }
+
+    /// Local clock minus sender clock since the fit started (ns)
+    pub fn offset_ns(&self) -> i64 {
+        match (self.points.front(), self.points.back()) {
+            (Some(first), Some(last)) => first.1 - last.1,
+            _ => 0,
+        }
+    }
+
+    /// RTP clock rate in use (None while still detecting)
+    pub fn clock_rate(&self) -> Option<u32> {
+        self.clock_rate
+    }
+
+    /// Packets fed so far
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Times the fit restarted after a timestamp jump
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+}
+
+// ============================================================================
+// Tracked sources
+// ============================================================================
+//
+// A stream is tracked while something follows it: the process-wide
+// selection (`select_source`) or per-stream clock references (`follow`).
+// Every tracked stream has its own estimator.
+
+/// Estimator of a tracked stream and how many users follow it
+struct Tracked {
+    estimator: MediaClockEstimator,
+    users: usize,
+}
+
+/// Key of the stream selected as the process-wide clock (0 = none)
+static SOURCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of tracked streams; lets `feed` skip the lock when there are none
+static TRACKED: AtomicUsize = AtomicUsize::new(0);
+
+/// Next key handed out by `new_source_key`
+static NEXT_KEY: AtomicUsize = AtomicUsize::new(1);
+
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+lazy_static! {
+    static ref SOURCES: Mutex<HashMap<usize, Tracked>> = Mutex::new(HashMap::new());
+}
+
+fn now_ns() -> i64 {
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as i64
+}
+
+fn track(sources: &mut HashMap<usize, Tracked>, key: usize, clock_rate: Option<u32>) {
+    sources
+        .entry(key)
+        .or_insert_with(|| Tracked { estimator: MediaClockEstimator::new(clock_rate), users: 0 })
+        .users += 1;
+    TRACKED.store(sources.len(), Ordering::Release);
+}
+
+fn untrack(sources: &mut HashMap<usize, Tracked>, key: usize) {
+    if let Some(tracked) = sources.get_mut(&key) {
+        tracked.users -= 1;
+        if tracked.users == 0 {
+            sources.remove(&key);
+        }
+    }
+    TRACKED.store(sources.len(), Ordering::Release);
+}
+
+/// Key identifying a stream that can feed the media clock
+pub fn new_source_key() -> usize {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_new_source_key)() };
+    }
+    NEXT_KEY.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Make the stream with `key` the process-wide clock source (0 = none).
+/// `clock_rate` is its RTP clock rate, or None to detect it.
+pub fn select_source(key: usize, clock_rate: Option<u32>) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_select_source)(key, clock_rate.unwrap_or(0)) };
+    }
+    let mut sources = SOURCES.lock();
+    let old = SOURCE.swap(key, Ordering::AcqRel);
+    if old != 0 {
+        untrack(&mut sources, old);
+    }
+    if key != 0 {
+        track(&mut sources, key, clock_rate);
+    }
+}
+
+/// Key of the process-wide source (0 = none)
+pub fn source() -> usize {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_get_source)() };
+    }
+    SOURCE.load(Ordering::Acquire)
+}
+
+/// Follow the stream with `key` for a per-stream clock reference. Each call
+/// must be matched by `unfollow`.
+pub fn follow(key: usize, clock_rate: Option<u32>) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_follow)(key, clock_rate.unwrap_or(0)) };
+    }
+    if key != 0 {
+        track(&mut SOURCES.lock(), key, clock_rate);
+    }
+}
+
+/// Stop following the stream with `key`
+pub fn unfollow(key: usize) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_unfollow)(key) };
+    }
+    untrack(&mut SOURCES.lock(), key);
+}
+
+/// The stream with `key` is going away: stop tracking it for all users
+pub fn release_source(key: usize) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_release_source)(key) };
+    }
+    let mut sources = SOURCES.lock();
+    let _ = SOURCE.compare_exchange(key, 0, Ordering::AcqRel, Ordering::Acquire);
+    sources.remove(&key);
+    TRACKED.store(sources.len(), Ordering::Release);
+}
+
+/// Feed a received packet's RTP timestamp (ignored unless `key` is tracked)
+pub fn feed(key: usize, timestamp: u32) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_feed)(key, timestamp) };
+    }
+    if key == 0 || TRACKED.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let now = now_ns();
+    if let Some(tracked) = SOURCES.lock().get_mut(&key) {
+        tracked.estimator.update(timestamp, now);
+    }
+}
+
+/// Run `f` on the estimator of the tracked stream `key`
+fn with_estimator<T>(key: usize, f: impl FnOnce(&MediaClockEstimator) -> T) -> Option<T> {
+    SOURCES.lock().get(&key).map(|tracked| f(&tracked.estimator))
+}
+
+/// Frequency adjustment of stream `key` in ppm (positive = sender runs faster)
+pub fn frequency_ppm(key: usize) -> f64 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_get_frequency_ppm)(key) };
+    }
+    with_estimator(key, |e| e.frequency_ppm()).unwrap_or(0.0)
+}
+
+/// Whether the clock of stream `key` is locked
+pub fn is_locked(key: usize) -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_is_locked)(key) != 0 };
+    }
+    let now = now_ns();
+    with_estimator(key, |e| e.is_locked(now)).unwrap_or(false)
+}
+
+/// Clock state of stream `key` (ClockState values; 0 when it isn't tracked)
+pub fn state(key: usize) -> u8 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_get_state)(key) };
+    }
+    let now = now_ns();
+    with_estimator(key, |e| e.state(now)).unwrap_or(0)
+}
+
+/// Local clock minus the clock of stream `key` (ns)
+pub fn offset_ns(key: usize) -> i64 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.media_get_offset)(key) };
+    }
+    with_estimator(key, |e| e.offset_ns()).unwrap_or(0)
+}
+
+/// Status line for stream `key` in the format of the other clocks
+pub fn stats_string(key: usize) -> String {
+    if let Some(library) = shared::library() {
+        return read_stats(|buffer, size| unsafe { (library.media_get_stats_string)(key, buffer, size) });
+    }
+    let now = now_ns();
+    with_estimator(key, |clock| {
+        let rate = clock
+            .clock_rate()
+            .map(|r| format!("{} Hz", r))
+            .unwrap_or_else(|| "rate unknown".to_string());
+        match clock.state(now) {
+            1 => format!("Stream: Waiting for packets ({})", rate),
+            2 => format!(
+                "Stream: Uncalibrated - {}, {} packets, {} restarts",
+                rate,
+                clock.packets(),
+                clock.restarts()
+            ),
+            _ => format!(
+                "Slave to: stream ({}), δ {:.1}µs, Freq: {:+.2}±{:.2}ppm{}",
+                rate,
+                clock.offset_ns() as f64 / 1_000.0,
+                clock.frequency_ppm(),
+                clock.frequency_error_ppm().unwrap_or(0.0),
+                if clock.is_locked(now) { " [LOCKED]" } else { " [UNLOCKED]" }
+            ),
+        }
+    })
+    .unwrap_or_else(|| String::from("Stream: No source selected"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `seconds` of 1 ms packets from a sender running `ppm` fast at
+    /// `rate`, with arrival jitter of up to `jitter_ns`
+    fn run(est: &mut MediaClockEstimator, start_ts: u32, rate: u32, ppm: f64, seconds: i64, jitter_ns: i64) {
+        let ticks_per_packet = rate as i64 / 1000;
+        for i in 0..seconds * 1000 {
+            let sender_ns = i * 1_000_000;
+            let local_ns = (sender_ns as f64 / (1.0 + ppm / 1e6)) as i64;
+            let delay = (i * 7919) % (jitter_ns + 1);
+            let ts = start_ts.wrapping_add((i * ticks_per_packet) as u32);
+            est.update(ts, local_ns + delay);
+        }
+    }
+
+    #[test]
+    fn test_recovers_sender_rate() {
+        let mut est = MediaClockEstimator::new(Some(48000));
+        run(&mut est, u32::MAX - 10_000, 48000, 40.0, 30, 2_000_000);
+        assert!((est.frequency_ppm() - 40.0).abs() < 1.0, "{}", est.frequency_ppm());
+        let last = est.last_arrival_ns.unwrap();
+        assert!(est.is_locked(last));
+        assert_eq!(est.state(last), 3);
+        // Source lost
+        assert!(!est.is_locked(last + 2 * SOURCE_TIMEOUT_NS));
+        assert_eq!(est.state(last + 2 * SOURCE_TIMEOUT_NS), 1);
+    }
+
+    #[test]
+    fn test_detects_clock_rate() {
+        let mut est = MediaClockEstimator::new(None);
+        run(&mut est, 0, 90000, -25.0, 30, 500_000);
+        assert_eq!(est.clock_rate(), Some(90000));
+        assert!((est.frequency_ppm() + 25.0).abs() < 1.0, "{}", est.frequency_ppm());
+        assert_eq!(snap_rate(44_100.9), 44100);
+        assert_eq!(snap_rate(12_345.0), 12345);
+    }
+
+    #[test]
+    fn test_restarts_on_timestamp_jump() {
+        let mut est = MediaClockEstimator::new(Some(48000));
+        run(&mut est, 0, 48000, 10.0, 10, 0);
+        assert!(est.frequency_error_ppm().is_some());
+        let last = est.last_arrival_ns.unwrap();
+        est.update(1_234_567_890, last + 1_000_000);
+        assert_eq!(est.restarts(), 1);
+        assert_eq!(est.state(last + 1_000_000), 2);
+        assert!((est.frequency_ppm() - 10.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_tracks_followed_streams() {
+        let key = new_source_key();
+        feed(key, 0); // not followed: ignored
+        follow(key, Some(48000));
+        follow(key, Some(48000));
+        feed(key, 0);
+        assert_eq!(with_estimator(key, |e| e.packets()), Some(1));
+        unfollow(key);
+        assert!(with_estimator(key, |_| ()).is_some());
+        unfollow(key);
+        assert!(with_estimator(key, |_| ()).is_none());
+        assert_eq!(stats_string(key), "Stream: No source selected");
+    }
+}
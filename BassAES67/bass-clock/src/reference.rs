@@ -0,0 +1,335 @@
+//! Per-stream clock references.
+//!
+//! A stream follows the process-wide clock unless it names its own
+//! reference: `ptp:<domain>`, `lw`, `sys`, `ntp` or `stream:<handle>`. The
+//! clock behind a reference is started when the first stream needs it and
+//! shared with every other stream (and the process-wide selection) that uses
+//! the same clock. It stops when the last one releases it.
+
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::registry::{self, clock_get_frequency_ppm, clock_get_offset, clock_get_state, clock_get_stats_string, clock_is_locked, Slew};
+use crate::shared::{self, SharedLease};
+use crate::source::ClockSource;
+use crate::stream::StreamClock;
+use crate::{ClockMode, ClockState, CLOCK_ERROR_INVALID};
+
+/// Clock reference of a single stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockRef {
+    /// The process-wide clock, with its fallback to the system clock
+    #[default]
+    Global,
+    /// PTP on a domain (0-127)
+    Ptp(u8),
+    /// Axia Livewire clock
+    Livewire,
+    /// Free-running system clock
+    System,
+    /// System clock rate-matched to the configured NTP server
+    Ntp,
+    /// Media clock of a received stream, by BASS stream handle
+    Stream(u32),
+}
+
+impl FromStr for ClockRef {
+    type Err = String;
+
+    /// Parse `global`, `ptp[:domain]`, `lw`/`livewire`, `sys`/`system`,
+    /// `ntp` or `stream:<handle>` (decimal or 0x hex)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (s.as_str(), None),
+        };
+
+        match (kind, arg) {
+            ("global" | "default", None) => Ok(ClockRef::Global),
+            ("ptp", None) => Ok(ClockRef::Ptp(0)),
+            ("ptp", Some(domain)) => match domain.parse::<u8>() {
+                Ok(domain) if domain <= 127 => Ok(ClockRef::Ptp(domain)),
+                _ => Err(format!("Invalid PTP domain '{}' (0-127)", domain)),
+            },
+            ("lw" | "livewire", None) => Ok(ClockRef::Livewire),
+            ("sys" | "system", None) => Ok(ClockRef::System),
+            ("ntp", None) => Ok(ClockRef::Ntp),
+            ("stream", Some(handle)) => {
+                let parsed = match handle.strip_prefix("0x") {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => handle.parse(),
+                };
+                match parsed {
+                    Ok(handle) if handle != 0 => Ok(ClockRef::Stream(handle)),
+                    _ => Err(format!("Invalid stream handle '{}'", handle)),
+                }
+            }
+            _ => Err(format!("Unknown clock '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for ClockRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockRef::Global => write!(f, "global"),
+            ClockRef::Ptp(domain) => write!(f, "ptp:{}", domain),
+            ClockRef::Livewire => write!(f, "lw"),
+            ClockRef::System => write!(f, "sys"),
+            ClockRef::Ntp => write!(f, "ntp"),
+            ClockRef::Stream(handle) => write!(f, "stream:{}", handle),
+        }
+    }
+}
+
+/// How a lease reaches its clock
+enum Held {
+    /// Read through the process-wide clock API
+    Global,
+    /// A registry clock, retained for this lease
+    Shared(&'static dyn ClockSource),
+    /// A clock of its own (PTP instance, followed stream), possibly shared
+    /// with other leases on the same clock, or a lease held by the bass_clock
+    /// library
+    Instance(Arc<dyn ClockSource>),
+}
+
+/// A stream's use of its clock reference. The getters mirror the
+/// process-wide `clock_*` API; dropping the lease releases the clock.
+///
+/// A reference other than [`ClockRef::Global`] has no fallback: while its
+/// clock is unlocked, its frequency heads for 0.0 and the stream runs free
+/// as it does during the process-wide clock's calibration. Its frequency is
+/// slewed per lease, as the process-wide one is (see `set_slew_rate`).
+pub struct ClockLease {
+    reference: ClockRef,
+    held: Held,
+    /// Frequency handed out last; None where the frequency is slewed
+    /// already (the process-wide clock, leases held by the bass_clock library)
+    slew: Option<Mutex<Slew>>,
+}
+
+impl Default for ClockLease {
+    fn default() -> Self {
+        Self::global()
+    }
+}
+
+impl ClockLease {
+    /// Follow the process-wide clock
+    pub fn global() -> Self {
+        Self {
+            reference: ClockRef::Global,
+            held: Held::Global,
+            slew: None,
+        }
+    }
+
+    /// Acquire the clock for `reference`, starting it on `interface` if
+    /// nothing uses it yet. `stream_key` maps a BASS stream handle to the
+    /// stream's media clock key (see `media::new_source_key`).
+    ///
+    /// PTP references run a bass_ptp client instance per domain and
+    /// interface. Without instance support they share the default client
+    /// while it runs on their domain; if it runs on another one (or was
+    /// started outside this crate), acquiring fails with CLOCK_ERROR_ALREADY.
+    pub fn acquire(
+        reference: ClockRef,
+        interface: Ipv4Addr,
+        stream_key: impl FnOnce(u32) -> Option<usize>,
+    ) -> Result<Self, i32> {
+        if let (Some(library), false) = (shared::library(), reference == ClockRef::Global) {
+            let key = match reference {
+                ClockRef::Stream(handle) => stream_key(handle).ok_or(CLOCK_ERROR_INVALID)?,
+                _ => 0,
+            };
+            let lease = SharedLease::acquire(library, reference, interface, key)?;
+            return Ok(Self {
+                reference,
+                held: Held::Instance(Arc::new(lease)),
+                slew: None,
+            });
+        }
+
+        let held = match reference {
+            ClockRef::Global => Held::Global,
+            ClockRef::Ptp(domain) => match registry::ptp_instance(interface, domain) {
+                Some(clock) => Held::Instance(clock),
+                None => Held::Shared(registry::retain_ptp(interface, domain)?),
+            },
+            ClockRef::Livewire => Held::Shared(registry::retain(ClockMode::Livewire, interface, 0)?),
+            ClockRef::System => Held::Shared(registry::retain(ClockMode::System, interface, 0)?),
+            ClockRef::Ntp => Held::Shared(registry::retain(ClockMode::Ntp, interface, 0)?),
+            ClockRef::Stream(handle) => {
+                let key = stream_key(handle).ok_or(CLOCK_ERROR_INVALID)?;
+                Held::Instance(Arc::new(StreamClock::follow(key)))
+            }
+        };
+        let slew = (reference != ClockRef::Global).then(|| Mutex::new(Slew::new()));
+        Ok(Self { reference, held, slew })
+    }
+
+    /// The reference this lease follows
+    pub fn reference(&self) -> ClockRef {
+        self.reference
+    }
+
+    /// Whether this lease follows the process-wide clock
+    pub fn is_global(&self) -> bool {
+        matches!(self.held, Held::Global)
+    }
+
+    fn clock(&self) -> Option<&dyn ClockSource> {
+        match &self.held {
+            Held::Global => None,
+            Held::Shared(clock) => Some(*clock),
+            Held::Instance(clock) => Some(clock.as_ref()),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.clock().map(|c| c.is_locked()).unwrap_or_else(clock_is_locked)
+    }
+
+    /// Frequency adjustment in ppm (positive = reference runs faster than
+    /// the local clock), slewed
+    pub fn frequency_ppm(&self) -> f64 {
+        let clock = match self.clock() {
+            Some(clock) => clock,
+            None => return clock_get_frequency_ppm(),
+        };
+        match &self.slew {
+            Some(slew) => {
+                let target = if clock.is_locked() { clock.frequency_ppm() } else { 0.0 };
+                slew.lock().unwrap_or_else(|e| e.into_inner()).towards(target)
+            }
+            None => clock.frequency_ppm(),
+        }
+    }
+
+    /// Offset from the reference in nanoseconds
+    pub fn offset_ns(&self) -> i64 {
+        self.clock().map(|c| c.offset_ns()).unwrap_or_else(clock_get_offset)
+    }
+
+    pub fn state(&self) -> ClockState {
+        self.clock().map(|c| c.state()).unwrap_or_else(clock_get_state)
+    }
+
+    pub fn stats_string(&self) -> String {
+        self.clock().map(|c| c.stats_string()).unwrap_or_else(clock_get_stats_string)
+    }
+}
+
+impl Drop for ClockLease {
+    fn drop(&mut self) {
+        if let Held::Shared(clock) = self.held {
+            registry::release(clock.mode());
+        }
+    }
+}
+
+// ============================================================================
+// Leases held for other binaries (bass_clock library)
+// ============================================================================
+
+/// Leases the C API holds for plugins, by handle
+static HELD: Mutex<Vec<(u32, ClockLease)>> = Mutex::new(Vec::new());
+
+/// Next lease handle
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+fn held() -> std::sync::MutexGuard<'static, Vec<(u32, ClockLease)>> {
+    HELD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Acquire a lease for the C API; `stream_key` is the media clock key of a
+/// stream reference (0 = none). Returns the lease handle.
+pub(crate) fn hold(reference: ClockRef, interface: Ipv4Addr, stream_key: usize) -> Result<u32, i32> {
+    let lease = ClockLease::acquire(reference, interface, |_| (stream_key != 0).then_some(stream_key))?;
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    held().push((handle, lease));
+    Ok(handle)
+}
+
+/// Run `f` on the lease with `handle`
+pub(crate) fn with_held<T>(handle: u32, f: impl FnOnce(&ClockLease) -> T) -> Option<T> {
+    held().iter().find(|(h, _)| *h == handle).map(|(_, lease)| f(lease))
+}
+
+/// Release the lease with `handle`
+pub(crate) fn release_held(handle: u32) {
+    let lease = {
+        let mut held = held();
+        let index = held.iter().position(|(h, _)| *h == handle);
+        index.map(|i| held.swap_remove(i))
+    };
+    // Dropped outside the lock: releasing may stop a clock
+    drop(lease);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media;
+
+    #[test]
+    fn test_parse_clock_ref() {
+        assert_eq!("ptp:127".parse(), Ok(ClockRef::Ptp(127)));
+        assert_eq!("PTP".parse(), Ok(ClockRef::Ptp(0)));
+        assert_eq!("lw".parse(), Ok(ClockRef::Livewire));
+        assert_eq!("livewire".parse(), Ok(ClockRef::Livewire));
+        assert_eq!("system".parse(), Ok(ClockRef::System));
+        assert_eq!("ntp".parse(), Ok(ClockRef::Ntp));
+        assert_eq!("global".parse(), Ok(ClockRef::Global));
+        assert_eq!("stream:42".parse(), Ok(ClockRef::Stream(42)));
+        assert_eq!("stream:0x80000001".parse(), Ok(ClockRef::Stream(0x8000_0001)));
+
+        assert!("ptp:128".parse::<ClockRef>().is_err());
+        assert!("stream".parse::<ClockRef>().is_err());
+        assert!("stream:0".parse::<ClockRef>().is_err());
+        assert!("lw:1".parse::<ClockRef>().is_err());
+        assert!("gps".parse::<ClockRef>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for reference in [ClockRef::Global, ClockRef::Ptp(5), ClockRef::Livewire, ClockRef::Stream(9)] {
+            assert_eq!(reference.to_string().parse(), Ok(reference));
+        }
+    }
+
+    #[test]
+    fn test_stream_lease_follows_stream() {
+        let key = media::new_source_key();
+        let lease = ClockLease::acquire(ClockRef::Stream(7), Ipv4Addr::UNSPECIFIED, |handle| {
+            (handle == 7).then_some(key)
+        })
+        .unwrap();
+        assert!(!lease.is_global());
+        assert_eq!(lease.state(), ClockState::Listening);
+        // Runs free until the stream's clock locks
+        assert_eq!(lease.frequency_ppm(), 0.0);
+        drop(lease);
+        assert_eq!(media::state(key), 0);
+
+        let unknown = ClockLease::acquire(ClockRef::Stream(8), Ipv4Addr::UNSPECIFIED, |_| None);
+        assert_eq!(unknown.err(), Some(CLOCK_ERROR_INVALID));
+    }
+
+    #[test]
+    fn test_held_lease_by_handle() {
+        let key = media::new_source_key();
+        let handle = hold(ClockRef::Stream(3), Ipv4Addr::UNSPECIFIED, key).unwrap();
+        assert_eq!(with_held(handle, |lease| lease.state()), Some(ClockState::Listening));
+        release_held(handle);
+        assert_eq!(with_held(handle, |lease| lease.state()), None);
+        assert_eq!(media::state(key), 0);
+
+        assert_eq!(hold(ClockRef::Stream(3), Ipv4Addr::UNSPECIFIED, 0).err(), Some(CLOCK_ERROR_INVALID));
+    }
+}
@@ -0,0 +1,853 @@
+//! Clock registry and the unified clock API.
+//!
+//! The registry holds one instance of every available clock, created on
+//! first use. One clock is the process-wide (active) clock. When a network
+//! clock stays unlocked for longer than the fallback timeout, the
+//! free-running system clock stands in until it has been locked again for
+//! the return delay.
+//!
+//! The frequency handed to the plugins is slewed: it moves towards the
+//! followed clock's frequency by at most the slew rate, so switching to the
+//! fallback and back (or to another clock) doesn't step every resampler.
+//!
+//! Plugins that load the bass_clock library forward every function here to
+//! it (see `shared`), so the process has one registry.
+//!
+//! Per-stream references (see [`crate::ClockLease`]) use the same clocks.
+//! Each clock counts its users, the active clock and its fallback included,
+//! and stops when the last one lets go.
+
+use std::ffi::c_void;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::Instant;
+
+use crate::events::{self, ClockEvent};
+use crate::library::{self, read_stats};
+use crate::shared;
+use crate::source::ClockSource;
+use crate::stream::StreamClock;
+use crate::{ClockMode, ClockState, ClockTimerCallback, CLOCK_ERROR_ALREADY, CLOCK_ERROR_NOT_INIT, CLOCK_OK};
+
+/// Every clock available in this process
+struct Registry {
+    clocks: Vec<Box<dyn ClockSource>>,
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+/// Currently active clock (0=none, 1=PTP, 2=Livewire, 3=System, 4=NTP, 5=Stream)
+static ACTIVE_CLOCK: AtomicU8 = AtomicU8::new(0);
+
+impl Registry {
+    fn load() -> Self {
+        let mut clocks: Vec<Box<dyn ClockSource>> = Vec::new();
+        for mode in [ClockMode::Ptp, ClockMode::Livewire, ClockMode::System, ClockMode::Ntp] {
+            if let Some(clock) = library::load(mode) {
+                clocks.push(Box::new(clock));
+            }
+        }
+        clocks.push(Box::<StreamClock>::default());
+        Self { clocks }
+    }
+}
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::load)
+}
+
+/// Users of each clock, indexed by ClockMode
+static USERS: Mutex<[usize; 5]> = Mutex::new([0; 5]);
+
+/// Serializes changes of the active clock
+static SELECTION: Mutex<()> = Mutex::new(());
+
+/// Whether the active clock holds a use of the system clock as its fallback
+static FALLBACK_RETAINED: AtomicBool = AtomicBool::new(false);
+
+/// Domain the registry started the default PTP client on (None = not
+/// running, or started outside the registry)
+static PTP_DOMAIN: Mutex<Option<u8>> = Mutex::new(None);
+
+/// A PTP client instance of per-stream references: interface, domain, clock
+type PtpInstanceEntry = (Ipv4Addr, u8, Weak<dyn ClockSource>);
+
+/// PTP client instances of per-stream references
+static PTP_INSTANCES: Mutex<Vec<PtpInstanceEntry>> = Mutex::new(Vec::new());
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// ACTIVE_CLOCK value for a mode
+fn active_code(mode: ClockMode) -> u8 {
+    mode as u8 + 1
+}
+
+/// Mode of an ACTIVE_CLOCK value (None = no clock)
+fn active_mode_of(code: u8) -> Option<ClockMode> {
+    match code {
+        1 => Some(ClockMode::Ptp),
+        2 => Some(ClockMode::Livewire),
+        3 => Some(ClockMode::System),
+        4 => Some(ClockMode::Ntp),
+        5 => Some(ClockMode::Stream),
+        _ => None,
+    }
+}
+
+/// The active clock, if one is started
+fn active() -> Option<&'static dyn ClockSource> {
+    active_mode_of(ACTIVE_CLOCK.load(Ordering::Acquire)).and_then(clock_source)
+}
+
+/// Start the clock for `mode` for one more user. The first user starts it
+/// on `interface` (and `domain`, for PTP); later ones share it.
+pub(crate) fn retain(mode: ClockMode, interface: Ipv4Addr, domain: u8) -> Result<&'static dyn ClockSource, i32> {
+    let clock = clock_source(mode).ok_or(CLOCK_ERROR_NOT_INIT)?;
+    let mut users = lock(&USERS);
+    add_user(clock, &mut users, interface, domain)?;
+    Ok(clock)
+}
+
+/// `retain` for the default PTP client, if it serves `domain`: it is either
+/// not running yet or was started on `domain` by the registry. A client on
+/// another domain (or of unknown domain) fails with CLOCK_ERROR_ALREADY.
+pub(crate) fn retain_ptp(interface: Ipv4Addr, domain: u8) -> Result<&'static dyn ClockSource, i32> {
+    let clock = clock_source(ClockMode::Ptp).ok_or(CLOCK_ERROR_NOT_INIT)?;
+    let mut users = lock(&USERS);
+    let started_on = *lock(&PTP_DOMAIN);
+    if !serves_domain(clock.is_running(), started_on, library::clock_get_ptp_instance(), domain) {
+        return Err(CLOCK_ERROR_ALREADY);
+    }
+    add_user(clock, &mut users, interface, domain)?;
+    Ok(clock)
+}
+
+/// Whether the default PTP client serves `domain`: it is started on it, or
+/// it isn't running and will be. Its getters read the application's
+/// instance while one is selected, whose domain is unknown.
+fn serves_domain(running: bool, started_on: Option<u8>, selected_instance: u32, domain: u8) -> bool {
+    selected_instance == 0 && (!running || started_on == Some(domain))
+}
+
+/// Count one more user of `clock`, starting it for the first
+fn add_user(clock: &dyn ClockSource, users: &mut [usize; 5], interface: Ipv4Addr, domain: u8) -> Result<(), i32> {
+    let mode = clock.mode();
+    if users[mode as usize] == 0 || !clock.is_running() {
+        let was_running = clock.is_running();
+        clock.start(interface, domain)?;
+        if mode == ClockMode::Ptp {
+            *lock(&PTP_DOMAIN) = (!was_running).then_some(domain);
+        }
+    }
+    users[mode as usize] += 1;
+    Ok(())
+}
+
+/// Drop one user of the clock for `mode`, stopping it after the last one
+pub(crate) fn release(mode: ClockMode) {
+    let Some(clock) = clock_source(mode) else {
+        return;
+    };
+    let mut users = lock(&USERS);
+    let count = &mut users[mode as usize];
+    *count = count.saturating_sub(1);
+    if *count == 0 {
+        clock.force_stop();
+        if mode == ClockMode::Ptp {
+            *lock(&PTP_DOMAIN) = None;
+        }
+    }
+}
+
+/// A PTP client for `domain` on `interface`, shared by the per-stream
+/// references that follow it. None if bass_ptp can't run client instances
+/// (or this one could not be created).
+pub(crate) fn ptp_instance(interface: Ipv4Addr, domain: u8) -> Option<Arc<dyn ClockSource>> {
+    let mut instances = lock(&PTP_INSTANCES);
+    instances.retain(|(_, _, clock)| clock.strong_count() > 0);
+    let shared = instances
+        .iter()
+        .filter(|(i, d, _)| *i == interface && *d == domain)
+        .find_map(|(_, _, clock)| clock.upgrade());
+    if shared.is_some() {
+        return shared;
+    }
+
+    let clock: Arc<dyn ClockSource> = Arc::from(clock_source(ClockMode::Ptp)?.create_instance(interface, domain)?);
+    instances.push((interface, domain, Arc::downgrade(&clock)));
+    Some(clock)
+}
+
+/// The system clock, used as fallback
+fn fallback_clock() -> Option<&'static dyn ClockSource> {
+    clock_source(ClockMode::System)
+}
+
+/// The clock whose timer the timer API drives: the active clock, or the
+/// system clock for clocks without a timer of their own
+fn timer_clock() -> Option<&'static dyn ClockSource> {
+    let clock = active()?;
+    if clock.has_timer() {
+        Some(clock)
+    } else {
+        fallback_clock()
+    }
+}
+
+// ============================================================================
+// Fallback State Tracking
+// ============================================================================
+
+/// Is fallback to system clock currently active?
+static FALLBACK_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Timestamp (in ms since start) of last successful lock
+static LAST_LOCK_TIME_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Fallback timeout in seconds (0 = disabled)
+static FALLBACK_TIMEOUT_SECS: AtomicU32 = AtomicU32::new(5);
+
+/// Start time for elapsed time tracking
+static START_TIME: OnceLock<Instant> = OnceLock::new();
+
+/// Seconds the active clock must stay locked before fallback ends
+static RETURN_DELAY_SECS: AtomicU32 = AtomicU32::new(3);
+
+/// Timestamp (in ms since start) the active clock last became locked
+static LOCKED_SINCE_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the active clock was locked when last checked
+static PRIMARY_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Grandmaster of the active clock when last polled (0 = none)
+static GRANDMASTER: AtomicU64 = AtomicU64::new(0);
+
+/// Get milliseconds since start
+fn elapsed_ms() -> u64 {
+    START_TIME
+        .get_or_init(Instant::now)
+        .elapsed()
+        .as_millis() as u64
+}
+
+// ============================================================================
+// Initialization and registry queries
+// ============================================================================
+
+/// Load the clock libraries (or set up the linked ones) and the stream clock.
+/// Call this once during plugin initialization. Returns true if at least one
+/// clock library is available.
+pub fn init_clock_bindings() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.init)() != 0 };
+    }
+    // Initialize start time for fallback tracking
+    let _ = START_TIME.get_or_init(Instant::now);
+
+    registry().clocks.iter().any(|c| c.mode() != ClockMode::Stream)
+}
+
+/// The clock implementing `mode`, if it is available in this process.
+///
+/// This is always the instance in this binary's registry, also when the rest
+/// of the API is forwarded to bass_clock. It reads the same running clock.
+pub fn clock_source(mode: ClockMode) -> Option<&'static dyn ClockSource> {
+    registry()
+        .clocks
+        .iter()
+        .find(|c| c.mode() == mode)
+        .map(|c| c.as_ref())
+}
+
+/// Modes of the clocks that are currently running (the active clock, its
+/// fallback, and any clock started directly through its library)
+pub fn running_clocks() -> Vec<ClockMode> {
+    if let Some(library) = shared::library() {
+        let mask = unsafe { (library.get_running_clocks)() };
+        return (0..5).filter(|m| mask & 1 << m != 0).map(ClockMode::from).collect();
+    }
+    registry()
+        .clocks
+        .iter()
+        .filter(|c| c.is_running())
+        .map(|c| c.mode())
+        .collect()
+}
+
+/// Check if PTP library is available
+pub fn is_ptp_available() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_available)(ClockMode::Ptp as u32) != 0 };
+    }
+    clock_source(ClockMode::Ptp).is_some()
+}
+
+/// Check if Livewire clock library is available
+pub fn is_lw_available() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_available)(ClockMode::Livewire as u32) != 0 };
+    }
+    clock_source(ClockMode::Livewire).is_some()
+}
+
+/// Check if System clock library is available
+pub fn is_sys_available() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_available)(ClockMode::System as u32) != 0 };
+    }
+    clock_source(ClockMode::System).is_some()
+}
+
+/// Check if NTP clock library is available
+pub fn is_ntp_available() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_available)(ClockMode::Ntp as u32) != 0 };
+    }
+    clock_source(ClockMode::Ntp).is_some()
+}
+
+/// Get currently active clock mode (0=none, 1=PTP, 2=Livewire, 3=System, 4=NTP, 5=Stream)
+pub fn get_active_clock() -> u8 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_active_clock)() as u8 };
+    }
+    ACTIVE_CLOCK.load(Ordering::Relaxed)
+}
+
+/// Get the mode of the currently active clock, None if no clock is started
+pub fn get_active_mode() -> Option<ClockMode> {
+    active_mode_of(get_active_clock())
+}
+
+/// Check if fallback to system clock is currently active
+pub fn is_fallback_active() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_fallback_active)() != 0 };
+    }
+    FALLBACK_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Set fallback timeout in seconds (0 = disable fallback)
+pub fn set_fallback_timeout(seconds: u32) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.set_fallback_timeout)(seconds) };
+    }
+    FALLBACK_TIMEOUT_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Get current fallback timeout in seconds
+pub fn get_fallback_timeout() -> u32 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_fallback_timeout)() };
+    }
+    FALLBACK_TIMEOUT_SECS.load(Ordering::Relaxed)
+}
+
+/// Set how long (in seconds) the active clock must stay locked before the
+/// fallback ends (0 = return at once)
+pub fn set_fallback_return_delay(seconds: u32) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.set_fallback_return_delay)(seconds) };
+    }
+    RETURN_DELAY_SECS.store(seconds, Ordering::Relaxed);
+}
+
+/// Get the fallback return delay in seconds
+pub fn get_fallback_return_delay() -> u32 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_fallback_return_delay)() };
+    }
+    RETURN_DELAY_SECS.load(Ordering::Relaxed)
+}
+
+// ============================================================================
+// Frequency Slewing
+// ============================================================================
+
+/// Frequency handed out last and when
+pub(crate) struct Slew {
+    ppm: f64,
+    updated: Option<Instant>,
+}
+
+impl Slew {
+    pub(crate) const fn new() -> Self {
+        Self { ppm: 0.0, updated: None }
+    }
+
+    /// Move the handed-out frequency towards `target`, by at most the slew
+    /// rate times the time since the last call
+    pub(crate) fn towards(&mut self, target: f64) -> f64 {
+        let now = Instant::now();
+        self.ppm = match self.updated {
+            Some(updated) => slew_step(self.ppm, target, get_slew_rate(), now.duration_since(updated).as_secs_f64()),
+            None => target,
+        };
+        self.updated = Some(now);
+        self.ppm
+    }
+}
+
+/// Frequency handed out by `clock_get_frequency_ppm`
+static SLEW: Mutex<Slew> = Mutex::new(Slew::new());
+
+/// Frequency handed out by `lw_get_frequency_ppm`
+static LW_SLEW: Mutex<Slew> = Mutex::new(Slew::new());
+
+/// Slew rate limit in ppm per second, as f64 bits (default 1 ppm/s)
+static SLEW_RATE_BITS: AtomicU64 = AtomicU64::new(0x3FF0_0000_0000_0000);
+
+/// Set the slew rate limit in ppm per second (0 = follow the clock directly)
+pub fn set_slew_rate(ppm_per_sec: f64) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.set_slew_rate)(ppm_per_sec) };
+    }
+    SLEW_RATE_BITS.store(ppm_per_sec.max(0.0).to_bits(), Ordering::Relaxed);
+}
+
+/// Get the slew rate limit in ppm per second
+pub fn get_slew_rate() -> f64 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_slew_rate)() };
+    }
+    f64::from_bits(SLEW_RATE_BITS.load(Ordering::Relaxed))
+}
+
+/// Longest time one slew step covers. Callers stop asking while the clock is
+/// unlocked; when they ask again the frequency continues from where they
+/// left it instead of jumping.
+const MAX_SLEW_STEP_SECS: f64 = 1.0;
+
+/// `current` moved towards `target` over `secs` at `rate` ppm/s (0 = at once)
+fn slew_step(current: f64, target: f64, rate: f64, secs: f64) -> f64 {
+    if rate <= 0.0 {
+        return target;
+    }
+    let max_step = rate * secs.min(MAX_SLEW_STEP_SECS);
+    current + (target - current).clamp(-max_step, max_step)
+}
+
+/// Forget the handed-out frequency; the next clock starts without slewing
+fn reset_slew() {
+    *lock(&SLEW) = Slew::new();
+}
+
+// ============================================================================
+// Unified Clock API
+// ============================================================================
+
+/// Start the clock client based on mode selection, replacing the active clock.
+/// For PTP mode, domain is used. For the other modes, domain is ignored; NTP
+/// mode uses the server set with `clock_set_ntp_server`.
+/// Also starts the system clock for fallback support when using another clock.
+/// A clock that per-stream references still use keeps running when it is
+/// replaced.
+pub fn clock_start(interface: Ipv4Addr, domain: u8, mode: ClockMode) -> Result<(), i32> {
+    if let Some(library) = shared::library() {
+        let interface = shared::interface_string(interface);
+        return shared::result(unsafe { (library.start)(interface.as_ptr(), domain, mode as u32) });
+    }
+    let _selection = lock(&SELECTION);
+
+    // Take the new clock before letting go of the old one, so restarting
+    // the same mode keeps it running
+    retain(mode, interface, domain)?;
+    let fallback = mode != ClockMode::System && retain(ClockMode::System, interface, 0).is_ok();
+
+    // Reset fallback state. The slewed frequency carries over, so the
+    // switch to the new clock is as smooth as a return from fallback.
+    FALLBACK_ACTIVE.store(false, Ordering::Relaxed);
+    PRIMARY_LOCKED.store(false, Ordering::Relaxed);
+    GRANDMASTER.store(0, Ordering::Relaxed);
+    LAST_LOCK_TIME_MS.store(elapsed_ms(), Ordering::Relaxed);
+
+    let previous = active().map(|c| c.mode());
+    let previous_fallback = FALLBACK_RETAINED.swap(fallback, Ordering::AcqRel);
+    ACTIVE_CLOCK.store(active_code(mode), Ordering::Release);
+
+    if let Some(previous) = previous {
+        release(previous);
+    }
+    if previous_fallback {
+        release(ClockMode::System);
+    }
+    Ok(())
+}
+
+/// Stop using the currently active clock client. It stops unless per-stream
+/// references still use it.
+pub fn clock_stop() {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.stop)() };
+    }
+    let _selection = lock(&SELECTION);
+    let previous = active().map(|c| c.mode());
+    ACTIVE_CLOCK.store(0, Ordering::Release);
+    FALLBACK_ACTIVE.store(false, Ordering::Relaxed);
+    PRIMARY_LOCKED.store(false, Ordering::Relaxed);
+    reset_slew();
+
+    if let Some(previous) = previous {
+        release(previous);
+    }
+    if FALLBACK_RETAINED.swap(false, Ordering::AcqRel) {
+        release(ClockMode::System);
+    }
+}
+
+/// Force stop every clock client in the registry, including those used by
+/// per-stream references.
+pub fn clock_force_stop() {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.force_stop)() };
+    }
+    let _selection = lock(&SELECTION);
+    ACTIVE_CLOCK.store(0, Ordering::Release);
+    FALLBACK_ACTIVE.store(false, Ordering::Relaxed);
+    FALLBACK_RETAINED.store(false, Ordering::Release);
+    PRIMARY_LOCKED.store(false, Ordering::Relaxed);
+    reset_slew();
+
+    let mut users = lock(&USERS);
+    for clock in &registry().clocks {
+        users[clock.mode() as usize] = 0;
+        clock.force_stop();
+    }
+    *lock(&PTP_DOMAIN) = None;
+}
+
+/// Check if any clock is running.
+pub fn clock_is_running() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_running)() != 0 };
+    }
+    active().map(|c| c.is_running()).unwrap_or(false)
+}
+
+/// Get current offset in nanoseconds from the active clock.
+/// When in fallback mode, returns 0 (system clock has no offset).
+pub fn clock_get_offset() -> i64 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_offset)() };
+    }
+    if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+        return 0;
+    }
+    active().map(|c| c.offset_ns()).unwrap_or(0)
+}
+
+/// Get current frequency adjustment in ppm from the active clock, slewed
+/// (see `set_slew_rate`). When in fallback mode, heads for 0.0 (system clock
+/// runs at nominal rate).
+pub fn clock_get_frequency_ppm() -> f64 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_frequency_ppm)() };
+    }
+    let clock = match active() {
+        Some(c) => c,
+        None => return 0.0,
+    };
+    let target = if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+        0.0
+    } else {
+        clock.frequency_ppm()
+    };
+    lock(&SLEW).towards(target)
+}
+
+/// Get the Livewire clock's frequency adjustment in ppm, whatever the active
+/// clock is, slewed like `clock_get_frequency_ppm`. Returns None if the
+/// Livewire clock isn't running.
+/// Used by outputs that send Livewire-formatted streams.
+pub fn lw_get_frequency_ppm() -> Option<f64> {
+    if let Some(library) = shared::library() {
+        let mut ppm = 0.0;
+        return (unsafe { (library.lw_get_frequency_ppm)(&mut ppm) } == CLOCK_OK).then_some(ppm);
+    }
+    let clock = clock_source(ClockMode::Livewire).filter(|c| c.is_running());
+    let mut slew = lock(&LW_SLEW);
+    match clock {
+        Some(clock) => Some(slew.towards(clock.frequency_ppm())),
+        None => {
+            *slew = Slew::new();
+            None
+        }
+    }
+}
+
+/// Get formatted stats string from the active clock.
+/// Shows fallback status when in fallback mode.
+pub fn clock_get_stats_string() -> String {
+    if let Some(library) = shared::library() {
+        return read_stats(|buffer, size| unsafe { (library.get_stats_string)(buffer, size) });
+    }
+    let clock = match active() {
+        Some(c) => c,
+        None => return String::from("Clock: Not started"),
+    };
+
+    if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+        let last_lock = LAST_LOCK_TIME_MS.load(Ordering::Relaxed);
+        let elapsed_secs = elapsed_ms().saturating_sub(last_lock) / 1000;
+        return format!(
+            "FALLBACK: System Clock (free-running) - {} lost lock {}s ago",
+            clock.name(),
+            elapsed_secs
+        );
+    }
+
+    clock.stats_string()
+}
+
+/// Get state from the active clock.
+/// In fallback mode, returns Slave (system clock is always "synchronized").
+pub fn clock_get_state() -> ClockState {
+    if let Some(library) = shared::library() {
+        return ClockState::from(unsafe { (library.get_state)() });
+    }
+    if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+        return ClockState::Slave;
+    }
+    active().map(|c| c.state()).unwrap_or(ClockState::Disabled)
+}
+
+/// Check if the active clock is locked (stable synchronization).
+/// This is the key function that implements fallback logic.
+///
+/// When using PTP, Livewire, NTP or a stream:
+/// - If primary clock is locked (or PTP is in holdover), update last lock time and return true
+/// - In fallback, return to the primary clock once it has stayed locked for the return delay
+/// - If primary clock loses lock, start timeout countdown
+/// - If timeout expires and system clock is available, activate fallback and return true
+/// - If timeout hasn't expired yet, return false (waiting for primary to recover)
+///
+/// Lock changes and fallback changes are reported as clock events.
+pub fn clock_is_locked() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.is_locked)() != 0 };
+    }
+    let clock = match active() {
+        Some(c) => c,
+        None => return false,
+    };
+
+    // System clock mode: always locked (no network dependency)
+    if clock.mode() == ClockMode::System {
+        return true;
+    }
+
+    let now = elapsed_ms();
+    if clock.is_locked() {
+        LAST_LOCK_TIME_MS.store(now, Ordering::Relaxed);
+        if !PRIMARY_LOCKED.swap(true, Ordering::Relaxed) {
+            LOCKED_SINCE_MS.store(now, Ordering::Relaxed);
+            events::emit(ClockEvent::Locked, clock.mode());
+        }
+
+        // Stay in fallback until the primary clock has proven stable
+        if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+            let locked_for = now.saturating_sub(LOCKED_SINCE_MS.load(Ordering::Relaxed));
+            let delay_ms = RETURN_DELAY_SECS.load(Ordering::Relaxed) as u64 * 1000;
+            if locked_for >= delay_ms && FALLBACK_ACTIVE.swap(false, Ordering::Relaxed) {
+                events::emit(ClockEvent::FallbackExited, clock.mode());
+            }
+        }
+        return true;
+    }
+
+    if PRIMARY_LOCKED.swap(false, Ordering::Relaxed) {
+        events::emit(ClockEvent::Lost, clock.mode());
+    }
+    if FALLBACK_ACTIVE.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    // Primary clock lost lock - check fallback timeout
+    let timeout_secs = FALLBACK_TIMEOUT_SECS.load(Ordering::Relaxed);
+    if timeout_secs == 0 || fallback_clock().is_none() {
+        return false;
+    }
+
+    let last_lock = LAST_LOCK_TIME_MS.load(Ordering::Relaxed);
+    let elapsed_secs = now.saturating_sub(last_lock) / 1000;
+    if elapsed_secs >= timeout_secs as u64 {
+        if !FALLBACK_ACTIVE.swap(true, Ordering::Relaxed) {
+            events::emit(ClockEvent::FallbackEntered, clock.mode());
+        }
+        return true;
+    }
+
+    false
+}
+
+/// Check the active clock for events: lock and fallback changes (as
+/// `clock_is_locked` does) and a change of grandmaster. Called by the event
+/// dispatcher.
+pub(crate) fn poll_events() {
+    let Some(clock) = active() else {
+        return;
+    };
+    clock_is_locked();
+
+    let grandmaster = clock.grandmaster_id();
+    if grandmaster != 0 {
+        let previous = GRANDMASTER.swap(grandmaster, Ordering::Relaxed);
+        if previous != 0 && previous != grandmaster {
+            events::emit(ClockEvent::GrandmasterChanged, clock.mode());
+        }
+    }
+}
+
+/// Get version from the active clock's library.
+pub fn clock_get_version() -> u32 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.get_version)() };
+    }
+    active().map(|c| c.version()).unwrap_or(0)
+}
+
+// ============================================================================
+// Timer API (delegates to the active clock's timer)
+// ============================================================================
+
+/// Start the precision timer using the active clock's timer.
+// `user` is only handed back to the callback
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn clock_timer_start(
+    interval_ms: u32,
+    callback: Option<ClockTimerCallback>,
+    user: *mut c_void,
+) -> Result<(), i32> {
+    if let Some(library) = shared::library() {
+        return shared::result(unsafe { (library.timer_start)(interval_ms, callback, user) });
+    }
+    let clock = timer_clock().ok_or(CLOCK_ERROR_NOT_INIT)?;
+    match clock.timer_start(interval_ms, callback, user) {
+        CLOCK_OK => Ok(()),
+        err => Err(err),
+    }
+}
+
+/// Stop the timer.
+pub fn clock_timer_stop() {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.timer_stop)() };
+    }
+    if let Some(clock) = timer_clock() {
+        clock.timer_stop();
+    }
+}
+
+/// Check if timer is running.
+pub fn clock_timer_is_running() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.timer_is_running)() != 0 };
+    }
+    timer_clock().map(|c| c.timer_is_running()).unwrap_or(false)
+}
+
+/// Set timer interval (can change while running).
+pub fn clock_timer_set_interval(interval_ms: u32) -> Result<(), i32> {
+    if let Some(library) = shared::library() {
+        return shared::result(unsafe { (library.timer_set_interval)(interval_ms) });
+    }
+    let clock = timer_clock().ok_or(CLOCK_ERROR_NOT_INIT)?;
+    match clock.timer_set_interval(interval_ms) {
+        CLOCK_OK => Ok(()),
+        err => Err(err),
+    }
+}
+
+/// Get current timer interval.
+pub fn clock_timer_get_interval() -> u32 {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.timer_get_interval)() };
+    }
+    timer_clock().map(|c| c.timer_get_interval()).unwrap_or(20)
+}
+
+/// Enable/disable PLL adjustment.
+pub fn clock_timer_set_pll(enabled: bool) {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.timer_set_pll)(enabled as i32) };
+    }
+    if let Some(clock) = timer_clock() {
+        clock.timer_set_pll(enabled);
+    }
+}
+
+/// Check if PLL adjustment is enabled.
+pub fn clock_timer_is_pll_enabled() -> bool {
+    if let Some(library) = shared::library() {
+        return unsafe { (library.timer_is_pll_enabled)() != 0 };
+    }
+    timer_clock().map(|c| c.timer_is_pll_enabled()).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_clock_is_always_registered() {
+        init_clock_bindings();
+        let clock = clock_source(ClockMode::Stream).expect("stream clock");
+        assert_eq!(clock.name(), "Stream");
+        assert!(!clock.has_timer());
+    }
+
+    #[test]
+    fn test_start_and_stop_track_active_clock() {
+        assert_eq!(clock_get_stats_string(), "Clock: Not started");
+
+        clock_start(Ipv4Addr::UNSPECIFIED, 0, ClockMode::Stream).unwrap();
+        assert_eq!(get_active_clock(), 5);
+        assert_eq!(get_active_mode(), Some(ClockMode::Stream));
+        assert!(clock_is_running());
+        assert!(running_clocks().contains(&ClockMode::Stream));
+        // No source stream selected: nothing to lock to
+        assert_eq!(clock_get_state(), ClockState::Disabled);
+
+        // Restarting the same mode keeps a single user
+        clock_start(Ipv4Addr::UNSPECIFIED, 0, ClockMode::Stream).unwrap();
+        let stream = retain(ClockMode::Stream, Ipv4Addr::UNSPECIFIED, 0).unwrap();
+
+        clock_stop();
+        assert_eq!(get_active_clock(), 0);
+        assert_eq!(get_active_mode(), None);
+        assert!(!clock_is_running());
+        assert!(!clock_is_locked());
+        assert_eq!(clock_get_frequency_ppm(), 0.0);
+        // Still used by the reference taken above
+        assert!(stream.is_running());
+        release(ClockMode::Stream);
+        assert!(!stream.is_running());
+    }
+
+    #[test]
+    fn test_ptp_client_serves_its_domain_only() {
+        // Not running: started on the requested domain
+        assert!(serves_domain(false, None, 0, 3));
+        assert!(serves_domain(true, Some(3), 0, 3));
+        assert!(!serves_domain(true, Some(0), 0, 3));
+        // Started outside the registry
+        assert!(!serves_domain(true, None, 0, 3));
+        // Getters read an application instance
+        assert!(!serves_domain(true, Some(3), 7, 3));
+    }
+
+    #[test]
+    fn test_slew_step_limits_rate() {
+        assert_eq!(slew_step(0.0, 10.0, 2.0, 0.5), 1.0);
+        assert_eq!(slew_step(10.0, 0.0, 2.0, 0.5), 9.0);
+        // Close enough: no overshoot
+        assert_eq!(slew_step(9.5, 10.0, 2.0, 0.5), 10.0);
+        // A long gap covers at most one step
+        assert_eq!(slew_step(0.0, 10.0, 2.0, 30.0), 2.0);
+        // No limit
+        assert_eq!(slew_step(0.0, 10.0, 0.0, 0.1), 10.0);
+    }
+}
@@ -0,0 +1,253 @@
+//! The bass_clock library: one copy of the clock state per process.
+//!
+//! This crate is linked into every plugin, so each plugin binary has its own
+//! registry, active clock, fallback and slew state, PTP instances, stream
+//! clocks and event queue. To give all plugins in a process the same ones,
+//! the crate is also built as the bass_clock library, which exports the
+//! public API as `BASS_CLOCK_*`. The plugins load it at runtime from their
+//! directory, like the clock libraries, and forward every call to it; the OS
+//! loads it once, so its state is the process's.
+//!
+//! A plugin keeps its own state when bass_clock can't be loaded, when it is
+//! built with a `static-*` feature (bass_clock would load clock libraries of
+//! its own instead of the linked ones), and in this crate's tests. Inside
+//! bass_clock itself the lookup finds its own exports and uses its state.
+
+use std::ffi::{c_char, c_void, CString};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+use crate::events::ClockEventCallback;
+use crate::library::read_stats;
+#[cfg(not(any(test, feature = "static-ptp", feature = "static-livewire", feature = "static-system", feature = "static-ntp")))]
+use crate::library::loader;
+use crate::source::ClockSource;
+use crate::{ClockMode, ClockRef, ClockState, ClockTimerCallback, CLOCK_ERROR_INVALID, CLOCK_ERROR_NOT_INIT, CLOCK_OK};
+
+/// Declare the function table and resolve it from a loaded library. Every
+/// function is required: an older bass_clock without one is not used.
+macro_rules! shared_functions {
+    ($($field:ident: $name:literal => $ty:ty,)*) => {
+        pub(crate) struct SharedLibrary {
+            $(pub(crate) $field: $ty,)*
+        }
+
+        impl SharedLibrary {
+            #[allow(dead_code)]
+            fn from_library(symbol: impl Fn(&str) -> *mut c_void) -> Option<Self> {
+                Some(Self {
+                    $($field: {
+                        let ptr = symbol(concat!("BASS_CLOCK_", $name));
+                        if ptr.is_null() {
+                            return None;
+                        }
+                        unsafe { std::mem::transmute_copy::<*mut c_void, $ty>(&ptr) }
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+shared_functions! {
+    init: "Init" => unsafe extern "C" fn() -> i32,
+    is_available: "IsAvailable" => unsafe extern "C" fn(u32) -> i32,
+    get_running_clocks: "GetRunningClocks" => unsafe extern "C" fn() -> u32,
+    get_active_clock: "GetActiveClock" => unsafe extern "C" fn() -> u32,
+    is_fallback_active: "IsFallbackActive" => unsafe extern "C" fn() -> i32,
+    set_fallback_timeout: "SetFallbackTimeout" => unsafe extern "C" fn(u32),
+    get_fallback_timeout: "GetFallbackTimeout" => unsafe extern "C" fn() -> u32,
+    set_fallback_return_delay: "SetFallbackReturnDelay" => unsafe extern "C" fn(u32),
+    get_fallback_return_delay: "GetFallbackReturnDelay" => unsafe extern "C" fn() -> u32,
+    set_slew_rate: "SetSlewRate" => unsafe extern "C" fn(f64),
+    get_slew_rate: "GetSlewRate" => unsafe extern "C" fn() -> f64,
+    start: "Start" => unsafe extern "C" fn(*const c_char, u8, u32) -> i32,
+    stop: "Stop" => unsafe extern "C" fn(),
+    force_stop: "ForceStop" => unsafe extern "C" fn(),
+    is_running: "IsRunning" => unsafe extern "C" fn() -> i32,
+    get_offset: "GetOffset" => unsafe extern "C" fn() -> i64,
+    get_frequency_ppm: "GetFrequencyPPM" => unsafe extern "C" fn() -> f64,
+    lw_get_frequency_ppm: "LwGetFrequencyPPM" => unsafe extern "C" fn(*mut f64) -> i32,
+    get_stats_string: "GetStatsString" => unsafe extern "C" fn(*mut c_char, i32) -> i32,
+    get_state: "GetState" => unsafe extern "C" fn() -> u8,
+    is_locked: "IsLocked" => unsafe extern "C" fn() -> i32,
+    get_version: "GetVersion" => unsafe extern "C" fn() -> u32,
+    timer_start: "TimerStart" => unsafe extern "C" fn(u32, Option<ClockTimerCallback>, *mut c_void) -> i32,
+    timer_stop: "TimerStop" => unsafe extern "C" fn(),
+    timer_is_running: "TimerIsRunning" => unsafe extern "C" fn() -> i32,
+    timer_set_interval: "TimerSetInterval" => unsafe extern "C" fn(u32) -> i32,
+    timer_get_interval: "TimerGetInterval" => unsafe extern "C" fn() -> u32,
+    timer_set_pll: "TimerSetPLL" => unsafe extern "C" fn(i32),
+    timer_is_pll_enabled: "TimerIsPLLEnabled" => unsafe extern "C" fn() -> i32,
+    set_ptp_instance: "SetPtpInstance" => unsafe extern "C" fn(u32),
+    get_ptp_instance: "GetPtpInstance" => unsafe extern "C" fn() -> u32,
+    set_ntp_server: "SetNtpServer" => unsafe extern "C" fn(*const c_char),
+    set_event_callback: "SetEventCallback" => unsafe extern "C" fn(Option<ClockEventCallback>, *mut c_void),
+    media_new_source_key: "MediaNewSourceKey" => unsafe extern "C" fn() -> usize,
+    media_select_source: "MediaSelectSource" => unsafe extern "C" fn(usize, u32),
+    media_get_source: "MediaGetSource" => unsafe extern "C" fn() -> usize,
+    media_follow: "MediaFollow" => unsafe extern "C" fn(usize, u32),
+    media_unfollow: "MediaUnfollow" => unsafe extern "C" fn(usize),
+    media_release_source: "MediaReleaseSource" => unsafe extern "C" fn(usize),
+    media_feed: "MediaFeed" => unsafe extern "C" fn(usize, u32),
+    media_get_frequency_ppm: "MediaGetFrequencyPPM" => unsafe extern "C" fn(usize) -> f64,
+    media_is_locked: "MediaIsLocked" => unsafe extern "C" fn(usize) -> i32,
+    media_get_state: "MediaGetState" => unsafe extern "C" fn(usize) -> u8,
+    media_get_offset: "MediaGetOffset" => unsafe extern "C" fn(usize) -> i64,
+    media_get_stats_string: "MediaGetStatsString" => unsafe extern "C" fn(usize, *mut c_char, i32) -> i32,
+    lease_acquire: "LeaseAcquire" => unsafe extern "C" fn(*const c_char, *const c_char, usize, *mut u32) -> i32,
+    lease_release: "LeaseRelease" => unsafe extern "C" fn(u32),
+    lease_is_locked: "LeaseIsLocked" => unsafe extern "C" fn(u32) -> i32,
+    lease_get_frequency_ppm: "LeaseGetFrequencyPPM" => unsafe extern "C" fn(u32) -> f64,
+    lease_get_offset: "LeaseGetOffset" => unsafe extern "C" fn(u32) -> i64,
+    lease_get_state: "LeaseGetState" => unsafe extern "C" fn(u32) -> u8,
+    lease_get_stats_string: "LeaseGetStatsString" => unsafe extern "C" fn(u32, *mut c_char, i32) -> i32,
+}
+
+static LIBRARY: OnceLock<Option<SharedLibrary>> = OnceLock::new();
+
+/// The bass_clock library to forward to, or None to use this binary's state
+pub(crate) fn library() -> Option<&'static SharedLibrary> {
+    LIBRARY.get_or_init(load).as_ref()
+}
+
+#[cfg(not(any(test, feature = "static-ptp", feature = "static-livewire", feature = "static-system", feature = "static-ntp")))]
+fn load() -> Option<SharedLibrary> {
+    let handle = loader::open("bass_clock");
+    if handle.is_null() {
+        return None;
+    }
+    // Kept loaded for the process lifetime, unless it is this binary
+    let library = SharedLibrary::from_library(|name| loader::symbol(handle, name))
+        .filter(|library| library.init as *const () != crate::BASS_CLOCK_Init as *const ());
+    if library.is_none() {
+        loader::close(handle);
+    }
+    library
+}
+
+#[cfg(any(test, feature = "static-ptp", feature = "static-livewire", feature = "static-system", feature = "static-ntp"))]
+fn load() -> Option<SharedLibrary> {
+    None
+}
+
+/// Interface address as the C string the library API takes
+pub(crate) fn interface_string(interface: Ipv4Addr) -> CString {
+    CString::new(interface.to_string()).unwrap_or_default()
+}
+
+/// Map an i32 result of the library API
+pub(crate) fn result(code: i32) -> Result<(), i32> {
+    match code {
+        CLOCK_OK => Ok(()),
+        err => Err(err),
+    }
+}
+
+/// A clock lease held by the bass_clock library, by handle
+pub(crate) struct SharedLease {
+    library: &'static SharedLibrary,
+    mode: ClockMode,
+    /// Lease handle (0 once released)
+    handle: AtomicU32,
+}
+
+impl SharedLease {
+    /// Acquire `reference` in the library. `stream_key` is the media clock
+    /// key of a `ClockRef::Stream` (0 otherwise).
+    pub(crate) fn acquire(
+        library: &'static SharedLibrary,
+        reference: ClockRef,
+        interface: Ipv4Addr,
+        stream_key: usize,
+    ) -> Result<Self, i32> {
+        let mode = match reference {
+            ClockRef::Global => return Err(CLOCK_ERROR_INVALID),
+            ClockRef::Ptp(_) => ClockMode::Ptp,
+            ClockRef::Livewire => ClockMode::Livewire,
+            ClockRef::System => ClockMode::System,
+            ClockRef::Ntp => ClockMode::Ntp,
+            ClockRef::Stream(_) => ClockMode::Stream,
+        };
+        let name = CString::new(reference.to_string()).map_err(|_| CLOCK_ERROR_INVALID)?;
+        let interface = interface_string(interface);
+        let mut handle = 0;
+        result(unsafe { (library.lease_acquire)(name.as_ptr(), interface.as_ptr(), stream_key, &mut handle) })?;
+        Ok(Self {
+            library,
+            mode,
+            handle: AtomicU32::new(handle),
+        })
+    }
+
+    fn handle(&self) -> u32 {
+        self.handle.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for SharedLease {
+    fn drop(&mut self) {
+        self.force_stop();
+    }
+}
+
+impl ClockSource for SharedLease {
+    fn mode(&self) -> ClockMode {
+        self.mode
+    }
+
+    fn name(&self) -> &'static str {
+        match self.mode {
+            ClockMode::Ptp => "PTP",
+            ClockMode::Livewire => "Livewire",
+            ClockMode::System => "System",
+            ClockMode::Ntp => "NTP",
+            ClockMode::Stream => "Stream",
+        }
+    }
+
+    /// The lease holds its clock from acquisition until it is released
+    fn start(&self, _interface: Ipv4Addr, _domain: u8) -> Result<(), i32> {
+        if self.is_running() {
+            Ok(())
+        } else {
+            Err(CLOCK_ERROR_NOT_INIT)
+        }
+    }
+
+    fn force_stop(&self) {
+        let handle = self.handle.swap(0, Ordering::AcqRel);
+        if handle != 0 {
+            unsafe { (self.library.lease_release)(handle) };
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.handle() != 0
+    }
+
+    fn offset_ns(&self) -> i64 {
+        unsafe { (self.library.lease_get_offset)(self.handle()) }
+    }
+
+    fn frequency_ppm(&self) -> f64 {
+        unsafe { (self.library.lease_get_frequency_ppm)(self.handle()) }
+    }
+
+    fn state(&self) -> ClockState {
+        ClockState::from(unsafe { (self.library.lease_get_state)(self.handle()) })
+    }
+
+    fn is_locked(&self) -> bool {
+        unsafe { (self.library.lease_is_locked)(self.handle()) != 0 }
+    }
+
+    fn stats_string(&self) -> String {
+        read_stats(|buffer, size| unsafe { (self.library.lease_get_stats_string)(self.handle(), buffer, size) })
+    }
+
+    fn version(&self) -> u32 {
+        unsafe { (self.library.get_version)() }
+    }
+}
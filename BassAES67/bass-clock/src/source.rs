@@ -0,0 +1,87 @@
+//! The interface every clock implements.
+
+use std::ffi::c_void;
+use std::net::Ipv4Addr;
+
+use crate::{ClockMode, ClockState, ClockTimerCallback, CLOCK_ERROR_NOT_INIT};
+
+/// A clock the plugins can follow.
+///
+/// Implementations are singletons owned by the registry, so
+/// every method takes `&self` and must be callable from any thread. The
+/// getters are called from audio threads and must not block for long.
+pub trait ClockSource: Send + Sync {
+    /// Mode this clock implements
+    fn mode(&self) -> ClockMode;
+
+    /// Short name for status strings ("PTP", "Livewire", ...)
+    fn name(&self) -> &'static str;
+
+    /// Start the clock on `interface`. `domain` is only used by PTP.
+    fn start(&self, interface: Ipv4Addr, domain: u8) -> Result<(), i32>;
+
+    /// Stop the clock regardless of how often it was started
+    fn force_stop(&self);
+
+    fn is_running(&self) -> bool;
+
+    /// Offset from the reference in nanoseconds
+    fn offset_ns(&self) -> i64;
+
+    /// Frequency adjustment in ppm (positive = reference runs faster than the local clock)
+    fn frequency_ppm(&self) -> f64;
+
+    fn state(&self) -> ClockState;
+
+    /// Whether the clock is good enough to follow
+    fn is_locked(&self) -> bool;
+
+    /// Human-readable status line
+    fn stats_string(&self) -> String;
+
+    fn version(&self) -> u32;
+
+    /// Identity of the master the clock follows (0 = unknown or none). Only
+    /// PTP reports one: its grandmaster's clock identity.
+    fn grandmaster_id(&self) -> u64 {
+        0
+    }
+
+    /// Start a separate instance of this clock on `domain`, for clocks that
+    /// can run several at once (PTP). The instance stops when dropped. None if
+    /// the clock runs once per process or the instance could not be created.
+    fn create_instance(&self, _interface: Ipv4Addr, _domain: u8) -> Option<Box<dyn ClockSource>> {
+        None
+    }
+
+    // Precision timer. Clocks without one leave these at their defaults and
+    // the registry uses the system clock's timer instead.
+
+    fn has_timer(&self) -> bool {
+        false
+    }
+
+    fn timer_start(&self, _interval_ms: u32, _callback: Option<ClockTimerCallback>, _user: *mut c_void) -> i32 {
+        CLOCK_ERROR_NOT_INIT
+    }
+
+    fn timer_stop(&self) {}
+
+    fn timer_is_running(&self) -> bool {
+        false
+    }
+
+    fn timer_set_interval(&self, _interval_ms: u32) -> i32 {
+        CLOCK_ERROR_NOT_INIT
+    }
+
+    fn timer_get_interval(&self) -> u32 {
+        20
+    }
+
+    fn timer_set_pll(&self, _enabled: bool) {}
+
+    fn timer_is_pll_enabled(&self) -> bool {
+        false
+    }
+}
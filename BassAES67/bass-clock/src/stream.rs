@@ -0,0 +1,95 @@
+//! Clock recovered from a received stream (see [`crate::media`]).
+
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::media;
+use crate::source::ClockSource;
+use crate::{ClockMode, ClockState};
+
+/// The media clock as a clock source. It runs in-process, so starting it
+/// only marks it active.
+///
+/// The registry's instance reads the process-wide source chosen with
+/// `media::select_source`; per-stream references follow one stream for as
+/// long as they live.
+#[derive(Default)]
+pub(crate) struct StreamClock {
+    running: AtomicBool,
+    /// Followed stream (0 = the process-wide source)
+    key: usize,
+}
+
+impl StreamClock {
+    /// A running clock following the stream with `key`
+    pub(crate) fn follow(key: usize) -> Self {
+        media::follow(key, None);
+        Self {
+            running: AtomicBool::new(true),
+            key,
+        }
+    }
+
+    fn source_key(&self) -> usize {
+        if self.key != 0 {
+            self.key
+        } else {
+            media::source()
+        }
+    }
+}
+
+impl Drop for StreamClock {
+    fn drop(&mut self) {
+        if self.key != 0 {
+            media::unfollow(self.key);
+        }
+    }
+}
+
+impl ClockSource for StreamClock {
+    fn mode(&self) -> ClockMode {
+        ClockMode::Stream
+    }
+
+    fn name(&self) -> &'static str {
+        "Stream"
+    }
+
+    fn start(&self, _interface: Ipv4Addr, _domain: u8) -> Result<(), i32> {
+        self.running.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn force_stop(&self) {
+        self.running.store(false, Ordering::Release);
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    fn offset_ns(&self) -> i64 {
+        media::offset_ns(self.source_key())
+    }
+
+    fn frequency_ppm(&self) -> f64 {
+        media::frequency_ppm(self.source_key())
+    }
+
+    fn state(&self) -> ClockState {
+        ClockState::from(media::state(self.source_key()))
+    }
+
+    fn is_locked(&self) -> bool {
+        media::is_locked(self.source_key())
+    }
+
+    fn stats_string(&self) -> String {
+        media::stats_string(self.source_key())
+    }
+
+    fn version(&self) -> u32 {
+        0
+    }
+}
@@ -2,16 +2,19 @@
 //!
 //! Receives multicast clock packets and calculates offset/frequency
 //! using the same algorithm as the Axia reference implementation.
+//! A master-capable client also sends clock packets while it wins the
+//! priority election (see `master`).
 
 use std::mem::MaybeUninit;
 use std::net::{Ipv4Addr, SocketAddrV4};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::thread::{self, JoinHandle};
 use std::time::Instant;
 use parking_lot::Mutex;
 use socket2::{Domain, Protocol, Socket, Type};
 
+use crate::master::{self, LwMasterConfig};
 use crate::servo::ClockServo;
 use crate::stats::{update_stats_string, LwState, LwStats, MasterIdentity};
 
@@ -19,16 +22,17 @@ use crate::stats::{update_stats_string, LwState, LwStats, MasterIdentity};
 const LWCLK_PACKET_TYPE_SYNC: u32 = 0x0C00CABA;
 const LWCLK_MAGIC: u8 = 0xAC;
 const LWCLK_RTP_EXT_PROFILE: u16 = 0xFA1A;
-const FRAME_DURATION_NS: u64 = 250_000; // 250µs in nanoseconds
-const NS_PER_MICROTICK: f64 = 81.380; // ~81.38 nanoseconds per microtick
+const LWCLK_PACKET_SIZE: usize = 36;
+pub(crate) const FRAME_DURATION_NS: u64 = 250_000; // 250µs in nanoseconds
+pub(crate) const NS_PER_MICROTICK: f64 = 81.380; // ~81.38 nanoseconds per microtick
 
 // Multicast address for Livewire clock (standard clock used by all devices)
-const MULTICAST_CLOCK: Ipv4Addr = Ipv4Addr::new(239, 192, 255, 2);
-const LIVEWIRE_PORT: u16 = 7000;
+pub(crate) const MULTICAST_CLOCK: Ipv4Addr = Ipv4Addr::new(239, 192, 255, 2);
+pub(crate) const LIVEWIRE_PORT: u16 = 7000;
 
 /// Parsed Livewire clock packet
 #[derive(Debug, Clone)]
-struct LwClockPacket {
+pub(crate) struct LwClockPacket {
     /// Frame number (250µs units)
     frame: u32,
     /// Packet type (should be 0x0C00CABA for sync)
@@ -48,9 +52,54 @@ struct LwClockPacket {
 }
 
 impl LwClockPacket {
+    /// Sync packet from `master` at the given frame and microticks
+    pub(crate) fn new_sync(frame: u32, microticks: u16, master: &MasterIdentity) -> Self {
+        Self {
+            frame,
+            packet_type: LWCLK_PACKET_TYPE_SYNC,
+            microticks,
+            magic: LWCLK_MAGIC,
+            priority: master.priority,
+            hardware_id: master.hardware_id,
+            mac_address: master.mac_address,
+            ext_profile: LWCLK_RTP_EXT_PROFILE,
+        }
+    }
+
+    /// Serialize as an RTP packet with the clock data in the header extension
+    pub(crate) fn to_bytes(&self, sequence: u16, samples_per_frame: u32) -> [u8; LWCLK_PACKET_SIZE] {
+        let mut buf = [0u8; LWCLK_PACKET_SIZE];
+
+        // RTP header: version 2 with extension, no payload
+        buf[0] = 0x90;
+        buf[2..4].copy_from_slice(&sequence.to_be_bytes());
+        buf[4..8].copy_from_slice(&self.frame.wrapping_mul(samples_per_frame).to_be_bytes());
+        buf[8..12].copy_from_slice(&u32::from_be_bytes([
+            self.mac_address[2],
+            self.mac_address[3],
+            self.mac_address[4],
+            self.mac_address[5],
+        ]).to_be_bytes());
+
+        // RTP extension header: profile and length in 32-bit words
+        buf[12..14].copy_from_slice(&self.ext_profile.to_be_bytes());
+        buf[14..16].copy_from_slice(&(((LWCLK_PACKET_SIZE - 16) / 4) as u16).to_be_bytes());
+
+        // Livewire clock data
+        buf[16..20].copy_from_slice(&self.frame.to_be_bytes());
+        buf[20..24].copy_from_slice(&self.packet_type.to_be_bytes());
+        buf[24..26].copy_from_slice(&self.microticks.to_be_bytes());
+        buf[26] = self.magic;
+        buf[27] = self.priority;
+        buf[28..30].copy_from_slice(&self.hardware_id.to_be_bytes());
+        buf[30..36].copy_from_slice(&self.mac_address);
+
+        buf
+    }
+
     /// Parse a raw UDP packet
     fn parse(data: &[u8]) -> Option<Self> {
-        if data.len() < 36 {
+        if data.len() < LWCLK_PACKET_SIZE {
             return None;
         }
 
@@ -106,12 +155,26 @@ static SHARED_STATS: OnceLock<Mutex<LwStats>> = OnceLock::new();
 struct LwClientHandle {
     running: Arc<AtomicBool>,
     thread: Option<JoinHandle<()>>,
+    master_thread: Option<JoinHandle<()>>,
 }
 
 /// Start the Livewire clock client.
 ///
 /// Multiple calls are reference-counted - only first call actually starts.
 pub fn start_lw_client(interface_ip: Ipv4Addr) -> Result<(), String> {
+    start_lw_client_ex(interface_ip, None)
+}
+
+/// Start the Livewire clock client, optionally as a master candidate.
+///
+/// With a master configuration the client clocks the network whenever no
+/// higher-priority master is present. Reference-counted like
+/// `start_lw_client`; the role is set by the call that actually starts it.
+pub fn start_lw_client_ex(interface_ip: Ipv4Addr, master: Option<LwMasterConfig>) -> Result<(), String> {
+    if let Some(ref m) = master {
+        m.validate()?;
+    }
+
     let count = REF_COUNT.fetch_add(1, Ordering::SeqCst);
     if count > 0 {
         // Already running, just increment ref count
@@ -131,6 +194,7 @@ pub fn start_lw_client(interface_ip: Ipv4Addr) -> Result<(), String> {
         let mut stats = stats_mutex.lock();
         *stats = LwStats {
             state: LwState::Listening,
+            master_priority: master.map(|m| m.priority).unwrap_or(0),
             ..Default::default()
         };
         update_stats_string(&stats);
@@ -138,17 +202,60 @@ pub fn start_lw_client(interface_ip: Ipv4Addr) -> Result<(), String> {
 
     let running = Arc::new(AtomicBool::new(true));
     let running_clone = running.clone();
+    let start_time = Instant::now();
+
+    // Master candidates listen for MASTER_TIMEOUT before taking over, so
+    // they don't disturb a network that already has a better master
+    let mastering = Arc::new(AtomicBool::new(false));
+    // Local time to master timeline, updated from every followed packet
+    let timeline_offset = Arc::new(AtomicI64::new(0));
+    let master_thread = match master {
+        Some(config) => {
+            let socket = match master::create_send_socket(interface_ip) {
+                Ok(s) => s,
+                Err(e) => {
+                    REF_COUNT.fetch_sub(1, Ordering::SeqCst);
+                    return Err(e);
+                }
+            };
+            let identity = config.identity(interface_ip);
+            let running_clone = running.clone();
+            let mastering_clone = mastering.clone();
+            let offset_clone = timeline_offset.clone();
+            let spawned = thread::Builder::new()
+                .name("lw-clock-master".to_string())
+                .spawn(move || {
+                    master::master_thread(socket, running_clone, mastering_clone, identity, start_time, offset_clone);
+                });
+            match spawned {
+                Ok(t) => Some(t),
+                Err(e) => {
+                    REF_COUNT.fetch_sub(1, Ordering::SeqCst);
+                    return Err(format!("Failed to spawn thread: {}", e));
+                }
+            }
+        }
+        None => None,
+    };
 
-    let thread = thread::Builder::new()
+    let thread = match thread::Builder::new()
         .name("lw-clock-client".to_string())
         .spawn(move || {
-            client_thread(running_clone, interface_ip);
-        })
-        .map_err(|e| format!("Failed to spawn thread: {}", e))?;
+            client_thread(running_clone, interface_ip, master, mastering, start_time, timeline_offset);
+        }) {
+        Ok(t) => t,
+        Err(e) => {
+            // Let the master thread exit
+            running.store(false, Ordering::SeqCst);
+            REF_COUNT.fetch_sub(1, Ordering::SeqCst);
+            return Err(format!("Failed to spawn thread: {}", e));
+        }
+    };
 
     *client_guard = Some(LwClientHandle {
         running,
         thread: Some(thread),
+        master_thread,
     });
 
     Ok(())
@@ -187,8 +294,9 @@ pub fn force_stop_lw_client() {
     // Take the handle but don't join - let thread terminate on its own
     // The thread will see running=false and exit after its socket timeout
     if let Some(mut handle) = client_guard.take() {
-        // Drop thread handle without joining - it'll terminate naturally
+        // Drop thread handles without joining - they'll terminate naturally
         drop(handle.thread.take());
+        drop(handle.master_thread.take());
     }
 
     // Update stats to disabled
@@ -204,6 +312,14 @@ pub fn is_lw_running() -> bool {
     REF_COUNT.load(Ordering::SeqCst) > 0
 }
 
+/// Check if this client is currently clocking the network
+pub fn is_lw_master() -> bool {
+    SHARED_STATS
+        .get()
+        .map(|m| m.lock().state == LwState::Master)
+        .unwrap_or(false)
+}
+
 /// Get current stats
 pub fn get_lw_stats() -> Option<LwStats> {
     SHARED_STATS.get().map(|m| m.lock().clone())
@@ -227,7 +343,18 @@ pub fn get_frequency_ppm() -> f64 {
 
 /// Client thread that receives and processes clock packets.
 /// Uses the same algorithm as the Axia reference implementation.
-fn client_thread(running: Arc<AtomicBool>, interface_ip: Ipv4Addr) {
+///
+/// For master candidates it also runs the election, switching the sender
+/// thread on and off through `mastering`, and keeps `timeline_offset_ns`
+/// at the followed master's timeline minus local time since `start_time`.
+fn client_thread(
+    running: Arc<AtomicBool>,
+    interface_ip: Ipv4Addr,
+    master: Option<LwMasterConfig>,
+    mastering: Arc<AtomicBool>,
+    start_time: Instant,
+    timeline_offset_ns: Arc<AtomicI64>,
+) {
     // Create socket
     let socket = match create_multicast_socket(interface_ip, MULTICAST_CLOCK) {
         Ok(s) => s,
@@ -241,12 +368,33 @@ fn client_thread(running: Arc<AtomicBool>, interface_ip: Ipv4Addr) {
     let _ = socket.set_read_timeout(Some(std::time::Duration::from_millis(100)));
 
     let mut servo = ClockServo::new();
-    let start_time = Instant::now();
     let mut current_master: Option<MasterIdentity> = None;
 
+    // Election state (master candidates only)
+    let local_identity = master.map(|m| m.identity(interface_ip));
+    let mut last_better_master = Instant::now();
+
     let mut buf: [MaybeUninit<u8>; 128] = unsafe { MaybeUninit::uninit().assume_init() };
 
     while running.load(Ordering::SeqCst) {
+        // Take over when no better master has been heard for a while
+        if let Some(identity) = local_identity {
+            if !mastering.load(Ordering::SeqCst) && last_better_master.elapsed() > master::MASTER_TIMEOUT {
+                current_master = None;
+                servo.reset();
+                if let Some(stats_mutex) = SHARED_STATS.get() {
+                    let mut stats = stats_mutex.lock();
+                    stats.state = LwState::Master;
+                    stats.master = identity;
+                    stats.offset_ns = 0;
+                    stats.frequency_ppm = 0.0;
+                    stats.locked = true;
+                    update_stats_string(&stats);
+                }
+                mastering.store(true, Ordering::SeqCst);
+            }
+        }
+
         // Receive packet
         let len = match socket.recv(&mut buf) {
             Ok(n) => n,
@@ -273,8 +421,28 @@ fn client_thread(running: Arc<AtomicBool>, interface_ip: Ipv4Addr) {
             _ => continue,
         };
 
-        // Master selection: highest priority wins
         let packet_master = packet.master_identity();
+
+        // Election: only masters that beat us are followed
+        if let Some(identity) = local_identity {
+            if packet_master.mac_address == identity.mac_address
+                || !master::outranks(&packet_master, &identity)
+            {
+                continue;
+            }
+            if mastering.swap(false, Ordering::SeqCst) {
+                // Yield to the better master
+                if let Some(stats_mutex) = SHARED_STATS.get() {
+                    let mut stats = stats_mutex.lock();
+                    stats.state = LwState::Listening;
+                    stats.locked = false;
+                    update_stats_string(&stats);
+                }
+            }
+        }
+
+        // Master selection: highest priority wins
+
         let accept_packet = match &current_master {
             None => true,
             Some(master) => {
@@ -288,6 +456,12 @@ fn client_thread(running: Arc<AtomicBool>, interface_ip: Ipv4Addr) {
         if !accept_packet {
             continue;
         }
+        last_better_master = Instant::now();
+
+        // Where the sender continues the counter if it takes over
+        let remote_ns = packet.frame as i64 * FRAME_DURATION_NS as i64
+            + (packet.microticks as f64 * NS_PER_MICROTICK) as i64;
+        timeline_offset_ns.store(remote_ns - local_ns as i64, Ordering::SeqCst);
 
         // Update current master if changed
         if current_master.as_ref() != Some(&packet_master) {
@@ -362,3 +536,34 @@ fn create_multicast_socket(interface_ip: Ipv4Addr, multicast_addr: Ipv4Addr) ->
 
     Ok(socket)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_roundtrip() {
+        let master = MasterIdentity {
+            mac_address: [0x00, 0x0C, 0xB3, 0x01, 0x02, 0x03],
+            priority: 12,
+            hardware_id: 0x1234,
+        };
+        let bytes = LwClockPacket::new_sync(0x8000_0001, 3071, &master).to_bytes(7, 12);
+
+        // RTP sequence and timestamp (12 samples per frame, wrapping)
+        assert_eq!(u16::from_be_bytes([bytes[2], bytes[3]]), 7);
+        assert_eq!(u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]), 0x8000_0001u32.wrapping_mul(12));
+
+        let packet = LwClockPacket::parse(&bytes).unwrap();
+        assert!(packet.is_valid_sync());
+        assert_eq!(packet.frame, 0x8000_0001);
+        assert_eq!(packet.microticks, 3071);
+        assert_eq!(packet.master_identity(), master);
+    }
+
+    #[test]
+    fn test_parse_short_packet() {
+        let bytes = LwClockPacket::new_sync(1, 0, &MasterIdentity::default()).to_bytes(0, 12);
+        assert!(LwClockPacket::parse(&bytes[..LWCLK_PACKET_SIZE - 1]).is_none());
+    }
+}
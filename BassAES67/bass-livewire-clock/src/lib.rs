@@ -4,6 +4,7 @@
 //! allowing applications to use either PTP or Livewire clock synchronization.
 
 pub mod client;
+pub mod master;
 pub mod servo;
 pub mod stats;
 pub mod timer;
@@ -13,9 +14,10 @@ use std::net::Ipv4Addr;
 
 // Re-export key types
 pub use client::{
-    force_stop_lw_client, get_frequency_ppm, get_lw_stats, get_offset_ns, is_lw_running,
-    start_lw_client, stop_lw_client
+    force_stop_lw_client, get_frequency_ppm, get_lw_stats, get_offset_ns, is_lw_master, is_lw_running,
+    start_lw_client, start_lw_client_ex, stop_lw_client
 };
+pub use master::LwMasterConfig;
 pub use stats::{LwState, LwStats};
 
 // ============================================================================
@@ -74,6 +76,44 @@ pub unsafe extern "C" fn BASS_LW_Start(interface_ip: *const c_char) -> i32 {
     }
 }
 
+/// Start the Livewire clock client as a master candidate.
+///
+/// Listens for one second; if no master with a higher priority (or equal
+/// priority and higher MAC address) is heard, starts sending clock packets
+/// on 239.192.255.2:7000. Yields to a better master as soon as one appears
+/// and takes over again when it has been silent for one second.
+/// Reference-counted together with BASS_LW_Start; stop with BASS_LW_Stop.
+///
+/// # Arguments
+/// * `interface_ip` - Network interface IP as null-terminated C string
+/// * `priority` - Clock priority (1-15, higher = more preferred)
+///
+/// # Returns
+/// * BASS_LW_OK on success
+/// * BASS_LW_ERROR_INVALID if interface_ip or priority is invalid
+/// * BASS_LW_ERROR_SOCKET if socket creation fails
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_StartMaster(interface_ip: *const c_char, priority: u8) -> i32 {
+    if interface_ip.is_null() {
+        return BASS_LW_ERROR_INVALID;
+    }
+
+    let ip_addr: Ipv4Addr = match CStr::from_ptr(interface_ip).to_str().ok().and_then(|s| s.parse().ok()) {
+        Some(ip) => ip,
+        None => return BASS_LW_ERROR_INVALID,
+    };
+
+    let config = LwMasterConfig::new(priority);
+    if config.validate().is_err() {
+        return BASS_LW_ERROR_INVALID;
+    }
+
+    match start_lw_client_ex(ip_addr, Some(config)) {
+        Ok(()) => BASS_LW_OK,
+        Err(_) => BASS_LW_ERROR_SOCKET,
+    }
+}
+
 /// Stop the Livewire clock client.
 ///
 /// Decrements reference count. Only actually stops when count reaches 0.
@@ -160,7 +200,7 @@ pub unsafe extern "C" fn BASS_LW_GetVersion() -> u32 {
 /// Get Livewire clock state.
 ///
 /// # Returns
-/// * 0 = Disabled, 1 = Listening, 2 = Uncalibrated, 3 = Slave
+/// * 0 = Disabled, 1 = Listening, 2 = Uncalibrated, 3 = Slave, 4 = Master
 #[no_mangle]
 pub unsafe extern "C" fn BASS_LW_GetState() -> u8 {
     get_lw_stats()
@@ -168,6 +208,15 @@ pub unsafe extern "C" fn BASS_LW_GetState() -> u8 {
         .unwrap_or(LwState::Disabled as u8)
 }
 
+/// Check if this client is currently the Livewire clock master.
+///
+/// # Returns
+/// * 1 if sending clock packets, 0 if not
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_IsMaster() -> i32 {
+    if is_lw_master() { 1 } else { 0 }
+}
+
 /// Check if Livewire clock is locked (stable synchronization).
 ///
 /// # Returns
@@ -281,21 +330,84 @@ pub unsafe extern "C" fn BASS_LW_TimerIsPLLEnabled() -> i32 {
     if timer::is_pll_enabled() { 1 } else { 0 }
 }
 
+/// Create an independent precision timer.
+///
+/// Any number of timers can run at once, each with its own interval,
+/// callback and PLL setting (enabled by default). The timer starts ticking
+/// immediately.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick (can be NULL)
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle, or 0 if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_CreateTimer(
+    interval_ms: u32,
+    callback: Option<BASS_LW_TimerProc>,
+    user: *mut c_void,
+) -> u32 {
+    timer::create_timer(interval_ms, callback, user).unwrap_or(0)
+}
+
+/// Stop a timer created with BASS_LW_CreateTimer and free its handle.
+///
+/// Can be called from the timer's own callback.
+///
+/// # Returns
+/// * BASS_LW_OK on success
+/// * BASS_LW_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_DestroyTimer(handle: u32) -> i32 {
+    if timer::destroy_timer(handle) { BASS_LW_OK } else { BASS_LW_ERROR_INVALID }
+}
+
+/// Set a timer's interval (can change while running).
+///
+/// # Returns
+/// * BASS_LW_OK on success
+/// * BASS_LW_ERROR_INVALID if handle is unknown or interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_TimerSetIntervalEx(handle: u32, interval_ms: u32) -> i32 {
+    if timer::set_timer_interval(handle, interval_ms) { BASS_LW_OK } else { BASS_LW_ERROR_INVALID }
+}
+
+/// Get a timer's interval.
+///
+/// # Returns
+/// * Interval in milliseconds, or 0 if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_TimerGetIntervalEx(handle: u32) -> u32 {
+    timer::timer_interval(handle).unwrap_or(0)
+}
+
+/// Enable or disable PLL frequency adjustment of a timer.
+///
+/// # Returns
+/// * BASS_LW_OK on success
+/// * BASS_LW_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_LW_TimerSetPLLEx(handle: u32, enabled: i32) -> i32 {
+    if timer::set_timer_pll(handle, enabled != 0) { BASS_LW_OK } else { BASS_LW_ERROR_INVALID }
+}
+
 // ============================================================================
-// Windows DLL Entry Point
+// Windows DLL Entry Point (left out when linked into a plugin)
 // ============================================================================
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_ATTACH: u32 = 1;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_DETACH: u32 = 0;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type BOOL = i32;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type DWORD = u32;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 #[no_mangle]
 pub unsafe extern "system" fn DllMain(
     _hinst: *mut c_void,
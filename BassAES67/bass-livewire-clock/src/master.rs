@@ -0,0 +1,207 @@
+//! Livewire clock master role.
+//!
+//! A master-capable client sends clock packets on 239.192.255.2:7000 while
+//! it is the best clock on the network. Election follows the Livewire rule
+//! that the highest priority wins: as soon as a packet from a master with a
+//! higher priority (or the same priority and a higher MAC address) arrives,
+//! the client stops sending and follows that master instead. It takes over
+//! again once that master has been silent for `MASTER_TIMEOUT`.
+//!
+//! Packets carry the master's frame counter (250 µs frames) and the
+//! microticks into the current frame at the time of sending, so a late
+//! wake-up of the sender thread only delays a packet and never puts a wrong
+//! time into it. The counter continues the timeline of the last master the
+//! client followed, so slaves see no jump when the master changes; without
+//! a previous master it counts from the start of the client.
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::client::{LwClockPacket, FRAME_DURATION_NS, LIVEWIRE_PORT, MULTICAST_CLOCK, NS_PER_MICROTICK};
+use crate::stats::MasterIdentity;
+
+/// Highest Livewire clock priority
+pub const LW_MAX_PRIORITY: u8 = 15;
+
+/// Time without packets from a better master before taking over again
+pub const MASTER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// RTP timestamp increment per frame (48 kHz samples in 250 µs)
+const SAMPLES_PER_FRAME: u32 = 12;
+
+/// Frames the sender may fall behind before it skips ahead
+const MAX_FRAMES_BEHIND: u32 = 4;
+
+/// Time before a send that is spun out instead of slept, as sleeps are
+/// far coarser than a frame on Windows
+const SPIN_BEFORE_SEND: Duration = Duration::from_millis(1);
+
+/// Master role configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LwMasterConfig {
+    /// Clock priority (1-15, higher = more preferred)
+    pub priority: u8,
+    /// MAC address sent in clock packets (None = derived from the interface IP)
+    pub mac_address: Option<[u8; 6]>,
+}
+
+impl LwMasterConfig {
+    /// Master with the given priority and a MAC address derived from the interface
+    pub fn new(priority: u8) -> Self {
+        Self {
+            priority,
+            mac_address: None,
+        }
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<(), String> {
+        if self.priority == 0 || self.priority > LW_MAX_PRIORITY {
+            return Err(format!("Livewire clock priority must be 1-{}", LW_MAX_PRIORITY));
+        }
+        Ok(())
+    }
+
+    /// Identity announced in our clock packets
+    pub fn identity(&self, interface_ip: Ipv4Addr) -> MasterIdentity {
+        let ip = interface_ip.octets();
+        MasterIdentity {
+            // Locally administered address built from the interface IP
+            mac_address: self.mac_address.unwrap_or([0x02, 0x00, ip[0], ip[1], ip[2], ip[3]]),
+            priority: self.priority,
+            // Lower 15 bits of the IP, as Livewire devices do
+            hardware_id: (u32::from(interface_ip) & 0x7FFF) as u16,
+        }
+    }
+}
+
+/// True if `other` wins the election against `local`
+pub fn outranks(other: &MasterIdentity, local: &MasterIdentity) -> bool {
+    (other.priority, other.mac_address) > (local.priority, local.mac_address)
+}
+
+/// Frame counter and microticks at `timeline_ns` on the master timeline.
+///
+/// The counter wraps at 32 bits like the one in the packets.
+pub(crate) fn frame_time(timeline_ns: i64) -> (u32, u16) {
+    let frame_ns = FRAME_DURATION_NS as i64;
+    (
+        timeline_ns.div_euclid(frame_ns) as u32,
+        (timeline_ns.rem_euclid(frame_ns) as f64 / NS_PER_MICROTICK) as u16,
+    )
+}
+
+/// Sleep until shortly before `deadline`, then spin until it is reached
+fn wait_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        let remaining = deadline - now;
+        if remaining > SPIN_BEFORE_SEND {
+            std::thread::sleep(remaining - SPIN_BEFORE_SEND);
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Create the socket clock packets are sent from.
+///
+/// Multicast loopback is off so other applications on this host only see
+/// our packets if they listen on another interface.
+pub(crate) fn create_send_socket(interface_ip: Ipv4Addr) -> Result<Socket, String> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+        .map_err(|e| format!("Socket creation failed: {}", e))?;
+
+    socket
+        .set_multicast_if_v4(&interface_ip)
+        .map_err(|e| format!("set_multicast_if_v4 failed: {}", e))?;
+    socket
+        .set_multicast_loop_v4(false)
+        .map_err(|e| format!("set_multicast_loop_v4 failed: {}", e))?;
+
+    Ok(socket)
+}
+
+/// Sender thread: sends one clock packet per frame while `mastering` is set.
+///
+/// `timeline_offset_ns` maps time since `start_time` to the master
+/// timeline; the client thread sets it before it sets `mastering`.
+pub(crate) fn master_thread(
+    socket: Socket,
+    running: Arc<AtomicBool>,
+    mastering: Arc<AtomicBool>,
+    identity: MasterIdentity,
+    start_time: Instant,
+    timeline_offset_ns: Arc<AtomicI64>,
+) {
+    let dest = SocketAddrV4::new(MULTICAST_CLOCK, LIVEWIRE_PORT).into();
+    let frame = Duration::from_nanos(FRAME_DURATION_NS);
+    let mut sequence: u16 = 0;
+    let mut next_send = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        if !mastering.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(10));
+            next_send = Instant::now();
+            continue;
+        }
+
+        wait_until(next_send);
+
+        let local_ns = start_time.elapsed().as_nanos() as i64;
+        let (frame_number, microticks) = frame_time(local_ns + timeline_offset_ns.load(Ordering::SeqCst));
+        let packet = LwClockPacket::new_sync(frame_number, microticks, &identity);
+        let _ = socket.send_to(&packet.to_bytes(sequence, SAMPLES_PER_FRAME), &dest);
+        sequence = sequence.wrapping_add(1);
+
+        next_send += frame;
+        // After a long stall (e.g. the thread was descheduled) skip ahead
+        // instead of sending a burst
+        let now = Instant::now();
+        if now > next_send + frame * MAX_FRAMES_BEHIND {
+            next_send = now + frame;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(priority: u8, last_mac_byte: u8) -> MasterIdentity {
+        MasterIdentity {
+            mac_address: [0x02, 0x00, 192, 168, 1, last_mac_byte],
+            priority,
+            hardware_id: last_mac_byte as u16,
+        }
+    }
+
+    #[test]
+    fn test_outranks_priority() {
+        assert!(outranks(&identity(10, 1), &identity(5, 200)));
+        assert!(!outranks(&identity(5, 200), &identity(10, 1)));
+    }
+
+    #[test]
+    fn test_outranks_tie_break_on_mac() {
+        // Same priority: the higher MAC address wins
+        assert!(outranks(&identity(8, 20), &identity(8, 10)));
+        assert!(!outranks(&identity(8, 10), &identity(8, 20)));
+        // Nobody outranks itself
+        assert!(!outranks(&identity(8, 10), &identity(8, 10)));
+    }
+
+    #[test]
+    fn test_frame_time() {
+        assert_eq!(frame_time(0), (0, 0));
+        assert_eq!(frame_time(FRAME_DURATION_NS as i64 * 7 + 814), (7, 10));
+        // Wraps with the 32-bit packet counter
+        assert_eq!(frame_time(FRAME_DURATION_NS as i64 * (u32::MAX as i64 + 3)), (2, 0));
+    }
+}
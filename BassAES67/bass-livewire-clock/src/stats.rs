@@ -15,6 +15,8 @@ pub enum LwState {
     Uncalibrated = 2,
     /// Synchronized to master clock
     Slave = 3,
+    /// Sending clock packets (won the master election)
+    Master = 4,
 }
 
 impl LwState {
@@ -25,6 +27,7 @@ impl LwState {
             LwState::Listening => "LISTENING",
             LwState::Uncalibrated => "UNCALIBRATED",
             LwState::Slave => "SLAVE",
+            LwState::Master => "MASTER",
         }
     }
 }
@@ -72,6 +75,8 @@ pub struct LwStats {
     pub packet_count: u64,
     /// Whether the servo is locked (stable tracking)
     pub locked: bool,
+    /// Our priority as a master candidate (0 = slave only)
+    pub master_priority: u8,
 }
 
 impl LwStats {
@@ -98,6 +103,13 @@ impl LwStats {
                     lock_indicator
                 )
             }
+            LwState::Master => {
+                format!(
+                    "LW: Master {} (prio={}), clocking network",
+                    self.master.mac_string(),
+                    self.master.priority
+                )
+            }
         }
     }
 
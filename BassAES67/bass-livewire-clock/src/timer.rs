@@ -1,41 +1,62 @@
-//! High-precision timer with PLL adjustment for no-soundcard mode.
+//! High-precision timers with PLL adjustment for no-soundcard mode.
 //!
-//! Provides a timer that fires at configurable intervals (default 20ms) with
-//! frequency adjustment based on Livewire clock servo output.
+//! Any number of timers can run at once, each on its own thread with its own
+//! interval, callback and PLL setting. Their period is adjusted based on
+//! Livewire clock servo output.
+//!
+//! The single-timer API (`start_timer`, `set_interval`, ...) drives the
+//! default timer.
 
+use std::collections::HashMap;
 use std::ffi::c_void;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::thread::{self, JoinHandle};
-use parking_lot::Mutex;
+
+use parking_lot::{Mutex, MutexGuard};
 
 use crate::client::{get_lw_stats, is_lw_running};
 
 /// Timer callback function type
 pub type TimerCallback = unsafe extern "C" fn(*mut c_void);
 
-/// Global timer instance
-static TIMER: OnceLock<Mutex<Option<TimerHandle>>> = OnceLock::new();
+/// Running timers by handle
+static TIMERS: OnceLock<Mutex<HashMap<u32, TimerHandle>>> = OnceLock::new();
+
+/// Next timer handle
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
 
-/// Timer configuration
+/// Handle of the default timer (0 = not running)
+static DEFAULT_TIMER: AtomicU32 = AtomicU32::new(0);
+
+/// Default timer configuration, kept while it is stopped
 static TIMER_INTERVAL_MS: AtomicU32 = AtomicU32::new(20);
 static TIMER_PLL_ENABLED: AtomicBool = AtomicBool::new(true);
 
+/// Settings a timer thread reads before every tick
+struct TimerSettings {
+    interval_ms: AtomicU32,
+    pll_enabled: AtomicBool,
+}
+
 /// Handle to a running timer
 struct TimerHandle {
     running: Arc<AtomicBool>,
     thread: Option<JoinHandle<()>>,
-    #[allow(dead_code)]
-    callback: Option<TimerCallback>,
-    #[allow(dead_code)]
-    user_data: *mut c_void,
+    settings: Arc<TimerSettings>,
+}
+
+fn timers() -> MutexGuard<'static, HashMap<u32, TimerHandle>> {
+    TIMERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
 }
 
-// Safety: user_data is only accessed from the timer thread
-unsafe impl Send for TimerHandle {}
-unsafe impl Sync for TimerHandle {}
+fn valid_interval(interval_ms: u32) -> bool {
+    interval_ms > 0 && interval_ms <= 1000
+}
 
-/// Start the precision timer.
+/// Start a timer.
 ///
 /// # Arguments
 /// * `interval_ms` - Timer period in milliseconds (1-1000)
@@ -43,105 +64,187 @@ unsafe impl Sync for TimerHandle {}
 /// * `user` - User data passed to callback
 ///
 /// # Returns
-/// * 0 on success, non-zero on error
-pub fn start_timer(
+/// * Timer handle (non-zero) on success, None if the interval is out of range
+pub fn create_timer(
     interval_ms: u32,
     callback: Option<TimerCallback>,
     user: *mut c_void,
-) -> i32 {
-    // Validate interval
-    if interval_ms == 0 || interval_ms > 1000 {
-        return -1;
-    }
-
-    // Store interval
-    TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
-
-    let timer_mutex = TIMER.get_or_init(|| Mutex::new(None));
-    let mut timer_guard = timer_mutex.lock();
-
-    // Check if already running
-    if timer_guard.is_some() {
-        return -3;
+) -> Option<u32> {
+    if !valid_interval(interval_ms) {
+        return None;
     }
 
+    let settings = Arc::new(TimerSettings {
+        interval_ms: AtomicU32::new(interval_ms),
+        pll_enabled: AtomicBool::new(true),
+    });
     let running = Arc::new(AtomicBool::new(true));
-    let running_clone = running.clone();
 
-    // Store callback info for the thread
-    let cb = callback;
+    let running_clone = running.clone();
+    let settings_clone = settings.clone();
     let user_ptr = user as usize; // Convert to usize for thread safety
 
     let thread = thread::spawn(move || {
-        timer_thread(running_clone, cb, user_ptr as *mut c_void);
+        timer_thread(running_clone, settings_clone, callback, user_ptr as *mut c_void);
     });
 
-    *timer_guard = Some(TimerHandle {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    timers().insert(handle, TimerHandle {
         running,
         thread: Some(thread),
-        callback,
-        user_data: user,
+        settings,
     });
+    Some(handle)
+}
 
-    0
+/// Stop a timer and free its handle.
+///
+/// Waits for the timer thread to finish, unless called from the timer's own
+/// callback.
+///
+/// # Returns
+/// * false if the handle is unknown
+pub fn destroy_timer(handle: u32) -> bool {
+    let timer = timers().remove(&handle);
+    match timer {
+        Some(mut timer) => {
+            timer.running.store(false, Ordering::SeqCst);
+            if let Some(thread) = timer.thread.take() {
+                if thread.thread().id() != thread::current().id() {
+                    let _ = thread.join();
+                }
+            }
+            true
+        }
+        None => false,
+    }
 }
 
-/// Stop the timer
-pub fn stop_timer() -> i32 {
-    let timer_mutex = match TIMER.get() {
-        Some(m) => m,
-        None => return 0,
-    };
+/// Run `f` on the settings of timer `handle`. None if the handle is unknown.
+fn with_settings<T>(handle: u32, f: impl FnOnce(&TimerSettings) -> T) -> Option<T> {
+    timers().get(&handle).map(|timer| f(&timer.settings))
+}
 
-    let mut timer_guard = timer_mutex.lock();
+/// Set the interval of timer `handle` (can change while running).
+///
+/// # Returns
+/// * false if the handle is unknown or the interval is out of range
+pub fn set_timer_interval(handle: u32, interval_ms: u32) -> bool {
+    valid_interval(interval_ms)
+        && with_settings(handle, |s| s.interval_ms.store(interval_ms, Ordering::SeqCst)).is_some()
+}
 
-    if let Some(mut handle) = timer_guard.take() {
-        handle.running.store(false, Ordering::SeqCst);
-        if let Some(thread) = handle.thread.take() {
-            let _ = thread.join();
-        }
+/// Interval of timer `handle` in milliseconds
+pub fn timer_interval(handle: u32) -> Option<u32> {
+    with_settings(handle, |s| s.interval_ms.load(Ordering::SeqCst))
+}
+
+/// Enable/disable PLL adjustment of timer `handle`
+pub fn set_timer_pll(handle: u32, enabled: bool) -> bool {
+    with_settings(handle, |s| s.pll_enabled.store(enabled, Ordering::SeqCst)).is_some()
+}
+
+/// Whether PLL adjustment of timer `handle` is enabled
+pub fn timer_pll(handle: u32) -> Option<bool> {
+    with_settings(handle, |s| s.pll_enabled.load(Ordering::SeqCst))
+}
+
+// ============================================================================
+// Default timer
+// ============================================================================
+
+/// Start the default timer.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * 0 on success, non-zero on error
+pub fn start_timer(
+    interval_ms: u32,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) -> i32 {
+    // Validate interval
+    if !valid_interval(interval_ms) {
+        return -1;
+    }
+
+    // Check if already running
+    if DEFAULT_TIMER.load(Ordering::SeqCst) != 0 {
+        return -3;
+    }
+
+    // Store interval
+    TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+
+    let handle = match create_timer(interval_ms, callback, user) {
+        Some(h) => h,
+        None => return -1,
+    };
+    set_timer_pll(handle, TIMER_PLL_ENABLED.load(Ordering::SeqCst));
+    if DEFAULT_TIMER.compare_exchange(0, handle, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        destroy_timer(handle);
+        return -3;
     }
 
     0
 }
 
-/// Check if timer is running
+/// Stop the default timer
+pub fn stop_timer() -> i32 {
+    let handle = DEFAULT_TIMER.swap(0, Ordering::SeqCst);
+    if handle != 0 {
+        destroy_timer(handle);
+    }
+    0
+}
+
+/// Check if the default timer is running
 pub fn is_timer_running() -> bool {
-    let timer_mutex = match TIMER.get() {
-        Some(m) => m,
-        None => return false,
-    };
-    let timer_guard = timer_mutex.lock();
-    timer_guard.is_some()
+    DEFAULT_TIMER.load(Ordering::SeqCst) != 0
 }
 
-/// Set timer interval (can change while running)
+/// Set the default timer's interval (can change while running)
 pub fn set_interval(interval_ms: u32) -> i32 {
-    if interval_ms == 0 || interval_ms > 1000 {
+    if !valid_interval(interval_ms) {
         return -1;
     }
     TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+    set_timer_interval(DEFAULT_TIMER.load(Ordering::SeqCst), interval_ms);
     0
 }
 
-/// Get current timer interval
+/// Get the default timer's interval
 pub fn get_interval() -> u32 {
     TIMER_INTERVAL_MS.load(Ordering::SeqCst)
 }
 
-/// Enable/disable PLL adjustment
+/// Enable/disable PLL adjustment of the default timer
 pub fn set_pll_enabled(enabled: bool) {
     TIMER_PLL_ENABLED.store(enabled, Ordering::SeqCst);
+    set_timer_pll(DEFAULT_TIMER.load(Ordering::SeqCst), enabled);
 }
 
-/// Check if PLL adjustment is enabled
+/// Check if PLL adjustment of the default timer is enabled
 pub fn is_pll_enabled() -> bool {
     TIMER_PLL_ENABLED.load(Ordering::SeqCst)
 }
 
+// ============================================================================
+// Timer threads
+// ============================================================================
+
 /// Timer thread implementation using multimedia timer (Windows)
 #[cfg(windows)]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user: *mut c_void) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
     use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
     use windows_sys::Win32::System::Threading::{
         CreateWaitableTimerW, SetWaitableTimer, WaitForSingleObject, INFINITE,
@@ -152,19 +255,18 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user:
 
     if timer_handle.is_null() || timer_handle == 0 as HANDLE {
         // Fallback to sleep-based timer
-        return timer_thread_fallback(running, callback, user);
+        return timer_thread_fallback(running, settings, callback, user);
     }
 
     while running.load(Ordering::SeqCst) {
-        // Get current settings
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
         // Calculate adjusted interval
-        let adjusted_interval_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
 
         // Set timer (negative value = relative time in 100ns units)
-        let due_time: i64 = -(adjusted_interval_100ns as i64);
+        let due_time: i64 = -(wait_100ns as i64);
         let result = unsafe {
             SetWaitableTimer(timer_handle, &due_time, 0, None, std::ptr::null(), 0)
         };
@@ -194,19 +296,20 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user:
 #[cfg(windows)]
 fn timer_thread_fallback(
     running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
     callback: Option<TimerCallback>,
     user: *mut c_void,
 ) {
     while running.load(Ordering::SeqCst) {
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
         // Calculate adjusted interval and convert to ms
-        let adjusted_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
-        let adjusted_ms = (adjusted_100ns / 10_000) as u32;
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
+        let wait_ms = (wait_100ns / 10_000) as u32;
 
         // Sleep
-        std::thread::sleep(std::time::Duration::from_millis(adjusted_ms as u64));
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms as u64));
 
         // Call user callback
         if let Some(cb) = callback {
@@ -217,17 +320,22 @@ fn timer_thread_fallback(
 
 /// Non-Windows fallback
 #[cfg(not(windows))]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user: *mut c_void) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
     while running.load(Ordering::SeqCst) {
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
         // Calculate adjusted interval
-        let adjusted_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
-        let adjusted_us = adjusted_100ns / 10;
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
+        let wait_us = wait_100ns / 10;
 
         // Sleep
-        std::thread::sleep(std::time::Duration::from_micros(adjusted_us as u64));
+        std::thread::sleep(std::time::Duration::from_micros(wait_us));
 
         // Call user callback
         if let Some(cb) = callback {
@@ -0,0 +1,276 @@
+//! NTP clock client implementation.
+//!
+//! Polls one NTP server over SNTP and feeds the samples to the frequency
+//! servo. The local clock is never stepped or slewed: the client only
+//! reports how fast the system clock runs against NTP, and the audio side
+//! applies that as a rate correction.
+
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::servo::{NtpServo, SampleEvent};
+use crate::sntp;
+use crate::stats::{update_stats_string, NtpState, NtpStats};
+
+/// Default poll interval in seconds
+pub const DEFAULT_POLL_INTERVAL_SECS: u32 = 16;
+
+/// Poll interval limits in seconds
+pub const MIN_POLL_INTERVAL_SECS: u32 = 1;
+pub const MAX_POLL_INTERVAL_SECS: u32 = 1024;
+
+/// Polls sent at the start (1 s apart) so a frequency is available quickly
+const STARTUP_POLLS: u32 = 4;
+
+/// How long to wait for a response
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Consecutive failed polls after which the server counts as lost
+const LOST_AFTER_TIMEOUTS: u32 = 4;
+
+/// Global client instance
+static CLIENT: OnceLock<Mutex<Option<NtpClientHandle>>> = OnceLock::new();
+static REF_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Shared stats accessible from outside
+static SHARED_STATS: OnceLock<Mutex<NtpStats>> = OnceLock::new();
+
+/// Poll interval (seconds), read by the client thread before each wait
+static POLL_INTERVAL_SECS: AtomicU32 = AtomicU32::new(DEFAULT_POLL_INTERVAL_SECS);
+
+/// Handle to a running client
+struct NtpClientHandle {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Start the NTP clock client.
+///
+/// `server` is "host[:port]". Multiple calls are reference-counted - only
+/// the first call actually starts (later calls keep the running server).
+pub fn start_ntp_client(interface_ip: Ipv4Addr, server: &str) -> Result<(), String> {
+    let count = REF_COUNT.fetch_add(1, Ordering::SeqCst);
+    if count > 0 {
+        // Already running, just increment ref count
+        return Ok(());
+    }
+
+    let client_mutex = CLIENT.get_or_init(|| Mutex::new(None));
+    let mut client_guard = client_mutex.lock();
+
+    if client_guard.is_some() {
+        return Ok(());
+    }
+
+    let setup = sntp::resolve_server(server).and_then(|addr| {
+        UdpSocket::bind((interface_ip, 0))
+            .map(|socket| (addr, socket))
+            .map_err(|e| format!("Bind failed: {}", e))
+    });
+    let (server_addr, socket) = match setup {
+        Ok(s) => s,
+        Err(e) => {
+            REF_COUNT.fetch_sub(1, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+
+    // Initialize shared stats
+    let stats_mutex = SHARED_STATS.get_or_init(|| Mutex::new(NtpStats::default()));
+    {
+        let mut stats = stats_mutex.lock();
+        *stats = NtpStats {
+            state: NtpState::Listening,
+            server: server.to_string(),
+            ..Default::default()
+        };
+        update_stats_string(&stats);
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let running_clone = running.clone();
+
+    let thread = thread::Builder::new()
+        .name("ntp-clock-client".to_string())
+        .spawn(move || {
+            client_thread(running_clone, socket, server_addr);
+        })
+        .map_err(|e| format!("Failed to spawn thread: {}", e))?;
+
+    *client_guard = Some(NtpClientHandle {
+        running,
+        thread: Some(thread),
+    });
+
+    Ok(())
+}
+
+/// Stop the NTP clock client (decrements ref count)
+pub fn stop_ntp_client() {
+    let prev = REF_COUNT.fetch_sub(1, Ordering::SeqCst);
+    if prev > 1 {
+        // Other references remain
+        return;
+    }
+
+    // Last reference, actually stop
+    force_stop_ntp_client();
+}
+
+/// Force stop regardless of reference count.
+/// This version signals thread to stop but does NOT join it.
+/// Safe to call from DllMain where joining threads would deadlock.
+pub fn force_stop_ntp_client() {
+    REF_COUNT.store(0, Ordering::SeqCst);
+
+    let client_mutex = match CLIENT.get() {
+        Some(m) => m,
+        None => return,
+    };
+
+    let mut client_guard = client_mutex.lock();
+    if let Some(mut handle) = client_guard.take() {
+        handle.running.store(false, Ordering::SeqCst);
+        // The thread sees running=false within its wait step or response timeout
+        drop(handle.thread.take());
+    }
+
+    // Update stats to disabled
+    if let Some(stats_mutex) = SHARED_STATS.get() {
+        let mut stats = stats_mutex.lock();
+        stats.state = NtpState::Disabled;
+        stats.locked = false;
+        update_stats_string(&stats);
+    }
+}
+
+/// Check if client is running
+pub fn is_ntp_running() -> bool {
+    REF_COUNT.load(Ordering::SeqCst) > 0
+}
+
+/// Get current stats
+pub fn get_ntp_stats() -> Option<NtpStats> {
+    SHARED_STATS.get().map(|m| m.lock().clone())
+}
+
+/// Get current offset (local wall clock minus server) in nanoseconds
+pub fn get_offset_ns() -> i64 {
+    SHARED_STATS
+        .get()
+        .map(|m| m.lock().offset_ns)
+        .unwrap_or(0)
+}
+
+/// Get current frequency in ppm
+pub fn get_frequency_ppm() -> f64 {
+    SHARED_STATS
+        .get()
+        .map(|m| m.lock().frequency_ppm)
+        .unwrap_or(0.0)
+}
+
+/// Set the poll interval in seconds (takes effect after the current wait)
+pub fn set_poll_interval(secs: u32) -> Result<(), String> {
+    if !(MIN_POLL_INTERVAL_SECS..=MAX_POLL_INTERVAL_SECS).contains(&secs) {
+        return Err(format!(
+            "Poll interval must be {}-{} seconds",
+            MIN_POLL_INTERVAL_SECS, MAX_POLL_INTERVAL_SECS
+        ));
+    }
+    POLL_INTERVAL_SECS.store(secs, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Get the poll interval in seconds
+pub fn get_poll_interval() -> u32 {
+    POLL_INTERVAL_SECS.load(Ordering::SeqCst)
+}
+
+/// Client thread: one exchange per poll interval
+fn client_thread(running: Arc<AtomicBool>, socket: UdpSocket, server: SocketAddr) {
+    let epoch = Instant::now();
+    let mut servo = NtpServo::default();
+    let mut polls = 0u32;
+    let mut failed = 0u32;
+
+    while running.load(Ordering::SeqCst) {
+        let result = sntp::query(&socket, server, epoch, RESPONSE_TIMEOUT);
+        polls += 1;
+
+        if let Some(stats_mutex) = SHARED_STATS.get() {
+            let mut stats = stats_mutex.lock();
+            match result {
+                Ok(sample) => {
+                    failed = 0;
+                    if servo.update(sample) != SampleEvent::RejectedDelay {
+                        stats.offset_ns = sample.wall_offset_ns;
+                    }
+                    stats.responses += 1;
+                    stats.stratum = sample.stratum;
+                    stats.delay_ns = sample.delay_ns;
+                    stats.frequency_ppm = servo.frequency_ppm();
+                    stats.frequency_error_ppm = servo.frequency_error_ppm().unwrap_or(0.0);
+                    stats.samples = servo.sample_count();
+                    stats.locked = servo.is_locked();
+                    stats.state = if servo.frequency_error_ppm().is_some() {
+                        NtpState::Slave
+                    } else {
+                        NtpState::Uncalibrated
+                    };
+                }
+                Err(_) => {
+                    failed += 1;
+                    stats.timeouts += 1;
+                    if failed >= LOST_AFTER_TIMEOUTS {
+                        // Keep the last frequency but stop claiming lock
+                        stats.locked = false;
+                        stats.state = NtpState::Listening;
+                    }
+                }
+            }
+            update_stats_string(&stats);
+        }
+
+        let wait = if polls < STARTUP_POLLS {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(POLL_INTERVAL_SECS.load(Ordering::SeqCst) as u64)
+        };
+        let deadline = Instant::now() + wait;
+        while running.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sntp::tests::stand_in;
+
+    #[test]
+    fn test_client_measures_stand_in_rate() {
+        let (server, stand_in_running) = stand_in(0, 200.0);
+        start_ntp_client(Ipv4Addr::LOCALHOST, &server.to_string()).unwrap();
+        assert!(is_ntp_running());
+
+        // Startup polls are a second apart; wait for five samples
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while get_ntp_stats().map(|s| s.samples).unwrap_or(0) < 5 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(100));
+        }
+        let stats = get_ntp_stats().unwrap();
+        stop_ntp_client();
+        stand_in_running.store(false, Ordering::SeqCst);
+
+        assert_eq!(stats.state, NtpState::Slave, "{:?}", stats);
+        assert!((stats.frequency_ppm - 200.0).abs() < 50.0, "{:?}", stats);
+        assert!(stats.offset_ns.abs() < 20_000_000, "{:?}", stats);
+        assert!(!is_ntp_running());
+    }
+}
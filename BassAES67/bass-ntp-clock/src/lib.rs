@@ -0,0 +1,435 @@
+//! bass_ntp_clock - NTP-disciplined clock library for AES67 audio
+//!
+//! Provides an NTP clock with the same API pattern as bass_ptp and
+//! bass_livewire_clock, for sites without PTP or Livewire. The system clock
+//! is not adjusted; the library reports its rate against an NTP server so
+//! audio can be paced at the NTP rate.
+
+pub mod client;
+pub mod servo;
+pub mod sntp;
+pub mod stats;
+pub mod timer;
+
+use std::ffi::{c_char, c_void, CStr};
+use std::net::Ipv4Addr;
+
+// Re-export key types
+pub use client::{
+    force_stop_ntp_client, get_frequency_ppm, get_ntp_stats, get_offset_ns, get_poll_interval, is_ntp_running,
+    set_poll_interval, start_ntp_client, stop_ntp_client
+};
+pub use stats::{NtpState, NtpStats};
+
+/// Server used when none is given
+pub const DEFAULT_NTP_SERVER: &str = "pool.ntp.org";
+
+// ============================================================================
+// C API Error Codes
+// ============================================================================
+
+pub const BASS_NTP_OK: i32 = 0;
+pub const BASS_NTP_ERROR_ALREADY: i32 = 1;
+pub const BASS_NTP_ERROR_NOT_INIT: i32 = 2;
+pub const BASS_NTP_ERROR_SOCKET: i32 = 3;
+pub const BASS_NTP_ERROR_INVALID: i32 = 4;
+
+// ============================================================================
+// C API Version
+// ============================================================================
+
+/// Library version (major.minor format: 0x0100 = 1.0)
+pub const BASS_NTP_VERSION: u32 = 0x0100;
+
+// ============================================================================
+// C API Functions
+// ============================================================================
+
+/// Start the NTP clock client.
+///
+/// Polls `server` over SNTP (once a second for the first few polls, then
+/// every poll interval) and estimates the system clock's rate against it.
+/// Multiple calls are reference-counted - only first call actually starts.
+/// Each Start must be matched with a Stop.
+///
+/// # Arguments
+/// * `interface_ip` - Network interface IP as null-terminated C string
+/// * `server` - NTP server as "host[:port]" (NULL or empty = pool.ntp.org)
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if interface_ip is null or invalid
+/// * BASS_NTP_ERROR_SOCKET if the server can't be resolved or socket creation fails
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_Start(interface_ip: *const c_char, server: *const c_char) -> i32 {
+    if interface_ip.is_null() {
+        return BASS_NTP_ERROR_INVALID;
+    }
+
+    let ip_addr: Ipv4Addr = match CStr::from_ptr(interface_ip).to_str().ok().and_then(|s| s.parse().ok()) {
+        Some(ip) => ip,
+        None => return BASS_NTP_ERROR_INVALID,
+    };
+
+    let server = if server.is_null() {
+        DEFAULT_NTP_SERVER
+    } else {
+        match CStr::from_ptr(server).to_str() {
+            Ok("") => DEFAULT_NTP_SERVER,
+            Ok(s) => s,
+            Err(_) => return BASS_NTP_ERROR_INVALID,
+        }
+    };
+
+    match start_ntp_client(ip_addr, server) {
+        Ok(()) => BASS_NTP_OK,
+        Err(_) => BASS_NTP_ERROR_SOCKET,
+    }
+}
+
+/// Stop the NTP clock client.
+///
+/// Decrements reference count. Only actually stops when count reaches 0.
+///
+/// # Returns
+/// * BASS_NTP_OK always
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_Stop() -> i32 {
+    stop_ntp_client();
+    BASS_NTP_OK
+}
+
+/// Force stop the NTP clock client regardless of reference count.
+///
+/// # Returns
+/// * BASS_NTP_OK always
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_ForceStop() -> i32 {
+    force_stop_ntp_client();
+    BASS_NTP_OK
+}
+
+/// Check if NTP clock client is running.
+///
+/// # Returns
+/// * 1 if running, 0 if not
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_IsRunning() -> i32 {
+    if is_ntp_running() { 1 } else { 0 }
+}
+
+/// Get the system clock offset from the server in nanoseconds.
+///
+/// # Returns
+/// * Local wall clock minus server time in nanoseconds, or 0 if no response yet
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetOffset() -> i64 {
+    get_offset_ns()
+}
+
+/// Get current frequency adjustment in PPM (parts per million).
+///
+/// # Returns
+/// * Frequency adjustment in ppm (positive = server runs faster than the
+///   system clock), or 0.0 until enough samples have been collected
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetFrequencyPPM() -> f64 {
+    get_frequency_ppm()
+}
+
+/// Get formatted stats string.
+///
+/// # Arguments
+/// * `buffer` - Output buffer for the string
+/// * `buffer_size` - Size of the buffer in bytes
+///
+/// # Returns
+/// * Length of string written (excluding null terminator), or 0 on error
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetStatsString(buffer: *mut c_char, buffer_size: i32) -> i32 {
+    if buffer.is_null() || buffer_size <= 0 {
+        return 0;
+    }
+
+    let stats_str = stats::get_stats_string();
+    let bytes = stats_str.as_bytes();
+    let max_len = (buffer_size - 1) as usize; // Leave room for null terminator
+    let copy_len = bytes.len().min(max_len);
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, copy_len);
+    *buffer.add(copy_len) = 0; // Null terminator
+
+    copy_len as i32
+}
+
+/// Get library version.
+///
+/// # Returns
+/// * Version in format 0xMMNN (major, minor)
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetVersion() -> u32 {
+    BASS_NTP_VERSION
+}
+
+/// Get NTP clock state.
+///
+/// # Returns
+/// * 0 = Disabled, 1 = Listening, 2 = Uncalibrated, 3 = Slave
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetState() -> u8 {
+    get_ntp_stats()
+        .map(|s| s.state as u8)
+        .unwrap_or(NtpState::Disabled as u8)
+}
+
+/// Check if NTP clock is locked (frequency estimate within 1 ppm).
+///
+/// # Returns
+/// * 1 if locked, 0 if not
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_IsLocked() -> i32 {
+    get_ntp_stats()
+        .map(|s| if s.locked { 1 } else { 0 })
+        .unwrap_or(0)
+}
+
+/// Set the poll interval (can change while running).
+///
+/// Public pool servers ask clients not to poll more often than every 64
+/// seconds; a local server can be polled faster for a quicker lock.
+///
+/// # Arguments
+/// * `seconds` - Poll interval in seconds (1-1024, default 16)
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_SetPollInterval(seconds: u32) -> i32 {
+    match set_poll_interval(seconds) {
+        Ok(()) => BASS_NTP_OK,
+        Err(_) => BASS_NTP_ERROR_INVALID,
+    }
+}
+
+/// Get the poll interval in seconds.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_GetPollInterval() -> u32 {
+    get_poll_interval()
+}
+
+// ============================================================================
+// Timer C API Functions
+// ============================================================================
+
+/// Timer callback function type
+#[allow(non_camel_case_types)]
+pub type BASS_NTP_TimerProc = unsafe extern "C" fn(*mut c_void);
+
+/// Start the precision timer.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick (can be NULL)
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if interval is out of range
+/// * BASS_NTP_ERROR_ALREADY if timer is already running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerStart(
+    interval_ms: u32,
+    callback: Option<BASS_NTP_TimerProc>,
+    user: *mut c_void,
+) -> i32 {
+    match timer::start_timer(interval_ms, callback, user) {
+        0 => BASS_NTP_OK,
+        -1 => BASS_NTP_ERROR_INVALID,
+        -3 => BASS_NTP_ERROR_ALREADY,
+        _ => BASS_NTP_ERROR_SOCKET,
+    }
+}
+
+/// Stop the precision timer.
+///
+/// # Returns
+/// * BASS_NTP_OK always
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerStop() -> i32 {
+    timer::stop_timer();
+    BASS_NTP_OK
+}
+
+/// Check if timer is running.
+///
+/// # Returns
+/// * 1 if running, 0 if not
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerIsRunning() -> i32 {
+    if timer::is_timer_running() { 1 } else { 0 }
+}
+
+/// Set timer interval (can change while running).
+///
+/// # Arguments
+/// * `interval_ms` - New interval in milliseconds (1-1000)
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerSetInterval(interval_ms: u32) -> i32 {
+    match timer::set_interval(interval_ms) {
+        0 => BASS_NTP_OK,
+        _ => BASS_NTP_ERROR_INVALID,
+    }
+}
+
+/// Get current timer interval.
+///
+/// # Returns
+/// * Current interval in milliseconds
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerGetInterval() -> u32 {
+    timer::get_interval()
+}
+
+/// Enable or disable PLL frequency adjustment.
+///
+/// When enabled (default), the timer period is adjusted based on the NTP
+/// frequency estimate to track the server's clock rate.
+///
+/// # Arguments
+/// * `enabled` - 1 to enable, 0 to disable
+///
+/// # Returns
+/// * BASS_NTP_OK always
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerSetPLL(enabled: i32) -> i32 {
+    timer::set_pll_enabled(enabled != 0);
+    BASS_NTP_OK
+}
+
+/// Check if PLL adjustment is enabled.
+///
+/// # Returns
+/// * 1 if enabled, 0 if disabled
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerIsPLLEnabled() -> i32 {
+    if timer::is_pll_enabled() { 1 } else { 0 }
+}
+
+/// Create an independent precision timer.
+///
+/// Any number of timers can run at once, each with its own interval,
+/// callback and PLL setting (enabled by default). The timer starts ticking
+/// immediately.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick (can be NULL)
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle, or 0 if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_CreateTimer(
+    interval_ms: u32,
+    callback: Option<BASS_NTP_TimerProc>,
+    user: *mut c_void,
+) -> u32 {
+    timer::create_timer(interval_ms, callback, user).unwrap_or(0)
+}
+
+/// Stop a timer created with BASS_NTP_CreateTimer and free its handle.
+///
+/// Can be called from the timer's own callback.
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_DestroyTimer(handle: u32) -> i32 {
+    if timer::destroy_timer(handle) { BASS_NTP_OK } else { BASS_NTP_ERROR_INVALID }
+}
+
+/// Set a timer's interval (can change while running).
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if handle is unknown or interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerSetIntervalEx(handle: u32, interval_ms: u32) -> i32 {
+    if timer::set_timer_interval(handle, interval_ms) { BASS_NTP_OK } else { BASS_NTP_ERROR_INVALID }
+}
+
+/// Get a timer's interval.
+///
+/// # Returns
+/// * Interval in milliseconds, or 0 if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerGetIntervalEx(handle: u32) -> u32 {
+    timer::timer_interval(handle).unwrap_or(0)
+}
+
+/// Enable or disable PLL frequency adjustment of a timer.
+///
+/// # Returns
+/// * BASS_NTP_OK on success
+/// * BASS_NTP_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_NTP_TimerSetPLLEx(handle: u32, enabled: i32) -> i32 {
+    if timer::set_timer_pll(handle, enabled != 0) { BASS_NTP_OK } else { BASS_NTP_ERROR_INVALID }
+}
+
+// ============================================================================
+// Windows DLL Entry Point (left out when linked into a plugin)
+// ============================================================================
+
+#[cfg(all(windows, feature = "dll-main"))]
+const DLL_PROCESS_ATTACH: u32 = 1;
+#[cfg(all(windows, feature = "dll-main"))]
+const DLL_PROCESS_DETACH: u32 = 0;
+
+#[cfg(all(windows, feature = "dll-main"))]
+type BOOL = i32;
+#[cfg(all(windows, feature = "dll-main"))]
+type DWORD = u32;
+
+#[cfg(all(windows, feature = "dll-main"))]
+#[no_mangle]
+pub unsafe extern "system" fn DllMain(
+    _hinst: *mut c_void,
+    reason: DWORD,
+    _reserved: *mut c_void,
+) -> BOOL {
+    match reason {
+        DLL_PROCESS_ATTACH => {
+            // Initialize - nothing special needed
+        }
+        DLL_PROCESS_DETACH => {
+            // Cleanup - stop timer and clock client
+            timer::stop_timer();
+            force_stop_ntp_client();
+        }
+        _ => {}
+    }
+    1 // TRUE
+}
+
+// ============================================================================
+// Linux/macOS Cleanup
+// ============================================================================
+
+#[cfg(not(windows))]
+#[used]
+#[link_section = ".fini_array"]
+static FINI: extern "C" fn() = {
+    extern "C" fn fini() {
+        // Cleanup - stop timer and clock client
+        timer::stop_timer();
+        force_stop_ntp_client();
+    }
+    fini
+};
@@ -0,0 +1,263 @@
+//! Frequency estimation from SNTP samples.
+//!
+//! NTP samples are sparse (one per poll) and noisy (network jitter of
+//! hundreds of microseconds to milliseconds), so instead of a PI loop the
+//! servo fits a line through the recent (monotonic time, server - monotonic
+//! time) samples. The slope is the rate of the server's clock relative to
+//! the local monotonic clock, which is what audio pacing needs. The lock
+//! decision uses the standard error of that slope, so a quiet LAN server
+//! locks within minutes while a jittery WAN server takes longer.
+//!
+//! Samples with a round-trip delay well above the recent minimum are
+//! dropped (queueing makes their offsets unreliable), and a jump far away
+//! from the fitted line restarts the fit, keeping the last frequency.
+
+use std::collections::VecDeque;
+
+use crate::sntp::NtpSample;
+
+/// Samples used for the fit
+pub const DEFAULT_WINDOW: usize = 64;
+
+/// Samples needed before a frequency is reported
+const MIN_SAMPLES: usize = 4;
+
+/// Standard error of the frequency estimate below which the servo is locked (ppm)
+const LOCK_STD_ERROR_PPM: f64 = 1.0;
+
+/// Delays up to twice the recent minimum plus this slack are accepted (ns)
+const DELAY_SLACK_NS: i64 = 1_000_000;
+
+/// After this many consecutive high-delay samples the path is assumed to
+/// have changed and samples are accepted again
+const MAX_DELAY_REJECTS: u32 = 4;
+
+/// Distance from the fitted line treated as a server time step (ns)
+const STEP_THRESHOLD_NS: f64 = 100_000_000.0;
+
+/// Largest frequency reported (ppm)
+const MAX_FREQUENCY_PPM: f64 = 500.0;
+
+/// What happened to a sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleEvent {
+    Accepted,
+    /// Round-trip delay too high
+    RejectedDelay,
+    /// Server time stepped; the fit restarted
+    Step,
+}
+
+/// Line fit through the sample window
+#[derive(Debug, Clone, Copy, Default)]
+struct Fit {
+    /// Slope (ns per ns)
+    slope: f64,
+    /// Standard error of the slope (ns per ns)
+    slope_error: f64,
+    /// Mean x (ns) and mean y (ns, relative to the first sample)
+    mean_x: f64,
+    mean_y: f64,
+}
+
+/// NTP frequency servo
+#[derive(Debug)]
+pub struct NtpServo {
+    samples: VecDeque<NtpSample>,
+    window: usize,
+    delay_rejects: u32,
+    frequency_ppm: f64,
+    fit: Option<Fit>,
+}
+
+impl NtpServo {
+    pub fn new(window: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(window),
+            window: window.max(MIN_SAMPLES),
+            delay_rejects: 0,
+            frequency_ppm: 0.0,
+            fit: None,
+        }
+    }
+
+    /// Feed one sample
+    pub fn update(&mut self, sample: NtpSample) -> SampleEvent {
+        if let Some(min_delay) = self.samples.iter().map(|s| s.delay_ns).min() {
+            if sample.delay_ns > min_delay * 2 + DELAY_SLACK_NS && self.delay_rejects < MAX_DELAY_REJECTS {
+                self.delay_rejects += 1;
+                return SampleEvent::RejectedDelay;
+            }
+        }
+        self.delay_rejects = 0;
+
+        if let Some(expected) = self.expected(sample.mono_ns) {
+            if (sample.server_minus_mono_ns as f64 - expected).abs() > STEP_THRESHOLD_NS {
+                self.samples.clear();
+                self.fit = None;
+                self.samples.push_back(sample);
+                return SampleEvent::Step;
+            }
+        }
+
+        if self.samples.len() >= self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.refit();
+        SampleEvent::Accepted
+    }
+
+    /// Frequency adjustment in ppm: positive when the server clock runs
+    /// faster than the local monotonic clock (local should speed up)
+    pub fn frequency_ppm(&self) -> f64 {
+        self.frequency_ppm
+    }
+
+    /// Whether the frequency estimate is good enough to follow
+    pub fn is_locked(&self) -> bool {
+        self.fit
+            .map(|f| f.slope_error * 1e6 < LOCK_STD_ERROR_PPM)
+            .unwrap_or(false)
+    }
+
+    /// Standard error of the frequency estimate (ppm), once there is one
+    pub fn frequency_error_ppm(&self) -> Option<f64> {
+        self.fit.map(|f| f.slope_error * 1e6)
+    }
+
+    /// Samples in the fit
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Server - monotonic time the fit expects at `mono_ns`
+    fn expected(&self, mono_ns: i64) -> Option<f64> {
+        let fit = self.fit?;
+        let first = self.samples.front()?;
+        let x = (mono_ns - first.mono_ns) as f64;
+        Some(first.server_minus_mono_ns as f64 + fit.mean_y + fit.slope * (x - fit.mean_x))
+    }
+
+    fn refit(&mut self) {
+        let n = self.samples.len();
+        if n < MIN_SAMPLES {
+            return;
+        }
+        let first = self.samples[0];
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|s| {
+                (
+                    (s.mono_ns - first.mono_ns) as f64,
+                    (s.server_minus_mono_ns - first.server_minus_mono_ns) as f64,
+                )
+            })
+            .collect();
+
+        let nf = n as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / nf;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / nf;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        if sxx <= 0.0 {
+            return;
+        }
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let slope = sxy / sxx;
+        let residuals: f64 = points
+            .iter()
+            .map(|p| (p.1 - mean_y - slope * (p.0 - mean_x)).powi(2))
+            .sum();
+        let slope_error = (residuals / (nf - 2.0)).sqrt() / sxx.sqrt();
+
+        self.fit = Some(Fit {
+            slope,
+            slope_error,
+            mean_x,
+            mean_y,
+        });
+        self.frequency_ppm = (slope * 1e6).clamp(-MAX_FREQUENCY_PPM, MAX_FREQUENCY_PPM);
+    }
+}
+
+impl Default for NtpServo {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLL_NS: i64 = 16_000_000_000;
+
+    /// Sample at poll `i` from a server running `ppm` fast, with `noise_ns`
+    /// of alternating jitter and `delay_ns` round trip
+    fn sample(i: i64, ppm: f64, noise_ns: i64, delay_ns: i64) -> NtpSample {
+        let mono_ns = i * POLL_NS;
+        let jitter = if i % 2 == 0 { noise_ns } else { -noise_ns };
+        NtpSample {
+            mono_ns,
+            server_minus_mono_ns: 1_000_000_000 + (mono_ns as f64 * ppm / 1e6) as i64 + jitter,
+            wall_offset_ns: 0,
+            delay_ns,
+            stratum: 2,
+        }
+    }
+
+    #[test]
+    fn test_tracks_frequency() {
+        let mut servo = NtpServo::default();
+        for i in 0..3 {
+            servo.update(sample(i, 25.0, 0, 500_000));
+        }
+        assert!(!servo.is_locked());
+        assert_eq!(servo.frequency_ppm(), 0.0);
+
+        for i in 3..40 {
+            assert_eq!(servo.update(sample(i, 25.0, 200_000, 500_000)), SampleEvent::Accepted);
+        }
+        assert!((servo.frequency_ppm() - 25.0).abs() < 0.5, "{}", servo.frequency_ppm());
+        assert!(servo.is_locked());
+    }
+
+    #[test]
+    fn test_noisy_server_does_not_lock_early() {
+        let mut servo = NtpServo::default();
+        for i in 0..6 {
+            servo.update(sample(i, -10.0, 5_000_000, 500_000));
+        }
+        assert!(!servo.is_locked());
+    }
+
+    #[test]
+    fn test_rejects_high_delay() {
+        let mut servo = NtpServo::default();
+        for i in 0..10 {
+            servo.update(sample(i, 0.0, 0, 400_000));
+        }
+        assert_eq!(servo.update(sample(10, 0.0, 0, 30_000_000)), SampleEvent::RejectedDelay);
+        assert_eq!(servo.sample_count(), 10);
+
+        // A lasting path change is accepted after a few rejects
+        let events: Vec<_> = (11..17).map(|i| servo.update(sample(i, 0.0, 0, 30_000_000))).collect();
+        assert!(events.contains(&SampleEvent::Accepted));
+    }
+
+    #[test]
+    fn test_step_restarts_fit() {
+        let mut servo = NtpServo::default();
+        for i in 0..20 {
+            servo.update(sample(i, 12.0, 0, 500_000));
+        }
+        let mut stepped = sample(20, 12.0, 0, 500_000);
+        stepped.server_minus_mono_ns += 2_000_000_000;
+        assert_eq!(servo.update(stepped), SampleEvent::Step);
+        assert_eq!(servo.sample_count(), 1);
+        assert!(!servo.is_locked());
+        // The last frequency is kept while the fit rebuilds
+        assert!((servo.frequency_ppm() - 12.0).abs() < 0.5);
+    }
+}
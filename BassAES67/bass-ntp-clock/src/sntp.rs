@@ -0,0 +1,278 @@
+//! SNTP (RFC 4330) client exchange.
+//!
+//! One request/response gives the four classic timestamps: T1 (request
+//! sent, local), T2 (request received, server), T3 (response sent, server)
+//! and T4 (response received, local). Local times are taken twice, from
+//! the wall clock and from the monotonic clock that paces audio, so the
+//! same exchange yields the wall clock offset for display and a monotonic
+//! sample for rate estimation.
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Standard NTP port
+pub const NTP_PORT: u16 = 123;
+
+/// NTP packet size without extensions
+pub const NTP_PACKET_SIZE: usize = 48;
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+
+// First byte: leap indicator, version, mode
+const NTP_VERSION: u8 = 4;
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+/// Leap indicator value for an unsynchronized server
+const LEAP_ALARM: u8 = 3;
+
+/// Convert nanoseconds since the NTP epoch to a 64-bit NTP timestamp
+pub fn ns_to_ntp(ns: u64) -> u64 {
+    let secs = ns / 1_000_000_000;
+    let frac = ((ns % 1_000_000_000) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+/// Convert a 64-bit NTP timestamp to nanoseconds since the NTP epoch
+pub fn ntp_to_ns(ts: u64) -> u64 {
+    let secs = ts >> 32;
+    let frac = ts & 0xFFFF_FFFF;
+    secs * 1_000_000_000 + ((frac * 1_000_000_000) >> 32)
+}
+
+/// Wall clock time in nanoseconds since the NTP epoch
+pub fn wall_clock_ns() -> u64 {
+    let since_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    since_unix.as_nanos() as u64 + NTP_UNIX_OFFSET_SECS * 1_000_000_000
+}
+
+/// Server response fields the client uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NtpResponse {
+    pub leap: u8,
+    pub stratum: u8,
+    /// T1 echoed by the server
+    pub originate: u64,
+    /// T2
+    pub receive: u64,
+    /// T3
+    pub transmit: u64,
+}
+
+/// Build a client request carrying `transmit` (T1) as its transmit timestamp
+pub fn build_request(transmit: u64) -> [u8; NTP_PACKET_SIZE] {
+    let mut packet = [0u8; NTP_PACKET_SIZE];
+    packet[0] = (NTP_VERSION << 3) | MODE_CLIENT;
+    packet[40..48].copy_from_slice(&transmit.to_be_bytes());
+    packet
+}
+
+/// Parse a server response. Rejects other modes, kiss-o'-death packets
+/// (stratum 0) and unsynchronized servers.
+pub fn parse_response(data: &[u8]) -> Option<NtpResponse> {
+    if data.len() < NTP_PACKET_SIZE {
+        return None;
+    }
+    let leap = data[0] >> 6;
+    let mode = data[0] & 0x07;
+    let stratum = data[1];
+    if mode != MODE_SERVER || stratum == 0 || leap == LEAP_ALARM {
+        return None;
+    }
+    let ts = |at: usize| {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(&data[at..at + 8]);
+        u64::from_be_bytes(b)
+    };
+    Some(NtpResponse {
+        leap,
+        stratum,
+        originate: ts(24),
+        receive: ts(32),
+        transmit: ts(40),
+    })
+}
+
+/// Result of one exchange
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtpSample {
+    /// Midpoint of the exchange on the local monotonic clock (ns since `epoch`)
+    pub mono_ns: i64,
+    /// Server time minus local monotonic time (ns, arbitrary constant base)
+    pub server_minus_mono_ns: i64,
+    /// Local wall clock minus server time (ns)
+    pub wall_offset_ns: i64,
+    /// Round-trip delay minus server processing time (ns)
+    pub delay_ns: i64,
+    pub stratum: u8,
+}
+
+/// Compute a sample from the four timestamps (all ns; T1/T4 local wall
+/// clock, T2/T3 server) and the monotonic send/receive times
+pub fn make_sample(t1: u64, t2: u64, t3: u64, t4: u64, mono_send: i64, mono_recv: i64, stratum: u8) -> NtpSample {
+    let (t1, t2, t3, t4) = (t1 as i64, t2 as i64, t3 as i64, t4 as i64);
+    let server_mid = t2 + (t3 - t2) / 2;
+    let mono_mid = mono_send + (mono_recv - mono_send) / 2;
+    NtpSample {
+        mono_ns: mono_mid,
+        server_minus_mono_ns: server_mid - mono_mid,
+        // θ = ((T2 - T1) + (T3 - T4)) / 2 is server minus local
+        wall_offset_ns: -(((t2 - t1) + (t3 - t4)) / 2),
+        delay_ns: ((t4 - t1) - (t3 - t2)).max(0),
+        stratum,
+    }
+}
+
+/// Resolve "host[:port]" (port defaults to 123)
+pub fn resolve_server(server: &str) -> Result<SocketAddr, String> {
+    let with_port = if server.rsplit_once(':').is_some_and(|(_, p)| p.parse::<u16>().is_ok()) {
+        server.to_string()
+    } else {
+        format!("{}:{}", server, NTP_PORT)
+    };
+    with_port
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve NTP server {}: {}", server, e))?
+        .find(SocketAddr::is_ipv4)
+        .ok_or_else(|| format!("No IPv4 address for NTP server {}", server))
+}
+
+/// Run one exchange with `server` on `socket`. `epoch` is the reference
+/// for monotonic times in the returned sample.
+pub fn query(socket: &UdpSocket, server: SocketAddr, epoch: Instant, timeout: Duration) -> Result<NtpSample, String> {
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| format!("Failed to set timeout: {}", e))?;
+
+    let t1 = wall_clock_ns();
+    let request = build_request(ns_to_ntp(t1));
+    let mono_send = epoch.elapsed().as_nanos() as i64;
+    socket
+        .send_to(&request, server)
+        .map_err(|e| format!("Send failed: {}", e))?;
+
+    let mut buf = [0u8; 512];
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (len, from) = socket
+            .recv_from(&mut buf)
+            .map_err(|e| format!("No response: {}", e))?;
+        let mono_recv = epoch.elapsed().as_nanos() as i64;
+        let t4 = wall_clock_ns();
+
+        // Ignore stray packets and late answers to earlier requests
+        if from == server {
+            if let Some(resp) = parse_response(&buf[..len]) {
+                if resp.originate == ns_to_ntp(t1) {
+                    return Ok(make_sample(
+                        t1,
+                        ntp_to_ns(resp.receive),
+                        ntp_to_ns(resp.transmit),
+                        t4,
+                        mono_send,
+                        mono_recv,
+                        resp.stratum,
+                    ));
+                }
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err("No matching response".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Local NTP stand-in answering with its wall clock shifted by
+    /// `offset_ns` and running `ppm` fast
+    pub(crate) fn stand_in(offset_ns: i64, ppm: f64) -> (SocketAddr, Arc<AtomicBool>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(Duration::from_millis(50))).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let running = Arc::new(AtomicBool::new(true));
+        let running_clone = running.clone();
+        let start = wall_clock_ns() as i64;
+        thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            while running_clone.load(Ordering::SeqCst) {
+                let Ok((len, from)) = socket.recv_from(&mut buf) else {
+                    continue;
+                };
+                if len < NTP_PACKET_SIZE {
+                    continue;
+                }
+                let elapsed = wall_clock_ns() as i64 - start;
+                let now = start + elapsed + (elapsed as f64 * ppm / 1e6) as i64 + offset_ns;
+                let now = ns_to_ntp(now as u64);
+                let mut reply = [0u8; NTP_PACKET_SIZE];
+                reply[0] = (NTP_VERSION << 3) | MODE_SERVER;
+                reply[1] = 2;
+                reply[24..32].copy_from_slice(&buf[40..48]);
+                reply[32..40].copy_from_slice(&now.to_be_bytes());
+                reply[40..48].copy_from_slice(&now.to_be_bytes());
+                let _ = socket.send_to(&reply, from);
+            }
+        });
+        (addr, running)
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let ns = 3_900_000_000_123_456_789;
+        assert!(ntp_to_ns(ns_to_ntp(ns)).abs_diff(ns) <= 1);
+        assert_eq!(ns_to_ntp(1_500_000_000), (1 << 32) | 0x8000_0000);
+    }
+
+    #[test]
+    fn test_sample_math() {
+        // Server 5 ms ahead, 2 ms each way, 1 ms processing
+        let t1 = 1_000_000_000;
+        let t2 = t1 + 2_000_000 + 5_000_000;
+        let t3 = t2 + 1_000_000;
+        let t4 = t1 + 5_000_000;
+        let s = make_sample(t1, t2, t3, t4, 0, 5_000_000, 2);
+        assert_eq!(s.wall_offset_ns, -5_000_000);
+        assert_eq!(s.delay_ns, 4_000_000);
+    }
+
+    #[test]
+    fn test_rejects_bad_responses() {
+        let mut packet = [0u8; NTP_PACKET_SIZE];
+        packet[0] = (NTP_VERSION << 3) | MODE_SERVER;
+        packet[1] = 1;
+        assert!(parse_response(&packet).is_some());
+
+        packet[1] = 0; // kiss-o'-death
+        assert!(parse_response(&packet).is_none());
+        packet[1] = 1;
+        packet[0] |= LEAP_ALARM << 6;
+        assert!(parse_response(&packet).is_none());
+        assert!(parse_response(&build_request(0)).is_none());
+        assert!(parse_response(&packet[..40]).is_none());
+    }
+
+    #[test]
+    fn test_query_stand_in() {
+        let (server, running) = stand_in(-250_000_000, 0.0);
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sample = query(&socket, server, Instant::now(), Duration::from_secs(1)).unwrap();
+        running.store(false, Ordering::SeqCst);
+
+        // Local clock is 250 ms ahead of the stand-in
+        assert!((sample.wall_offset_ns - 250_000_000).abs() < 20_000_000, "{:?}", sample);
+        assert!(sample.delay_ns < 20_000_000);
+        assert_eq!(sample.stratum, 2);
+    }
+
+    #[test]
+    fn test_resolve_server() {
+        assert_eq!(resolve_server("127.0.0.1").unwrap().port(), NTP_PORT);
+        assert_eq!(resolve_server("127.0.0.1:1123").unwrap().port(), 1123);
+    }
+}
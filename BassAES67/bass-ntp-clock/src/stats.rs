@@ -0,0 +1,102 @@
+//! NTP clock statistics tracking and formatting.
+
+use std::sync::OnceLock;
+use parking_lot::Mutex;
+
+/// NTP client states (same numbering as bass-ptp and bass-livewire-clock)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum NtpState {
+    /// Not started
+    #[default]
+    Disabled = 0,
+    /// Waiting for the first server response
+    Listening = 1,
+    /// Responses received, estimating frequency
+    Uncalibrated = 2,
+    /// Frequency estimate available
+    Slave = 3,
+}
+
+impl NtpState {
+    /// Returns human-readable state name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NtpState::Disabled => "DISABLED",
+            NtpState::Listening => "LISTENING",
+            NtpState::Uncalibrated => "UNCALIBRATED",
+            NtpState::Slave => "SLAVE",
+        }
+    }
+}
+
+/// NTP clock statistics for display
+#[derive(Debug, Clone, Default)]
+pub struct NtpStats {
+    /// Current state
+    pub state: NtpState,
+    /// Server as configured ("host[:port]")
+    pub server: String,
+    /// Stratum reported by the server
+    pub stratum: u8,
+    /// Local wall clock minus server time in nanoseconds
+    pub offset_ns: i64,
+    /// Last round-trip delay in nanoseconds
+    pub delay_ns: i64,
+    /// Frequency adjustment in ppm (positive = server runs faster than the local clock)
+    pub frequency_ppm: f64,
+    /// Standard error of the frequency estimate in ppm
+    pub frequency_error_ppm: f64,
+    /// Samples in the frequency fit
+    pub samples: usize,
+    /// Responses received
+    pub responses: u64,
+    /// Requests that got no usable response
+    pub timeouts: u64,
+    /// Whether the frequency estimate is good enough to follow
+    pub locked: bool,
+}
+
+impl NtpStats {
+    /// Format statistics for display (similar format to bass-ptp)
+    pub fn format_display(&self) -> String {
+        match self.state {
+            NtpState::Disabled => "NTP: Disabled".to_string(),
+            NtpState::Listening => format!("NTP: Waiting for {}...", self.server),
+            NtpState::Uncalibrated => {
+                format!(
+                    "NTP: Uncalibrated - Server: {} (stratum {}), {} samples",
+                    self.server, self.stratum, self.samples
+                )
+            }
+            NtpState::Slave => {
+                let lock_indicator = if self.locked { " [LOCKED]" } else { " [UNLOCKED]" };
+                format!(
+                    "Slave to: NTP/{} (stratum {}), δ {:.3}ms, Freq: {:+.2}±{:.2}ppm{}",
+                    self.server,
+                    self.stratum,
+                    self.offset_ns as f64 / 1_000_000.0,
+                    self.frequency_ppm,
+                    self.frequency_error_ppm,
+                    lock_indicator
+                )
+            }
+        }
+    }
+}
+
+// Thread-safe statistics storage using static buffer
+static STATS_STRING: OnceLock<Mutex<String>> = OnceLock::new();
+
+/// Update the global stats string
+pub fn update_stats_string(stats: &NtpStats) {
+    let formatted = stats.format_display();
+    let mutex = STATS_STRING.get_or_init(|| Mutex::new(String::new()));
+    *mutex.lock() = formatted;
+}
+
+/// Get the current stats string
+pub fn get_stats_string() -> String {
+    let mutex = STATS_STRING.get_or_init(|| Mutex::new(String::from("NTP: Not initialized")));
+    mutex.lock().clone()
+}
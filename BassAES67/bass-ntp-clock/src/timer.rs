@@ -0,0 +1,380 @@
+//! High-precision timers with PLL adjustment for no-soundcard mode.
+//!
+//! Any number of timers can run at once, each on its own thread with its own
+//! interval, callback and PLL setting. Their period is adjusted based on
+//! the NTP frequency estimate.
+//!
+//! The single-timer API (`start_timer`, `set_interval`, ...) drives the
+//! default timer.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::client::{get_ntp_stats, is_ntp_running};
+
+/// Timer callback function type
+pub type TimerCallback = unsafe extern "C" fn(*mut c_void);
+
+/// Running timers by handle
+static TIMERS: OnceLock<Mutex<HashMap<u32, TimerHandle>>> = OnceLock::new();
+
+/// Next timer handle
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+/// Handle of the default timer (0 = not running)
+static DEFAULT_TIMER: AtomicU32 = AtomicU32::new(0);
+
+/// Default timer configuration, kept while it is stopped
+static TIMER_INTERVAL_MS: AtomicU32 = AtomicU32::new(20);
+static TIMER_PLL_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Settings a timer thread reads before every tick
+struct TimerSettings {
+    interval_ms: AtomicU32,
+    pll_enabled: AtomicBool,
+}
+
+/// Handle to a running timer
+struct TimerHandle {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    settings: Arc<TimerSettings>,
+}
+
+fn timers() -> MutexGuard<'static, HashMap<u32, TimerHandle>> {
+    TIMERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+}
+
+fn valid_interval(interval_ms: u32) -> bool {
+    interval_ms > 0 && interval_ms <= 1000
+}
+
+/// Start a timer.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle (non-zero) on success, None if the interval is out of range
+pub fn create_timer(
+    interval_ms: u32,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) -> Option<u32> {
+    if !valid_interval(interval_ms) {
+        return None;
+    }
+
+    let settings = Arc::new(TimerSettings {
+        interval_ms: AtomicU32::new(interval_ms),
+        pll_enabled: AtomicBool::new(true),
+    });
+    let running = Arc::new(AtomicBool::new(true));
+
+    let running_clone = running.clone();
+    let settings_clone = settings.clone();
+    let user_ptr = user as usize; // Convert to usize for thread safety
+
+    let thread = thread::spawn(move || {
+        timer_thread(running_clone, settings_clone, callback, user_ptr as *mut c_void);
+    });
+
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    timers().insert(handle, TimerHandle {
+        running,
+        thread: Some(thread),
+        settings,
+    });
+    Some(handle)
+}
+
+/// Stop a timer and free its handle.
+///
+/// Waits for the timer thread to finish, unless called from the timer's own
+/// callback.
+///
+/// # Returns
+/// * false if the handle is unknown
+pub fn destroy_timer(handle: u32) -> bool {
+    let timer = timers().remove(&handle);
+    match timer {
+        Some(mut timer) => {
+            timer.running.store(false, Ordering::SeqCst);
+            if let Some(thread) = timer.thread.take() {
+                if thread.thread().id() != thread::current().id() {
+                    let _ = thread.join();
+                }
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Run `f` on the settings of timer `handle`. None if the handle is unknown.
+fn with_settings<T>(handle: u32, f: impl FnOnce(&TimerSettings) -> T) -> Option<T> {
+    timers().get(&handle).map(|timer| f(&timer.settings))
+}
+
+/// Set the interval of timer `handle` (can change while running).
+///
+/// # Returns
+/// * false if the handle is unknown or the interval is out of range
+pub fn set_timer_interval(handle: u32, interval_ms: u32) -> bool {
+    valid_interval(interval_ms)
+        && with_settings(handle, |s| s.interval_ms.store(interval_ms, Ordering::SeqCst)).is_some()
+}
+
+/// Interval of timer `handle` in milliseconds
+pub fn timer_interval(handle: u32) -> Option<u32> {
+    with_settings(handle, |s| s.interval_ms.load(Ordering::SeqCst))
+}
+
+/// Enable/disable PLL adjustment of timer `handle`
+pub fn set_timer_pll(handle: u32, enabled: bool) -> bool {
+    with_settings(handle, |s| s.pll_enabled.store(enabled, Ordering::SeqCst)).is_some()
+}
+
+/// Whether PLL adjustment of timer `handle` is enabled
+pub fn timer_pll(handle: u32) -> Option<bool> {
+    with_settings(handle, |s| s.pll_enabled.load(Ordering::SeqCst))
+}
+
+// ============================================================================
+// Default timer
+// ============================================================================
+
+/// Start the default timer.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * 0 on success, non-zero on error
+pub fn start_timer(
+    interval_ms: u32,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) -> i32 {
+    // Validate interval
+    if !valid_interval(interval_ms) {
+        return -1;
+    }
+
+    // Check if already running
+    if DEFAULT_TIMER.load(Ordering::SeqCst) != 0 {
+        return -3;
+    }
+
+    // Store interval
+    TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+
+    let handle = match create_timer(interval_ms, callback, user) {
+        Some(h) => h,
+        None => return -1,
+    };
+    set_timer_pll(handle, TIMER_PLL_ENABLED.load(Ordering::SeqCst));
+    if DEFAULT_TIMER.compare_exchange(0, handle, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        destroy_timer(handle);
+        return -3;
+    }
+
+    0
+}
+
+/// Stop the default timer
+pub fn stop_timer() -> i32 {
+    let handle = DEFAULT_TIMER.swap(0, Ordering::SeqCst);
+    if handle != 0 {
+        destroy_timer(handle);
+    }
+    0
+}
+
+/// Check if the default timer is running
+pub fn is_timer_running() -> bool {
+    DEFAULT_TIMER.load(Ordering::SeqCst) != 0
+}
+
+/// Set the default timer's interval (can change while running)
+pub fn set_interval(interval_ms: u32) -> i32 {
+    if !valid_interval(interval_ms) {
+        return -1;
+    }
+    TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+    set_timer_interval(DEFAULT_TIMER.load(Ordering::SeqCst), interval_ms);
+    0
+}
+
+/// Get the default timer's interval
+pub fn get_interval() -> u32 {
+    TIMER_INTERVAL_MS.load(Ordering::SeqCst)
+}
+
+/// Enable/disable PLL adjustment of the default timer
+pub fn set_pll_enabled(enabled: bool) {
+    TIMER_PLL_ENABLED.store(enabled, Ordering::SeqCst);
+    set_timer_pll(DEFAULT_TIMER.load(Ordering::SeqCst), enabled);
+}
+
+/// Check if PLL adjustment of the default timer is enabled
+pub fn is_pll_enabled() -> bool {
+    TIMER_PLL_ENABLED.load(Ordering::SeqCst)
+}
+
+// ============================================================================
+// Timer threads
+// ============================================================================
+
+/// Timer thread implementation using multimedia timer (Windows)
+#[cfg(windows)]
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+    use windows_sys::Win32::System::Threading::{
+        CreateWaitableTimerW, SetWaitableTimer, WaitForSingleObject, INFINITE,
+    };
+
+    // Create waitable timer (standard, not high-resolution for compatibility)
+    let timer_handle: HANDLE = unsafe { CreateWaitableTimerW(std::ptr::null(), 1, std::ptr::null()) };
+
+    if timer_handle.is_null() || timer_handle == 0 as HANDLE {
+        // Fallback to sleep-based timer
+        return timer_thread_fallback(running, settings, callback, user);
+    }
+
+    while running.load(Ordering::SeqCst) {
+        // Calculate adjusted interval
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
+
+        // Set timer (negative value = relative time in 100ns units)
+        let due_time: i64 = -(wait_100ns as i64);
+        let result = unsafe {
+            SetWaitableTimer(timer_handle, &due_time, 0, None, std::ptr::null(), 0)
+        };
+
+        if result == 0 {
+            // Timer set failed
+            break;
+        }
+
+        // Wait for timer
+        let wait_result = unsafe { WaitForSingleObject(timer_handle, INFINITE) };
+        if wait_result != WAIT_OBJECT_0 {
+            break;
+        }
+
+        // Call user callback
+        if let Some(cb) = callback {
+            unsafe { cb(user) };
+        }
+    }
+
+    // Cleanup
+    unsafe { CloseHandle(timer_handle) };
+}
+
+/// Fallback timer using Sleep (less precise)
+#[cfg(windows)]
+fn timer_thread_fallback(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
+    while running.load(Ordering::SeqCst) {
+        // Calculate adjusted interval and convert to ms
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
+        let wait_ms = (wait_100ns / 10_000) as u32;
+
+        // Sleep
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms as u64));
+
+        // Call user callback
+        if let Some(cb) = callback {
+            unsafe { cb(user) };
+        }
+    }
+}
+
+/// Non-Windows fallback
+#[cfg(not(windows))]
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
+    while running.load(Ordering::SeqCst) {
+        // Calculate adjusted interval
+        let wait_100ns = calculate_adjusted_interval(
+            settings.interval_ms.load(Ordering::SeqCst),
+            settings.pll_enabled.load(Ordering::SeqCst),
+        );
+        let wait_us = wait_100ns / 10;
+
+        // Sleep
+        std::thread::sleep(std::time::Duration::from_micros(wait_us));
+
+        // Call user callback
+        if let Some(cb) = callback {
+            unsafe { cb(user) };
+        }
+    }
+}
+
+/// Calculate PLL-adjusted interval in 100ns units
+fn calculate_adjusted_interval(base_interval_ms: u32, pll_enabled: bool) -> u64 {
+    let base_100ns = (base_interval_ms as u64) * 10_000; // ms to 100ns
+
+    if !pll_enabled {
+        return base_100ns;
+    }
+
+    // Only apply PLL when the NTP client is running and locked
+    if !is_ntp_running() {
+        return base_100ns;
+    }
+
+    let stats = match get_ntp_stats() {
+        Some(s) => s,
+        None => return base_100ns,
+    };
+
+    if !stats.locked {
+        return base_100ns;
+    }
+
+    // Apply frequency correction
+    // frequency_ppm > 0 means the server runs faster than the local clock, so shorten interval
+    // frequency_ppm < 0 means the server runs slower than the local clock, so lengthen interval
+    let freq_ppm = stats.frequency_ppm;
+    let adjustment_factor = 1.0 - (freq_ppm / 1_000_000.0);
+    let adjusted = (base_100ns as f64) * adjustment_factor;
+
+    // Clamp to reasonable range (±10% of base)
+    let min = (base_100ns as f64) * 0.9;
+    let max = (base_100ns as f64) * 1.1;
+    adjusted.clamp(min, max) as u64
+}
@@ -1,25 +1,43 @@
 //! PTP (IEEE 1588v2) client for AES67 clock synchronization.
 //!
 //! Provides a lightweight PTP slave implementation that tracks offset
-//! and frequency from a network grandmaster clock.
+//! and frequency from a network grandmaster clock. Optionally the client can
+//! also act as a software grandmaster (see `master`), taking part in BMCA.
 
+use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+
+use crate::config::{PtpClientConfig, PtpDelayMechanism, PtpProfile, PtpTransportMode};
+use crate::history::{HistorySample, PtpHistory};
+use crate::master::{self, ClockDataset, PtpMasterConfig, ANNOUNCE_RECEIPT_TIMEOUT};
 use crate::messages::*;
-use crate::servo::PtpServo;
+use crate::pdelay::{self, PeerDelay, PTP_PDELAY_MULTICAST_ADDR};
+use crate::servo::{PtpServo, ServoConfig, ServoEvent};
+use crate::smpte::SmpteSyncMetadata;
 use crate::stats::{PtpState, PtpStats};
+use crate::timemap::{PtpTime, PtpTimeMap};
+use crate::timestamping::{self, EventSocket, Timestamp};
+use crate::unicast::UnicastNegotiator;
 use crate::{platform, stats};
 
 /// PTP multicast address
-const PTP_MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
+pub(crate) const PTP_MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
 /// PTP event port (Sync, Delay_Req)
 const PTP_EVENT_PORT: u16 = 319;
 /// PTP general port (Announce, Follow_Up, Delay_Resp)
-const PTP_GENERAL_PORT: u16 = 320;
+pub(crate) const PTP_GENERAL_PORT: u16 = 320;
+
+/// Rate at which the time error accumulated in holdover is slewed out (50 ppm)
+const HOLDOVER_SLEW_PPB: f64 = 50_000.0;
+
+/// Largest holdover time error slewed out; larger errors are stepped
+const HOLDOVER_MAX_SLEW_NS: i64 = 10_000_000;
 
 /// Global PTP client instance
 static PTP_CLIENT: OnceLock<Mutex<Option<PtpClientHandle>>> = OnceLock::new();
@@ -27,22 +45,65 @@ static PTP_CLIENT: OnceLock<Mutex<Option<PtpClientHandle>>> = OnceLock::new();
 /// Reference count for Start/Stop calls
 static REFERENCE_COUNT: AtomicU32 = AtomicU32::new(0);
 
+/// Additional handle-based client instances (handle -> client)
+static INSTANCES: OnceLock<Mutex<HashMap<u32, PtpClientHandle>>> = OnceLock::new();
+
+/// Next instance handle (0 is reserved for the default instance)
+static NEXT_INSTANCE: AtomicU32 = AtomicU32::new(1);
+
 /// Handle to a running PTP client
 struct PtpClientHandle {
     running: Arc<AtomicBool>,
     stats: Arc<Mutex<PtpStats>>,
+    state: Arc<Mutex<PtpSharedState>>,
+    history: Arc<Mutex<PtpHistory>>,
+    /// Configuration the client was started with
+    config: PtpClientConfig,
     event_thread: Option<JoinHandle<()>>,
     general_thread: Option<JoinHandle<()>>,
+    master_thread: Option<JoinHandle<()>>,
+    unicast_thread: Option<JoinHandle<()>>,
+    pdelay_thread: Option<JoinHandle<()>>,
 }
 
 /// Shared state between PTP threads
 struct PtpSharedState {
     /// Current grandmaster info
     grandmaster: Option<PortIdentity>,
+    /// IP address the current grandmaster sends from
+    grandmaster_addr: Option<Ipv4Addr>,
+    /// BMCA dataset of the current grandmaster
+    grandmaster_dataset: Option<ClockDataset>,
+    /// When the last Announce from the current grandmaster arrived
+    last_announce: Option<Instant>,
+    /// Announce receipt timeout for the current grandmaster
+    announce_timeout: Duration,
+    /// Master role configuration (None = slave only)
+    master_config: Option<PtpMasterConfig>,
+    /// Message transport (multicast, hybrid, unicast)
+    transport: PtpTransportMode,
+    /// Path delay measurement (E2E or P2P)
+    delay_mechanism: PtpDelayMechanism,
+    /// Peer delay measurement (P2P only)
+    peer_delay: PeerDelay,
+    /// Pdelay_Req sequence counter
+    pdelay_req_seq: u16,
+    /// PTP profile
+    profile: PtpProfile,
+    /// Send a Delay_Req every this many Follow_Ups (E2E)
+    delay_req_every: u64,
+    /// Unicast negotiation state (unicast mode only)
+    unicast: Option<UnicastNegotiator>,
+    /// Signaling sequence counter
+    signaling_seq: u16,
+    /// When the client was started (for the initial listening period)
+    started_at: Instant,
     /// PI servo for offset/frequency
     servo: PtpServo,
     /// Current statistics
     stats: PtpStats,
+    /// Offset/delay history (shared with the handle for export)
+    history: Arc<Mutex<PtpHistory>>,
     /// Pending Sync data (waiting for Follow_Up)
     pending_sync: Option<PendingSyncData>,
     /// Pending Delay_Req data (waiting for Delay_Resp)
@@ -51,19 +112,33 @@ struct PtpSharedState {
     local_port: PortIdentity,
     /// Delay request sequence counter
     delay_req_seq: u16,
-    /// Socket for sending Delay_Req
-    event_socket: Option<UdpSocket>,
+    /// Socket for sending Delay_Req (and Sync as master), with its timestamping
+    event_socket: Option<Arc<EventSocket>>,
+    /// Socket for sending Announce, Follow_Up and Delay_Resp as master
+    general_socket: Option<UdpSocket>,
+    /// Master Sync sequence counter
+    sync_seq: u16,
+    /// Master Announce sequence counter
+    announce_seq: u16,
     /// Initial offset baseline (for relative tracking)
     /// PTP grandmasters may use TAI while local clock uses UTC
     initial_offset_ns: Option<i64>,
     /// Last (t2 - t1) measurement for path delay calculation
     last_sync_diff_ns: i64,
+    /// When the last Follow_Up from the grandmaster was processed
+    last_follow_up: Option<Instant>,
+    /// When holdover started (Holdover state only)
+    holdover_since: Option<Instant>,
+    /// Default instance (drives the global stats string)
+    is_default: bool,
 }
 
 /// Data from a Sync message waiting for Follow_Up
 struct PendingSyncData {
     sequence_id: u16,
     receive_time_ns: i64,
+    /// Sync correction field (residence time added by transparent clocks)
+    correction_ns: i64,
 }
 
 /// Data from a Delay_Req waiting for Delay_Resp
@@ -74,6 +149,26 @@ struct PendingDelayData {
 
 /// Start the global PTP client (with reference counting)
 pub fn start_ptp_client(interface: Ipv4Addr, domain: u8) -> Result<(), String> {
+    start_ptp_client_ex(PtpClientConfig::new(interface, domain))
+}
+
+/// Start the global PTP client with the master role enabled.
+///
+/// The client becomes grandmaster when no better clock is announced on the
+/// domain and steps down (becoming a slave) when a better one appears.
+/// If the client is already running, only the reference count is incremented
+/// and the existing role is kept.
+pub fn start_ptp_master(interface: Ipv4Addr, domain: u8, config: PtpMasterConfig) -> Result<(), String> {
+    let mut client_config = PtpClientConfig::new(interface, domain);
+    client_config.master = Some(config);
+    start_ptp_client_ex(client_config)
+}
+
+/// Start the global PTP client with a full configuration (transport mode,
+/// unicast master list, master role). Reference-counted like `start_ptp_client`.
+pub fn start_ptp_client_ex(config: PtpClientConfig) -> Result<(), String> {
+    config.validate()?;
+
     // Increment reference count
     let prev_count = REFERENCE_COUNT.fetch_add(1, Ordering::SeqCst);
 
@@ -92,41 +187,129 @@ pub fn start_ptp_client(interface: Ipv4Addr, domain: u8) -> Result<(), String> {
         return Ok(());
     }
 
+    let others = instance_configs();
+    let result = check_instance_conflict(&config, &others).and_then(|_| spawn_client(&config, true));
+
+    match result {
+        Ok(handle) => {
+            *client_guard = Some(handle);
+            Ok(())
+        }
+        Err(e) => {
+            // Nothing was started: undo the reference taken above
+            REFERENCE_COUNT.fetch_sub(1, Ordering::SeqCst);
+            Err(e)
+        }
+    }
+}
+
+/// Create the sockets and threads of a client instance.
+///
+/// `is_default` selects whether this instance drives the global stats string.
+fn spawn_client(config: &PtpClientConfig, is_default: bool) -> Result<PtpClientHandle, String> {
+    let interface = config.interface;
+    let domain = config.domain;
+    let master_config = config.master;
+
     // Create shared state
     let stats = Arc::new(Mutex::new(PtpStats {
         state: PtpState::Listening,
         domain,
+        transport: config.transport,
+        delay_mechanism: config.delay_mechanism,
+        profile: config.profile,
+        servo: config.servo.kind.as_str(),
         ..Default::default()
     }));
+
+    let unicast = if config.transport == PtpTransportMode::Unicast {
+        Some(UnicastNegotiator::new(
+            &config.unicast_masters,
+            config.unicast_duration_s,
+            config.log_sync_interval,
+            config.log_announce_interval,
+            config.log_delay_req_interval,
+        ))
+    } else {
+        None
+    };
+    let history = Arc::new(Mutex::new(PtpHistory::new(config.history_size)));
     let state = Arc::new(Mutex::new(PtpSharedState {
         grandmaster: None,
-        servo: PtpServo::new(),
+        grandmaster_addr: None,
+        grandmaster_dataset: None,
+        last_announce: None,
+        announce_timeout: Duration::from_secs(6),
+        master_config,
+        transport: config.transport,
+        delay_mechanism: config.delay_mechanism,
+        peer_delay: PeerDelay::new(),
+        pdelay_req_seq: 0,
+        profile: config.profile,
+        // 2^(logMinDelayReqInterval - logSyncInterval) Syncs per Delay_Req
+        delay_req_every: 1u64 << (config.log_delay_req_interval as i32 - config.log_sync_interval as i32).clamp(0, 6),
+        unicast,
+        signaling_seq: 0,
+        started_at: Instant::now(),
+        servo: PtpServo::with_config(config.servo),
         stats: PtpStats {
             state: PtpState::Listening,
             domain,
+            transport: config.transport,
+            delay_mechanism: config.delay_mechanism,
+            profile: config.profile,
+            servo: config.servo.kind.as_str(),
             ..Default::default()
         },
+        history: Arc::clone(&history),
         pending_sync: None,
         pending_delay: None,
         local_port: generate_local_port_identity(),
         delay_req_seq: 0,
         event_socket: None,
+        general_socket: None,
+        sync_seq: 0,
+        announce_seq: 0,
         initial_offset_ns: None,
         last_sync_diff_ns: 0,
+        last_follow_up: None,
+        holdover_since: None,
+        is_default,
     }));
 
     let running = Arc::new(AtomicBool::new(true));
-
-    // Create event socket (port 319)
-    let event_socket = create_ptp_socket(interface, PTP_EVENT_PORT)?;
+    let peer_delay = config.delay_mechanism == PtpDelayMechanism::P2P;
+
+    // Create event socket (port 319), with kernel timestamps for Sync receive
+    // and Delay_Req transmit (falls back to user space)
+    let event_socket = EventSocket::new(
+        create_ptp_socket(interface, PTP_EVENT_PORT, peer_delay)?,
+        interface,
+        timestamping::get_timestamp_mode(),
+    );
+    let timestamp_source = event_socket.source;
+    if let Ok(mut s) = state.lock() {
+        s.stats.rx_timestamp_source = timestamp_source;
+        s.stats.tx_timestamp_source = timestamp_source;
+    }
+    if let Ok(mut ext_stats) = stats.lock() {
+        ext_stats.rx_timestamp_source = timestamp_source;
+        ext_stats.tx_timestamp_source = timestamp_source;
+    }
 
     // Store socket in shared state for sending Delay_Req
+    let event_rx_socket = event_socket.socket.try_clone().map_err(|e| e.to_string())?;
     if let Ok(mut s) = state.lock() {
-        s.event_socket = Some(event_socket.try_clone().map_err(|e| e.to_string())?);
+        s.event_socket = Some(Arc::new(event_socket));
     }
 
     // Create general socket (port 320)
-    let general_socket = create_ptp_socket(interface, PTP_GENERAL_PORT)?;
+    let general_socket = create_ptp_socket(interface, PTP_GENERAL_PORT, peer_delay)?;
+
+    // Store socket in shared state for sending Announce/Follow_Up/Delay_Resp
+    if let Ok(mut s) = state.lock() {
+        s.general_socket = Some(general_socket.try_clone().map_err(|e| e.to_string())?);
+    }
 
     // Start event thread (Sync messages)
     let event_running = running.clone();
@@ -134,7 +317,7 @@ pub fn start_ptp_client(interface: Ipv4Addr, domain: u8) -> Result<(), String> {
     let event_stats = stats.clone();
     let event_domain = domain;
     let event_thread = thread::spawn(move || {
-        run_event_thread(event_socket, event_running, event_state, event_stats, event_domain);
+        run_event_thread(event_rx_socket, event_running, event_state, event_stats, event_domain);
     });
 
     // Start general thread (Announce, Follow_Up, Delay_Resp)
@@ -146,14 +329,54 @@ pub fn start_ptp_client(interface: Ipv4Addr, domain: u8) -> Result<(), String> {
         run_general_thread(general_socket, general_running, general_state, general_stats, general_domain);
     });
 
-    *client_guard = Some(PtpClientHandle {
+    // Start master thread (Announce, Sync, Follow_Up) if the master role is enabled
+    let master_thread = if master_config.is_some() {
+        let master_running = running.clone();
+        let master_state = state.clone();
+        let master_stats = stats.clone();
+        Some(thread::spawn(move || {
+            run_master_thread(master_running, master_state, master_stats);
+        }))
+    } else {
+        None
+    };
+
+    // Start unicast negotiation thread (unicast mode only)
+    let unicast_thread = if config.transport == PtpTransportMode::Unicast {
+        let unicast_running = running.clone();
+        let unicast_state = state.clone();
+        let unicast_stats = stats.clone();
+        Some(thread::spawn(move || {
+            run_unicast_thread(unicast_running, unicast_state, unicast_stats);
+        }))
+    } else {
+        None
+    };
+
+    // Start peer delay thread (P2P delay mechanism only)
+    let pdelay_thread = if peer_delay {
+        let pdelay_running = running.clone();
+        let pdelay_state = state.clone();
+        let pdelay_interval = master::log_interval_to_duration(config.log_delay_req_interval);
+        Some(thread::spawn(move || {
+            run_pdelay_thread(pdelay_running, pdelay_state, pdelay_interval);
+        }))
+    } else {
+        None
+    };
+
+    Ok(PtpClientHandle {
         running,
         stats,
+        state,
+        history,
+        config: config.clone(),
         event_thread: Some(event_thread),
         general_thread: Some(general_thread),
-    });
-
-    Ok(())
+        master_thread,
+        unicast_thread,
+        pdelay_thread,
+    })
 }
 
 /// Stop the global PTP client (with reference counting)
@@ -191,13 +414,27 @@ pub fn stop_ptp_client() {
         Err(_) => return,
     };
 
-    if let Some(mut handle) = client_guard.take() {
-        if let Some(thread) = handle.event_thread.take() {
-            let _ = thread.join();
-        }
-        if let Some(thread) = handle.general_thread.take() {
-            let _ = thread.join();
-        }
+    if let Some(handle) = client_guard.take() {
+        join_client(handle);
+    }
+}
+
+/// Join the threads of a stopped client
+fn join_client(mut handle: PtpClientHandle) {
+    if let Some(thread) = handle.event_thread.take() {
+        let _ = thread.join();
+    }
+    if let Some(thread) = handle.general_thread.take() {
+        let _ = thread.join();
+    }
+    if let Some(thread) = handle.master_thread.take() {
+        let _ = thread.join();
+    }
+    if let Some(thread) = handle.unicast_thread.take() {
+        let _ = thread.join();
+    }
+    if let Some(thread) = handle.pdelay_thread.take() {
+        let _ = thread.join();
     }
 }
 
@@ -224,11 +461,182 @@ pub fn force_stop_ptp_client() {
 
     // Take the handle but don't join - let threads terminate on their own
     // The threads will see running=false and exit after their socket timeout
-    if let Some(mut handle) = client_guard.take() {
-        // Drop thread handles without joining - they'll terminate naturally
-        drop(handle.event_thread.take());
-        drop(handle.general_thread.take());
+    // Dropping the JoinHandles detaches the threads
+    drop(client_guard.take());
+    drop(client_guard);
+
+    // Additional instances go down with the library as well
+    if let Some(instances) = INSTANCES.get() {
+        if let Ok(mut instances) = instances.lock() {
+            for (_, handle) in instances.drain() {
+                handle.running.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// Create an additional PTP client instance.
+///
+/// Instances are independent of the reference-counted default client: each
+/// has its own interface, domain, servo and statistics. Returns the instance
+/// handle (never 0, which addresses the default client in the `*_instance`
+/// getters).
+///
+/// Instances share the PTP ports, so multicast instances are told apart by
+/// interface and domain. Hybrid and unicast transports receive unicast
+/// datagrams on the shared ports and therefore cannot run next to any
+/// other instance.
+pub fn create_ptp_instance(config: PtpClientConfig) -> Result<u32, String> {
+    config.validate()?;
+
+    // Lock order: default client before the instance table (see start_ptp_client_ex)
+    let mut others: Vec<PtpClientConfig> = PTP_CLIENT
+        .get()
+        .and_then(|m| m.lock().ok())
+        .and_then(|g| g.as_ref().map(|h| h.config.clone()))
+        .into_iter()
+        .collect();
+
+    let instances_mutex = INSTANCES.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut instances = instances_mutex
+        .lock()
+        .map_err(|_| "Failed to lock PTP instance table")?;
+    others.extend(instances.values().map(|h| h.config.clone()));
+    check_instance_conflict(&config, &others)?;
+
+    let handle = spawn_client(&config, false)?;
+    let id = NEXT_INSTANCE.fetch_add(1, Ordering::SeqCst);
+    instances.insert(id, handle);
+    Ok(id)
+}
+
+/// Stop and remove a PTP client instance. Returns false for unknown handles.
+pub fn destroy_ptp_instance(id: u32) -> bool {
+    let handle = match INSTANCES.get().and_then(|m| m.lock().ok()).and_then(|mut g| g.remove(&id)) {
+        Some(h) => h,
+        None => return false,
+    };
+
+    handle.running.store(false, Ordering::SeqCst);
+    join_client(handle);
+    true
+}
+
+/// Get statistics of an instance (0 = default client)
+pub fn get_instance_stats(id: u32) -> Option<PtpStats> {
+    if id == 0 {
+        return get_ptp_stats();
+    }
+    let instances = INSTANCES.get()?.lock().ok()?;
+    let handle = instances.get(&id)?;
+    let stats = handle.stats.lock().ok()?;
+    Some(stats.clone())
+}
+
+/// Change the servo configuration of a running client (0 = default client).
+///
+/// Returns an error for unknown handles or an invalid configuration.
+pub fn set_servo_config(id: u32, servo: ServoConfig) -> Result<(), String> {
+    servo.validate()?;
+
+    let apply = |handle: &mut PtpClientHandle| {
+        handle.config.servo = servo;
+        if let Ok(mut s) = handle.state.lock() {
+            s.servo.set_config(servo);
+            s.stats.servo = s.servo.algorithm_name();
+            if let Ok(mut ext_stats) = handle.stats.lock() {
+                ext_stats.servo = s.stats.servo;
+            }
+        }
+    };
+
+    let found = if id == 0 {
+        PTP_CLIENT
+            .get()
+            .and_then(|m| m.lock().ok())
+            .and_then(|mut g| g.as_mut().map(apply))
+    } else {
+        INSTANCES
+            .get()
+            .and_then(|m| m.lock().ok())
+            .and_then(|mut g| g.get_mut(&id).map(apply))
+    };
+    found.ok_or_else(|| "PTP client not running".to_string())
+}
+
+/// Servo configuration of a running client (0 = default client)
+pub fn get_servo_config(id: u32) -> Option<ServoConfig> {
+    if id == 0 {
+        let client = PTP_CLIENT.get()?.lock().ok()?;
+        return client.as_ref().map(|h| h.config.servo);
+    }
+    let instances = INSTANCES.get()?.lock().ok()?;
+    instances.get(&id).map(|h| h.config.servo)
+}
+
+/// Copy of the offset/delay history of a client (0 = default client)
+pub fn get_instance_history(id: u32) -> Option<PtpHistory> {
+    let copy = |handle: &PtpClientHandle| handle.history.lock().ok().map(|h| h.clone());
+    if id == 0 {
+        let client = PTP_CLIENT.get()?.lock().ok()?;
+        return client.as_ref().and_then(copy);
+    }
+    let instances = INSTANCES.get()?.lock().ok()?;
+    instances.get(&id).and_then(copy)
+}
+
+/// Discard the offset/delay history of a client (0 = default client).
+/// Returns false for unknown handles.
+pub fn clear_instance_history(id: u32) -> bool {
+    let clear = |handle: &PtpClientHandle| {
+        if let Ok(mut h) = handle.history.lock() {
+            h.clear();
+        }
+    };
+    if id == 0 {
+        return PTP_CLIENT
+            .get()
+            .and_then(|m| m.lock().ok())
+            .and_then(|g| g.as_ref().map(clear))
+            .is_some();
+    }
+    INSTANCES
+        .get()
+        .and_then(|m| m.lock().ok())
+        .and_then(|g| g.get(&id).map(clear))
+        .is_some()
+}
+
+/// Configurations of all running handle-based instances
+fn instance_configs() -> Vec<PtpClientConfig> {
+    INSTANCES
+        .get()
+        .and_then(|m| m.lock().ok())
+        .map(|g| g.values().map(|h| h.config.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Check that `config` can run next to the clients in `others`
+fn check_instance_conflict(config: &PtpClientConfig, others: &[PtpClientConfig]) -> Result<(), String> {
+    for other in others {
+        if other.interface == config.interface && other.domain == config.domain {
+            return Err(format!(
+                "A PTP client for domain {} on {} is already running",
+                config.domain, config.interface
+            ));
+        }
+        if config.transport != PtpTransportMode::Multicast || other.transport != PtpTransportMode::Multicast {
+            return Err(format!(
+                "{} transport cannot share the PTP ports with another instance",
+                if config.transport != PtpTransportMode::Multicast {
+                    config.transport.as_str()
+                } else {
+                    other.transport.as_str()
+                }
+            ));
+        }
     }
+    Ok(())
 }
 
 /// Get current PTP statistics
@@ -263,17 +671,79 @@ pub fn get_frequency_ppm() -> f64 {
     get_ptp_stats().map(|s| s.frequency_ppm).unwrap_or(0.0)
 }
 
-/// Create a PTP multicast socket
-fn create_ptp_socket(interface: Ipv4Addr, port: u16) -> Result<UdpSocket, String> {
+/// Current PTP time of the default client (None until synchronized or master)
+pub fn ptp_now() -> Option<PtpTime> {
+    local_to_ptp_time(0, platform::get_monotonic_ns())
+}
+
+/// Convert a local timestamp (`platform::get_monotonic_ns` clock) to PTP time.
+///
+/// `id` selects the client instance (0 = default client). Between Syncs the
+/// result is extrapolated with the servo's frequency estimate.
+pub fn local_to_ptp_time(id: u32, local_ns: i64) -> Option<PtpTime> {
+    let map = get_instance_stats(id)?.time_map?;
+    Some(map.local_to_ptp(local_ns))
+}
+
+/// Create a PTP multicast socket (optionally also joined to the peer delay group)
+pub(crate) fn create_ptp_socket(interface: Ipv4Addr, port: u16, peer_delay: bool) -> Result<UdpSocket, String> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+        .map_err(|e| format!("Failed to create PTP socket: {}", e))?;
+
+    // Allow several PTP instances on one host (e.g. master + slave over loopback)
+    socket
+        .set_reuse_address(true)
+        .map_err(|e| format!("Failed to set SO_REUSEADDR: {}", e))?;
+    #[cfg(unix)]
+    socket
+        .set_reuse_port(true)
+        .map_err(|e| format!("Failed to set SO_REUSEPORT: {}", e))?;
+
     // Bind to INADDR_ANY with the port
     let socket_addr = SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port);
-    let socket = UdpSocket::bind(socket_addr)
+    socket
+        .bind(&SockAddr::from(socket_addr))
         .map_err(|e| format!("Failed to bind PTP socket on port {}: {}", port, e))?;
 
     // Join PTP multicast group
     socket
         .join_multicast_v4(&PTP_MULTICAST_ADDR, &interface)
         .map_err(|e| format!("Failed to join PTP multicast group: {}", e))?;
+    if peer_delay {
+        socket
+            .join_multicast_v4(&PTP_PDELAY_MULTICAST_ADDR, &interface)
+            .map_err(|e| format!("Failed to join PTP peer delay multicast group: {}", e))?;
+    }
+
+    // Linux delivers a group to every socket bound to the port once any socket
+    // joined it; restrict delivery to this socket's own membership (interface)
+    // so instances on different interfaces don't see each other's traffic.
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+        let off: libc::c_int = 0;
+        // SAFETY: valid socket and option buffer of the size passed
+        unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_MULTICAST_ALL,
+                &off as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+        }
+    }
+
+    // Send multicast out of the selected interface, and loop it back so
+    // other instances on the same host can see it
+    socket
+        .set_multicast_if_v4(&interface)
+        .map_err(|e| format!("Failed to set multicast interface: {}", e))?;
+    socket
+        .set_multicast_loop_v4(true)
+        .map_err(|e| format!("Failed to enable multicast loopback: {}", e))?;
+
+    let socket: UdpSocket = socket.into();
 
     // Set read timeout for clean shutdown
     socket
@@ -284,7 +754,7 @@ fn create_ptp_socket(interface: Ipv4Addr, port: u16) -> Result<UdpSocket, String
 }
 
 /// Generate a local port identity based on system info
-fn generate_local_port_identity() -> PortIdentity {
+pub(crate) fn generate_local_port_identity() -> PortIdentity {
     // Use a simple hash of current time as a pseudo-random ID
     // In production, this should use MAC address
     let now = std::time::SystemTime::now()
@@ -312,10 +782,8 @@ fn run_event_thread(
     let mut buf = [0u8; 1024];
 
     while running.load(Ordering::SeqCst) {
-        match socket.recv(&mut buf) {
-            Ok(len) => {
-                let receive_time = platform::get_timestamp_ns();
-
+        match timestamping::recv_with_timestamp(&socket, &mut buf) {
+            Ok((len, from, receive_time)) => {
                 if let Some(header) = PtpHeader::parse(&buf[..len]) {
                     // Filter by domain
                     if header.domain_number != domain {
@@ -328,6 +796,21 @@ fn run_event_thread(
                                 handle_sync(&state, &stats, &sync, receive_time);
                             }
                         }
+                        PtpMessageType::DelayReq => {
+                            if let Some(delay_req) = DelayReqMessage::parse(&buf[..len]) {
+                                handle_delay_req(&state, &stats, &delay_req, receive_time, from);
+                            }
+                        }
+                        PtpMessageType::PDelayReq => {
+                            if let Some(pdelay_req) = PdelayReqMessage::parse(&buf[..len]) {
+                                handle_pdelay_req(&state, &stats, &pdelay_req, receive_time);
+                            }
+                        }
+                        PtpMessageType::PDelayResp => {
+                            if let Some(pdelay_resp) = PdelayRespMessage::parse(&buf[..len]) {
+                                handle_pdelay_resp(&state, &stats, &pdelay_resp, receive_time);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -350,8 +833,15 @@ fn run_general_thread(
     let mut buf = [0u8; 1024];
 
     while running.load(Ordering::SeqCst) {
-        match socket.recv(&mut buf) {
-            Ok(len) => {
+        check_holdover(&state, &stats);
+
+        match socket.recv_from(&mut buf) {
+            Ok((len, from)) => {
+                let from_ip = match from {
+                    std::net::SocketAddr::V4(addr) => Some(*addr.ip()),
+                    _ => None,
+                };
+
                 if let Some(header) = PtpHeader::parse(&buf[..len]) {
                     // Filter by domain
                     if header.domain_number != domain {
@@ -361,7 +851,7 @@ fn run_general_thread(
                     match header.message_type {
                         PtpMessageType::Announce => {
                             if let Some(announce) = AnnounceMessage::parse(&buf[..len]) {
-                                handle_announce(&state, &stats, &announce);
+                                handle_announce(&state, &stats, &announce, from_ip);
                             }
                         }
                         PtpMessageType::FollowUp => {
@@ -374,6 +864,18 @@ fn run_general_thread(
                                 handle_delay_resp(&state, &stats, &delay_resp);
                             }
                         }
+                        PtpMessageType::PDelayRespFollowUp => {
+                            if let Some(follow_up) = PdelayRespFollowUpMessage::parse(&buf[..len]) {
+                                handle_pdelay_resp_follow_up(&state, &stats, &follow_up);
+                            }
+                        }
+                        PtpMessageType::Signaling => {
+                            if let (Some(signaling), Some(ip)) =
+                                (SignalingMessage::parse(&buf[..len]), from_ip)
+                            {
+                                handle_signaling(&state, &stats, &signaling, ip);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -390,37 +892,178 @@ fn handle_announce(
     state: &Arc<Mutex<PtpSharedState>>,
     stats: &Arc<Mutex<PtpStats>>,
     announce: &AnnounceMessage,
+    from: Option<Ipv4Addr>,
 ) {
     let mut s = match state.lock() {
         Ok(s) => s,
         Err(_) => return,
     };
 
-    // Update grandmaster (simple: just accept first/any announce)
-    // A full implementation would run BMCA here
     let gm_port = announce.header.source_port_identity;
 
-    s.grandmaster = Some(gm_port);
+    // Ignore our own Announce looped back while acting as master
+    if gm_port.clock_identity == s.local_port.clock_identity {
+        return;
+    }
+
+    // In unicast mode only the configured masters take part in BMCA
+    if let Some(ref unicast) = s.unicast {
+        match from {
+            Some(ip) if unicast.is_master(ip) => {}
+            _ => return,
+        }
+    }
+
+    let foreign = ClockDataset::from_announce(announce);
+    let now = Instant::now();
+
+    // BMCA: only switch to another grandmaster if it is better than the current
+    // one (or the current one has gone silent), and - in the master role - only
+    // if it is also better than ourselves.
+    if s.grandmaster != Some(gm_port) {
+        if s.grandmaster.is_some() && !grandmaster_expired(&s, now) {
+            if let Some(current) = s.grandmaster_dataset {
+                if !foreign.is_better_than(&current) {
+                    return;
+                }
+            }
+        }
+
+        if let Some(config) = s.master_config {
+            if !foreign.is_better_than(&config.dataset(s.local_port.clock_identity)) {
+                return;
+            }
+        }
+
+        select_grandmaster(&mut s, gm_port);
+    }
+
+    s.grandmaster_addr = from;
+    s.grandmaster_dataset = Some(foreign);
+    s.last_announce = Some(now);
+    s.announce_timeout =
+        master::log_interval_to_duration(announce.header.log_message_interval) * ANNOUNCE_RECEIPT_TIMEOUT;
+
     s.stats.grandmaster_id = announce.grandmaster_identity;
     s.stats.grandmaster_port = gm_port.port_number;
     s.stats.clock_class = announce.grandmaster_clock_quality.clock_class;
+    s.stats.priority1 = announce.grandmaster_priority1;
+    s.stats.priority2 = announce.grandmaster_priority2;
     s.stats.announce_count += 1;
 
+    // UTC offset / leap second state and SMPTE synchronization metadata
+    let now_ptp = master::local_to_ptp_ns(platform::get_timestamp_ns(), announce.current_utc_offset);
+    s.stats.time_properties.update(announce, now_ptp);
+    s.stats.smpte = SmpteSyncMetadata::find(&announce.tlvs);
+
     if s.stats.state == PtpState::Listening {
         s.stats.state = PtpState::Uncalibrated;
     }
 
     // Update external stats
+    publish_stats(&s, stats);
+}
+
+/// Check if the current grandmaster has stopped announcing
+fn grandmaster_expired(s: &PtpSharedState, now: Instant) -> bool {
+    match s.last_announce {
+        Some(t) => now.duration_since(t) > s.announce_timeout,
+        None => true,
+    }
+}
+
+/// Switch to a new grandmaster and restart offset tracking against it.
+///
+/// In holdover the old time mapping and frequency stay in use until the new
+/// grandmaster's first Sync, which then slews in rather than steps.
+fn select_grandmaster(s: &mut PtpSharedState, gm_port: PortIdentity) {
+    s.grandmaster = Some(gm_port);
+    s.pending_sync = None;
+    s.pending_delay = None;
+    s.initial_offset_ns = None;
+    s.servo.reset();
+    s.stats.offset_ns = 0;
+    s.stats.locked = false;
+    s.stats.outlier_count = 0;
+    s.stats.step_count = 0;
+    s.stats.sync_count = 0;
+    if s.stats.state != PtpState::Holdover {
+        s.stats.frequency_ppm = 0.0;
+        s.stats.time_map = None;
+        s.stats.state = PtpState::Uncalibrated;
+    }
+}
+
+/// Enter holdover when the grandmaster goes quiet, keep its statistics up
+/// to date, and give up once the holdover timeout has passed
+fn check_holdover(state: &Arc<Mutex<PtpSharedState>>, stats: &Arc<Mutex<PtpStats>>) {
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let now = Instant::now();
+
+    match s.stats.state {
+        PtpState::Slave => {
+            let timeout = s.announce_timeout;
+            let sync_lost = s.last_follow_up.is_none_or(|t| now.duration_since(t) > timeout);
+            if !sync_lost && !grandmaster_expired(&s, now) {
+                return;
+            }
+            // Keep running on the servo's filtered frequency estimate
+            let frequency_ppb = s.servo.mean_frequency_ppb();
+            s.holdover_since = Some(now);
+            s.stats.state = PtpState::Holdover;
+            s.stats.locked = false;
+            s.stats.frequency_ppm = frequency_ppb / 1_000.0;
+            if let Some(map) = s.stats.time_map {
+                s.stats.time_map = Some(map.rebase(platform::get_monotonic_ns(), frequency_ppb));
+            }
+        }
+        PtpState::Holdover => {}
+        _ => return,
+    }
+
+    let duration = now.duration_since(s.holdover_since.unwrap_or(now));
+    let timeout_s = s.servo.config().holdover_timeout_s;
+    if timeout_s > 0 && duration.as_secs() >= timeout_s as u64 {
+        // Holdover exhausted: forget the grandmaster and listen again
+        s.grandmaster = None;
+        s.grandmaster_addr = None;
+        s.grandmaster_dataset = None;
+        s.last_announce = None;
+        s.pending_sync = None;
+        s.pending_delay = None;
+        s.initial_offset_ns = None;
+        s.holdover_since = None;
+        s.servo.reset();
+        s.stats.state = PtpState::Listening;
+        s.stats.offset_ns = 0;
+        s.stats.frequency_ppm = 0.0;
+        s.stats.time_map = None;
+        s.stats.holdover_duration_ms = 0;
+        s.stats.holdover_error_ns = 0;
+    } else {
+        s.stats.holdover_duration_ms = duration.as_millis() as u64;
+        s.stats.holdover_error_ns = s.servo.holdover_error_ns(duration.as_secs_f64());
+    }
+
+    publish_stats(&s, stats);
+}
+
+/// Copy shared statistics to the external stats and stats string
+fn publish_stats(s: &PtpSharedState, stats: &Arc<Mutex<PtpStats>>) {
     if let Ok(mut ext_stats) = stats.lock() {
-        ext_stats.grandmaster_id = s.stats.grandmaster_id;
-        ext_stats.grandmaster_port = s.stats.grandmaster_port;
-        ext_stats.clock_class = s.stats.clock_class;
-        ext_stats.announce_count = s.stats.announce_count;
-        ext_stats.state = s.stats.state;
+        *ext_stats = s.stats.clone();
     }
+    update_stats_string(s);
+}
 
-    // Update stats string
-    stats::update_stats_string(&s.stats);
+/// Update the global stats string (default instance only)
+fn update_stats_string(s: &PtpSharedState) {
+    if s.is_default {
+        stats::update_stats_string(&s.stats);
+    }
 }
 
 /// Handle Sync message - record receive time
@@ -428,7 +1071,7 @@ fn handle_sync(
     state: &Arc<Mutex<PtpSharedState>>,
     stats: &Arc<Mutex<PtpStats>>,
     sync: &SyncMessage,
-    receive_time_ns: i64,
+    receive_time: Timestamp,
 ) {
     let mut s = match state.lock() {
         Ok(s) => s,
@@ -447,13 +1090,16 @@ fn handle_sync(
     // Store pending sync data for Follow_Up
     s.pending_sync = Some(PendingSyncData {
         sequence_id: sync.header.sequence_id,
-        receive_time_ns,
+        receive_time_ns: receive_time.ns,
+        correction_ns: sync.header.correction_ns(),
     });
 
     s.stats.sync_count += 1;
+    s.stats.rx_timestamp_source = receive_time.source;
 
     if let Ok(mut ext_stats) = stats.lock() {
         ext_stats.sync_count = s.stats.sync_count;
+        ext_stats.rx_timestamp_source = s.stats.rx_timestamp_source;
     }
 }
 
@@ -487,15 +1133,21 @@ fn handle_follow_up(
     };
 
     s.stats.follow_up_count += 1;
+    s.last_follow_up = Some(Instant::now());
 
-    // t1 = master send time (from Follow_Up)
-    let t1_ns = follow_up.precise_origin_timestamp.to_ns();
+    // t1 = master send time (from Follow_Up), on the UTC timescale of the local clock
+    let t1_ns = s
+        .stats
+        .time_properties
+        .ptp_to_utc_ns(follow_up.precise_origin_timestamp.to_ns());
     // t2 = slave receive time (recorded when Sync arrived)
     let t2_ns = pending.receive_time_ns;
 
-    // Calculate raw (t2 - t1) for path delay calculation
+    // Calculate raw (t2 - t1) for path delay calculation, less the residence
+    // (and, on P2P networks, upstream link delay) reported by transparent clocks
     // This is: forward_delay + clock_offset
-    let raw_sync_diff_ns = t2_ns - t1_ns;
+    let correction_ns = pending.correction_ns + follow_up.header.correction_ns();
+    let raw_sync_diff_ns = t2_ns - t1_ns - correction_ns;
     s.last_sync_diff_ns = raw_sync_diff_ns;
 
     // For software PTP (no system clock discipline), we track RELATIVE offset.
@@ -511,6 +1163,13 @@ fn handle_follow_up(
     //
     // This is similar to how Omnia and other software PTP displays work.
 
+    // Back from holdover: the old samples say nothing about the phase now,
+    // so restart tracking from here at the holdover frequency
+    if s.stats.state == PtpState::Holdover {
+        s.servo.relock();
+        s.initial_offset_ns = None;
+    }
+
     // Use first measurement as baseline (includes epoch diff + path delay)
     if s.initial_offset_ns.is_none() {
         s.initial_offset_ns = Some(raw_sync_diff_ns);
@@ -523,22 +1182,73 @@ fn handle_follow_up(
     // Path delay for display (from Delay_Req/Resp RTT measurement)
     let path_delay = s.stats.mean_path_delay_ns;
 
-    // Update servo with the relative offset
-    s.servo.update(offset_ns, path_delay);
+    // Update servo with the relative offset, timed by the Sync's arrival
+    match s.servo.update_at(t2_ns, offset_ns, path_delay) {
+        ServoEvent::Accepted => {}
+        ServoEvent::Outlier => {
+            // A delayed Sync: keep the last offset and time mapping
+            s.stats.outlier_count = s.servo.outlier_count();
+            publish_stats(&s, stats);
+            return;
+        }
+        ServoEvent::Step => {
+            // The grandmaster's time stepped: track changes from the new time
+            s.initial_offset_ns = Some(raw_sync_diff_ns);
+            s.stats.step_count = s.servo.step_count();
+        }
+    }
+
+    // The grandmaster's clock read t1 + path delay + corrections when the Sync arrived
+    // The map runs on the monotonic clock
+    let ptp_at_t2 = follow_up.precise_origin_timestamp.to_ns() + correction_ns + path_delay;
+    let t2_mono_ns = platform::wall_to_monotonic_ns(t2_ns);
+    let mut time_map = PtpTimeMap::new(t2_mono_ns, ptp_at_t2, s.servo.frequency_ppb(), s.stats.time_properties);
+
+    if let Some(previous) = s.stats.time_map {
+        time_map = if s.stats.state == PtpState::Holdover {
+            // Back from holdover: slew out the accumulated error instead of stepping
+            let error_ns = time_map.local_to_ptp_ns(t2_mono_ns) - previous.local_to_ptp_ns(t2_mono_ns);
+            if error_ns.abs() <= HOLDOVER_MAX_SLEW_NS {
+                time_map.with_slew(error_ns, HOLDOVER_SLEW_PPB)
+            } else {
+                time_map
+            }
+        } else {
+            time_map.continue_slew(&previous)
+        };
+    }
+
+    if s.stats.state == PtpState::Holdover {
+        s.stats.state = PtpState::Slave;
+        s.holdover_since = None;
+        s.stats.holdover_duration_ms = 0;
+        s.stats.holdover_error_ns = 0;
+    }
 
-    // Update stats
+    // Update stats. The time map's slew only keeps PTP time continuous; it
+    // stays out of the frequency so resamplers following it don't jump.
     s.stats.offset_ns = s.servo.offset_ns();
     s.stats.frequency_ppm = s.servo.frequency_ppm();
     s.stats.locked = s.servo.is_locked();
+    s.stats.time_map = Some(time_map);
+
+    if let Ok(mut history) = s.history.lock() {
+        history.push(HistorySample {
+            local_ns: t2_ns,
+            offset_ns: s.stats.offset_ns,
+            path_delay_ns: path_delay,
+            frequency_ppb: s.stats.frequency_ppm * 1_000.0,
+        });
+    }
 
     if s.stats.state == PtpState::Uncalibrated && s.stats.sync_count > 5 {
         s.stats.state = PtpState::Slave;
     }
 
-    // Send Delay_Req periodically (every 8 syncs)
-    if s.stats.sync_count % 8 == 0 {
-        send_delay_req(&mut s);
-    }
+    // Send Delay_Req periodically (every 8 syncs with the AES67 rates); P2P
+    // measures in its own thread
+    let delay_req_due =
+        s.delay_mechanism == PtpDelayMechanism::E2E && s.stats.sync_count % s.delay_req_every == 0;
 
     // Update external stats
     if let Ok(mut ext_stats) = stats.lock() {
@@ -548,41 +1258,83 @@ fn handle_follow_up(
         ext_stats.locked = s.stats.locked;
         ext_stats.state = s.stats.state;
         ext_stats.follow_up_count = s.stats.follow_up_count;
+        ext_stats.time_map = s.stats.time_map;
+        ext_stats.step_count = s.stats.step_count;
+        ext_stats.holdover_duration_ms = s.stats.holdover_duration_ms;
+        ext_stats.holdover_error_ns = s.stats.holdover_error_ns;
     }
 
     // Update stats string
-    stats::update_stats_string(&s.stats);
+    update_stats_string(&s);
+
+    if delay_req_due {
+        drop(s);
+        send_delay_req(state);
+    }
 }
 
-/// Send a Delay_Req message
-fn send_delay_req(state: &mut PtpSharedState) {
-    let socket = match &state.event_socket {
-        Some(s) => s,
-        None => return,
-    };
+/// Send an event message and return its send time, the kernel's transmit
+/// timestamp when one is reported. Waiting for that timestamp takes up to a
+/// few milliseconds, so callers must not hold the state lock.
+fn send_event(socket: &EventSocket, bytes: &[u8], dest: SocketAddrV4) -> Option<Timestamp> {
+    let user_send_time = Timestamp::user_now();
+    socket.socket.send_to(bytes, dest).ok()?;
+    Some(socket.tx_timestamp().unwrap_or(user_send_time))
+}
 
-    state.delay_req_seq = state.delay_req_seq.wrapping_add(1);
+/// Send a Delay_Req message
+fn send_delay_req(state: &Arc<Mutex<PtpSharedState>>) {
+    let (socket, msg, dest) = {
+        let mut s = match state.lock() {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+        let socket = match &s.event_socket {
+            Some(sock) => Arc::clone(sock),
+            None => return,
+        };
 
-    let msg = DelayReqMessage::new(
-        state.local_port,
-        state.delay_req_seq,
-        state.stats.domain,
-    );
+        s.delay_req_seq = s.delay_req_seq.wrapping_add(1);
+        let mut msg = DelayReqMessage::new(s.local_port, s.delay_req_seq, s.stats.domain);
 
-    let send_time = platform::get_timestamp_ns();
-    let dest = SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_EVENT_PORT);
+        // Hybrid/unicast: Delay_Req goes straight to the grandmaster
+        let dest = match s.grandmaster_addr {
+            Some(gm_ip) if s.transport.unicast_delay_req() => {
+                msg.header.flags |= FLAG_UNICAST;
+                SocketAddrV4::new(gm_ip, PTP_EVENT_PORT)
+            }
+            _ => SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_EVENT_PORT),
+        };
 
-    if socket.send_to(&msg.to_bytes(), dest).is_ok() {
-        state.pending_delay = Some(PendingDelayData {
-            sequence_id: state.delay_req_seq,
-            send_time_ns: send_time,
+        // Pending from now on with a user-space send time, so a Delay_Resp
+        // that beats the transmit timestamp still finds it
+        s.pending_delay = Some(PendingDelayData {
+            sequence_id: s.delay_req_seq,
+            send_time_ns: Timestamp::user_now().ns,
         });
+        (socket, msg, dest)
+    };
+
+    let send_time = match send_event(&socket, &msg.to_bytes(), dest) {
+        Some(t) => t,
+        None => return,
+    };
+
+    if let Ok(mut s) = state.lock() {
+        s.stats.tx_timestamp_source = send_time.source;
+        if let Some(pending) = s.pending_delay.as_mut().filter(|p| p.sequence_id == msg.header.sequence_id) {
+            pending.send_time_ns = send_time.ns;
+        }
     }
 }
 
-/// Handle Delay_Resp message - update delay count
-/// Note: We can't calculate true path delay because master timestamps (TAI)
-/// and local timestamps (Unix) use different epochs.
+/// Handle Delay_Resp message - update mean path delay
+///
+/// Uses the standard two-way formula
+///   delay = ((t2 - t1) + (t4 - t3)) / 2
+/// The master timestamps (t1, t4) are converted to UTC with the same offset,
+/// so any remaining epoch difference appears with opposite signs in the two
+/// terms and cancels out.
 fn handle_delay_resp(
     state: &Arc<Mutex<PtpSharedState>>,
     stats: &Arc<Mutex<PtpStats>>,
@@ -609,18 +1361,496 @@ fn handle_delay_resp(
 
     s.stats.delay_resp_count += 1;
 
-    // For software PTP, we use a fixed path delay estimate.
-    // On a typical switched LAN, path delay is 100-500µs.
-    // This is only used for display - the offset tracking doesn't depend on it.
-    // We measure round-trip time from local timestamps only:
-    let now_ns = platform::get_timestamp_ns();
-    let rtt_ns = now_ns - pending.send_time_ns;
-    // Estimate one-way delay as half of RTT
-    s.stats.mean_path_delay_ns = rtt_ns / 2;
+    // t3 = Delay_Req send time (kernel TX timestamp when available)
+    // t4 = master receive time (from Delay_Resp)
+    // Delay_Resp carries the Delay_Req residence time of transparent clocks
+    let t4_ns = s.stats.time_properties.ptp_to_utc_ns(delay_resp.receive_timestamp.to_ns());
+    let t4_minus_t3 = t4_ns - pending.send_time_ns - delay_resp.header.correction_ns();
+    let path_delay_ns = (s.last_sync_diff_ns + t4_minus_t3) / 2;
+
+    // Ignore nonsense before the first Sync/Follow_Up pair has been measured
+    if s.last_sync_diff_ns != 0 && path_delay_ns.abs() < 1_000_000_000 {
+        match s.servo.filter_path_delay(path_delay_ns.max(0)) {
+            Some(delay_ns) => s.stats.mean_path_delay_ns = delay_ns,
+            None => s.stats.outlier_count = s.servo.outlier_count(),
+        }
+    }
 
     // Update external stats
     if let Ok(mut ext_stats) = stats.lock() {
         ext_stats.mean_path_delay_ns = s.stats.mean_path_delay_ns;
         ext_stats.delay_resp_count = s.stats.delay_resp_count;
+        ext_stats.outlier_count = s.stats.outlier_count;
+        ext_stats.tx_timestamp_source = s.stats.tx_timestamp_source;
+    }
+}
+
+/// Handle Delay_Req message - answer with Delay_Resp while acting as master
+fn handle_delay_req(
+    state: &Arc<Mutex<PtpSharedState>>,
+    stats: &Arc<Mutex<PtpStats>>,
+    delay_req: &DelayReqMessage,
+    receive_time: Timestamp,
+    from: Option<SocketAddrV4>,
+) {
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    // P2P ports don't answer Delay_Req
+    if s.stats.state != PtpState::Master || s.delay_mechanism != PtpDelayMechanism::E2E {
+        return;
+    }
+
+    let utc_offset = match s.master_config {
+        Some(c) => c.current_utc_offset,
+        None => return,
+    };
+
+    let mut resp = master::build_delay_resp(
+        s.local_port,
+        delay_req,
+        master::local_to_ptp_ns(receive_time.ns, utc_offset),
+    );
+
+    let socket = match &s.general_socket {
+        Some(sock) => sock,
+        None => return,
+    };
+
+    // Answer unicast Delay_Req (hybrid slaves) with a unicast Delay_Resp
+    let dest = match from {
+        Some(addr) if (delay_req.header.flags & FLAG_UNICAST) != 0 => {
+            resp.header.flags |= FLAG_UNICAST;
+            SocketAddrV4::new(*addr.ip(), PTP_GENERAL_PORT)
+        }
+        _ => SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_GENERAL_PORT),
+    };
+    if socket.send_to(&resp.to_bytes(), dest).is_ok() {
+        s.stats.delay_req_count += 1;
+        if let Ok(mut ext_stats) = stats.lock() {
+            ext_stats.delay_req_count = s.stats.delay_req_count;
+        }
+    }
+}
+
+/// Peer delay thread - sends Pdelay_Req to the link neighbour every interval
+fn run_pdelay_thread(running: Arc<AtomicBool>, state: Arc<Mutex<PtpSharedState>>, interval: Duration) {
+    let mut next_request = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(20));
+
+        let now = Instant::now();
+        if now < next_request {
+            continue;
+        }
+        next_request = advance_deadline(next_request, interval, now);
+
+        send_pdelay_req(&state);
+    }
+}
+
+/// Send a Pdelay_Req to the peer delay multicast group
+fn send_pdelay_req(state: &Arc<Mutex<PtpSharedState>>) {
+    let (socket, msg) = {
+        let mut s = match state.lock() {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+        let socket = match &s.event_socket {
+            Some(sock) => Arc::clone(sock),
+            None => return,
+        };
+
+        s.pdelay_req_seq = s.pdelay_req_seq.wrapping_add(1);
+        let seq = s.pdelay_req_seq;
+        let msg = PdelayReqMessage::new(s.local_port, seq, s.stats.domain, 0);
+
+        // Pending from now on, like a Delay_Req
+        s.peer_delay.request_sent(seq, Timestamp::user_now().ns);
+        (socket, msg)
+    };
+
+    let dest = SocketAddrV4::new(PTP_PDELAY_MULTICAST_ADDR, PTP_EVENT_PORT);
+    let send_time = match send_event(&socket, &msg.to_bytes(), dest) {
+        Some(t) => t,
+        None => return,
+    };
+
+    if let Ok(mut s) = state.lock() {
+        s.stats.tx_timestamp_source = send_time.source;
+        s.peer_delay.request_timestamped(msg.header.sequence_id, send_time.ns);
+    }
+}
+
+/// Handle Pdelay_Req - every P2P port answers its link neighbour (two-step)
+fn handle_pdelay_req(
+    state: &Arc<Mutex<PtpSharedState>>,
+    stats: &Arc<Mutex<PtpStats>>,
+    request: &PdelayReqMessage,
+    receive_time: Timestamp,
+) {
+    let (socket, resp) = {
+        let s = match state.lock() {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        // Our own request looped back
+        if s.delay_mechanism != PtpDelayMechanism::P2P || request.header.source_port_identity == s.local_port {
+            return;
+        }
+
+        let socket = match &s.event_socket {
+            Some(sock) => Arc::clone(sock),
+            None => return,
+        };
+        (socket, pdelay::build_pdelay_resp(s.local_port, request, receive_time.ns))
+    };
+
+    let dest = SocketAddrV4::new(PTP_PDELAY_MULTICAST_ADDR, PTP_EVENT_PORT);
+    let send_time = match send_event(&socket, &resp.to_bytes(), dest) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let general_socket = match &s.general_socket {
+        Some(sock) => sock,
+        None => return,
+    };
+
+    let follow_up = pdelay::build_pdelay_resp_follow_up(s.local_port, request, send_time.ns);
+    if general_socket
+        .send_to(
+            &follow_up.to_bytes(),
+            SocketAddrV4::new(PTP_PDELAY_MULTICAST_ADDR, PTP_GENERAL_PORT),
+        )
+        .is_ok()
+    {
+        s.stats.pdelay_req_count += 1;
+        if let Ok(mut ext_stats) = stats.lock() {
+            ext_stats.pdelay_req_count = s.stats.pdelay_req_count;
+        }
+    }
+}
+
+/// Handle Pdelay_Resp - completes the measurement for one-step responders
+fn handle_pdelay_resp(
+    state: &Arc<Mutex<PtpSharedState>>,
+    stats: &Arc<Mutex<PtpStats>>,
+    resp: &PdelayRespMessage,
+    receive_time: Timestamp,
+) {
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let local_port = s.local_port;
+    let delay = s.peer_delay.handle_resp(local_port, resp, receive_time.ns);
+    if resp.requesting_port_identity == local_port {
+        s.stats.pdelay_resp_count += 1;
+    }
+    update_link_delay(&mut s, stats, delay);
+}
+
+/// Handle Pdelay_Resp_Follow_Up - completes the measurement for two-step responders
+fn handle_pdelay_resp_follow_up(
+    state: &Arc<Mutex<PtpSharedState>>,
+    stats: &Arc<Mutex<PtpStats>>,
+    follow_up: &PdelayRespFollowUpMessage,
+) {
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let local_port = s.local_port;
+    let delay = s.peer_delay.handle_follow_up(local_port, follow_up);
+    update_link_delay(&mut s, stats, delay);
+}
+
+/// Store a completed peer delay measurement as the mean path delay
+fn update_link_delay(s: &mut PtpSharedState, stats: &Arc<Mutex<PtpStats>>, delay: Option<i64>) {
+    if let Some(delay_ns) = delay {
+        match s.servo.filter_path_delay(delay_ns) {
+            Some(delay_ns) => s.stats.mean_path_delay_ns = delay_ns,
+            None => s.stats.outlier_count = s.servo.outlier_count(),
+        }
+    }
+    if let Ok(mut ext_stats) = stats.lock() {
+        ext_stats.mean_path_delay_ns = s.stats.mean_path_delay_ns;
+        ext_stats.pdelay_resp_count = s.stats.pdelay_resp_count;
+        ext_stats.outlier_count = s.stats.outlier_count;
+        ext_stats.tx_timestamp_source = s.stats.tx_timestamp_source;
+    }
+}
+
+/// Master thread - runs the master side of BMCA and sends Announce, Sync and Follow_Up
+fn run_master_thread(
+    running: Arc<AtomicBool>,
+    state: Arc<Mutex<PtpSharedState>>,
+    stats: Arc<Mutex<PtpStats>>,
+) {
+    let mut next_announce = Instant::now();
+    let mut next_sync = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(5));
+
+        let mut s = match state.lock() {
+            Ok(s) => s,
+            Err(_) => break,
+        };
+
+        let config = match s.master_config {
+            Some(c) => c,
+            None => break,
+        };
+
+        let now = Instant::now();
+
+        if s.stats.state != PtpState::Master {
+            if !should_become_master(&s, &config, now) {
+                continue;
+            }
+            become_master(&mut s, &config);
+            publish_stats(&s, &stats);
+            next_announce = now;
+            next_sync = now;
+        }
+
+        if now >= next_announce {
+            send_announce(&mut s, &config);
+            next_announce = advance_deadline(next_announce, config.announce_interval(), now);
+        }
+
+        if now >= next_sync {
+            next_sync = advance_deadline(next_sync, config.sync_interval(), now);
+            drop(s);
+            send_sync(&state, &stats, &config);
+        }
+    }
+}
+
+/// Decide whether the local clock should take over as grandmaster
+fn should_become_master(s: &PtpSharedState, config: &PtpMasterConfig, now: Instant) -> bool {
+    match (s.grandmaster, s.grandmaster_dataset) {
+        (Some(_), Some(current)) if !grandmaster_expired(s, now) => {
+            // A worse grandmaster is active: we win the BMCA
+            config
+                .dataset(s.local_port.clock_identity)
+                .is_better_than(&current)
+        }
+        _ => {
+            // No (live) grandmaster: wait one announce receipt timeout after
+            // start before claiming the domain, so an existing master is found first
+            let listen_time = config.announce_interval() * ANNOUNCE_RECEIPT_TIMEOUT;
+            now.duration_since(s.started_at) >= listen_time
+        }
+    }
+}
+
+/// Enter the master state
+fn become_master(s: &mut PtpSharedState, config: &PtpMasterConfig) {
+    s.grandmaster = None;
+    s.grandmaster_addr = None;
+    s.grandmaster_dataset = None;
+    s.last_announce = None;
+    s.pending_sync = None;
+    s.pending_delay = None;
+    s.initial_offset_ns = None;
+    s.servo.reset();
+
+    // We are the reference: zero offset, zero frequency correction, always locked
+    s.stats.state = PtpState::Master;
+    s.stats.grandmaster_id = s.local_port.clock_identity;
+    s.stats.grandmaster_port = s.local_port.port_number;
+    s.stats.clock_class = config.clock_class;
+    s.stats.priority1 = config.priority1;
+    s.stats.priority2 = config.priority2;
+    s.stats.offset_ns = 0;
+    s.stats.frequency_ppm = 0.0;
+    s.stats.mean_path_delay_ns = 0;
+    s.stats.locked = true;
+    s.holdover_since = None;
+    s.stats.holdover_duration_ms = 0;
+    s.stats.holdover_error_ns = 0;
+}
+
+/// Send an Announce message as master
+fn send_announce(s: &mut PtpSharedState, config: &PtpMasterConfig) {
+    s.announce_seq = s.announce_seq.wrapping_add(1);
+    let now_ptp = master::local_to_ptp_ns(platform::get_timestamp_ns(), config.current_utc_offset);
+    let mut msg = master::build_announce(config, s.local_port, s.stats.domain, s.announce_seq, now_ptp);
+    if s.profile == PtpProfile::Smpte2059 {
+        msg.tlvs.push(SmpteSyncMetadata::default().to_tlv());
+    }
+
+    // Our own time properties are what the slaves see
+    s.stats.time_properties.update(&msg, now_ptp);
+    s.stats.smpte = SmpteSyncMetadata::find(&msg.tlvs);
+
+    // As grandmaster our own clock (shifted to TAI) is PTP time
+    s.stats.time_map = Some(PtpTimeMap::new(
+        platform::get_monotonic_ns(),
+        now_ptp,
+        0.0,
+        s.stats.time_properties,
+    ));
+
+    if let Some(socket) = &s.general_socket {
+        let dest = SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_GENERAL_PORT);
+        if socket.send_to(&msg.to_bytes(), dest).is_ok() {
+            s.stats.announce_count += 1;
+        }
+    }
+}
+
+/// Send a two-step Sync and its Follow_Up as master
+fn send_sync(state: &Arc<Mutex<PtpSharedState>>, stats: &Arc<Mutex<PtpStats>>, config: &PtpMasterConfig) {
+    let (socket, sync) = {
+        let mut s = match state.lock() {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+        let socket = match &s.event_socket {
+            Some(sock) => Arc::clone(sock),
+            None => return,
+        };
+        s.sync_seq = s.sync_seq.wrapping_add(1);
+        (socket, master::build_sync(config, s.local_port, s.stats.domain, s.sync_seq))
+    };
+
+    let dest = SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_EVENT_PORT);
+    let send_time = match send_event(&socket, &sync.to_bytes(), dest) {
+        Some(t) => t,
+        None => return,
+    };
+
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    s.stats.tx_timestamp_source = send_time.source;
+
+    let follow_up = master::build_follow_up(
+        config,
+        s.local_port,
+        s.stats.domain,
+        sync.header.sequence_id,
+        master::local_to_ptp_ns(send_time.ns, config.current_utc_offset),
+    );
+
+    if let Some(socket) = &s.general_socket {
+        let dest = SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_GENERAL_PORT);
+        if socket.send_to(&follow_up.to_bytes(), dest).is_ok() {
+            s.stats.sync_count += 1;
+            s.stats.follow_up_count += 1;
+        }
+    }
+    if let Ok(mut ext_stats) = stats.lock() {
+        ext_stats.sync_count = s.stats.sync_count;
+        ext_stats.announce_count = s.stats.announce_count;
+    }
+}
+
+/// Advance a periodic deadline, skipping missed periods instead of bursting
+fn advance_deadline(deadline: Instant, interval: Duration, now: Instant) -> Instant {
+    let next = deadline + interval;
+    if next <= now {
+        now + interval
+    } else {
+        next
+    }
+}
+
+/// Handle Signaling message - unicast negotiation grants and cancels
+fn handle_signaling(
+    state: &Arc<Mutex<PtpSharedState>>,
+    stats: &Arc<Mutex<PtpStats>>,
+    signaling: &SignalingMessage,
+    from: Ipv4Addr,
+) {
+    let mut s = match state.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    // Must be addressed to us (or to everyone)
+    let target = signaling.target_port_identity;
+    if target != s.local_port && target != PortIdentity::ALL {
+        return;
+    }
+
+    let now = Instant::now();
+    let replies = match s.unicast.as_mut() {
+        Some(unicast) => unicast.handle_signaling(now, from, signaling),
+        None => return,
+    };
+
+    if !replies.is_empty() {
+        send_signaling(&mut s, from, replies);
+    }
+
+    s.stats.unicast_grants = s.unicast.as_ref().map(|u| u.active_grants(now)).unwrap_or(0);
+    if let Ok(mut ext_stats) = stats.lock() {
+        ext_stats.unicast_grants = s.stats.unicast_grants;
+    }
+}
+
+/// Unicast thread - requests, renews and cancels unicast grants
+fn run_unicast_thread(
+    running: Arc<AtomicBool>,
+    state: Arc<Mutex<PtpSharedState>>,
+    stats: Arc<Mutex<PtpStats>>,
+) {
+    while running.load(Ordering::SeqCst) {
+        {
+            let mut s = match state.lock() {
+                Ok(s) => s,
+                Err(_) => break,
+            };
+
+            let now = Instant::now();
+            let grandmaster = s.grandmaster.and(s.grandmaster_addr);
+            let requests = match s.unicast.as_mut() {
+                Some(unicast) => unicast.poll(now, grandmaster),
+                None => break,
+            };
+
+            for (addr, tlvs) in requests {
+                send_signaling(&mut s, addr, tlvs);
+            }
+
+            s.stats.unicast_grants = s.unicast.as_ref().map(|u| u.active_grants(now)).unwrap_or(0);
+            if let Ok(mut ext_stats) = stats.lock() {
+                ext_stats.unicast_grants = s.stats.unicast_grants;
+            }
+        }
+
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// Send a unicast Signaling message carrying negotiation TLVs
+fn send_signaling(s: &mut PtpSharedState, to: Ipv4Addr, tlvs: Vec<UnicastTlv>) {
+    s.signaling_seq = s.signaling_seq.wrapping_add(1);
+    let msg = SignalingMessage::new(
+        s.local_port,
+        s.signaling_seq,
+        s.stats.domain,
+        PortIdentity::ALL,
+        tlvs.iter().map(UnicastTlv::to_tlv).collect(),
+    );
+
+    if let Some(socket) = &s.general_socket {
+        let _ = socket.send_to(&msg.to_bytes(), SocketAddrV4::new(to, PTP_GENERAL_PORT));
     }
 }
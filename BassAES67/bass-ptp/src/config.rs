@@ -0,0 +1,271 @@
+//! PTP client configuration.
+
+use std::net::Ipv4Addr;
+
+use crate::history::DEFAULT_HISTORY_SIZE;
+use crate::master::PtpMasterConfig;
+use crate::servo::ServoConfig;
+
+/// How the client exchanges messages with the grandmaster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PtpTransportMode {
+    /// Everything multicast (default, classic AES67)
+    #[default]
+    Multicast = 0,
+    /// Sync/Announce multicast, Delay_Req/Delay_Resp unicast to the grandmaster
+    Hybrid = 1,
+    /// Everything unicast, negotiated with a configured master list
+    Unicast = 2,
+}
+
+impl PtpTransportMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PtpTransportMode::Multicast => "multicast",
+            PtpTransportMode::Hybrid => "hybrid",
+            PtpTransportMode::Unicast => "unicast",
+        }
+    }
+
+    /// Check if Delay_Req goes unicast to the grandmaster
+    pub fn unicast_delay_req(&self) -> bool {
+        *self != PtpTransportMode::Multicast
+    }
+}
+
+impl TryFrom<u8> for PtpTransportMode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PtpTransportMode::Multicast),
+            1 => Ok(PtpTransportMode::Hybrid),
+            2 => Ok(PtpTransportMode::Unicast),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How the path delay to the grandmaster is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PtpDelayMechanism {
+    /// End-to-end: Delay_Req / Delay_Resp with the grandmaster (default)
+    #[default]
+    E2E = 0,
+    /// Peer-to-peer: Pdelay_Req / Pdelay_Resp with the link neighbour
+    P2P = 1,
+}
+
+impl PtpDelayMechanism {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PtpDelayMechanism::E2E => "E2E",
+            PtpDelayMechanism::P2P => "P2P",
+        }
+    }
+}
+
+impl TryFrom<u8> for PtpDelayMechanism {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PtpDelayMechanism::E2E),
+            1 => Ok(PtpDelayMechanism::P2P),
+            _ => Err(()),
+        }
+    }
+}
+
+/// PTP profile (default message rates and profile-specific behaviour)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PtpProfile {
+    /// AES67 media profile (default)
+    #[default]
+    Aes67 = 0,
+    /// SMPTE ST 2059-2 (ST 2110 facilities)
+    Smpte2059 = 1,
+}
+
+impl PtpProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PtpProfile::Aes67 => "AES67",
+            PtpProfile::Smpte2059 => "SMPTE ST 2059-2",
+        }
+    }
+
+    /// Default domain number of the profile
+    pub fn default_domain(&self) -> u8 {
+        match self {
+            PtpProfile::Aes67 => 0,
+            PtpProfile::Smpte2059 => 127,
+        }
+    }
+
+    /// Default (log2 Sync, log2 Announce, log2 Delay_Req) intervals
+    pub fn default_intervals(&self) -> (i8, i8, i8) {
+        match self {
+            PtpProfile::Aes67 => (-3, 1, 0),
+            PtpProfile::Smpte2059 => (-3, -2, -3),
+        }
+    }
+}
+
+impl TryFrom<u8> for PtpProfile {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PtpProfile::Aes67),
+            1 => Ok(PtpProfile::Smpte2059),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Full client configuration (see `start_ptp_client_ex`)
+#[derive(Debug, Clone)]
+pub struct PtpClientConfig {
+    /// Network interface IP
+    pub interface: Ipv4Addr,
+    /// PTP domain number (0-127)
+    pub domain: u8,
+    /// Message transport
+    pub transport: PtpTransportMode,
+    /// Path delay measurement
+    pub delay_mechanism: PtpDelayMechanism,
+    /// PTP profile (see `set_profile`)
+    pub profile: PtpProfile,
+    /// Masters to negotiate with in unicast mode
+    pub unicast_masters: Vec<Ipv4Addr>,
+    /// Requested unicast grant duration in seconds
+    pub unicast_duration_s: u32,
+    /// Requested log2 Sync interval for unicast grants
+    pub log_sync_interval: i8,
+    /// Requested log2 Announce interval for unicast grants
+    pub log_announce_interval: i8,
+    /// Requested log2 Delay_Resp interval for unicast grants, and the
+    /// Pdelay_Req interval in P2P mode
+    pub log_delay_req_interval: i8,
+    /// Master role (None = slave only)
+    pub master: Option<PtpMasterConfig>,
+    /// Servo algorithm and tuning
+    pub servo: ServoConfig,
+    /// Samples kept in the offset/delay history (0 = no history)
+    pub history_size: usize,
+}
+
+impl PtpClientConfig {
+    /// Default multicast slave configuration
+    pub fn new(interface: Ipv4Addr, domain: u8) -> Self {
+        Self {
+            interface,
+            domain,
+            transport: PtpTransportMode::Multicast,
+            delay_mechanism: PtpDelayMechanism::E2E,
+            profile: PtpProfile::Aes67,
+            unicast_masters: Vec::new(),
+            unicast_duration_s: 300,
+            log_sync_interval: -3,
+            log_announce_interval: 1,
+            log_delay_req_interval: 0,
+            master: None,
+            servo: ServoConfig::default(),
+            history_size: DEFAULT_HISTORY_SIZE,
+        }
+    }
+
+    /// Select a profile and apply its default message rates (also to the
+    /// master role, if enabled). The domain is left as configured; see
+    /// `PtpProfile::default_domain`.
+    pub fn set_profile(&mut self, profile: PtpProfile) {
+        let (log_sync, log_announce, log_delay_req) = profile.default_intervals();
+        self.profile = profile;
+        self.log_sync_interval = log_sync;
+        self.log_announce_interval = log_announce;
+        self.log_delay_req_interval = log_delay_req;
+        if let Some(master) = self.master.as_mut() {
+            master.log_sync_interval = log_sync;
+            master.log_announce_interval = log_announce;
+        }
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<(), String> {
+        if self.transport == PtpTransportMode::Unicast && self.unicast_masters.is_empty() {
+            return Err("Unicast mode requires at least one master address".to_string());
+        }
+        if self.transport == PtpTransportMode::Unicast && self.unicast_duration_s == 0 {
+            return Err("Unicast grant duration must be non-zero".to_string());
+        }
+        if self.delay_mechanism == PtpDelayMechanism::P2P && self.transport != PtpTransportMode::Multicast {
+            // Peer delay is link-local; there is no unicast peer to talk to
+            return Err("P2P delay mechanism requires multicast transport".to_string());
+        }
+        self.servo.validate()
+    }
+}
+
+/// Parse a comma/semicolon/space separated list of IPv4 addresses
+pub fn parse_master_list(list: &str) -> Result<Vec<Ipv4Addr>, String> {
+    list.split([',', ';', ' '])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<Ipv4Addr>()
+                .map_err(|_| format!("Invalid master address '{}'", s))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_master_list() {
+        let list = parse_master_list("192.168.1.10, 192.168.1.11;10.0.0.1").unwrap();
+        assert_eq!(
+            list,
+            vec![
+                Ipv4Addr::new(192, 168, 1, 10),
+                Ipv4Addr::new(192, 168, 1, 11),
+                Ipv4Addr::new(10, 0, 0, 1)
+            ]
+        );
+        assert!(parse_master_list("192.168.1.x").is_err());
+    }
+
+    #[test]
+    fn test_unicast_requires_masters() {
+        let mut config = PtpClientConfig::new(Ipv4Addr::LOCALHOST, 0);
+        config.transport = PtpTransportMode::Unicast;
+        assert!(config.validate().is_err());
+        config.unicast_masters.push(Ipv4Addr::new(10, 0, 0, 1));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_smpte_profile_rates() {
+        let mut config = PtpClientConfig::new(Ipv4Addr::LOCALHOST, PtpProfile::Smpte2059.default_domain());
+        config.master = Some(PtpMasterConfig::default());
+        config.set_profile(PtpProfile::Smpte2059);
+        assert_eq!(config.domain, 127);
+        assert_eq!(config.log_announce_interval, -2);
+        assert_eq!(config.master.unwrap().log_announce_interval, -2);
+        assert_eq!(config.master.unwrap().log_sync_interval, -3);
+    }
+
+    #[test]
+    fn test_p2p_requires_multicast() {
+        let mut config = PtpClientConfig::new(Ipv4Addr::LOCALHOST, 0);
+        config.delay_mechanism = PtpDelayMechanism::P2P;
+        assert!(config.validate().is_ok());
+        config.transport = PtpTransportMode::Hybrid;
+        assert!(config.validate().is_err());
+    }
+}
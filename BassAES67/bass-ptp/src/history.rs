@@ -0,0 +1,498 @@
+//! Rolling history of servo samples for network qualification.
+//!
+//! Every accepted Sync adds one sample (offset, path delay, frequency). The
+//! history keeps the most recent samples and derives the metrics commonly
+//! used to qualify a PTP network:
+//!
+//! - mean and standard deviation of offset, path delay and frequency
+//! - peak-to-peak offset over the whole history
+//! - MTIE (maximum time interval error), Allan deviation and time deviation
+//!   (TDEV) at 1-2-5 taus from 1 s
+//! - an offset histogram
+//!
+//! The offsets are treated as time error (phase) samples taken at the mean
+//! Sync interval, which assumes the grandmaster sends Syncs at a fixed rate.
+//! Taus that the history is too short for are reported as unavailable.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+/// Default number of samples kept (about 68 minutes at 8 Syncs per second)
+pub const DEFAULT_HISTORY_SIZE: usize = 32_768;
+
+/// Observation intervals (seconds) for MTIE, Allan deviation and TDEV
+pub const HISTORY_TAUS: [f64; 10] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0];
+
+/// Number of bins in an automatically sized histogram
+const AUTO_HISTOGRAM_BINS: i64 = 20;
+
+/// One servo sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistorySample {
+    /// Local time of the Sync (ns since the Unix epoch)
+    pub local_ns: i64,
+    /// Offset from master in nanoseconds
+    pub offset_ns: i64,
+    /// Mean path delay in nanoseconds
+    pub path_delay_ns: i64,
+    /// Frequency estimate in ppb
+    pub frequency_ppb: f64,
+}
+
+/// MTIE, Allan deviation and TDEV at one tau
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StabilityPoint {
+    /// Observation interval in seconds (rounded to a whole number of samples)
+    pub tau_s: f64,
+    /// Allan deviation (dimensionless, e.g. 1e-9 = 1 ppb)
+    pub adev: f64,
+    /// Time deviation in nanoseconds
+    pub tdev_ns: f64,
+    /// Maximum time interval error in nanoseconds: the largest peak-to-peak
+    /// offset within any window of tau
+    pub mtie_ns: f64,
+}
+
+/// Metrics derived from the history
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryMetrics {
+    /// Number of samples
+    pub count: usize,
+    /// Time covered by the samples in seconds
+    pub duration_s: f64,
+    /// Mean interval between samples in seconds
+    pub interval_s: f64,
+    pub offset_mean_ns: f64,
+    pub offset_stddev_ns: f64,
+    pub offset_min_ns: i64,
+    pub offset_max_ns: i64,
+    /// Peak-to-peak offset over the whole history (max - min offset)
+    pub peak_to_peak_ns: i64,
+    pub path_delay_mean_ns: f64,
+    pub path_delay_stddev_ns: f64,
+    pub frequency_mean_ppb: f64,
+    pub frequency_stddev_ppb: f64,
+    /// MTIE, Allan deviation and TDEV at the `HISTORY_TAUS` the history covers
+    pub stability: Vec<StabilityPoint>,
+}
+
+/// Offset histogram
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    /// Width of each bin in nanoseconds
+    pub bin_width_ns: i64,
+    /// (lower bound in ns, count) of each non-empty bin, in ascending order
+    pub bins: Vec<(i64, u32)>,
+}
+
+/// Rolling sample history
+#[derive(Debug, Clone)]
+pub struct PtpHistory {
+    samples: VecDeque<HistorySample>,
+    capacity: usize,
+}
+
+impl Default for PtpHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_SIZE)
+    }
+}
+
+impl PtpHistory {
+    /// History keeping the last `capacity` samples (0 = disabled)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity.min(DEFAULT_HISTORY_SIZE)),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Samples from oldest to newest
+    pub fn samples(&self) -> impl Iterator<Item = &HistorySample> {
+        self.samples.iter()
+    }
+
+    /// Add a sample, dropping the oldest one when full
+    pub fn push(&mut self, sample: HistorySample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Compute metrics over the current history
+    pub fn metrics(&self) -> HistoryMetrics {
+        let n = self.samples.len();
+        if n == 0 {
+            return HistoryMetrics::default();
+        }
+
+        let offsets: Vec<f64> = self.samples.iter().map(|s| s.offset_ns as f64).collect();
+        let delays: Vec<f64> = self.samples.iter().map(|s| s.path_delay_ns as f64).collect();
+        let frequencies: Vec<f64> = self.samples.iter().map(|s| s.frequency_ppb).collect();
+        let (offset_mean_ns, offset_stddev_ns) = mean_stddev(&offsets);
+        let (path_delay_mean_ns, path_delay_stddev_ns) = mean_stddev(&delays);
+        let (frequency_mean_ppb, frequency_stddev_ppb) = mean_stddev(&frequencies);
+
+        let offset_min_ns = self.samples.iter().map(|s| s.offset_ns).min().unwrap_or(0);
+        let offset_max_ns = self.samples.iter().map(|s| s.offset_ns).max().unwrap_or(0);
+
+        let first = self.samples.front().map(|s| s.local_ns).unwrap_or(0);
+        let last = self.samples.back().map(|s| s.local_ns).unwrap_or(0);
+        let duration_s = (last - first) as f64 / 1e9;
+        let interval_s = if n > 1 { duration_s / (n - 1) as f64 } else { 0.0 };
+
+        HistoryMetrics {
+            count: n,
+            duration_s,
+            interval_s,
+            offset_mean_ns,
+            offset_stddev_ns,
+            offset_min_ns,
+            offset_max_ns,
+            peak_to_peak_ns: offset_max_ns - offset_min_ns,
+            path_delay_mean_ns,
+            path_delay_stddev_ns,
+            frequency_mean_ppb,
+            frequency_stddev_ppb,
+            stability: stability(&offsets, interval_s),
+        }
+    }
+
+    /// Offset histogram with bins of `bin_width_ns` (0 = about 20 bins
+    /// spanning the observed range)
+    pub fn histogram(&self, bin_width_ns: i64) -> Histogram {
+        let min = self.samples.iter().map(|s| s.offset_ns).min().unwrap_or(0);
+        let max = self.samples.iter().map(|s| s.offset_ns).max().unwrap_or(0);
+        let bin_width_ns = if bin_width_ns > 0 {
+            bin_width_ns
+        } else {
+            ((max - min) / AUTO_HISTOGRAM_BINS + 1).max(1)
+        };
+
+        let mut bins: Vec<(i64, u32)> = Vec::new();
+        let mut lowers: Vec<i64> = self
+            .samples
+            .iter()
+            .map(|s| s.offset_ns.div_euclid(bin_width_ns) * bin_width_ns)
+            .collect();
+        lowers.sort_unstable();
+        for lower in lowers {
+            match bins.last_mut() {
+                Some((l, count)) if *l == lower => *count += 1,
+                _ => bins.push((lower, 1)),
+            }
+        }
+
+        Histogram { bin_width_ns, bins }
+    }
+
+    /// Samples as CSV, one line per sample with a header line
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("local_time_ns,offset_ns,path_delay_ns,frequency_ppb\n");
+        for s in &self.samples {
+            let _ = writeln!(out, "{},{},{},{:.3}", s.local_ns, s.offset_ns, s.path_delay_ns, s.frequency_ppb);
+        }
+        out
+    }
+
+    /// Metrics, offset histogram and samples as JSON
+    pub fn to_json(&self) -> String {
+        let m = self.metrics();
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{\"metrics\":{{\"count\":{},\"duration_s\":{:.3},\"interval_s\":{:.6},\
+             \"offset_mean_ns\":{:.1},\"offset_stddev_ns\":{:.1},\"offset_min_ns\":{},\
+             \"offset_max_ns\":{},\"peak_to_peak_ns\":{},\"path_delay_mean_ns\":{:.1},\
+             \"path_delay_stddev_ns\":{:.1},\"frequency_mean_ppb\":{:.3},\
+             \"frequency_stddev_ppb\":{:.3},\"stability\":[",
+            m.count,
+            m.duration_s,
+            m.interval_s,
+            m.offset_mean_ns,
+            m.offset_stddev_ns,
+            m.offset_min_ns,
+            m.offset_max_ns,
+            m.peak_to_peak_ns,
+            m.path_delay_mean_ns,
+            m.path_delay_stddev_ns,
+            m.frequency_mean_ppb,
+            m.frequency_stddev_ppb
+        );
+        for (i, p) in m.stability.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"tau_s\":{:.3},\"adev\":{:e},\"tdev_ns\":{:.3},\"mtie_ns\":{:.1}}}",
+                p.tau_s, p.adev, p.tdev_ns, p.mtie_ns
+            );
+        }
+
+        let histogram = self.histogram(0);
+        let _ = write!(
+            out,
+            "]}},\"histogram\":{{\"bin_width_ns\":{},\"bins\":[",
+            histogram.bin_width_ns
+        );
+        for (i, (lower, count)) in histogram.bins.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "[{},{}]", lower, count);
+        }
+
+        out.push_str("]},\"samples\":[");
+        for (i, s) in self.samples.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "[{},{},{},{:.3}]",
+                s.local_ns, s.offset_ns, s.path_delay_ns, s.frequency_ppb
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+fn mean_stddev(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// MTIE, Allan deviation and TDEV of the phase samples `x` (ns) taken every
+/// `interval_s`, at each of `HISTORY_TAUS` the data covers
+fn stability(x: &[f64], interval_s: f64) -> Vec<StabilityPoint> {
+    let mut points = Vec::new();
+    if interval_s <= 0.0 {
+        return points;
+    }
+
+    let mut last_m = 0;
+    for &tau in HISTORY_TAUS.iter() {
+        let m = (tau / interval_s).round() as usize;
+        // TDEV needs at least one full 3m window
+        if m == 0 || m == last_m || x.len() < 3 * m + 1 {
+            continue;
+        }
+        last_m = m;
+
+        // Second differences of phase over m samples
+        let d: Vec<f64> = (0..x.len() - 2 * m)
+            .map(|i| x[i + 2 * m] - 2.0 * x[i + m] + x[i])
+            .collect();
+
+        let tau_s = m as f64 * interval_s;
+        let tau_ns = tau_s * 1e9;
+        let avar = d.iter().map(|v| v * v).sum::<f64>() / (2.0 * tau_ns * tau_ns * d.len() as f64);
+
+        // TDEV: mean square of m-sample averages of the second differences
+        let windows = d.len() - m + 1;
+        let mut window_sum: f64 = d[..m].iter().sum();
+        let mut tvar_sum = window_sum * window_sum;
+        for j in 1..windows {
+            window_sum += d[j + m - 1] - d[j - 1];
+            tvar_sum += window_sum * window_sum;
+        }
+        let tvar = tvar_sum / (6.0 * (m * m) as f64 * windows as f64);
+
+        points.push(StabilityPoint {
+            tau_s,
+            adev: avar.sqrt(),
+            tdev_ns: tvar.sqrt(),
+            mtie_ns: mtie(x, m),
+        });
+    }
+    points
+}
+
+/// Largest max - min of the phase samples `x` in any window of m + 1
+/// samples (m intervals)
+fn mtie(x: &[f64], m: usize) -> f64 {
+    // Indices of the window's maximum and minimum candidates, in order
+    let mut maxima: VecDeque<usize> = VecDeque::new();
+    let mut minima: VecDeque<usize> = VecDeque::new();
+    let mut worst = 0.0f64;
+
+    for (i, &v) in x.iter().enumerate() {
+        while maxima.back().is_some_and(|&j| x[j] <= v) {
+            maxima.pop_back();
+        }
+        maxima.push_back(i);
+        while minima.back().is_some_and(|&j| x[j] >= v) {
+            minima.pop_back();
+        }
+        minima.push_back(i);
+
+        // Window [i - m, i], once full
+        let start = match i.checked_sub(m) {
+            Some(start) => start,
+            None => continue,
+        };
+        if maxima[0] < start {
+            maxima.pop_front();
+        }
+        if minima[0] < start {
+            minima.pop_front();
+        }
+        worst = worst.max(x[maxima[0]] - x[minima[0]]);
+    }
+    worst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(i: i64, offset_ns: i64) -> HistorySample {
+        HistorySample {
+            local_ns: i * 125_000_000,
+            offset_ns,
+            path_delay_ns: 50_000,
+            frequency_ppb: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_rolls_over_at_capacity() {
+        let mut history = PtpHistory::new(4);
+        for i in 0..10 {
+            history.push(sample(i, i));
+        }
+        let offsets: Vec<i64> = history.samples().map(|s| s.offset_ns).collect();
+        assert_eq!(offsets, vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_basic_metrics() {
+        let mut history = PtpHistory::new(16);
+        for (i, offset) in [-100, 100, -100, 100, 300].iter().enumerate() {
+            history.push(sample(i as i64, *offset));
+        }
+        let m = history.metrics();
+        assert_eq!(m.count, 5);
+        assert!((m.duration_s - 0.5).abs() < 1e-9);
+        assert!((m.interval_s - 0.125).abs() < 1e-9);
+        assert!((m.offset_mean_ns - 60.0).abs() < 1e-9);
+        assert_eq!(m.peak_to_peak_ns, 400);
+        assert!((m.path_delay_mean_ns - 50_000.0).abs() < 1e-9);
+        assert_eq!(m.path_delay_stddev_ns, 0.0);
+    }
+
+    #[test]
+    fn test_stability_of_constant_frequency_offset() {
+        // A pure frequency offset is a phase ramp: its second differences,
+        // and so Allan deviation and TDEV, are zero
+        let mut history = PtpHistory::new(DEFAULT_HISTORY_SIZE);
+        for i in 0..1000 {
+            history.push(sample(i, i * 10));
+        }
+        let m = history.metrics();
+        assert!(!m.stability.is_empty());
+        assert_eq!(m.stability[0].tau_s, 1.0);
+        for p in &m.stability {
+            assert!(p.adev < 1e-15 && p.tdev_ns < 1e-6, "{:?}", p);
+            // 80 ns of phase per second
+            assert!((p.mtie_ns - p.tau_s * 80.0).abs() < 1e-6, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_stability_of_white_phase_noise() {
+        // Alternating ±100 ns: second differences at odd m are ±400 ns
+        let mut history = PtpHistory::new(DEFAULT_HISTORY_SIZE);
+        for i in 0..200 {
+            history.push(sample(i, if i % 2 == 0 { 100 } else { -100 }));
+        }
+        let m = history.metrics();
+        // tau = 1s is m = 8 samples: second differences vanish
+        assert_eq!(m.stability[0].tau_s, 1.0);
+        assert!(m.stability[0].adev < 1e-15);
+        // No tau beyond 200 samples / 3
+        assert!(m.stability.iter().all(|p| p.tau_s * 8.0 * 3.0 < 200.0));
+        assert!(m.stability.iter().all(|p| p.mtie_ns == 200.0));
+
+        // Five Syncs per second: tau = 1s is m = 5, where every second
+        // difference is ±400 ns and every 5-sample sum of them is ±400 ns
+        let mut history = PtpHistory::new(DEFAULT_HISTORY_SIZE);
+        for i in 0..200 {
+            let offset_ns = if i % 2 == 0 { 100 } else { -100 };
+            history.push(HistorySample {
+                local_ns: i * 200_000_000,
+                ..sample(i, offset_ns)
+            });
+        }
+        let m = history.metrics();
+        let p = m.stability[0];
+        assert!((p.tau_s - 1.0).abs() < 1e-9);
+        // ADEV = 400 ns / (sqrt(2) * 1 s)
+        assert!((p.adev - 400e-9 / 2f64.sqrt()).abs() < 1e-15, "{:?}", p);
+        // TDEV = 400 ns / (sqrt(6) * 5)
+        assert!((p.tdev_ns - 400.0 / (6f64.sqrt() * 5.0)).abs() < 1e-9, "{:?}", p);
+        assert_eq!(p.mtie_ns, 200.0);
+        // tau = 2s is m = 10 again
+        assert!((m.stability[1].tau_s - 2.0).abs() < 1e-9);
+        assert!(m.stability[1].adev < 1e-15);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut history = PtpHistory::new(16);
+        for (i, offset) in [-15, -5, 0, 4, 9, 10, 25].iter().enumerate() {
+            history.push(sample(i as i64, *offset));
+        }
+        let h = history.histogram(10);
+        assert_eq!(h.bin_width_ns, 10);
+        assert_eq!(h.bins, vec![(-20, 1), (-10, 1), (0, 3), (10, 1), (20, 1)]);
+
+        let auto = history.histogram(0);
+        assert_eq!(auto.bins.iter().map(|b| b.1).sum::<u32>(), 7);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut history = PtpHistory::new(16);
+        history.push(sample(0, -12));
+        history.push(sample(1, 8));
+
+        assert_eq!(
+            history.to_csv(),
+            "local_time_ns,offset_ns,path_delay_ns,frequency_ppb\n\
+             0,-12,50000,0.000\n\
+             125000000,8,50000,0.000\n"
+        );
+        let json = history.to_json();
+        assert!(json.starts_with("{\"metrics\":{\"count\":2,"));
+        assert!(json.contains("\"peak_to_peak_ns\":20,"));
+        assert!(json.ends_with("\"samples\":[[0,-12,50000,0.000],[125000000,8,50000,0.000]]}"));
+    }
+}
@@ -4,21 +4,40 @@
 //! (bass_aes67 input and future bass_aes67_send output).
 
 pub mod client;
+pub mod config;
+pub mod history;
+pub mod management;
+pub mod master;
 pub mod messages;
+pub mod pdelay;
 pub mod platform;
 pub mod servo;
+pub mod smpte;
 pub mod stats;
+pub mod timemap;
 pub mod timer;
+pub mod timescale;
+pub mod timestamping;
+pub mod unicast;
 
 use std::ffi::{c_char, c_void, CStr};
 use std::net::Ipv4Addr;
 
 // Re-export key types
 pub use client::{
-    force_stop_ptp_client, get_frequency_ppm, get_offset_ns, get_ptp_stats, is_ptp_running,
-    start_ptp_client, stop_ptp_client,
+    clear_instance_history, create_ptp_instance, destroy_ptp_instance, force_stop_ptp_client, get_frequency_ppm,
+    get_instance_history, get_instance_stats, get_offset_ns, get_ptp_stats, get_servo_config, is_ptp_running, local_to_ptp_time,
+    ptp_now, set_servo_config, start_ptp_client, start_ptp_client_ex, start_ptp_master, stop_ptp_client,
 };
+pub use config::{PtpClientConfig, PtpDelayMechanism, PtpProfile, PtpTransportMode};
+pub use history::{HistoryMetrics, HistorySample, PtpHistory};
+pub use master::PtpMasterConfig;
+pub use servo::{ServoConfig, ServoKind};
+pub use smpte::SmpteSyncMetadata;
+pub use timemap::{PtpTime, PtpTimeMap};
+pub use timescale::TimeProperties;
 pub use stats::{PtpState, PtpStats};
+pub use timestamping::{TimestampMode, TimestampSource};
 
 // ============================================================================
 // C API Error Codes
@@ -34,8 +53,8 @@ pub const BASS_PTP_ERROR_INVALID: i32 = 4;
 // C API Version
 // ============================================================================
 
-/// Library version (major.minor format: 0x0100 = 1.0)
-pub const BASS_PTP_VERSION: u32 = 0x0100;
+/// Library version (major.minor format: 0x0200 = 2.0)
+pub const BASS_PTP_VERSION: u32 = 0x0200;
 
 // ============================================================================
 // C API Functions
@@ -76,6 +95,204 @@ pub unsafe extern "C" fn BASS_PTP_Start(interface_ip: *const c_char, domain: u8)
     }
 }
 
+/// Start the PTP client with the master (grandmaster) role enabled.
+///
+/// The client listens for one announce receipt timeout, then becomes
+/// grandmaster unless a better clock (by BMCA) is announced on the domain.
+/// It steps down to slave whenever a better grandmaster appears.
+/// Reference-counted like BASS_PTP_Start; if the client is already running,
+/// its existing role is kept.
+///
+/// # Arguments
+/// * `interface_ip` - Network interface IP as null-terminated C string
+/// * `domain` - PTP domain number (0-127)
+/// * `priority1` - BMCA priority1 (lower wins, default 128)
+/// * `priority2` - BMCA priority2 (lower wins, default 128)
+/// * `clock_class` - Advertised clock class (default 248)
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if interface_ip is null or invalid
+/// * BASS_PTP_ERROR_SOCKET if socket creation fails
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_StartMaster(
+    interface_ip: *const c_char,
+    domain: u8,
+    priority1: u8,
+    priority2: u8,
+    clock_class: u8,
+) -> i32 {
+    if interface_ip.is_null() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+
+    let ip_str = match CStr::from_ptr(interface_ip).to_str() {
+        Ok(s) => s,
+        Err(_) => return BASS_PTP_ERROR_INVALID,
+    };
+
+    let ip_addr: Ipv4Addr = match ip_str.parse() {
+        Ok(ip) => ip,
+        Err(_) => return BASS_PTP_ERROR_INVALID,
+    };
+
+    let config = PtpMasterConfig {
+        priority1,
+        priority2,
+        clock_class,
+        ..Default::default()
+    };
+
+    match start_ptp_master(ip_addr, domain, config) {
+        Ok(()) => BASS_PTP_OK,
+        Err(_) => BASS_PTP_ERROR_SOCKET,
+    }
+}
+
+/// Transport mode: everything multicast (default)
+pub const BASS_PTP_TRANSPORT_MULTICAST: u8 = 0;
+/// Transport mode: multicast Sync/Announce, unicast Delay_Req to the grandmaster
+pub const BASS_PTP_TRANSPORT_HYBRID: u8 = 1;
+/// Transport mode: unicast negotiation with a configured master list
+pub const BASS_PTP_TRANSPORT_UNICAST: u8 = 2;
+
+/// Delay mechanism: end-to-end Delay_Req/Delay_Resp with the grandmaster (default)
+pub const BASS_PTP_DELAY_E2E: u8 = 0;
+/// Delay mechanism: peer-to-peer Pdelay with the link neighbour (multicast transport only)
+pub const BASS_PTP_DELAY_P2P: u8 = 1;
+
+/// Servo: windowed linear regression (default)
+pub const BASS_PTP_SERVO_REGRESSION: u8 = 0;
+/// Servo: proportional-integral loop
+pub const BASS_PTP_SERVO_PI: u8 = 1;
+
+/// Profile: AES67 media profile (default)
+pub const BASS_PTP_PROFILE_AES67: u8 = 0;
+/// Profile: SMPTE ST 2059-2 (default domain 127, see BASS_PTP_GetSyncMetadata)
+pub const BASS_PTP_PROFILE_SMPTE2059: u8 = 1;
+
+/// Extended client configuration for BASS_PTP_StartEx.
+///
+/// Zero-initialize and set `struct_size` to `sizeof(BASS_PTP_CONFIG)`;
+/// zero values select the defaults noted below.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct BASS_PTP_CONFIG {
+    /// Size of this struct in bytes (for versioning)
+    pub struct_size: u32,
+    /// Network interface IP as null-terminated C string
+    pub interface_ip: *const c_char,
+    /// PTP domain number (0-127)
+    pub domain: u8,
+    /// BASS_PTP_TRANSPORT_* mode
+    pub transport: u8,
+    /// Non-zero enables the master role
+    pub master_enabled: u8,
+    /// Master role priority1 (0 = 128)
+    pub priority1: u8,
+    /// Master role priority2 (0 = 128)
+    pub priority2: u8,
+    /// Master role clock class (0 = 248)
+    pub clock_class: u8,
+    /// Requested log2 Sync interval (unicast grants and master role, 0 = -3)
+    pub log_sync_interval: i8,
+    /// Requested log2 Announce interval (unicast grants and master role, 0 = 1)
+    pub log_announce_interval: i8,
+    /// Requested log2 Delay_Resp interval (unicast grants), Pdelay_Req interval (P2P) (0 = 0)
+    pub log_delay_req_interval: i8,
+    /// Unicast grant duration in seconds (0 = 300)
+    pub unicast_duration: u32,
+    /// Comma-separated master IPs for unicast mode (may be NULL otherwise)
+    pub unicast_masters: *const c_char,
+    /// BASS_PTP_DELAY_* mechanism
+    pub delay_mechanism: u8,
+    /// BASS_PTP_PROFILE_* profile; a non-default profile replaces the
+    /// log_*_interval values with the profile's message rates
+    pub profile: u8,
+}
+
+/// Start the PTP client with an extended configuration.
+///
+/// Supports hybrid (unicast Delay_Req) and full unicast (IEEE 1588 unicast
+/// negotiation against `unicast_masters`) transport, and the master role.
+/// Reference-counted like BASS_PTP_Start.
+///
+/// # Arguments
+/// * `config` - Pointer to a BASS_PTP_CONFIG structure
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if the configuration is invalid
+/// * BASS_PTP_ERROR_SOCKET if socket creation fails
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_StartEx(config: *const BASS_PTP_CONFIG) -> i32 {
+    let client_config = match config_from_ffi(config) {
+        Some(c) => c,
+        None => return BASS_PTP_ERROR_INVALID,
+    };
+
+    if client_config.validate().is_err() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+
+    match start_ptp_client_ex(client_config) {
+        Ok(()) => BASS_PTP_OK,
+        Err(_) => BASS_PTP_ERROR_SOCKET,
+    }
+}
+
+/// Convert a C configuration struct to a PtpClientConfig
+unsafe fn config_from_ffi(config: *const BASS_PTP_CONFIG) -> Option<PtpClientConfig> {
+    if config.is_null() {
+        return None;
+    }
+    let c = &*config;
+    if (c.struct_size as usize) < std::mem::size_of::<BASS_PTP_CONFIG>() || c.interface_ip.is_null() {
+        return None;
+    }
+
+    let interface: Ipv4Addr = CStr::from_ptr(c.interface_ip).to_str().ok()?.parse().ok()?;
+
+    let mut client_config = PtpClientConfig::new(interface, c.domain);
+    client_config.transport = PtpTransportMode::try_from(c.transport).ok()?;
+    if c.log_sync_interval != 0 {
+        client_config.log_sync_interval = c.log_sync_interval;
+    }
+    if c.log_announce_interval != 0 {
+        client_config.log_announce_interval = c.log_announce_interval;
+    }
+    if c.log_delay_req_interval != 0 {
+        client_config.log_delay_req_interval = c.log_delay_req_interval;
+    }
+    if c.unicast_duration != 0 {
+        client_config.unicast_duration_s = c.unicast_duration;
+    }
+    if !c.unicast_masters.is_null() {
+        let list = CStr::from_ptr(c.unicast_masters).to_str().ok()?;
+        client_config.unicast_masters = config::parse_master_list(list).ok()?;
+    }
+    client_config.delay_mechanism = PtpDelayMechanism::try_from(c.delay_mechanism).ok()?;
+    let profile = PtpProfile::try_from(c.profile).ok()?;
+
+    if c.master_enabled != 0 {
+        let defaults = PtpMasterConfig::default();
+        client_config.master = Some(PtpMasterConfig {
+            priority1: if c.priority1 != 0 { c.priority1 } else { defaults.priority1 },
+            priority2: if c.priority2 != 0 { c.priority2 } else { defaults.priority2 },
+            clock_class: if c.clock_class != 0 { c.clock_class } else { defaults.clock_class },
+            log_sync_interval: client_config.log_sync_interval,
+            log_announce_interval: client_config.log_announce_interval,
+            ..defaults
+        });
+    }
+
+    if profile != PtpProfile::Aes67 {
+        client_config.set_profile(profile);
+    }
+
+    Some(client_config)
+}
+
 /// Stop the PTP client.
 ///
 /// Decrements reference count. Only actually stops when count reaches 0.
@@ -162,7 +379,8 @@ pub unsafe extern "C" fn BASS_PTP_GetVersion() -> u32 {
 /// Get PTP state.
 ///
 /// # Returns
-/// * 0 = Disabled, 1 = Listening, 2 = Uncalibrated, 3 = Slave
+/// * 0 = Disabled, 1 = Listening, 2 = Uncalibrated, 3 = Slave, 4 = Master,
+///   5 = Holdover (grandmaster lost, running on the last frequency estimate)
 #[no_mangle]
 pub unsafe extern "C" fn BASS_PTP_GetState() -> u8 {
     get_ptp_stats()
@@ -181,6 +399,679 @@ pub unsafe extern "C" fn BASS_PTP_IsLocked() -> i32 {
         .unwrap_or(0)
 }
 
+/// Select how event messages are timestamped.
+///
+/// Applies to clients started after this call. Kernel timestamps fall back
+/// to user space when the platform or driver does not support them.
+///
+/// # Arguments
+/// * `mode` - 0 = user space, 1 = kernel software (default), 2 = hardware (NIC)
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if mode is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_SetTimestampMode(mode: u8) -> i32 {
+    if mode > TimestampMode::Hardware as u8 {
+        return BASS_PTP_ERROR_INVALID;
+    }
+    timestamping::set_timestamp_mode(TimestampMode::from(mode));
+    BASS_PTP_OK
+}
+
+/// Get the source of the timestamps currently in use.
+///
+/// # Arguments
+/// * `tx` - 0 for Sync receive timestamps, 1 for Delay_Req/Sync transmit timestamps
+///
+/// # Returns
+/// * 0 = user space, 1 = kernel software, 2 = hardware
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetTimestampSource(tx: i32) -> u8 {
+    get_ptp_stats()
+        .map(|s| {
+            if tx != 0 {
+                s.tx_timestamp_source as u8
+            } else {
+                s.rx_timestamp_source as u8
+            }
+        })
+        .unwrap_or(TimestampSource::User as u8)
+}
+
+// ============================================================================
+// Instance C API Functions
+// ============================================================================
+//
+// Additional clients, each with its own interface, domain, servo and stats.
+// They run independently of the reference-counted BASS_PTP_Start client,
+// which can be addressed as instance 0 in the getters below.
+
+/// Create an additional PTP client instance.
+///
+/// Multicast instances on different interfaces and/or domains can run side
+/// by side (and next to the default client). Hybrid and unicast transports
+/// cannot share the PTP ports with any other instance.
+///
+/// # Arguments
+/// * `config` - Pointer to a BASS_PTP_CONFIG structure
+///
+/// # Returns
+/// * Instance handle (non-zero) on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_CreateInstance(config: *const BASS_PTP_CONFIG) -> u32 {
+    match config_from_ffi(config) {
+        Some(c) => create_ptp_instance(c).unwrap_or(0),
+        None => 0,
+    }
+}
+
+/// Stop and free a PTP client instance.
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_NOT_INIT if the handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_DestroyInstance(handle: u32) -> i32 {
+    if destroy_ptp_instance(handle) {
+        BASS_PTP_OK
+    } else {
+        BASS_PTP_ERROR_NOT_INIT
+    }
+}
+
+/// Get the clock offset of an instance in nanoseconds (0 = default client).
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_InstanceGetOffset(handle: u32) -> i64 {
+    get_instance_stats(handle).map(|s| s.offset_ns).unwrap_or(0)
+}
+
+/// Get the frequency adjustment of an instance in ppm (0 = default client).
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_InstanceGetFrequencyPPM(handle: u32) -> f64 {
+    get_instance_stats(handle).map(|s| s.frequency_ppm).unwrap_or(0.0)
+}
+
+/// Get the state of an instance (0 = default client).
+///
+/// # Returns
+/// * Same values as BASS_PTP_GetState; 0 (Disabled) for unknown handles
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_InstanceGetState(handle: u32) -> u8 {
+    get_instance_stats(handle)
+        .map(|s| s.state as u8)
+        .unwrap_or(PtpState::Disabled as u8)
+}
+
+/// Check if an instance is locked (0 = default client).
+///
+/// # Returns
+/// * 1 if locked, 0 if not or if the handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_InstanceIsLocked(handle: u32) -> i32 {
+    get_instance_stats(handle)
+        .map(|s| if s.locked { 1 } else { 0 })
+        .unwrap_or(0)
+}
+
+/// Get the formatted stats string of an instance (0 = default client).
+///
+/// # Returns
+/// * Length of string written (excluding null terminator), or 0 on error
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_InstanceGetStatsString(
+    handle: u32,
+    buffer: *mut c_char,
+    buffer_size: i32,
+) -> i32 {
+    if buffer.is_null() || buffer_size <= 0 {
+        return 0;
+    }
+
+    let stats_str = match get_instance_stats(handle) {
+        Some(s) => s.format_display(),
+        None => return 0,
+    };
+    let bytes = stats_str.as_bytes();
+    let max_len = (buffer_size - 1) as usize;
+    let copy_len = bytes.len().min(max_len);
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, copy_len);
+    *buffer.add(copy_len) = 0;
+
+    copy_len as i32
+}
+
+/// Grandmaster time properties and SMPTE ST 2059-2 synchronization metadata.
+///
+/// Set `struct_size` to `sizeof(BASS_PTP_SYNC_METADATA)` before calling
+/// BASS_PTP_GetSyncMetadata.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct BASS_PTP_SYNC_METADATA {
+    /// Size of this struct in bytes (for versioning)
+    pub struct_size: u32,
+    /// TAI - UTC offset in seconds announced by the grandmaster
+    pub current_utc_offset: i16,
+    /// Non-zero if current_utc_offset is valid
+    pub utc_offset_valid: u8,
+    /// Non-zero if the grandmaster uses the PTP (TAI) timescale
+    pub ptp_timescale: u8,
+    /// Non-zero if the last minute of the current UTC day has 61 seconds
+    pub leap61: u8,
+    /// Non-zero if the last minute of the current UTC day has 59 seconds
+    pub leap59: u8,
+    /// Non-zero if time is traceable to a primary reference
+    pub time_traceable: u8,
+    /// Non-zero if frequency is traceable to a primary reference
+    pub frequency_traceable: u8,
+    /// Grandmaster time source (IEEE 1588 timeSource)
+    pub time_source: u8,
+    /// Non-zero if the SMPTE fields below are valid (grandmaster sends the TLV)
+    pub smpte_valid: u8,
+    /// SMPTE masterLockingStatus (0 = not in use, 1 = free run, 2 = cold, 3 = warm, 4 = locked)
+    pub master_locking_status: u8,
+    /// SMPTE timeAddressFlags (bit 0 = drop frame, bit 1 = color frame)
+    pub time_address_flags: u8,
+    /// Default system frame rate numerator
+    pub frame_rate_numerator: u32,
+    /// Default system frame rate denominator
+    pub frame_rate_denominator: u32,
+    /// Local time offset from PTP time in seconds
+    pub current_local_offset: i32,
+    /// Size of the next local offset change in seconds
+    pub jump_seconds: i32,
+    /// PTP time (seconds) of the next local offset change
+    pub time_of_next_jump: u64,
+    /// PTP time (seconds) of the next daily jam
+    pub time_of_next_jam: u64,
+    /// PTP time (seconds) of the previous daily jam
+    pub time_of_previous_jam: u64,
+    /// Local offset at the previous daily jam in seconds
+    pub previous_jam_local_offset: i32,
+    /// SMPTE daylightSaving flags
+    pub daylight_saving: u8,
+    /// SMPTE leapSecondJump flags
+    pub leap_second_jump: u8,
+}
+
+/// Get the grandmaster time properties and SMPTE synchronization metadata.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `metadata` - Output structure with `struct_size` set
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if metadata is null or struct_size too small
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetSyncMetadata(handle: u32, metadata: *mut BASS_PTP_SYNC_METADATA) -> i32 {
+    if metadata.is_null() || ((*metadata).struct_size as usize) < std::mem::size_of::<BASS_PTP_SYNC_METADATA>() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+    let stats = match get_instance_stats(handle) {
+        Some(s) => s,
+        None => return BASS_PTP_ERROR_NOT_INIT,
+    };
+
+    let props = stats.time_properties;
+    let smpte = stats.smpte.unwrap_or(SmpteSyncMetadata {
+        frame_rate_numerator: 0,
+        frame_rate_denominator: 0,
+        master_locking_status: smpte::LOCKING_STATUS_NOT_IN_USE,
+        ..Default::default()
+    });
+
+    *metadata = BASS_PTP_SYNC_METADATA {
+        struct_size: (*metadata).struct_size,
+        current_utc_offset: props.current_utc_offset,
+        utc_offset_valid: props.utc_offset_valid as u8,
+        ptp_timescale: props.ptp_timescale as u8,
+        leap61: props.leap61 as u8,
+        leap59: props.leap59 as u8,
+        time_traceable: props.time_traceable as u8,
+        frequency_traceable: props.frequency_traceable as u8,
+        time_source: props.time_source,
+        smpte_valid: stats.smpte.is_some() as u8,
+        master_locking_status: smpte.master_locking_status,
+        time_address_flags: smpte.time_address_flags,
+        frame_rate_numerator: smpte.frame_rate_numerator,
+        frame_rate_denominator: smpte.frame_rate_denominator,
+        current_local_offset: smpte.current_local_offset,
+        jump_seconds: smpte.jump_seconds,
+        time_of_next_jump: smpte.time_of_next_jump,
+        time_of_next_jam: smpte.time_of_next_jam,
+        time_of_previous_jam: smpte.time_of_previous_jam,
+        previous_jam_local_offset: smpte.previous_jam_local_offset,
+        daylight_saving: smpte.daylight_saving,
+        leap_second_jump: smpte.leap_second_jump,
+    };
+
+    BASS_PTP_OK
+}
+
+// ============================================================================
+// Time-of-Day API
+// ============================================================================
+
+/// Get the current time of the local clock PTP time is mapped from, in
+/// nanoseconds. This is a monotonic clock (CLOCK_MONOTONIC on Linux/macOS,
+/// QueryPerformanceCounter on Windows) with an arbitrary epoch, so stepping
+/// the system time doesn't affect it. Use this, or the same OS clock, to
+/// take timestamps for BASS_PTP_LocalToPtpTime.
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetLocalTime() -> i64 {
+    platform::get_timestamp_ns()
+}
+
+/// Get the current PTP time.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `tai_ns` - Receives PTP time in nanoseconds (TAI on the PTP timescale), may be null
+/// * `utc_ns` - Receives UTC in nanoseconds (announced UTC offset applied), may be null
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_NOT_INIT if the client is not running or not yet synchronized
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetTime(handle: u32, tai_ns: *mut i64, utc_ns: *mut i64) -> i32 {
+    BASS_PTP_LocalToPtpTime(handle, platform::get_monotonic_ns(), tai_ns, utc_ns)
+}
+
+/// Convert a local timestamp (see BASS_PTP_GetLocalTime) to PTP time.
+///
+/// Between Sync messages the PTP time is extrapolated with the servo's
+/// frequency estimate.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `local_ns` - Local timestamp in nanoseconds
+/// * `tai_ns` - Receives PTP time in nanoseconds (TAI on the PTP timescale), may be null
+/// * `utc_ns` - Receives UTC in nanoseconds (announced UTC offset applied), may be null
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_NOT_INIT if the client is not running or not yet synchronized
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_LocalToPtpTime(
+    handle: u32,
+    local_ns: i64,
+    tai_ns: *mut i64,
+    utc_ns: *mut i64,
+) -> i32 {
+    let time = match local_to_ptp_time(handle, local_ns) {
+        Some(t) => t,
+        None => return BASS_PTP_ERROR_NOT_INIT,
+    };
+    if !tai_ns.is_null() {
+        *tai_ns = time.tai_ns;
+    }
+    if !utc_ns.is_null() {
+        *utc_ns = time.utc_ns;
+    }
+    BASS_PTP_OK
+}
+
+/// Get holdover information (see state 5, Holdover).
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `duration_ms` - Receives the time in holdover in milliseconds, may be null
+/// * `error_ns` - Receives the estimated accumulated time error in nanoseconds, may be null
+///
+/// Both values are 0 when the client is not in holdover.
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetHoldover(handle: u32, duration_ms: *mut u64, error_ns: *mut i64) -> i32 {
+    let stats = match get_instance_stats(handle) {
+        Some(s) => s,
+        None => return BASS_PTP_ERROR_NOT_INIT,
+    };
+    if !duration_ms.is_null() {
+        *duration_ms = stats.holdover_duration_ms;
+    }
+    if !error_ns.is_null() {
+        *error_ns = stats.holdover_error_ns;
+    }
+    BASS_PTP_OK
+}
+
+/// Get the clock identity of the grandmaster the client follows.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+///
+/// # Returns
+/// * The 8-byte clock identity as a big-endian number
+/// * 0 if the client/instance is not running or has no grandmaster yet
+#[no_mangle]
+pub extern "C" fn BASS_PTP_GetGrandmasterId(handle: u32) -> u64 {
+    match get_instance_stats(handle) {
+        Some(s) if !matches!(s.state, PtpState::Disabled | PtpState::Listening) => s.grandmaster_id.to_u64(),
+        _ => 0,
+    }
+}
+
+// ============================================================================
+// Servo API
+// ============================================================================
+
+/// Servo algorithm and tuning.
+///
+/// Set `struct_size` to `sizeof(BASS_PTP_SERVO_CONFIG)`. Read the current
+/// values with BASS_PTP_GetServoConfig and change only what is needed.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct BASS_PTP_SERVO_CONFIG {
+    /// Size of this struct in bytes (for versioning)
+    pub struct_size: u32,
+    /// BASS_PTP_SERVO_* algorithm
+    pub servo: u8,
+    /// Time constant in seconds (0 = algorithm default)
+    pub time_constant: f64,
+    /// Largest deviation from the expected offset counted as locked (ns)
+    pub lock_threshold_ns: i64,
+    /// Largest frequency estimate counted as locked (ppm)
+    pub lock_frequency_ppm: f64,
+    /// Consecutive samples in lock before locking
+    pub lock_samples: u32,
+    /// Consecutive samples out of lock before unlocking
+    pub unlock_samples: u32,
+    /// Deviation beyond which a Sync or path delay measurement is rejected
+    /// (ns, 0 = no outlier rejection or step detection)
+    pub outlier_threshold_ns: i64,
+    /// Consecutive consistent outliers taken as a grandmaster time step
+    pub step_samples: u32,
+    /// Longest holdover after the grandmaster disappears in seconds (0 = unlimited)
+    pub holdover_timeout: u32,
+}
+
+/// Change the servo of a running client.
+///
+/// Switching algorithm or time constant restarts the frequency estimation;
+/// threshold changes apply immediately.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `config` - Servo configuration with `struct_size` set
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if config is null or invalid
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_SetServoConfig(handle: u32, config: *const BASS_PTP_SERVO_CONFIG) -> i32 {
+    if config.is_null() || ((*config).struct_size as usize) < std::mem::size_of::<BASS_PTP_SERVO_CONFIG>() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+    let c = &*config;
+    let kind = match ServoKind::try_from(c.servo) {
+        Ok(k) => k,
+        Err(()) => return BASS_PTP_ERROR_INVALID,
+    };
+    let servo = ServoConfig {
+        kind,
+        time_constant_s: c.time_constant,
+        lock_threshold_ns: c.lock_threshold_ns,
+        lock_frequency_ppb: c.lock_frequency_ppm * 1_000.0,
+        lock_samples: c.lock_samples,
+        unlock_samples: c.unlock_samples,
+        outlier_threshold_ns: c.outlier_threshold_ns,
+        step_samples: c.step_samples,
+        holdover_timeout_s: c.holdover_timeout,
+    };
+    if servo.validate().is_err() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+
+    match set_servo_config(handle, servo) {
+        Ok(()) => BASS_PTP_OK,
+        Err(_) => BASS_PTP_ERROR_NOT_INIT,
+    }
+}
+
+/// Get the servo configuration of a running client.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `config` - Output structure with `struct_size` set
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if config is null or struct_size too small
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetServoConfig(handle: u32, config: *mut BASS_PTP_SERVO_CONFIG) -> i32 {
+    if config.is_null() || ((*config).struct_size as usize) < std::mem::size_of::<BASS_PTP_SERVO_CONFIG>() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+    let servo = match get_servo_config(handle) {
+        Some(s) => s,
+        None => return BASS_PTP_ERROR_NOT_INIT,
+    };
+
+    *config = BASS_PTP_SERVO_CONFIG {
+        struct_size: (*config).struct_size,
+        servo: servo.kind as u8,
+        time_constant: servo.time_constant_s,
+        lock_threshold_ns: servo.lock_threshold_ns,
+        lock_frequency_ppm: servo.lock_frequency_ppb / 1_000.0,
+        lock_samples: servo.lock_samples,
+        unlock_samples: servo.unlock_samples,
+        outlier_threshold_ns: servo.outlier_threshold_ns,
+        step_samples: servo.step_samples,
+        holdover_timeout: servo.holdover_timeout_s,
+    };
+    BASS_PTP_OK
+}
+
+// ============================================================================
+// History API
+// ============================================================================
+
+/// History export format: CSV (one line per sample)
+pub const BASS_PTP_HISTORY_CSV: u32 = 0;
+/// History export format: JSON (metrics, offset histogram and samples)
+pub const BASS_PTP_HISTORY_JSON: u32 = 1;
+
+/// Number of taus in BASS_PTP_HISTORY_METRICS
+pub const BASS_PTP_HISTORY_TAUS: usize = history::HISTORY_TAUS.len();
+
+/// Metrics over the offset/delay history.
+///
+/// Set `struct_size` to `sizeof(BASS_PTP_HISTORY_METRICS)`.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct BASS_PTP_HISTORY_METRICS {
+    /// Size of this struct in bytes (for versioning)
+    pub struct_size: u32,
+    /// Number of samples
+    pub count: u32,
+    /// Time covered by the samples in seconds
+    pub duration: f64,
+    pub offset_mean_ns: f64,
+    pub offset_stddev_ns: f64,
+    pub offset_min_ns: i64,
+    pub offset_max_ns: i64,
+    /// Peak-to-peak offset over the history (max - min, ns)
+    pub peak_to_peak_ns: i64,
+    pub path_delay_mean_ns: f64,
+    pub path_delay_stddev_ns: f64,
+    pub frequency_mean_ppm: f64,
+    pub frequency_stddev_ppm: f64,
+    /// Observation intervals in seconds (1, 2, 5 ... 1000)
+    pub tau: [f64; BASS_PTP_HISTORY_TAUS],
+    /// Allan deviation at each tau (0 = history too short)
+    pub adev: [f64; BASS_PTP_HISTORY_TAUS],
+    /// Time deviation at each tau in ns (0 = history too short)
+    pub tdev_ns: [f64; BASS_PTP_HISTORY_TAUS],
+    /// Maximum time interval error at each tau in ns (0 = history too short)
+    pub mtie_ns: [f64; BASS_PTP_HISTORY_TAUS],
+}
+
+/// Get metrics over the offset/delay history of a client.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `metrics` - Output structure with `struct_size` set
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if metrics is null or struct_size too small
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetHistoryMetrics(handle: u32, metrics: *mut BASS_PTP_HISTORY_METRICS) -> i32 {
+    if metrics.is_null() || ((*metrics).struct_size as usize) < std::mem::size_of::<BASS_PTP_HISTORY_METRICS>() {
+        return BASS_PTP_ERROR_INVALID;
+    }
+    let m = match get_instance_history(handle) {
+        Some(h) => h.metrics(),
+        None => return BASS_PTP_ERROR_NOT_INIT,
+    };
+
+    let mut adev = [0.0; BASS_PTP_HISTORY_TAUS];
+    let mut tdev_ns = [0.0; BASS_PTP_HISTORY_TAUS];
+    let mut mtie_ns = [0.0; BASS_PTP_HISTORY_TAUS];
+    for p in &m.stability {
+        // Report each point at the nominal tau it was computed for
+        if let Some(i) = history::HISTORY_TAUS
+            .iter()
+            .position(|&t| (t - p.tau_s).abs() <= m.interval_s)
+        {
+            adev[i] = p.adev;
+            tdev_ns[i] = p.tdev_ns;
+            mtie_ns[i] = p.mtie_ns;
+        }
+    }
+
+    *metrics = BASS_PTP_HISTORY_METRICS {
+        struct_size: (*metrics).struct_size,
+        count: m.count as u32,
+        duration: m.duration_s,
+        offset_mean_ns: m.offset_mean_ns,
+        offset_stddev_ns: m.offset_stddev_ns,
+        offset_min_ns: m.offset_min_ns,
+        offset_max_ns: m.offset_max_ns,
+        peak_to_peak_ns: m.peak_to_peak_ns,
+        path_delay_mean_ns: m.path_delay_mean_ns,
+        path_delay_stddev_ns: m.path_delay_stddev_ns,
+        frequency_mean_ppm: m.frequency_mean_ppb / 1_000.0,
+        frequency_stddev_ppm: m.frequency_stddev_ppb / 1_000.0,
+        tau: history::HISTORY_TAUS,
+        adev,
+        tdev_ns,
+        mtie_ns,
+    };
+    BASS_PTP_OK
+}
+
+/// Export the offset/delay history of a client.
+///
+/// # Arguments
+/// * `handle` - Instance handle (0 = default client)
+/// * `format` - BASS_PTP_HISTORY_CSV or BASS_PTP_HISTORY_JSON
+/// * `buffer` - Output buffer
+/// * `buffer_size` - Size of the buffer in bytes
+///
+/// # Returns
+/// * Length of the export (excluding null terminator). It is only written
+///   if it fits; compare against `buffer_size` and retry with a larger buffer.
+/// * -1 on error (unknown handle or format)
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_GetHistory(handle: u32, format: u32, buffer: *mut c_char, buffer_size: i32) -> i32 {
+    let history = match get_instance_history(handle) {
+        Some(h) => h,
+        None => return -1,
+    };
+    let text = match format {
+        BASS_PTP_HISTORY_CSV => history.to_csv(),
+        BASS_PTP_HISTORY_JSON => history.to_json(),
+        _ => return -1,
+    };
+    let bytes = text.as_bytes();
+
+    if !buffer.is_null() && buffer_size > 0 && bytes.len() < buffer_size as usize {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, bytes.len());
+        *buffer.add(bytes.len()) = 0; // Null terminator
+    }
+
+    bytes.len() as i32
+}
+
+/// Discard the offset/delay history of a client (e.g. before a measurement).
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_NOT_INIT if the client/instance is not running
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_ClearHistory(handle: u32) -> i32 {
+    if clear_instance_history(handle) {
+        BASS_PTP_OK
+    } else {
+        BASS_PTP_ERROR_NOT_INIT
+    }
+}
+
+// ============================================================================
+// Management API
+// ============================================================================
+
+/// Query the data sets of every PTP clock in a domain.
+///
+/// Sends IEEE 1588 management GET requests (DEFAULT, CURRENT, PARENT,
+/// TIME_PROPERTIES and PORT data sets) to all clocks in the domain and
+/// returns their responses as JSON:
+/// `{"domain":0,"clocks":[{"port_identity":{...},"address":"...","DEFAULT_DATA_SET":{...},...}]}`.
+/// Blocks for `timeout_ms`. Clocks without management support don't appear.
+///
+/// # Arguments
+/// * `interface_ip` - Network interface IP as null-terminated C string
+/// * `domain` - PTP domain number
+/// * `timeout_ms` - Time to collect responses in milliseconds
+/// * `buffer` - Output buffer for the JSON
+/// * `buffer_size` - Size of the buffer in bytes
+///
+/// # Returns
+/// * Length of the JSON (excluding null terminator). It is only written if
+///   it fits; compare against `buffer_size` and retry with a larger buffer.
+/// * -1 on error (invalid arguments or socket failure)
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_ManagementQuery(
+    interface_ip: *const c_char,
+    domain: u8,
+    timeout_ms: u32,
+    buffer: *mut c_char,
+    buffer_size: i32,
+) -> i32 {
+    if interface_ip.is_null() {
+        return -1;
+    }
+    let interface: Ipv4Addr = match CStr::from_ptr(interface_ip).to_str().ok().and_then(|s| s.parse().ok()) {
+        Some(ip) => ip,
+        None => return -1,
+    };
+
+    let timeout = std::time::Duration::from_millis(timeout_ms as u64);
+    let clocks = match management::query_clocks(interface, domain, 0, timeout) {
+        Ok(c) => c,
+        Err(_) => return -1,
+    };
+    let json = management::format_json(domain, &clocks);
+    let bytes = json.as_bytes();
+
+    if !buffer.is_null() && buffer_size > 0 && bytes.len() < buffer_size as usize {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, bytes.len());
+        *buffer.add(bytes.len()) = 0; // Null terminator
+    }
+
+    bytes.len() as i32
+}
+
 // ============================================================================
 // Timer C API Functions
 // ============================================================================
@@ -283,21 +1174,103 @@ pub unsafe extern "C" fn BASS_PTP_TimerIsPLLEnabled() -> i32 {
     if timer::is_pll_enabled() { 1 } else { 0 }
 }
 
+/// Create an independent precision timer.
+///
+/// Any number of timers can run at once, each with its own interval,
+/// callback and PLL setting (enabled by default). The timer starts ticking
+/// immediately.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick (can be NULL)
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle, or 0 if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_CreateTimer(
+    interval_ms: u32,
+    callback: Option<BASS_PTP_TimerProc>,
+    user: *mut c_void,
+) -> u32 {
+    timer::create_timer(interval_ms, callback, user).unwrap_or(0)
+}
+
+/// Stop a timer created with BASS_PTP_CreateTimer and free its handle.
+///
+/// Can be called from the timer's own callback.
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_DestroyTimer(handle: u32) -> i32 {
+    if timer::destroy_timer(handle) { BASS_PTP_OK } else { BASS_PTP_ERROR_INVALID }
+}
+
+/// Set a timer's interval (can change while running).
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if handle is unknown or interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_TimerSetIntervalEx(handle: u32, interval_ms: u32) -> i32 {
+    if timer::set_timer_interval(handle, interval_ms) { BASS_PTP_OK } else { BASS_PTP_ERROR_INVALID }
+}
+
+/// Get a timer's interval.
+///
+/// # Returns
+/// * Interval in milliseconds, or 0 if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_TimerGetIntervalEx(handle: u32) -> u32 {
+    timer::timer_interval(handle).unwrap_or(0)
+}
+
+/// Enable or disable PLL frequency adjustment of a timer.
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_TimerSetPLLEx(handle: u32, enabled: i32) -> i32 {
+    if timer::set_timer_pll(handle, enabled != 0) { BASS_PTP_OK } else { BASS_PTP_ERROR_INVALID }
+}
+
+/// Align a timer's ticks to PTP time.
+///
+/// Each tick then lands on a multiple of `period_us` on the PTP timescale,
+/// so timers on different machines tick in phase (e.g. interval 20 ms,
+/// period 5000 us). The period should be the interval or a divisor of it.
+/// Until the default client is synchronized the timer ticks unaligned.
+///
+/// # Arguments
+/// * `handle` - Timer handle
+/// * `period_us` - Alignment period in microseconds (0 = no alignment)
+///
+/// # Returns
+/// * BASS_PTP_OK on success
+/// * BASS_PTP_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_PTP_TimerSetAlignment(handle: u32, period_us: u32) -> i32 {
+    if timer::set_timer_alignment(handle, period_us) { BASS_PTP_OK } else { BASS_PTP_ERROR_INVALID }
+}
+
 // ============================================================================
-// Windows DLL Entry Point
+// Windows DLL Entry Point (left out when linked into a plugin)
 // ============================================================================
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_ATTACH: u32 = 1;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_DETACH: u32 = 0;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type BOOL = i32;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type DWORD = u32;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 #[no_mangle]
 pub unsafe extern "system" fn DllMain(
     _hinst: *mut c_void,
@@ -333,3 +1306,49 @@ static FINI: extern "C" fn() = {
     }
     fini
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ffi_config(interface_ip: &CStr, log_interval: i8) -> BASS_PTP_CONFIG {
+        BASS_PTP_CONFIG {
+            struct_size: std::mem::size_of::<BASS_PTP_CONFIG>() as u32,
+            interface_ip: interface_ip.as_ptr(),
+            domain: 0,
+            transport: BASS_PTP_TRANSPORT_MULTICAST,
+            master_enabled: 1,
+            priority1: 0,
+            priority2: 0,
+            clock_class: 0,
+            log_sync_interval: log_interval,
+            log_announce_interval: log_interval,
+            log_delay_req_interval: log_interval,
+            unicast_duration: 0,
+            unicast_masters: std::ptr::null(),
+            delay_mechanism: BASS_PTP_DELAY_E2E,
+            profile: BASS_PTP_PROFILE_AES67,
+        }
+    }
+
+    #[test]
+    fn test_config_from_ffi_log_intervals() {
+        let ip = c"127.0.0.1";
+
+        // Zero-initialized: the defaults
+        let config = unsafe { config_from_ffi(&ffi_config(ip, 0)) }.unwrap();
+        assert_eq!(config.log_sync_interval, -3);
+        assert_eq!(config.log_announce_interval, 1);
+        assert_eq!(config.log_delay_req_interval, 0);
+        assert_eq!(config.master.unwrap().log_sync_interval, -3);
+
+        let config = unsafe { config_from_ffi(&ffi_config(ip, -2)) }.unwrap();
+        assert_eq!(config.log_sync_interval, -2);
+        assert_eq!(config.master.unwrap().log_announce_interval, -2);
+
+        // Too small for the current struct
+        let mut short = ffi_config(ip, 0);
+        short.struct_size -= 1;
+        assert!(unsafe { config_from_ffi(&short) }.is_none());
+    }
+}
@@ -0,0 +1,464 @@
+//! PTP management client (IEEE 1588-2008 clause 15).
+//!
+//! Sends GET requests for the standard data sets to every clock in a domain
+//! (target port identity all ones, multicast on the general port) and
+//! collects the RESPONSE messages until a timeout. This lets a monitoring
+//! tool see what each grandmaster and boundary clock believes about itself:
+//! its priorities and quality, the parent it follows, its offset and path
+//! delay, and its UTC offset.
+//!
+//! Responses to multicast requests are multicast as well, so the query works
+//! alongside a running PTP client on the same host. Devices that answer by
+//! unicast are only seen if no other socket on port 320 picks up the reply.
+
+use std::fmt::Write;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::{Duration, Instant};
+
+use crate::client::{create_ptp_socket, generate_local_port_identity, PTP_GENERAL_PORT, PTP_MULTICAST_ADDR};
+use crate::messages::*;
+
+/// Data sets requested from every clock
+pub const QUERY_DATA_SETS: [u16; 5] = [
+    MANAGEMENT_DEFAULT_DATA_SET,
+    MANAGEMENT_CURRENT_DATA_SET,
+    MANAGEMENT_PARENT_DATA_SET,
+    MANAGEMENT_TIME_PROPERTIES_DATA_SET,
+    MANAGEMENT_PORT_DATA_SET,
+];
+
+/// Name of a management ID as used in IEEE 1588 (e.g. "DEFAULT_DATA_SET")
+pub fn management_id_name(id: u16) -> &'static str {
+    match id {
+        MANAGEMENT_DEFAULT_DATA_SET => "DEFAULT_DATA_SET",
+        MANAGEMENT_CURRENT_DATA_SET => "CURRENT_DATA_SET",
+        MANAGEMENT_PARENT_DATA_SET => "PARENT_DATA_SET",
+        MANAGEMENT_TIME_PROPERTIES_DATA_SET => "TIME_PROPERTIES_DATA_SET",
+        MANAGEMENT_PORT_DATA_SET => "PORT_DATA_SET",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Responses from one PTP port
+#[derive(Debug, Clone)]
+pub struct ManagedClock {
+    pub port_identity: PortIdentity,
+    /// Address the responses came from
+    pub address: Ipv4Addr,
+    /// Decoded data sets and errors, in arrival order
+    pub data: Vec<ManagementData>,
+}
+
+/// Query the standard data sets of every clock in `domain`.
+///
+/// Requests pass through up to `boundary_hops` boundary clocks. Blocks for
+/// `timeout`; clocks that didn't answer any request are not reported.
+pub fn query_clocks(
+    interface: Ipv4Addr,
+    domain: u8,
+    boundary_hops: u8,
+    timeout: Duration,
+) -> Result<Vec<ManagedClock>, String> {
+    let socket = create_ptp_socket(interface, PTP_GENERAL_PORT, false)?;
+    let local_port = generate_local_port_identity();
+    let dest = SocketAddrV4::new(PTP_MULTICAST_ADDR, PTP_GENERAL_PORT);
+
+    let mut sequence_ids = Vec::with_capacity(QUERY_DATA_SETS.len());
+    for (seq, &id) in QUERY_DATA_SETS.iter().enumerate() {
+        let request = ManagementMessage::new_get(
+            local_port,
+            seq as u16,
+            domain,
+            PortIdentity::ALL,
+            boundary_hops,
+            id,
+        );
+        socket
+            .send_to(&request.to_bytes(), dest)
+            .map_err(|e| format!("Failed to send management request: {}", e))?;
+        sequence_ids.push(seq as u16);
+    }
+
+    let mut clocks: Vec<ManagedClock> = Vec::new();
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1500];
+
+    while Instant::now() < deadline {
+        let (len, src) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(ref e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                continue
+            }
+            Err(e) => return Err(format!("Failed to receive management response: {}", e)),
+        };
+
+        let msg = match ManagementMessage::parse(&buf[..len]) {
+            Some(m) => m,
+            None => continue,
+        };
+        // Only responses to our own requests (our GETs loop back too)
+        if msg.action != ManagementAction::Response
+            || msg.header.domain_number != domain
+            || msg.target_port_identity != local_port
+            || !sequence_ids.contains(&msg.header.sequence_id)
+        {
+            continue;
+        }
+        let data = match msg.data() {
+            Some(d) => d,
+            None => continue,
+        };
+        let address = match src {
+            SocketAddr::V4(a) => *a.ip(),
+            SocketAddr::V6(_) => continue,
+        };
+
+        let source = msg.header.source_port_identity;
+        let clock = match clocks.iter().position(|c| c.port_identity == source) {
+            Some(i) => &mut clocks[i],
+            None => {
+                clocks.push(ManagedClock {
+                    port_identity: source,
+                    address,
+                    data: Vec::new(),
+                });
+                clocks.last_mut().unwrap()
+            }
+        };
+        if !clock.data.contains(&data) {
+            clock.data.push(data);
+        }
+    }
+
+    Ok(clocks)
+}
+
+/// Format query results as JSON
+pub fn format_json(domain: u8, clocks: &[ManagedClock]) -> String {
+    let mut out = String::new();
+    let _ = write!(out, "{{\"domain\":{},\"clocks\":[", domain);
+    for (i, clock) in clocks.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"port_identity\":{},\"address\":\"{}\"",
+            port_identity_json(&clock.port_identity),
+            clock.address
+        );
+        let mut errors = Vec::new();
+        for data in &clock.data {
+            match data {
+                ManagementData::Error {
+                    error_id,
+                    management_id,
+                    display_data,
+                } => errors.push(format!(
+                    "{{\"management_id\":\"{}\",\"error_id\":{},\"display_data\":\"{}\"}}",
+                    management_id_name(*management_id),
+                    error_id,
+                    escape_json(display_data)
+                )),
+                _ => {
+                    out.push(',');
+                    write_data_set(&mut out, data);
+                }
+            }
+        }
+        if !errors.is_empty() {
+            let _ = write!(out, ",\"errors\":[{}]", errors.join(","));
+        }
+        out.push('}');
+    }
+    out.push_str("]}");
+    out
+}
+
+fn write_data_set(out: &mut String, data: &ManagementData) {
+    let _ = match data {
+        ManagementData::DefaultDataSet {
+            two_step,
+            slave_only,
+            number_ports,
+            priority1,
+            clock_quality,
+            priority2,
+            clock_identity,
+            domain_number,
+        } => write!(
+            out,
+            "\"DEFAULT_DATA_SET\":{{\"two_step\":{},\"slave_only\":{},\"number_ports\":{},\
+             \"priority1\":{},\"clock_quality\":{},\"priority2\":{},\"clock_identity\":\"{}\",\
+             \"domain_number\":{}}}",
+            two_step,
+            slave_only,
+            number_ports,
+            priority1,
+            clock_quality_json(clock_quality),
+            priority2,
+            clock_identity.to_hex_string(),
+            domain_number
+        ),
+        ManagementData::CurrentDataSet {
+            steps_removed,
+            offset_from_master_ns,
+            mean_path_delay_ns,
+        } => write!(
+            out,
+            "\"CURRENT_DATA_SET\":{{\"steps_removed\":{},\"offset_from_master_ns\":{},\
+             \"mean_path_delay_ns\":{}}}",
+            steps_removed, offset_from_master_ns, mean_path_delay_ns
+        ),
+        ManagementData::ParentDataSet {
+            parent_port_identity,
+            parent_stats,
+            observed_parent_offset_scaled_log_variance,
+            observed_parent_clock_phase_change_rate,
+            grandmaster_priority1,
+            grandmaster_clock_quality,
+            grandmaster_priority2,
+            grandmaster_identity,
+        } => write!(
+            out,
+            "\"PARENT_DATA_SET\":{{\"parent_port_identity\":{},\"parent_stats\":{},\
+             \"observed_parent_offset_scaled_log_variance\":{},\
+             \"observed_parent_clock_phase_change_rate\":{},\"grandmaster_priority1\":{},\
+             \"grandmaster_clock_quality\":{},\"grandmaster_priority2\":{},\
+             \"grandmaster_identity\":\"{}\"}}",
+            port_identity_json(parent_port_identity),
+            parent_stats,
+            observed_parent_offset_scaled_log_variance,
+            observed_parent_clock_phase_change_rate,
+            grandmaster_priority1,
+            clock_quality_json(grandmaster_clock_quality),
+            grandmaster_priority2,
+            grandmaster_identity.to_hex_string()
+        ),
+        ManagementData::TimePropertiesDataSet {
+            current_utc_offset,
+            flags,
+            time_source,
+        } => write!(
+            out,
+            "\"TIME_PROPERTIES_DATA_SET\":{{\"current_utc_offset\":{},\"leap61\":{},\"leap59\":{},\
+             \"current_utc_offset_valid\":{},\"ptp_timescale\":{},\"time_traceable\":{},\
+             \"frequency_traceable\":{},\"time_source\":{}}}",
+            current_utc_offset,
+            (flags & 0x01) != 0,
+            (flags & 0x02) != 0,
+            (flags & 0x04) != 0,
+            (flags & 0x08) != 0,
+            (flags & 0x10) != 0,
+            (flags & 0x20) != 0,
+            time_source
+        ),
+        ManagementData::PortDataSet {
+            port_identity,
+            port_state,
+            log_min_delay_req_interval,
+            peer_mean_path_delay_ns,
+            log_announce_interval,
+            announce_receipt_timeout,
+            log_sync_interval,
+            delay_mechanism,
+            log_min_pdelay_req_interval,
+            version_number,
+        } => write!(
+            out,
+            "\"PORT_DATA_SET\":{{\"port_identity\":{},\"port_state\":\"{}\",\
+             \"log_min_delay_req_interval\":{},\"peer_mean_path_delay_ns\":{},\
+             \"log_announce_interval\":{},\"announce_receipt_timeout\":{},\
+             \"log_sync_interval\":{},\"delay_mechanism\":\"{}\",\
+             \"log_min_pdelay_req_interval\":{},\"version_number\":{}}}",
+            port_identity_json(port_identity),
+            port_state_name(*port_state),
+            log_min_delay_req_interval,
+            peer_mean_path_delay_ns,
+            log_announce_interval,
+            announce_receipt_timeout,
+            log_sync_interval,
+            delay_mechanism_name(*delay_mechanism),
+            log_min_pdelay_req_interval,
+            version_number
+        ),
+        ManagementData::Error { .. } => Ok(()),
+    };
+}
+
+fn port_identity_json(p: &PortIdentity) -> String {
+    format!(
+        "{{\"clock_identity\":\"{}\",\"port_number\":{}}}",
+        p.clock_identity.to_hex_string(),
+        p.port_number
+    )
+}
+
+fn clock_quality_json(q: &ClockQuality) -> String {
+    format!(
+        "{{\"clock_class\":{},\"clock_accuracy\":{},\"offset_scaled_log_variance\":{}}}",
+        q.clock_class, q.clock_accuracy, q.offset_scaled_log_variance
+    )
+}
+
+/// IEEE 1588 portState enumeration
+fn port_state_name(state: u8) -> &'static str {
+    match state {
+        1 => "INITIALIZING",
+        2 => "FAULTY",
+        3 => "DISABLED",
+        4 => "LISTENING",
+        5 => "PRE_MASTER",
+        6 => "MASTER",
+        7 => "PASSIVE",
+        8 => "UNCALIBRATED",
+        9 => "SLAVE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// IEEE 1588 delayMechanism enumeration
+fn delay_mechanism_name(mechanism: u8) -> &'static str {
+    match mechanism {
+        1 => "E2E",
+        2 => "P2P",
+        0xFE => "DISABLED",
+        _ => "UNKNOWN",
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(id: u8) -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([id; 8]),
+            port_number: 1,
+        }
+    }
+
+    /// RESPONSE carrying `data_field` for `id`, as a device would send it
+    fn response(source: PortIdentity, target: PortIdentity, id: u16, data_field: &[u8]) -> Vec<u8> {
+        let mut msg = ManagementMessage::new_get(source, 7, 0, target, 0, id);
+        msg.action = ManagementAction::Response;
+        let tlv = msg.tlv.as_mut().unwrap();
+        tlv.value.extend_from_slice(data_field);
+        msg.to_bytes()
+    }
+
+    #[test]
+    fn test_get_round_trip() {
+        let get = ManagementMessage::new_get(port(1), 3, 5, PortIdentity::ALL, 2, MANAGEMENT_PARENT_DATA_SET);
+        let bytes = get.to_bytes();
+        assert_eq!(bytes.len(), ManagementMessage::BASE_SIZE + 6);
+
+        let parsed = match PtpMessage::parse(&bytes) {
+            Some(PtpMessage::Management(m)) => m,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(parsed.header.domain_number, 5);
+        assert_eq!(parsed.header.sequence_id, 3);
+        assert_eq!(parsed.target_port_identity, PortIdentity::ALL);
+        assert_eq!(parsed.starting_boundary_hops, 2);
+        assert_eq!(parsed.boundary_hops, 2);
+        assert_eq!(parsed.action, ManagementAction::Get);
+        assert_eq!(parsed.management_id(), Some(MANAGEMENT_PARENT_DATA_SET));
+    }
+
+    #[test]
+    fn test_decode_current_data_set() {
+        let mut field = Vec::new();
+        field.extend_from_slice(&1u16.to_be_bytes());
+        field.extend_from_slice(&((-1500i64) << 16).to_be_bytes());
+        field.extend_from_slice(&(25_000i64 << 16).to_be_bytes());
+
+        let bytes = response(port(2), port(1), MANAGEMENT_CURRENT_DATA_SET, &field);
+        let msg = ManagementMessage::parse(&bytes).unwrap();
+        assert_eq!(msg.action, ManagementAction::Response);
+        assert_eq!(
+            msg.data(),
+            Some(ManagementData::CurrentDataSet {
+                steps_removed: 1,
+                offset_from_master_ns: -1500,
+                mean_path_delay_ns: 25_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_error_status() {
+        let mut msg = ManagementMessage::new_get(port(2), 0, 0, port(1), 0, 0);
+        msg.action = ManagementAction::Response;
+        let mut value = Vec::new();
+        value.extend_from_slice(&0x0002u16.to_be_bytes()); // NO_SUCH_ID
+        value.extend_from_slice(&MANAGEMENT_PORT_DATA_SET.to_be_bytes());
+        value.extend_from_slice(&[0; 4]);
+        value.extend_from_slice(&[2, b'n', b'o']);
+        value.push(0); // pad to even length
+        msg.tlv = Some(Tlv {
+            tlv_type: TLV_MANAGEMENT_ERROR_STATUS,
+            value,
+        });
+
+        let parsed = ManagementMessage::parse(&msg.to_bytes()).unwrap();
+        assert_eq!(parsed.management_id(), Some(MANAGEMENT_PORT_DATA_SET));
+        assert_eq!(
+            parsed.data(),
+            Some(ManagementData::Error {
+                error_id: 2,
+                management_id: MANAGEMENT_PORT_DATA_SET,
+                display_data: "no".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_format_json() {
+        let clock = ManagedClock {
+            port_identity: port(0xAB),
+            address: Ipv4Addr::new(192, 168, 1, 10),
+            data: vec![
+                ManagementData::TimePropertiesDataSet {
+                    current_utc_offset: 37,
+                    flags: 0x0C,
+                    time_source: 0x20,
+                },
+                ManagementData::Error {
+                    error_id: 2,
+                    management_id: MANAGEMENT_PORT_DATA_SET,
+                    display_data: "say \"hi\"".to_string(),
+                },
+            ],
+        };
+
+        let json = format_json(0, &[clock]);
+        assert_eq!(
+            json,
+            "{\"domain\":0,\"clocks\":[{\"port_identity\":{\"clock_identity\":\"abababababababab\",\
+             \"port_number\":1},\"address\":\"192.168.1.10\",\"TIME_PROPERTIES_DATA_SET\":\
+             {\"current_utc_offset\":37,\"leap61\":false,\"leap59\":false,\
+             \"current_utc_offset_valid\":true,\"ptp_timescale\":true,\"time_traceable\":false,\
+             \"frequency_traceable\":false,\"time_source\":32},\"errors\":[{\"management_id\":\
+             \"PORT_DATA_SET\",\"error_id\":2,\"display_data\":\"say \\\"hi\\\"\"}]}]}"
+        );
+    }
+}
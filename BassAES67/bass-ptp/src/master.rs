@@ -0,0 +1,333 @@
+//! Software PTP master (grandmaster) role.
+//!
+//! Lets a BASS machine clock a small AES67 network when no hardware
+//! grandmaster is present. The master sends Announce, Sync and Follow_Up
+//! (two-step) from the local system clock and answers Delay_Req.
+//!
+//! The master takes part in the Best Master Clock Algorithm (BMCA): as soon
+//! as an Announce from a better clock is seen, it steps down and becomes a
+//! slave of that clock. When that clock disappears, it takes over again.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::messages::*;
+
+/// Current TAI - UTC offset in seconds (valid since 2017-01-01)
+pub const DEFAULT_UTC_OFFSET: i16 = 37;
+
+/// Default clock class (free-running, no external reference)
+pub const CLOCK_CLASS_DEFAULT: u8 = 248;
+
+/// Clock accuracy "unknown"
+pub const CLOCK_ACCURACY_UNKNOWN: u8 = 0xFE;
+
+/// Announce receipt timeout in announce intervals (IEEE 1588 default)
+pub const ANNOUNCE_RECEIPT_TIMEOUT: u32 = 3;
+
+/// Master role configuration
+#[derive(Debug, Clone, Copy)]
+pub struct PtpMasterConfig {
+    /// BMCA priority1 (lower wins, default 128)
+    pub priority1: u8,
+    /// BMCA priority2 (lower wins, default 128)
+    pub priority2: u8,
+    /// Advertised clock class (default 248)
+    pub clock_class: u8,
+    /// Advertised clock accuracy (default 0xFE = unknown)
+    pub clock_accuracy: u8,
+    /// Advertised offsetScaledLogVariance (default 0xFFFF = not computed)
+    pub offset_scaled_log_variance: u16,
+    /// log2 of the Sync interval in seconds (default -3 = 125ms, AES67 media profile)
+    pub log_sync_interval: i8,
+    /// log2 of the Announce interval in seconds (default 1 = 2s)
+    pub log_announce_interval: i8,
+    /// Advertised TAI - UTC offset in seconds
+    pub current_utc_offset: i16,
+}
+
+impl Default for PtpMasterConfig {
+    fn default() -> Self {
+        Self {
+            priority1: 128,
+            priority2: 128,
+            clock_class: CLOCK_CLASS_DEFAULT,
+            clock_accuracy: CLOCK_ACCURACY_UNKNOWN,
+            offset_scaled_log_variance: 0xFFFF,
+            log_sync_interval: -3,
+            log_announce_interval: 1,
+            current_utc_offset: DEFAULT_UTC_OFFSET,
+        }
+    }
+}
+
+impl PtpMasterConfig {
+    /// Sync interval as a Duration
+    pub fn sync_interval(&self) -> Duration {
+        log_interval_to_duration(self.log_sync_interval)
+    }
+
+    /// Announce interval as a Duration
+    pub fn announce_interval(&self) -> Duration {
+        log_interval_to_duration(self.log_announce_interval)
+    }
+
+    /// Dataset this clock advertises when acting as grandmaster
+    pub fn dataset(&self, identity: ClockIdentity) -> ClockDataset {
+        ClockDataset {
+            priority1: self.priority1,
+            clock_quality: ClockQuality {
+                clock_class: self.clock_class,
+                clock_accuracy: self.clock_accuracy,
+                offset_scaled_log_variance: self.offset_scaled_log_variance,
+            },
+            priority2: self.priority2,
+            identity,
+            steps_removed: 0,
+        }
+    }
+}
+
+/// Convert a PTP logMessageInterval to a Duration (clamped to 1/128s..64s)
+pub fn log_interval_to_duration(log_interval: i8) -> Duration {
+    let log = log_interval.clamp(-7, 6) as i32;
+    Duration::from_secs_f64(2f64.powi(log))
+}
+
+/// Grandmaster attributes compared by the BMCA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockDataset {
+    pub priority1: u8,
+    pub clock_quality: ClockQuality,
+    pub priority2: u8,
+    pub identity: ClockIdentity,
+    pub steps_removed: u16,
+}
+
+impl ClockDataset {
+    /// Extract the grandmaster dataset from an Announce message
+    pub fn from_announce(announce: &AnnounceMessage) -> Self {
+        Self {
+            priority1: announce.grandmaster_priority1,
+            clock_quality: announce.grandmaster_clock_quality,
+            priority2: announce.grandmaster_priority2,
+            identity: announce.grandmaster_identity,
+            steps_removed: announce.steps_removed,
+        }
+    }
+
+    /// IEEE 1588 dataset comparison.
+    ///
+    /// Returns `Ordering::Less` if `self` is the better clock.
+    pub fn compare(&self, other: &ClockDataset) -> Ordering {
+        if self.identity == other.identity {
+            // Same grandmaster seen through different paths: fewer hops wins
+            return self.steps_removed.cmp(&other.steps_removed);
+        }
+
+        self.priority1
+            .cmp(&other.priority1)
+            .then(self.clock_quality.clock_class.cmp(&other.clock_quality.clock_class))
+            .then(self.clock_quality.clock_accuracy.cmp(&other.clock_quality.clock_accuracy))
+            .then(
+                self.clock_quality
+                    .offset_scaled_log_variance
+                    .cmp(&other.clock_quality.offset_scaled_log_variance),
+            )
+            .then(self.priority2.cmp(&other.priority2))
+            .then(self.identity.0.cmp(&other.identity.0))
+    }
+
+    /// Check if `self` is strictly better than `other`
+    pub fn is_better_than(&self, other: &ClockDataset) -> bool {
+        self.compare(other) == Ordering::Less
+    }
+}
+
+/// Build an Announce message for this master
+pub fn build_announce(
+    config: &PtpMasterConfig,
+    port: PortIdentity,
+    domain: u8,
+    sequence_id: u16,
+    now_ptp_ns: i64,
+) -> AnnounceMessage {
+    let mut header = PtpHeader::new(
+        PtpMessageType::Announce,
+        AnnounceMessage::SIZE,
+        domain,
+        port,
+        sequence_id,
+        config.log_announce_interval,
+    );
+    header.flags = FLAG_UTC_OFFSET_VALID | FLAG_PTP_TIMESCALE;
+
+    let dataset = config.dataset(port.clock_identity);
+    AnnounceMessage {
+        header,
+        origin_timestamp: PtpTimestamp::from_ns(now_ptp_ns),
+        current_utc_offset: config.current_utc_offset,
+        grandmaster_priority1: dataset.priority1,
+        grandmaster_clock_quality: dataset.clock_quality,
+        grandmaster_priority2: dataset.priority2,
+        grandmaster_identity: dataset.identity,
+        steps_removed: 0,
+        time_source: TIME_SOURCE_INTERNAL_OSCILLATOR,
+        tlvs: Vec::new(),
+    }
+}
+
+/// Build a two-step Sync message (origin timestamp follows in Follow_Up)
+pub fn build_sync(
+    config: &PtpMasterConfig,
+    port: PortIdentity,
+    domain: u8,
+    sequence_id: u16,
+) -> SyncMessage {
+    let mut header = PtpHeader::new(
+        PtpMessageType::Sync,
+        SyncMessage::SIZE,
+        domain,
+        port,
+        sequence_id,
+        config.log_sync_interval,
+    );
+    header.flags = FLAG_TWO_STEP;
+
+    SyncMessage {
+        header,
+        origin_timestamp: PtpTimestamp::default(),
+    }
+}
+
+/// Build the Follow_Up carrying the precise Sync send time
+pub fn build_follow_up(
+    config: &PtpMasterConfig,
+    port: PortIdentity,
+    domain: u8,
+    sequence_id: u16,
+    sync_send_ptp_ns: i64,
+) -> FollowUpMessage {
+    FollowUpMessage {
+        header: PtpHeader::new(
+            PtpMessageType::FollowUp,
+            FollowUpMessage::SIZE,
+            domain,
+            port,
+            sequence_id,
+            config.log_sync_interval,
+        ),
+        precise_origin_timestamp: PtpTimestamp::from_ns(sync_send_ptp_ns),
+    }
+}
+
+/// Build a Delay_Resp answering `request`
+pub fn build_delay_resp(
+    port: PortIdentity,
+    request: &DelayReqMessage,
+    receive_ptp_ns: i64,
+) -> DelayRespMessage {
+    let mut header = PtpHeader::new(
+        PtpMessageType::DelayResp,
+        DelayRespMessage::SIZE,
+        request.header.domain_number,
+        port,
+        request.header.sequence_id,
+        0,
+    );
+    // Delay_Resp echoes the requester's correction field
+    header.correction_field = request.header.correction_field;
+
+    DelayRespMessage {
+        header,
+        receive_timestamp: PtpTimestamp::from_ns(receive_ptp_ns),
+        requesting_port_identity: request.header.source_port_identity,
+    }
+}
+
+/// Convert a local (Unix/UTC) timestamp to the PTP timescale (TAI)
+pub fn local_to_ptp_ns(local_ns: i64, utc_offset: i16) -> i64 {
+    local_ns + utc_offset as i64 * 1_000_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(priority1: u8, clock_class: u8, id: u8) -> ClockDataset {
+        ClockDataset {
+            priority1,
+            clock_quality: ClockQuality {
+                clock_class,
+                clock_accuracy: CLOCK_ACCURACY_UNKNOWN,
+                offset_scaled_log_variance: 0xFFFF,
+            },
+            priority2: 128,
+            identity: ClockIdentity([id; 8]),
+            steps_removed: 0,
+        }
+    }
+
+    #[test]
+    fn test_bmca_priority1_wins() {
+        let a = dataset(100, 248, 9);
+        let b = dataset(128, 6, 1);
+        assert!(a.is_better_than(&b));
+        assert!(!b.is_better_than(&a));
+    }
+
+    #[test]
+    fn test_bmca_clock_class_then_identity() {
+        let a = dataset(128, 6, 9);
+        let b = dataset(128, 248, 1);
+        assert!(a.is_better_than(&b));
+
+        // Everything equal except identity: lower identity wins
+        let c = dataset(128, 248, 1);
+        let d = dataset(128, 248, 2);
+        assert!(c.is_better_than(&d));
+    }
+
+    #[test]
+    fn test_announce_round_trip() {
+        let config = PtpMasterConfig {
+            priority1: 10,
+            priority2: 20,
+            clock_class: 13,
+            ..Default::default()
+        };
+        let port = PortIdentity {
+            clock_identity: ClockIdentity([1, 2, 3, 4, 5, 6, 7, 8]),
+            port_number: 1,
+        };
+        let msg = build_announce(&config, port, 0, 42, 1_700_000_000_123_456_789);
+        let parsed = AnnounceMessage::parse(&msg.to_bytes()).unwrap();
+
+        assert_eq!(parsed.header.sequence_id, 42);
+        assert_eq!(parsed.header.source_port_identity, port);
+        assert_eq!(parsed.grandmaster_priority1, 10);
+        assert_eq!(parsed.grandmaster_priority2, 20);
+        assert_eq!(parsed.grandmaster_clock_quality.clock_class, 13);
+        assert_eq!(parsed.current_utc_offset, DEFAULT_UTC_OFFSET);
+        assert_eq!(parsed.origin_timestamp.to_ns(), 1_700_000_000_123_456_789);
+    }
+
+    #[test]
+    fn test_delay_resp_round_trip() {
+        let requester = PortIdentity {
+            clock_identity: ClockIdentity([9; 8]),
+            port_number: 1,
+        };
+        let req = DelayReqMessage::parse(&DelayReqMessage::new(requester, 7, 0).to_bytes()).unwrap();
+        let master = PortIdentity {
+            clock_identity: ClockIdentity([1; 8]),
+            port_number: 1,
+        };
+        let resp = build_delay_resp(master, &req, 5_000_000_001);
+        let parsed = DelayRespMessage::parse(&resp.to_bytes()).unwrap();
+
+        assert_eq!(parsed.header.sequence_id, 7);
+        assert_eq!(parsed.requesting_port_identity, requester);
+        assert_eq!(parsed.receive_timestamp.to_ns(), 5_000_000_001);
+    }
+}
@@ -35,6 +35,53 @@ impl From<u8> for PtpMessageType {
     }
 }
 
+/// Header flag: two-step clock (precise timestamp follows in Follow_Up)
+pub const FLAG_TWO_STEP: u16 = 0x0200;
+/// Header flag: currentUtcOffset field is valid
+pub const FLAG_UTC_OFFSET_VALID: u16 = 0x0004;
+/// Header flag: grandmaster uses the PTP (TAI) timescale
+pub const FLAG_PTP_TIMESCALE: u16 = 0x0008;
+/// Header flag: message was sent to a unicast address
+pub const FLAG_UNICAST: u16 = 0x0400;
+/// Header flag: last minute of the current UTC day has 61 seconds
+pub const FLAG_LEAP61: u16 = 0x0001;
+/// Header flag: last minute of the current UTC day has 59 seconds
+pub const FLAG_LEAP59: u16 = 0x0002;
+/// Header flag: timescale is traceable to a primary reference
+pub const FLAG_TIME_TRACEABLE: u16 = 0x0010;
+/// Header flag: frequency is traceable to a primary reference
+pub const FLAG_FREQUENCY_TRACEABLE: u16 = 0x0020;
+
+/// TLV type: ORGANIZATION_EXTENSION
+pub const TLV_ORGANIZATION_EXTENSION: u16 = 0x0003;
+/// TLV type: REQUEST_UNICAST_TRANSMISSION
+pub const TLV_REQUEST_UNICAST_TRANSMISSION: u16 = 0x0004;
+/// TLV type: GRANT_UNICAST_TRANSMISSION
+pub const TLV_GRANT_UNICAST_TRANSMISSION: u16 = 0x0005;
+/// TLV type: CANCEL_UNICAST_TRANSMISSION
+pub const TLV_CANCEL_UNICAST_TRANSMISSION: u16 = 0x0006;
+/// TLV type: ACKNOWLEDGE_CANCEL_UNICAST_TRANSMISSION
+pub const TLV_ACKNOWLEDGE_CANCEL_UNICAST_TRANSMISSION: u16 = 0x0007;
+
+/// TLV type: MANAGEMENT
+pub const TLV_MANAGEMENT: u16 = 0x0001;
+/// TLV type: MANAGEMENT_ERROR_STATUS
+pub const TLV_MANAGEMENT_ERROR_STATUS: u16 = 0x0002;
+
+/// Management ID: DEFAULT_DATA_SET
+pub const MANAGEMENT_DEFAULT_DATA_SET: u16 = 0x2000;
+/// Management ID: CURRENT_DATA_SET
+pub const MANAGEMENT_CURRENT_DATA_SET: u16 = 0x2001;
+/// Management ID: PARENT_DATA_SET
+pub const MANAGEMENT_PARENT_DATA_SET: u16 = 0x2002;
+/// Management ID: TIME_PROPERTIES_DATA_SET
+pub const MANAGEMENT_TIME_PROPERTIES_DATA_SET: u16 = 0x2003;
+/// Management ID: PORT_DATA_SET
+pub const MANAGEMENT_PORT_DATA_SET: u16 = 0x2004;
+
+/// Time source: internal oscillator (free-running software master)
+pub const TIME_SOURCE_INTERNAL_OSCILLATOR: u8 = 0xA0;
+
 /// PTP clock identity (EUI-64 format, 8 bytes)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct ClockIdentity(pub [u8; 8]);
@@ -70,6 +117,12 @@ pub struct PortIdentity {
 }
 
 impl PortIdentity {
+    /// Wildcard identity (all ones) addressing every port
+    pub const ALL: PortIdentity = PortIdentity {
+        clock_identity: ClockIdentity([0xFF; 8]),
+        port_number: 0xFFFF,
+    };
+
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         if bytes.len() < 10 {
             return None;
@@ -79,6 +132,12 @@ impl PortIdentity {
             port_number: u16::from_be_bytes([bytes[8], bytes[9]]),
         })
     }
+
+    /// Write 10-byte wire representation into `buf`
+    pub fn write_to(&self, buf: &mut [u8]) {
+        buf[0..8].copy_from_slice(&self.clock_identity.0);
+        buf[8..10].copy_from_slice(&self.port_number.to_be_bytes());
+    }
 }
 
 /// PTP timestamp (48-bit seconds + 32-bit nanoseconds)
@@ -104,6 +163,21 @@ impl PtpTimestamp {
     pub fn to_ns(&self) -> i64 {
         self.seconds as i64 * 1_000_000_000 + self.nanoseconds as i64
     }
+
+    /// Build a timestamp from nanoseconds (negative values clamp to zero)
+    pub fn from_ns(ns: i64) -> Self {
+        let ns = ns.max(0);
+        Self {
+            seconds: (ns / 1_000_000_000) as u64,
+            nanoseconds: (ns % 1_000_000_000) as u32,
+        }
+    }
+
+    /// Write 10-byte wire representation into `buf`
+    pub fn write_to(&self, buf: &mut [u8]) {
+        buf[0..6].copy_from_slice(&self.seconds.to_be_bytes()[2..8]);
+        buf[6..10].copy_from_slice(&self.nanoseconds.to_be_bytes());
+    }
 }
 
 /// Common PTP header (34 bytes)
@@ -161,7 +235,60 @@ impl PtpHeader {
 
     /// Check if this is a two-step message (Follow_Up will contain precise timestamp)
     pub fn is_two_step(&self) -> bool {
-        (self.flags & 0x0200) != 0
+        (self.flags & FLAG_TWO_STEP) != 0
+    }
+
+    /// Correction field in nanoseconds (the wire format is ns * 2^16)
+    pub fn correction_ns(&self) -> i64 {
+        self.correction_field >> 16
+    }
+
+    /// Build a header for an outgoing message
+    pub fn new(
+        message_type: PtpMessageType,
+        message_length: usize,
+        domain: u8,
+        source_port: PortIdentity,
+        sequence_id: u16,
+        log_message_interval: i8,
+    ) -> Self {
+        // controlField is deprecated in v2 but still set for v1 interoperability
+        let control_field = match message_type {
+            PtpMessageType::Sync => 0,
+            PtpMessageType::DelayReq => 1,
+            PtpMessageType::FollowUp => 2,
+            PtpMessageType::DelayResp => 3,
+            PtpMessageType::Management => 4,
+            _ => 5,
+        };
+        Self {
+            message_type,
+            version: 2,
+            message_length: message_length as u16,
+            domain_number: domain,
+            flags: 0,
+            correction_field: 0,
+            source_port_identity: source_port,
+            sequence_id,
+            control_field,
+            log_message_interval,
+        }
+    }
+
+    /// Write the 34-byte header into the start of `buf`
+    pub fn write_to(&self, buf: &mut [u8]) {
+        buf[0] = self.message_type as u8;
+        buf[1] = self.version;
+        buf[2..4].copy_from_slice(&self.message_length.to_be_bytes());
+        buf[4] = self.domain_number;
+        buf[5] = 0;
+        buf[6..8].copy_from_slice(&self.flags.to_be_bytes());
+        buf[8..16].copy_from_slice(&self.correction_field.to_be_bytes());
+        buf[16..20].fill(0);
+        self.source_port_identity.write_to(&mut buf[20..30]);
+        buf[30..32].copy_from_slice(&self.sequence_id.to_be_bytes());
+        buf[32] = self.control_field;
+        buf[33] = self.log_message_interval as u8;
     }
 }
 
@@ -177,10 +304,12 @@ pub struct AnnounceMessage {
     pub grandmaster_identity: ClockIdentity,
     pub steps_removed: u16,
     pub time_source: u8,
+    /// Trailing TLVs (e.g. the SMPTE ST 2059-2 organization extension)
+    pub tlvs: Vec<Tlv>,
 }
 
 /// Clock quality information
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct ClockQuality {
     pub clock_class: u8,
     pub clock_accuracy: u8,
@@ -198,6 +327,13 @@ impl ClockQuality {
             offset_scaled_log_variance: u16::from_be_bytes([bytes[2], bytes[3]]),
         })
     }
+
+    /// Write 4-byte wire representation into `buf`
+    pub fn write_to(&self, buf: &mut [u8]) {
+        buf[0] = self.clock_class;
+        buf[1] = self.clock_accuracy;
+        buf[2..4].copy_from_slice(&self.offset_scaled_log_variance.to_be_bytes());
+    }
 }
 
 impl AnnounceMessage {
@@ -223,6 +359,10 @@ impl AnnounceMessage {
         let steps_removed = u16::from_be_bytes([body[27], body[28]]);
         let time_source = body[29];
 
+        // messageLength bounds the TLV area (trailing padding is ignored)
+        let end = (header.message_length as usize).clamp(Self::SIZE, data.len());
+        let tlvs = Tlv::parse_all(&data[Self::SIZE..end]);
+
         Some(Self {
             header,
             origin_timestamp,
@@ -233,8 +373,35 @@ impl AnnounceMessage {
             grandmaster_identity,
             steps_removed,
             time_source,
+            tlvs,
         })
     }
+
+    pub const SIZE: usize = PtpHeader::SIZE + 30;
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+
+        let body = &mut buf[PtpHeader::SIZE..];
+        self.origin_timestamp.write_to(&mut body[0..10]);
+        body[10..12].copy_from_slice(&self.current_utc_offset.to_be_bytes());
+        body[13] = self.grandmaster_priority1;
+        self.grandmaster_clock_quality.write_to(&mut body[14..18]);
+        body[18] = self.grandmaster_priority2;
+        body[19..27].copy_from_slice(&self.grandmaster_identity.0);
+        body[27..29].copy_from_slice(&self.steps_removed.to_be_bytes());
+        body[29] = self.time_source;
+
+        for tlv in &self.tlvs {
+            tlv.write_to(&mut buf);
+        }
+        let length = buf.len() as u16;
+        buf[2..4].copy_from_slice(&length.to_be_bytes());
+
+        buf
+    }
 }
 
 /// Sync message body
@@ -263,6 +430,16 @@ impl SyncMessage {
             origin_timestamp,
         })
     }
+
+    pub const SIZE: usize = PtpHeader::SIZE + 10;
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+        self.origin_timestamp.write_to(&mut buf[PtpHeader::SIZE..]);
+        buf
+    }
 }
 
 /// Follow_Up message body
@@ -291,6 +468,16 @@ impl FollowUpMessage {
             precise_origin_timestamp,
         })
     }
+
+    pub const SIZE: usize = PtpHeader::SIZE + 10;
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+        self.precise_origin_timestamp.write_to(&mut buf[PtpHeader::SIZE..]);
+        buf
+    }
 }
 
 /// Delay_Req message body
@@ -310,38 +497,42 @@ impl DelayReqMessage {
         domain: u8,
     ) -> Self {
         Self {
-            header: PtpHeader {
-                message_type: PtpMessageType::DelayReq,
-                version: 2,
-                message_length: Self::SIZE as u16,
-                domain_number: domain,
-                flags: 0,
-                correction_field: 0,
-                source_port_identity: source_port,
+            header: PtpHeader::new(
+                PtpMessageType::DelayReq,
+                Self::SIZE,
+                domain,
+                source_port,
                 sequence_id,
-                control_field: 1, // Delay_Req control
-                log_message_interval: 0x7F, // No periodic sending
-            },
+                0x7F, // No periodic sending
+            ),
             origin_timestamp: PtpTimestamp::default(),
         }
     }
 
+    /// Parse a Delay_Req (used when acting as master)
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::DelayReq {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        if body.len() < 10 {
+            return None;
+        }
+
+        let origin_timestamp = PtpTimestamp::from_bytes(&body[0..10])?;
+
+        Some(Self {
+            header,
+            origin_timestamp,
+        })
+    }
+
     /// Serialize to bytes for transmission
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = vec![0u8; Self::SIZE];
-
-        // Header
-        buf[0] = self.header.message_type as u8;
-        buf[1] = self.header.version;
-        buf[2..4].copy_from_slice(&self.header.message_length.to_be_bytes());
-        buf[4] = self.header.domain_number;
-        buf[6..8].copy_from_slice(&self.header.flags.to_be_bytes());
-        buf[8..16].copy_from_slice(&self.header.correction_field.to_be_bytes());
-        buf[20..28].copy_from_slice(&self.header.source_port_identity.clock_identity.0);
-        buf[28..30].copy_from_slice(&self.header.source_port_identity.port_number.to_be_bytes());
-        buf[30..32].copy_from_slice(&self.header.sequence_id.to_be_bytes());
-        buf[32] = self.header.control_field;
-        buf[33] = self.header.log_message_interval as u8;
+        self.header.write_to(&mut buf);
 
         // Origin timestamp (zeros - we'll record actual time when sending)
         // bytes 34-43 are already zero
@@ -379,6 +570,633 @@ impl DelayRespMessage {
             requesting_port_identity,
         })
     }
+
+    pub const SIZE: usize = PtpHeader::SIZE + 20;
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+
+        let body = &mut buf[PtpHeader::SIZE..];
+        self.receive_timestamp.write_to(&mut body[0..10]);
+        self.requesting_port_identity.write_to(&mut body[10..20]);
+
+        buf
+    }
+}
+
+/// Pdelay_Req message body (peer delay mechanism)
+#[derive(Debug, Clone)]
+pub struct PdelayReqMessage {
+    pub header: PtpHeader,
+    pub origin_timestamp: PtpTimestamp,
+}
+
+impl PdelayReqMessage {
+    /// Header + originTimestamp + 10 reserved bytes
+    pub const SIZE: usize = PtpHeader::SIZE + 20;
+
+    /// Create a new Pdelay_Req message
+    pub fn new(source_port: PortIdentity, sequence_id: u16, domain: u8, log_interval: i8) -> Self {
+        Self {
+            header: PtpHeader::new(
+                PtpMessageType::PDelayReq,
+                Self::SIZE,
+                domain,
+                source_port,
+                sequence_id,
+                log_interval,
+            ),
+            origin_timestamp: PtpTimestamp::default(),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::PDelayReq {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        if body.len() < 10 {
+            return None;
+        }
+
+        let origin_timestamp = PtpTimestamp::from_bytes(&body[0..10])?;
+
+        Some(Self {
+            header,
+            origin_timestamp,
+        })
+    }
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+        self.origin_timestamp.write_to(&mut buf[PtpHeader::SIZE..PtpHeader::SIZE + 10]);
+        buf
+    }
+}
+
+/// Pdelay_Resp message body (peer delay mechanism)
+#[derive(Debug, Clone)]
+pub struct PdelayRespMessage {
+    pub header: PtpHeader,
+    /// Time the responder received the Pdelay_Req (t2, zero for one-step responders)
+    pub request_receipt_timestamp: PtpTimestamp,
+    pub requesting_port_identity: PortIdentity,
+}
+
+impl PdelayRespMessage {
+    pub const SIZE: usize = PtpHeader::SIZE + 20;
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::PDelayResp {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        if body.len() < 20 {
+            return None;
+        }
+
+        Some(Self {
+            header,
+            request_receipt_timestamp: PtpTimestamp::from_bytes(&body[0..10])?,
+            requesting_port_identity: PortIdentity::from_bytes(&body[10..20])?,
+        })
+    }
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+
+        let body = &mut buf[PtpHeader::SIZE..];
+        self.request_receipt_timestamp.write_to(&mut body[0..10]);
+        self.requesting_port_identity.write_to(&mut body[10..20]);
+
+        buf
+    }
+}
+
+/// Pdelay_Resp_Follow_Up message body (peer delay mechanism, two-step responders)
+#[derive(Debug, Clone)]
+pub struct PdelayRespFollowUpMessage {
+    pub header: PtpHeader,
+    /// Time the responder sent the Pdelay_Resp (t3)
+    pub response_origin_timestamp: PtpTimestamp,
+    pub requesting_port_identity: PortIdentity,
+}
+
+impl PdelayRespFollowUpMessage {
+    pub const SIZE: usize = PtpHeader::SIZE + 20;
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::PDelayRespFollowUp {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        if body.len() < 20 {
+            return None;
+        }
+
+        Some(Self {
+            header,
+            response_origin_timestamp: PtpTimestamp::from_bytes(&body[0..10])?,
+            requesting_port_identity: PortIdentity::from_bytes(&body[10..20])?,
+        })
+    }
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::SIZE];
+        self.header.write_to(&mut buf);
+
+        let body = &mut buf[PtpHeader::SIZE..];
+        self.response_origin_timestamp.write_to(&mut body[0..10]);
+        self.requesting_port_identity.write_to(&mut body[10..20]);
+
+        buf
+    }
+}
+
+/// Generic TLV (type-length-value) extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlv {
+    pub tlv_type: u16,
+    pub value: Vec<u8>,
+}
+
+impl Tlv {
+    /// Parse all TLVs in `data` (stops at the first truncated TLV)
+    pub fn parse_all(mut data: &[u8]) -> Vec<Tlv> {
+        let mut tlvs = Vec::new();
+        while data.len() >= 4 {
+            let tlv_type = u16::from_be_bytes([data[0], data[1]]);
+            let length = u16::from_be_bytes([data[2], data[3]]) as usize;
+            if data.len() < 4 + length {
+                break;
+            }
+            tlvs.push(Tlv {
+                tlv_type,
+                value: data[4..4 + length].to_vec(),
+            });
+            data = &data[4 + length..];
+        }
+        tlvs
+    }
+
+    /// Append wire representation to `buf`
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.tlv_type.to_be_bytes());
+        buf.extend_from_slice(&(self.value.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&self.value);
+    }
+}
+
+/// Unicast negotiation TLVs (IEEE 1588-2008 clause 16.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicastTlv {
+    /// Ask a master to send `message_type` at the given rate for `duration_s` seconds
+    Request {
+        message_type: PtpMessageType,
+        log_inter_message_period: i8,
+        duration_s: u32,
+    },
+    /// Master's answer to a request (duration 0 = denied)
+    Grant {
+        message_type: PtpMessageType,
+        log_inter_message_period: i8,
+        duration_s: u32,
+        renewal_invited: bool,
+    },
+    /// Stop sending `message_type`
+    Cancel { message_type: PtpMessageType },
+    /// Acknowledge a Cancel
+    AcknowledgeCancel { message_type: PtpMessageType },
+}
+
+impl UnicastTlv {
+    /// Decode a unicast negotiation TLV (None for other TLV types)
+    pub fn from_tlv(tlv: &Tlv) -> Option<Self> {
+        let v = &tlv.value;
+        match tlv.tlv_type {
+            TLV_REQUEST_UNICAST_TRANSMISSION if v.len() >= 6 => Some(UnicastTlv::Request {
+                message_type: PtpMessageType::from(v[0] >> 4),
+                log_inter_message_period: v[1] as i8,
+                duration_s: u32::from_be_bytes([v[2], v[3], v[4], v[5]]),
+            }),
+            TLV_GRANT_UNICAST_TRANSMISSION if v.len() >= 8 => Some(UnicastTlv::Grant {
+                message_type: PtpMessageType::from(v[0] >> 4),
+                log_inter_message_period: v[1] as i8,
+                duration_s: u32::from_be_bytes([v[2], v[3], v[4], v[5]]),
+                renewal_invited: (v[7] & 0x01) != 0,
+            }),
+            TLV_CANCEL_UNICAST_TRANSMISSION if !v.is_empty() => Some(UnicastTlv::Cancel {
+                message_type: PtpMessageType::from(v[0] >> 4),
+            }),
+            TLV_ACKNOWLEDGE_CANCEL_UNICAST_TRANSMISSION if !v.is_empty() => {
+                Some(UnicastTlv::AcknowledgeCancel {
+                    message_type: PtpMessageType::from(v[0] >> 4),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Encode as a generic TLV
+    pub fn to_tlv(&self) -> Tlv {
+        match *self {
+            UnicastTlv::Request {
+                message_type,
+                log_inter_message_period,
+                duration_s,
+            } => {
+                let mut value = vec![(message_type as u8) << 4, log_inter_message_period as u8];
+                value.extend_from_slice(&duration_s.to_be_bytes());
+                Tlv {
+                    tlv_type: TLV_REQUEST_UNICAST_TRANSMISSION,
+                    value,
+                }
+            }
+            UnicastTlv::Grant {
+                message_type,
+                log_inter_message_period,
+                duration_s,
+                renewal_invited,
+            } => {
+                let mut value = vec![(message_type as u8) << 4, log_inter_message_period as u8];
+                value.extend_from_slice(&duration_s.to_be_bytes());
+                value.push(0);
+                value.push(renewal_invited as u8);
+                Tlv {
+                    tlv_type: TLV_GRANT_UNICAST_TRANSMISSION,
+                    value,
+                }
+            }
+            UnicastTlv::Cancel { message_type } => Tlv {
+                tlv_type: TLV_CANCEL_UNICAST_TRANSMISSION,
+                value: vec![(message_type as u8) << 4, 0],
+            },
+            UnicastTlv::AcknowledgeCancel { message_type } => Tlv {
+                tlv_type: TLV_ACKNOWLEDGE_CANCEL_UNICAST_TRANSMISSION,
+                value: vec![(message_type as u8) << 4, 0],
+            },
+        }
+    }
+}
+
+/// Signaling message (carries unicast negotiation TLVs)
+#[derive(Debug, Clone)]
+pub struct SignalingMessage {
+    pub header: PtpHeader,
+    pub target_port_identity: PortIdentity,
+    pub tlvs: Vec<Tlv>,
+}
+
+impl SignalingMessage {
+    /// Size without TLVs
+    pub const BASE_SIZE: usize = PtpHeader::SIZE + 10;
+
+    /// Create a unicast Signaling message carrying `tlvs`
+    pub fn new(
+        source_port: PortIdentity,
+        sequence_id: u16,
+        domain: u8,
+        target_port_identity: PortIdentity,
+        tlvs: Vec<Tlv>,
+    ) -> Self {
+        let mut header = PtpHeader::new(
+            PtpMessageType::Signaling,
+            Self::BASE_SIZE,
+            domain,
+            source_port,
+            sequence_id,
+            0x7F,
+        );
+        header.flags = FLAG_UNICAST;
+        Self {
+            header,
+            target_port_identity,
+            tlvs,
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::Signaling {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        if body.len() < 10 {
+            return None;
+        }
+
+        let target_port_identity = PortIdentity::from_bytes(&body[0..10])?;
+        // messageLength bounds the TLV area (trailing padding is ignored)
+        let end = (header.message_length as usize).clamp(Self::BASE_SIZE, data.len());
+        let tlvs = Tlv::parse_all(&data[Self::BASE_SIZE..end]);
+
+        Some(Self {
+            header,
+            target_port_identity,
+            tlvs,
+        })
+    }
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::BASE_SIZE];
+        for tlv in &self.tlvs {
+            tlv.write_to(&mut buf);
+        }
+
+        let mut header = self.header.clone();
+        header.message_length = buf.len() as u16;
+        header.write_to(&mut buf);
+        self.target_port_identity
+            .write_to(&mut buf[PtpHeader::SIZE..Self::BASE_SIZE]);
+
+        buf
+    }
+
+    /// Unicast negotiation TLVs carried by this message
+    pub fn unicast_tlvs(&self) -> Vec<UnicastTlv> {
+        self.tlvs.iter().filter_map(UnicastTlv::from_tlv).collect()
+    }
+}
+
+/// Management message actionField values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ManagementAction {
+    Get = 0,
+    Set = 1,
+    Response = 2,
+    Command = 3,
+    Acknowledge = 4,
+}
+
+impl TryFrom<u8> for ManagementAction {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value & 0x0F {
+            0 => Ok(ManagementAction::Get),
+            1 => Ok(ManagementAction::Set),
+            2 => Ok(ManagementAction::Response),
+            3 => Ok(ManagementAction::Command),
+            4 => Ok(ManagementAction::Acknowledge),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Management message (IEEE 1588-2008 clause 15)
+#[derive(Debug, Clone)]
+pub struct ManagementMessage {
+    pub header: PtpHeader,
+    pub target_port_identity: PortIdentity,
+    pub starting_boundary_hops: u8,
+    pub boundary_hops: u8,
+    pub action: ManagementAction,
+    /// MANAGEMENT or MANAGEMENT_ERROR_STATUS TLV
+    pub tlv: Option<Tlv>,
+}
+
+impl ManagementMessage {
+    /// Size without the TLV
+    pub const BASE_SIZE: usize = PtpHeader::SIZE + 14;
+
+    /// Create a GET for `management_id`, forwarded through up to
+    /// `boundary_hops` boundary clocks
+    pub fn new_get(
+        source_port: PortIdentity,
+        sequence_id: u16,
+        domain: u8,
+        target_port_identity: PortIdentity,
+        boundary_hops: u8,
+        management_id: u16,
+    ) -> Self {
+        Self {
+            header: PtpHeader::new(
+                PtpMessageType::Management,
+                Self::BASE_SIZE,
+                domain,
+                source_port,
+                sequence_id,
+                0x7F,
+            ),
+            target_port_identity,
+            starting_boundary_hops: boundary_hops,
+            boundary_hops,
+            action: ManagementAction::Get,
+            tlv: Some(Tlv {
+                tlv_type: TLV_MANAGEMENT,
+                value: management_id.to_be_bytes().to_vec(),
+            }),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let header = PtpHeader::parse(data)?;
+        if header.message_type != PtpMessageType::Management || data.len() < Self::BASE_SIZE {
+            return None;
+        }
+
+        let body = &data[PtpHeader::SIZE..];
+        let target_port_identity = PortIdentity::from_bytes(&body[0..10])?;
+        let action = ManagementAction::try_from(body[12]).ok()?;
+        let end = (header.message_length as usize).clamp(Self::BASE_SIZE, data.len());
+        let tlv = Tlv::parse_all(&data[Self::BASE_SIZE..end]).into_iter().next();
+
+        Some(Self {
+            header,
+            target_port_identity,
+            starting_boundary_hops: body[10],
+            boundary_hops: body[11],
+            action,
+            tlv,
+        })
+    }
+
+    /// Serialize to bytes for transmission
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0u8; Self::BASE_SIZE];
+        if let Some(tlv) = &self.tlv {
+            tlv.write_to(&mut buf);
+        }
+
+        let mut header = self.header.clone();
+        header.message_length = buf.len() as u16;
+        header.write_to(&mut buf);
+
+        let body = &mut buf[PtpHeader::SIZE..Self::BASE_SIZE];
+        self.target_port_identity.write_to(&mut body[0..10]);
+        body[10] = self.starting_boundary_hops;
+        body[11] = self.boundary_hops;
+        body[12] = self.action as u8;
+
+        buf
+    }
+
+    /// Management ID of the carried TLV
+    pub fn management_id(&self) -> Option<u16> {
+        let tlv = self.tlv.as_ref()?;
+        let v = &tlv.value;
+        match tlv.tlv_type {
+            TLV_MANAGEMENT if v.len() >= 2 => Some(u16::from_be_bytes([v[0], v[1]])),
+            // managementErrorId precedes the managementId
+            TLV_MANAGEMENT_ERROR_STATUS if v.len() >= 4 => Some(u16::from_be_bytes([v[2], v[3]])),
+            _ => None,
+        }
+    }
+
+    /// Decode the carried data set or error status
+    pub fn data(&self) -> Option<ManagementData> {
+        let tlv = self.tlv.as_ref()?;
+        match tlv.tlv_type {
+            TLV_MANAGEMENT => ManagementData::parse(self.management_id()?, &tlv.value[2..]),
+            TLV_MANAGEMENT_ERROR_STATUS => {
+                let v = &tlv.value;
+                if v.len() < 8 {
+                    return None;
+                }
+                Some(ManagementData::Error {
+                    error_id: u16::from_be_bytes([v[0], v[1]]),
+                    management_id: u16::from_be_bytes([v[2], v[3]]),
+                    display_data: parse_ptp_text(&v[8..]),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Management data sets (IEEE 1588-2008 clause 15.5.3)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManagementData {
+    DefaultDataSet {
+        two_step: bool,
+        slave_only: bool,
+        number_ports: u16,
+        priority1: u8,
+        clock_quality: ClockQuality,
+        priority2: u8,
+        clock_identity: ClockIdentity,
+        domain_number: u8,
+    },
+    CurrentDataSet {
+        steps_removed: u16,
+        offset_from_master_ns: i64,
+        mean_path_delay_ns: i64,
+    },
+    ParentDataSet {
+        parent_port_identity: PortIdentity,
+        parent_stats: bool,
+        observed_parent_offset_scaled_log_variance: u16,
+        observed_parent_clock_phase_change_rate: i32,
+        grandmaster_priority1: u8,
+        grandmaster_clock_quality: ClockQuality,
+        grandmaster_priority2: u8,
+        grandmaster_identity: ClockIdentity,
+    },
+    TimePropertiesDataSet {
+        current_utc_offset: i16,
+        /// LI61, LI59, UTCV, PTP, TTRA, FTRA in bits 0-5 (as in the header flags)
+        flags: u8,
+        time_source: u8,
+    },
+    PortDataSet {
+        port_identity: PortIdentity,
+        port_state: u8,
+        log_min_delay_req_interval: i8,
+        peer_mean_path_delay_ns: i64,
+        log_announce_interval: i8,
+        announce_receipt_timeout: u8,
+        log_sync_interval: i8,
+        delay_mechanism: u8,
+        log_min_pdelay_req_interval: i8,
+        version_number: u8,
+    },
+    /// MANAGEMENT_ERROR_STATUS
+    Error {
+        error_id: u16,
+        management_id: u16,
+        display_data: String,
+    },
+}
+
+impl ManagementData {
+    /// Decode the dataField of a MANAGEMENT TLV (None for unsupported IDs)
+    pub fn parse(management_id: u16, d: &[u8]) -> Option<Self> {
+        let time_interval_ns = |b: &[u8]| {
+            i64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) >> 16
+        };
+
+        match management_id {
+            MANAGEMENT_DEFAULT_DATA_SET if d.len() >= 20 => Some(ManagementData::DefaultDataSet {
+                two_step: (d[0] & 0x01) != 0,
+                slave_only: (d[0] & 0x02) != 0,
+                number_ports: u16::from_be_bytes([d[2], d[3]]),
+                priority1: d[4],
+                clock_quality: ClockQuality::from_bytes(&d[5..9])?,
+                priority2: d[9],
+                clock_identity: ClockIdentity::from_bytes(&d[10..18]),
+                domain_number: d[18],
+            }),
+            MANAGEMENT_CURRENT_DATA_SET if d.len() >= 18 => Some(ManagementData::CurrentDataSet {
+                steps_removed: u16::from_be_bytes([d[0], d[1]]),
+                offset_from_master_ns: time_interval_ns(&d[2..10]),
+                mean_path_delay_ns: time_interval_ns(&d[10..18]),
+            }),
+            MANAGEMENT_PARENT_DATA_SET if d.len() >= 32 => Some(ManagementData::ParentDataSet {
+                parent_port_identity: PortIdentity::from_bytes(&d[0..10])?,
+                parent_stats: (d[10] & 0x01) != 0,
+                observed_parent_offset_scaled_log_variance: u16::from_be_bytes([d[12], d[13]]),
+                observed_parent_clock_phase_change_rate: i32::from_be_bytes([d[14], d[15], d[16], d[17]]),
+                grandmaster_priority1: d[18],
+                grandmaster_clock_quality: ClockQuality::from_bytes(&d[19..23])?,
+                grandmaster_priority2: d[23],
+                grandmaster_identity: ClockIdentity::from_bytes(&d[24..32]),
+            }),
+            MANAGEMENT_TIME_PROPERTIES_DATA_SET if d.len() >= 4 => {
+                Some(ManagementData::TimePropertiesDataSet {
+                    current_utc_offset: i16::from_be_bytes([d[0], d[1]]),
+                    flags: d[2],
+                    time_source: d[3],
+                })
+            }
+            MANAGEMENT_PORT_DATA_SET if d.len() >= 26 => Some(ManagementData::PortDataSet {
+                port_identity: PortIdentity::from_bytes(&d[0..10])?,
+                port_state: d[10],
+                log_min_delay_req_interval: d[11] as i8,
+                peer_mean_path_delay_ns: time_interval_ns(&d[12..20]),
+                log_announce_interval: d[20] as i8,
+                announce_receipt_timeout: d[21],
+                log_sync_interval: d[22] as i8,
+                delay_mechanism: d[23],
+                log_min_pdelay_req_interval: d[24] as i8,
+                version_number: d[25] & 0x0F,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Decode a PTPText (length-prefixed UTF-8)
+fn parse_ptp_text(data: &[u8]) -> String {
+    match data.split_first() {
+        Some((&len, rest)) => String::from_utf8_lossy(&rest[..(len as usize).min(rest.len())]).into_owned(),
+        None => String::new(),
+    }
 }
 
 /// Parsed PTP message (any type)
@@ -387,7 +1205,13 @@ pub enum PtpMessage {
     Announce(AnnounceMessage),
     Sync(SyncMessage),
     FollowUp(FollowUpMessage),
+    DelayReq(DelayReqMessage),
     DelayResp(DelayRespMessage),
+    PdelayReq(PdelayReqMessage),
+    PdelayResp(PdelayRespMessage),
+    PdelayRespFollowUp(PdelayRespFollowUpMessage),
+    Signaling(SignalingMessage),
+    Management(ManagementMessage),
     Other(PtpHeader),
 }
 
@@ -406,9 +1230,27 @@ impl PtpMessage {
             PtpMessageType::FollowUp => {
                 FollowUpMessage::parse(data).map(PtpMessage::FollowUp)
             }
+            PtpMessageType::DelayReq => {
+                DelayReqMessage::parse(data).map(PtpMessage::DelayReq)
+            }
             PtpMessageType::DelayResp => {
                 DelayRespMessage::parse(data).map(PtpMessage::DelayResp)
             }
+            PtpMessageType::PDelayReq => {
+                PdelayReqMessage::parse(data).map(PtpMessage::PdelayReq)
+            }
+            PtpMessageType::PDelayResp => {
+                PdelayRespMessage::parse(data).map(PtpMessage::PdelayResp)
+            }
+            PtpMessageType::PDelayRespFollowUp => {
+                PdelayRespFollowUpMessage::parse(data).map(PtpMessage::PdelayRespFollowUp)
+            }
+            PtpMessageType::Signaling => {
+                SignalingMessage::parse(data).map(PtpMessage::Signaling)
+            }
+            PtpMessageType::Management => {
+                ManagementMessage::parse(data).map(PtpMessage::Management)
+            }
             _ => Some(PtpMessage::Other(header)),
         }
     }
@@ -418,7 +1260,13 @@ impl PtpMessage {
             PtpMessage::Announce(m) => &m.header,
             PtpMessage::Sync(m) => &m.header,
             PtpMessage::FollowUp(m) => &m.header,
+            PtpMessage::DelayReq(m) => &m.header,
             PtpMessage::DelayResp(m) => &m.header,
+            PtpMessage::PdelayReq(m) => &m.header,
+            PtpMessage::PdelayResp(m) => &m.header,
+            PtpMessage::PdelayRespFollowUp(m) => &m.header,
+            PtpMessage::Signaling(m) => &m.header,
+            PtpMessage::Management(m) => &m.header,
             PtpMessage::Other(h) => h,
         }
     }
@@ -0,0 +1,278 @@
+//! Peer-to-peer delay mechanism (IEEE 1588-2008 clause 11.4).
+//!
+//! On P2P networks every port measures the link delay to its direct
+//! neighbour with Pdelay_Req / Pdelay_Resp / Pdelay_Resp_Follow_Up instead
+//! of sending Delay_Req to the grandmaster. The exchange gives four
+//! timestamps:
+//!
+//! ```text
+//!   requester            responder
+//!   t1  Pdelay_Req  --->  t2
+//!   t4  <--- Pdelay_Resp  t3
+//! ```
+//!
+//! and the mean link delay is `((t4 - t1) - (t3 - t2)) / 2`, minus any
+//! correction carried in the response messages. One-step responders put the
+//! turnaround time (t3 - t2) straight into the Pdelay_Resp correction field.
+//!
+//! Only timestamp differences taken on the same clock are used, so the
+//! local and peer timescales never need to agree.
+
+use std::net::Ipv4Addr;
+
+use crate::messages::*;
+
+/// Peer delay multicast address (link-local, never forwarded by switches)
+pub const PTP_PDELAY_MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 107);
+
+/// Largest link delay accepted as plausible
+const MAX_LINK_DELAY_NS: i64 = 1_000_000_000;
+
+/// Requester side of one Pdelay exchange
+#[derive(Debug, Clone)]
+struct PendingExchange {
+    sequence_id: u16,
+    /// Pdelay_Req send time
+    t1_ns: i64,
+    /// Pdelay_Resp receive time
+    t4_ns: i64,
+    /// Pdelay_Req receive time at the responder
+    t2_ns: i64,
+    /// Responder that answered (None until Pdelay_Resp arrived)
+    responder: Option<PortIdentity>,
+    /// Sum of response correction fields
+    correction_ns: i64,
+}
+
+/// Requester-side peer delay measurement
+#[derive(Debug, Clone, Default)]
+pub struct PeerDelay {
+    pending: Option<PendingExchange>,
+    /// Last measured mean link delay
+    link_delay_ns: Option<i64>,
+    /// Pdelay_Resp from more than one responder seen (not a P2P link)
+    multiple_responders: bool,
+}
+
+impl PeerDelay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last measured mean link delay in nanoseconds
+    pub fn link_delay_ns(&self) -> Option<i64> {
+        self.link_delay_ns
+    }
+
+    /// Check if the last exchange was answered by several responders
+    pub fn multiple_responders(&self) -> bool {
+        self.multiple_responders
+    }
+
+    /// Record a Pdelay_Req that was just sent at `t1_ns`
+    pub fn request_sent(&mut self, sequence_id: u16, t1_ns: i64) {
+        self.pending = Some(PendingExchange {
+            sequence_id,
+            t1_ns,
+            t4_ns: 0,
+            t2_ns: 0,
+            responder: None,
+            correction_ns: 0,
+        });
+        self.multiple_responders = false;
+    }
+
+    /// Replace the send time of pending request `sequence_id` once its
+    /// transmit timestamp is known
+    pub fn request_timestamped(&mut self, sequence_id: u16, t1_ns: i64) {
+        if let Some(pending) = self.pending.as_mut().filter(|p| p.sequence_id == sequence_id) {
+            pending.t1_ns = t1_ns;
+        }
+    }
+
+    /// Process a Pdelay_Resp received at `t4_ns`.
+    ///
+    /// Returns the new link delay for one-step responders; two-step
+    /// responders complete the exchange with the Follow_Up.
+    pub fn handle_resp(&mut self, local_port: PortIdentity, resp: &PdelayRespMessage, t4_ns: i64) -> Option<i64> {
+        if resp.requesting_port_identity != local_port {
+            return None;
+        }
+
+        let pending = self.pending.as_mut()?;
+        if pending.sequence_id != resp.header.sequence_id {
+            return None;
+        }
+
+        if let Some(responder) = pending.responder {
+            // A second answer to the same request: more than one peer on the link
+            if responder != resp.header.source_port_identity {
+                self.multiple_responders = true;
+                self.pending = None;
+            }
+            return None;
+        }
+
+        pending.responder = Some(resp.header.source_port_identity);
+        pending.t4_ns = t4_ns;
+        pending.t2_ns = resp.request_receipt_timestamp.to_ns();
+        pending.correction_ns = resp.header.correction_ns();
+
+        if resp.header.is_two_step() {
+            return None;
+        }
+
+        // One-step: turnaround time is in the correction field
+        let exchange = self.pending.take()?;
+        self.complete((exchange.t4_ns - exchange.t1_ns - exchange.correction_ns) / 2)
+    }
+
+    /// Process a Pdelay_Resp_Follow_Up. Returns the new link delay.
+    pub fn handle_follow_up(&mut self, local_port: PortIdentity, follow_up: &PdelayRespFollowUpMessage) -> Option<i64> {
+        if follow_up.requesting_port_identity != local_port {
+            return None;
+        }
+
+        let pending = self.pending.as_ref()?;
+        if pending.sequence_id != follow_up.header.sequence_id
+            || pending.responder != Some(follow_up.header.source_port_identity)
+        {
+            return None;
+        }
+
+        let exchange = self.pending.take()?;
+        let t3_ns = follow_up.response_origin_timestamp.to_ns();
+        let turnaround_ns = t3_ns - exchange.t2_ns;
+        let correction_ns = exchange.correction_ns + follow_up.header.correction_ns();
+
+        self.complete((exchange.t4_ns - exchange.t1_ns - turnaround_ns - correction_ns) / 2)
+    }
+
+    fn complete(&mut self, delay_ns: i64) -> Option<i64> {
+        if delay_ns.abs() >= MAX_LINK_DELAY_NS {
+            return None;
+        }
+        let delay_ns = delay_ns.max(0);
+        self.link_delay_ns = Some(delay_ns);
+        Some(delay_ns)
+    }
+}
+
+/// Build the (two-step) Pdelay_Resp answering `request`, received at `t2_ns`
+pub fn build_pdelay_resp(port: PortIdentity, request: &PdelayReqMessage, t2_ns: i64) -> PdelayRespMessage {
+    let mut header = PtpHeader::new(
+        PtpMessageType::PDelayResp,
+        PdelayRespMessage::SIZE,
+        request.header.domain_number,
+        port,
+        request.header.sequence_id,
+        0x7F,
+    );
+    header.flags = FLAG_TWO_STEP;
+
+    PdelayRespMessage {
+        header,
+        request_receipt_timestamp: PtpTimestamp::from_ns(t2_ns),
+        requesting_port_identity: request.header.source_port_identity,
+    }
+}
+
+/// Build the Pdelay_Resp_Follow_Up carrying the Pdelay_Resp send time `t3_ns`
+pub fn build_pdelay_resp_follow_up(
+    port: PortIdentity,
+    request: &PdelayReqMessage,
+    t3_ns: i64,
+) -> PdelayRespFollowUpMessage {
+    let mut header = PtpHeader::new(
+        PtpMessageType::PDelayRespFollowUp,
+        PdelayRespFollowUpMessage::SIZE,
+        request.header.domain_number,
+        port,
+        request.header.sequence_id,
+        0x7F,
+    );
+    // The requester's correction field is returned in the Follow_Up
+    header.correction_field = request.header.correction_field;
+
+    PdelayRespFollowUpMessage {
+        header,
+        response_origin_timestamp: PtpTimestamp::from_ns(t3_ns),
+        requesting_port_identity: request.header.source_port_identity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(id: u8) -> PortIdentity {
+        PortIdentity {
+            clock_identity: ClockIdentity([id; 8]),
+            port_number: 1,
+        }
+    }
+
+    fn request(seq: u16) -> PdelayReqMessage {
+        PdelayReqMessage::parse(&PdelayReqMessage::new(port(1), seq, 0, 0).to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_two_step_link_delay() {
+        let mut pd = PeerDelay::new();
+        // Requester clock and responder clock are far apart; only differences matter
+        pd.request_sent(5, 1_000_000);
+        let req = request(5);
+        let resp = build_pdelay_resp(port(2), &req, 900_000_000 + 1_500);
+        let resp = PdelayRespMessage::parse(&resp.to_bytes()).unwrap();
+        assert_eq!(pd.handle_resp(port(1), &resp, 1_000_000 + 3_000 + 20_000), None);
+
+        // Responder turnaround 20µs, link delay 1.5µs each way
+        let fu = build_pdelay_resp_follow_up(port(2), &req, 900_000_000 + 21_500);
+        let fu = PdelayRespFollowUpMessage::parse(&fu.to_bytes()).unwrap();
+        assert_eq!(pd.handle_follow_up(port(1), &fu), Some(1_500));
+        assert_eq!(pd.link_delay_ns(), Some(1_500));
+    }
+
+    #[test]
+    fn test_transmit_timestamp_replaces_send_time() {
+        let mut pd = PeerDelay::new();
+        pd.request_sent(9, 500);
+        // Only the pending request takes the timestamp
+        pd.request_timestamped(8, 900);
+        pd.request_timestamped(9, 0);
+        let mut resp = build_pdelay_resp(port(2), &request(9), 0);
+        resp.header.flags = 0;
+        resp.header.correction_field = 20_000 << 16;
+        let resp = PdelayRespMessage::parse(&resp.to_bytes()).unwrap();
+        assert_eq!(pd.handle_resp(port(1), &resp, 23_000), Some(1_500));
+    }
+
+    #[test]
+    fn test_one_step_uses_correction() {
+        let mut pd = PeerDelay::new();
+        pd.request_sent(9, 0);
+        let mut resp = build_pdelay_resp(port(2), &request(9), 0);
+        resp.header.flags = 0;
+        resp.header.correction_field = 20_000 << 16; // turnaround
+        let resp = PdelayRespMessage::parse(&resp.to_bytes()).unwrap();
+        assert_eq!(pd.handle_resp(port(1), &resp, 23_000), Some(1_500));
+    }
+
+    #[test]
+    fn test_ignores_other_requesters_and_multiple_responders() {
+        let mut pd = PeerDelay::new();
+        pd.request_sent(1, 0);
+        let req = request(1);
+
+        let resp = build_pdelay_resp(port(2), &req, 0);
+        assert_eq!(pd.handle_resp(port(7), &resp, 10), None);
+
+        pd.handle_resp(port(1), &resp, 10);
+        let other = build_pdelay_resp(port(3), &req, 0);
+        pd.handle_resp(port(1), &other, 12);
+        assert!(pd.multiple_responders());
+
+        let fu = build_pdelay_resp_follow_up(port(2), &req, 5);
+        assert_eq!(pd.handle_follow_up(port(1), &fu), None);
+    }
+}
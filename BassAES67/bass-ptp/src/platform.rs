@@ -2,6 +2,9 @@
 //!
 //! For PTP we need wall clock time (epoch-based) to compare with PTP timestamps.
 //! Using GetSystemTimePreciseAsFileTime on Windows for better precision.
+//!
+//! The local-to-PTP time mapping uses a monotonic clock instead, so stepping
+//! the system time doesn't move mapped timestamps.
 
 #[cfg(windows)]
 mod windows_time {
@@ -40,6 +43,28 @@ mod windows_time {
         // 2. Multiply by 100 to get nanoseconds
         (filetime - FILETIME_TO_UNIX_EPOCH) * 100
     }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn QueryPerformanceCounter(lpPerformanceCount: *mut i64) -> i32;
+        fn QueryPerformanceFrequency(lpFrequency: *mut i64) -> i32;
+    }
+
+    pub fn get_monotonic_ns() -> i64 {
+        let mut counter = 0i64;
+        let mut frequency = 0i64;
+        unsafe {
+            QueryPerformanceCounter(&mut counter);
+            QueryPerformanceFrequency(&mut frequency);
+        }
+        if frequency <= 0 {
+            return 0;
+        }
+        // Split to avoid overflowing counter * 1e9
+        let secs = counter / frequency;
+        let rest = counter % frequency;
+        secs * 1_000_000_000 + rest * 1_000_000_000 / frequency
+    }
 }
 
 /// Get current timestamp in nanoseconds since Unix epoch.
@@ -71,6 +96,40 @@ pub fn get_timestamp_ns() -> i64 {
     }
 }
 
+/// Get the current monotonic time in nanoseconds.
+///
+/// QueryPerformanceCounter on Windows, CLOCK_MONOTONIC elsewhere. The epoch
+/// is arbitrary (usually system boot); only differences are meaningful.
+#[cfg(windows)]
+pub fn get_monotonic_ns() -> i64 {
+    windows_time::get_monotonic_ns()
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // time_t and c_long are 32-bit on some targets
+pub fn get_monotonic_ns() -> i64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+    }
+    ts.tv_sec as i64 * 1_000_000_000 + ts.tv_nsec as i64
+}
+
+#[cfg(not(any(windows, unix)))]
+pub fn get_monotonic_ns() -> i64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static BASE: OnceLock<Instant> = OnceLock::new();
+    BASE.get_or_init(Instant::now).elapsed().as_nanos() as i64
+}
+
+/// Convert a recent wall clock timestamp (`get_timestamp_ns`) to the
+/// monotonic clock, by its age relative to now.
+pub fn wall_to_monotonic_ns(wall_ns: i64) -> i64 {
+    get_monotonic_ns() - (get_timestamp_ns() - wall_ns)
+}
+
 /// Convert nanoseconds to PTP timestamp format (seconds + nanoseconds)
 pub fn ns_to_ptp_timestamp(ns: i64) -> (u64, u32) {
     let secs = (ns / 1_000_000_000) as u64;
@@ -82,3 +141,18 @@ pub fn ns_to_ptp_timestamp(ns: i64) -> (u64, u32) {
 pub fn ptp_timestamp_to_ns(secs: u64, nanos: u32) -> i64 {
     secs as i64 * 1_000_000_000 + nanos as i64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wall_to_monotonic() {
+        let mono = wall_to_monotonic_ns(get_timestamp_ns() - 1_000_000);
+        let now = get_monotonic_ns();
+        // 1ms ago, give or take scheduling
+        assert!(now - mono >= 1_000_000 - 100_000);
+        assert!(now - mono < 50_000_000);
+        assert!(get_monotonic_ns() >= now);
+    }
+}
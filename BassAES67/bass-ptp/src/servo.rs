@@ -1,117 +1,570 @@
-//! Drift rate servo for PTP clock offset tracking.
+//! Clock servos for PTP offset tracking.
 //!
-//! For software PTP (no system clock adjustment), we measure the rate of change
-//! of offset (drift rate) using linear regression and output that as frequency.
+//! For software PTP (no system clock adjustment) the servo doesn't steer a
+//! clock; it estimates the drift rate of the local clock against the
+//! grandmaster and outputs that as frequency. `PtpServo` is the front end
+//! the client feeds. It rejects outliers, detects grandmaster time steps and
+//! tracks the lock state, and hands accepted samples to a `ServoAlgorithm`:
+//!
+//! - `RegressionServo`: windowed linear regression of offset over time,
+//!   low-pass filtered (the original estimator)
+//! - `PiServo`: proportional-integral tracking loop
+//!
+//! A sample is an outlier when it is further than the outlier threshold from
+//! where the algorithm expects the offset to be (a Sync delayed in a switch
+//! queue). When several consecutive outliers agree with each other the
+//! grandmaster's time has stepped: the algorithm restarts its offset
+//! tracking, keeping its frequency estimate, and the caller re-baselines.
+
+
+/// Number of regression history samples
+const HISTORY_LEN: usize = 32;
+
+/// Regression samples needed before a drift estimate is made
+const MIN_REGRESSION_SAMPLES: usize = 8;
+
+/// Path delay measurements used for the median filter
+const DELAY_WINDOW_LEN: usize = 8;
+
+/// Frequency estimates are clamped to +/- 500 ppm
+const MAX_FREQUENCY_PPB: f64 = 500_000.0;
+
+/// PI loop damping factor
+const PI_DAMPING: f64 = 0.707;
+
+/// Filter coefficient of the frequency and deviation statistics
+const STATS_ALPHA: f64 = 0.1;
+
+/// Smallest frequency uncertainty assumed in holdover (ppb): the short-term
+/// wander of an uncompensated crystal
+const MIN_HOLDOVER_UNCERTAINTY_PPB: f64 = 100.0;
+
+/// Servo algorithm selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum ServoKind {
+    /// Windowed linear regression (default)
+    #[default]
+    Regression = 0,
+    /// Proportional-integral loop
+    Pi = 1,
+}
+
+impl ServoKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServoKind::Regression => "regression",
+            ServoKind::Pi => "PI",
+        }
+    }
+
+    /// Default time constant in seconds
+    pub fn default_time_constant(&self) -> f64 {
+        match self {
+            // Filter coefficient of 0.1 at the AES67 Sync rate of 8/s
+            ServoKind::Regression => 1.25,
+            ServoKind::Pi => 4.0,
+        }
+    }
+}
+
+impl TryFrom<u8> for ServoKind {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ServoKind::Regression),
+            1 => Ok(ServoKind::Pi),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Servo tuning
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoConfig {
+    /// Algorithm
+    pub kind: ServoKind,
+    /// Time constant in seconds (0 = algorithm default)
+    pub time_constant_s: f64,
+    /// Largest deviation from the expected offset counted as in lock
+    pub lock_threshold_ns: i64,
+    /// Largest frequency estimate counted as in lock (ppb)
+    pub lock_frequency_ppb: f64,
+    /// Consecutive samples in lock before locking
+    pub lock_samples: u32,
+    /// Consecutive samples out of lock before unlocking
+    pub unlock_samples: u32,
+    /// Deviation from the expected offset (or median path delay) beyond
+    /// which a sample is rejected (0 = no outlier rejection or step detection)
+    pub outlier_threshold_ns: i64,
+    /// Consecutive, mutually consistent outliers taken as a time step
+    pub step_samples: u32,
+    /// Longest holdover after the grandmaster disappears, in seconds
+    /// (0 = unlimited)
+    pub holdover_timeout_s: u32,
+}
+
+impl Default for ServoConfig {
+    fn default() -> Self {
+        Self {
+            kind: ServoKind::Regression,
+            time_constant_s: 0.0,
+            lock_threshold_ns: 10_000_000,
+            lock_frequency_ppb: 50_000.0,
+            lock_samples: 3,
+            unlock_samples: 5,
+            outlier_threshold_ns: 1_000_000,
+            step_samples: 3,
+            holdover_timeout_s: 300,
+        }
+    }
+}
+
+impl ServoConfig {
+    /// Time constant in seconds with the default resolved
+    pub fn time_constant(&self) -> f64 {
+        if self.time_constant_s > 0.0 {
+            self.time_constant_s
+        } else {
+            self.kind.default_time_constant()
+        }
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.time_constant_s.is_finite() || self.time_constant_s < 0.0 {
+            return Err("Servo time constant must be a non-negative number".to_string());
+        }
+        if self.lock_threshold_ns < 0 || self.outlier_threshold_ns < 0 {
+            return Err("Servo thresholds must not be negative".to_string());
+        }
+        if self.lock_samples == 0 || self.unlock_samples == 0 || self.step_samples == 0 {
+            return Err("Servo sample counts must be non-zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Create the algorithm this configuration selects
+    fn build(&self) -> Box<dyn ServoAlgorithm> {
+        match self.kind {
+            ServoKind::Regression => Box::new(RegressionServo::new(self.time_constant())),
+            ServoKind::Pi => Box::new(PiServo::new(self.time_constant())),
+        }
+    }
+}
+
+/// Outcome of feeding a sample to `PtpServo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServoEvent {
+    /// Sample used for tracking
+    Accepted,
+    /// Sample rejected as an outlier
+    Outlier,
+    /// Grandmaster time step detected; offset tracking restarted and the
+    /// caller should re-baseline its offsets
+    Step,
+}
+
+/// Frequency estimator fed with accepted (time, offset) samples
+pub trait ServoAlgorithm: Send {
+    fn name(&self) -> &'static str;
+
+    /// Process an accepted sample taken at local time `t_ns`
+    fn sample(&mut self, t_ns: i64, offset_ns: i64);
+
+    /// Expected offset at local time `t_ns` (None until enough samples)
+    fn predict(&self, t_ns: i64) -> Option<i64>;
+
+    /// Frequency adjustment in ppb (the negated drift of the offset)
+    fn frequency_ppb(&self) -> f64;
+
+    /// Restart offset tracking after a time step, keeping the frequency estimate
+    fn restart(&mut self);
+
+    /// Replace the frequency estimate (ppb)
+    fn set_frequency_ppb(&mut self, frequency_ppb: f64);
+
+    /// Forget everything
+    fn reset(&mut self);
+}
+
+/// Windowed linear regression drift estimator
+pub struct RegressionServo {
+    /// Filter time constant in seconds
+    time_constant_s: f64,
+    /// Ring buffer of recent (time, offset) samples
+    history: [(i64, i64); HISTORY_LEN],
+    /// Current position in ring buffer
+    history_pos: usize,
+    /// Number of valid samples in history
+    history_count: usize,
+    /// Filtered drift rate (None until the first estimate)
+    filtered_drift_ppb: Option<f64>,
+    /// Time of the previous sample
+    last_t_ns: Option<i64>,
+}
+
+impl RegressionServo {
+    pub fn new(time_constant_s: f64) -> Self {
+        Self {
+            time_constant_s,
+            history: [(0, 0); HISTORY_LEN],
+            history_pos: 0,
+            history_count: 0,
+            filtered_drift_ppb: None,
+            last_t_ns: None,
+        }
+    }
+
+    /// Samples in history order (oldest first)
+    fn samples(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (0..self.history_count).map(move |i| {
+            self.history[(self.history_pos + HISTORY_LEN - self.history_count + i) % HISTORY_LEN]
+        })
+    }
+
+    /// Mean time (relative to `t0_ns`, in seconds) and mean offset of the history
+    fn means(&self, t0_ns: i64) -> (f64, f64) {
+        let n = self.history_count as f64;
+        let (sum_t, sum_y) = self.samples().fold((0.0, 0.0), |(st, sy), (t, y)| {
+            (st + (t - t0_ns) as f64 / 1e9, sy + y as f64)
+        });
+        (sum_t / n, sum_y / n)
+    }
+
+    /// Drift rate (ns/s = ppb) by linear regression over the history window
+    fn regression_slope(&self) -> f64 {
+        if self.history_count < 2 {
+            return 0.0;
+        }
+
+        // Times relative to the newest sample keep the sums well conditioned
+        let t0 = self.history[(self.history_pos + HISTORY_LEN - 1) % HISTORY_LEN].0;
+        let (mean_t, mean_y) = self.means(t0);
+        let (mut s_ty, mut s_tt) = (0.0f64, 0.0f64);
+        for (t_ns, y_ns) in self.samples() {
+            let dt = (t_ns - t0) as f64 / 1e9 - mean_t;
+            s_ty += dt * (y_ns as f64 - mean_y);
+            s_tt += dt * dt;
+        }
+
+        if s_tt < 1e-12 {
+            return 0.0;
+        }
+        s_ty / s_tt
+    }
+}
+
+impl ServoAlgorithm for RegressionServo {
+    fn name(&self) -> &'static str {
+        ServoKind::Regression.as_str()
+    }
+
+    fn sample(&mut self, t_ns: i64, offset_ns: i64) {
+        self.history[self.history_pos] = (t_ns, offset_ns);
+        self.history_pos = (self.history_pos + 1) % HISTORY_LEN;
+        if self.history_count < HISTORY_LEN {
+            self.history_count += 1;
+        }
+
+        let dt_s = self.last_t_ns.map(|last| (t_ns - last) as f64 / 1e9).unwrap_or(0.0);
+        self.last_t_ns = Some(t_ns);
+
+        if self.history_count >= MIN_REGRESSION_SAMPLES {
+            let drift_ppb = self.regression_slope();
+            let alpha = (dt_s / self.time_constant_s).clamp(0.001, 1.0);
+            self.filtered_drift_ppb = Some(match self.filtered_drift_ppb {
+                Some(filtered) => alpha * drift_ppb + (1.0 - alpha) * filtered,
+                None => drift_ppb,
+            });
+        }
+    }
+
+    fn predict(&self, t_ns: i64) -> Option<i64> {
+        if self.history_count < MIN_REGRESSION_SAMPLES / 2 {
+            return None;
+        }
+        let (mean_t, mean_y) = self.means(t_ns);
+        let slope = self.filtered_drift_ppb.unwrap_or(0.0);
+        Some((mean_y - slope * mean_t) as i64)
+    }
+
+    fn frequency_ppb(&self) -> f64 {
+        -self.filtered_drift_ppb.unwrap_or(0.0)
+    }
+
+    fn restart(&mut self) {
+        self.history_pos = 0;
+        self.history_count = 0;
+        self.last_t_ns = None;
+    }
+
+    fn set_frequency_ppb(&mut self, frequency_ppb: f64) {
+        self.filtered_drift_ppb = Some(-frequency_ppb);
+    }
+
+    fn reset(&mut self) {
+        self.restart();
+        self.filtered_drift_ppb = None;
+    }
+}
+
+/// Proportional-integral tracking loop.
+///
+/// Tracks a model offset that advances with the estimated drift between
+/// samples; each sample's deviation from the model corrects the offset
+/// (proportional term) and the drift (integral term). Gains follow from the
+/// time constant as a critically damped second-order loop.
+pub struct PiServo {
+    /// Loop time constant in seconds
+    time_constant_s: f64,
+    /// Model offset at `last_t_ns`
+    offset_ns: f64,
+    /// Estimated drift (ns/s = ppb)
+    drift_ppb: f64,
+    /// Time of the previous sample (None before the first)
+    last_t_ns: Option<i64>,
+    /// Samples since the last (re)start
+    samples: u64,
+}
+
+impl PiServo {
+    pub fn new(time_constant_s: f64) -> Self {
+        Self {
+            time_constant_s,
+            offset_ns: 0.0,
+            drift_ppb: 0.0,
+            last_t_ns: None,
+            samples: 0,
+        }
+    }
+}
 
-/// Drift rate estimator for software PTP
-/// Uses windowed linear regression to estimate clock drift rate (ppb)
+impl ServoAlgorithm for PiServo {
+    fn name(&self) -> &'static str {
+        ServoKind::Pi.as_str()
+    }
+
+    fn sample(&mut self, t_ns: i64, offset_ns: i64) {
+        self.samples += 1;
+        let last_t_ns = match self.last_t_ns.replace(t_ns) {
+            Some(t) => t,
+            None => {
+                self.offset_ns = offset_ns as f64;
+                return;
+            }
+        };
+
+        let dt_s = (t_ns - last_t_ns) as f64 / 1e9;
+        if dt_s <= 0.0 {
+            return;
+        }
+
+        let predicted = self.offset_ns + self.drift_ppb * dt_s;
+        let error = offset_ns as f64 - predicted;
+        let wn = 1.0 / self.time_constant_s;
+        let kp = (2.0 * PI_DAMPING * wn * dt_s).min(1.0);
+        let ki = wn * wn * dt_s;
+
+        self.offset_ns = predicted + kp * error;
+        self.drift_ppb = (self.drift_ppb + ki * error).clamp(-MAX_FREQUENCY_PPB, MAX_FREQUENCY_PPB);
+    }
+
+    fn predict(&self, t_ns: i64) -> Option<i64> {
+        if self.samples < 2 {
+            return None;
+        }
+        let dt_s = (t_ns - self.last_t_ns?) as f64 / 1e9;
+        Some((self.offset_ns + self.drift_ppb * dt_s) as i64)
+    }
+
+    fn frequency_ppb(&self) -> f64 {
+        -self.drift_ppb
+    }
+
+    fn restart(&mut self) {
+        self.last_t_ns = None;
+        self.samples = 0;
+    }
+
+    fn set_frequency_ppb(&mut self, frequency_ppb: f64) {
+        self.drift_ppb = -frequency_ppb;
+    }
+
+    fn reset(&mut self) {
+        self.restart();
+        self.offset_ns = 0.0;
+        self.drift_ppb = 0.0;
+    }
+}
+
+/// Servo front end: outlier rejection, step detection and lock tracking
 pub struct PtpServo {
-    /// Current offset in nanoseconds
+    config: ServoConfig,
+    algorithm: Box<dyn ServoAlgorithm>,
+    /// Last accepted offset in nanoseconds
     offset_ns: i64,
-    /// Current frequency adjustment in ppb (parts per billion)
-    frequency_ppb: f64,
     /// Mean path delay in nanoseconds
     mean_path_delay_ns: i64,
     /// Number of samples processed
     sample_count: u64,
     /// Locked state (stable tracking)
     locked: bool,
-    /// Lock threshold in nanoseconds (unused but kept for API compatibility)
-    lock_threshold_ns: i64,
     /// Consecutive samples within threshold for lock
     samples_in_lock: u32,
-    /// Required consecutive samples for lock
-    lock_count_threshold: u32,
     /// Consecutive samples outside threshold (for unlock hysteresis)
     samples_out_of_lock: u32,
-    /// Required consecutive bad samples for unlock
-    unlock_count_threshold: u32,
-    /// Start time for elapsed calculation
-    start_time: std::time::Instant,
-    /// Ring buffer of recent (time, offset) samples for windowed regression
-    history: [(i64, i64); 32],
-    /// Current position in ring buffer
-    history_pos: usize,
-    /// Number of valid samples in history
-    history_count: usize,
-    /// Filtered drift rate (low-pass filtered)
-    filtered_drift_ppb: f64,
+    /// Deviations of the current run of consecutive outliers
+    step_candidates: Vec<i64>,
+    /// Recent path delay measurements (median filter)
+    delay_window: Vec<i64>,
+    /// Samples rejected as outliers (Sync offsets and path delays)
+    outlier_count: u64,
+    /// Time steps detected
+    step_count: u64,
+    /// Filtered frequency estimate (ppb)
+    frequency_mean_ppb: f64,
+    /// Filtered variance of the frequency estimate (ppb²)
+    frequency_var: f64,
+    /// Filtered magnitude of the deviation from the expected offset (ns)
+    deviation_ns: f64,
 }
 
 impl PtpServo {
-    /// Create a new servo for software PTP frequency estimation
-    ///
-    /// Uses a sliding window of recent samples to calculate drift rate via
-    /// linear regression. This gives a stable estimate that responds to
-    /// changes in clock drift over time.
+    /// Create a servo with the default configuration (regression algorithm)
     pub fn new() -> Self {
+        Self::with_config(ServoConfig::default())
+    }
+
+    pub fn with_config(config: ServoConfig) -> Self {
         Self {
+            config,
+            algorithm: config.build(),
             offset_ns: 0,
-            frequency_ppb: 0.0,
             mean_path_delay_ns: 0,
             sample_count: 0,
             locked: false,
-            lock_threshold_ns: 10_000_000,
             samples_in_lock: 0,
-            lock_count_threshold: 3,
             samples_out_of_lock: 0,
-            unlock_count_threshold: 5,
-            start_time: std::time::Instant::now(),
-            history: [(0, 0); 32],
-            history_pos: 0,
-            history_count: 0,
-            filtered_drift_ppb: 0.0,
+            step_candidates: Vec::new(),
+            delay_window: Vec::with_capacity(DELAY_WINDOW_LEN),
+            outlier_count: 0,
+            step_count: 0,
+            frequency_mean_ppb: 0.0,
+            frequency_var: 0.0,
+            deviation_ns: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> ServoConfig {
+        self.config
+    }
+
+    /// Change the configuration. Switching algorithm or time constant
+    /// restarts the estimation; threshold changes apply immediately.
+    pub fn set_config(&mut self, config: ServoConfig) {
+        let rebuild = config.kind != self.config.kind || config.time_constant() != self.config.time_constant();
+        self.config = config;
+        if rebuild {
+            self.algorithm = config.build();
+            self.locked = false;
+            self.samples_in_lock = 0;
+            self.samples_out_of_lock = 0;
         }
     }
 
     /// Process a new offset measurement
     ///
-    /// Measures drift rate using linear regression over recent samples.
-    /// The drift rate (ns/s = ppb) tells us how much to adjust packet timing.
-    ///
     /// # Arguments
+    /// * `t_ns` - When the measurement was taken (the Sync receive time), in
+    ///   any nanosecond timebase
     /// * `offset_ns` - Measured clock offset in nanoseconds (relative to baseline)
     /// * `path_delay_ns` - Measured path delay in nanoseconds
-    pub fn update(&mut self, offset_ns: i64, path_delay_ns: i64) {
+    pub fn update_at(&mut self, t_ns: i64, offset_ns: i64, path_delay_ns: i64) -> ServoEvent {
         self.sample_count += 1;
-        self.offset_ns = offset_ns;
         self.mean_path_delay_ns = path_delay_ns;
 
-        // Get elapsed time in nanoseconds
-        let now_ns = self.start_time.elapsed().as_nanos() as i64;
+        let deviation = self.algorithm.predict(t_ns).map(|p| offset_ns - p).unwrap_or(0);
+        let threshold = self.config.outlier_threshold_ns;
+
+        if threshold > 0 && deviation.abs() > threshold {
+            // A step moves every following sample by the same amount;
+            // a delayed packet is on its own
+            let consistent = self
+                .step_candidates
+                .first()
+                .is_none_or(|first| (deviation - first).abs() <= threshold);
+            if !consistent {
+                self.step_candidates.clear();
+            }
+            self.step_candidates.push(deviation);
+
+            if self.step_candidates.len() >= self.config.step_samples as usize {
+                self.step_candidates.clear();
+                self.step_count += 1;
+                self.algorithm.restart();
+                self.offset_ns = 0;
+                self.update_lock(false);
+                return ServoEvent::Step;
+            }
 
-        // Store in history for rate calculation
-        self.history[self.history_pos] = (now_ns, offset_ns);
-        self.history_pos = (self.history_pos + 1) % self.history.len();
-        if self.history_count < self.history.len() {
-            self.history_count += 1;
+            self.outlier_count += 1;
+            self.update_lock(false);
+            return ServoEvent::Outlier;
         }
 
-        // Need at least 8 samples for stable regression
-        if self.history_count >= 8 {
-            // Calculate drift rate using linear regression
-            // drift_rate = d(offset)/dt in ns/s = ppb
-            let drift_ppb = self.calculate_drift_rate_regression();
+        self.step_candidates.clear();
+        self.offset_ns = offset_ns;
+        self.algorithm.sample(t_ns, offset_ns);
+        self.update_statistics(deviation);
 
-            // Low-pass filter the drift rate (alpha = 0.1 for smooth response)
-            const ALPHA: f64 = 0.1;
-            self.filtered_drift_ppb = ALPHA * drift_ppb + (1.0 - ALPHA) * self.filtered_drift_ppb;
+        let in_lock = deviation.abs() <= self.config.lock_threshold_ns
+            && self.frequency_ppb().abs() < self.config.lock_frequency_ppb;
+        self.update_lock(in_lock);
+        ServoEvent::Accepted
+    }
 
-            // Output is the filtered drift rate
-            // This is what we need to compensate: if drift is +20ppb, we need -20ppb adjustment
-            self.frequency_ppb = -self.filtered_drift_ppb;
-        }
+    /// Track how stable the frequency estimate and the offset are
+    fn update_statistics(&mut self, deviation: i64) {
+        let freq = self.frequency_ppb();
+        let diff = freq - self.frequency_mean_ppb;
+        self.frequency_mean_ppb += STATS_ALPHA * diff;
+        self.frequency_var = (1.0 - STATS_ALPHA) * (self.frequency_var + STATS_ALPHA * diff * diff);
+        self.deviation_ns += STATS_ALPHA * (deviation.abs() as f64 - self.deviation_ns);
+    }
+
+    /// Filtered frequency estimate in ppb, the one to hold over on
+    pub fn mean_frequency_ppb(&self) -> f64 {
+        self.frequency_mean_ppb.clamp(-MAX_FREQUENCY_PPB, MAX_FREQUENCY_PPB)
+    }
+
+    /// Resume tracking after holdover. The samples before the holdover say
+    /// nothing about the phase now, so offset tracking restarts (the caller
+    /// re-baselines) and the frequency continues from the holdover frequency.
+    pub fn relock(&mut self) {
+        let frequency_ppb = self.mean_frequency_ppb();
+        self.algorithm.restart();
+        self.algorithm.set_frequency_ppb(frequency_ppb);
+        self.step_candidates.clear();
+        self.offset_ns = 0;
+    }
 
-        // Clamp to reasonable range (+/- 500 ppm)
-        self.frequency_ppb = self.frequency_ppb.clamp(-500_000.0, 500_000.0);
+    /// Estimated time error after `holdover_s` seconds of running on the
+    /// last frequency estimate: the recent offset noise plus the frequency
+    /// uncertainty integrated over the holdover
+    pub fn holdover_error_ns(&self, holdover_s: f64) -> i64 {
+        let uncertainty_ppb = self.frequency_var.sqrt().max(MIN_HOLDOVER_UNCERTAINTY_PPB);
+        (self.deviation_ns + uncertainty_ppb * holdover_s) as i64
+    }
 
-        // Lock state machine - based on drift rate being small and stable
-        let drift_threshold = 50_000.0; // 50 ppm threshold for lock
-        if self.filtered_drift_ppb.abs() < drift_threshold {
+    /// Lock state machine with hysteresis
+    fn update_lock(&mut self, in_lock: bool) {
+        if in_lock {
             self.samples_in_lock += 1;
             self.samples_out_of_lock = 0;
 
-            if self.samples_in_lock >= self.lock_count_threshold {
+            if self.samples_in_lock >= self.config.lock_samples {
                 self.locked = true;
             }
         } else {
@@ -119,7 +572,7 @@ impl PtpServo {
 
             if self.locked {
                 self.samples_out_of_lock += 1;
-                if self.samples_out_of_lock >= self.unlock_count_threshold {
+                if self.samples_out_of_lock >= self.config.unlock_samples {
                     self.locked = false;
                     self.samples_out_of_lock = 0;
                 }
@@ -127,42 +580,32 @@ impl PtpServo {
         }
     }
 
-    /// Calculate drift rate using linear regression over history window
-    /// Returns drift rate in ppb (ns per second)
-    fn calculate_drift_rate_regression(&self) -> f64 {
-        if self.history_count < 2 {
-            return 0.0;
-        }
-
-        // Collect valid samples from ring buffer
-        let mut sum_t = 0.0f64;
-        let mut sum_y = 0.0f64;
-        let mut sum_tt = 0.0f64;
-        let mut sum_ty = 0.0f64;
-        let n = self.history_count as f64;
-
-        for i in 0..self.history_count {
-            let idx = (self.history_pos + self.history.len() - self.history_count + i) % self.history.len();
-            let (t_ns, y_ns) = self.history[idx];
-            let t = t_ns as f64 / 1_000_000_000.0; // Convert to seconds
-            let y = y_ns as f64; // Keep in nanoseconds
-
-            sum_t += t;
-            sum_y += y;
-            sum_tt += t * t;
-            sum_ty += t * y;
+    /// Filter a path delay measurement.
+    ///
+    /// Returns the median of the recent accepted measurements, or None if
+    /// the measurement is an outlier (further than the outlier threshold
+    /// from the median).
+    pub fn filter_path_delay(&mut self, delay_ns: i64) -> Option<i64> {
+        let threshold = self.config.outlier_threshold_ns;
+        if threshold > 0
+            && self.delay_window.len() >= 3
+            && (delay_ns - self.median_path_delay()).abs() > threshold
+        {
+            self.outlier_count += 1;
+            return None;
         }
 
-        // Linear regression: slope = (n*sum_ty - sum_t*sum_y) / (n*sum_tt - sum_t*sum_t)
-        let denominator = n * sum_tt - sum_t * sum_t;
-        if denominator.abs() < 1e-10 {
-            return 0.0;
+        if self.delay_window.len() == DELAY_WINDOW_LEN {
+            self.delay_window.remove(0);
         }
+        self.delay_window.push(delay_ns);
+        Some(self.median_path_delay())
+    }
 
-        let slope = (n * sum_ty - sum_t * sum_y) / denominator;
-
-        // slope is in ns/s = ppb
-        slope
+    fn median_path_delay(&self) -> i64 {
+        let mut sorted = self.delay_window.clone();
+        sorted.sort_unstable();
+        sorted.get(sorted.len() / 2).copied().unwrap_or(0)
     }
 
     /// Get current offset in nanoseconds
@@ -177,12 +620,12 @@ impl PtpServo {
 
     /// Get current frequency adjustment in ppm (parts per million)
     pub fn frequency_ppm(&self) -> f64 {
-        self.frequency_ppb / 1_000.0
+        self.frequency_ppb() / 1_000.0
     }
 
     /// Get current frequency adjustment in ppb (parts per billion)
     pub fn frequency_ppb(&self) -> f64 {
-        self.frequency_ppb
+        self.algorithm.frequency_ppb().clamp(-MAX_FREQUENCY_PPB, MAX_FREQUENCY_PPB)
     }
 
     /// Get mean path delay in nanoseconds
@@ -205,25 +648,42 @@ impl PtpServo {
         self.sample_count
     }
 
+    /// Name of the servo algorithm
+    pub fn algorithm_name(&self) -> &'static str {
+        self.algorithm.name()
+    }
+
+    /// Samples rejected as outliers since the last reset
+    pub fn outlier_count(&self) -> u64 {
+        self.outlier_count
+    }
+
+    /// Time steps detected since the last reset
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
     /// Reset the servo state
     pub fn reset(&mut self) {
+        self.algorithm.reset();
         self.offset_ns = 0;
-        self.frequency_ppb = 0.0;
         self.mean_path_delay_ns = 0;
         self.sample_count = 0;
         self.locked = false;
         self.samples_in_lock = 0;
         self.samples_out_of_lock = 0;
-        self.start_time = std::time::Instant::now();
-        self.history = [(0, 0); 32];
-        self.history_pos = 0;
-        self.history_count = 0;
-        self.filtered_drift_ppb = 0.0;
+        self.step_candidates.clear();
+        self.delay_window.clear();
+        self.outlier_count = 0;
+        self.step_count = 0;
+        self.frequency_mean_ppb = 0.0;
+        self.frequency_var = 0.0;
+        self.deviation_ns = 0.0;
     }
 
     /// Get the filtered offset rate in ppb (ns/s)
     pub fn offset_rate_ppb(&self) -> f64 {
-        self.frequency_ppb
+        self.frequency_ppb()
     }
 }
 
@@ -236,19 +696,75 @@ impl Default for PtpServo {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
-    use std::time::Duration;
+
+    /// Sync interval of the simulations (AES67 rate, 8/s)
+    const SYNC_INTERVAL_NS: i64 = 125_000_000;
+
+    /// Synthetic offset series: a local clock drifting at `drift_ppb` with
+    /// deterministic timestamp noise of up to `noise_ns`
+    struct Simulation {
+        t_ns: i64,
+        drift_ppb: f64,
+        step_ns: i64,
+        noise_ns: i64,
+        rng: u64,
+    }
+
+    impl Simulation {
+        fn new(drift_ppb: f64, noise_ns: i64) -> Self {
+            Self {
+                t_ns: 0,
+                drift_ppb,
+                step_ns: 0,
+                noise_ns,
+                rng: 0x2545_F491_4F6C_DD1D,
+            }
+        }
+
+        fn noise(&mut self) -> i64 {
+            if self.noise_ns == 0 {
+                return 0;
+            }
+            // xorshift64
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            (self.rng % (2 * self.noise_ns as u64 + 1)) as i64 - self.noise_ns
+        }
+
+        /// Next (time, offset) sample
+        fn next(&mut self) -> (i64, i64) {
+            self.t_ns += SYNC_INTERVAL_NS;
+            let offset = (self.t_ns as f64 * self.drift_ppb / 1e9) as i64 + self.step_ns + self.noise();
+            (self.t_ns, offset)
+        }
+
+        /// Feed `count` samples, returning the events
+        fn run(&mut self, servo: &mut PtpServo, count: usize) -> Vec<ServoEvent> {
+            (0..count)
+                .map(|_| {
+                    let (t, offset) = self.next();
+                    servo.update_at(t, offset, 200_000)
+                })
+                .collect()
+        }
+    }
+
+    fn servo(kind: ServoKind) -> PtpServo {
+        PtpServo::with_config(ServoConfig {
+            kind,
+            ..Default::default()
+        })
+    }
 
     #[test]
     fn test_servo_basic() {
         let mut servo = PtpServo::new();
 
-        // Simulate drifting offset (local clock slow by ~10ppm)
-        // At 10ppm slow, offset grows by 10ns per ms
+        // Simulate drifting offset (local clock fast by ~100ppm)
         for i in 0..100 {
             let offset = i * 1000; // Growing by 1000ns per iteration
-            servo.update(offset, 500);
-            thread::sleep(Duration::from_millis(10));
+            servo.update_at(i * 10_000_000, offset, 500);
         }
 
         // Should detect the drift and have frequency adjustment
@@ -260,9 +776,8 @@ mod tests {
         let mut servo = PtpServo::new();
 
         // Constant offset (not drifting) = stable rate = should lock
-        for _ in 0..20 {
-            servo.update(1000, 500); // Same offset each time
-            thread::sleep(Duration::from_millis(10));
+        for i in 0..20 {
+            servo.update_at(i * 10_000_000, 1000, 500); // Same offset each time
         }
 
         // Offset rate is ~0, should lock
@@ -271,19 +786,134 @@ mod tests {
 
     #[test]
     fn test_servo_detects_drift() {
-        let mut servo = PtpServo::new();
+        // Offset growing at 40ppm: the local clock runs fast, so the
+        // frequency adjustment is -40ppm
+        for kind in [ServoKind::Regression, ServoKind::Pi] {
+            let mut servo = servo(kind);
+            let mut sim = Simulation::new(40_000.0, 20_000);
+            sim.run(&mut servo, 400);
+
+            let freq = servo.frequency_ppm();
+            assert!((freq + 40.0).abs() < 2.0, "{}: expected -40ppm, got {}", kind.as_str(), freq);
+            assert!(servo.is_locked());
+        }
+    }
+
+    #[test]
+    fn test_delayed_sync_is_rejected() {
+        for kind in [ServoKind::Regression, ServoKind::Pi] {
+            let mut servo = servo(kind);
+            let mut sim = Simulation::new(-20_000.0, 10_000);
+            sim.run(&mut servo, 200);
+            let before = servo.frequency_ppb();
+
+            // One Sync stuck 5ms in a switch queue
+            let (t, offset) = sim.next();
+            assert_eq!(servo.update_at(t, offset + 5_000_000, 200_000), ServoEvent::Outlier);
+            let events = sim.run(&mut servo, 8);
+            assert!(events.iter().all(|e| *e == ServoEvent::Accepted));
+
+            assert_eq!(servo.outlier_count(), 1);
+            assert!((servo.frequency_ppb() - before).abs() < 500.0, "{}", kind.as_str());
+        }
+    }
+
+    #[test]
+    fn test_time_step_restarts_tracking() {
+        for kind in [ServoKind::Regression, ServoKind::Pi] {
+            let mut servo = servo(kind);
+            let mut sim = Simulation::new(30_000.0, 10_000);
+            sim.run(&mut servo, 200);
+
+            // Grandmaster time jumps by one second
+            sim.step_ns = 1_000_000_000;
+            let events = sim.run(&mut servo, 3);
+            assert_eq!(events, [ServoEvent::Outlier, ServoEvent::Outlier, ServoEvent::Step]);
+            assert_eq!(servo.step_count(), 1);
+
+            // The caller re-baselines; tracking continues with the old frequency
+            sim.step_ns = 0;
+            let offset_before = sim.next().1;
+            sim.t_ns -= SYNC_INTERVAL_NS;
+            let events: Vec<_> = (0..40)
+                .map(|_| {
+                    let (t, offset) = sim.next();
+                    servo.update_at(t, offset - offset_before, 200_000)
+                })
+                .collect();
+            assert!(events.iter().all(|e| *e == ServoEvent::Accepted));
+            assert!((servo.frequency_ppm() + 30.0).abs() < 1.0, "{}", kind.as_str());
+            assert!(servo.is_locked());
+        }
+    }
+
+    #[test]
+    fn test_holdover_error_grows() {
+        let mut servo = servo(ServoKind::Regression);
+        let mut sim = Simulation::new(10_000.0, 5_000);
+        sim.run(&mut servo, 400);
+
+        let short = servo.holdover_error_ns(1.0);
+        let long = servo.holdover_error_ns(60.0);
+        assert!(short > 0 && long > short);
+        // At least the crystal wander floor
+        assert!(long >= (MIN_HOLDOVER_UNCERTAINTY_PPB * 60.0) as i64);
+    }
+
+    #[test]
+    fn test_relock_frequency_is_continuous() {
+        for kind in [ServoKind::Regression, ServoKind::Pi] {
+            let mut servo = servo(kind);
+            let mut sim = Simulation::new(10_000.0, 2_000);
+            sim.run(&mut servo, 200);
+
+            // What the client reports: the estimate, then the mean in holdover
+            let mut reported = vec![servo.frequency_ppb(), servo.mean_frequency_ppb()];
+
+            // 20s later the grandmaster is back, 400µs off the extrapolation;
+            // the client re-baselines on relock
+            sim.t_ns += 20_000_000_000;
+            sim.step_ns = 400_000;
+            servo.relock();
+            let offset_before = sim.next().1;
+            sim.t_ns -= SYNC_INTERVAL_NS;
+            for _ in 0..100 {
+                let (t, offset) = sim.next();
+                assert_eq!(servo.update_at(t, offset - offset_before, 200_000), ServoEvent::Accepted);
+                reported.push(servo.frequency_ppb());
+            }
+
+            for pair in reported.windows(2) {
+                assert!((pair[1] - pair[0]).abs() < 500.0, "{}: {} -> {}", kind.as_str(), pair[0], pair[1]);
+            }
+            assert!((servo.frequency_ppm() + 10.0).abs() < 1.0, "{}", kind.as_str());
+        }
+    }
 
-        // Simulate 50ppm drift (50,000 ns per second)
-        // At 10ms intervals: 500ns per measurement
-        for i in 0..50 {
-            let offset = i * 500; // 500ns per 10ms = 50,000 ns/s = 50ppm
-            servo.update(offset, 500);
-            thread::sleep(Duration::from_millis(10));
+    #[test]
+    fn test_path_delay_filter() {
+        let mut servo = PtpServo::new();
+        for delay in [200_000, 210_000, 190_000, 205_000] {
+            assert!(servo.filter_path_delay(delay).is_some());
         }
+        assert_eq!(servo.filter_path_delay(4_000_000), None);
+        assert_eq!(servo.filter_path_delay(195_000), Some(200_000));
+    }
 
-        // Should detect ~50ppm drift and output similar frequency adjustment
-        // Allow some margin for filter settling
-        let freq = servo.frequency_ppm();
-        assert!(freq > 10.0, "Expected positive frequency for slow local clock, got {}", freq);
+    #[test]
+    fn test_config_switches_algorithm() {
+        let mut servo = PtpServo::new();
+        assert_eq!(servo.algorithm_name(), "regression");
+        servo.set_config(ServoConfig {
+            kind: ServoKind::Pi,
+            ..servo.config()
+        });
+        assert_eq!(servo.algorithm_name(), "PI");
+        assert!(ServoConfig {
+            lock_samples: 0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
     }
 }
@@ -0,0 +1,217 @@
+//! SMPTE ST 2059-2 synchronization metadata.
+//!
+//! ST 2059-2 grandmasters append an organization extension TLV to every
+//! Announce message. It carries the data needed to derive time code and
+//! video frame alignment from PTP time: the default system frame rate, the
+//! local time offset and daylight saving schedule (jumps), and the daily jam
+//! times.
+
+use crate::messages::{Tlv, TLV_ORGANIZATION_EXTENSION};
+
+/// SMPTE organization identifier (OUI 68-97-E8)
+pub const SMPTE_ORGANIZATION_ID: [u8; 3] = [0x68, 0x97, 0xE8];
+
+/// Organization subtype of the synchronization metadata TLV
+pub const SMPTE_SUBTYPE_SYNC_METADATA: [u8; 3] = [0x00, 0x00, 0x01];
+
+/// TLV value length (organizationId + subtype + 42 bytes of data)
+const SMPTE_TLV_LENGTH: usize = 48;
+
+/// masterLockingStatus: not in use
+pub const LOCKING_STATUS_NOT_IN_USE: u8 = 0;
+/// masterLockingStatus: free run
+pub const LOCKING_STATUS_FREE_RUN: u8 = 1;
+/// masterLockingStatus: cold locking
+pub const LOCKING_STATUS_COLD_LOCKING: u8 = 2;
+/// masterLockingStatus: warm locking
+pub const LOCKING_STATUS_WARM_LOCKING: u8 = 3;
+/// masterLockingStatus: locked
+pub const LOCKING_STATUS_LOCKED: u8 = 4;
+
+/// timeAddressFlags bit: drop frame time code
+pub const TIME_ADDRESS_DROP_FRAME: u8 = 0x01;
+/// timeAddressFlags bit: color frame identification in use
+pub const TIME_ADDRESS_COLOR_FRAME: u8 = 0x02;
+
+/// daylightSaving bit: daylight saving currently in effect
+pub const DAYLIGHT_SAVING_CURRENT: u8 = 0x01;
+/// daylightSaving bit: daylight saving in effect after the next jump
+pub const DAYLIGHT_SAVING_NEXT: u8 = 0x02;
+/// daylightSaving bit: daylight saving was in effect at the previous jam
+pub const DAYLIGHT_SAVING_PREVIOUS: u8 = 0x04;
+
+/// leapSecondJump bit: the next jump is a UTC offset (leap second) change
+pub const LEAP_SECOND_JUMP_CHANGE: u8 = 0x01;
+
+/// Contents of the ST 2059-2 synchronization metadata TLV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmpteSyncMetadata {
+    /// Default system frame rate numerator (e.g. 30000)
+    pub frame_rate_numerator: u32,
+    /// Default system frame rate denominator (e.g. 1001)
+    pub frame_rate_denominator: u32,
+    /// Grandmaster locking status (LOCKING_STATUS_*)
+    pub master_locking_status: u8,
+    /// TIME_ADDRESS_* flags
+    pub time_address_flags: u8,
+    /// Local time offset from PTP time in seconds
+    pub current_local_offset: i32,
+    /// Size of the next local offset change in seconds
+    pub jump_seconds: i32,
+    /// PTP time (seconds) of the next local offset change
+    pub time_of_next_jump: u64,
+    /// PTP time (seconds) of the next daily jam
+    pub time_of_next_jam: u64,
+    /// PTP time (seconds) of the previous daily jam
+    pub time_of_previous_jam: u64,
+    /// Local offset at the previous daily jam in seconds
+    pub previous_jam_local_offset: i32,
+    /// DAYLIGHT_SAVING_* flags
+    pub daylight_saving: u8,
+    /// LEAP_SECOND_JUMP_* flags
+    pub leap_second_jump: u8,
+}
+
+impl Default for SmpteSyncMetadata {
+    /// Free-running grandmaster at the ST 2059-2 default rate of 30000/1001
+    fn default() -> Self {
+        Self {
+            frame_rate_numerator: 30000,
+            frame_rate_denominator: 1001,
+            master_locking_status: LOCKING_STATUS_FREE_RUN,
+            time_address_flags: 0,
+            current_local_offset: 0,
+            jump_seconds: 0,
+            time_of_next_jump: 0,
+            time_of_next_jam: 0,
+            time_of_previous_jam: 0,
+            previous_jam_local_offset: 0,
+            daylight_saving: 0,
+            leap_second_jump: 0,
+        }
+    }
+}
+
+impl SmpteSyncMetadata {
+    /// Decode an organization extension TLV (None if it isn't the SMPTE one)
+    pub fn from_tlv(tlv: &Tlv) -> Option<Self> {
+        if tlv.tlv_type != TLV_ORGANIZATION_EXTENSION || tlv.value.len() < SMPTE_TLV_LENGTH {
+            return None;
+        }
+        let v = &tlv.value;
+        if v[0..3] != SMPTE_ORGANIZATION_ID || v[3..6] != SMPTE_SUBTYPE_SYNC_METADATA {
+            return None;
+        }
+
+        let d = &v[6..];
+        Some(Self {
+            frame_rate_numerator: u32::from_be_bytes([d[0], d[1], d[2], d[3]]),
+            frame_rate_denominator: u32::from_be_bytes([d[4], d[5], d[6], d[7]]),
+            master_locking_status: d[8],
+            time_address_flags: d[9],
+            current_local_offset: i32::from_be_bytes([d[10], d[11], d[12], d[13]]),
+            jump_seconds: i32::from_be_bytes([d[14], d[15], d[16], d[17]]),
+            time_of_next_jump: read_u48(&d[18..24]),
+            time_of_next_jam: read_u48(&d[24..30]),
+            time_of_previous_jam: read_u48(&d[30..36]),
+            previous_jam_local_offset: i32::from_be_bytes([d[36], d[37], d[38], d[39]]),
+            daylight_saving: d[40],
+            leap_second_jump: d[41],
+        })
+    }
+
+    /// Find and decode the SMPTE TLV among `tlvs`
+    pub fn find(tlvs: &[Tlv]) -> Option<Self> {
+        tlvs.iter().find_map(Self::from_tlv)
+    }
+
+    /// Encode as an organization extension TLV
+    pub fn to_tlv(&self) -> Tlv {
+        let mut value = Vec::with_capacity(SMPTE_TLV_LENGTH);
+        value.extend_from_slice(&SMPTE_ORGANIZATION_ID);
+        value.extend_from_slice(&SMPTE_SUBTYPE_SYNC_METADATA);
+        value.extend_from_slice(&self.frame_rate_numerator.to_be_bytes());
+        value.extend_from_slice(&self.frame_rate_denominator.to_be_bytes());
+        value.push(self.master_locking_status);
+        value.push(self.time_address_flags);
+        value.extend_from_slice(&self.current_local_offset.to_be_bytes());
+        value.extend_from_slice(&self.jump_seconds.to_be_bytes());
+        value.extend_from_slice(&self.time_of_next_jump.to_be_bytes()[2..]);
+        value.extend_from_slice(&self.time_of_next_jam.to_be_bytes()[2..]);
+        value.extend_from_slice(&self.time_of_previous_jam.to_be_bytes()[2..]);
+        value.extend_from_slice(&self.previous_jam_local_offset.to_be_bytes());
+        value.push(self.daylight_saving);
+        value.push(self.leap_second_jump);
+
+        Tlv {
+            tlv_type: TLV_ORGANIZATION_EXTENSION,
+            value,
+        }
+    }
+
+    /// Default system frame rate in frames per second (0.0 if not set)
+    pub fn frame_rate(&self) -> f64 {
+        if self.frame_rate_denominator == 0 {
+            return 0.0;
+        }
+        self.frame_rate_numerator as f64 / self.frame_rate_denominator as f64
+    }
+
+    /// Check if time code uses drop frame counting
+    pub fn drop_frame(&self) -> bool {
+        (self.time_address_flags & TIME_ADDRESS_DROP_FRAME) != 0
+    }
+
+    pub fn locking_status_str(&self) -> &'static str {
+        match self.master_locking_status {
+            LOCKING_STATUS_NOT_IN_USE => "not in use",
+            LOCKING_STATUS_FREE_RUN => "free run",
+            LOCKING_STATUS_COLD_LOCKING => "cold locking",
+            LOCKING_STATUS_WARM_LOCKING => "warm locking",
+            LOCKING_STATUS_LOCKED => "locked",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Read a 48-bit big-endian unsigned integer
+fn read_u48(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[2..8].copy_from_slice(&bytes[..6]);
+    u64::from_be_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tlv_round_trip() {
+        let meta = SmpteSyncMetadata {
+            frame_rate_numerator: 25,
+            frame_rate_denominator: 1,
+            master_locking_status: LOCKING_STATUS_LOCKED,
+            time_address_flags: TIME_ADDRESS_COLOR_FRAME,
+            current_local_offset: 3600,
+            jump_seconds: 3600,
+            time_of_next_jump: 1_711_846_837,
+            time_of_next_jam: 1_700_000_037,
+            time_of_previous_jam: 1_699_913_637,
+            previous_jam_local_offset: 3600,
+            daylight_saving: DAYLIGHT_SAVING_NEXT,
+            leap_second_jump: 0,
+        };
+        let tlv = meta.to_tlv();
+        assert_eq!(tlv.value.len(), SMPTE_TLV_LENGTH);
+        assert_eq!(SmpteSyncMetadata::from_tlv(&tlv), Some(meta));
+        assert_eq!(meta.frame_rate(), 25.0);
+    }
+
+    #[test]
+    fn test_ignores_other_organizations() {
+        let mut tlv = SmpteSyncMetadata::default().to_tlv();
+        tlv.value[0] = 0x00;
+        assert_eq!(SmpteSyncMetadata::from_tlv(&tlv), None);
+        assert_eq!(SmpteSyncMetadata::find(&[tlv]), None);
+    }
+}
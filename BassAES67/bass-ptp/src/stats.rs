@@ -1,6 +1,11 @@
 //! PTP statistics tracking and formatting.
 
+use crate::config::{PtpDelayMechanism, PtpProfile, PtpTransportMode};
 use crate::messages::ClockIdentity;
+use crate::smpte::SmpteSyncMetadata;
+use crate::timemap::PtpTimeMap;
+use crate::timescale::TimeProperties;
+use crate::timestamping::TimestampSource;
 
 /// PTP client state machine states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +19,10 @@ pub enum PtpState {
     Uncalibrated = 2,
     /// Synchronized to grandmaster
     Slave = 3,
+    /// Acting as grandmaster (master role won BMCA)
+    Master = 4,
+    /// Grandmaster lost; running on the last frequency estimate
+    Holdover = 5,
 }
 
 impl PtpState {
@@ -23,6 +32,8 @@ impl PtpState {
             PtpState::Listening => "LISTENING",
             PtpState::Uncalibrated => "UNCALIBRATED",
             PtpState::Slave => "SLAVE",
+            PtpState::Master => "MASTER",
+            PtpState::Holdover => "HOLDOVER",
         }
     }
 }
@@ -62,6 +73,44 @@ pub struct PtpStats {
     pub domain: u8,
     /// Grandmaster clock class
     pub clock_class: u8,
+    /// Grandmaster BMCA priority1
+    pub priority1: u8,
+    /// Grandmaster BMCA priority2
+    pub priority2: u8,
+    /// Number of Delay_Req messages answered as master
+    pub delay_req_count: u64,
+    /// Source of the last Sync receive timestamp
+    pub rx_timestamp_source: TimestampSource,
+    /// Source of the last event message transmit timestamp
+    pub tx_timestamp_source: TimestampSource,
+    /// Message transport in use
+    pub transport: PtpTransportMode,
+    /// Number of active unicast grants (unicast mode)
+    pub unicast_grants: u32,
+    /// Path delay measurement in use
+    pub delay_mechanism: PtpDelayMechanism,
+    /// Number of Pdelay_Resp messages received for our requests (P2P)
+    pub pdelay_resp_count: u64,
+    /// Number of Pdelay_Req messages answered (P2P)
+    pub pdelay_req_count: u64,
+    /// PTP profile in use
+    pub profile: PtpProfile,
+    /// Grandmaster UTC offset, leap second and traceability flags
+    pub time_properties: TimeProperties,
+    /// SMPTE ST 2059-2 synchronization metadata (if the grandmaster sends it)
+    pub smpte: Option<SmpteSyncMetadata>,
+    /// Local clock to PTP time mapping (None until the first Sync/Follow_Up)
+    pub time_map: Option<PtpTimeMap>,
+    /// Servo algorithm in use
+    pub servo: &'static str,
+    /// Sync offsets and path delays rejected as outliers
+    pub outlier_count: u64,
+    /// Grandmaster time steps detected
+    pub step_count: u64,
+    /// Time in holdover in milliseconds (0 when not in holdover)
+    pub holdover_duration_ms: u64,
+    /// Estimated accumulated time error in holdover in nanoseconds
+    pub holdover_error_ns: i64,
 }
 
 impl PtpStats {
@@ -92,6 +141,27 @@ impl PtpStats {
                     lock_indicator
                 )
             }
+            PtpState::Holdover => {
+                format!(
+                    "Holdover from: PTP/{}:{}, {:.1}s, est. error ±{:.1}µs, Freq: {:+.2}ppm",
+                    self.grandmaster_id.to_hex_string(),
+                    self.grandmaster_port,
+                    self.holdover_duration_ms as f64 / 1_000.0,
+                    self.holdover_error_ns as f64 / 1_000.0,
+                    self.frequency_ppm
+                )
+            }
+            PtpState::Master => {
+                format!(
+                    "Master: PTP/{}:{}, P1 {}, P2 {}, Class {}, DelayReq: {}",
+                    self.grandmaster_id.to_hex_string(),
+                    self.grandmaster_port,
+                    self.priority1,
+                    self.priority2,
+                    self.clock_class,
+                    self.delay_req_count
+                )
+            }
         }
     }
 
@@ -102,27 +172,85 @@ impl PtpStats {
              State: {}\n\
              Grandmaster: {}:{}\n\
              Clock Class: {}\n\
+             Priority1/2: {}/{}\n\
              Domain: {}\n\
              Offset: {:.3}µs\n\
              Frequency: {:+.3}ppm\n\
-             Path Delay: {:.3}µs\n\
+             Path Delay: {:.3}µs ({})\n\
              Locked: {}\n\
-             Messages: Sync={}, FollowUp={}, Announce={}, DelayResp={}",
+             Servo: {} (outliers: {}, steps: {})\n\
+             Timestamps: RX={}, TX={}\n\
+             Transport: {} (grants: {}), Profile: {}\n\
+             UTC Offset: {}s{}{}\n\
+             Messages: Sync={}, FollowUp={}, Announce={}, DelayResp={}, DelayReq={}, \
+             PdelayResp={}, PdelayReq={}",
             self.state.as_str(),
             self.grandmaster_id.to_hex_string(),
             self.grandmaster_port,
             self.clock_class,
+            self.priority1,
+            self.priority2,
             self.domain,
             self.offset_ns as f64 / 1_000.0,
             self.frequency_ppm,
             self.mean_path_delay_ns as f64 / 1_000.0,
+            self.delay_mechanism.as_str(),
             if self.locked { "Yes" } else { "No" },
+            self.servo,
+            self.outlier_count,
+            self.step_count,
+            self.rx_timestamp_source.as_str(),
+            self.tx_timestamp_source.as_str(),
+            self.transport.as_str(),
+            self.unicast_grants,
+            self.profile.as_str(),
+            self.time_properties.current_utc_offset,
+            if self.time_properties.utc_offset_valid { "" } else { " (not valid)" },
+            if self.time_properties.leap61 {
+                ", leap second pending (+1)"
+            } else if self.time_properties.leap59 {
+                ", leap second pending (-1)"
+            } else {
+                ""
+            },
             self.sync_count,
             self.follow_up_count,
             self.announce_count,
-            self.delay_resp_count
+            self.delay_resp_count,
+            self.delay_req_count,
+            self.pdelay_resp_count,
+            self.pdelay_req_count
+        ) + &self.format_holdover()
+            + &self.format_smpte()
+    }
+
+    /// Holdover line for `format_detailed` (empty when not in holdover)
+    fn format_holdover(&self) -> String {
+        if self.state != PtpState::Holdover {
+            return String::new();
+        }
+        format!(
+            "\nHoldover: {:.1}s, est. error ±{:.1}µs",
+            self.holdover_duration_ms as f64 / 1_000.0,
+            self.holdover_error_ns as f64 / 1_000.0
         )
     }
+
+    /// SMPTE metadata lines for `format_detailed` (empty without the TLV)
+    fn format_smpte(&self) -> String {
+        match self.smpte {
+            Some(m) => format!(
+                "\nSMPTE: {}/{} fps{}, Lock: {}, Local Offset: {}s, Next Jam: {}",
+                m.frame_rate_numerator,
+                m.frame_rate_denominator,
+                if m.drop_frame() { " DF" } else { "" },
+                m.locking_status_str(),
+                m.current_local_offset,
+                m.time_of_next_jam
+            ),
+            None => String::new(),
+        }
+    }
 }
 
 // Thread-safe statistics storage using static buffer
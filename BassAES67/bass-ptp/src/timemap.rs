@@ -0,0 +1,175 @@
+//! Mapping between the local clock and the PTP timescale.
+//!
+//! Every Sync/Follow_Up pair gives one point where both clocks are known:
+//! the Sync arrived at local time t2, when the grandmaster's clock read
+//! t1 + path delay + corrections. Between Syncs the PTP time of any local
+//! instant is extrapolated from the last point with the servo's frequency
+//! estimate, so callers get a continuous PTP clock without waiting for the
+//! next message.
+//!
+//! After holdover the first new reference point usually disagrees with the
+//! extrapolated time. Rather than stepping, the difference is slewed out at
+//! a bounded rate so the PTP clock seen by consumers stays continuous.
+//!
+//! Local time is the monotonic clock (`platform::get_monotonic_ns`), so an
+//! NTP or manual step of the system time doesn't shift mapped timestamps.
+//! Packet timestamps are wall clock time and are converted when a reference
+//! point is taken.
+
+use crate::timescale::TimeProperties;
+
+/// A point in time on the PTP timescale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtpTime {
+    /// PTP time in nanoseconds (TAI for grandmasters on the PTP timescale)
+    pub tai_ns: i64,
+    /// UTC in nanoseconds, using the grandmaster's announced UTC offset
+    /// (equal to `tai_ns` for arbitrary timescales or an invalid offset)
+    pub utc_ns: i64,
+}
+
+/// Local clock to PTP time mapping
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PtpTimeMap {
+    /// Local time of the reference point
+    local_ns: i64,
+    /// PTP time at the reference point
+    ptp_ns: i64,
+    /// Rate of the PTP clock relative to the local clock, in ppb
+    frequency_ppb: f64,
+    /// Grandmaster time properties (UTC offset, leap seconds)
+    time_properties: TimeProperties,
+    /// Correction still to be slewed out at the reference point
+    slew_ns: i64,
+    /// Slew rate in ppb
+    slew_rate_ppb: f64,
+}
+
+impl PtpTimeMap {
+    /// Mapping through the reference point (`local_ns`, `ptp_ns`)
+    pub fn new(local_ns: i64, ptp_ns: i64, frequency_ppb: f64, time_properties: TimeProperties) -> Self {
+        Self {
+            local_ns,
+            ptp_ns,
+            frequency_ppb,
+            time_properties,
+            slew_ns: 0,
+            slew_rate_ppb: 0.0,
+        }
+    }
+
+    /// Hide a jump of `error_ns` (new reference minus old extrapolation)
+    /// by slewing it out at `rate_ppb` instead
+    pub fn with_slew(mut self, error_ns: i64, rate_ppb: f64) -> Self {
+        self.slew_ns = error_ns;
+        self.slew_rate_ppb = rate_ppb.abs();
+        self
+    }
+
+    /// Carry on with what is left of the `previous` mapping's slew
+    pub fn continue_slew(self, previous: &PtpTimeMap) -> Self {
+        self.with_slew(previous.slew_remaining_ns(self.local_ns), previous.slew_rate_ppb)
+    }
+
+    /// The same mapping from `local_ns` on, at a new frequency
+    pub fn rebase(&self, local_ns: i64, frequency_ppb: f64) -> Self {
+        let ptp_ns = self.local_to_ptp_ns(local_ns) + self.slew_remaining_ns(local_ns);
+        Self::new(local_ns, ptp_ns, frequency_ppb, self.time_properties).continue_slew(self)
+    }
+
+    /// Correction still to be slewed out at local time `local_ns`
+    pub fn slew_remaining_ns(&self, local_ns: i64) -> i64 {
+        if self.slew_ns == 0 {
+            return 0;
+        }
+        let elapsed = (local_ns - self.local_ns).max(0);
+        let slewed = (elapsed as f64 * self.slew_rate_ppb / 1e9) as i64;
+        let remaining = (self.slew_ns.abs() - slewed).max(0);
+        remaining * self.slew_ns.signum()
+    }
+
+    /// Slew rate in ppb applied at local time `local_ns` (0 when not slewing)
+    pub fn slew_frequency_ppb(&self, local_ns: i64) -> f64 {
+        match self.slew_remaining_ns(local_ns).signum() {
+            0 => 0.0,
+            sign => sign as f64 * self.slew_rate_ppb,
+        }
+    }
+
+    /// PTP time in nanoseconds at local time `local_ns`
+    pub fn local_to_ptp_ns(&self, local_ns: i64) -> i64 {
+        let elapsed = local_ns - self.local_ns;
+        let adjust = (elapsed as f64 * self.frequency_ppb / 1e9).round() as i64;
+        self.ptp_ns + elapsed + adjust - self.slew_remaining_ns(local_ns)
+    }
+
+    /// PTP time (TAI and UTC) at local time `local_ns`
+    pub fn local_to_ptp(&self, local_ns: i64) -> PtpTime {
+        let tai_ns = self.local_to_ptp_ns(local_ns);
+        PtpTime {
+            tai_ns,
+            utc_ns: self.time_properties.ptp_to_utc_ns(tai_ns),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolates_with_frequency() {
+        // PTP clock runs 10 ppm faster than the local clock
+        let map = PtpTimeMap::new(1_000_000_000, 5_000_000_000, 10_000.0, TimeProperties::default());
+        assert_eq!(map.local_to_ptp_ns(1_000_000_000), 5_000_000_000);
+        assert_eq!(map.local_to_ptp_ns(2_000_000_000), 6_000_010_000);
+        assert_eq!(map.local_to_ptp_ns(0), 3_999_990_000);
+    }
+
+    #[test]
+    fn test_slew_is_continuous() {
+        let old = PtpTimeMap::new(0, 1_000_000_000, 0.0, TimeProperties::default());
+        // New reference says the extrapolation is 500µs behind
+        let new = PtpTimeMap::new(10_000_000_000, 11_000_500_000, 0.0, TimeProperties::default());
+        let error = new.local_to_ptp_ns(10_000_000_000) - old.local_to_ptp_ns(10_000_000_000);
+        let map = new.with_slew(error, 50_000.0);
+
+        assert_eq!(map.local_to_ptp_ns(10_000_000_000), old.local_to_ptp_ns(10_000_000_000));
+        assert_eq!(map.slew_frequency_ppb(10_000_000_000), 50_000.0);
+        // 500µs at 50ppm takes 10s
+        assert_eq!(map.local_to_ptp_ns(15_000_000_000), 16_000_250_000);
+        assert_eq!(map.local_to_ptp_ns(21_000_000_000), 22_000_500_000);
+        assert_eq!(map.slew_frequency_ppb(21_000_000_000), 0.0);
+    }
+
+    #[test]
+    fn test_rebase_is_continuous() {
+        let map = PtpTimeMap::new(0, 1_000_000_000, 10_000.0, TimeProperties::default()).with_slew(100_000, 50_000.0);
+        let rebased = map.rebase(1_000_000_000, 0.0);
+        assert_eq!(rebased.local_to_ptp_ns(1_000_000_000), map.local_to_ptp_ns(1_000_000_000));
+        assert_eq!(rebased.slew_remaining_ns(1_000_000_000), map.slew_remaining_ns(1_000_000_000));
+        // No more frequency offset from here on
+        assert_eq!(rebased.local_to_ptp_ns(3_000_000_000) - rebased.local_to_ptp_ns(2_000_000_000), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_utc_uses_announced_offset() {
+        let mut props = TimeProperties::default();
+        let announce = crate::master::build_announce(
+            &crate::master::PtpMasterConfig {
+                current_utc_offset: 37,
+                ..Default::default()
+            },
+            Default::default(),
+            0,
+            0,
+            0,
+        );
+        props.update(&announce, 0);
+
+        let map = PtpTimeMap::new(0, 100_000_000_000, 0.0, props);
+        let t = map.local_to_ptp(0);
+        assert_eq!(t.tai_ns, 100_000_000_000);
+        assert_eq!(t.utc_ns, 63_000_000_000);
+    }
+}
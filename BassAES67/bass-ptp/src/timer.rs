@@ -1,38 +1,177 @@
-//! High-precision timer with PLL adjustment for no-soundcard mode.
+//! High-precision timers with PLL adjustment for no-soundcard mode.
 //!
-//! Provides a timer that fires at configurable intervals (default 20ms) with
-//! frequency adjustment based on PTP servo output.
+//! Any number of timers can run at once, each on its own thread with its own
+//! interval, callback and PLL setting. Their period is adjusted based on PTP
+//! servo output. A timer can also be aligned to PTP time, so that its ticks
+//! land on multiples of a period on the PTP timescale and timers on several
+//! machines tick in phase.
+//!
+//! The single-timer API (`start_timer`, `set_interval`, ...) drives the
+//! default timer.
 
+use std::collections::HashMap;
 use std::ffi::c_void;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 use std::thread::{self, JoinHandle};
 
-use crate::client::{get_ptp_stats, is_ptp_running};
+use crate::client::{get_ptp_stats, is_ptp_running, ptp_now};
 
 /// Timer callback function type
 pub type TimerCallback = unsafe extern "C" fn(*mut c_void);
 
-/// Global timer instance
-static TIMER: OnceLock<Mutex<Option<TimerHandle>>> = OnceLock::new();
+/// Running timers by handle
+static TIMERS: OnceLock<Mutex<HashMap<u32, TimerHandle>>> = OnceLock::new();
+
+/// Next timer handle
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
 
-/// Timer configuration
+/// Handle of the default timer (0 = not running)
+static DEFAULT_TIMER: AtomicU32 = AtomicU32::new(0);
+
+/// Default timer configuration, kept while it is stopped
 static TIMER_INTERVAL_MS: AtomicU32 = AtomicU32::new(20);
 static TIMER_PLL_ENABLED: AtomicBool = AtomicBool::new(true);
 
+/// Settings a timer thread reads before every tick
+struct TimerSettings {
+    interval_ms: AtomicU32,
+    pll_enabled: AtomicBool,
+    /// Tick on multiples of this period of PTP time (0 = not aligned)
+    align_us: AtomicU32,
+}
+
 /// Handle to a running timer
 struct TimerHandle {
     running: Arc<AtomicBool>,
     thread: Option<JoinHandle<()>>,
+    settings: Arc<TimerSettings>,
+}
+
+fn timers() -> MutexGuard<'static, HashMap<u32, TimerHandle>> {
+    TIMERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn valid_interval(interval_ms: u32) -> bool {
+    interval_ms > 0 && interval_ms <= 1000
+}
+
+/// Start a timer.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle (non-zero) on success, None if the interval is out of range
+pub fn create_timer(
+    interval_ms: u32,
     callback: Option<TimerCallback>,
-    user_data: *mut c_void,
+    user: *mut c_void,
+) -> Option<u32> {
+    if !valid_interval(interval_ms) {
+        return None;
+    }
+
+    let settings = Arc::new(TimerSettings {
+        interval_ms: AtomicU32::new(interval_ms),
+        pll_enabled: AtomicBool::new(true),
+        align_us: AtomicU32::new(0),
+    });
+    let running = Arc::new(AtomicBool::new(true));
+
+    let running_clone = running.clone();
+    let settings_clone = settings.clone();
+    let user_ptr = user as usize; // Convert to usize for thread safety
+
+    let thread = thread::spawn(move || {
+        timer_thread(running_clone, settings_clone, callback, user_ptr as *mut c_void);
+    });
+
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    timers().insert(handle, TimerHandle {
+        running,
+        thread: Some(thread),
+        settings,
+    });
+    Some(handle)
+}
+
+/// Stop a timer and free its handle.
+///
+/// Waits for the timer thread to finish, unless called from the timer's own
+/// callback.
+///
+/// # Returns
+/// * false if the handle is unknown
+pub fn destroy_timer(handle: u32) -> bool {
+    let timer = timers().remove(&handle);
+    match timer {
+        Some(mut timer) => {
+            timer.running.store(false, Ordering::SeqCst);
+            if let Some(thread) = timer.thread.take() {
+                if thread.thread().id() != thread::current().id() {
+                    let _ = thread.join();
+                }
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Run `f` on the settings of timer `handle`. None if the handle is unknown.
+fn with_settings<T>(handle: u32, f: impl FnOnce(&TimerSettings) -> T) -> Option<T> {
+    timers().get(&handle).map(|timer| f(&timer.settings))
+}
+
+/// Set the interval of timer `handle` (can change while running).
+///
+/// # Returns
+/// * false if the handle is unknown or the interval is out of range
+pub fn set_timer_interval(handle: u32, interval_ms: u32) -> bool {
+    valid_interval(interval_ms)
+        && with_settings(handle, |s| s.interval_ms.store(interval_ms, Ordering::SeqCst)).is_some()
+}
+
+/// Interval of timer `handle` in milliseconds
+pub fn timer_interval(handle: u32) -> Option<u32> {
+    with_settings(handle, |s| s.interval_ms.load(Ordering::SeqCst))
+}
+
+/// Enable/disable PLL adjustment of timer `handle`
+pub fn set_timer_pll(handle: u32, enabled: bool) -> bool {
+    with_settings(handle, |s| s.pll_enabled.store(enabled, Ordering::SeqCst)).is_some()
+}
+
+/// Whether PLL adjustment of timer `handle` is enabled
+pub fn timer_pll(handle: u32) -> Option<bool> {
+    with_settings(handle, |s| s.pll_enabled.load(Ordering::SeqCst))
+}
+
+/// Align the ticks of timer `handle` to multiples of `period_us` of PTP
+/// time (0 = no alignment). Use the interval or a divisor of it.
+///
+/// While the default client has no PTP time (not running, or not yet
+/// synchronized), the timer ticks unaligned.
+pub fn set_timer_alignment(handle: u32, period_us: u32) -> bool {
+    with_settings(handle, |s| s.align_us.store(period_us, Ordering::SeqCst)).is_some()
+}
+
+/// Alignment period of timer `handle` in microseconds
+pub fn timer_alignment(handle: u32) -> Option<u32> {
+    with_settings(handle, |s| s.align_us.load(Ordering::SeqCst))
 }
 
-// Safety: user_data is only accessed from the timer thread
-unsafe impl Send for TimerHandle {}
-unsafe impl Sync for TimerHandle {}
+// ============================================================================
+// Default timer
+// ============================================================================
 
-/// Start the precision timer.
+/// Start the default timer.
 ///
 /// # Arguments
 /// * `interval_ms` - Timer period in milliseconds (1-1000)
@@ -47,107 +186,118 @@ pub fn start_timer(
     user: *mut c_void,
 ) -> i32 {
     // Validate interval
-    if interval_ms == 0 || interval_ms > 1000 {
+    if !valid_interval(interval_ms) {
         return -1;
     }
 
+    // Check if already running
+    if DEFAULT_TIMER.load(Ordering::SeqCst) != 0 {
+        return -3;
+    }
+
     // Store interval
     TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
 
-    let timer_mutex = TIMER.get_or_init(|| Mutex::new(None));
-    let mut timer_guard = match timer_mutex.lock() {
-        Ok(g) => g,
-        Err(_) => return -2,
+    let handle = match create_timer(interval_ms, callback, user) {
+        Some(h) => h,
+        None => return -1,
     };
-
-    // Check if already running
-    if timer_guard.is_some() {
+    set_timer_pll(handle, TIMER_PLL_ENABLED.load(Ordering::SeqCst));
+    if DEFAULT_TIMER.compare_exchange(0, handle, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+        destroy_timer(handle);
         return -3;
     }
 
-    let running = Arc::new(AtomicBool::new(true));
-    let running_clone = running.clone();
-
-    // Store callback info for the thread
-    let cb = callback;
-    let user_ptr = user as usize; // Convert to usize for thread safety
-
-    let thread = thread::spawn(move || {
-        timer_thread(running_clone, cb, user_ptr as *mut c_void);
-    });
-
-    *timer_guard = Some(TimerHandle {
-        running,
-        thread: Some(thread),
-        callback,
-        user_data: user,
-    });
-
     0
 }
 
-/// Stop the timer
+/// Stop the default timer
 pub fn stop_timer() -> i32 {
-    let timer_mutex = match TIMER.get() {
-        Some(m) => m,
-        None => return 0,
-    };
-
-    let mut timer_guard = match timer_mutex.lock() {
-        Ok(g) => g,
-        Err(_) => return -1,
-    };
-
-    if let Some(mut handle) = timer_guard.take() {
-        handle.running.store(false, Ordering::SeqCst);
-        if let Some(thread) = handle.thread.take() {
-            let _ = thread.join();
-        }
+    let handle = DEFAULT_TIMER.swap(0, Ordering::SeqCst);
+    if handle != 0 {
+        destroy_timer(handle);
     }
-
     0
 }
 
-/// Check if timer is running
+/// Check if the default timer is running
 pub fn is_timer_running() -> bool {
-    let timer_mutex = match TIMER.get() {
-        Some(m) => m,
-        None => return false,
-    };
-    let timer_guard = match timer_mutex.lock() {
-        Ok(g) => g,
-        Err(_) => return false,
-    };
-    timer_guard.is_some()
+    DEFAULT_TIMER.load(Ordering::SeqCst) != 0
 }
 
-/// Set timer interval (can change while running)
+/// Set the default timer's interval (can change while running)
 pub fn set_interval(interval_ms: u32) -> i32 {
-    if interval_ms == 0 || interval_ms > 1000 {
+    if !valid_interval(interval_ms) {
         return -1;
     }
     TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+    set_timer_interval(DEFAULT_TIMER.load(Ordering::SeqCst), interval_ms);
     0
 }
 
-/// Get current timer interval
+/// Get the default timer's interval
 pub fn get_interval() -> u32 {
     TIMER_INTERVAL_MS.load(Ordering::SeqCst)
 }
 
-/// Enable/disable PLL adjustment
+/// Enable/disable PLL adjustment of the default timer
 pub fn set_pll_enabled(enabled: bool) {
     TIMER_PLL_ENABLED.store(enabled, Ordering::SeqCst);
+    set_timer_pll(DEFAULT_TIMER.load(Ordering::SeqCst), enabled);
 }
 
-/// Check if PLL adjustment is enabled
+/// Check if PLL adjustment of the default timer is enabled
 pub fn is_pll_enabled() -> bool {
     TIMER_PLL_ENABLED.load(Ordering::SeqCst)
 }
 
+// ============================================================================
+// Timer threads
+// ============================================================================
+
+/// PTP time (ns) of the last aligned tick, None when not aligned
+type AlignedTick = Option<i64>;
+
+/// Time to wait for the next tick in 100ns units
+fn next_wait_100ns(settings: &TimerSettings, last_aligned: &mut AlignedTick) -> u64 {
+    let interval_ms = settings.interval_ms.load(Ordering::SeqCst);
+    let align_us = settings.align_us.load(Ordering::SeqCst);
+
+    if align_us > 0 {
+        if let Some(now_ns) = ptp_now().map(|t| t.tai_ns) {
+            let interval_ns = interval_ms as i64 * 1_000_000;
+            let next = next_aligned_tick(now_ns, *last_aligned, interval_ns, align_us as i64 * 1000);
+            *last_aligned = Some(next);
+            return ((next - now_ns) / 100) as u64;
+        }
+    }
+
+    *last_aligned = None;
+    calculate_adjusted_interval(interval_ms, settings.pll_enabled.load(Ordering::SeqCst))
+}
+
+/// PTP time of the next aligned tick: one interval after the last tick,
+/// snapped to the nearest multiple of `period_ns`. The first tick (or the
+/// first after falling behind) goes to the next multiple after `now_ns`.
+fn next_aligned_tick(now_ns: i64, last_ns: AlignedTick, interval_ns: i64, period_ns: i64) -> i64 {
+    if let Some(last) = last_ns {
+        let target = last + interval_ns;
+        let snapped = (target + period_ns / 2).div_euclid(period_ns) * period_ns;
+        if snapped > now_ns && snapped > last {
+            return snapped;
+        }
+    }
+    (now_ns.div_euclid(period_ns) + 1) * period_ns
+}
+
 /// Timer thread implementation using multimedia timer (Windows)
 #[cfg(windows)]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user: *mut c_void) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
     use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
     use windows_sys::Win32::System::Threading::{
         CreateWaitableTimerW, SetWaitableTimer, WaitForSingleObject, INFINITE,
@@ -158,19 +308,16 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user:
 
     if timer_handle.is_null() || timer_handle == 0 as HANDLE {
         // Fallback to sleep-based timer
-        return timer_thread_fallback(running, callback, user);
+        return timer_thread_fallback(running, settings, callback, user);
     }
 
+    let mut last_aligned = None;
     while running.load(Ordering::SeqCst) {
-        // Get current settings
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
-        // Calculate adjusted interval
-        let adjusted_interval_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
+        // Calculate adjusted interval (or the time to the next aligned tick)
+        let wait_100ns = next_wait_100ns(&settings, &mut last_aligned);
 
         // Set timer (negative value = relative time in 100ns units)
-        let due_time: i64 = -(adjusted_interval_100ns as i64);
+        let due_time: i64 = -(wait_100ns as i64);
         let result = unsafe {
             SetWaitableTimer(timer_handle, &due_time, 0, None, std::ptr::null(), 0)
         };
@@ -200,19 +347,18 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user:
 #[cfg(windows)]
 fn timer_thread_fallback(
     running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
     callback: Option<TimerCallback>,
     user: *mut c_void,
 ) {
+    let mut last_aligned = None;
     while running.load(Ordering::SeqCst) {
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
         // Calculate adjusted interval and convert to ms
-        let adjusted_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
-        let adjusted_ms = (adjusted_100ns / 10_000) as u32;
+        let wait_100ns = next_wait_100ns(&settings, &mut last_aligned);
+        let wait_ms = (wait_100ns / 10_000) as u32;
 
         // Sleep
-        std::thread::sleep(std::time::Duration::from_millis(adjusted_ms as u64));
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms as u64));
 
         // Call user callback
         if let Some(cb) = callback {
@@ -223,17 +369,20 @@ fn timer_thread_fallback(
 
 /// Non-Windows fallback
 #[cfg(not(windows))]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>, user: *mut c_void) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    settings: Arc<TimerSettings>,
+    callback: Option<TimerCallback>,
+    user: *mut c_void,
+) {
+    let mut last_aligned = None;
     while running.load(Ordering::SeqCst) {
-        let base_interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
-        let pll_enabled = TIMER_PLL_ENABLED.load(Ordering::SeqCst);
-
-        // Calculate adjusted interval
-        let adjusted_100ns = calculate_adjusted_interval(base_interval_ms, pll_enabled);
-        let adjusted_us = adjusted_100ns / 10;
+        // Calculate adjusted interval (or the time to the next aligned tick)
+        let wait_100ns = next_wait_100ns(&settings, &mut last_aligned);
+        let wait_us = wait_100ns / 10;
 
         // Sleep
-        std::thread::sleep(std::time::Duration::from_micros(adjusted_us as u64));
+        std::thread::sleep(std::time::Duration::from_micros(wait_us));
 
         // Call user callback
         if let Some(cb) = callback {
@@ -287,4 +436,37 @@ mod tests {
         let base = calculate_adjusted_interval(20, false);
         assert_eq!(base, 200_000);
     }
+
+    #[test]
+    fn test_aligned_ticks_land_on_period() {
+        const MS: i64 = 1_000_000;
+
+        // First tick: next multiple of the period
+        assert_eq!(next_aligned_tick(1_003 * MS, None, 20 * MS, 5 * MS), 1_005 * MS);
+
+        // Then one interval later, on the grid
+        assert_eq!(next_aligned_tick(1_006 * MS, Some(1_005 * MS), 20 * MS, 5 * MS), 1_025 * MS);
+
+        // Fell behind by more than an interval: resync to the grid
+        assert_eq!(next_aligned_tick(1_051 * MS, Some(1_005 * MS), 20 * MS, 5 * MS), 1_055 * MS);
+    }
+
+    #[test]
+    fn test_timers_are_independent() {
+        let fast = create_timer(1, None, std::ptr::null_mut()).unwrap();
+        let slow = create_timer(20, None, std::ptr::null_mut()).unwrap();
+        assert_ne!(fast, slow);
+
+        assert!(set_timer_interval(slow, 40));
+        assert_eq!(timer_interval(fast), Some(1));
+        assert_eq!(timer_interval(slow), Some(40));
+        assert!(set_timer_alignment(slow, 5000));
+        assert_eq!(timer_alignment(fast), Some(0));
+
+        assert!(destroy_timer(fast));
+        assert!(!destroy_timer(fast));
+        assert_eq!(timer_interval(slow), Some(40));
+        assert!(destroy_timer(slow));
+        assert!(create_timer(0, None, std::ptr::null_mut()).is_none());
+    }
 }
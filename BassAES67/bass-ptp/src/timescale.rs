@@ -0,0 +1,158 @@
+//! Grandmaster time properties: UTC offset and leap second handling.
+//!
+//! A grandmaster on the PTP timescale sends TAI. The TAI - UTC offset and
+//! any upcoming leap second are announced in Announce (currentUtcOffset and
+//! the leap59/leap61 flags). The local clock runs on UTC, so Sync timestamps
+//! are converted to UTC with the offset in force at that instant. At a leap
+//! second the offset changes by one second at UTC midnight, possibly before
+//! the grandmaster's next Announce reports the new offset, so the pending
+//! change is applied locally as soon as midnight is crossed.
+
+use crate::messages::*;
+
+const NS_PER_SEC: i64 = 1_000_000_000;
+const NS_PER_DAY: i64 = 86_400 * NS_PER_SEC;
+
+/// Time properties announced by the grandmaster
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeProperties {
+    /// TAI - UTC in seconds (as announced)
+    pub current_utc_offset: i16,
+    /// currentUtcOffset is valid
+    pub utc_offset_valid: bool,
+    /// Last minute of the current UTC day has 61 seconds
+    pub leap61: bool,
+    /// Last minute of the current UTC day has 59 seconds
+    pub leap59: bool,
+    /// Grandmaster uses the PTP (TAI) timescale rather than an arbitrary one
+    pub ptp_timescale: bool,
+    /// Time is traceable to a primary reference
+    pub time_traceable: bool,
+    /// Frequency is traceable to a primary reference
+    pub frequency_traceable: bool,
+    /// Grandmaster time source (IEEE 1588 timeSource enumeration)
+    pub time_source: u8,
+    /// UTC midnight (ns) at which the announced leap second takes effect,
+    /// and the UTC offset it was announced against
+    leap_at_utc_ns: Option<(i64, i16)>,
+}
+
+impl TimeProperties {
+    /// Take over the properties from an Announce received at PTP time `now_ptp_ns`
+    pub fn update(&mut self, announce: &AnnounceMessage, now_ptp_ns: i64) {
+        let flags = announce.header.flags;
+        let leap61 = (flags & FLAG_LEAP61) != 0;
+        let leap59 = (flags & FLAG_LEAP59) != 0;
+
+        self.current_utc_offset = announce.current_utc_offset;
+        self.utc_offset_valid = (flags & FLAG_UTC_OFFSET_VALID) != 0;
+        self.ptp_timescale = (flags & FLAG_PTP_TIMESCALE) != 0;
+        self.time_traceable = (flags & FLAG_TIME_TRACEABLE) != 0;
+        self.frequency_traceable = (flags & FLAG_FREQUENCY_TRACEABLE) != 0;
+        self.time_source = announce.time_source;
+
+        if leap61 || leap59 {
+            // Remember the end of the UTC day the leap was announced for, so
+            // crossing midnight doesn't move the leap to the following day
+            if self.leap_at_utc_ns.is_none() {
+                let utc_ns = now_ptp_ns - self.current_utc_offset as i64 * NS_PER_SEC;
+                let midnight = (utc_ns.div_euclid(NS_PER_DAY) + 1) * NS_PER_DAY;
+                self.leap_at_utc_ns = Some((midnight, self.current_utc_offset));
+            }
+        } else {
+            self.leap_at_utc_ns = None;
+        }
+        self.leap61 = leap61;
+        self.leap59 = leap59;
+    }
+
+    /// TAI - UTC in seconds in force at PTP time `ptp_ns`.
+    ///
+    /// Includes a pending leap second once its UTC midnight has passed.
+    pub fn utc_offset_at(&self, ptp_ns: i64) -> i16 {
+        let offset = self.current_utc_offset;
+        // Once the grandmaster announces the new offset the leap is done,
+        // even if it keeps the leap flag set for a while
+        let leap_at = match self.leap_at_utc_ns {
+            Some((t, base)) if base == offset => t,
+            _ => return offset,
+        };
+
+        let utc_ns = ptp_ns - offset as i64 * NS_PER_SEC;
+        if self.leap61 && utc_ns >= leap_at {
+            offset + 1
+        } else if self.leap59 && utc_ns >= leap_at - NS_PER_SEC {
+            // 23:59:59 is skipped
+            offset - 1
+        } else {
+            offset
+        }
+    }
+
+    /// Convert a PTP timestamp to UTC (unchanged for arbitrary timescales
+    /// and when no valid UTC offset has been announced)
+    pub fn ptp_to_utc_ns(&self, ptp_ns: i64) -> i64 {
+        if !self.ptp_timescale || !self.utc_offset_valid {
+            return ptp_ns;
+        }
+        ptp_ns - self.utc_offset_at(ptp_ns) as i64 * NS_PER_SEC
+    }
+
+    /// Check if a leap second is announced for the end of the current UTC day
+    pub fn leap_pending(&self) -> bool {
+        matches!(self.leap_at_utc_ns, Some((_, base)) if base == self.current_utc_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::master::{build_announce, PtpMasterConfig};
+
+    fn announce(utc_offset: i16, flags: u16) -> AnnounceMessage {
+        let config = PtpMasterConfig {
+            current_utc_offset: utc_offset,
+            ..Default::default()
+        };
+        let mut msg = build_announce(&config, PortIdentity::default(), 0, 0, 0);
+        msg.header.flags |= flags;
+        msg
+    }
+
+    #[test]
+    fn test_utc_conversion() {
+        let mut props = TimeProperties::default();
+        props.update(&announce(37, 0), 0);
+        assert!(props.ptp_timescale && props.utc_offset_valid);
+        assert_eq!(props.ptp_to_utc_ns(100 * NS_PER_SEC), 63 * NS_PER_SEC);
+    }
+
+    #[test]
+    fn test_leap61_applies_at_midnight() {
+        let mut props = TimeProperties::default();
+        // Announced at 12:00 UTC on day 10
+        let noon_tai = 10 * NS_PER_DAY + NS_PER_DAY / 2 + 37 * NS_PER_SEC;
+        props.update(&announce(37, FLAG_LEAP61), noon_tai);
+        assert!(props.leap_pending());
+
+        let midnight_tai = 11 * NS_PER_DAY + 37 * NS_PER_SEC;
+        assert_eq!(props.utc_offset_at(midnight_tai - NS_PER_SEC), 37);
+        assert_eq!(props.utc_offset_at(midnight_tai), 38);
+
+        // The grandmaster catches up after the leap (flag still set at first)
+        props.update(&announce(38, FLAG_LEAP61), midnight_tai + 3 * NS_PER_SEC);
+        assert_eq!(props.utc_offset_at(midnight_tai + 3 * NS_PER_SEC), 38);
+        props.update(&announce(38, 0), midnight_tai + 5 * NS_PER_SEC);
+        assert!(!props.leap_pending());
+        assert_eq!(props.utc_offset_at(midnight_tai + 5 * NS_PER_SEC), 38);
+    }
+
+    #[test]
+    fn test_arbitrary_timescale_is_not_converted() {
+        let mut props = TimeProperties::default();
+        let mut msg = announce(37, 0);
+        msg.header.flags &= !FLAG_PTP_TIMESCALE;
+        props.update(&msg, 0);
+        assert_eq!(props.ptp_to_utc_ns(5 * NS_PER_SEC), 5 * NS_PER_SEC);
+    }
+}
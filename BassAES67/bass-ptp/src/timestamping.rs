@@ -0,0 +1,372 @@
+//! Kernel RX/TX timestamping for PTP event messages.
+//!
+//! Timestamping Sync arrival in user space after `recv` returns adds
+//! scheduler latency to every offset sample. On Linux we ask the kernel for
+//! SO_TIMESTAMPING timestamps instead:
+//! - software timestamps (taken in the network stack) by default
+//! - hardware timestamps (taken by the NIC) when requested and supported
+//!
+//! Hardware timestamps are in the NIC's PTP hardware clock (PHC) timebase,
+//! so the reported offset/frequency then describe the PHC rather than the
+//! system clock. Use them only when the PHC is disciplined to the system
+//! clock (e.g. by phc2sys) or when the PHC itself is the reference.
+//!
+//! When kernel timestamps are unavailable (other platforms, old kernels,
+//! unsupported drivers) we fall back to user-space timestamps.
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::platform;
+
+/// Where a timestamp came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum TimestampSource {
+    /// Taken in user space after recv/send returned
+    #[default]
+    User = 0,
+    /// Taken by the kernel network stack
+    KernelSoftware = 1,
+    /// Taken by the network interface hardware
+    Hardware = 2,
+}
+
+impl TimestampSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimestampSource::User => "user",
+            TimestampSource::KernelSoftware => "kernel",
+            TimestampSource::Hardware => "hardware",
+        }
+    }
+}
+
+/// Requested timestamping mode (applied when the client starts)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TimestampMode {
+    /// User-space timestamps only
+    User = 0,
+    /// Kernel software timestamps (default)
+    Software = 1,
+    /// Hardware timestamps where supported, else kernel software
+    Hardware = 2,
+}
+
+impl From<u8> for TimestampMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => TimestampMode::User,
+            2 => TimestampMode::Hardware,
+            _ => TimestampMode::Software,
+        }
+    }
+}
+
+/// Timestamp mode used for newly started clients
+static TIMESTAMP_MODE: AtomicU8 = AtomicU8::new(TimestampMode::Software as u8);
+
+/// Set the timestamping mode for clients started afterwards
+pub fn set_timestamp_mode(mode: TimestampMode) {
+    TIMESTAMP_MODE.store(mode as u8, Ordering::SeqCst);
+}
+
+/// Get the configured timestamping mode
+pub fn get_timestamp_mode() -> TimestampMode {
+    TimestampMode::from(TIMESTAMP_MODE.load(Ordering::SeqCst))
+}
+
+/// A timestamp and where it came from
+#[derive(Debug, Clone, Copy)]
+pub struct Timestamp {
+    pub ns: i64,
+    pub source: TimestampSource,
+}
+
+impl Timestamp {
+    /// User-space timestamp for "now"
+    pub fn user_now() -> Self {
+        Self {
+            ns: platform::get_timestamp_ns(),
+            source: TimestampSource::User,
+        }
+    }
+}
+
+/// An event socket and the timestamping enabled on it
+pub struct EventSocket {
+    pub socket: UdpSocket,
+    /// Best source enabled (User = no kernel timestamps)
+    pub source: TimestampSource,
+}
+
+impl EventSocket {
+    /// Enable kernel timestamping on `socket`.
+    ///
+    /// Failure is never fatal: the socket keeps working with user-space
+    /// timestamps.
+    pub fn new(socket: UdpSocket, interface: Ipv4Addr, mode: TimestampMode) -> Self {
+        let source = match mode {
+            TimestampMode::User => TimestampSource::User,
+            _ => imp::enable(&socket, interface, mode),
+        };
+        Self { socket, source }
+    }
+
+    /// Fetch the transmit timestamp of the last datagram sent.
+    ///
+    /// Returns None at once when kernel timestamps aren't enabled, and None
+    /// if the kernel did not report one within a few milliseconds. Don't
+    /// call it with locks held that other threads need meanwhile.
+    pub fn tx_timestamp(&self) -> Option<Timestamp> {
+        match self.source {
+            TimestampSource::User => None,
+            _ => imp::tx_timestamp(&self.socket),
+        }
+    }
+}
+
+/// Receive a datagram together with its sender and receive timestamp.
+///
+/// The kernel timestamp is used when present, otherwise a user-space
+/// timestamp is taken immediately after `recv` returns.
+pub fn recv_with_timestamp(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, Option<SocketAddrV4>, Timestamp)> {
+    imp::recv_with_timestamp(socket, buf)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::*;
+    use std::mem;
+    use std::os::unix::io::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    /// How long to wait for the kernel to report a TX timestamp
+    const TX_TIMESTAMP_WAIT: Duration = Duration::from_millis(5);
+
+    const SW_FLAGS: libc::c_uint = libc::SOF_TIMESTAMPING_RX_SOFTWARE
+        | libc::SOF_TIMESTAMPING_TX_SOFTWARE
+        | libc::SOF_TIMESTAMPING_SOFTWARE;
+
+    const HW_FLAGS: libc::c_uint = libc::SOF_TIMESTAMPING_RX_HARDWARE
+        | libc::SOF_TIMESTAMPING_TX_HARDWARE
+        | libc::SOF_TIMESTAMPING_RAW_HARDWARE;
+
+    pub fn enable(socket: &UdpSocket, interface: Ipv4Addr, mode: TimestampMode) -> TimestampSource {
+        let fd = socket.as_raw_fd();
+
+        if mode == TimestampMode::Hardware && enable_nic_timestamping(fd, interface) {
+            // Also request software stamps so packets the NIC does not stamp still get one
+            if set_flags(fd, HW_FLAGS | SW_FLAGS) {
+                return TimestampSource::Hardware;
+            }
+        }
+
+        if set_flags(fd, SW_FLAGS) {
+            TimestampSource::KernelSoftware
+        } else {
+            TimestampSource::User
+        }
+    }
+
+    /// Set SO_TIMESTAMPING flags, preferring timestamp-only TX reports
+    fn set_flags(fd: libc::c_int, flags: libc::c_uint) -> bool {
+        // OPT_TSONLY (Linux 4.0+) avoids looping the whole packet back on the error queue
+        setsockopt_timestamping(fd, flags | libc::SOF_TIMESTAMPING_OPT_TSONLY)
+            || setsockopt_timestamping(fd, flags)
+    }
+
+    fn setsockopt_timestamping(fd: libc::c_int, flags: libc::c_uint) -> bool {
+        let value = flags as libc::c_int;
+        let result = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_TIMESTAMPING,
+                &value as *const _ as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        result == 0
+    }
+
+    /// Turn on hardware timestamping in the NIC driver (SIOCSHWTSTAMP)
+    fn enable_nic_timestamping(fd: libc::c_int, interface: Ipv4Addr) -> bool {
+        let name = match interface_name(interface) {
+            Some(n) => n,
+            None => return false,
+        };
+
+        let mut config = libc::hwtstamp_config {
+            flags: 0,
+            tx_type: libc::HWTSTAMP_TX_ON as libc::c_int,
+            rx_filter: libc::HWTSTAMP_FILTER_PTP_V2_L4_EVENT as libc::c_int,
+        };
+
+        let mut ifr: libc::ifreq = unsafe { mem::zeroed() };
+        for (dst, src) in ifr.ifr_name.iter_mut().zip(name.as_bytes()) {
+            *dst = *src as libc::c_char;
+        }
+        ifr.ifr_ifru.ifru_data = &mut config as *mut _ as *mut libc::c_char;
+
+        unsafe { libc::ioctl(fd, libc::SIOCSHWTSTAMP as _, &mut ifr) == 0 }
+    }
+
+    /// Find the interface name that owns `ip`
+    fn interface_name(ip: Ipv4Addr) -> Option<String> {
+        let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+        if unsafe { libc::getifaddrs(&mut addrs) } != 0 {
+            return None;
+        }
+
+        let mut result = None;
+        let mut cur = addrs;
+        while !cur.is_null() {
+            let ifa = unsafe { &*cur };
+            if !ifa.ifa_addr.is_null()
+                && unsafe { (*ifa.ifa_addr).sa_family } as libc::c_int == libc::AF_INET
+            {
+                let sin = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+                if Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr)) == ip {
+                    let name = unsafe { std::ffi::CStr::from_ptr(ifa.ifa_name) };
+                    result = name.to_str().ok().map(|s| s.to_string());
+                    break;
+                }
+            }
+            cur = ifa.ifa_next;
+        }
+
+        unsafe { libc::freeifaddrs(addrs) };
+        result
+    }
+
+    /// Receive via recvmsg and extract the sender and an SCM_TIMESTAMPING control message
+    fn recvmsg_timestamped(
+        fd: libc::c_int,
+        buf: &mut [u8],
+        flags: libc::c_int,
+    ) -> io::Result<(usize, Option<SocketAddrV4>, Option<Timestamp>)> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        // Room for SCM_TIMESTAMPING (3 timespecs) plus extended error info
+        let mut control = [0u64; 64];
+
+        let mut name: libc::sockaddr_in = unsafe { mem::zeroed() };
+
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut name as *mut _ as *mut libc::c_void;
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        let len = unsafe { libc::recvmsg(fd, &mut msg, flags) };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut timestamp = None;
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let hdr = unsafe { &*cmsg };
+            if hdr.cmsg_level == libc::SOL_SOCKET && hdr.cmsg_type == libc::SCM_TIMESTAMPING {
+                // Three timespecs: [0] software, [1] deprecated, [2] raw hardware
+                let ts = unsafe {
+                    std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const [libc::timespec; 3])
+                };
+                timestamp = timespec_to_timestamp(&ts[2], TimestampSource::Hardware)
+                    .or_else(|| timespec_to_timestamp(&ts[0], TimestampSource::KernelSoftware));
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+
+        let from = if msg.msg_namelen as usize >= mem::size_of::<libc::sockaddr_in>()
+            && name.sin_family as libc::c_int == libc::AF_INET
+        {
+            Some(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(name.sin_addr.s_addr)),
+                u16::from_be(name.sin_port),
+            ))
+        } else {
+            None
+        };
+
+        Ok((len as usize, from, timestamp))
+    }
+
+    // time_t/c_long are 32-bit on some targets
+    #[allow(clippy::unnecessary_cast)]
+    fn timespec_to_timestamp(ts: &libc::timespec, source: TimestampSource) -> Option<Timestamp> {
+        if ts.tv_sec == 0 && ts.tv_nsec == 0 {
+            return None;
+        }
+        Some(Timestamp {
+            ns: ts.tv_sec as i64 * 1_000_000_000 + ts.tv_nsec as i64,
+            source,
+        })
+    }
+
+    pub fn recv_with_timestamp(
+        socket: &UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, Option<SocketAddrV4>, Timestamp)> {
+        let (len, from, ts) = recvmsg_timestamped(socket.as_raw_fd(), buf, 0)?;
+        Ok((len, from, ts.unwrap_or_else(Timestamp::user_now)))
+    }
+
+    pub fn tx_timestamp(socket: &UdpSocket) -> Option<Timestamp> {
+        let fd = socket.as_raw_fd();
+        let mut buf = [0u8; 256];
+        let deadline = Instant::now() + TX_TIMESTAMP_WAIT;
+        let mut latest = None;
+
+        // Drain the error queue and keep the newest report, so a stale
+        // timestamp from an earlier send can't be paired with this one
+        loop {
+            match recvmsg_timestamped(fd, &mut buf, libc::MSG_ERRQUEUE | libc::MSG_DONTWAIT) {
+                Ok((_, _, Some(ts))) => latest = Some(ts),
+                Ok((_, _, None)) => continue,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if latest.is_some() || Instant::now() >= deadline {
+                        return latest;
+                    }
+                    std::thread::sleep(Duration::from_micros(100));
+                }
+                Err(_) => return latest,
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::*;
+
+    pub fn enable(_socket: &UdpSocket, _interface: Ipv4Addr, _mode: TimestampMode) -> TimestampSource {
+        TimestampSource::User
+    }
+
+    pub fn recv_with_timestamp(
+        socket: &UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, Option<SocketAddrV4>, Timestamp)> {
+        let (len, from) = socket.recv_from(buf)?;
+        let from = match from {
+            std::net::SocketAddr::V4(addr) => Some(addr),
+            _ => None,
+        };
+        Ok((len, from, Timestamp::user_now()))
+    }
+
+    pub fn tx_timestamp(_socket: &UdpSocket) -> Option<Timestamp> {
+        None
+    }
+}
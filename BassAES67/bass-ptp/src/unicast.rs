@@ -0,0 +1,288 @@
+//! Unicast negotiation (IEEE 1588-2008 clause 16.1) for the slave side.
+//!
+//! In unicast mode the client asks every configured master for Announce
+//! messages via REQUEST_UNICAST_TRANSMISSION TLVs. Once BMCA has picked a
+//! grandmaster among them, Sync and Delay_Resp are also requested from that
+//! master. Grants are renewed before they expire and cancelled when the
+//! grandmaster changes.
+
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+use crate::messages::*;
+
+/// Minimum time between two requests for the same grant
+const REQUEST_RETRY: Duration = Duration::from_secs(2);
+
+/// Time to wait before asking again after a master denied a request
+const DENIED_RETRY: Duration = Duration::from_secs(16);
+
+/// Negotiation state of a single message type with a single master
+#[derive(Debug, Clone, Copy, Default)]
+struct Grant {
+    /// When the current grant runs out (None = not granted)
+    granted_until: Option<Instant>,
+    /// Granted log2 message interval
+    log_interval: i8,
+    /// When we last sent a request for this grant
+    last_request: Option<Instant>,
+    /// Master explicitly denied the last request
+    denied: bool,
+}
+
+impl Grant {
+    fn is_active(&self, now: Instant) -> bool {
+        matches!(self.granted_until, Some(until) if until > now)
+    }
+
+    /// Check if a (re)request should be sent now
+    fn needs_request(&self, now: Instant, renew_margin: Duration) -> bool {
+        let retry = if self.denied { DENIED_RETRY } else { REQUEST_RETRY };
+        if let Some(last) = self.last_request {
+            if now.duration_since(last) < retry {
+                return false;
+            }
+        }
+        match self.granted_until {
+            Some(until) => until.saturating_duration_since(now) < renew_margin,
+            None => true,
+        }
+    }
+}
+
+/// Grants held with one configured master
+#[derive(Debug, Clone)]
+struct MasterGrants {
+    addr: Ipv4Addr,
+    announce: Grant,
+    sync: Grant,
+    delay_resp: Grant,
+}
+
+impl MasterGrants {
+    fn grant_mut(&mut self, message_type: PtpMessageType) -> Option<&mut Grant> {
+        match message_type {
+            PtpMessageType::Announce => Some(&mut self.announce),
+            PtpMessageType::Sync => Some(&mut self.sync),
+            PtpMessageType::DelayResp => Some(&mut self.delay_resp),
+            _ => None,
+        }
+    }
+}
+
+/// Slave-side unicast negotiation with a list of masters
+#[derive(Debug, Clone)]
+pub struct UnicastNegotiator {
+    masters: Vec<MasterGrants>,
+    duration_s: u32,
+    log_sync_interval: i8,
+    log_announce_interval: i8,
+    log_delay_req_interval: i8,
+}
+
+impl UnicastNegotiator {
+    pub fn new(
+        masters: &[Ipv4Addr],
+        duration_s: u32,
+        log_sync_interval: i8,
+        log_announce_interval: i8,
+        log_delay_req_interval: i8,
+    ) -> Self {
+        Self {
+            masters: masters
+                .iter()
+                .map(|&addr| MasterGrants {
+                    addr,
+                    announce: Grant::default(),
+                    sync: Grant::default(),
+                    delay_resp: Grant::default(),
+                })
+                .collect(),
+            duration_s,
+            log_sync_interval,
+            log_announce_interval,
+            log_delay_req_interval,
+        }
+    }
+
+    /// Check if `addr` is one of the configured masters
+    pub fn is_master(&self, addr: Ipv4Addr) -> bool {
+        self.masters.iter().any(|m| m.addr == addr)
+    }
+
+    /// Number of currently active grants (all masters, all message types)
+    pub fn active_grants(&self, now: Instant) -> u32 {
+        self.masters
+            .iter()
+            .map(|m| {
+                m.announce.is_active(now) as u32
+                    + m.sync.is_active(now) as u32
+                    + m.delay_resp.is_active(now) as u32
+            })
+            .sum()
+    }
+
+    /// Work out which TLVs must be sent to which master right now.
+    ///
+    /// Announce is requested from every master; Sync and Delay_Resp only
+    /// from `grandmaster`. Sync/Delay_Resp grants held with any other master
+    /// are cancelled.
+    pub fn poll(&mut self, now: Instant, grandmaster: Option<Ipv4Addr>) -> Vec<(Ipv4Addr, Vec<UnicastTlv>)> {
+        let renew_margin = Duration::from_secs((self.duration_s / 4).max(2) as u64);
+        let mut out = Vec::new();
+
+        for master in &mut self.masters {
+            let mut tlvs = Vec::new();
+            let selected = grandmaster == Some(master.addr);
+
+            let wanted = [
+                (PtpMessageType::Announce, self.log_announce_interval, true),
+                (PtpMessageType::Sync, self.log_sync_interval, selected),
+                (PtpMessageType::DelayResp, self.log_delay_req_interval, selected),
+            ];
+
+            for (message_type, log_interval, want) in wanted {
+                let grant = match master.grant_mut(message_type) {
+                    Some(g) => g,
+                    None => continue,
+                };
+
+                if want {
+                    if grant.needs_request(now, renew_margin) {
+                        grant.last_request = Some(now);
+                        tlvs.push(UnicastTlv::Request {
+                            message_type,
+                            log_inter_message_period: log_interval,
+                            duration_s: self.duration_s,
+                        });
+                    }
+                } else if grant.is_active(now) {
+                    // No longer our grandmaster: release the grant
+                    *grant = Grant::default();
+                    tlvs.push(UnicastTlv::Cancel { message_type });
+                }
+            }
+
+            if !tlvs.is_empty() {
+                out.push((master.addr, tlvs));
+            }
+        }
+
+        out
+    }
+
+    /// Process a Signaling message from `from`.
+    ///
+    /// Returns TLVs that must be sent back (Cancel acknowledgements).
+    pub fn handle_signaling(&mut self, now: Instant, from: Ipv4Addr, msg: &SignalingMessage) -> Vec<UnicastTlv> {
+        let master = match self.masters.iter_mut().find(|m| m.addr == from) {
+            Some(m) => m,
+            None => return Vec::new(),
+        };
+
+        let mut replies = Vec::new();
+        for tlv in msg.unicast_tlvs() {
+            match tlv {
+                UnicastTlv::Grant {
+                    message_type,
+                    log_inter_message_period,
+                    duration_s,
+                    ..
+                } => {
+                    if let Some(grant) = master.grant_mut(message_type) {
+                        if duration_s == 0 {
+                            grant.granted_until = None;
+                            grant.denied = true;
+                        } else {
+                            grant.granted_until = Some(now + Duration::from_secs(duration_s as u64));
+                            grant.log_interval = log_inter_message_period;
+                            grant.denied = false;
+                        }
+                    }
+                }
+                UnicastTlv::Cancel { message_type } => {
+                    if let Some(grant) = master.grant_mut(message_type) {
+                        *grant = Grant::default();
+                    }
+                    replies.push(UnicastTlv::AcknowledgeCancel { message_type });
+                }
+                _ => {}
+            }
+        }
+
+        replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GM: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const OTHER: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    fn grant(message_type: PtpMessageType, duration_s: u32) -> SignalingMessage {
+        SignalingMessage::new(
+            PortIdentity::default(),
+            1,
+            0,
+            PortIdentity::ALL,
+            vec![UnicastTlv::Grant {
+                message_type,
+                log_inter_message_period: 0,
+                duration_s,
+                renewal_invited: true,
+            }
+            .to_tlv()],
+        )
+    }
+
+    #[test]
+    fn test_requests_announce_from_all_then_sync_from_gm() {
+        let mut n = UnicastNegotiator::new(&[GM, OTHER], 60, -3, 1, 0);
+        let now = Instant::now();
+
+        let out = n.poll(now, None);
+        assert_eq!(out.len(), 2);
+        assert!(out.iter().all(|(_, tlvs)| tlvs.len() == 1));
+
+        // Grants arrive, GM gets selected
+        n.handle_signaling(now, GM, &grant(PtpMessageType::Announce, 60));
+        n.handle_signaling(now, OTHER, &grant(PtpMessageType::Announce, 60));
+        let later = now + REQUEST_RETRY;
+        let out = n.poll(later, Some(GM));
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, GM);
+        assert_eq!(out[0].1.len(), 2); // Sync + Delay_Resp
+    }
+
+    #[test]
+    fn test_cancels_grants_when_grandmaster_changes() {
+        let mut n = UnicastNegotiator::new(&[GM, OTHER], 60, -3, 1, 0);
+        let now = Instant::now();
+        n.poll(now, Some(GM));
+        n.handle_signaling(now, GM, &grant(PtpMessageType::Sync, 60));
+
+        let out = n.poll(now + REQUEST_RETRY, Some(OTHER));
+        let gm_tlvs = &out.iter().find(|(a, _)| *a == GM).unwrap().1;
+        assert!(gm_tlvs.contains(&UnicastTlv::Cancel {
+            message_type: PtpMessageType::Sync
+        }));
+    }
+
+    #[test]
+    fn test_signaling_round_trip() {
+        let msg = grant(PtpMessageType::Sync, 300);
+        let parsed = SignalingMessage::parse(&msg.to_bytes()).unwrap();
+        assert_eq!(parsed.target_port_identity, PortIdentity::ALL);
+        assert_eq!(
+            parsed.unicast_tlvs(),
+            vec![UnicastTlv::Grant {
+                message_type: PtpMessageType::Sync,
+                log_inter_message_period: 0,
+                duration_s: 300,
+                renewal_invited: true,
+            }]
+        );
+    }
+}
@@ -1,11 +1,12 @@
-//! FFmpeg AAC decoder bindings for bass-rtp.
+//! FFmpeg AAC codec bindings for bass-rtp.
 //!
 //! AAC (Advanced Audio Coding) is a lossy audio codec commonly used for
-//! streaming and broadcast. This module uses FFmpeg's libavcodec for
-//! decoding AAC audio received from Z/IP ONE (PT 99 / MP2-AAC Xstream).
+//! streaming and broadcast. This module uses FFmpeg's libavcodec for:
+//! - Decoding ADTS AAC from Z/IP ONE (PT 99 / MP2-AAC Xstream)
+//! - Decoding and encoding AAC-LATM (PT 122), framed by the `latm` module
 //!
-//! Note: AAC encoding is not supported due to FFmpeg API complexity.
-//! For sending to Z/IP ONE, use PCM or MP2 codecs instead.
+//! AAC-LC is encoded with FFmpeg's native encoder. HE-AAC and AAC-ELD need
+//! an FFmpeg build with libfdk_aac; without it those encoders fail to open.
 //!
 //! Requires: avcodec-62.dll, avutil-60.dll (Windows)
 //!           libavcodec.so.62, libavutil.so.60 (Linux)
@@ -21,7 +22,9 @@ use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Once;
 
-use super::CodecError;
+use super::latm::{self, AudioSpecificConfig, LatmDepacketizer, AOT_AAC_LC, AOT_ER_AAC_ELD};
+use super::{AudioEncoder, AudioFormat, CodecError};
+use crate::rtp::PayloadCodec;
 
 // ============================================================================
 // FFmpeg Availability Check
@@ -155,11 +158,65 @@ pub struct AVPacket {
     _private: [u8; 0],
 }
 
+/// Reference to a data buffer (public layout)
+#[repr(C)]
+struct AVBufferRef {
+    buffer: *mut std::ffi::c_void,
+    data: *mut u8,
+    size: usize,
+}
+
+/// Leading fields of AVPacket
+#[repr(C)]
+struct PacketHeader {
+    buf: *mut std::ffi::c_void,
+    pts: i64,
+    dts: i64,
+    data: *mut u8,
+    size: c_int,
+}
+
+/// Leading fields of AVFrame
+#[repr(C)]
+struct FrameHeader {
+    data: [*mut u8; 8],
+    linesize: [c_int; 8],
+    extended_data: *mut *mut u8,
+    width: c_int,
+    height: c_int,
+    nb_samples: c_int,
+    format: c_int,
+}
+
+/// AVFrame fields up to `buf`, as laid out by libavutil 60
+///
+/// The encoder hands FFmpeg a refcounted frame so it is referenced rather
+/// than copied; copying would need the channel layout, which sits much
+/// further into the struct. The layout may change with the libavutil major
+/// version, so the encoder refuses to open against any other one.
+#[repr(C)]
+struct EncodeFrameHeader {
+    header: FrameHeader,
+    pict_type: c_int,
+    sample_aspect_ratio: [c_int; 2],
+    pts: i64,
+    pkt_dts: i64,
+    time_base: [c_int; 2],
+    quality: c_int,
+    opaque: *mut std::ffi::c_void,
+    repeat_pict: c_int,
+    sample_rate: c_int,
+    buf: [*mut AVBufferRef; 8],
+}
+
 
 // ============================================================================
 // FFmpeg Constants
 // ============================================================================
 
+/// libavutil major version `EncodeFrameHeader` is laid out for
+const LIBAVUTIL_VERSION_MAJOR: u32 = 60;
+
 /// AV_CODEC_ID_AAC = 0x15000 + 2 = 86018
 const AV_CODEC_ID_AAC: c_int = 0x15002;
 
@@ -175,6 +232,21 @@ const AV_SAMPLE_FMT_S16P: c_int = 6;
 /// Sample format: float planar
 const AV_SAMPLE_FMT_FLTP: c_int = 8;
 
+/// AV_OPT_SEARCH_CHILDREN - also set private codec options
+const AV_OPT_SEARCH_CHILDREN: c_int = 1;
+
+/// AV_PROFILE_AAC_LOW
+const AV_PROFILE_AAC_LOW: i64 = 1;
+
+/// AV_PROFILE_AAC_HE
+const AV_PROFILE_AAC_HE: i64 = 4;
+
+/// AV_PROFILE_AAC_ELD
+const AV_PROFILE_AAC_ELD: i64 = 38;
+
+/// RTP payload type for AAC-LATM (Z/IP ONE)
+const LATM_PAYLOAD_TYPE: u8 = 122;
+
 /// AVERROR_EAGAIN - need more input
 const AVERROR_EAGAIN: c_int = -11; // -EAGAIN on most systems
 
@@ -190,6 +262,8 @@ const AVERROR_EOF: c_int = -(('E' as c_int) | (('O' as c_int) << 8) | (('F' as c
 extern "C" {
     // Codec lookup
     fn avcodec_find_decoder(id: c_int) -> *const AVCodec;
+    fn avcodec_find_decoder_by_name(name: *const i8) -> *const AVCodec;
+    fn avcodec_find_encoder_by_name(name: *const i8) -> *const AVCodec;
 
     // Context management
     fn avcodec_alloc_context3(codec: *const AVCodec) -> *mut AVCodecContext;
@@ -200,6 +274,10 @@ extern "C" {
     fn avcodec_send_packet(ctx: *mut AVCodecContext, pkt: *const AVPacket) -> c_int;
     fn avcodec_receive_frame(ctx: *mut AVCodecContext, frame: *mut AVFrame) -> c_int;
 
+    // Encode API
+    fn avcodec_send_frame(ctx: *mut AVCodecContext, frame: *const AVFrame) -> c_int;
+    fn avcodec_receive_packet(ctx: *mut AVCodecContext, pkt: *mut AVPacket) -> c_int;
+
     // Context properties
     fn av_opt_set_int(obj: *mut std::ffi::c_void, name: *const i8, val: i64, search_flags: c_int) -> c_int;
 }
@@ -217,19 +295,33 @@ extern "C" {
     fn av_packet_free(pkt: *mut *mut AVPacket);
     fn av_packet_unref(pkt: *mut AVPacket);
 
+    // Buffers
+    fn av_buffer_alloc(size: usize) -> *mut AVBufferRef;
+
+    // Options
+    fn av_opt_set(obj: *mut std::ffi::c_void, name: *const i8, val: *const i8, search_flags: c_int) -> c_int;
+    fn av_opt_get_int(obj: *mut std::ffi::c_void, name: *const i8, search_flags: c_int, out_val: *mut i64) -> c_int;
+
     // Error handling
     fn av_strerror(errnum: c_int, errbuf: *mut i8, errbuf_size: usize) -> c_int;
+
+    // Version
+    fn avutil_version() -> u32;
 }
 
 #[cfg(target_os = "linux")]
 #[link(name = "avcodec")]
 extern "C" {
     fn avcodec_find_decoder(id: c_int) -> *const AVCodec;
+    fn avcodec_find_decoder_by_name(name: *const i8) -> *const AVCodec;
+    fn avcodec_find_encoder_by_name(name: *const i8) -> *const AVCodec;
     fn avcodec_alloc_context3(codec: *const AVCodec) -> *mut AVCodecContext;
     fn avcodec_free_context(ctx: *mut *mut AVCodecContext);
     fn avcodec_open2(ctx: *mut AVCodecContext, codec: *const AVCodec, options: *mut *mut std::ffi::c_void) -> c_int;
     fn avcodec_send_packet(ctx: *mut AVCodecContext, pkt: *const AVPacket) -> c_int;
     fn avcodec_receive_frame(ctx: *mut AVCodecContext, frame: *mut AVFrame) -> c_int;
+    fn avcodec_send_frame(ctx: *mut AVCodecContext, frame: *const AVFrame) -> c_int;
+    fn avcodec_receive_packet(ctx: *mut AVCodecContext, pkt: *mut AVPacket) -> c_int;
     fn av_opt_set_int(obj: *mut std::ffi::c_void, name: *const i8, val: i64, search_flags: c_int) -> c_int;
 }
 
@@ -242,7 +334,11 @@ extern "C" {
     fn av_packet_alloc() -> *mut AVPacket;
     fn av_packet_free(pkt: *mut *mut AVPacket);
     fn av_packet_unref(pkt: *mut AVPacket);
+    fn av_buffer_alloc(size: usize) -> *mut AVBufferRef;
+    fn av_opt_set(obj: *mut std::ffi::c_void, name: *const i8, val: *const i8, search_flags: c_int) -> c_int;
+    fn av_opt_get_int(obj: *mut std::ffi::c_void, name: *const i8, search_flags: c_int, out_val: *mut i64) -> c_int;
     fn av_strerror(errnum: c_int, errbuf: *mut i8, errbuf_size: usize) -> c_int;
+    fn avutil_version() -> u32;
 }
 
 // ============================================================================
@@ -266,8 +362,8 @@ pub fn error_string(errnum: c_int) -> String {
 
 /// AAC Decoder using FFmpeg libavcodec
 ///
-/// Supports AAC with ADTS format (Z/IP ONE PT 99 / MP2-AAC Xstream).
-/// Note: PT 122 (AAC-LATM) is NOT supported - requires native LATM decoder.
+/// Supports AAC with ADTS format (Z/IP ONE PT 99 / MP2-AAC Xstream) and,
+/// created with `new_latm`, AAC-LATM (PT 122).
 pub struct Decoder {
     /// AAC decoder context
     ctx: *mut AVCodecContext,
@@ -275,6 +371,8 @@ pub struct Decoder {
     packet: *mut AVPacket,
     sample_rate: u32,
     channels: u8,
+    /// LATM framing (PT 122), None for ADTS
+    latm: Option<LatmDepacketizer>,
 }
 
 // SAFETY: FFmpeg contexts are internally managed
@@ -288,14 +386,30 @@ impl Decoder {
     ///
     /// Returns error if FFmpeg libraries are not available.
     pub fn new() -> Result<Self, CodecError> {
+        Self::open(None)
+    }
+
+    /// Create a decoder for AAC-LATM (PT 122) with the config sent in-band.
+    ///
+    /// Handles every profile FFmpeg decodes (AAC-LC, HE-AAC v1/v2, AAC-ELD);
+    /// the profile comes from the AudioSpecificConfig in the stream.
+    pub fn new_latm() -> Result<Self, CodecError> {
+        Self::open(Some(LatmDepacketizer::new()))
+    }
+
+    fn open(latm: Option<LatmDepacketizer>) -> Result<Self, CodecError> {
         // Check FFmpeg availability first (safe check before calling FFmpeg functions)
         if !is_available() {
             return Err(CodecError::Other("FFmpeg not available - AAC codec disabled".to_string()));
         }
 
         unsafe {
-            // Find the AAC decoder
-            let codec = avcodec_find_decoder(AV_CODEC_ID_AAC);
+            // Find the AAC decoder (LATM frames go to FFmpeg as LOAS)
+            let codec = if latm.is_some() {
+                avcodec_find_decoder_by_name(c"aac_latm".as_ptr())
+            } else {
+                avcodec_find_decoder(AV_CODEC_ID_AAC)
+            };
             if codec.is_null() {
                 return Err(CodecError::Other("AAC decoder not found".to_string()));
             }
@@ -336,46 +450,55 @@ impl Decoder {
                 packet,
                 sample_rate: 48000,
                 channels: 2,
+                latm,
             })
         }
     }
 
     /// Decode AAC data to PCM samples.
     ///
-    /// Only supports ADTS format (PT 99 / MP2-AAC Xstream).
-    /// PT 122 (LATM) is not supported.
-    ///
     /// # Arguments
-    /// * `data` - AAC compressed data (ADTS format or RFC 3640 with AU headers)
+    /// * `data` - AAC compressed data (ADTS format or RFC 3640 with AU headers,
+    ///   or an MP4A-LATM payload for LATM decoders)
     /// * `output` - Output buffer for f32 interleaved stereo samples
     ///
     /// # Returns
     /// Number of samples written (total, including all channels), or error.
     pub fn decode(&mut self, data: &[u8], output: &mut [f32]) -> Result<usize, CodecError> {
+        if let Some(latm) = self.latm.as_mut() {
+            let units = latm.depacketize(data)?;
+            let config = match latm.config() {
+                Some(config) => config.clone(),
+                None => return Ok(0), // No config yet
+            };
+
+            let mut total = 0;
+            for au in &units {
+                let frame = latm::loas_frame(&config, au)?;
+                total += self.decode_packet(&frame, &mut output[total..])?;
+            }
+            return Ok(total);
+        }
+
         if data.len() < 4 {
             return Ok(0); // Too small
         }
 
         // Prepare data - strip AU headers if present
         let aac_data = self.prepare_data(data);
+        self.decode_packet(aac_data, output)
+    }
 
+    /// Send one packet to the decoder and extract the frame it yields.
+    fn decode_packet(&mut self, data: &[u8], output: &mut [f32]) -> Result<usize, CodecError> {
         unsafe {
             // Unref any previous packet data
             av_packet_unref(self.packet);
 
             // Set up packet with data
-            #[repr(C)]
-            struct PacketHeader {
-                buf: *mut std::ffi::c_void,
-                pts: i64,
-                dts: i64,
-                data: *mut u8,
-                size: c_int,
-            }
-
             let pkt_header = self.packet as *mut PacketHeader;
-            (*pkt_header).data = aac_data.as_ptr() as *mut u8;
-            (*pkt_header).size = aac_data.len() as c_int;
+            (*pkt_header).data = data.as_ptr() as *mut u8;
+            (*pkt_header).size = data.len() as c_int;
 
             // Send packet to decoder
             let ret = avcodec_send_packet(self.ctx, self.packet);
@@ -419,17 +542,6 @@ impl Decoder {
     /// Extract samples from decoded frame to output buffer.
     fn extract_samples(&self, output: &mut [f32]) -> Result<usize, CodecError> {
         unsafe {
-            #[repr(C)]
-            struct FrameHeader {
-                data: [*mut u8; 8],
-                linesize: [c_int; 8],
-                extended_data: *mut *mut u8,
-                width: c_int,
-                height: c_int,
-                nb_samples: c_int,
-                format: c_int,
-            }
-
             let frame_header = self.frame as *const FrameHeader;
             let nb_samples = (*frame_header).nb_samples as usize;
             let format = (*frame_header).format;
@@ -572,6 +684,336 @@ impl Drop for Decoder {
     }
 }
 
+// ============================================================================
+// AAC Encoder
+// ============================================================================
+
+/// AAC profile to encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AacProfile {
+    /// AAC-LC (FFmpeg native encoder)
+    Lc,
+    /// HE-AAC v1, SBR signalled implicitly (libfdk_aac)
+    He,
+    /// AAC-ELD (libfdk_aac)
+    Eld,
+}
+
+impl AacProfile {
+    /// Profile sent for a payload codec, None if it is not an AAC-LATM codec
+    pub fn from_codec(codec: PayloadCodec) -> Option<Self> {
+        match codec {
+            PayloadCodec::AacLatm => Some(AacProfile::Lc),
+            PayloadCodec::HeAac => Some(AacProfile::He),
+            PayloadCodec::AacEld => Some(AacProfile::Eld),
+            _ => None,
+        }
+    }
+
+    /// Supported bitrate range in kbps for a channel count
+    fn bitrate_range(self, channels: u8) -> (u32, u32) {
+        let (min, max) = match self {
+            AacProfile::Lc => (16, 320),
+            AacProfile::He => (16, 64),
+            AacProfile::Eld => (24, 256),
+        };
+        if channels == 1 {
+            (min / 2, max / 2)
+        } else {
+            (min, max)
+        }
+    }
+}
+
+/// AAC Encoder using FFmpeg libavcodec
+///
+/// Produces MP4A-LATM RTP payloads (PT 122) with the config in-band.
+pub struct Encoder {
+    ctx: *mut AVCodecContext,
+    frame: *mut AVFrame,
+    packet: *mut AVPacket,
+    profile: AacProfile,
+    format: AudioFormat,
+    /// Input samples per channel per frame
+    frame_size: usize,
+    /// FFmpeg sample format the encoder takes
+    sample_fmt: c_int,
+    /// Config signalled in every packet
+    config: AudioSpecificConfig,
+}
+
+// SAFETY: FFmpeg contexts are internally managed
+unsafe impl Send for Encoder {}
+
+/// Set a codec context option, failing with its name
+unsafe fn set_option(ctx: *mut AVCodecContext, name: &[u8], value: &[u8]) -> Result<(), CodecError> {
+    let ret = av_opt_set(ctx as *mut _, name.as_ptr() as *const i8, value.as_ptr() as *const i8, AV_OPT_SEARCH_CHILDREN);
+    if ret < 0 {
+        return Err(CodecError::Other(format!(
+            "AAC option {}: {}",
+            String::from_utf8_lossy(&name[..name.len() - 1]),
+            error_string(ret)
+        )));
+    }
+    Ok(())
+}
+
+/// Set an integer codec context option, failing with its name
+unsafe fn set_int_option(ctx: *mut AVCodecContext, name: &[u8], value: i64) -> Result<(), CodecError> {
+    let ret = av_opt_set_int(ctx as *mut _, name.as_ptr() as *const i8, value, AV_OPT_SEARCH_CHILDREN);
+    if ret < 0 {
+        return Err(CodecError::Other(format!(
+            "AAC option {}: {}",
+            String::from_utf8_lossy(&name[..name.len() - 1]),
+            error_string(ret)
+        )));
+    }
+    Ok(())
+}
+
+impl Encoder {
+    /// Create a new AAC encoder.
+    ///
+    /// # Arguments
+    /// * `format` - Audio format (sample rate and channels, 1 or 2)
+    /// * `bitrate` - Target bitrate in kbps, clamped to the profile's range
+    /// * `profile` - AAC profile
+    ///
+    /// Returns error if FFmpeg is not available, or for HE-AAC/AAC-ELD if
+    /// FFmpeg was built without libfdk_aac.
+    pub fn new(format: AudioFormat, bitrate: u32, profile: AacProfile) -> Result<Self, CodecError> {
+        if format.channels < 1 || format.channels > 2 {
+            return Err(CodecError::Other(format!(
+                "AAC requires 1 or 2 channels, got {}",
+                format.channels
+            )));
+        }
+        if !is_available() {
+            return Err(CodecError::Other("FFmpeg not available - AAC codec disabled".to_string()));
+        }
+        // Frames are filled in through EncodeFrameHeader
+        let avutil_major = unsafe { avutil_version() } >> 16;
+        if avutil_major != LIBAVUTIL_VERSION_MAJOR {
+            return Err(CodecError::Other(format!(
+                "AAC encoding needs libavutil {}, found {}",
+                LIBAVUTIL_VERSION_MAJOR, avutil_major
+            )));
+        }
+
+        let (name, sample_fmt, sample_fmt_name, ff_profile): (&[u8], c_int, &[u8], i64) = match profile {
+            AacProfile::Lc => (b"aac\0", AV_SAMPLE_FMT_FLTP, b"fltp\0", AV_PROFILE_AAC_LOW),
+            AacProfile::He => (b"libfdk_aac\0", AV_SAMPLE_FMT_S16, b"s16\0", AV_PROFILE_AAC_HE),
+            AacProfile::Eld => (b"libfdk_aac\0", AV_SAMPLE_FMT_S16, b"s16\0", AV_PROFILE_AAC_ELD),
+        };
+        let (min, max) = profile.bitrate_range(format.channels);
+        let bitrate = bitrate.clamp(min, max);
+
+        unsafe {
+            let codec = avcodec_find_encoder_by_name(name.as_ptr() as *const i8);
+            if codec.is_null() {
+                return Err(CodecError::Other(match profile {
+                    AacProfile::Lc => "AAC encoder not found".to_string(),
+                    _ => format!("{:?} needs FFmpeg built with libfdk_aac", profile),
+                }));
+            }
+
+            let ctx = avcodec_alloc_context3(codec);
+            if ctx.is_null() {
+                return Err(CodecError::NotInitialized);
+            }
+
+            let layout: &[u8] = if format.channels == 1 { b"mono\0" } else { b"stereo\0" };
+            let configured = set_int_option(ctx, b"ar\0", format.sample_rate as i64)
+                .and_then(|_| set_option(ctx, b"ch_layout\0", layout))
+                .and_then(|_| set_option(ctx, b"sample_fmt\0", sample_fmt_name))
+                .and_then(|_| set_int_option(ctx, b"b\0", bitrate as i64 * 1000))
+                .and_then(|_| set_int_option(ctx, b"profile\0", ff_profile))
+                .and_then(|_| match profile {
+                    // The config we signal has no SBR in it
+                    AacProfile::Lc => Ok(()),
+                    _ => set_option(ctx, b"signaling\0", b"implicit\0"),
+                });
+            if let Err(e) = configured {
+                avcodec_free_context(&mut (ctx as *mut _));
+                return Err(e);
+            }
+
+            let ret = avcodec_open2(ctx, codec, ptr::null_mut());
+            if ret < 0 {
+                avcodec_free_context(&mut (ctx as *mut _));
+                return Err(CodecError::Other(format!("AAC encoder open: {}", error_string(ret))));
+            }
+
+            // Samples per frame as chosen by the encoder
+            let mut frame_size = 0i64;
+            av_opt_get_int(ctx as *mut _, c"frame_size".as_ptr(), 0, &mut frame_size);
+            let frame_size = match (frame_size, profile) {
+                (n, _) if n > 0 => n as usize,
+                (_, AacProfile::Lc) => 1024,
+                (_, AacProfile::He) => 2048,
+                (_, AacProfile::Eld) => 512,
+            };
+
+            let config = match profile {
+                AacProfile::Lc => AudioSpecificConfig::new(AOT_AAC_LC, format.sample_rate, format.channels, frame_size as u32),
+                AacProfile::He => AudioSpecificConfig::new(AOT_AAC_LC, format.sample_rate / 2, format.channels, frame_size as u32 / 2),
+                AacProfile::Eld => AudioSpecificConfig::new(AOT_ER_AAC_ELD, format.sample_rate, format.channels, frame_size as u32),
+            };
+            let config = match config {
+                Ok(config) => config,
+                Err(e) => {
+                    avcodec_free_context(&mut (ctx as *mut _));
+                    return Err(e);
+                }
+            };
+
+            let frame = av_frame_alloc();
+            let packet = av_packet_alloc();
+            if frame.is_null() || packet.is_null() {
+                if !frame.is_null() {
+                    av_frame_free(&mut (frame as *mut _));
+                }
+                if !packet.is_null() {
+                    av_packet_free(&mut (packet as *mut _));
+                }
+                avcodec_free_context(&mut (ctx as *mut _));
+                return Err(CodecError::NotInitialized);
+            }
+
+            Ok(Self {
+                ctx,
+                frame,
+                packet,
+                profile,
+                format,
+                frame_size,
+                sample_fmt,
+                config,
+            })
+        }
+    }
+
+    /// Profile being encoded
+    pub fn profile(&self) -> AacProfile {
+        self.profile
+    }
+
+    /// Config signalled in the stream
+    pub fn config(&self) -> &AudioSpecificConfig {
+        &self.config
+    }
+
+    /// Hand one frame of interleaved float samples to FFmpeg.
+    unsafe fn send_frame(&mut self, pcm: &[f32]) -> Result<(), CodecError> {
+        let channels = self.format.channels as usize;
+        let samples = self.frame_size;
+        let planar = self.sample_fmt == AV_SAMPLE_FMT_FLTP;
+        let size = if planar { samples * channels * 4 } else { samples * channels * 2 };
+
+        let buf = av_buffer_alloc(size);
+        if buf.is_null() {
+            return Err(CodecError::Other("AAC frame allocation failed".to_string()));
+        }
+        let data = (*buf).data;
+
+        let frame = self.frame as *mut EncodeFrameHeader;
+        if planar {
+            for ch in 0..channels {
+                let plane = data.add(ch * samples * 4) as *mut f32;
+                for i in 0..samples {
+                    *plane.add(i) = pcm[i * channels + ch];
+                }
+                (*frame).header.data[ch] = plane as *mut u8;
+            }
+            (*frame).header.linesize[0] = (samples * 4) as c_int;
+        } else {
+            let out = data as *mut i16;
+            for (i, sample) in pcm[..samples * channels].iter().enumerate() {
+                *out.add(i) = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
+            }
+            (*frame).header.data[0] = data;
+            (*frame).header.linesize[0] = size as c_int;
+        }
+        (*frame).header.extended_data = (*frame).header.data.as_mut_ptr();
+        (*frame).header.nb_samples = samples as c_int;
+        (*frame).header.format = self.sample_fmt;
+        (*frame).sample_rate = self.format.sample_rate as c_int;
+        (*frame).buf[0] = buf;
+
+        // FFmpeg takes its own reference; ours goes with the unref
+        let ret = avcodec_send_frame(self.ctx, self.frame);
+        av_frame_unref(self.frame);
+        if ret < 0 {
+            return Err(CodecError::EncodeError(error_string(ret)));
+        }
+        Ok(())
+    }
+}
+
+impl AudioEncoder for Encoder {
+    /// Encode one frame; writes nothing while the encoder is priming.
+    fn encode(&mut self, pcm: &[f32], output: &mut [u8]) -> Result<usize, CodecError> {
+        if pcm.len() < self.total_samples_per_frame() {
+            return Err(CodecError::InvalidInput);
+        }
+
+        unsafe {
+            self.send_frame(pcm)?;
+
+            let mut written = 0;
+            loop {
+                let ret = avcodec_receive_packet(self.ctx, self.packet);
+                if ret == AVERROR_EAGAIN || ret == AVERROR_EOF {
+                    break;
+                }
+                if ret < 0 {
+                    return Err(CodecError::EncodeError(error_string(ret)));
+                }
+
+                let pkt = self.packet as *const PacketHeader;
+                let au = std::slice::from_raw_parts((*pkt).data, (*pkt).size as usize);
+                let element = latm::audio_mux_element(&self.config, au);
+                av_packet_unref(self.packet);
+
+                if written + element.len() > output.len() {
+                    return Err(CodecError::BufferTooSmall);
+                }
+                output[written..written + element.len()].copy_from_slice(&element);
+                written += element.len();
+            }
+            Ok(written)
+        }
+    }
+
+    fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    fn total_samples_per_frame(&self) -> usize {
+        self.frame_size * self.format.channels as usize
+    }
+
+    fn payload_type(&self) -> u8 {
+        LATM_PAYLOAD_TYPE
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.frame.is_null() {
+                av_frame_free(&mut self.frame);
+            }
+            if !self.packet.is_null() {
+                av_packet_free(&mut self.packet);
+            }
+            if !self.ctx.is_null() {
+                avcodec_free_context(&mut self.ctx);
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -594,4 +1036,19 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_encoder_create() {
+        // Like the decoder test, only checks anything when FFmpeg is present
+        match Encoder::new(AudioFormat::standard(), 128, AacProfile::Lc) {
+            Ok(encoder) => {
+                assert_eq!(encoder.frame_size(), 1024);
+                assert_eq!(encoder.payload_type(), 122);
+                assert_eq!(encoder.config().to_bytes(), vec![0x11, 0x90]);
+            }
+            Err(e) => {
+                eprintln!("FFmpeg AAC encoder not available: {:?}", e);
+            }
+        }
+    }
 }
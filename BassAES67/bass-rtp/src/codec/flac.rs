@@ -276,6 +276,31 @@ impl Encoder {
     /// * `format` - Audio format (sample rate and channels)
     /// * `compression_level` - Compression level 0-8 (higher = better compression, more CPU)
     pub fn new(format: AudioFormat, compression_level: u32) -> Result<Self, CodecError> {
+        Self::with_block_size(format, compression_level, DEFAULT_FRAME_SIZE)
+    }
+
+    /// Create a new FLAC encoder with a block size other than the default.
+    ///
+    /// Every frame holds one block, so small blocks keep frames within a
+    /// single RTP packet at the cost of compression.
+    ///
+    /// # Arguments
+    /// * `format` - Audio format (sample rate and channels)
+    /// * `compression_level` - Compression level 0-8 (higher = better compression, more CPU)
+    /// * `frame_size` - Block size in samples per channel (16-4608)
+    pub fn with_block_size(
+        format: AudioFormat,
+        compression_level: u32,
+        frame_size: usize,
+    ) -> Result<Self, CodecError> {
+        // Streamable subset limits for 48kHz and below
+        if !(16..=4608).contains(&frame_size) {
+            return Err(CodecError::Other(format!(
+                "FLAC block size must be 16-4608, got {}",
+                frame_size
+            )));
+        }
+
         // Validate channels (FLAC supports 1-8, but we limit to stereo)
         if format.channels < 1 || format.channels > 2 {
             return Err(CodecError::Other(format!(
@@ -303,20 +328,20 @@ impl Encoder {
             FLAC__stream_encoder_set_bits_per_sample(encoder, 16);
             FLAC__stream_encoder_set_sample_rate(encoder, format.sample_rate);
             FLAC__stream_encoder_set_compression_level(encoder, compression_level);
-            FLAC__stream_encoder_set_blocksize(encoder, DEFAULT_FRAME_SIZE as u32);
+            FLAC__stream_encoder_set_blocksize(encoder, frame_size as u32);
             FLAC__stream_encoder_set_streamable_subset(encoder, 1);
 
             let client_data = Box::new(EncoderClientData {
-                output_buffer: Vec::with_capacity(DEFAULT_FRAME_SIZE * 4),
+                output_buffer: Vec::with_capacity(frame_size * 4),
                 total_written: 0,
             });
 
             Ok(Self {
                 encoder,
                 format,
-                frame_size: DEFAULT_FRAME_SIZE,
+                frame_size,
                 compression_level,
-                sample_buffer: Vec::with_capacity(DEFAULT_FRAME_SIZE * format.channels as usize),
+                sample_buffer: Vec::with_capacity(frame_size * format.channels as usize),
                 client_data,
                 initialized: false,
             })
@@ -382,6 +407,22 @@ impl Encoder {
             self.sample_buffer.push(sample as i32);
         }
 
+        self.encode_buffered(output)
+    }
+
+    /// Encode float PCM samples to FLAC (16-bit).
+    pub fn encode_float(&mut self, pcm: &[f32], output: &mut [u8]) -> Result<usize, CodecError> {
+        self.ensure_initialized()?;
+
+        for &sample in pcm {
+            self.sample_buffer.push((sample.clamp(-1.0, 1.0) * 32767.0) as i32);
+        }
+
+        self.encode_buffered(output)
+    }
+
+    /// Encode all complete frames in the sample buffer
+    fn encode_buffered(&mut self, output: &mut [u8]) -> Result<usize, CodecError> {
         let samples_per_frame = self.total_samples_per_frame();
         let mut total_written = 0;
 
@@ -730,6 +771,15 @@ mod tests {
         assert_eq!(encoder.total_samples_per_frame(), DEFAULT_FRAME_SIZE * 2);
     }
 
+    #[test]
+    fn test_encoder_block_size() {
+        let encoder = Encoder::with_block_size(AudioFormat::standard(), 5, 240).unwrap();
+        assert_eq!(encoder.frame_size(), 240);
+        assert_eq!(encoder.total_samples_per_frame(), 480);
+
+        assert!(Encoder::with_block_size(AudioFormat::standard(), 5, 8).is_err());
+    }
+
     #[test]
     fn test_decoder_create() {
         let decoder = Decoder::new_48k_stereo();
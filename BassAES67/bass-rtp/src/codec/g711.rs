@@ -1,9 +1,11 @@
-//! G.711 mu-law (PCMU) audio codec encoder and decoder.
+//! G.711 mu-law (PCMU) and A-law (PCMA) audio codec encoders and decoders.
 //!
 //! G.711 is a narrowband audio codec operating at 8 kHz sample rate.
 //! Each 8-bit encoded byte represents one 16-bit PCM sample.
+//! Mu-law is used in North America and Japan, A-law in Europe and most
+//! other places.
 //!
-//! Payload type: PT 0 (mu-law)
+//! Payload types: PT 0 (mu-law), PT 8 (A-law)
 //!
 //! Algorithm sourced from ezk-media (MIT license):
 //! https://github.com/kbalt/ezk-media
@@ -235,6 +237,201 @@ impl AudioEncoder for G711UlawEncoder {
     }
 }
 
+// ============================================================================
+// G.711 A-law
+// ============================================================================
+
+/// Decode a single A-law byte to a 16-bit signed sample.
+///
+/// Standard ITU-T G.711 A-law decoding algorithm.
+#[inline]
+fn alaw_decode(a: u8) -> i16 {
+    // Even bits are inverted on the wire
+    let a = a ^ 0x55;
+
+    let mut t = ((a & 0x0F) as i16) << 4;
+    let segment = (a & 0x70) >> 4;
+    match segment {
+        0 => t += 8,
+        1 => t += 0x108,
+        _ => {
+            t += 0x108;
+            t <<= segment - 1;
+        }
+    }
+
+    if a & 0x80 != 0 {
+        t
+    } else {
+        -t
+    }
+}
+
+/// Encode a single 16-bit signed sample to A-law byte.
+///
+/// Standard ITU-T G.711 A-law encoding algorithm.
+#[inline]
+fn alaw_encode(sample: i16) -> u8 {
+    /// Upper bound of each segment (13-bit magnitude)
+    const SEGMENT_END: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+
+    // A-law works on 13-bit magnitudes; the mask sets the sign and
+    // inverts the even bits
+    let sample = (sample as i32) >> 3;
+    let (mask, magnitude) = if sample >= 0 {
+        (0xD5, sample)
+    } else {
+        (0x55, -sample - 1)
+    };
+
+    let segment = match SEGMENT_END.iter().position(|&end| magnitude <= end) {
+        Some(segment) => segment as i32,
+        // Clip to the largest value
+        None => return (0x7F ^ mask) as u8,
+    };
+
+    let mantissa = if segment < 2 {
+        (magnitude >> 1) & 0x0F
+    } else {
+        (magnitude >> segment) & 0x0F
+    };
+
+    (((segment << 4) | mantissa) ^ mask) as u8
+}
+
+/// G.711 A-law decoder.
+///
+/// Decodes 8-bit A-law encoded audio to f32 PCM samples.
+/// Stateless decoder - each byte is independently decoded.
+pub struct G711AlawDecoder;
+
+impl G711AlawDecoder {
+    /// Create a new G.711 A-law decoder.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for G711AlawDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioDecoder for G711AlawDecoder {
+    /// Decode A-law encoded data to f32 samples.
+    ///
+    /// Like the mu-law decoder, upsamples 8 kHz mono to 48 kHz stereo.
+    fn decode(&mut self, data: &[u8], output: &mut [f32]) -> Result<usize, CodecError> {
+        let output_samples = data.len() * 12;
+
+        if output.len() < output_samples {
+            return Err(CodecError::BufferTooSmall);
+        }
+
+        let mut out_idx = 0;
+        for &byte in data.iter() {
+            let f32_sample = alaw_decode(byte) as f32 / 32768.0;
+            for _ in 0..6 {
+                output[out_idx] = f32_sample;     // Left
+                output[out_idx + 1] = f32_sample; // Right
+                out_idx += 2;
+            }
+        }
+
+        Ok(output_samples)
+    }
+
+    /// Frame size in samples per channel (20ms at 48kHz after upsampling = 960 samples).
+    fn frame_size(&self) -> usize {
+        960
+    }
+
+    /// Total samples per frame including all channels (stereo output).
+    fn total_samples_per_frame(&self) -> usize {
+        960 * 2
+    }
+}
+
+/// G.711 A-law encoder.
+///
+/// Encodes f32 PCM samples to 8-bit A-law encoded audio.
+/// Input is 48kHz stereo, output is 8kHz mono A-law.
+pub struct G711AlawEncoder {
+    /// Accumulator for downsampling (6:1 ratio from 48kHz to 8kHz)
+    downsample_accum: f32,
+    downsample_count: usize,
+}
+
+impl G711AlawEncoder {
+    /// Create a new G.711 A-law encoder.
+    pub fn new() -> Self {
+        Self {
+            downsample_accum: 0.0,
+            downsample_count: 0,
+        }
+    }
+}
+
+impl Default for G711AlawEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioEncoder for G711AlawEncoder {
+    /// Encode f32 samples to A-law.
+    ///
+    /// Input: 48kHz stereo f32 samples (interleaved L,R,L,R,...)
+    /// Output: 8kHz mono A-law bytes
+    fn encode(&mut self, pcm: &[f32], output: &mut [u8]) -> Result<usize, CodecError> {
+        let stereo_pairs = pcm.len() / 2;
+        let output_bytes = stereo_pairs / 6;
+
+        if output.len() < output_bytes {
+            return Err(CodecError::BufferTooSmall);
+        }
+
+        let mut out_idx = 0;
+
+        for i in 0..stereo_pairs {
+            let mono = (pcm[i * 2] + pcm[i * 2 + 1]) * 0.5;
+
+            self.downsample_accum += mono;
+            self.downsample_count += 1;
+
+            if self.downsample_count >= 6 {
+                let sample = self.downsample_accum / 6.0;
+                self.downsample_accum = 0.0;
+                self.downsample_count = 0;
+
+                let sample_i16 = (sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
+                output[out_idx] = alaw_encode(sample_i16);
+                out_idx += 1;
+            }
+        }
+
+        Ok(out_idx)
+    }
+
+    /// Frame size in samples per channel.
+    /// 20ms at 48kHz = 960 samples per channel.
+    fn frame_size(&self) -> usize {
+        960
+    }
+
+    /// Total samples per frame (stereo input).
+    /// 20ms at 48kHz stereo = 1920 samples.
+    fn total_samples_per_frame(&self) -> usize {
+        1920
+    }
+
+    /// RTP payload type for G.711 A-law.
+    fn payload_type(&self) -> u8 {
+        8
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -331,4 +528,42 @@ mod tests {
         let unique_values: std::collections::HashSet<u8> = output[..bytes].iter().copied().collect();
         assert!(unique_values.len() > 1, "Output should have variation");
     }
+
+    #[test]
+    fn test_alaw_known_values() {
+        // A-law silence is 0xD5 (positive zero) / 0x55 (negative zero)
+        assert_eq!(alaw_encode(0), 0xD5);
+        assert_eq!(alaw_encode(-1), 0x55);
+        assert_eq!(alaw_decode(0xD5), 8);
+        assert_eq!(alaw_decode(0x55), -8);
+        // Full scale
+        assert_eq!(alaw_encode(i16::MAX), 0xAA);
+        assert_eq!(alaw_encode(i16::MIN), 0x2A);
+        assert_eq!(alaw_decode(0xAA), 32256);
+        assert_eq!(alaw_decode(0x2A), -32256);
+    }
+
+    #[test]
+    fn test_alaw_encode_decode_roundtrip() {
+        // Every code decodes to a value that encodes back to the same code
+        for code in 0..=255u8 {
+            assert_eq!(alaw_encode(alaw_decode(code)), code, "code {:#04x}", code);
+        }
+    }
+
+    #[test]
+    fn test_alaw_encoder_basic() {
+        let mut encoder = G711AlawEncoder::new();
+        let input = vec![0.0f32; 1920];
+        let mut output = [0u8; 256];
+
+        let bytes = encoder.encode(&input, &mut output).unwrap();
+        assert_eq!(bytes, 160);
+        assert!(output[..bytes].iter().all(|&b| b == 0xD5));
+        assert_eq!(encoder.payload_type(), 8);
+
+        let mut decoder = G711AlawDecoder::new();
+        let mut decoded = vec![0.0f32; 160 * 12];
+        assert_eq!(decoder.decode(&output[..bytes], &mut decoded).unwrap(), 1920);
+    }
 }
@@ -0,0 +1,688 @@
+//! LATM/LOAS framing for MPEG-4 AAC over RTP (RFC 3016 / RFC 6416).
+//!
+//! The Z/IP ONE sends its AAC modes on PT 122 as LATM AudioMuxElements
+//! (ISO/IEC 14496-3, 1.7.3). The StreamMuxConfig that carries the
+//! AudioSpecificConfig must be sent in-band (`cpresent=1`, the only option
+//! for plain RTP without SDP); a config agreed out of band through the SDP
+//! `config` parameter (`cpresent=0`) is not supported. Some senders also wrap
+//! the elements in the LOAS sync layer; both forms are accepted.
+//!
+//! Received payloads are reduced to raw access units plus the
+//! AudioSpecificConfig. FFmpeg's LATM decoder only takes LOAS frames, so each
+//! access unit is re-framed as LOAS with the config in-band before decoding.
+//!
+//! Sent payloads carry the StreamMuxConfig in every packet, so a receiver can
+//! join the stream at any time.
+//!
+//! Supported subset: one program, one layer, variable frame length
+//! (frameLengthType 0) and audioMuxVersionA 0. That is what AAC encoders
+//! produce; scalable and CELP/HVXC configurations are rejected.
+
+use super::CodecError;
+
+/// LOAS AudioSyncStream sync word (11 bits)
+const LOAS_SYNC_WORD: u32 = 0x2B7;
+
+/// Largest AudioMuxElement a LOAS frame can carry (13-bit length)
+const LOAS_MAX_LENGTH: usize = 0x1FFF;
+
+/// Sampling frequencies by samplingFrequencyIndex
+const SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// Audio object type: AAC-LC
+pub const AOT_AAC_LC: u8 = 2;
+/// Audio object type: SBR (HE-AAC, explicit signalling)
+pub const AOT_SBR: u8 = 5;
+/// Audio object type: PS (HE-AAC v2, explicit signalling)
+pub const AOT_PS: u8 = 29;
+/// Audio object type: ER AAC-ELD
+pub const AOT_ER_AAC_ELD: u8 = 39;
+
+fn truncated() -> CodecError {
+    CodecError::DecodeError("LATM data truncated".to_string())
+}
+
+fn unsupported(what: &str) -> CodecError {
+    CodecError::DecodeError(format!("LATM: {} not supported", what))
+}
+
+// ============================================================================
+// Bit I/O
+// ============================================================================
+
+/// MSB-first bit reader
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    fn read(&mut self, bits: u32) -> Result<u32, CodecError> {
+        if bits as usize > self.remaining() {
+            return Err(truncated());
+        }
+        let mut value = 0u32;
+        for _ in 0..bits {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn skip(&mut self, bits: usize) -> Result<(), CodecError> {
+        if bits > self.remaining() {
+            return Err(truncated());
+        }
+        self.pos += bits;
+        Ok(())
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// MSB-first bit writer
+struct BitWriter {
+    data: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { data: Vec::new(), bits: 0 }
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            self.write_bit((value >> i) & 1 != 0);
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        if self.bits == self.data.len() * 8 {
+            self.data.push(0);
+        }
+        if bit {
+            let last = self.data.len() - 1;
+            self.data[last] |= 0x80 >> (self.bits % 8);
+        }
+        self.bits += 1;
+    }
+
+    /// Copy `len` bits of `data` starting at bit 0
+    fn write_bits_from(&mut self, data: &[u8], len: usize) {
+        for pos in 0..len {
+            self.write_bit((data[pos / 8] >> (7 - pos % 8)) & 1 != 0);
+        }
+    }
+
+    fn align(&mut self) {
+        self.bits = self.data.len() * 8;
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// LatmGetValue(): a 1-4 byte value prefixed by its length
+fn read_latm_value(r: &mut BitReader) -> Result<u32, CodecError> {
+    let bytes = r.read(2)?;
+    let mut value = 0u32;
+    for _ in 0..=bytes {
+        value = (value << 8) | r.read(8)?;
+    }
+    Ok(value)
+}
+
+// ============================================================================
+// AudioSpecificConfig
+// ============================================================================
+
+/// MPEG-4 AudioSpecificConfig (ISO/IEC 14496-3, 1.6.2.1)
+///
+/// Keeps the config bits as received so it can be passed on unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSpecificConfig {
+    /// Audio object type of the core codec (2 = AAC-LC, 39 = ER AAC-ELD)
+    pub object_type: u8,
+    /// Core sampling rate in Hz
+    pub sample_rate: u32,
+    /// channelConfiguration (1-6 = that many channels, 7 = 7.1)
+    pub channel_config: u8,
+    /// SBR signalled explicitly (HE-AAC)
+    pub sbr: bool,
+    /// Parametric stereo signalled explicitly (HE-AAC v2)
+    pub ps: bool,
+    /// Core samples per frame (1024/960, or 512/480 for ELD)
+    pub frame_length: u32,
+    bits: Vec<u8>,
+    bit_len: usize,
+}
+
+fn read_object_type(r: &mut BitReader) -> Result<u8, CodecError> {
+    let aot = r.read(5)?;
+    if aot == 31 {
+        Ok((32 + r.read(6)?) as u8)
+    } else {
+        Ok(aot as u8)
+    }
+}
+
+fn write_object_type(w: &mut BitWriter, aot: u8) {
+    if aot >= 31 {
+        w.write(31, 5);
+        w.write((aot - 32) as u32, 6);
+    } else {
+        w.write(aot as u32, 5);
+    }
+}
+
+fn read_sample_rate(r: &mut BitReader) -> Result<u32, CodecError> {
+    let index = r.read(4)? as usize;
+    if index == 0xF {
+        r.read(24)
+    } else {
+        SAMPLE_RATES
+            .get(index)
+            .copied()
+            .ok_or_else(|| unsupported("reserved sampling frequency index"))
+    }
+}
+
+fn write_sample_rate(w: &mut BitWriter, rate: u32) {
+    match SAMPLE_RATES.iter().position(|&r| r == rate) {
+        Some(index) => w.write(index as u32, 4),
+        None => {
+            w.write(0xF, 4);
+            w.write(rate, 24);
+        }
+    }
+}
+
+/// GASpecificConfig; returns the frame length
+fn read_ga_specific_config(r: &mut BitReader, channel_config: u8, aot: u8) -> Result<u32, CodecError> {
+    let frame_length = if r.read(1)? == 1 { 960 } else { 1024 };
+    if r.read(1)? == 1 {
+        r.skip(14)?; // coreCoderDelay
+    }
+    let extension_flag = r.read(1)?;
+    if channel_config == 0 {
+        return Err(unsupported("program_config_element"));
+    }
+    if aot == 6 || aot == 20 {
+        r.skip(3)?; // layerNr
+    }
+    if extension_flag == 1 {
+        if aot == 22 {
+            r.skip(5 + 11)?; // numOfSubFrame, layer_length
+        }
+        if matches!(aot, 17 | 19 | 20 | 23) {
+            r.skip(3)?; // resilience flags
+        }
+        r.skip(1)?; // extensionFlag3
+    }
+    Ok(frame_length)
+}
+
+/// ELDSpecificConfig; returns the frame length
+fn read_eld_specific_config(r: &mut BitReader, channel_config: u8) -> Result<u32, CodecError> {
+    let frame_length = if r.read(1)? == 1 { 480 } else { 512 };
+    r.skip(3)?; // resilience flags
+    if r.read(1)? == 1 {
+        // ldSbrSamplingRate, ldSbrCrcFlag, ld_sbr_header()
+        r.skip(2)?;
+        let headers = match channel_config {
+            1 | 2 => 1,
+            3 => 2,
+            4..=6 => 3,
+            7 => 4,
+            _ => 0,
+        };
+        for _ in 0..headers {
+            r.skip(1 + 4 + 4 + 3 + 2)?;
+            let extra_1 = r.read(1)?;
+            let extra_2 = r.read(1)?;
+            if extra_1 == 1 {
+                r.skip(2 + 1 + 2)?;
+            }
+            if extra_2 == 1 {
+                r.skip(2 + 2 + 1 + 1)?;
+            }
+        }
+    }
+    // Extensions up to ELDEXT_TERM
+    while r.read(4)? != 0 {
+        let mut len = r.read(4)? as usize;
+        if len == 15 {
+            let add = r.read(8)? as usize;
+            len += add;
+            if add == 255 {
+                len += r.read(16)? as usize;
+            }
+        }
+        r.skip(len * 8)?;
+    }
+    Ok(frame_length)
+}
+
+impl AudioSpecificConfig {
+    /// Build the config an encoder signals for plain AAC-LC or AAC-ELD
+    ///
+    /// HE-AAC is signalled implicitly: AAC-LC at the core (half) rate, with
+    /// the SBR data found by the decoder in the stream.
+    ///
+    /// # Arguments
+    /// * `object_type` - AOT_AAC_LC or AOT_ER_AAC_ELD
+    /// * `sample_rate` - Core sampling rate in Hz
+    /// * `channels` - 1 or 2
+    /// * `frame_length` - Core samples per frame
+    pub fn new(object_type: u8, sample_rate: u32, channels: u8, frame_length: u32) -> Result<Self, CodecError> {
+        if !(1..=2).contains(&channels) {
+            return Err(CodecError::Other(format!("AAC config for {} channels not supported", channels)));
+        }
+        let mut w = BitWriter::new();
+        write_object_type(&mut w, object_type);
+        write_sample_rate(&mut w, sample_rate);
+        w.write(channels as u32, 4);
+        match object_type {
+            AOT_AAC_LC => {
+                w.write((frame_length == 960) as u32, 1); // frameLengthFlag
+                w.write(0, 1); // dependsOnCoreCoder
+                w.write(0, 1); // extensionFlag
+            }
+            AOT_ER_AAC_ELD => {
+                w.write((frame_length == 480) as u32, 1); // frameLengthFlag
+                w.write(0, 3); // resilience flags
+                w.write(0, 1); // ldSbrPresentFlag
+                w.write(0, 4); // ELDEXT_TERM
+                w.write(0, 2); // epConfig
+            }
+            _ => return Err(CodecError::Other(format!("AAC object type {} not supported", object_type))),
+        }
+        let bit_len = w.bits;
+        Ok(Self {
+            object_type,
+            sample_rate,
+            channel_config: channels,
+            sbr: false,
+            ps: false,
+            frame_length,
+            bits: w.finish(),
+            bit_len,
+        })
+    }
+
+    /// Parse a config from bytes (e.g. the MP4A `config` of RFC 3640)
+    pub fn parse(data: &[u8]) -> Result<Self, CodecError> {
+        Self::read(&mut BitReader::new(data))
+    }
+
+    fn read(r: &mut BitReader) -> Result<Self, CodecError> {
+        let start = r.pos;
+        let mut object_type = read_object_type(r)?;
+        let sample_rate = read_sample_rate(r)?;
+        let channel_config = r.read(4)? as u8;
+        let mut sbr = false;
+        let mut ps = false;
+        if object_type == AOT_SBR || object_type == AOT_PS {
+            sbr = true;
+            ps = object_type == AOT_PS;
+            read_sample_rate(r)?; // extensionSamplingFrequency
+            object_type = read_object_type(r)?;
+            if object_type == 22 {
+                r.skip(4)?; // extensionChannelConfiguration
+            }
+        }
+        let frame_length = match object_type {
+            1..=4 | 6 | 7 | 17 | 19..=23 => read_ga_specific_config(r, channel_config, object_type)?,
+            AOT_ER_AAC_ELD => read_eld_specific_config(r, channel_config)?,
+            _ => return Err(unsupported(&format!("audio object type {}", object_type))),
+        };
+        if matches!(object_type, 17 | 19..=27 | AOT_ER_AAC_ELD) && r.read(2)? >= 2 {
+            return Err(unsupported("error protection config"));
+        }
+
+        // Keep the bits as sent
+        let bit_len = r.pos - start;
+        let mut copy = BitReader { data: r.data, pos: start };
+        let mut w = BitWriter::new();
+        for _ in 0..bit_len {
+            w.write(copy.read(1)?, 1);
+        }
+
+        Ok(Self {
+            object_type,
+            sample_rate,
+            channel_config,
+            sbr,
+            ps,
+            frame_length,
+            bits: w.finish(),
+            bit_len,
+        })
+    }
+
+    fn write(&self, w: &mut BitWriter) {
+        w.write_bits_from(&self.bits, self.bit_len);
+    }
+
+    /// Number of channels
+    pub fn channels(&self) -> u8 {
+        if self.channel_config == 7 {
+            8
+        } else {
+            self.channel_config
+        }
+    }
+
+    /// Config bytes (zero-padded to a byte boundary)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bits.clone()
+    }
+}
+
+// ============================================================================
+// StreamMuxConfig
+// ============================================================================
+
+/// LATM StreamMuxConfig (the supported subset)
+#[derive(Debug, Clone)]
+pub struct StreamMuxConfig {
+    /// Config of the single program/layer
+    pub asc: AudioSpecificConfig,
+    /// Access units per AudioMuxElement (numSubFrames + 1)
+    sub_frames: u32,
+    /// Bits of other data after the payloads
+    other_data_bits: u32,
+}
+
+impl StreamMuxConfig {
+    fn read(r: &mut BitReader) -> Result<Self, CodecError> {
+        let version = r.read(1)?;
+        if version == 1 && r.read(1)? == 1 {
+            return Err(unsupported("audioMuxVersionA 1"));
+        }
+        if version == 1 {
+            read_latm_value(r)?; // taraBufferFullness
+        }
+        r.skip(1)?; // allStreamsSameTimeFraming
+        let sub_frames = r.read(6)? + 1;
+        let programs = r.read(4)?;
+        let layers = r.read(3)?;
+        if programs != 0 || layers != 0 {
+            return Err(unsupported("more than one program or layer"));
+        }
+
+        let asc = if version == 1 {
+            let asc_len = read_latm_value(r)? as usize;
+            let start = r.pos;
+            let asc = AudioSpecificConfig::read(r)?;
+            let used = r.pos - start;
+            if used > asc_len {
+                return Err(CodecError::DecodeError("LATM: AudioSpecificConfig overruns its length".to_string()));
+            }
+            r.skip(asc_len - used)?; // fillBits
+            asc
+        } else {
+            AudioSpecificConfig::read(r)?
+        };
+
+        if r.read(3)? != 0 {
+            return Err(unsupported("fixed frame length"));
+        }
+        r.skip(8)?; // latmBufferFullness
+
+        let mut other_data_bits = 0;
+        if r.read(1)? == 1 {
+            if version == 1 {
+                other_data_bits = read_latm_value(r)?;
+            } else {
+                loop {
+                    let escape = r.read(1)?;
+                    other_data_bits = (other_data_bits << 8) + r.read(8)?;
+                    if escape == 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        if r.read(1)? == 1 {
+            r.skip(8)?; // crcCheckSum
+        }
+
+        Ok(Self { asc, sub_frames, other_data_bits })
+    }
+}
+
+/// Write a version 0 StreamMuxConfig for one access unit per element
+fn write_stream_mux_config(w: &mut BitWriter, asc: &AudioSpecificConfig) {
+    w.write(0, 1); // audioMuxVersion
+    w.write(1, 1); // allStreamsSameTimeFraming
+    w.write(0, 6); // numSubFrames
+    w.write(0, 4); // numProgram
+    w.write(0, 3); // numLayer
+    asc.write(w);
+    w.write(0, 3); // frameLengthType
+    w.write(0xFF, 8); // latmBufferFullness
+    w.write(0, 1); // otherDataPresent
+    w.write(0, 1); // crcCheckPresent
+}
+
+/// AudioMuxElement(1) with the config in-band, carrying one access unit
+fn write_audio_mux_element(w: &mut BitWriter, asc: &AudioSpecificConfig, au: &[u8]) {
+    w.write(0, 1); // useSameStreamMux
+    write_stream_mux_config(w, asc);
+    // PayloadLengthInfo
+    let mut len = au.len();
+    while len >= 255 {
+        w.write(255, 8);
+        len -= 255;
+    }
+    w.write(len as u32, 8);
+    for &byte in au {
+        w.write(byte as u32, 8);
+    }
+    w.align();
+}
+
+/// RTP payload (RFC 3016, cpresent=1) for one access unit
+pub fn audio_mux_element(asc: &AudioSpecificConfig, au: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    write_audio_mux_element(&mut w, asc, au);
+    w.finish()
+}
+
+/// LOAS frame (AudioSyncStream) for one access unit
+pub fn loas_frame(asc: &AudioSpecificConfig, au: &[u8]) -> Result<Vec<u8>, CodecError> {
+    let element = audio_mux_element(asc, au);
+    if element.len() > LOAS_MAX_LENGTH {
+        return Err(CodecError::BufferTooSmall);
+    }
+    let mut w = BitWriter::new();
+    w.write(LOAS_SYNC_WORD, 11);
+    w.write(element.len() as u32, 13);
+    let mut frame = w.finish();
+    frame.extend_from_slice(&element);
+    Ok(frame)
+}
+
+/// Split a payload into LOAS frames, if it is a chain of them
+fn split_loas(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut frames = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        if rest.len() < 3 || ((rest[0] as u32) << 3 | (rest[1] as u32) >> 5) != LOAS_SYNC_WORD {
+            return None;
+        }
+        let len = ((rest[1] as usize & 0x1F) << 8) | rest[2] as usize;
+        if rest.len() < 3 + len {
+            return None;
+        }
+        frames.push(&rest[3..3 + len]);
+        rest = &rest[3 + len..];
+    }
+    if frames.is_empty() {
+        None
+    } else {
+        Some(frames)
+    }
+}
+
+// ============================================================================
+// Depacketizer
+// ============================================================================
+
+/// Extracts AAC access units from MP4A-LATM RTP payloads
+pub struct LatmDepacketizer {
+    config: Option<StreamMuxConfig>,
+}
+
+impl Default for LatmDepacketizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatmDepacketizer {
+    /// Depacketizer for in-band configs (cpresent=1, or LOAS)
+    pub fn new() -> Self {
+        Self { config: None }
+    }
+
+    /// Config of the stream, once known
+    pub fn config(&self) -> Option<&AudioSpecificConfig> {
+        self.config.as_ref().map(|c| &c.asc)
+    }
+
+    /// Extract the access units of one RTP payload
+    ///
+    /// Returns no units until an in-band config has been seen.
+    pub fn depacketize(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, CodecError> {
+        let mut units = Vec::new();
+        if let Some(frames) = split_loas(payload) {
+            for frame in frames {
+                self.read_audio_mux_element(&mut BitReader::new(frame), &mut units)?;
+            }
+            return Ok(units);
+        }
+
+        // Several AudioMuxElements may share a packet
+        let mut r = BitReader::new(payload);
+        let mut elements = 0;
+        while r.remaining() >= 8 {
+            let start = r.pos;
+            match self.read_audio_mux_element(&mut r, &mut units) {
+                Ok(()) if r.pos > start => elements += 1,
+                Ok(()) => break,
+                // Trailing padding after complete elements
+                Err(_) if elements > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(units)
+    }
+
+    fn read_audio_mux_element(&mut self, r: &mut BitReader, units: &mut Vec<Vec<u8>>) -> Result<(), CodecError> {
+        // useSameStreamMux
+        if r.read(1)? == 0 {
+            self.config = Some(StreamMuxConfig::read(r)?);
+        }
+        let (sub_frames, other_data_bits) = match &self.config {
+            Some(config) => (config.sub_frames, config.other_data_bits),
+            None => return Ok(()), // Waiting for a config
+        };
+
+        for _ in 0..sub_frames {
+            // PayloadLengthInfo
+            let mut len = 0usize;
+            loop {
+                let tmp = r.read(8)?;
+                len += tmp as usize;
+                if tmp != 255 {
+                    break;
+                }
+            }
+            let mut au = Vec::with_capacity(len);
+            for _ in 0..len {
+                au.push(r.read(8)? as u8);
+            }
+            units.push(au);
+        }
+        r.skip(other_data_bits as usize)?;
+        r.align();
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audio_specific_config() {
+        // AAC-LC, 48 kHz, stereo
+        let lc = AudioSpecificConfig::new(AOT_AAC_LC, 48000, 2, 1024).unwrap();
+        assert_eq!(lc.to_bytes(), vec![0x11, 0x90]);
+        assert_eq!(AudioSpecificConfig::parse(&[0x11, 0x90]).unwrap(), lc);
+
+        // AAC-ELD, 48 kHz, stereo, 480 samples
+        let eld = AudioSpecificConfig::new(AOT_ER_AAC_ELD, 48000, 2, 480).unwrap();
+        assert_eq!(eld.to_bytes(), vec![0xF8, 0xE6, 0x50, 0x00]);
+        let parsed = AudioSpecificConfig::parse(&eld.to_bytes()).unwrap();
+        assert_eq!(parsed.object_type, AOT_ER_AAC_ELD);
+        assert_eq!(parsed.frame_length, 480);
+
+        // HE-AAC with explicit SBR: AOT 5, 24 kHz core, stereo, 48 kHz, AAC-LC
+        let he = AudioSpecificConfig::parse(&[0x2B, 0x11, 0x88, 0x00]).unwrap();
+        assert!(he.sbr);
+        assert_eq!(he.object_type, AOT_AAC_LC);
+        assert_eq!(he.sample_rate, 24000);
+        assert_eq!(he.channels(), 2);
+    }
+
+    #[test]
+    fn test_in_band_and_loas_roundtrip() {
+        let asc = AudioSpecificConfig::new(AOT_AAC_LC, 48000, 2, 1024).unwrap();
+        let au: Vec<u8> = (0..300).map(|i| i as u8).collect();
+
+        let mut depack = LatmDepacketizer::new();
+        assert_eq!(depack.depacketize(&audio_mux_element(&asc, &au)).unwrap(), vec![au.clone()]);
+        assert_eq!(depack.config(), Some(&asc));
+
+        let mut depack = LatmDepacketizer::new();
+        let loas = loas_frame(&asc, &au).unwrap();
+        assert_eq!((loas[0], loas[1] & 0xE0), (0x56, 0xE0));
+        assert_eq!(depack.depacketize(&loas).unwrap(), vec![au]);
+    }
+
+    #[test]
+    fn test_stream_mux_config_roundtrip() {
+        // StreamMuxConfig 400024203fc0: AAC-LC, 44.1 kHz, stereo
+        let config = [0x40, 0x00, 0x24, 0x20, 0x3F, 0xC0];
+        let asc = StreamMuxConfig::read(&mut BitReader::new(&config)).unwrap().asc;
+        assert_eq!(asc.object_type, AOT_AAC_LC);
+        assert_eq!(asc.sample_rate, 44100);
+        assert_eq!(asc.channels(), 2);
+
+        let mut w = BitWriter::new();
+        write_stream_mux_config(&mut w, &asc);
+        assert_eq!(w.finish(), config.to_vec());
+    }
+}
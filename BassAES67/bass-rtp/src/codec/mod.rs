@@ -9,7 +9,7 @@
 //! - FLAC: Free Lossless Audio Codec (libFLAC)
 //! - G.711: Narrowband codec (8kHz)
 //! - G.722: Wideband codec (16kHz)
-//! - AAC: Advanced Audio Coding (FFmpeg)
+//! - AAC: Advanced Audio Coding (FFmpeg), ADTS and LATM
 
 pub mod pcm;
 pub mod opus;
@@ -19,6 +19,7 @@ pub mod flac;
 pub mod g711;
 pub mod g722;
 pub mod ffmpeg_aac;
+pub mod latm;
 
 pub use pcm::*;
 
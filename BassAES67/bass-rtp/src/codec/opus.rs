@@ -40,6 +40,8 @@ pub const OPUS_APPLICATION_RESTRICTED_LOWDELAY: c_int = 2051;
 pub const OPUS_SET_BITRATE_REQUEST: c_int = 4002;
 pub const OPUS_GET_BITRATE_REQUEST: c_int = 4003;
 pub const OPUS_SET_COMPLEXITY_REQUEST: c_int = 4010;
+pub const OPUS_SET_INBAND_FEC_REQUEST: c_int = 4012;
+pub const OPUS_SET_PACKET_LOSS_PERC_REQUEST: c_int = 4014;
 pub const OPUS_SET_SIGNAL_REQUEST: c_int = 4024;
 
 // Signal types
@@ -50,6 +52,10 @@ pub const OPUS_SIGNAL_MUSIC: c_int = 3002;
 pub const OPUS_AUTO: c_int = -1000;
 pub const OPUS_BITRATE_MAX: c_int = -1;
 
+/// Longest run of lost RTP packets that gets concealed; a bigger jump in
+/// sequence numbers means the sender restarted
+pub const MAX_CONCEALED_PACKETS: usize = 8;
+
 #[link(name = "opus")]
 extern "C" {
     // Encoder
@@ -98,6 +104,8 @@ extern "C" {
         decode_fec: c_int,
     ) -> c_int;
 
+    fn opus_packet_get_nb_samples(packet: *const u8, len: i32, fs: i32) -> c_int;
+
     // Error string
     fn opus_strerror(error: c_int) -> *const i8;
 }
@@ -116,6 +124,19 @@ pub fn error_string(error: c_int) -> String {
     }
 }
 
+/// Number of RTP packets lost between sequence numbers `last` and `sequence`.
+///
+/// Late and reordered packets count as no loss, as do gaps longer than
+/// `MAX_CONCEALED_PACKETS`.
+pub fn lost_packets(last: u16, sequence: u16) -> usize {
+    let gap = sequence.wrapping_sub(last).wrapping_sub(1) as usize;
+    if gap <= MAX_CONCEALED_PACKETS {
+        gap
+    } else {
+        0
+    }
+}
+
 /// OPUS Encoder wrapper
 pub struct Encoder {
     encoder: *mut OpusEncoder,
@@ -225,6 +246,34 @@ impl Encoder {
         }
     }
 
+    /// Enable in-band forward error correction.
+    ///
+    /// Each packet then also carries a low-bitrate copy of the previous one,
+    /// which the decoder uses when that packet was lost. The copy is only
+    /// sent in SILK mode, so it needs 10 ms frames or longer.
+    ///
+    /// # Arguments
+    /// * `loss_percent` - Expected packet loss in percent (0 = FEC off)
+    pub fn set_inband_fec(&mut self, loss_percent: u8) -> Result<(), CodecError> {
+        let loss_percent = loss_percent.min(100) as c_int;
+        unsafe {
+            let result = opus_encoder_ctl(
+                self.encoder,
+                OPUS_SET_INBAND_FEC_REQUEST,
+                (loss_percent > 0) as c_int,
+            );
+            if result != OPUS_OK {
+                return Err(CodecError::LibraryError(result));
+            }
+            let result = opus_encoder_ctl(self.encoder, OPUS_SET_PACKET_LOSS_PERC_REQUEST, loss_percent);
+            if result != OPUS_OK {
+                Err(CodecError::LibraryError(result))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
     /// Get the frame size in samples per channel
     pub fn frame_size(&self) -> usize {
         self.frame_size
@@ -430,6 +479,52 @@ impl Decoder {
         }
     }
 
+    /// Get the duration of an OPUS packet in samples per channel
+    pub fn packet_samples(&self, data: &[u8]) -> Result<usize, CodecError> {
+        let result = unsafe {
+            opus_packet_get_nb_samples(data.as_ptr(), data.len() as i32, self.format.sample_rate as i32)
+        };
+        if result < 0 {
+            Err(CodecError::LibraryError(result))
+        } else {
+            Ok(result as usize)
+        }
+    }
+
+    /// Decode OPUS data that follows `lost` lost packets to float PCM samples.
+    ///
+    /// The last lost packet is rebuilt from the FEC data in `data` (libopus
+    /// conceals it when there is none), the ones before it are concealed.
+    /// Lost packets that don't fit in `output` are skipped.
+    ///
+    /// # Returns
+    /// Number of samples per channel written, lost packets included.
+    pub fn decode_float_after_loss(
+        &mut self,
+        data: &[u8],
+        lost: usize,
+        output: &mut [f32],
+    ) -> Result<usize, CodecError> {
+        let channels = self.format.channels as usize;
+        let packet_samples = self.packet_samples(data)?;
+        if packet_samples == 0 {
+            return Err(CodecError::InvalidInput);
+        }
+        let lost = lost.min((output.len() / channels / packet_samples).saturating_sub(1));
+
+        let mut written = 0;
+        for i in 0..lost {
+            let frame = &mut output[written * channels..(written + packet_samples) * channels];
+            written += if i + 1 == lost {
+                self.decode_float(data, frame, true)?
+            } else {
+                self.decode_float(&[], frame, false)?
+            };
+        }
+        written += self.decode_float(data, &mut output[written * channels..], false)?;
+        Ok(written)
+    }
+
     /// Decode a lost packet using packet loss concealment.
     ///
     /// Call this when a packet is lost to generate audio that smoothly conceals the gap.
@@ -507,4 +602,33 @@ mod tests {
         let decoded_samples = decoder.decode(&encoded[..encoded_len], &mut pcm_out, false).unwrap();
         assert_eq!(decoded_samples, encoder.frame_size());
     }
+
+    #[test]
+    fn test_decode_after_loss() {
+        assert_eq!(lost_packets(10, 11), 0);
+        assert_eq!(lost_packets(65535, 1), 1);
+        assert_eq!(lost_packets(11, 10), 0);
+        assert_eq!(lost_packets(10, 1000), 0);
+
+        let format = AudioFormat::standard();
+        let mut encoder = Encoder::new(format, 20.0, OPUS_APPLICATION_AUDIO).unwrap();
+        encoder.set_inband_fec(10).unwrap();
+        let mut decoder = Decoder::new(format, 20.0).unwrap();
+
+        let pcm_in = vec![0.25f32; encoder.total_samples_per_frame()];
+        let mut packets = Vec::new();
+        for _ in 0..3 {
+            let mut encoded = vec![0u8; 4000];
+            let len = encoder.encode_float(&pcm_in, &mut encoded).unwrap();
+            encoded.truncate(len);
+            packets.push(encoded);
+        }
+
+        // Second packet lost: it is rebuilt before the third one
+        let mut pcm_out = vec![0.0f32; 8192];
+        let frame = decoder.decode_float_after_loss(&packets[0], 0, &mut pcm_out).unwrap();
+        assert_eq!(frame, 960);
+        let decoded = decoder.decode_float_after_loss(&packets[2], 1, &mut pcm_out).unwrap();
+        assert_eq!(decoded, 2 * frame);
+    }
 }
@@ -38,7 +38,9 @@ pub const BASS_ERROR_POSITION: i32 = 7;
 pub const BASS_ERROR_INIT: i32 = 8;
 pub const BASS_ERROR_START: i32 = 9;
 pub const BASS_ERROR_SSL: i32 = 10;
+pub const BASS_ERROR_ALREADY: i32 = 14;
 pub const BASS_ERROR_CREATE: i32 = 18;
+pub const BASS_ERROR_ILLPARAM: i32 = 20;
 pub const BASS_ERROR_NOTAVAIL: i32 = 37;
 pub const BASS_ERROR_VERSION: i32 = 43;
 pub const BASS_ERROR_UNKNOWN: i32 = -1;
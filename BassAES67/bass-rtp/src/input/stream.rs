@@ -15,18 +15,20 @@ use std::time::{Duration, Instant};
 
 use ringbuf::{traits::{Consumer, Observer, Producer, Split}, HeapRb};
 
-use crate::clock_bindings::{clock_get_frequency_ppm, clock_is_locked, init_clock_bindings, ClockMode};
-use crate::codec::g711::{G711UlawDecoder, G711UlawEncoder};
+use crate::clock_bindings::{init_clock_bindings, ClockLease, ClockMode};
+use crate::codec::g711::{G711AlawDecoder, G711AlawEncoder, G711UlawDecoder, G711UlawEncoder};
 use crate::codec::g722::{G722Decoder, G722Encoder};
 use crate::codec::ffmpeg_aac;
+use crate::codec::flac;
 use crate::codec::mpg123;
+use crate::codec::opus;
 use crate::codec::twolame;
 use crate::codec::{
     AudioDecoder, AudioEncoder, AudioFormat, Pcm16Decoder, Pcm16Encoder, Pcm20Decoder,
     Pcm20Encoder, Pcm24Decoder, Pcm24Encoder,
 };
 use crate::ffi::*;
-use crate::rtp::{PayloadCodec, RtpPacket, RtpPacketBuilder, RtpSocket};
+use crate::rtp::{DynamicPayloadTypes, PayloadCodec, RtpPacket, RtpPacketBuilder, RtpSocket};
 
 /// FFI import for BASS_ChannelGetData
 #[link(name = "bass")]
@@ -91,6 +93,10 @@ pub struct RtpInputConfig {
     pub send_bitrate: u32,
     /// Frame duration in milliseconds
     pub frame_duration_ms: u32,
+    /// Expected packet loss in percent for Opus in-band FEC (0 = FEC off)
+    pub opus_fec_loss: u8,
+    /// Payload types for Opus and FLAC, in both directions
+    pub payload_types: DynamicPayloadTypes,
     /// Clock mode (PTP/Livewire/System)
     pub clock_mode: ClockMode,
     /// PTP domain (0-127)
@@ -113,6 +119,8 @@ impl Default for RtpInputConfig {
             send_codec: PayloadCodec::Pcm16,
             send_bitrate: 256,
             frame_duration_ms: 1,
+            opus_fec_loss: 0,
+            payload_types: DynamicPayloadTypes::default(),
             clock_mode: ClockMode::System,
             ptp_domain: 0,
             return_buffer_mode: BufferMode::Simple { buffer_ms: 100 },
@@ -121,6 +129,30 @@ impl Default for RtpInputConfig {
     }
 }
 
+/// What the TX thread takes over when the stream starts
+struct TransmitterParams {
+    running: Arc<AtomicBool>,
+    stats: Arc<AtomicStats>,
+    current_ppm_x1000: Arc<AtomicI64>,
+    socket: RtpSocket,
+    remote_addr: SocketAddr,
+    config: RtpInputConfig,
+    source_channel: HSTREAM,
+    clock: Arc<ClockLease>,
+}
+
+/// What the RX thread takes over when the stream starts
+struct ReceiverParams {
+    running: Arc<AtomicBool>,
+    stats: Arc<AtomicStats>,
+    socket: RtpSocket,
+    producer: ringbuf::HeapProd<f32>,
+    /// Payload types that select the Opus and FLAC decoders
+    payload_types: DynamicPayloadTypes,
+    /// Media clock key of this stream
+    clock_key: usize,
+}
+
 // ============================================================================
 // Statistics
 // ============================================================================
@@ -199,7 +231,11 @@ enum SendEncoderType {
     Pcm24(Pcm24Encoder),
     Mp2(twolame::Encoder),
     G711Ulaw(G711UlawEncoder),
+    G711Alaw(G711AlawEncoder),
     G722(G722Encoder),
+    Aac(ffmpeg_aac::Encoder),
+    Opus(opus::Encoder),
+    Flac(flac::Encoder),
 }
 
 impl SendEncoderType {
@@ -221,9 +257,21 @@ impl SendEncoderType {
             SendEncoderType::G711Ulaw(enc) => {
                 enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
             }
+            SendEncoderType::G711Alaw(enc) => {
+                enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
+            }
             SendEncoderType::G722(enc) => {
                 enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
             }
+            SendEncoderType::Aac(enc) => {
+                enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
+            }
+            SendEncoderType::Opus(enc) => {
+                enc.encode_float(pcm, output).map_err(|e| format!("{:?}", e))
+            }
+            SendEncoderType::Flac(enc) => {
+                enc.encode_float(pcm, output).map_err(|e| format!("{:?}", e))
+            }
         }
     }
 
@@ -235,11 +283,15 @@ impl SendEncoderType {
             SendEncoderType::Pcm24(enc) => enc.total_samples_per_frame(),
             SendEncoderType::Mp2(enc) => enc.total_samples_per_frame(),
             SendEncoderType::G711Ulaw(enc) => enc.total_samples_per_frame(),
+            SendEncoderType::G711Alaw(enc) => enc.total_samples_per_frame(),
             SendEncoderType::G722(enc) => enc.total_samples_per_frame(),
+            SendEncoderType::Aac(enc) => enc.total_samples_per_frame(),
+            SendEncoderType::Opus(enc) => enc.total_samples_per_frame(),
+            SendEncoderType::Flac(enc) => enc.total_samples_per_frame(),
         }
     }
 
-    fn payload_type(&self) -> u8 {
+    fn payload_type(&self, dynamic: &DynamicPayloadTypes) -> u8 {
         match self {
             SendEncoderType::None => 0,
             SendEncoderType::Pcm16(enc) => enc.payload_type(),
@@ -247,7 +299,11 @@ impl SendEncoderType {
             SendEncoderType::Pcm24(enc) => enc.payload_type(),
             SendEncoderType::Mp2(_) => 14, // MPEG Audio
             SendEncoderType::G711Ulaw(enc) => enc.payload_type(),
+            SendEncoderType::G711Alaw(enc) => enc.payload_type(),
             SendEncoderType::G722(enc) => enc.payload_type(),
+            SendEncoderType::Aac(enc) => enc.payload_type(),
+            SendEncoderType::Opus(_) => dynamic.opus,
+            SendEncoderType::Flac(_) => dynamic.flac,
         }
     }
 }
@@ -264,12 +320,16 @@ enum ReturnDecoderType {
     Pcm24(Pcm24Decoder),
     Mp2(mpg123::Decoder),
     G711Ulaw(G711UlawDecoder),
+    G711Alaw(G711AlawDecoder),
     G722(G722Decoder),
     Aac(ffmpeg_aac::Decoder),
+    /// Decoder and the last RTP sequence number, to spot lost packets
+    Opus(opus::Decoder, Option<u16>),
+    Flac(flac::Decoder),
 }
 
 impl ReturnDecoderType {
-    fn decode(&mut self, data: &[u8], output: &mut [f32]) -> Result<usize, String> {
+    fn decode(&mut self, data: &[u8], sequence: u16, output: &mut [f32]) -> Result<usize, String> {
         match self {
             ReturnDecoderType::None => Err("No decoder".to_string()),
             ReturnDecoderType::Pcm16(dec) => {
@@ -319,19 +379,42 @@ impl ReturnDecoderType {
             ReturnDecoderType::G711Ulaw(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
+            ReturnDecoderType::G711Alaw(dec) => {
+                dec.decode(data, output).map_err(|e| format!("{:?}", e))
+            }
             ReturnDecoderType::G722(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
             ReturnDecoderType::Aac(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
+            ReturnDecoderType::Opus(dec, last_sequence) => {
+                let lost = last_sequence.map_or(0, |last| opus::lost_packets(last, sequence));
+                *last_sequence = Some(sequence);
+                dec.decode_float_after_loss(data, lost, output)
+                    .map(|samples| samples * 2)
+                    .map_err(|e| format!("{:?}", e))
+            }
+            ReturnDecoderType::Flac(dec) => {
+                let samples = dec.decode(data, output).map_err(|e| format!("{:?}", e))?;
+                // Mono senders play on both channels
+                if dec.detected_format().is_some_and(|f| f.channels == 1) {
+                    let samples = samples.min(output.len() / 2);
+                    for i in (0..samples).rev() {
+                        output[2 * i] = output[i];
+                        output[2 * i + 1] = output[i];
+                    }
+                    return Ok(samples * 2);
+                }
+                Ok(samples)
+            }
         }
     }
 }
 
 /// Create decoder for payload type.
-fn create_decoder_for_pt(pt: u8) -> Option<ReturnDecoderType> {
-    match PayloadCodec::from_pt(pt) {
+fn create_decoder_for_pt(pt: u8, dynamic: &DynamicPayloadTypes) -> Option<ReturnDecoderType> {
+    match dynamic.codec(pt) {
         PayloadCodec::Pcm16 => Some(ReturnDecoderType::Pcm16(Pcm16Decoder::new_auto(2))),
         PayloadCodec::Pcm20 => Some(ReturnDecoderType::Pcm20(Pcm20Decoder::new_auto(2))),
         PayloadCodec::Pcm24 => Some(ReturnDecoderType::Pcm24(Pcm24Decoder::new_auto(2))),
@@ -339,10 +422,20 @@ fn create_decoder_for_pt(pt: u8) -> Option<ReturnDecoderType> {
             mpg123::Decoder::new().ok().map(ReturnDecoderType::Mp2)
         }
         PayloadCodec::G711Ulaw => Some(ReturnDecoderType::G711Ulaw(G711UlawDecoder::new())),
+        PayloadCodec::G711Alaw => Some(ReturnDecoderType::G711Alaw(G711AlawDecoder::new())),
         PayloadCodec::G722 => Some(ReturnDecoderType::G722(G722Decoder::new())),
         PayloadCodec::Aac => {
             ffmpeg_aac::Decoder::new().ok().map(ReturnDecoderType::Aac)
         }
+        PayloadCodec::AacLatm => {
+            ffmpeg_aac::Decoder::new_latm().ok().map(ReturnDecoderType::Aac)
+        }
+        PayloadCodec::Opus => opus::Decoder::new(AudioFormat::standard(), 20.0)
+            .ok()
+            .map(|dec| ReturnDecoderType::Opus(dec, None)),
+        PayloadCodec::Flac => {
+            flac::Decoder::new_48k_stereo().ok().map(ReturnDecoderType::Flac)
+        }
         PayloadCodec::Unknown(_) => None,
         _ => None,
     }
@@ -388,6 +481,10 @@ pub struct RtpInput {
     integral_error: f64,
     /// Initial buffering flag
     buffering: AtomicBool,
+    /// Key to select the return stream as the media clock source
+    clock_key: usize,
+    /// Clock the transmitter follows
+    clock: Arc<ClockLease>,
 }
 
 impl RtpInput {
@@ -439,6 +536,8 @@ impl RtpInput {
             resample_init: false,
             integral_error: 0.0,
             buffering: AtomicBool::new(true),
+            clock_key: crate::media_clock::new_source_key(),
+            clock: Arc::new(ClockLease::global()),
         })
     }
 
@@ -486,22 +585,33 @@ impl RtpInput {
         self.max_samples = max_samples;
 
         // Start TX thread
-        let running_tx = self.running.clone();
-        let stats_tx = self.stats.clone();
-        let ppm_tx = self.current_ppm_x1000.clone();
-        let config_tx = self.config.clone();
-        let source = self.source_channel;
+        let tx_params = TransmitterParams {
+            running: self.running.clone(),
+            stats: self.stats.clone(),
+            current_ppm_x1000: self.current_ppm_x1000.clone(),
+            socket,
+            remote_addr,
+            config: self.config.clone(),
+            source_channel: self.source_channel,
+            clock: self.clock.clone(),
+        };
 
         self.tx_thread = Some(thread::spawn(move || {
-            Self::transmitter_loop(running_tx, stats_tx, ppm_tx, socket, remote_addr, config_tx, source);
+            Self::transmitter_loop(tx_params);
         }));
 
         // Start RX thread
-        let running_rx = self.running.clone();
-        let stats_rx = self.stats.clone();
+        let rx_params = ReceiverParams {
+            running: self.running.clone(),
+            stats: self.stats.clone(),
+            socket: socket_for_rx,
+            producer,
+            payload_types: self.config.payload_types,
+            clock_key: self.clock_key,
+        };
 
         self.rx_thread = Some(thread::spawn(move || {
-            Self::receiver_loop(running_rx, stats_rx, socket_for_rx, producer);
+            Self::receiver_loop(rx_params);
         }));
 
         Ok(())
@@ -520,15 +630,17 @@ impl RtpInput {
     }
 
     /// Transmitter loop - reads from BASS, encodes, sends RTP.
-    fn transmitter_loop(
-        running: Arc<AtomicBool>,
-        stats: Arc<AtomicStats>,
-        current_ppm_x1000: Arc<AtomicI64>,
-        socket: RtpSocket,
-        remote_addr: SocketAddr,
-        config: RtpInputConfig,
-        source_channel: HSTREAM,
-    ) {
+    fn transmitter_loop(params: TransmitterParams) {
+        let TransmitterParams {
+            running,
+            stats,
+            current_ppm_x1000,
+            socket,
+            remote_addr,
+            config,
+            source_channel,
+            clock,
+        } = params;
         // Set thread priority
         #[cfg(windows)]
         {
@@ -561,7 +673,51 @@ impl RtpInput {
                 Err(_) => return,
             },
             PayloadCodec::G711Ulaw => SendEncoderType::G711Ulaw(G711UlawEncoder::new()),
+            PayloadCodec::G711Alaw => SendEncoderType::G711Alaw(G711AlawEncoder::new()),
             PayloadCodec::G722 => SendEncoderType::G722(G722Encoder::new()),
+            PayloadCodec::AacLatm | PayloadCodec::HeAac | PayloadCodec::AacEld => {
+                let profile = ffmpeg_aac::AacProfile::from_codec(config.send_codec)
+                    .unwrap_or(ffmpeg_aac::AacProfile::Lc);
+                match ffmpeg_aac::Encoder::new(format, config.send_bitrate, profile) {
+                    Ok(e) => SendEncoderType::Aac(e),
+                    Err(e) => {
+                        eprintln!("Send AAC encoder: {}", e);
+                        return;
+                    }
+                }
+            }
+            PayloadCodec::Opus => match opus::Encoder::new(
+                format,
+                config.frame_duration_ms as f32,
+                opus::OPUS_APPLICATION_AUDIO,
+            ) {
+                Ok(mut e) => {
+                    if let Err(err) = e
+                        .set_bitrate(config.send_bitrate as i32 * 1000)
+                        .and_then(|_| e.set_inband_fec(config.opus_fec_loss))
+                    {
+                        eprintln!("Send Opus encoder: {}", err);
+                        return;
+                    }
+                    SendEncoderType::Opus(e)
+                }
+                Err(e) => {
+                    eprintln!("Send Opus encoder: {}", e);
+                    return;
+                }
+            },
+            PayloadCodec::Flac => {
+                // One block per packet, 1-5 ms keeps packets below the MTU
+                let block_size =
+                    (config.sample_rate * config.frame_duration_ms.clamp(1, 5) / 1000) as usize;
+                match flac::Encoder::with_block_size(format, 5, block_size) {
+                    Ok(e) => SendEncoderType::Flac(e),
+                    Err(e) => {
+                        eprintln!("Send FLAC encoder: {}", e);
+                        return;
+                    }
+                }
+            }
             _ => {
                 eprintln!("Unsupported send codec: {:?}", config.send_codec);
                 return;
@@ -576,13 +732,13 @@ impl RtpInput {
         let mut pcm_buffer = vec![0.0f32; samples_per_frame];
         let encode_buffer_size = match config.send_codec {
             PayloadCodec::Mp2 => 4608,
-            PayloadCodec::G711Ulaw => samples_per_channel,
+            PayloadCodec::G711Ulaw | PayloadCodec::G711Alaw => samples_per_channel,
             PayloadCodec::G722 => samples_per_channel,
             _ => samples_per_frame * 3,
         };
         let mut encode_buffer = vec![0u8; encode_buffer_size];
 
-        let mut packet_builder = RtpPacketBuilder::new(encoder.payload_type());
+        let mut packet_builder = RtpPacketBuilder::new(encoder.payload_type(&config.payload_types));
         let mut next_tx = Instant::now();
         let mut ppm_counter = 0u32;
         let mut current_ppm = 0.0f64;
@@ -594,8 +750,8 @@ impl RtpInput {
             ppm_counter += 1;
             if ppm_counter >= 100 {
                 ppm_counter = 0;
-                if clock_is_locked() {
-                    current_ppm = clock_get_frequency_ppm();
+                if clock.is_locked() {
+                    current_ppm = clock.frequency_ppm();
                     current_ppm_x1000.store((current_ppm * 1000.0) as i64, Ordering::Relaxed);
                 }
             }
@@ -662,12 +818,15 @@ impl RtpInput {
     }
 
     /// Receiver loop - receives RTP, decodes, pushes to ring buffer.
-    fn receiver_loop(
-        running: Arc<AtomicBool>,
-        stats: Arc<AtomicStats>,
-        socket: RtpSocket,
-        mut producer: ringbuf::HeapProd<f32>,
-    ) {
+    fn receiver_loop(params: ReceiverParams) {
+        let ReceiverParams {
+            running,
+            stats,
+            socket,
+            mut producer,
+            payload_types,
+            clock_key,
+        } = params;
         let mut recv_buf = vec![0u8; 4096];
         let mut decode_buf = vec![0.0f32; 8192];
         let mut decoder: ReturnDecoderType = ReturnDecoderType::None;
@@ -678,10 +837,11 @@ impl RtpInput {
                 Ok(len) if len >= 12 => {
                     if let Some(packet) = RtpPacket::parse(&recv_buf[..len]) {
                         let pt = packet.header.payload_type;
+                        crate::media_clock::feed(clock_key, packet.header.timestamp);
 
                         // Switch decoder if PT changed
                         if current_pt != Some(pt) {
-                            if let Some(new_dec) = create_decoder_for_pt(pt) {
+                            if let Some(new_dec) = create_decoder_for_pt(pt, &payload_types) {
                                 decoder = new_dec;
                                 current_pt = Some(pt);
                                 stats.detected_return_pt.store(pt as u32, Ordering::Relaxed);
@@ -689,7 +849,7 @@ impl RtpInput {
                         }
 
                         // Decode
-                        match decoder.decode(packet.payload, &mut decode_buf) {
+                        match decoder.decode(packet.payload, packet.header.sequence, &mut decode_buf) {
                             Ok(samples) if samples > 0 => {
                                 stats.rx_packets.fetch_add(1, Ordering::Relaxed);
                                 stats.rx_bytes.fetch_add(len as u64, Ordering::Relaxed);
@@ -737,6 +897,20 @@ impl RtpInput {
         }
     }
 
+    /// Key to select the return stream as the media clock source
+    pub fn clock_key(&self) -> usize {
+        self.clock_key
+    }
+
+    /// Follow `clock` instead of the current clock. Only possible while stopped.
+    pub fn set_clock(&mut self, clock: ClockLease) -> Result<(), String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stream is running".to_string());
+        }
+        self.clock = Arc::new(clock);
+        Ok(())
+    }
+
     /// Get current PPM.
     pub fn applied_ppm(&self) -> f64 {
         self.current_ppm_x1000.load(Ordering::Relaxed) as f64 / 1000.0
@@ -830,6 +1004,7 @@ impl RtpInput {
 impl Drop for RtpInput {
     fn drop(&mut self) {
         self.stop();
+        crate::media_clock::release_source(self.clock_key);
     }
 }
 
@@ -1,7 +1,7 @@
 //! bass-rtp: Bidirectional RTP audio module for BASS with Telos Z/IP ONE codec support.
 //!
 //! This module provides bidirectional unicast RTP audio streaming with support for
-//! multiple codecs including PCM-16, PCM-20, PCM-24, MP2, G.711, G.722, and AAC (decode only).
+//! multiple codecs including PCM-16, PCM-20, PCM-24, MP2, G.711 (u-Law and A-Law), G.722, and AAC (ADTS decode, LATM decode and encode).
 //!
 //! ## Modules
 //!
@@ -11,11 +11,16 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
-use std::ffi::c_void;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr};
 use std::net::Ipv4Addr;
 
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
 pub mod ffi;
 pub mod clock_bindings;
+pub use bass_clock::media as media_clock;
 pub mod rtp;
 pub mod codec;
 pub mod input;
@@ -23,8 +28,8 @@ pub mod input;
 pub mod output;
 
 use ffi::*;
-use rtp::PayloadCodec;
-use clock_bindings::ClockMode;
+use rtp::{DynamicPayloadTypes, PayloadCodec};
+use clock_bindings::{ClockLease, ClockMode, ClockRef};
 use input::{RtpInput, RtpInputConfig, RtpInputStats, BufferMode, input_return_stream_proc};
 use output::{RtpOutput, RtpOutputConfig, RtpOutputStats, output_incoming_stream_proc};
 
@@ -44,6 +49,18 @@ pub const BASS_RTP_CODEC_MP2: u8 = 3;
 pub const BASS_RTP_CODEC_G711: u8 = 4;
 /// G.722 codec
 pub const BASS_RTP_CODEC_G722: u8 = 5;
+/// G.711 A-Law codec
+pub const BASS_RTP_CODEC_G711A: u8 = 6;
+/// AAC-LC over LATM (PT 122)
+pub const BASS_RTP_CODEC_AAC_LC: u8 = 7;
+/// HE-AAC over LATM (PT 122, needs FFmpeg with libfdk_aac)
+pub const BASS_RTP_CODEC_HE_AAC: u8 = 8;
+/// AAC-ELD over LATM (PT 122, needs FFmpeg with libfdk_aac)
+pub const BASS_RTP_CODEC_AAC_ELD: u8 = 9;
+/// Opus (RFC 7587, dynamic PT, 111 unless set with BASS_RTP_*SetPayloadType)
+pub const BASS_RTP_CODEC_OPUS: u8 = 10;
+/// FLAC, one frame per packet (dynamic PT, 112 unless set with BASS_RTP_*SetPayloadType)
+pub const BASS_RTP_CODEC_FLAC: u8 = 11;
 
 // ============================================================================
 // Buffer Mode Constants
@@ -64,6 +81,8 @@ pub const BASS_RTP_CLOCK_PTP: u8 = 0;
 pub const BASS_RTP_CLOCK_LIVEWIRE: u8 = 1;
 /// System clock mode
 pub const BASS_RTP_CLOCK_SYSTEM: u8 = 2;
+/// Stream clock mode (recovered from a received stream, see BASS_RTP_InputSetClockSource)
+pub const BASS_RTP_CLOCK_STREAM: u8 = 4;
 
 // ============================================================================
 // DLL Entry Point (Windows)
@@ -93,6 +112,101 @@ pub extern "system" fn DllMain(
     1 // TRUE
 }
 
+// ============================================================================
+// Clock Selection
+// ============================================================================
+
+/// Select which bass_ptp instance PTP clock mode follows.
+///
+/// # Arguments
+/// * `instance` - Handle from BASS_PTP_CreateInstance, or 0 for the default client
+///
+/// # Returns
+/// * 1 always
+#[no_mangle]
+pub extern "system" fn BASS_RTP_SetPtpInstance(instance: u32) -> i32 {
+    clock_bindings::clock_set_ptp_instance(instance);
+    1
+}
+
+/// Limit how fast the clock frequency the streams follow may change, so
+/// switching to the fallback clock and back doesn't step the resamplers.
+///
+/// # Arguments
+/// * `ppm_per_sec_x1000` - Slew limit in ppm per second x 1000 (0 = unlimited, default 1000)
+/// * `return_delay_secs` - Seconds the clock must stay locked before fallback ends (default 3)
+///
+/// # Returns
+/// * 1 always
+#[no_mangle]
+pub extern "system" fn BASS_RTP_SetClockSlew(ppm_per_sec_x1000: u32, return_delay_secs: u32) -> i32 {
+    clock_bindings::set_slew_rate(ppm_per_sec_x1000 as f64 / 1000.0);
+    clock_bindings::set_fallback_return_delay(return_delay_secs);
+    1
+}
+
+/// Receive clock events for logging and alarms, on a thread of their own.
+///
+/// # Arguments
+/// * `callback` - Called with the event (1 = locked, 2 = lost, 3 = fallback
+///   entered, 4 = fallback exited, 5 = grandmaster changed), the clock mode
+///   (BASS_RTP_CLOCK_*) and `user`; NULL stops the events
+/// * `user` - Passed to the callback
+///
+/// # Returns
+/// * 1 always
+#[no_mangle]
+pub extern "system" fn BASS_RTP_SetClockEventCallback(
+    callback: Option<clock_bindings::ClockEventCallback>,
+    user: *mut c_void,
+) -> i32 {
+    clock_bindings::clock_set_event_callback(callback, user);
+    1
+}
+
+/// Make a received stream the media clock for this process (key 0 = none).
+/// Starts the Stream clock mode if it isn't running, or stops it when the
+/// source is cleared.
+fn set_clock_source(key: usize, interface: Ipv4Addr) -> i32 {
+    media_clock::select_source(key, None);
+    let stream_active = clock_bindings::get_active_mode() == Some(ClockMode::Stream);
+    if key == 0 {
+        if stream_active {
+            clock_bindings::clock_stop();
+        }
+        return 1;
+    }
+    if !stream_active {
+        clock_bindings::clock_stop();
+        if clock_bindings::clock_start(interface, 0, ClockMode::Stream).is_err() {
+            return 0;
+        }
+    }
+    1
+}
+
+lazy_static! {
+    /// Media clock key of every received stream, by BASS stream handle, so
+    /// other streams can follow it with a "stream:HANDLE" clock reference
+    static ref CLOCK_KEYS: Mutex<HashMap<HSTREAM, usize>> = Mutex::new(HashMap::new());
+}
+
+/// Parse a clock reference string (NULL or "" = the process-wide clock)
+/// and acquire its clock
+unsafe fn acquire_clock(clock: *const c_char, interface: Ipv4Addr) -> Result<ClockLease, i32> {
+    let reference = if clock.is_null() {
+        ClockRef::Global
+    } else {
+        match CStr::from_ptr(clock).to_str() {
+            Ok("") => ClockRef::Global,
+            Ok(s) => s.parse().map_err(|_| BASS_ERROR_ILLPARAM)?,
+            Err(_) => return Err(BASS_ERROR_ILLPARAM),
+        }
+    };
+    ClockLease::acquire(reference, interface, |handle| CLOCK_KEYS.lock().get(&handle).copied())
+        .map_err(|_| BASS_ERROR_NOTAVAIL)
+}
+
 // ============================================================================
 // INPUT MODULE FFI API (WE connect TO Z/IP ONE)
 // ============================================================================
@@ -114,9 +228,9 @@ pub struct RtpInputConfigFFI {
     pub channels: u16,
     /// Send codec (BASS_RTP_CODEC_*)
     pub send_codec: u8,
-    /// Send bitrate in kbps (for MP2, 0 = default 256)
+    /// Send bitrate in kbps (for MP2, AAC and Opus, 0 = default 256)
     pub send_bitrate: u32,
-    /// Frame duration in milliseconds (1-5, 0 = default 1)
+    /// Frame duration in milliseconds (1-5, 0 = default 1; Opus up to 60, rounded to its frame sizes)
     pub frame_duration_ms: u32,
     /// Clock mode (BASS_RTP_CLOCK_*)
     pub clock_mode: u8,
@@ -168,12 +282,19 @@ fn convert_input_ffi_config(config: &RtpInputConfigFFI) -> RtpInputConfig {
         BASS_RTP_CODEC_MP2 => PayloadCodec::Mp2,
         BASS_RTP_CODEC_G711 => PayloadCodec::G711Ulaw,
         BASS_RTP_CODEC_G722 => PayloadCodec::G722,
+        BASS_RTP_CODEC_G711A => PayloadCodec::G711Alaw,
+        BASS_RTP_CODEC_AAC_LC => PayloadCodec::AacLatm,
+        BASS_RTP_CODEC_HE_AAC => PayloadCodec::HeAac,
+        BASS_RTP_CODEC_AAC_ELD => PayloadCodec::AacEld,
+        BASS_RTP_CODEC_OPUS => PayloadCodec::Opus,
+        BASS_RTP_CODEC_FLAC => PayloadCodec::Flac,
         _ => PayloadCodec::Pcm16,
     };
 
     let clock_mode = match config.clock_mode {
         BASS_RTP_CLOCK_PTP => ClockMode::Ptp,
         BASS_RTP_CLOCK_LIVEWIRE => ClockMode::Livewire,
+        BASS_RTP_CLOCK_STREAM => ClockMode::Stream,
         _ => ClockMode::System,
     };
 
@@ -212,6 +333,8 @@ fn convert_input_ffi_config(config: &RtpInputConfigFFI) -> RtpInputConfig {
         send_codec,
         send_bitrate: if config.send_bitrate > 0 { config.send_bitrate } else { 256 },
         frame_duration_ms: if config.frame_duration_ms > 0 { config.frame_duration_ms } else { 1 },
+        opus_fec_loss: 0,
+        payload_types: DynamicPayloadTypes::default(),
         clock_mode,
         ptp_domain: config.ptp_domain,
         return_buffer_mode,
@@ -287,6 +410,7 @@ pub unsafe extern "system" fn BASS_RTP_InputStart(handle: *mut c_void) -> i32 {
     }
 
     stream.return_handle = bass_stream;
+    CLOCK_KEYS.lock().insert(bass_stream, stream.clock_key());
 
     match stream.start() {
         Ok(_) => 1,
@@ -394,6 +518,130 @@ pub unsafe extern "system" fn BASS_RTP_InputIsRunning(handle: *mut c_void) -> i3
     if stream.is_running() { 1 } else { 0 }
 }
 
+/// Use the return audio of an RTP Input stream as the media clock.
+///
+/// Every RTP stream in this process then paces its audio to the sender of
+/// that return audio. The RTP clock rate is detected from the stream.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_InputCreate
+/// * `enable` - 1 to use this stream as the clock, 0 to stop using it
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_InputSetClockSource(handle: *mut c_void, enable: i32) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &*(handle as *const RtpInput);
+    if enable != 0 {
+        set_clock_source(stream.clock_key(), stream.config.interface_addr)
+    } else if media_clock::source() == stream.clock_key() {
+        set_clock_source(0, stream.config.interface_addr)
+    } else {
+        1
+    }
+}
+
+/// Pace the RTP Input stream to its own clock instead of the process-wide
+/// clock. The stream must be stopped.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_InputCreate
+/// * `clock` - "ptp:N", "lw", "sys", "ntp" or "stream:HANDLE" (NULL or "" = process-wide clock)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_InputSetClock(handle: *mut c_void, clock: *const c_char) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpInput);
+    if stream.is_running() {
+        set_error(BASS_ERROR_ALREADY);
+        return 0;
+    }
+    match acquire_clock(clock, stream.config.interface_addr) {
+        Ok(lease) => match stream.set_clock(lease) {
+            Ok(()) => 1,
+            Err(_) => {
+                set_error(BASS_ERROR_ALREADY);
+                0
+            }
+        },
+        Err(code) => {
+            set_error(code);
+            0
+        }
+    }
+}
+
+/// Protect the Opus audio the RTP Input stream sends with in-band FEC.
+/// Takes effect the next time the stream starts.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_InputCreate
+/// * `loss_percent` - Expected packet loss in percent (0 = FEC off)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_InputSetOpusFec(handle: *mut c_void, loss_percent: u32) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpInput);
+    stream.config.opus_fec_loss = loss_percent.min(100) as u8;
+    1
+}
+
+/// Set the dynamic payload type the RTP Input stream uses for Opus or FLAC,
+/// both for sending and for picking the decoder of received packets.
+/// Takes effect the next time the stream starts.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_InputCreate
+/// * `codec` - BASS_RTP_CODEC_OPUS or BASS_RTP_CODEC_FLAC
+/// * `payload_type` - Dynamic payload type (96-127)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_InputSetPayloadType(
+    handle: *mut c_void,
+    codec: u8,
+    payload_type: u8,
+) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    if !DynamicPayloadTypes::is_dynamic(payload_type) {
+        set_error(BASS_ERROR_ILLPARAM);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpInput);
+    match codec {
+        BASS_RTP_CODEC_OPUS => stream.config.payload_types.opus = payload_type,
+        BASS_RTP_CODEC_FLAC => stream.config.payload_types.flac = payload_type,
+        _ => {
+            set_error(BASS_ERROR_ILLPARAM);
+            return 0;
+        }
+    }
+    1
+}
+
 /// Free resources associated with an RTP Input stream
 ///
 /// # Arguments
@@ -415,6 +663,7 @@ pub unsafe extern "system" fn BASS_RTP_InputFree(handle: *mut c_void) -> i32 {
     // Free the BASS return stream
     let return_handle = stream.return_handle;
     if return_handle != 0 {
+        CLOCK_KEYS.lock().remove(&return_handle);
         BASS_StreamFree(return_handle);
     }
 
@@ -440,9 +689,9 @@ pub struct RtpOutputConfigFFI {
     pub channels: u16,
     /// Backfeed codec (BASS_RTP_CODEC_*)
     pub backfeed_codec: u8,
-    /// Backfeed bitrate in kbps (for MP2, 0 = default 256)
+    /// Backfeed bitrate in kbps (for MP2, AAC and Opus, 0 = default 256)
     pub backfeed_bitrate: u32,
-    /// Frame duration in milliseconds (1-5, 0 = default 1)
+    /// Frame duration in milliseconds (1-5, 0 = default 1; Opus up to 60, rounded to its frame sizes)
     pub frame_duration_ms: u32,
     /// Clock mode (BASS_RTP_CLOCK_*)
     pub clock_mode: u8,
@@ -498,12 +747,19 @@ fn convert_output_ffi_config(config: &RtpOutputConfigFFI) -> RtpOutputConfig {
         BASS_RTP_CODEC_MP2 => PayloadCodec::Mp2,
         BASS_RTP_CODEC_G711 => PayloadCodec::G711Ulaw,
         BASS_RTP_CODEC_G722 => PayloadCodec::G722,
+        BASS_RTP_CODEC_G711A => PayloadCodec::G711Alaw,
+        BASS_RTP_CODEC_AAC_LC => PayloadCodec::AacLatm,
+        BASS_RTP_CODEC_HE_AAC => PayloadCodec::HeAac,
+        BASS_RTP_CODEC_AAC_ELD => PayloadCodec::AacEld,
+        BASS_RTP_CODEC_OPUS => PayloadCodec::Opus,
+        BASS_RTP_CODEC_FLAC => PayloadCodec::Flac,
         _ => PayloadCodec::Pcm16,
     };
 
     let clock_mode = match config.clock_mode {
         BASS_RTP_CLOCK_PTP => ClockMode::Ptp,
         BASS_RTP_CLOCK_LIVEWIRE => ClockMode::Livewire,
+        BASS_RTP_CLOCK_STREAM => ClockMode::Stream,
         _ => ClockMode::System,
     };
 
@@ -535,6 +791,8 @@ fn convert_output_ffi_config(config: &RtpOutputConfigFFI) -> RtpOutputConfig {
         backfeed_codec,
         backfeed_bitrate: if config.backfeed_bitrate > 0 { config.backfeed_bitrate } else { 256 },
         frame_duration_ms: if config.frame_duration_ms > 0 { config.frame_duration_ms } else { 1 },
+        opus_fec_loss: 0,
+        payload_types: DynamicPayloadTypes::default(),
         clock_mode,
         ptp_domain: config.ptp_domain,
         buffer_mode,
@@ -612,6 +870,7 @@ pub unsafe extern "system" fn BASS_RTP_OutputStart(handle: *mut c_void) -> i32 {
     }
 
     stream.incoming_handle = bass_stream;
+    CLOCK_KEYS.lock().insert(bass_stream, stream.clock_key());
 
     match stream.start() {
         Ok(_) => 1,
@@ -719,6 +978,130 @@ pub unsafe extern "system" fn BASS_RTP_OutputIsRunning(handle: *mut c_void) -> i
     if stream.is_running() { 1 } else { 0 }
 }
 
+/// Use the incoming audio of an RTP Output stream as the media clock.
+///
+/// Every RTP stream in this process then paces its audio to the sender of
+/// that incoming audio. The RTP clock rate is detected from the stream.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_OutputCreate
+/// * `enable` - 1 to use this stream as the clock, 0 to stop using it
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_OutputSetClockSource(handle: *mut c_void, enable: i32) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &*(handle as *const RtpOutput);
+    if enable != 0 {
+        set_clock_source(stream.clock_key(), stream.config.interface_addr)
+    } else if media_clock::source() == stream.clock_key() {
+        set_clock_source(0, stream.config.interface_addr)
+    } else {
+        1
+    }
+}
+
+/// Pace the RTP Output stream's backfeed to its own clock instead of the
+/// process-wide clock. The stream must be stopped.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_OutputCreate
+/// * `clock` - "ptp:N", "lw", "sys", "ntp" or "stream:HANDLE" (NULL or "" = process-wide clock)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_OutputSetClock(handle: *mut c_void, clock: *const c_char) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpOutput);
+    if stream.is_running() {
+        set_error(BASS_ERROR_ALREADY);
+        return 0;
+    }
+    match acquire_clock(clock, stream.config.interface_addr) {
+        Ok(lease) => match stream.set_clock(lease) {
+            Ok(()) => 1,
+            Err(_) => {
+                set_error(BASS_ERROR_ALREADY);
+                0
+            }
+        },
+        Err(code) => {
+            set_error(code);
+            0
+        }
+    }
+}
+
+/// Protect the Opus audio the RTP Output stream sends with in-band FEC.
+/// Takes effect the next time the stream starts.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_OutputCreate
+/// * `loss_percent` - Expected packet loss in percent (0 = FEC off)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_OutputSetOpusFec(handle: *mut c_void, loss_percent: u32) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpOutput);
+    stream.config.opus_fec_loss = loss_percent.min(100) as u8;
+    1
+}
+
+/// Set the dynamic payload type the RTP Output stream uses for Opus or FLAC,
+/// both for sending and for picking the decoder of received packets.
+/// Takes effect the next time the stream starts.
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_RTP_OutputCreate
+/// * `codec` - BASS_RTP_CODEC_OPUS or BASS_RTP_CODEC_FLAC
+/// * `payload_type` - Dynamic payload type (96-127)
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_RTP_OutputSetPayloadType(
+    handle: *mut c_void,
+    codec: u8,
+    payload_type: u8,
+) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+
+    if !DynamicPayloadTypes::is_dynamic(payload_type) {
+        set_error(BASS_ERROR_ILLPARAM);
+        return 0;
+    }
+
+    let stream = &mut *(handle as *mut RtpOutput);
+    match codec {
+        BASS_RTP_CODEC_OPUS => stream.config.payload_types.opus = payload_type,
+        BASS_RTP_CODEC_FLAC => stream.config.payload_types.flac = payload_type,
+        _ => {
+            set_error(BASS_ERROR_ILLPARAM);
+            return 0;
+        }
+    }
+    1
+}
+
 /// Free resources associated with an RTP Output stream
 ///
 /// # Arguments
@@ -740,6 +1123,7 @@ pub unsafe extern "system" fn BASS_RTP_OutputFree(handle: *mut c_void) -> i32 {
     // Free the BASS incoming stream
     let incoming_handle = stream.incoming_handle;
     if incoming_handle != 0 {
+        CLOCK_KEYS.lock().remove(&incoming_handle);
         BASS_StreamFree(incoming_handle);
     }
 
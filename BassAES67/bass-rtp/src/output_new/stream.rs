@@ -31,18 +31,20 @@ pub type ConnectionCallback = extern "C" fn(state: ConnectionState, user_data: *
 
 use ringbuf::{traits::{Consumer, Observer, Producer, Split}, HeapRb};
 
-use crate::clock_bindings::{clock_get_frequency_ppm, clock_is_locked, init_clock_bindings, ClockMode};
-use crate::codec::g711::{G711UlawDecoder, G711UlawEncoder};
+use crate::clock_bindings::{init_clock_bindings, ClockLease, ClockMode};
+use crate::codec::g711::{G711AlawDecoder, G711AlawEncoder, G711UlawDecoder, G711UlawEncoder};
 use crate::codec::g722::{G722Decoder, G722Encoder};
 use crate::codec::ffmpeg_aac;
+use crate::codec::flac;
 use crate::codec::mpg123;
+use crate::codec::opus;
 use crate::codec::twolame;
 use crate::codec::{
     AudioDecoder, AudioEncoder, AudioFormat, Pcm16Decoder, Pcm16Encoder, Pcm20Decoder,
     Pcm20Encoder, Pcm24Decoder, Pcm24Encoder,
 };
 use crate::ffi::*;
-use crate::rtp::{PayloadCodec, RtpPacket, RtpPacketBuilder, RtpSocket};
+use crate::rtp::{DynamicPayloadTypes, PayloadCodec, RtpPacket, RtpPacketBuilder, RtpSocket};
 use crate::input::BufferMode;
 
 /// FFI import for BASS_ChannelGetData
@@ -77,6 +79,10 @@ pub struct RtpOutputConfig {
     pub backfeed_bitrate: u32,
     /// Frame duration in milliseconds
     pub frame_duration_ms: u32,
+    /// Expected packet loss in percent for Opus in-band FEC (0 = FEC off)
+    pub opus_fec_loss: u8,
+    /// Payload types for Opus and FLAC, in both directions
+    pub payload_types: DynamicPayloadTypes,
     /// Clock mode (PTP/Livewire/System)
     pub clock_mode: ClockMode,
     /// PTP domain (0-127)
@@ -101,6 +107,8 @@ impl Default for RtpOutputConfig {
             backfeed_codec: PayloadCodec::Pcm16,
             backfeed_bitrate: 256,
             frame_duration_ms: 1,
+            opus_fec_loss: 0,
+            payload_types: DynamicPayloadTypes::default(),
             clock_mode: ClockMode::System,
             ptp_domain: 0,
             buffer_mode: BufferMode::Simple { buffer_ms: 100 },
@@ -119,6 +127,39 @@ struct TxConfig {
     backfeed_codec: PayloadCodec,
     backfeed_bitrate: u32,
     frame_duration_ms: u32,
+    opus_fec_loss: u8,
+    payload_types: DynamicPayloadTypes,
+}
+
+/// What the RX thread takes over when the stream starts
+struct ReceiverParams {
+    running: Arc<AtomicBool>,
+    stats: Arc<AtomicStats>,
+    socket: RtpSocket,
+    producer: ringbuf::HeapProd<f32>,
+    remote_addr: Arc<SharedRemoteAddr>,
+    callback: Option<ConnectionCallback>,
+    /// Callback user data, as usize for Send
+    callback_user_data: usize,
+    /// Payload types that select the Opus and FLAC decoders
+    payload_types: DynamicPayloadTypes,
+    /// Media clock key of this stream
+    clock_key: usize,
+}
+
+/// What the TX thread takes over when the stream starts
+struct TransmitterParams {
+    running: Arc<AtomicBool>,
+    stats: Arc<AtomicStats>,
+    current_ppm_x1000: Arc<AtomicI64>,
+    socket: RtpSocket,
+    config: TxConfig,
+    backfeed_channel: HSTREAM,
+    remote_addr: Arc<SharedRemoteAddr>,
+    callback: Option<ConnectionCallback>,
+    /// Callback user data, as usize for Send
+    callback_user_data: usize,
+    clock: Arc<ClockLease>,
 }
 
 // ============================================================================
@@ -199,7 +240,11 @@ enum BackfeedEncoderType {
     Pcm24(Pcm24Encoder),
     Mp2(twolame::Encoder),
     G711Ulaw(G711UlawEncoder),
+    G711Alaw(G711AlawEncoder),
     G722(G722Encoder),
+    Aac(ffmpeg_aac::Encoder),
+    Opus(opus::Encoder),
+    Flac(flac::Encoder),
 }
 
 impl BackfeedEncoderType {
@@ -221,9 +266,21 @@ impl BackfeedEncoderType {
             BackfeedEncoderType::G711Ulaw(enc) => {
                 enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
             }
+            BackfeedEncoderType::G711Alaw(enc) => {
+                enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
+            }
             BackfeedEncoderType::G722(enc) => {
                 enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
             }
+            BackfeedEncoderType::Aac(enc) => {
+                enc.encode(pcm, output).map_err(|e| format!("{:?}", e))
+            }
+            BackfeedEncoderType::Opus(enc) => {
+                enc.encode_float(pcm, output).map_err(|e| format!("{:?}", e))
+            }
+            BackfeedEncoderType::Flac(enc) => {
+                enc.encode_float(pcm, output).map_err(|e| format!("{:?}", e))
+            }
         }
     }
 
@@ -235,11 +292,15 @@ impl BackfeedEncoderType {
             BackfeedEncoderType::Pcm24(enc) => enc.total_samples_per_frame(),
             BackfeedEncoderType::Mp2(enc) => enc.total_samples_per_frame(),
             BackfeedEncoderType::G711Ulaw(enc) => enc.total_samples_per_frame(),
+            BackfeedEncoderType::G711Alaw(enc) => enc.total_samples_per_frame(),
             BackfeedEncoderType::G722(enc) => enc.total_samples_per_frame(),
+            BackfeedEncoderType::Aac(enc) => enc.total_samples_per_frame(),
+            BackfeedEncoderType::Opus(enc) => enc.total_samples_per_frame(),
+            BackfeedEncoderType::Flac(enc) => enc.total_samples_per_frame(),
         }
     }
 
-    fn payload_type(&self) -> u8 {
+    fn payload_type(&self, dynamic: &DynamicPayloadTypes) -> u8 {
         match self {
             BackfeedEncoderType::None => 0,
             BackfeedEncoderType::Pcm16(enc) => enc.payload_type(),
@@ -247,7 +308,11 @@ impl BackfeedEncoderType {
             BackfeedEncoderType::Pcm24(enc) => enc.payload_type(),
             BackfeedEncoderType::Mp2(_) => 14, // MPEG Audio
             BackfeedEncoderType::G711Ulaw(enc) => enc.payload_type(),
+            BackfeedEncoderType::G711Alaw(enc) => enc.payload_type(),
             BackfeedEncoderType::G722(enc) => enc.payload_type(),
+            BackfeedEncoderType::Aac(enc) => enc.payload_type(),
+            BackfeedEncoderType::Opus(_) => dynamic.opus,
+            BackfeedEncoderType::Flac(_) => dynamic.flac,
         }
     }
 }
@@ -264,12 +329,16 @@ enum IncomingDecoderType {
     Pcm24(Pcm24Decoder),
     Mp2(mpg123::Decoder),
     G711Ulaw(G711UlawDecoder),
+    G711Alaw(G711AlawDecoder),
     G722(G722Decoder),
     Aac(ffmpeg_aac::Decoder),
+    /// Decoder and the last RTP sequence number, to spot lost packets
+    Opus(opus::Decoder, Option<u16>),
+    Flac(flac::Decoder),
 }
 
 impl IncomingDecoderType {
-    fn decode(&mut self, data: &[u8], output: &mut [f32]) -> Result<usize, String> {
+    fn decode(&mut self, data: &[u8], sequence: u16, output: &mut [f32]) -> Result<usize, String> {
         match self {
             IncomingDecoderType::None => Err("No decoder".to_string()),
             IncomingDecoderType::Pcm16(dec) => {
@@ -319,19 +388,42 @@ impl IncomingDecoderType {
             IncomingDecoderType::G711Ulaw(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
+            IncomingDecoderType::G711Alaw(dec) => {
+                dec.decode(data, output).map_err(|e| format!("{:?}", e))
+            }
             IncomingDecoderType::G722(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
             IncomingDecoderType::Aac(dec) => {
                 dec.decode(data, output).map_err(|e| format!("{:?}", e))
             }
+            IncomingDecoderType::Opus(dec, last_sequence) => {
+                let lost = last_sequence.map_or(0, |last| opus::lost_packets(last, sequence));
+                *last_sequence = Some(sequence);
+                dec.decode_float_after_loss(data, lost, output)
+                    .map(|samples| samples * 2)
+                    .map_err(|e| format!("{:?}", e))
+            }
+            IncomingDecoderType::Flac(dec) => {
+                let samples = dec.decode(data, output).map_err(|e| format!("{:?}", e))?;
+                // Mono senders play on both channels
+                if dec.detected_format().is_some_and(|f| f.channels == 1) {
+                    let samples = samples.min(output.len() / 2);
+                    for i in (0..samples).rev() {
+                        output[2 * i] = output[i];
+                        output[2 * i + 1] = output[i];
+                    }
+                    return Ok(samples * 2);
+                }
+                Ok(samples)
+            }
         }
     }
 }
 
 /// Create decoder for payload type.
-fn create_decoder_for_pt(pt: u8) -> Option<IncomingDecoderType> {
-    match PayloadCodec::from_pt(pt) {
+fn create_decoder_for_pt(pt: u8, dynamic: &DynamicPayloadTypes) -> Option<IncomingDecoderType> {
+    match dynamic.codec(pt) {
         PayloadCodec::Pcm16 => Some(IncomingDecoderType::Pcm16(Pcm16Decoder::new_auto(2))),
         PayloadCodec::Pcm20 => Some(IncomingDecoderType::Pcm20(Pcm20Decoder::new_auto(2))),
         PayloadCodec::Pcm24 => Some(IncomingDecoderType::Pcm24(Pcm24Decoder::new_auto(2))),
@@ -339,10 +431,20 @@ fn create_decoder_for_pt(pt: u8) -> Option<IncomingDecoderType> {
             mpg123::Decoder::new().ok().map(IncomingDecoderType::Mp2)
         }
         PayloadCodec::G711Ulaw => Some(IncomingDecoderType::G711Ulaw(G711UlawDecoder::new())),
+        PayloadCodec::G711Alaw => Some(IncomingDecoderType::G711Alaw(G711AlawDecoder::new())),
         PayloadCodec::G722 => Some(IncomingDecoderType::G722(G722Decoder::new())),
         PayloadCodec::Aac => {
             ffmpeg_aac::Decoder::new().ok().map(IncomingDecoderType::Aac)
         }
+        PayloadCodec::AacLatm => {
+            ffmpeg_aac::Decoder::new_latm().ok().map(IncomingDecoderType::Aac)
+        }
+        PayloadCodec::Opus => opus::Decoder::new(AudioFormat::standard(), 20.0)
+            .ok()
+            .map(|dec| IncomingDecoderType::Opus(dec, None)),
+        PayloadCodec::Flac => {
+            flac::Decoder::new_48k_stereo().ok().map(IncomingDecoderType::Flac)
+        }
         PayloadCodec::Unknown(_) => None,
         _ => None,
     }
@@ -482,6 +584,10 @@ pub struct RtpOutput {
     remote_addr: Arc<SharedRemoteAddr>,
     /// Last seen connection generation (for detecting reconnection)
     last_generation: AtomicU64,
+    /// Key to select the incoming stream as the media clock source
+    clock_key: usize,
+    /// Clock the transmitter follows
+    clock: Arc<ClockLease>,
 }
 
 impl RtpOutput {
@@ -535,6 +641,8 @@ impl RtpOutput {
             buffering: AtomicBool::new(true),
             remote_addr: Arc::new(SharedRemoteAddr::new()),
             last_generation: AtomicU64::new(0),
+            clock_key: crate::media_clock::new_source_key(),
+            clock: Arc::new(ClockLease::global()),
         })
     }
 
@@ -577,34 +685,46 @@ impl RtpOutput {
         self.max_samples = max_samples;
 
         // Start RX thread (receives incoming audio, detects remote address)
-        let running_rx = self.running.clone();
-        let stats_rx = self.stats.clone();
-        let remote_addr_rx = self.remote_addr.clone();
-        let callback_rx = self.config.connection_callback;
-        let callback_user_data_rx = self.config.callback_user_data as usize; // Cast to usize for Send
+        let rx_params = ReceiverParams {
+            running: self.running.clone(),
+            stats: self.stats.clone(),
+            socket,
+            producer,
+            remote_addr: self.remote_addr.clone(),
+            callback: self.config.connection_callback,
+            callback_user_data: self.config.callback_user_data as usize, // Cast to usize for Send
+            payload_types: self.config.payload_types,
+            clock_key: self.clock_key,
+        };
 
         self.rx_thread = Some(thread::spawn(move || {
-            Self::receiver_loop(running_rx, stats_rx, socket, producer, remote_addr_rx, callback_rx, callback_user_data_rx);
+            Self::receiver_loop(rx_params);
         }));
 
         // Start TX thread (sends backfeed once remote is known)
-        let running_tx = self.running.clone();
-        let stats_tx = self.stats.clone();
-        let ppm_tx = self.current_ppm_x1000.clone();
-        let tx_config = TxConfig {
-            sample_rate: self.config.sample_rate,
-            channels: self.config.channels,
-            backfeed_codec: self.config.backfeed_codec.clone(),
-            backfeed_bitrate: self.config.backfeed_bitrate,
-            frame_duration_ms: self.config.frame_duration_ms,
+        let tx_params = TransmitterParams {
+            running: self.running.clone(),
+            stats: self.stats.clone(),
+            current_ppm_x1000: self.current_ppm_x1000.clone(),
+            socket: socket_for_tx,
+            config: TxConfig {
+                sample_rate: self.config.sample_rate,
+                channels: self.config.channels,
+                backfeed_codec: self.config.backfeed_codec.clone(),
+                backfeed_bitrate: self.config.backfeed_bitrate,
+                frame_duration_ms: self.config.frame_duration_ms,
+                opus_fec_loss: self.config.opus_fec_loss,
+                payload_types: self.config.payload_types,
+            },
+            backfeed_channel: self.backfeed_channel,
+            remote_addr: self.remote_addr.clone(),
+            callback: self.config.connection_callback,
+            callback_user_data: self.config.callback_user_data as usize,
+            clock: self.clock.clone(),
         };
-        let backfeed = self.backfeed_channel;
-        let remote_addr_tx = self.remote_addr.clone();
-        let callback_tx = self.config.connection_callback;
-        let callback_user_data_tx = self.config.callback_user_data as usize;
 
         self.tx_thread = Some(thread::spawn(move || {
-            Self::transmitter_loop(running_tx, stats_tx, ppm_tx, socket_for_tx, tx_config, backfeed, remote_addr_tx, callback_tx, callback_user_data_tx);
+            Self::transmitter_loop(tx_params);
         }));
 
         Ok(())
@@ -623,15 +743,18 @@ impl RtpOutput {
     }
 
     /// Receiver loop - receives RTP, decodes, pushes to ring buffer, auto-detects remote.
-    fn receiver_loop(
-        running: Arc<AtomicBool>,
-        stats: Arc<AtomicStats>,
-        socket: RtpSocket,
-        mut producer: ringbuf::HeapProd<f32>,
-        remote_addr: Arc<SharedRemoteAddr>,
-        callback: Option<ConnectionCallback>,
-        callback_user_data: usize,
-    ) {
+    fn receiver_loop(params: ReceiverParams) {
+        let ReceiverParams {
+            running,
+            stats,
+            socket,
+            mut producer,
+            remote_addr,
+            callback,
+            callback_user_data,
+            payload_types,
+            clock_key,
+        } = params;
         let mut recv_buf = vec![0u8; 4096];
         let mut decode_buf = vec![0.0f32; 8192];
         let mut decoder: IncomingDecoderType = IncomingDecoderType::None;
@@ -661,10 +784,11 @@ impl RtpOutput {
 
                     if let Some(packet) = RtpPacket::parse(&recv_buf[..len]) {
                         let pt = packet.header.payload_type;
+                        crate::media_clock::feed(clock_key, packet.header.timestamp);
 
                         // Switch decoder if PT changed
                         if current_pt != Some(pt) {
-                            if let Some(new_dec) = create_decoder_for_pt(pt) {
+                            if let Some(new_dec) = create_decoder_for_pt(pt, &payload_types) {
                                 decoder = new_dec;
                                 current_pt = Some(pt);
                                 stats.detected_incoming_pt.store(pt as u32, Ordering::Relaxed);
@@ -672,7 +796,7 @@ impl RtpOutput {
                         }
 
                         // Decode
-                        match decoder.decode(packet.payload, &mut decode_buf) {
+                        match decoder.decode(packet.payload, packet.header.sequence, &mut decode_buf) {
                             Ok(samples) if samples > 0 => {
                                 stats.rx_packets.fetch_add(1, Ordering::Relaxed);
                                 stats.rx_bytes.fetch_add(len as u64, Ordering::Relaxed);
@@ -702,17 +826,19 @@ impl RtpOutput {
 
     /// Transmitter loop - reads from BASS, encodes, sends backfeed RTP.
     /// Automatically stops sending when connection is lost and resumes when reconnected.
-    fn transmitter_loop(
-        running: Arc<AtomicBool>,
-        stats: Arc<AtomicStats>,
-        current_ppm_x1000: Arc<AtomicI64>,
-        socket: RtpSocket,
-        config: TxConfig,
-        backfeed_channel: HSTREAM,
-        remote_addr: Arc<SharedRemoteAddr>,
-        callback: Option<ConnectionCallback>,
-        callback_user_data: usize,
-    ) {
+    fn transmitter_loop(params: TransmitterParams) {
+        let TransmitterParams {
+            running,
+            stats,
+            current_ppm_x1000,
+            socket,
+            config,
+            backfeed_channel,
+            remote_addr,
+            callback,
+            callback_user_data,
+            clock,
+        } = params;
         // Set thread priority
         #[cfg(windows)]
         {
@@ -745,7 +871,51 @@ impl RtpOutput {
                 Err(_) => return,
             },
             PayloadCodec::G711Ulaw => BackfeedEncoderType::G711Ulaw(G711UlawEncoder::new()),
+            PayloadCodec::G711Alaw => BackfeedEncoderType::G711Alaw(G711AlawEncoder::new()),
             PayloadCodec::G722 => BackfeedEncoderType::G722(G722Encoder::new()),
+            PayloadCodec::AacLatm | PayloadCodec::HeAac | PayloadCodec::AacEld => {
+                let profile = ffmpeg_aac::AacProfile::from_codec(config.backfeed_codec)
+                    .unwrap_or(ffmpeg_aac::AacProfile::Lc);
+                match ffmpeg_aac::Encoder::new(format, config.backfeed_bitrate, profile) {
+                    Ok(e) => BackfeedEncoderType::Aac(e),
+                    Err(e) => {
+                        eprintln!("Backfeed AAC encoder: {}", e);
+                        return;
+                    }
+                }
+            }
+            PayloadCodec::Opus => match opus::Encoder::new(
+                format,
+                config.frame_duration_ms as f32,
+                opus::OPUS_APPLICATION_AUDIO,
+            ) {
+                Ok(mut e) => {
+                    if let Err(err) = e
+                        .set_bitrate(config.backfeed_bitrate as i32 * 1000)
+                        .and_then(|_| e.set_inband_fec(config.opus_fec_loss))
+                    {
+                        eprintln!("Backfeed Opus encoder: {}", err);
+                        return;
+                    }
+                    BackfeedEncoderType::Opus(e)
+                }
+                Err(e) => {
+                    eprintln!("Backfeed Opus encoder: {}", e);
+                    return;
+                }
+            },
+            PayloadCodec::Flac => {
+                // One block per packet, 1-5 ms keeps packets below the MTU
+                let block_size =
+                    (config.sample_rate * config.frame_duration_ms.clamp(1, 5) / 1000) as usize;
+                match flac::Encoder::with_block_size(format, 5, block_size) {
+                    Ok(e) => BackfeedEncoderType::Flac(e),
+                    Err(e) => {
+                        eprintln!("Backfeed FLAC encoder: {}", e);
+                        return;
+                    }
+                }
+            }
             _ => {
                 eprintln!("Unsupported backfeed codec: {:?}", config.backfeed_codec);
                 return;
@@ -759,13 +929,13 @@ impl RtpOutput {
         let mut pcm_buffer = vec![0.0f32; samples_per_frame];
         let encode_buffer_size = match config.backfeed_codec {
             PayloadCodec::Mp2 => 4608,
-            PayloadCodec::G711Ulaw => samples_per_channel,
+            PayloadCodec::G711Ulaw | PayloadCodec::G711Alaw => samples_per_channel,
             PayloadCodec::G722 => samples_per_channel,
             _ => samples_per_frame * 3,
         };
         let mut encode_buffer = vec![0u8; encode_buffer_size];
 
-        let mut packet_builder = RtpPacketBuilder::new(encoder.payload_type());
+        let mut packet_builder = RtpPacketBuilder::new(encoder.payload_type(&config.payload_types));
         let mut next_tx = Instant::now();
         let mut ppm_counter = 0u32;
         let mut current_ppm = 0.0f64;
@@ -795,8 +965,8 @@ impl RtpOutput {
                 ppm_counter += 1;
                 if ppm_counter >= 100 {
                     ppm_counter = 0;
-                    if clock_is_locked() {
-                        current_ppm = clock_get_frequency_ppm();
+                    if clock.is_locked() {
+                        current_ppm = clock.frequency_ppm();
                         current_ppm_x1000.store((current_ppm * 1000.0) as i64, Ordering::Relaxed);
                     }
                 }
@@ -900,6 +1070,20 @@ impl RtpOutput {
         }
     }
 
+    /// Key to select the incoming stream as the media clock source
+    pub fn clock_key(&self) -> usize {
+        self.clock_key
+    }
+
+    /// Follow `clock` instead of the current clock. Only possible while stopped.
+    pub fn set_clock(&mut self, clock: ClockLease) -> Result<(), String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stream is running".to_string());
+        }
+        self.clock = Arc::new(clock);
+        Ok(())
+    }
+
     /// Get current PPM.
     pub fn applied_ppm(&self) -> f64 {
         self.current_ppm_x1000.load(Ordering::Relaxed) as f64 / 1000.0
@@ -1027,6 +1211,7 @@ impl RtpOutput {
 impl Drop for RtpOutput {
     fn drop(&mut self) {
         self.stop();
+        crate::media_clock::release_source(self.clock_key);
     }
 }
 
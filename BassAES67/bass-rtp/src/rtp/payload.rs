@@ -7,6 +7,8 @@
 pub enum PayloadCodec {
     /// G.711 u-Law (PT 0)
     G711Ulaw,
+    /// G.711 A-Law (PT 8)
+    G711Alaw,
     /// G.722 wideband (PT 9)
     G722,
     /// PCM 16-bit (PT 21)
@@ -21,8 +23,14 @@ pub enum PayloadCodec {
     Opus,
     /// FLAC (dynamic PT)
     Flac,
-    /// AAC (PT 99 for MP2-AAC Xstream only - PT 122 LATM not supported)
+    /// AAC (PT 99, MP2-AAC Xstream with ADTS format)
     Aac,
+    /// AAC-LATM (PT 122); sent as AAC-LC, received in any AAC profile
+    AacLatm,
+    /// HE-AAC sent as AAC-LATM (PT 122)
+    HeAac,
+    /// AAC-ELD sent as AAC-LATM (PT 122)
+    AacEld,
     /// Unknown codec
     Unknown(u8),
 }
@@ -31,24 +39,28 @@ impl PayloadCodec {
     /// Get codec from RTP payload type
     ///
     /// Based on Telos Z/IP ONE payload types:
-    /// - G711: PT 0
+    /// - G711 u-Law: PT 0
+    /// - G711 A-Law: PT 8
     /// - G722: PT 9
     /// - PCM-16: PT 21
     /// - PCM-20: PT 116
     /// - PCM-24: PT 22
     /// - MP2: PT 14 or 96
     /// - AAC: PT 99 (MP2-AAC Xstream with ADTS format)
-    /// - PT 122 (AAC-LATM) is NOT supported - requires native LATM decoder
+    /// - AAC-LATM: PT 122 (the profile is signalled in the stream)
     pub fn from_pt(pt: u8) -> Self {
         match pt {
             0 => PayloadCodec::G711Ulaw,
+            8 => PayloadCodec::G711Alaw,
             9 => PayloadCodec::G722,
             14 | 96 => PayloadCodec::Mp2,
             21 => PayloadCodec::Pcm16,
             22 => PayloadCodec::Pcm24,
             99 => PayloadCodec::Aac, // MP2-AAC Xstream (ADTS format - works)
-            // PT 122 (AAC-LATM) intentionally not mapped - requires native LATM decoder
+            122 => PayloadCodec::AacLatm,
             116 => PayloadCodec::Pcm20,
+            111 => PayloadCodec::Opus,
+            112 => PayloadCodec::Flac,
             _ => PayloadCodec::Unknown(pt),
         }
     }
@@ -57,6 +69,7 @@ impl PayloadCodec {
     pub fn to_pt(&self) -> u8 {
         match self {
             PayloadCodec::G711Ulaw => 0,
+            PayloadCodec::G711Alaw => 8,
             PayloadCodec::G722 => 9,
             PayloadCodec::Pcm16 => 21,
             PayloadCodec::Pcm20 => 116,
@@ -65,6 +78,7 @@ impl PayloadCodec {
             PayloadCodec::Opus => 111, // Common dynamic PT for OPUS
             PayloadCodec::Flac => 112, // Custom dynamic PT for FLAC
             PayloadCodec::Aac => 99, // MP2-AAC Xstream (ADTS format)
+            PayloadCodec::AacLatm | PayloadCodec::HeAac | PayloadCodec::AacEld => 122,
             PayloadCodec::Unknown(pt) => *pt,
         }
     }
@@ -73,6 +87,7 @@ impl PayloadCodec {
     pub fn name(&self) -> &'static str {
         match self {
             PayloadCodec::G711Ulaw => "G.711 u-Law",
+            PayloadCodec::G711Alaw => "G.711 A-Law",
             PayloadCodec::G722 => "G.722",
             PayloadCodec::Pcm16 => "PCM 16-bit",
             PayloadCodec::Pcm20 => "PCM 20-bit",
@@ -81,6 +96,9 @@ impl PayloadCodec {
             PayloadCodec::Opus => "OPUS",
             PayloadCodec::Flac => "FLAC",
             PayloadCodec::Aac => "AAC",
+            PayloadCodec::AacLatm => "AAC-LATM",
+            PayloadCodec::HeAac => "HE-AAC",
+            PayloadCodec::AacEld => "AAC-ELD",
             PayloadCodec::Unknown(_) => "Unknown",
         }
     }
@@ -91,7 +109,8 @@ impl PayloadCodec {
             self,
             PayloadCodec::Pcm16 | PayloadCodec::Pcm24 | PayloadCodec::Mp2 |
             PayloadCodec::Opus | PayloadCodec::Flac |
-            PayloadCodec::G711Ulaw | PayloadCodec::G722 | PayloadCodec::Aac
+            PayloadCodec::G711Ulaw | PayloadCodec::G711Alaw | PayloadCodec::G722 | PayloadCodec::Aac |
+            PayloadCodec::AacLatm | PayloadCodec::HeAac | PayloadCodec::AacEld
         )
     }
 
@@ -100,7 +119,8 @@ impl PayloadCodec {
         matches!(
             self,
             PayloadCodec::Pcm16 | PayloadCodec::Pcm24 | PayloadCodec::Mp2 |
-            PayloadCodec::Opus | PayloadCodec::Flac | PayloadCodec::Aac
+            PayloadCodec::Opus | PayloadCodec::Flac |
+            PayloadCodec::AacLatm | PayloadCodec::HeAac | PayloadCodec::AacEld
         )
     }
 
@@ -120,11 +140,15 @@ impl PayloadCodec {
             // FLAC: typically 1152 samples (matches MP2)
             PayloadCodec::Flac => 1152,
             // G.711: 8kHz, 160 samples (20ms)
-            PayloadCodec::G711Ulaw => 160,
+            PayloadCodec::G711Ulaw | PayloadCodec::G711Alaw => 160,
             // G.722: 16kHz, 320 samples (20ms)
             PayloadCodec::G722 => 320,
             // AAC: typically 1024 samples per frame
-            PayloadCodec::Aac => 1024,
+            PayloadCodec::Aac | PayloadCodec::AacLatm => 1024,
+            // HE-AAC: SBR doubles the 1024-sample core frame
+            PayloadCodec::HeAac => 2048,
+            // AAC-ELD: 512 samples per frame
+            PayloadCodec::AacEld => 512,
             // Unknown: assume 1ms
             PayloadCodec::Unknown(_) => (sample_rate / 1000) as usize,
         }
@@ -141,6 +165,54 @@ impl PayloadCodec {
     }
 }
 
+/// Payload types used for the codecs without a static assignment.
+///
+/// Opus and FLAC are carried on dynamic payload types (96-127) that the
+/// endpoints agree on out of band, e.g. through SDP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicPayloadTypes {
+    /// Payload type for Opus
+    pub opus: u8,
+    /// Payload type for FLAC
+    pub flac: u8,
+}
+
+impl Default for DynamicPayloadTypes {
+    fn default() -> Self {
+        Self {
+            opus: PayloadCodec::Opus.to_pt(),
+            flac: PayloadCodec::Flac.to_pt(),
+        }
+    }
+}
+
+impl DynamicPayloadTypes {
+    /// Check that a payload type lies in the dynamic range
+    pub fn is_dynamic(pt: u8) -> bool {
+        (96..=127).contains(&pt)
+    }
+
+    /// Get codec from RTP payload type, configured types first
+    pub fn codec(&self, pt: u8) -> PayloadCodec {
+        if pt == self.opus {
+            PayloadCodec::Opus
+        } else if pt == self.flac {
+            PayloadCodec::Flac
+        } else {
+            PayloadCodec::from_pt(pt)
+        }
+    }
+
+    /// Get the RTP payload type to send this codec with
+    pub fn pt(&self, codec: PayloadCodec) -> u8 {
+        match codec {
+            PayloadCodec::Opus => self.opus,
+            PayloadCodec::Flac => self.flac,
+            other => other.to_pt(),
+        }
+    }
+}
+
 /// Convert BASS_RTP_CODEC_* constant to PayloadCodec
 pub fn codec_from_bass_constant(codec: u8) -> PayloadCodec {
     match codec {
@@ -172,11 +244,31 @@ mod tests {
     #[test]
     fn test_payload_type_mapping() {
         assert_eq!(PayloadCodec::from_pt(0), PayloadCodec::G711Ulaw);
+        assert_eq!(PayloadCodec::from_pt(8), PayloadCodec::G711Alaw);
         assert_eq!(PayloadCodec::from_pt(9), PayloadCodec::G722);
         assert_eq!(PayloadCodec::from_pt(21), PayloadCodec::Pcm16);
         assert_eq!(PayloadCodec::from_pt(22), PayloadCodec::Pcm24);
         assert_eq!(PayloadCodec::from_pt(14), PayloadCodec::Mp2);
         assert_eq!(PayloadCodec::from_pt(96), PayloadCodec::Mp2);
+        assert_eq!(PayloadCodec::from_pt(122), PayloadCodec::AacLatm);
+        assert_eq!(PayloadCodec::HeAac.to_pt(), 122);
+        assert_eq!(PayloadCodec::from_pt(111), PayloadCodec::Opus);
+        assert_eq!(PayloadCodec::from_pt(112), PayloadCodec::Flac);
+    }
+
+    #[test]
+    fn test_dynamic_payload_types() {
+        let dynamic = DynamicPayloadTypes { opus: 101, flac: 96 };
+        assert_eq!(dynamic.codec(101), PayloadCodec::Opus);
+        assert_eq!(dynamic.codec(96), PayloadCodec::Flac);
+        assert_eq!(dynamic.codec(21), PayloadCodec::Pcm16);
+        assert_eq!(dynamic.pt(PayloadCodec::Opus), 101);
+        assert_eq!(dynamic.pt(PayloadCodec::Flac), 96);
+        assert_eq!(dynamic.pt(PayloadCodec::Pcm24), 22);
+        assert_eq!(DynamicPayloadTypes::default().codec(111), PayloadCodec::Opus);
+        assert!(DynamicPayloadTypes::is_dynamic(96));
+        assert!(!DynamicPayloadTypes::is_dynamic(95));
+        assert!(!DynamicPayloadTypes::is_dynamic(128));
     }
 
     #[test]
@@ -4,7 +4,7 @@
 //! rtp://host:port[?options]
 //!
 //! Options:
-//! - codec: Output codec (pcm16, pcm24, mp2, opus, flac)
+//! - codec: Output codec (pcm16, pcm24, mp2, opus, flac, pcma)
 //! - bitrate: Output bitrate in kbps (for mp2/opus)
 //! - jitter: Jitter buffer depth in ms
 //! - channels: Number of channels (1 or 2)
@@ -164,6 +164,7 @@ fn parse_codec(name: &str) -> Result<PayloadCodec, String> {
         "mp2" | "mpeg2" | "mpa" => Ok(PayloadCodec::Mp2),
         "opus" => Ok(PayloadCodec::Opus),
         "flac" => Ok(PayloadCodec::Flac),
+        "pcma" | "g711a" | "alaw" => Ok(PayloadCodec::G711Alaw),
         _ => Err(format!("Unknown codec: {}", name)),
     }
 }
@@ -182,6 +183,7 @@ pub fn build_rtp_url(config: &RtpUrl) -> String {
             PayloadCodec::Mp2 => "mp2",
             PayloadCodec::Opus => "opus",
             PayloadCodec::Flac => "flac",
+            PayloadCodec::G711Alaw => "pcma",
             _ => "pcm16",
         };
         params.push(format!("codec={}", codec_name));
@@ -237,6 +239,13 @@ mod tests {
         assert_eq!(url.jitter_ms, 50);
     }
 
+    #[test]
+    fn test_parse_url_with_pcma() {
+        let url = parse_rtp_url("rtp://192.168.1.1:5004?codec=pcma").unwrap();
+        assert_eq!(url.codec, PayloadCodec::G711Alaw);
+        assert_eq!(build_rtp_url(&url), "rtp://192.168.1.1:5004?codec=pcma");
+    }
+
     #[test]
     fn test_parse_url_with_channels() {
         let url = parse_rtp_url("rtp://192.168.1.1:5004?channels=1").unwrap();
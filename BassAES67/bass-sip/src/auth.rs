@@ -0,0 +1,144 @@
+//! HTTP digest authentication for SIP (RFC 2617, RFC 3261 section 22).
+//!
+//! Answers the WWW-Authenticate (401) and Proxy-Authenticate (407)
+//! challenges of a registrar or proxy. MD5 only, with or without qop=auth.
+
+/// Parsed digest challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: Option<String>,
+    /// Whether the server offers qop=auth
+    pub qop_auth: bool,
+}
+
+impl Challenge {
+    /// Parse a WWW-Authenticate / Proxy-Authenticate header value
+    pub fn parse(value: &str) -> Option<Self> {
+        let rest = value.trim();
+        let (scheme, params) = rest.split_once(char::is_whitespace)?;
+        if !scheme.eq_ignore_ascii_case("Digest") {
+            return None;
+        }
+
+        let mut challenge = Challenge {
+            realm: String::new(),
+            nonce: String::new(),
+            opaque: None,
+            algorithm: None,
+            qop_auth: false,
+        };
+        for (name, value) in split_params(params) {
+            match name.to_ascii_lowercase().as_str() {
+                "realm" => challenge.realm = value,
+                "nonce" => challenge.nonce = value,
+                "opaque" => challenge.opaque = Some(value),
+                "algorithm" => challenge.algorithm = Some(value),
+                "qop" => challenge.qop_auth = value.split(',').any(|q| q.trim() == "auth"),
+                _ => {}
+            }
+        }
+
+        // Only MD5 is supported
+        match &challenge.algorithm {
+            Some(a) if !a.eq_ignore_ascii_case("MD5") => None,
+            _ if challenge.nonce.is_empty() => None,
+            _ => Some(challenge),
+        }
+    }
+
+    /// Authorization / Proxy-Authorization header value for a request
+    ///
+    /// `nc` counts the requests made with this nonce (from 1).
+    pub fn authorize(
+        &self,
+        method: &str,
+        uri: &str,
+        username: &str,
+        password: &str,
+        cnonce: &str,
+        nc: u32,
+    ) -> String {
+        let ha1 = md5_hex(&format!("{}:{}:{}", username, self.realm, password));
+        let ha2 = md5_hex(&format!("{}:{}", method, uri));
+
+        let mut value = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\"",
+            username, self.realm, self.nonce, uri
+        );
+        if self.qop_auth {
+            let nc = format!("{:08x}", nc);
+            let response = md5_hex(&format!("{}:{}:{}:{}:auth:{}", ha1, self.nonce, nc, cnonce, ha2));
+            value.push_str(&format!(
+                ", response=\"{}\", qop=auth, nc={}, cnonce=\"{}\"",
+                response, nc, cnonce
+            ));
+        } else {
+            let response = md5_hex(&format!("{}:{}:{}", ha1, self.nonce, ha2));
+            value.push_str(&format!(", response=\"{}\"", response));
+        }
+        value.push_str(", algorithm=MD5");
+        if let Some(opaque) = &self.opaque {
+            value.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        value
+    }
+}
+
+fn md5_hex(input: &str) -> String {
+    format!("{:x}", md5::compute(input.as_bytes()))
+}
+
+/// Split `name=value, name="quoted, value"` pairs
+fn split_params(params: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = params.trim();
+    while !rest.is_empty() {
+        let Some((name, after)) = rest.split_once('=') else { break };
+        let after = after.trim_start();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match after.find(',') {
+                Some(end) => (after[..end].trim(), &after[end..]),
+                None => (after.trim(), ""),
+            }
+        };
+        pairs.push((name.trim().to_string(), value.to_string()));
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc2617_example() {
+        let challenge = Challenge::parse(
+            "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", \
+             nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", \
+             opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"",
+        )
+        .unwrap();
+        assert!(challenge.qop_auth);
+
+        let value = challenge.authorize("GET", "/dir/index.html", "Mufasa", "Circle Of Life", "0a4f113b", 1);
+        assert!(value.contains("response=\"6629fae49393a05397450978507c4ef1\""));
+        assert!(value.contains("nc=00000001"));
+        assert!(value.contains("opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+    }
+
+    #[test]
+    fn test_rejects_other_schemes() {
+        assert!(Challenge::parse("Basic realm=\"x\"").is_none());
+        assert!(Challenge::parse("Digest realm=\"x\", nonce=\"n\", algorithm=SHA-256").is_none());
+        assert!(Challenge::parse("Digest realm=\"x\", nonce=\"n\"").is_some());
+    }
+}
@@ -0,0 +1,662 @@
+//! bass-sip: SIP calls for BASS, carried over bass-rtp codec links.
+//!
+//! A user agent registers with a PBX, places and answers calls and
+//! negotiates the codec with SDP. A connected call is a bass-rtp Input link:
+//! a BASS channel the application provides is sent to the other side, and
+//! what comes back plays through a BASS return stream.
+//!
+//! ## Modules
+//!
+//! - **message / sdp / auth**: SIP message, SDP and digest auth handling
+//! - **ua**: user agent (registration, transactions, dialogs)
+//! - **media**: bass-rtp link of a connected call
+
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use bass_rtp::ffi::*;
+use bass_rtp::rtp::PayloadCodec;
+use bass_rtp::{
+    BASS_RTP_CODEC_G711, BASS_RTP_CODEC_G711A, BASS_RTP_CODEC_G722, BASS_RTP_CODEC_MP2, BASS_RTP_CODEC_PCM16,
+    BASS_RTP_CODEC_PCM20, BASS_RTP_CODEC_PCM24,
+};
+
+pub mod auth;
+pub mod media;
+pub mod message;
+pub mod sdp;
+pub mod ua;
+
+use media::{CallMedia, MediaSettings};
+use ua::{UaConfig, UaEvent, UserAgent};
+
+// ============================================================================
+// Event Constants
+// ============================================================================
+
+/// Registration accepted (code = expiry in seconds)
+pub const BASS_SIP_EVENT_REGISTERED: u32 = 1;
+/// Registration refused (code = SIP status, 408 = no answer)
+pub const BASS_SIP_EVENT_REGISTER_FAILED: u32 = 2;
+/// Incoming call, answer with BASS_SIP_Answer or refuse with BASS_SIP_Hangup
+pub const BASS_SIP_EVENT_INCOMING: u32 = 3;
+/// Called side is alerting
+pub const BASS_SIP_EVENT_RINGING: u32 = 4;
+/// Call connected, return stream available
+pub const BASS_SIP_EVENT_CONNECTED: u32 = 5;
+/// Call on hold (by either side)
+pub const BASS_SIP_EVENT_HELD: u32 = 6;
+/// Call off hold
+pub const BASS_SIP_EVENT_RESUMED: u32 = 7;
+/// Call ended (code = SIP status of a failed call, 0 = hangup)
+pub const BASS_SIP_EVENT_ENDED: u32 = 8;
+
+// ============================================================================
+// Call State Constants
+// ============================================================================
+
+/// Outgoing call, no answer yet
+pub const BASS_SIP_STATE_CALLING: u32 = 1;
+/// Outgoing call, called side alerting
+pub const BASS_SIP_STATE_RINGING: u32 = 2;
+/// Incoming call, not answered yet
+pub const BASS_SIP_STATE_INCOMING: u32 = 3;
+/// Connected
+pub const BASS_SIP_STATE_CONNECTED: u32 = 4;
+/// Connected, on hold
+pub const BASS_SIP_STATE_HELD: u32 = 5;
+
+/// Event callback: (event, call, code, user)
+pub type SipEventCallback = extern "system" fn(event: u32, call: u32, code: u32, user: *mut c_void);
+
+// ============================================================================
+// DLL Entry Point (Windows)
+// ============================================================================
+
+#[cfg(windows)]
+#[no_mangle]
+pub extern "system" fn DllMain(
+    _hinst: *mut c_void,
+    _reason: u32,
+    _reserved: *mut c_void,
+) -> i32 {
+    1
+}
+
+// ============================================================================
+// State
+// ============================================================================
+
+struct CallEntry {
+    /// User agent (address of its SipUa)
+    ua: usize,
+    agent: Weak<UserAgent>,
+    send_channel: HSTREAM,
+    state: u32,
+    codec: Option<PayloadCodec>,
+    media: Option<CallMedia>,
+}
+
+lazy_static! {
+    /// Calls of all user agents, by call handle
+    static ref CALLS: Mutex<HashMap<u32, CallEntry>> = Mutex::new(HashMap::new());
+}
+
+/// Event callback and its user pointer
+type CallbackSlot = Arc<Mutex<Option<(SipEventCallback, usize)>>>;
+
+/// User agent behind a BASS_SIP handle
+struct SipUa {
+    agent: Option<Arc<UserAgent>>,
+    callback: CallbackSlot,
+    dispatcher: Option<JoinHandle<()>>,
+}
+
+/// Run call media and forward user agent events to the callback
+fn dispatch_events(
+    ua: usize,
+    agent: Weak<UserAgent>,
+    events: Receiver<UaEvent>,
+    callback: CallbackSlot,
+    settings: MediaSettings,
+) {
+    for event in events {
+        let (kind, call, code) = match event {
+            UaEvent::Registered { expires } => (BASS_SIP_EVENT_REGISTERED, 0, expires),
+            UaEvent::RegistrationFailed { code } => (BASS_SIP_EVENT_REGISTER_FAILED, 0, code as u32),
+            UaEvent::Incoming { call, .. } => {
+                CALLS.lock().insert(
+                    call,
+                    CallEntry {
+                        ua,
+                        agent: agent.clone(),
+                        send_channel: 0,
+                        state: BASS_SIP_STATE_INCOMING,
+                        codec: None,
+                        media: None,
+                    },
+                );
+                (BASS_SIP_EVENT_INCOMING, call, 0)
+            }
+            UaEvent::Ringing { call } => {
+                if let Some(entry) = CALLS.lock().get_mut(&call) {
+                    entry.state = BASS_SIP_STATE_RINGING;
+                }
+                (BASS_SIP_EVENT_RINGING, call, 0)
+            }
+            UaEvent::Established { call, media } => {
+                let failed = {
+                    let mut calls = CALLS.lock();
+                    match calls.get_mut(&call) {
+                        Some(entry) => {
+                            entry.state = BASS_SIP_STATE_CONNECTED;
+                            entry.codec = Some(media.codec);
+                            match CallMedia::new(entry.send_channel, &media, &settings) {
+                                Ok(m) => {
+                                    entry.media = Some(m);
+                                    false
+                                }
+                                Err(_) => true,
+                            }
+                        }
+                        None => false,
+                    }
+                };
+                if failed {
+                    // No media, no call
+                    if let Some(agent) = agent.upgrade() {
+                        let _ = agent.hangup(call);
+                    }
+                    continue;
+                }
+                (BASS_SIP_EVENT_CONNECTED, call, 0)
+            }
+            UaEvent::MediaChanged { call, media } | UaEvent::Resumed { call, media } => {
+                let resumed = if let Some(entry) = CALLS.lock().get_mut(&call) {
+                    let resumed = entry.state == BASS_SIP_STATE_HELD;
+                    entry.state = BASS_SIP_STATE_CONNECTED;
+                    entry.codec = Some(media.codec);
+                    if let Some(m) = entry.media.as_mut() {
+                        let _ = m.resume(&media);
+                    }
+                    resumed
+                } else {
+                    false
+                };
+                if !resumed {
+                    continue;
+                }
+                (BASS_SIP_EVENT_RESUMED, call, 0)
+            }
+            UaEvent::Held { call } => {
+                if let Some(entry) = CALLS.lock().get_mut(&call) {
+                    entry.state = BASS_SIP_STATE_HELD;
+                    if let Some(m) = entry.media.as_mut() {
+                        m.stop();
+                    }
+                }
+                (BASS_SIP_EVENT_HELD, call, 0)
+            }
+            UaEvent::Ended { call, code } => {
+                // Drop the media outside the lock (joins the link threads)
+                let entry = CALLS.lock().remove(&call);
+                drop(entry);
+                (BASS_SIP_EVENT_ENDED, call, code as u32)
+            }
+        };
+
+        let slot = *callback.lock();
+        if let Some((cb, user)) = slot {
+            cb(kind, call, code, user as *mut c_void);
+        }
+    }
+}
+
+/// Read an optional C string (NULL = "")
+unsafe fn c_string(s: *const c_char) -> Result<String, i32> {
+    if s.is_null() {
+        return Ok(String::new());
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| BASS_ERROR_ILLPARAM)
+}
+
+fn codec_from_ffi(codec: u8) -> Option<PayloadCodec> {
+    match codec {
+        BASS_RTP_CODEC_PCM16 => Some(PayloadCodec::Pcm16),
+        BASS_RTP_CODEC_PCM20 => Some(PayloadCodec::Pcm20),
+        BASS_RTP_CODEC_PCM24 => Some(PayloadCodec::Pcm24),
+        BASS_RTP_CODEC_MP2 => Some(PayloadCodec::Mp2),
+        BASS_RTP_CODEC_G711 => Some(PayloadCodec::G711Ulaw),
+        BASS_RTP_CODEC_G722 => Some(PayloadCodec::G722),
+        BASS_RTP_CODEC_G711A => Some(PayloadCodec::G711Alaw),
+        _ => None,
+    }
+}
+
+fn codec_to_ffi(codec: PayloadCodec) -> i32 {
+    match codec {
+        PayloadCodec::Pcm16 => BASS_RTP_CODEC_PCM16 as i32,
+        PayloadCodec::Pcm20 => BASS_RTP_CODEC_PCM20 as i32,
+        PayloadCodec::Pcm24 => BASS_RTP_CODEC_PCM24 as i32,
+        PayloadCodec::Mp2 => BASS_RTP_CODEC_MP2 as i32,
+        PayloadCodec::G711Ulaw => BASS_RTP_CODEC_G711 as i32,
+        PayloadCodec::G722 => BASS_RTP_CODEC_G722 as i32,
+        PayloadCodec::G711Alaw => BASS_RTP_CODEC_G711A as i32,
+        _ => -1,
+    }
+}
+
+/// User agent of a call (if the call and its user agent still exist)
+fn call_agent(call: u32) -> Option<Arc<UserAgent>> {
+    CALLS.lock().get(&call).and_then(|e| e.agent.upgrade())
+}
+
+// ============================================================================
+// FFI API
+// ============================================================================
+
+/// User agent configuration
+#[repr(C)]
+pub struct SipConfigFFI {
+    /// Network interface IP address (4 bytes, 0.0.0.0 = any)
+    pub interface_addr: [u8; 4],
+    /// Local SIP port (0 = auto-assign)
+    pub sip_port: u16,
+    /// PBX (registrar and outbound proxy) as "host[:port]", NULL = call peers directly
+    pub server: *const c_char,
+    /// User name (address of record and authentication)
+    pub username: *const c_char,
+    /// Password for digest authentication (NULL = none)
+    pub password: *const c_char,
+    /// SIP domain (NULL = server host)
+    pub domain: *const c_char,
+    /// Display name (NULL = none)
+    pub display_name: *const c_char,
+    /// Registration expiry in seconds (0 = default 300)
+    pub expires: u32,
+    /// Codecs to offer and accept (BASS_RTP_CODEC_*), in order of preference
+    pub codecs: *const u8,
+    /// Number of entries in `codecs` (0 = G.722, G.711 u-Law)
+    pub codec_count: u32,
+    /// Channels for PCM and MP2 calls (1 or 2)
+    pub channels: u16,
+    /// Packet time in milliseconds (0 = default 20)
+    pub ptime: u32,
+    /// Return audio buffer in milliseconds (0 = default 100)
+    pub return_buffer_ms: u32,
+    /// Create return streams with BASS_STREAM_DECODE flag (for mixer compatibility)
+    pub decode_stream: u8,
+}
+
+/// Create a SIP user agent
+///
+/// # Arguments
+/// * `config` - User agent configuration
+///
+/// # Returns
+/// Opaque handle to the user agent, or null on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_SIP_Create(config: *const SipConfigFFI) -> *mut c_void {
+    if config.is_null() {
+        set_error(BASS_ERROR_MEM);
+        return std::ptr::null_mut();
+    }
+    let c = &*config;
+
+    let strings = (|| {
+        Ok::<_, i32>((
+            c_string(c.server)?,
+            c_string(c.username)?,
+            c_string(c.password)?,
+            c_string(c.domain)?,
+            c_string(c.display_name)?,
+        ))
+    })();
+    let (server, username, password, domain, display_name) = match strings {
+        Ok(s) => s,
+        Err(e) => {
+            set_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let server = if server.is_empty() {
+        None
+    } else {
+        let with_port = if server.contains(':') { server.clone() } else { format!("{}:5060", server) };
+        match with_port.to_socket_addrs().ok().and_then(|mut a| a.find(SocketAddr::is_ipv4)) {
+            Some(addr) => Some(addr),
+            None => {
+                set_error(BASS_ERROR_ILLPARAM);
+                return std::ptr::null_mut();
+            }
+        }
+    };
+
+    let mut codecs = Vec::new();
+    if !c.codecs.is_null() {
+        for &codec in std::slice::from_raw_parts(c.codecs, c.codec_count as usize) {
+            match codec_from_ffi(codec) {
+                Some(codec) => codecs.push(codec),
+                None => {
+                    set_error(BASS_ERROR_ILLPARAM);
+                    return std::ptr::null_mut();
+                }
+            }
+        }
+    }
+
+    let interface = Ipv4Addr::from(c.interface_addr);
+    let defaults = UaConfig::default();
+    let ua_config = UaConfig {
+        interface,
+        port: c.sip_port,
+        server,
+        domain,
+        username: if username.is_empty() { defaults.username } else { username },
+        password,
+        display_name,
+        expires: if c.expires > 0 { c.expires } else { defaults.expires },
+        codecs: if codecs.is_empty() { defaults.codecs } else { codecs },
+        channels: c.channels.clamp(1, 2),
+        ptime: if c.ptime > 0 { c.ptime } else { defaults.ptime },
+    };
+    let settings = MediaSettings {
+        interface,
+        return_buffer_ms: c.return_buffer_ms,
+        decode_stream: c.decode_stream != 0,
+    };
+
+    let (agent, events) = match UserAgent::new(ua_config) {
+        Ok(created) => created,
+        Err(_) => {
+            set_error(BASS_ERROR_CREATE);
+            return std::ptr::null_mut();
+        }
+    };
+    let agent = Arc::new(agent);
+
+    let mut sip = Box::new(SipUa {
+        agent: Some(agent.clone()),
+        callback: Arc::new(Mutex::new(None)),
+        dispatcher: None,
+    });
+    let ua = sip.as_ref() as *const SipUa as usize;
+    let weak = Arc::downgrade(&agent);
+    let callback = sip.callback.clone();
+    sip.dispatcher = Some(thread::spawn(move || dispatch_events(ua, weak, events, callback, settings)));
+
+    Box::into_raw(sip) as *mut c_void
+}
+
+/// Set the event callback (BASS_SIP_EVENT_*), called from an internal thread
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_SIP_Create
+/// * `callback` - Callback, or NULL to remove it
+/// * `user` - User pointer passed to the callback
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_SIP_SetEventCallback(
+    handle: *mut c_void,
+    callback: Option<SipEventCallback>,
+    user: *mut c_void,
+) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+    let sip = &*(handle as *const SipUa);
+    *sip.callback.lock() = callback.map(|cb| (cb, user as usize));
+    1
+}
+
+/// Register with the server and keep the registration refreshed
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_SIP_Create
+///
+/// # Returns
+/// 1 on success (result arrives as an event), 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_SIP_Register(handle: *mut c_void) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+    let sip = &*(handle as *const SipUa);
+    match sip.agent.as_ref().map(|a| a.register()) {
+        Some(Ok(())) => 1,
+        _ => {
+            set_error(BASS_ERROR_NOTAVAIL);
+            0
+        }
+    }
+}
+
+/// Place a call
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_SIP_Create
+/// * `uri` - Who to call ("sip:user@host", "user@host" or a user at the domain)
+/// * `send_channel` - BASS channel to send to the called side
+///
+/// # Returns
+/// Call handle, or 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_SIP_Call(handle: *mut c_void, uri: *const c_char, send_channel: HSTREAM) -> u32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+    let sip = &*(handle as *const SipUa);
+    let target = match c_string(uri) {
+        Ok(t) if !t.is_empty() => t,
+        _ => {
+            set_error(BASS_ERROR_ILLPARAM);
+            return 0;
+        }
+    };
+    let Some(agent) = sip.agent.as_ref() else {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    };
+
+    // Hold the lock so the call is known before its first event
+    let mut calls = CALLS.lock();
+    match agent.call(&target) {
+        Ok(call) => {
+            calls.insert(
+                call,
+                CallEntry {
+                    ua: handle as usize,
+                    agent: Arc::downgrade(agent),
+                    send_channel,
+                    state: BASS_SIP_STATE_CALLING,
+                    codec: None,
+                    media: None,
+                },
+            );
+            call
+        }
+        Err(_) => {
+            set_error(BASS_ERROR_CREATE);
+            0
+        }
+    }
+}
+
+/// Answer an incoming call
+///
+/// # Arguments
+/// * `call` - Call handle from the BASS_SIP_EVENT_INCOMING event
+/// * `send_channel` - BASS channel to send to the caller
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub extern "system" fn BASS_SIP_Answer(call: u32, send_channel: HSTREAM) -> i32 {
+    let agent = {
+        let mut calls = CALLS.lock();
+        match calls.get_mut(&call) {
+            Some(entry) if entry.state == BASS_SIP_STATE_INCOMING => {
+                entry.send_channel = send_channel;
+                entry.agent.upgrade()
+            }
+            _ => None,
+        }
+    };
+    match agent.map(|a| a.answer(call)) {
+        Some(Ok(())) => 1,
+        Some(Err(_)) => {
+            set_error(BASS_ERROR_NOTAVAIL);
+            0
+        }
+        None => {
+            set_error(BASS_ERROR_HANDLE);
+            0
+        }
+    }
+}
+
+/// End a call (cancels a ringing call, declines an incoming one)
+///
+/// # Arguments
+/// * `call` - Call handle
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub extern "system" fn BASS_SIP_Hangup(call: u32) -> i32 {
+    match call_agent(call).map(|a| a.hangup(call)) {
+        Some(Ok(())) => 1,
+        _ => {
+            set_error(BASS_ERROR_HANDLE);
+            0
+        }
+    }
+}
+
+/// Put a connected call on hold or take it off hold
+///
+/// # Arguments
+/// * `call` - Call handle
+/// * `hold` - 1 = hold, 0 = resume
+///
+/// # Returns
+/// 1 on success (result arrives as an event), 0 on failure
+#[no_mangle]
+pub extern "system" fn BASS_SIP_Hold(call: u32, hold: i32) -> i32 {
+    match call_agent(call).map(|a| a.hold(call, hold != 0)) {
+        Some(Ok(())) => 1,
+        Some(Err(_)) => {
+            set_error(BASS_ERROR_NOTAVAIL);
+            0
+        }
+        None => {
+            set_error(BASS_ERROR_HANDLE);
+            0
+        }
+    }
+}
+
+/// Get the return stream of a connected call (audio from the other side)
+///
+/// # Arguments
+/// * `call` - Call handle
+///
+/// # Returns
+/// BASS stream handle, or 0 if not connected
+#[no_mangle]
+pub extern "system" fn BASS_SIP_GetReturnStream(call: u32) -> HSTREAM {
+    match CALLS.lock().get(&call) {
+        Some(entry) => entry.media.as_ref().map(|m| m.return_stream()).unwrap_or(0),
+        None => {
+            set_error(BASS_ERROR_HANDLE);
+            0
+        }
+    }
+}
+
+/// Get the state of a call
+///
+/// # Arguments
+/// * `call` - Call handle
+///
+/// # Returns
+/// BASS_SIP_STATE_*, or 0 if the call doesn't exist (anymore)
+#[no_mangle]
+pub extern "system" fn BASS_SIP_GetCallState(call: u32) -> u32 {
+    match CALLS.lock().get(&call) {
+        Some(entry) => entry.state,
+        None => {
+            set_error(BASS_ERROR_HANDLE);
+            0
+        }
+    }
+}
+
+/// Get the codec a call agreed on
+///
+/// # Arguments
+/// * `call` - Call handle
+///
+/// # Returns
+/// BASS_RTP_CODEC_*, or -1 if not connected yet
+#[no_mangle]
+pub extern "system" fn BASS_SIP_GetCallCodec(call: u32) -> i32 {
+    match CALLS.lock().get(&call) {
+        Some(entry) => entry.codec.map(codec_to_ffi).unwrap_or(-1),
+        None => {
+            set_error(BASS_ERROR_HANDLE);
+            -1
+        }
+    }
+}
+
+/// Free a user agent: hangs up its calls and unregisters
+///
+/// # Arguments
+/// * `handle` - Handle from BASS_SIP_Create
+///
+/// # Returns
+/// 1 on success, 0 on failure
+#[no_mangle]
+pub unsafe extern "system" fn BASS_SIP_Free(handle: *mut c_void) -> i32 {
+    if handle.is_null() {
+        set_error(BASS_ERROR_HANDLE);
+        return 0;
+    }
+    let mut sip = Box::from_raw(handle as *mut SipUa);
+    *sip.callback.lock() = None;
+
+    // Stop the media, then let the user agent hang up and unregister
+    let entries: Vec<CallEntry> = {
+        let mut calls = CALLS.lock();
+        let ids: Vec<u32> = calls.iter().filter(|(_, e)| e.ua == handle as usize).map(|(&id, _)| id).collect();
+        ids.iter().filter_map(|id| calls.remove(id)).collect()
+    };
+    drop(entries);
+    drop(sip.agent.take());
+
+    // The event channel closes with the user agent
+    if let Some(dispatcher) = sip.dispatcher.take() {
+        let _ = dispatcher.join();
+    }
+    1
+}
@@ -0,0 +1,131 @@
+//! Call media: a bass-rtp Input link driven by the negotiated SDP.
+//!
+//! The send channel is read and encoded towards the other side; what comes
+//! back is played through a BASS return stream. The return stream lives as
+//! long as the call, across hold and media changes, so the application can
+//! keep it in a mixer.
+
+use std::ffi::c_void;
+
+use bass_rtp::clock_bindings::ClockMode;
+use bass_rtp::ffi::*;
+use bass_rtp::input::{input_return_stream_proc, BufferMode, RtpInput, RtpInputConfig};
+use bass_rtp::rtp::{DynamicPayloadTypes, PayloadCodec};
+
+use crate::ua::NegotiatedMedia;
+
+/// Media settings shared by all calls of a user agent
+#[derive(Clone, Copy, Debug)]
+pub struct MediaSettings {
+    /// Interface to bind RTP to
+    pub interface: std::net::Ipv4Addr,
+    /// Return audio buffer in milliseconds
+    pub return_buffer_ms: u32,
+    /// Create the return stream with BASS_STREAM_DECODE (for mixers)
+    pub decode_stream: bool,
+}
+
+/// RTP link of a connected call
+pub struct CallMedia {
+    input: Box<RtpInput>,
+    return_handle: HSTREAM,
+}
+
+/// bass-rtp stream configuration for negotiated media
+fn input_config(media: &NegotiatedMedia, settings: &MediaSettings) -> RtpInputConfig {
+    // bass-rtp runs G.711 and G.722 from 48 kHz stereo
+    let channels = match media.codec {
+        PayloadCodec::G711Ulaw | PayloadCodec::G711Alaw | PayloadCodec::G722 => 2,
+        _ => media.channels.max(1),
+    };
+    RtpInputConfig {
+        remote_addr: *media.remote.ip(),
+        remote_port: media.remote.port(),
+        local_port: media.local_port,
+        interface_addr: settings.interface,
+        sample_rate: 48000,
+        channels,
+        send_codec: media.codec,
+        send_bitrate: 256,
+        frame_duration_ms: media.ptime.max(1),
+        opus_fec_loss: 0,
+        payload_types: DynamicPayloadTypes::default(),
+        clock_mode: ClockMode::System,
+        ptp_domain: 0,
+        return_buffer_mode: BufferMode::Simple {
+            buffer_ms: if settings.return_buffer_ms > 0 { settings.return_buffer_ms } else { 100 },
+        },
+        decode_stream: settings.decode_stream,
+    }
+}
+
+impl CallMedia {
+    /// Create the link and its return stream, and start sending
+    ///
+    /// # Arguments
+    /// * `send_channel` - BASS channel to send to the other side
+    /// * `media` - Negotiated media of the call
+    /// * `settings` - User agent media settings
+    pub fn new(send_channel: HSTREAM, media: &NegotiatedMedia, settings: &MediaSettings) -> Result<Self, String> {
+        let config = input_config(media, settings);
+        let mut input = Box::new(RtpInput::new(send_channel, config)?);
+
+        let flags = if settings.decode_stream {
+            BASS_SAMPLE_FLOAT | BASS_STREAM_DECODE
+        } else {
+            BASS_SAMPLE_FLOAT
+        };
+        // The box keeps the stream's address stable for the callback
+        let return_handle = unsafe {
+            BASS_StreamCreate(
+                48000,
+                input.config.channels as u32,
+                flags,
+                Some(input_return_stream_proc),
+                input.as_mut() as *mut RtpInput as *mut c_void,
+            )
+        };
+        if return_handle == 0 {
+            return Err(format!("BASS_StreamCreate failed ({})", unsafe { BASS_ErrorGetCode() }));
+        }
+        input.return_handle = return_handle;
+
+        let mut call_media = Self { input, return_handle };
+        call_media.input.start()?;
+        Ok(call_media)
+    }
+
+    /// BASS stream playing what the other side sends
+    pub fn return_stream(&self) -> HSTREAM {
+        self.return_handle
+    }
+
+    /// Codec being sent
+    pub fn codec(&self) -> PayloadCodec {
+        self.input.config.send_codec
+    }
+
+    /// Stop sending and receiving (call on hold)
+    pub fn stop(&mut self) {
+        self.input.stop();
+    }
+
+    /// (Re)start the link with the current media of the call
+    pub fn resume(&mut self, media: &NegotiatedMedia) -> Result<(), String> {
+        self.input.stop();
+        self.input.config.remote_addr = *media.remote.ip();
+        self.input.config.remote_port = media.remote.port();
+        self.input.config.send_codec = media.codec;
+        self.input.config.frame_duration_ms = media.ptime.max(1);
+        self.input.start()
+    }
+}
+
+impl Drop for CallMedia {
+    fn drop(&mut self) {
+        self.input.stop();
+        unsafe {
+            BASS_StreamFree(self.return_handle);
+        }
+    }
+}
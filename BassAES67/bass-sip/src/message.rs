@@ -0,0 +1,370 @@
+//! SIP message parsing and building (RFC 3261).
+//!
+//! Messages are kept as a start line, an ordered header list and a body.
+//! Header lookups are case-insensitive and understand the compact header
+//! forms (`v`, `f`, `t`, `i`, `m`, `l`, `c`).
+
+use std::fmt::Write;
+
+/// First line of a SIP message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartLine {
+    /// Request: method and Request-URI
+    Request { method: String, uri: String },
+    /// Response: status code and reason phrase
+    Response { code: u16, reason: String },
+}
+
+/// A SIP request or response
+#[derive(Debug, Clone)]
+pub struct SipMessage {
+    pub start: StartLine,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Full header name for a compact form
+fn expand_name(name: &str) -> &str {
+    match name {
+        "v" | "V" => "Via",
+        "f" | "F" => "From",
+        "t" | "T" => "To",
+        "i" | "I" => "Call-ID",
+        "m" | "M" => "Contact",
+        "l" | "L" => "Content-Length",
+        "c" | "C" => "Content-Type",
+        "k" | "K" => "Supported",
+        _ => name,
+    }
+}
+
+impl SipMessage {
+    /// New request without headers
+    pub fn request(method: &str, uri: &str) -> Self {
+        Self {
+            start: StartLine::Request {
+                method: method.to_string(),
+                uri: uri.to_string(),
+            },
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// New response without headers
+    pub fn response(code: u16, reason: &str) -> Self {
+        Self {
+            start: StartLine::Response {
+                code,
+                reason: reason.to_string(),
+            },
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Response to `request`, with its Via, From, To, Call-ID and CSeq
+    pub fn response_to(request: &SipMessage, code: u16, reason: &str) -> Self {
+        let mut response = Self::response(code, reason);
+        for (name, value) in &request.headers {
+            if ["Via", "From", "To", "Call-ID", "CSeq"]
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(name))
+            {
+                response.headers.push((name.clone(), value.clone()));
+            }
+        }
+        response
+    }
+
+    /// Parse a datagram
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let split = data
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or("No end of headers")?;
+        let head = std::str::from_utf8(&data[..split]).map_err(|_| "Headers are not UTF-8")?;
+        let mut body = data[split + 4..].to_vec();
+
+        let mut lines = head.split("\r\n");
+        let first = lines.next().ok_or("Empty message")?;
+        let start = if let Some(rest) = first.strip_prefix("SIP/2.0 ") {
+            let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+            StartLine::Response {
+                code: code.parse().map_err(|_| "Bad status code")?,
+                reason: reason.to_string(),
+            }
+        } else {
+            let mut parts = first.split(' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(method), Some(uri), Some("SIP/2.0")) => StartLine::Request {
+                    method: method.to_string(),
+                    uri: uri.to_string(),
+                },
+                _ => return Err("Bad start line".to_string()),
+            }
+        };
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines {
+            if line.starts_with(' ') || line.starts_with('\t') {
+                // Folded continuation of the previous header
+                if let Some(last) = headers.last_mut() {
+                    last.1.push(' ');
+                    last.1.push_str(line.trim());
+                }
+                continue;
+            }
+            let (name, value) = line.split_once(':').ok_or("Bad header line")?;
+            headers.push((expand_name(name.trim()).to_string(), value.trim().to_string()));
+        }
+
+        let message = Self { start, headers, body: Vec::new() };
+        if let Some(len) = message.header("Content-Length").and_then(|l| l.parse::<usize>().ok()) {
+            body.truncate(len);
+        }
+        Ok(Self { body, ..message })
+    }
+
+    /// Serialize, with a Content-Length matching the body
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut text = String::new();
+        match &self.start {
+            StartLine::Request { method, uri } => {
+                let _ = write!(text, "{} {} SIP/2.0\r\n", method, uri);
+            }
+            StartLine::Response { code, reason } => {
+                let _ = write!(text, "SIP/2.0 {} {}\r\n", code, reason);
+            }
+        }
+        for (name, value) in &self.headers {
+            if !name.eq_ignore_ascii_case("Content-Length") {
+                let _ = write!(text, "{}: {}\r\n", name, value);
+            }
+        }
+        let _ = write!(text, "Content-Length: {}\r\n\r\n", self.body.len());
+
+        let mut bytes = text.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// First value of header `name`
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.header_values(name).next()
+    }
+
+    /// All values of header `name`, including comma-separated ones
+    pub fn header_values<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> + 'a {
+        let list = is_list_header(name);
+        let name = name.to_string();
+        self.headers
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(&name))
+            .flat_map(move |(_, v)| {
+                let values: Vec<&str> = if list { split_list(v) } else { vec![v.as_str()] };
+                values
+            })
+    }
+
+    /// Append a header
+    pub fn add_header(&mut self, name: &str, value: impl Into<String>) {
+        self.headers.push((name.to_string(), value.into()));
+    }
+
+    /// Replace all values of a header with one value
+    pub fn set_header(&mut self, name: &str, value: impl Into<String>) {
+        self.remove_header(name);
+        self.add_header(name, value);
+    }
+
+    /// Remove all values of a header
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    }
+
+    /// Request method, or None for responses
+    pub fn method(&self) -> Option<&str> {
+        match &self.start {
+            StartLine::Request { method, .. } => Some(method),
+            StartLine::Response { .. } => None,
+        }
+    }
+
+    /// Status code, or None for requests
+    pub fn status(&self) -> Option<u16> {
+        match &self.start {
+            StartLine::Request { .. } => None,
+            StartLine::Response { code, .. } => Some(*code),
+        }
+    }
+
+    /// Call-ID header
+    pub fn call_id(&self) -> Option<&str> {
+        self.header("Call-ID")
+    }
+
+    /// CSeq number and method
+    pub fn cseq(&self) -> Option<(u32, &str)> {
+        let (number, method) = self.header("CSeq")?.split_once(' ')?;
+        Some((number.trim().parse().ok()?, method.trim()))
+    }
+
+    /// Branch parameter of the top Via
+    pub fn branch(&self) -> Option<&str> {
+        param(self.header("Via")?, "branch")
+    }
+
+    /// Tag parameter of the From header
+    pub fn from_tag(&self) -> Option<&str> {
+        param(self.header("From")?, "tag")
+    }
+
+    /// Tag parameter of the To header
+    pub fn to_tag(&self) -> Option<&str> {
+        param(self.header("To")?, "tag")
+    }
+}
+
+/// Headers whose values may be comma-separated lists
+fn is_list_header(name: &str) -> bool {
+    ["Via", "Contact", "Route", "Record-Route", "Allow", "Supported"]
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case(name))
+}
+
+/// Split a header value at commas outside quotes and angle brackets
+fn split_list(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut angle = false;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                parts.push(value[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(value[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Value of parameter `name` in a header value (after the URI)
+pub fn param<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    // Parameters of a name-addr follow the closing '>'
+    let params = match value.rfind('>') {
+        Some(end) => &value[end + 1..],
+        None => value,
+    };
+    params.split(';').skip(1).find_map(|p| {
+        let (key, val) = p.split_once('=').unwrap_or((p, ""));
+        if key.trim().eq_ignore_ascii_case(name) {
+            Some(val.trim().trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+/// URI of a name-addr or addr-spec header value
+pub fn uri_of(value: &str) -> &str {
+    match (value.find('<'), value.find('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value.split(';').next().unwrap_or(value).trim(),
+    }
+}
+
+/// Host and port of a SIP URI (port None if not given)
+pub fn uri_host_port(uri: &str) -> Option<(&str, Option<u16>)> {
+    let rest = uri
+        .strip_prefix("sip:")
+        .or_else(|| uri.strip_prefix("sips:"))
+        .unwrap_or(uri);
+    let rest = rest.rsplit_once('@').map(|(_, h)| h).unwrap_or(rest);
+    let host_port = rest.split([';', '?', '>']).next()?;
+    match host_port.rsplit_once(':') {
+        Some((host, port)) => Some((host, Some(port.parse().ok()?))),
+        None if !host_port.is_empty() => Some((host_port, None)),
+        None => None,
+    }
+}
+
+/// Reason phrase for the status codes this user agent sends
+pub fn reason_phrase(code: u16) -> &'static str {
+    match code {
+        100 => "Trying",
+        180 => "Ringing",
+        200 => "OK",
+        400 => "Bad Request",
+        481 => "Call/Transaction Does Not Exist",
+        486 => "Busy Here",
+        487 => "Request Terminated",
+        488 => "Not Acceptable Here",
+        491 => "Request Pending",
+        500 => "Server Internal Error",
+        501 => "Not Implemented",
+        603 => "Decline",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "INVITE sip:bob@192.168.1.20 SIP/2.0\r\n\
+        v: SIP/2.0/UDP 192.168.1.10:5060;branch=z9hG4bK776asdhds;rport\r\n\
+        Max-Forwards: 70\r\n\
+        To: Bob <sip:bob@192.168.1.20>\r\n\
+        From: \"Alice, Studio\" <sip:alice@192.168.1.10>;tag=1928301774\r\n\
+        Call-ID: a84b4c76e66710\r\n\
+        CSeq: 314159 INVITE\r\n\
+        Contact: <sip:alice@192.168.1.10:5060>\r\n\
+        Record-Route: <sip:p1.example.com;lr>, <sip:p2.example.com;lr>\r\n\
+        Content-Type: application/sdp\r\n\
+        Content-Length: 4\r\n\r\nv=0\r\ntrailing";
+
+    #[test]
+    fn test_parse_request() {
+        let msg = SipMessage::parse(INVITE.as_bytes()).unwrap();
+        assert_eq!(msg.method(), Some("INVITE"));
+        assert_eq!(msg.branch(), Some("z9hG4bK776asdhds"));
+        assert_eq!(msg.from_tag(), Some("1928301774"));
+        assert_eq!(msg.to_tag(), None);
+        assert_eq!(msg.cseq(), Some((314159, "INVITE")));
+        assert_eq!(msg.call_id(), Some("a84b4c76e66710"));
+        assert_eq!(msg.body, b"v=0\r");
+
+        let routes: Vec<&str> = msg.header_values("Record-Route").collect();
+        assert_eq!(routes, ["<sip:p1.example.com;lr>", "<sip:p2.example.com;lr>"]);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let request = SipMessage::parse(INVITE.as_bytes()).unwrap();
+        let mut response = SipMessage::response_to(&request, 180, "Ringing");
+        response.set_header("To", format!("{};tag=abc", request.header("To").unwrap()));
+
+        let parsed = SipMessage::parse(&response.to_bytes()).unwrap();
+        assert_eq!(parsed.status(), Some(180));
+        assert_eq!(parsed.to_tag(), Some("abc"));
+        assert_eq!(parsed.branch(), request.branch());
+        assert_eq!(parsed.cseq(), Some((314159, "INVITE")));
+        assert!(parsed.body.is_empty());
+    }
+
+    #[test]
+    fn test_uri_helpers() {
+        assert_eq!(uri_of("\"Bob\" <sip:bob@host:5070;transport=udp>;tag=x"), "sip:bob@host:5070;transport=udp");
+        assert_eq!(uri_host_port("sip:bob@host:5070;transport=udp"), Some(("host", Some(5070))));
+        assert_eq!(uri_host_port("sip:10.0.0.1"), Some(("10.0.0.1", None)));
+        assert_eq!(param("<sip:a@b>;expires=60", "expires"), Some("60"));
+    }
+}
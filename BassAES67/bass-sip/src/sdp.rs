@@ -0,0 +1,319 @@
+//! SDP offer/answer (RFC 3264) for single-stream audio calls.
+//!
+//! Only the first audio media line is used. Codecs map onto the bass-rtp
+//! payload codecs. Static payload types are used where one exists. PCM links
+//! use the payload types bass-rtp sends (the Z/IP ONE numbering), so an offer
+//! with L16 on another payload type is not accepted.
+
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+
+use bass_rtp::rtp::PayloadCodec;
+
+/// Media direction attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    SendRecv,
+    SendOnly,
+    RecvOnly,
+    Inactive,
+}
+
+impl Direction {
+    fn attribute(self) -> &'static str {
+        match self {
+            Direction::SendRecv => "sendrecv",
+            Direction::SendOnly => "sendonly",
+            Direction::RecvOnly => "recvonly",
+            Direction::Inactive => "inactive",
+        }
+    }
+
+    /// Direction to answer an offer with, for a side that wants to send
+    /// and receive
+    pub fn answer(self) -> Self {
+        match self {
+            Direction::SendRecv => Direction::SendRecv,
+            Direction::SendOnly => Direction::RecvOnly,
+            Direction::RecvOnly => Direction::SendOnly,
+            Direction::Inactive => Direction::Inactive,
+        }
+    }
+
+    /// Whether this side of the call is on hold (the other side doesn't send)
+    pub fn is_hold(self) -> bool {
+        matches!(self, Direction::SendOnly | Direction::Inactive)
+    }
+}
+
+/// One rtpmap entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpCodec {
+    pub payload_type: u8,
+    pub name: String,
+    pub clock_rate: u32,
+    pub channels: u16,
+}
+
+/// Audio media description of a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaDescription {
+    pub addr: Ipv4Addr,
+    pub port: u16,
+    pub codecs: Vec<SdpCodec>,
+    pub direction: Direction,
+    pub ptime: Option<u32>,
+}
+
+/// Codec as it appears in SDP (static payload types, RFC 3551)
+pub fn sdp_codec(codec: PayloadCodec, channels: u16) -> Option<SdpCodec> {
+    let (name, clock_rate, channels) = match codec {
+        PayloadCodec::G711Ulaw => ("PCMU", 8000, 1),
+        PayloadCodec::G711Alaw => ("PCMA", 8000, 1),
+        // G.722 is signalled at 8000 Hz for historical reasons (RFC 3551)
+        PayloadCodec::G722 => ("G722", 8000, 1),
+        PayloadCodec::Pcm16 => ("L16", 48000, channels),
+        PayloadCodec::Pcm20 => ("L20", 48000, channels),
+        PayloadCodec::Pcm24 => ("L24", 48000, channels),
+        PayloadCodec::Mp2 => ("MPA", 90000, channels),
+        _ => return None,
+    };
+    Some(SdpCodec {
+        payload_type: codec.to_pt(),
+        name: name.to_string(),
+        clock_rate,
+        channels,
+    })
+}
+
+/// bass-rtp codec for an SDP codec (None if it can't be sent or received)
+pub fn payload_codec(codec: &SdpCodec) -> Option<PayloadCodec> {
+    let name = codec.name.to_ascii_uppercase();
+    let mapped = match name.as_str() {
+        "PCMU" => PayloadCodec::G711Ulaw,
+        "PCMA" => PayloadCodec::G711Alaw,
+        "G722" => PayloadCodec::G722,
+        "L16" if codec.clock_rate == 48000 => PayloadCodec::Pcm16,
+        "L20" if codec.clock_rate == 48000 => PayloadCodec::Pcm20,
+        "L24" if codec.clock_rate == 48000 => PayloadCodec::Pcm24,
+        "MPA" => PayloadCodec::Mp2,
+        _ => return None,
+    };
+    // bass-rtp picks its decoder by payload type
+    if mapped.to_pt() == codec.payload_type {
+        Some(mapped)
+    } else {
+        None
+    }
+}
+
+/// Build a session description
+///
+/// `codecs` are listed in order of preference.
+pub fn build(
+    session_id: u64,
+    version: u32,
+    addr: Ipv4Addr,
+    port: u16,
+    codecs: &[SdpCodec],
+    direction: Direction,
+    ptime: u32,
+) -> String {
+    let mut sdp = String::new();
+    let _ = write!(sdp, "v=0\r\n");
+    let _ = write!(sdp, "o=- {} {} IN IP4 {}\r\n", session_id, version, addr);
+    let _ = write!(sdp, "s=bass-sip\r\n");
+    let _ = write!(sdp, "c=IN IP4 {}\r\n", addr);
+    let _ = write!(sdp, "t=0 0\r\n");
+
+    let pts: Vec<String> = codecs.iter().map(|c| c.payload_type.to_string()).collect();
+    let _ = write!(sdp, "m=audio {} RTP/AVP {}\r\n", port, pts.join(" "));
+    for codec in codecs {
+        if codec.channels > 1 {
+            let _ = write!(sdp, "a=rtpmap:{} {}/{}/{}\r\n", codec.payload_type, codec.name, codec.clock_rate, codec.channels);
+        } else {
+            let _ = write!(sdp, "a=rtpmap:{} {}/{}\r\n", codec.payload_type, codec.name, codec.clock_rate);
+        }
+    }
+    let _ = write!(sdp, "a=ptime:{}\r\n", ptime);
+    let _ = write!(sdp, "a={}\r\n", direction.attribute());
+    sdp
+}
+
+/// Parse the first audio stream of a session description
+pub fn parse(body: &str) -> Result<MediaDescription, String> {
+    let mut session_addr = None;
+    let mut media_addr = None;
+    let mut port = None;
+    let mut pts: Vec<u8> = Vec::new();
+    let mut rtpmaps: Vec<SdpCodec> = Vec::new();
+    let mut direction = Direction::SendRecv;
+    let mut ptime = None;
+    // 0 = session level, 1 = in the audio stream, 2 = in another stream
+    let mut level = 0;
+    let mut found = false;
+
+    for line in body.lines() {
+        let line = line.trim_end();
+        let Some((kind, value)) = line.split_once('=') else { continue };
+        match kind {
+            "m" => {
+                if !found && value.starts_with("audio ") {
+                    let mut fields = value.split_whitespace().skip(1);
+                    port = fields.next().and_then(|p| p.parse::<u16>().ok());
+                    pts = fields.skip(1).filter_map(|pt| pt.parse().ok()).collect();
+                    found = true;
+                    level = 1;
+                } else {
+                    level = 2;
+                }
+            }
+            _ if level == 2 => {}
+            "c" => {
+                let addr = value
+                    .split_whitespace()
+                    .nth(2)
+                    .and_then(|a| a.split('/').next())
+                    .and_then(|a| a.parse::<Ipv4Addr>().ok());
+                if level == 0 {
+                    session_addr = addr;
+                } else {
+                    media_addr = addr;
+                }
+            }
+            "a" => {
+                let (name, arg) = value.split_once(':').unwrap_or((value, ""));
+                match name {
+                    "rtpmap" if level == 1 => {
+                        if let Some(codec) = parse_rtpmap(arg) {
+                            rtpmaps.push(codec);
+                        }
+                    }
+                    "ptime" => ptime = arg.trim().parse().ok(),
+                    "sendrecv" => direction = Direction::SendRecv,
+                    "sendonly" => direction = Direction::SendOnly,
+                    "recvonly" => direction = Direction::RecvOnly,
+                    "inactive" => direction = Direction::Inactive,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let port = port.ok_or("No audio stream")?;
+    let addr = media_addr.or(session_addr).ok_or("No connection address")?;
+
+    // Payload types without an rtpmap use their static mapping
+    let codecs = pts
+        .iter()
+        .filter_map(|&pt| {
+            rtpmaps
+                .iter()
+                .find(|c| c.payload_type == pt)
+                .cloned()
+                .or_else(|| static_codec(pt))
+        })
+        .collect();
+
+    // A zero address is the old (RFC 2543) way to put a call on hold
+    if addr.is_unspecified() && direction == Direction::SendRecv {
+        direction = Direction::Inactive;
+    }
+
+    Ok(MediaDescription { addr, port, codecs, direction, ptime })
+}
+
+fn parse_rtpmap(arg: &str) -> Option<SdpCodec> {
+    let (pt, encoding) = arg.trim().split_once(' ')?;
+    let mut parts = encoding.split('/');
+    Some(SdpCodec {
+        payload_type: pt.parse().ok()?,
+        name: parts.next()?.to_string(),
+        clock_rate: parts.next()?.parse().ok()?,
+        channels: parts.next().and_then(|c| c.parse().ok()).unwrap_or(1),
+    })
+}
+
+/// Static payload types (RFC 3551) this user agent handles
+fn static_codec(pt: u8) -> Option<SdpCodec> {
+    let (name, clock_rate) = match pt {
+        0 => ("PCMU", 8000),
+        8 => ("PCMA", 8000),
+        9 => ("G722", 8000),
+        14 => ("MPA", 90000),
+        _ => return None,
+    };
+    Some(SdpCodec {
+        payload_type: pt,
+        name: name.to_string(),
+        clock_rate,
+        channels: 1,
+    })
+}
+
+/// Pick the codec to answer `offer` with: the first offered codec we
+/// support, in the offerer's order of preference
+pub fn negotiate(offer: &MediaDescription, supported: &[PayloadCodec]) -> Option<(SdpCodec, PayloadCodec)> {
+    offer.codecs.iter().find_map(|codec| {
+        payload_codec(codec)
+            .filter(|c| supported.contains(c))
+            .map(|c| (codec.clone(), c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFER: &str = "v=0\r\n\
+        o=pbx 1 1 IN IP4 10.0.0.5\r\n\
+        s=call\r\n\
+        c=IN IP4 10.0.0.5\r\n\
+        t=0 0\r\n\
+        m=audio 40000 RTP/AVP 8 9 0 101\r\n\
+        a=rtpmap:8 PCMA/8000\r\n\
+        a=rtpmap:101 telephone-event/8000\r\n\
+        a=ptime:20\r\n\
+        a=sendrecv\r\n\
+        m=video 40002 RTP/AVP 96\r\n\
+        a=inactive\r\n";
+
+    #[test]
+    fn test_parse_offer() {
+        let media = parse(OFFER).unwrap();
+        assert_eq!(media.addr, Ipv4Addr::new(10, 0, 0, 5));
+        assert_eq!(media.port, 40000);
+        assert_eq!(media.direction, Direction::SendRecv);
+        assert_eq!(media.ptime, Some(20));
+        let names: Vec<&str> = media.codecs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["PCMA", "G722", "PCMU", "telephone-event"]);
+    }
+
+    #[test]
+    fn test_negotiate_follows_offer_order() {
+        let media = parse(OFFER).unwrap();
+        let (sdp, codec) = negotiate(&media, &[PayloadCodec::G711Ulaw, PayloadCodec::G722]).unwrap();
+        assert_eq!(codec, PayloadCodec::G722);
+        assert_eq!(sdp.payload_type, 9);
+        assert!(negotiate(&media, &[PayloadCodec::Pcm16]).is_none());
+
+        let (sdp, codec) = negotiate(&media, &[PayloadCodec::G711Ulaw, PayloadCodec::G711Alaw]).unwrap();
+        assert_eq!(codec, PayloadCodec::G711Alaw);
+        assert_eq!(sdp.payload_type, 8);
+    }
+
+    #[test]
+    fn test_build_parses_back() {
+        let codecs: Vec<SdpCodec> = [PayloadCodec::Pcm16, PayloadCodec::G711Ulaw]
+            .iter()
+            .filter_map(|&c| sdp_codec(c, 2))
+            .collect();
+        let sdp = build(7, 2, Ipv4Addr::new(192, 168, 0, 2), 5004, &codecs, Direction::SendOnly, 20);
+        let media = parse(&sdp).unwrap();
+        assert_eq!(media.port, 5004);
+        assert_eq!(media.codecs, codecs);
+        assert_eq!(media.direction, Direction::SendOnly);
+        assert_eq!(payload_codec(&media.codecs[0]), Some(PayloadCodec::Pcm16));
+    }
+}
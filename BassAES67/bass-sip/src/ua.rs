@@ -0,0 +1,1501 @@
+//! SIP user agent over UDP.
+//!
+//! Registers with a PBX, places and answers calls and keeps their dialogs:
+//! INVITE/ACK/BYE/CANCEL, digest authentication and re-INVITE hold. Media is
+//! not handled here; the negotiated RTP parameters are reported as events
+//! and the caller runs the media (see `crate::media`).
+//!
+//! One thread per user agent receives messages and runs the transaction
+//! timers. Requests the application makes are sent from its own thread.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use bass_rtp::rtp::{PayloadCodec, RtpSocket};
+use parking_lot::Mutex;
+
+use crate::auth::Challenge;
+use crate::message::{param, reason_phrase, uri_host_port, uri_of, SipMessage};
+use crate::sdp::{self, Direction, MediaDescription};
+
+/// RTT estimate, first retransmission interval (RFC 3261 timer T1)
+const T1: Duration = Duration::from_millis(500);
+/// Longest retransmission interval for non-INVITE requests (timer T2)
+const T2: Duration = Duration::from_secs(4);
+/// Transaction timeout (timers B, F and H: 64 * T1)
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(32);
+/// Wait before registering again after a failed registration
+const REGISTER_RETRY: Duration = Duration::from_secs(60);
+/// How long the receive thread blocks before running timers
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+const USER_AGENT: &str = "bass-sip/0.1";
+const ALLOW: &str = "INVITE, ACK, CANCEL, BYE, OPTIONS, INFO, NOTIFY";
+
+/// Next call handle, unique across user agents
+static NEXT_CALL: AtomicU32 = AtomicU32::new(1);
+
+// ============================================================================
+// Configuration and events
+// ============================================================================
+
+/// User agent configuration
+#[derive(Clone, Debug)]
+pub struct UaConfig {
+    /// Interface to bind to (0.0.0.0 = any)
+    pub interface: Ipv4Addr,
+    /// Local SIP port (0 = auto-assign)
+    pub port: u16,
+    /// PBX: registrar and outbound proxy (None = call peers directly)
+    pub server: Option<SocketAddr>,
+    /// SIP domain of our address of record ("" = server address)
+    pub domain: String,
+    /// User name (address of record and authentication)
+    pub username: String,
+    /// Password for digest authentication
+    pub password: String,
+    /// Display name in From headers ("" = none)
+    pub display_name: String,
+    /// Registration expiry in seconds
+    pub expires: u32,
+    /// Codecs to offer and accept, in order of preference
+    pub codecs: Vec<PayloadCodec>,
+    /// Channels for PCM and MP2 links (G.711/G.722 are always mono)
+    pub channels: u16,
+    /// Packet time in milliseconds
+    pub ptime: u32,
+}
+
+impl Default for UaConfig {
+    fn default() -> Self {
+        Self {
+            interface: Ipv4Addr::UNSPECIFIED,
+            port: 5060,
+            server: None,
+            domain: String::new(),
+            username: "bass".to_string(),
+            password: String::new(),
+            display_name: String::new(),
+            expires: 300,
+            codecs: vec![PayloadCodec::G722, PayloadCodec::G711Ulaw],
+            channels: 1,
+            ptime: 20,
+        }
+    }
+}
+
+/// RTP parameters agreed for a call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NegotiatedMedia {
+    /// Local RTP port (offered or answered in our SDP)
+    pub local_port: u16,
+    /// Where the other side receives RTP
+    pub remote: SocketAddrV4,
+    /// Codec both sides send
+    pub codec: PayloadCodec,
+    /// RTP payload type of the codec
+    pub payload_type: u8,
+    /// Channels of the codec
+    pub channels: u16,
+    /// Packet time in milliseconds
+    pub ptime: u32,
+}
+
+/// Something that happened to the registration or a call
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UaEvent {
+    /// The registrar accepted the registration for `expires` seconds
+    Registered { expires: u32 },
+    /// The registrar refused the registration (status code, 408 = no answer)
+    RegistrationFailed { code: u16 },
+    /// A call comes in; answer or hang up
+    Incoming { call: u32, from: String },
+    /// The called side is alerting
+    Ringing { call: u32 },
+    /// The call is connected and media can flow
+    Established { call: u32, media: NegotiatedMedia },
+    /// The other side moved its media (re-INVITE)
+    MediaChanged { call: u32, media: NegotiatedMedia },
+    /// The call is on hold (by either side); media stops
+    Held { call: u32 },
+    /// The call is off hold; media flows again
+    Resumed { call: u32, media: NegotiatedMedia },
+    /// The call is over (status code of a failed call, 0 = normal hangup)
+    Ended { call: u32, code: u16 },
+}
+
+// ============================================================================
+// Internal state
+// ============================================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CallState {
+    /// Outgoing INVITE sent, nothing heard yet
+    Calling,
+    /// Outgoing INVITE got a provisional response
+    Early,
+    /// Incoming INVITE, not answered yet
+    Incoming,
+    /// Connected
+    Established,
+}
+
+struct Call {
+    state: CallState,
+    /// Hung up locally; kept until its transactions finish, without events
+    terminated: bool,
+    call_id: String,
+    local_tag: String,
+    remote_tag: Option<String>,
+    /// Our From/To header value (without tag)
+    local_uri: String,
+    /// Their From/To header value (without tag)
+    remote_uri: String,
+    /// Request-URI of in-dialog requests (their Contact)
+    remote_target: String,
+    /// Route headers for in-dialog requests
+    route_set: Vec<String>,
+    /// Where to send requests when there is no route set
+    peer: SocketAddr,
+    local_cseq: u32,
+    local_ip: Ipv4Addr,
+    local_port: u16,
+    session_id: u64,
+    sdp_version: u32,
+    /// Outgoing INVITE (for CANCEL) or incoming INVITE (to answer)
+    invite: Option<SipMessage>,
+    /// Where an incoming INVITE came from
+    invite_source: Option<SocketAddr>,
+    /// Incoming offer
+    offer: Option<MediaDescription>,
+    media: Option<NegotiatedMedia>,
+    local_hold: bool,
+    remote_hold: bool,
+    /// CANCEL once the INVITE gets a provisional response
+    cancel_pending: bool,
+    /// Our final response to an INVITE, resent until ACKed
+    pending_final: Option<Retransmit>,
+}
+
+impl Call {
+    fn media_active(&self) -> bool {
+        !self.local_hold && !self.remote_hold
+    }
+}
+
+/// A response resent until the other side acknowledges it
+struct Retransmit {
+    bytes: Vec<u8>,
+    dest: SocketAddr,
+    cseq: u32,
+    next: Instant,
+    interval: Duration,
+    deadline: Instant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TxKind {
+    Register,
+    Invite(u32),
+    ReInvite(u32),
+    Bye,
+    Cancel,
+}
+
+/// Client transaction: a request we sent and its retransmissions
+struct ClientTx {
+    request: SipMessage,
+    dest: SocketAddr,
+    kind: TxKind,
+    next: Option<Instant>,
+    interval: Duration,
+    deadline: Instant,
+    auth_tried: bool,
+}
+
+/// Last response to a request, resent when the request is retransmitted
+struct Served {
+    bytes: Vec<u8>,
+    expires: Instant,
+}
+
+struct Registration {
+    call_id: String,
+    local_tag: String,
+    cseq: u32,
+    /// When to send the next REGISTER (None = not registering)
+    next: Option<Instant>,
+    expires: u32,
+}
+
+struct Inner {
+    config: UaConfig,
+    socket: Arc<UdpSocket>,
+    port: u16,
+    domain: String,
+    events: Sender<UaEvent>,
+    registration: Registration,
+    calls: HashMap<u32, Call>,
+    clients: HashMap<String, ClientTx>,
+    served: HashMap<String, Served>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0),
+    );
+    hasher.finish()
+}
+
+fn random_token() -> String {
+    format!("{:016x}", random_u64())
+}
+
+fn new_branch() -> String {
+    format!("z9hG4bK{}", random_token())
+}
+
+/// Resolve the host of a SIP URI (IPv4, port 5060 if not given)
+fn resolve(uri: &str) -> Option<SocketAddr> {
+    let (host, port) = uri_host_port(uri)?;
+    (host, port.unwrap_or(5060))
+        .to_socket_addrs()
+        .ok()?
+        .find(|a| a.is_ipv4())
+}
+
+/// Local address the OS would send from to reach `dest`
+fn local_ip_towards(dest: SocketAddr, interface: Ipv4Addr) -> Ipv4Addr {
+    if !interface.is_unspecified() {
+        return interface;
+    }
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|s| s.connect(dest).and_then(|_| s.local_addr()))
+        .ok()
+        .and_then(|a| match a.ip() {
+            IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+            _ => None,
+        })
+        .unwrap_or(Ipv4Addr::LOCALHOST)
+}
+
+/// Free local UDP port for RTP
+fn allocate_rtp_port(interface: Ipv4Addr) -> Result<u16, String> {
+    let socket = RtpSocket::bind(SocketAddr::V4(SocketAddrV4::new(interface, 0)))?;
+    Ok(socket.local_addr().port())
+}
+
+/// Direction to answer a remote offer with
+fn answer_direction(offer: Direction, local_hold: bool) -> Direction {
+    match (offer.answer(), local_hold) {
+        (answer, false) => answer,
+        (Direction::SendRecv | Direction::SendOnly, true) => Direction::SendOnly,
+        (_, true) => Direction::Inactive,
+    }
+}
+
+// ============================================================================
+// User agent
+// ============================================================================
+
+/// SIP user agent
+pub struct UserAgent {
+    inner: Arc<Mutex<Inner>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    local_addr: SocketAddr,
+}
+
+impl UserAgent {
+    /// Bind the SIP socket and start the user agent.
+    ///
+    /// Events arrive on the returned receiver.
+    pub fn new(config: UaConfig) -> Result<(Self, Receiver<UaEvent>), String> {
+        let socket = UdpSocket::bind((config.interface, config.port)).map_err(|e| e.to_string())?;
+        socket.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| e.to_string())?;
+        let local_addr = socket.local_addr().map_err(|e| e.to_string())?;
+        let socket = Arc::new(socket);
+
+        let domain = if !config.domain.is_empty() {
+            config.domain.clone()
+        } else if let Some(server) = config.server {
+            server.ip().to_string()
+        } else {
+            local_ip_towards(SocketAddr::from((Ipv4Addr::LOCALHOST, 5060)), config.interface).to_string()
+        };
+
+        let (events, received) = mpsc::channel();
+        let inner = Arc::new(Mutex::new(Inner {
+            config,
+            socket: socket.clone(),
+            port: local_addr.port(),
+            domain,
+            events,
+            registration: Registration {
+                call_id: format!("{}@bass-sip", random_token()),
+                local_tag: random_token(),
+                cseq: 0,
+                next: None,
+                expires: 0,
+            },
+            calls: HashMap::new(),
+            clients: HashMap::new(),
+            served: HashMap::new(),
+        }));
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let inner = inner.clone();
+            let running = running.clone();
+            thread::spawn(move || receive_loop(socket, inner, running))
+        };
+
+        Ok((
+            Self {
+                inner,
+                running,
+                thread: Some(thread),
+                local_addr,
+            },
+            received,
+        ))
+    }
+
+    /// Address the SIP socket is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Register with the server now and keep the registration refreshed
+    pub fn register(&self) -> Result<(), String> {
+        let mut inner = self.inner.lock();
+        if inner.config.server.is_none() {
+            return Err("No server configured".to_string());
+        }
+        let expires = inner.config.expires;
+        inner.send_register(expires)
+    }
+
+    /// Call `target` ("sip:user@host", "user@host" or a user at our domain).
+    ///
+    /// # Returns
+    /// * Call handle
+    pub fn call(&self, target: &str) -> Result<u32, String> {
+        self.inner.lock().start_call(target)
+    }
+
+    /// Answer an incoming call
+    pub fn answer(&self, call: u32) -> Result<(), String> {
+        self.inner.lock().answer(call)
+    }
+
+    /// End a call: cancel it while ringing, decline it while incoming or
+    /// send BYE when connected. Reports `Ended` right away.
+    pub fn hangup(&self, call: u32) -> Result<(), String> {
+        self.inner.lock().hangup(call)
+    }
+
+    /// Put a connected call on hold or take it off hold (re-INVITE)
+    pub fn hold(&self, call: u32, hold: bool) -> Result<(), String> {
+        self.inner.lock().hold(call, hold)
+    }
+
+    /// Whether `call` belongs to this user agent
+    pub fn has_call(&self, call: u32) -> bool {
+        self.inner.lock().calls.get(&call).is_some_and(|c| !c.terminated)
+    }
+}
+
+impl Drop for UserAgent {
+    fn drop(&mut self) {
+        {
+            let mut inner = self.inner.lock();
+            let calls: Vec<u32> = inner.calls.keys().copied().collect();
+            for call in calls {
+                let _ = inner.hangup(call);
+            }
+            if inner.registration.next.is_some() {
+                let _ = inner.send_register(0);
+            }
+        }
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Receive thread: handle incoming messages and run the timers
+fn receive_loop(socket: Arc<UdpSocket>, inner: Arc<Mutex<Inner>>, running: Arc<AtomicBool>) {
+    let mut buf = vec![0u8; 65535];
+    while running.load(Ordering::SeqCst) {
+        if let Ok((len, source)) = socket.recv_from(&mut buf) {
+            // Keep-alives (CRLF) and garbage don't parse
+            if let Ok(message) = SipMessage::parse(&buf[..len]) {
+                let mut inner = inner.lock();
+                if message.method().is_some() {
+                    inner.on_request(message, source);
+                } else {
+                    inner.on_response(message);
+                }
+            }
+        }
+        inner.lock().on_timer(Instant::now());
+    }
+}
+
+impl Inner {
+    fn emit(&self, event: UaEvent) {
+        let _ = self.events.send(event);
+    }
+
+    fn send(&self, bytes: &[u8], dest: SocketAddr) {
+        let _ = self.socket.send_to(bytes, dest);
+    }
+
+    fn aor(&self) -> String {
+        format!("sip:{}@{}", self.config.username, self.domain)
+    }
+
+    fn name_addr(&self) -> String {
+        if self.config.display_name.is_empty() {
+            format!("<{}>", self.aor())
+        } else {
+            format!("\"{}\" <{}>", self.config.display_name, self.aor())
+        }
+    }
+
+    fn contact(&self, local_ip: Ipv4Addr) -> String {
+        format!("<sip:{}@{}:{}>", self.config.username, local_ip, self.port)
+    }
+
+    fn via(&self, local_ip: Ipv4Addr, branch: &str) -> String {
+        format!("SIP/2.0/UDP {}:{};branch={};rport", local_ip, self.port, branch)
+    }
+
+    // ------------------------------------------------------------------------
+    // Client transactions
+    // ------------------------------------------------------------------------
+
+    /// Send a request and retransmit it until answered
+    fn send_request(&mut self, request: SipMessage, dest: SocketAddr, kind: TxKind, auth_tried: bool) {
+        let Some(branch) = request.branch().map(str::to_string) else { return };
+        self.send(&request.to_bytes(), dest);
+        let now = Instant::now();
+        self.clients.insert(
+            branch,
+            ClientTx {
+                request,
+                dest,
+                kind,
+                next: Some(now + T1),
+                interval: T1,
+                deadline: now + TRANSACTION_TIMEOUT,
+                auth_tried,
+            },
+        );
+    }
+
+    fn on_response(&mut self, response: SipMessage) {
+        let Some(code) = response.status() else { return };
+        let branch = response.branch().unwrap_or_default().to_string();
+
+        let Some(tx) = self.clients.get_mut(&branch) else {
+            // A retransmitted 2xx after its transaction ended: ACK again
+            if (200..300).contains(&code) && response.cseq().is_some_and(|(_, m)| m == "INVITE") {
+                self.ack_retransmitted_2xx(&response);
+            }
+            return;
+        };
+
+        if code < 200 {
+            // INVITEs stop retransmitting once answered provisionally
+            if matches!(tx.kind, TxKind::Invite(_) | TxKind::ReInvite(_)) {
+                tx.next = None;
+            } else {
+                tx.interval = T2;
+            }
+            if let TxKind::Invite(call) = tx.kind {
+                self.on_invite_provisional(call, &response);
+            }
+            return;
+        }
+
+        let Some(tx) = self.clients.remove(&branch) else { return };
+
+        if (code == 401 || code == 407)
+            && !tx.auth_tried
+            && tx.kind != TxKind::Cancel
+            && self.retry_with_auth(&tx, &response)
+        {
+            return;
+        }
+
+        match tx.kind {
+            TxKind::Register => self.on_register_final(&tx.request, &response),
+            TxKind::Invite(call) => self.on_invite_final(call, &tx, &response),
+            TxKind::ReInvite(call) => self.on_reinvite_final(call, &tx, &response),
+            TxKind::Bye | TxKind::Cancel => {}
+        }
+    }
+
+    /// Resend a challenged request with credentials
+    fn retry_with_auth(&mut self, tx: &ClientTx, response: &SipMessage) -> bool {
+        let (challenge_header, auth_header) = if response.status() == Some(407) {
+            ("Proxy-Authenticate", "Proxy-Authorization")
+        } else {
+            ("WWW-Authenticate", "Authorization")
+        };
+        let Some(challenge) = response.header_values(challenge_header).find_map(Challenge::parse) else {
+            return false;
+        };
+        if self.config.password.is_empty() {
+            return false;
+        }
+
+        // A failed INVITE is acknowledged before trying again
+        if matches!(tx.kind, TxKind::Invite(_) | TxKind::ReInvite(_)) {
+            self.send_failure_ack(tx, response);
+        }
+
+        let mut request = tx.request.clone();
+        let (method, uri) = match &request.start {
+            crate::message::StartLine::Request { method, uri } => (method.clone(), uri.clone()),
+            _ => return false,
+        };
+
+        let cseq = match tx.kind {
+            TxKind::Register => {
+                self.registration.cseq += 1;
+                self.registration.cseq
+            }
+            TxKind::Invite(call) | TxKind::ReInvite(call) => match self.calls.get_mut(&call) {
+                Some(c) => {
+                    c.local_cseq += 1;
+                    c.local_cseq
+                }
+                None => return false,
+            },
+            _ => request.cseq().map(|(n, _)| n + 1).unwrap_or(1),
+        };
+
+        let via = request.header("Via").unwrap_or_default().to_string();
+        let sent_by = via.split(';').next().unwrap_or_default().to_string();
+        request.set_header("Via", format!("{};branch={};rport", sent_by, new_branch()));
+        request.set_header("CSeq", format!("{} {}", cseq, method));
+        request.set_header(
+            auth_header,
+            challenge.authorize(&method, &uri, &self.config.username, &self.config.password, &random_token(), 1),
+        );
+
+        if let TxKind::Invite(call) = tx.kind {
+            if let Some(c) = self.calls.get_mut(&call) {
+                c.invite = Some(request.clone());
+            }
+        }
+        self.send_request(request, tx.dest, tx.kind, true);
+        true
+    }
+
+    /// ACK for a non-2xx final response to an INVITE (same transaction)
+    fn send_failure_ack(&self, tx: &ClientTx, response: &SipMessage) {
+        let invite = &tx.request;
+        let uri = match &invite.start {
+            crate::message::StartLine::Request { uri, .. } => uri.clone(),
+            _ => return,
+        };
+        let mut ack = SipMessage::request("ACK", &uri);
+        for name in ["Via", "From", "Call-ID", "Route"] {
+            for value in invite.headers.iter().filter(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                ack.add_header(name, value.1.clone());
+            }
+        }
+        ack.add_header("To", response.header("To").unwrap_or_default());
+        let cseq = invite.cseq().map(|(n, _)| n).unwrap_or(1);
+        ack.add_header("CSeq", format!("{} ACK", cseq));
+        ack.add_header("Max-Forwards", "70");
+        self.send(&ack.to_bytes(), tx.dest);
+    }
+
+    fn on_timer(&mut self, now: Instant) {
+        // Request retransmissions and timeouts
+        let mut timed_out = Vec::new();
+        for (branch, tx) in self.clients.iter_mut() {
+            if now >= tx.deadline {
+                timed_out.push(branch.clone());
+            } else if tx.next.is_some_and(|next| now >= next) {
+                let _ = self.socket.send_to(&tx.request.to_bytes(), tx.dest);
+                let is_invite = matches!(tx.kind, TxKind::Invite(_) | TxKind::ReInvite(_));
+                tx.interval = if is_invite { tx.interval * 2 } else { (tx.interval * 2).min(T2) };
+                tx.next = Some(now + tx.interval);
+            }
+        }
+        for branch in timed_out {
+            if let Some(tx) = self.clients.remove(&branch) {
+                let timeout = SipMessage::response(408, "Request Timeout");
+                match tx.kind {
+                    TxKind::Register => self.on_register_final(&tx.request, &timeout),
+                    TxKind::Invite(call) => self.end_call(call, 408),
+                    TxKind::ReInvite(call) => self.end_call(call, 408),
+                    TxKind::Bye | TxKind::Cancel => {}
+                }
+            }
+        }
+
+        // Our final responses to INVITEs, until ACKed
+        let mut unacked = Vec::new();
+        for (&id, call) in self.calls.iter_mut() {
+            if let Some(pending) = call.pending_final.as_mut() {
+                if now >= pending.deadline {
+                    unacked.push(id);
+                } else if now >= pending.next {
+                    let _ = self.socket.send_to(&pending.bytes, pending.dest);
+                    pending.interval = (pending.interval * 2).min(T2);
+                    pending.next = now + pending.interval;
+                }
+            }
+        }
+        for id in unacked {
+            if let Some(call) = self.calls.get_mut(&id) {
+                call.pending_final = None;
+            }
+            // No ACK for our 200 OK: the call never connected
+            let _ = self.send_bye(id);
+            self.end_call(id, 408);
+        }
+
+        self.served.retain(|_, s| s.expires > now);
+
+        if self.registration.next.is_some_and(|next| now >= next) {
+            let expires = self.config.expires;
+            let _ = self.send_register(expires);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Registration
+    // ------------------------------------------------------------------------
+
+    fn send_register(&mut self, expires: u32) -> Result<(), String> {
+        let server = self.config.server.ok_or("No server configured")?;
+        let local_ip = local_ip_towards(server, self.config.interface);
+
+        self.registration.cseq += 1;
+        // Refreshed from the response; retried if there is none
+        self.registration.next = if expires > 0 { Some(Instant::now() + REGISTER_RETRY) } else { None };
+
+        let mut request = SipMessage::request("REGISTER", &format!("sip:{}", self.domain));
+        request.add_header("Via", self.via(local_ip, &new_branch()));
+        request.add_header("Max-Forwards", "70");
+        request.add_header("From", format!("{};tag={}", self.name_addr(), self.registration.local_tag));
+        request.add_header("To", self.name_addr());
+        request.add_header("Call-ID", self.registration.call_id.clone());
+        request.add_header("CSeq", format!("{} REGISTER", self.registration.cseq));
+        request.add_header("Contact", format!("{};expires={}", self.contact(local_ip), expires));
+        request.add_header("Expires", expires.to_string());
+        request.add_header("Allow", ALLOW);
+        request.add_header("User-Agent", USER_AGENT);
+
+        self.send_request(request, server, TxKind::Register, false);
+        Ok(())
+    }
+
+    fn on_register_final(&mut self, request: &SipMessage, response: &SipMessage) {
+        let code = response.status().unwrap_or(500);
+        let requested: u32 = request.header("Expires").and_then(|e| e.parse().ok()).unwrap_or(0);
+        if requested == 0 {
+            // Unregistered (or failed to); nothing to refresh
+            return;
+        }
+
+        if (200..300).contains(&code) {
+            // Expiry the registrar granted for our contact
+            let contact = request.header("Contact").map(uri_of).unwrap_or_default().to_string();
+            let expires = response
+                .header_values("Contact")
+                .find(|c| uri_of(c) == contact)
+                .and_then(|c| param(c, "expires"))
+                .or_else(|| response.header("Expires"))
+                .and_then(|e| e.parse().ok())
+                .unwrap_or(requested)
+                .max(1);
+            self.registration.expires = expires;
+            self.registration.next = Some(Instant::now() + Duration::from_secs((expires as u64 / 2).max(1)));
+            self.emit(UaEvent::Registered { expires });
+        } else {
+            self.registration.expires = 0;
+            self.registration.next = Some(Instant::now() + REGISTER_RETRY);
+            self.emit(UaEvent::RegistrationFailed { code });
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Outgoing calls
+    // ------------------------------------------------------------------------
+
+    fn start_call(&mut self, target: &str) -> Result<u32, String> {
+        let target = target.trim().trim_start_matches('<').trim_end_matches('>');
+        let uri = if target.starts_with("sip:") {
+            target.to_string()
+        } else if target.contains('@') {
+            format!("sip:{}", target)
+        } else {
+            format!("sip:{}@{}", target, self.domain)
+        };
+
+        let peer = match self.config.server {
+            Some(server) => server,
+            None => resolve(&uri).ok_or("Cannot resolve target")?,
+        };
+        let local_ip = local_ip_towards(peer, self.config.interface);
+        let local_port = allocate_rtp_port(self.config.interface)?;
+
+        let id = NEXT_CALL.fetch_add(1, Ordering::SeqCst);
+        let call = Call {
+            state: CallState::Calling,
+            terminated: false,
+            call_id: format!("{}@{}", random_token(), local_ip),
+            local_tag: random_token(),
+            remote_tag: None,
+            local_uri: self.name_addr(),
+            remote_uri: format!("<{}>", uri),
+            remote_target: uri,
+            route_set: Vec::new(),
+            peer,
+            local_cseq: 1,
+            local_ip,
+            local_port,
+            session_id: random_u64() >> 1,
+            sdp_version: 1,
+            invite: None,
+            invite_source: None,
+            offer: None,
+            media: None,
+            local_hold: false,
+            remote_hold: false,
+            cancel_pending: false,
+            pending_final: None,
+        };
+        self.calls.insert(id, call);
+
+        let invite = self.dialog_request(id, "INVITE", true).ok_or("No call")?;
+        if let Some(call) = self.calls.get_mut(&id) {
+            call.invite = Some(invite.clone());
+        }
+        self.send_request(invite, peer, TxKind::Invite(id), false);
+        Ok(id)
+    }
+
+    /// Build a request within the dialog of `call` (with an SDP offer if
+    /// `offer`), using the call's next CSeq
+    fn dialog_request(&mut self, id: u32, method: &str, offer: bool) -> Option<SipMessage> {
+        let contact = {
+            let call = self.calls.get(&id)?;
+            self.contact(call.local_ip)
+        };
+        let sdp = if offer { Some(self.local_sdp(id, None)?) } else { None };
+        let call = self.calls.get(&id)?;
+
+        let mut request = SipMessage::request(method, &call.remote_target);
+        request.add_header("Via", format!("SIP/2.0/UDP {}:{};branch={};rport", call.local_ip, self.port, new_branch()));
+        request.add_header("Max-Forwards", "70");
+        for route in &call.route_set {
+            request.add_header("Route", route.clone());
+        }
+        request.add_header("From", format!("{};tag={}", call.local_uri, call.local_tag));
+        match &call.remote_tag {
+            Some(tag) => request.add_header("To", format!("{};tag={}", call.remote_uri, tag)),
+            None => request.add_header("To", call.remote_uri.clone()),
+        }
+        request.add_header("Call-ID", call.call_id.clone());
+        request.add_header("CSeq", format!("{} {}", call.local_cseq, method));
+        if method == "INVITE" {
+            request.add_header("Contact", contact);
+            request.add_header("Allow", ALLOW);
+        }
+        request.add_header("User-Agent", USER_AGENT);
+        if let Some(sdp) = sdp {
+            request.add_header("Content-Type", "application/sdp");
+            request.body = sdp.into_bytes();
+        }
+        Some(request)
+    }
+
+    /// Next hop for in-dialog requests of `call`
+    fn next_hop(&self, call: &Call) -> SocketAddr {
+        if let Some(server) = self.config.server {
+            return server;
+        }
+        call.route_set
+            .first()
+            .and_then(|r| resolve(uri_of(r)))
+            .or_else(|| resolve(&call.remote_target))
+            .unwrap_or(call.peer)
+    }
+
+    /// Our SDP: an offer of all configured codecs, or an answer with the
+    /// negotiated codec (`answer` = direction of the answer)
+    fn local_sdp(&mut self, id: u32, answer: Option<Direction>) -> Option<String> {
+        let channels = self.config.channels;
+        let ptime = self.config.ptime;
+        let all: Vec<sdp::SdpCodec> = self.config.codecs.iter().filter_map(|&c| sdp::sdp_codec(c, channels)).collect();
+
+        let call = self.calls.get_mut(&id)?;
+        call.sdp_version += 1;
+        let codecs = match (&call.media, answer) {
+            // Once agreed, only the codec in use is offered again
+            (Some(media), _) => sdp::sdp_codec(media.codec, media.channels).into_iter().collect(),
+            (None, _) => all,
+        };
+        let direction = answer.unwrap_or(if call.local_hold { Direction::SendOnly } else { Direction::SendRecv });
+        Some(sdp::build(call.session_id, call.sdp_version, call.local_ip, call.local_port, &codecs, direction, ptime))
+    }
+
+    fn on_invite_provisional(&mut self, id: u32, response: &SipMessage) {
+        let Some(call) = self.calls.get_mut(&id) else { return };
+        if response.status() == Some(100) {
+            return;
+        }
+        let was_calling = call.state == CallState::Calling;
+        call.state = CallState::Early;
+        if call.cancel_pending {
+            call.cancel_pending = false;
+            self.send_cancel(id);
+            return;
+        }
+        if was_calling && !call.terminated {
+            self.emit(UaEvent::Ringing { call: id });
+        }
+    }
+
+    fn on_invite_final(&mut self, id: u32, tx: &ClientTx, response: &SipMessage) {
+        let code = response.status().unwrap_or(500);
+        if !(200..300).contains(&code) {
+            self.send_failure_ack(tx, response);
+            self.end_call(id, code);
+            return;
+        }
+
+        let Some(call) = self.calls.get_mut(&id) else { return };
+        call.remote_tag = response.to_tag().map(str::to_string);
+        if let Some(contact) = response.header("Contact") {
+            call.remote_target = uri_of(contact).to_string();
+        }
+        // The route set is the Record-Route of the response, reversed
+        call.route_set = response.header_values("Record-Route").map(str::to_string).collect();
+        call.route_set.reverse();
+        call.state = CallState::Established;
+        call.cancel_pending = false;
+        let terminated = call.terminated;
+
+        self.send_ack(id, tx.request.cseq().map(|(n, _)| n).unwrap_or(1));
+
+        if terminated {
+            // Hung up while ringing, but answered anyway
+            let _ = self.send_bye(id);
+            self.calls.remove(&id);
+            return;
+        }
+
+        match self.media_from_answer(id, response) {
+            Some(media) => {
+                if let Some(call) = self.calls.get_mut(&id) {
+                    call.media = Some(media);
+                }
+                self.emit(UaEvent::Established { call: id, media });
+            }
+            None => {
+                let _ = self.send_bye(id);
+                self.end_call(id, 488);
+            }
+        }
+    }
+
+    /// Media agreed in the SDP answer of `response`
+    fn media_from_answer(&self, id: u32, response: &SipMessage) -> Option<NegotiatedMedia> {
+        let call = self.calls.get(&id)?;
+        let answer = sdp::parse(std::str::from_utf8(&response.body).ok()?).ok()?;
+        let supported = match &call.media {
+            Some(media) => vec![media.codec],
+            None => self.config.codecs.clone(),
+        };
+        let (codec, payload_codec) = sdp::negotiate(&answer, &supported)?;
+        Some(NegotiatedMedia {
+            local_port: call.local_port,
+            remote: SocketAddrV4::new(answer.addr, answer.port),
+            codec: payload_codec,
+            payload_type: codec.payload_type,
+            channels: codec.channels,
+            ptime: answer.ptime.unwrap_or(self.config.ptime),
+        })
+    }
+
+    /// ACK a 2xx response to our INVITE number `cseq`
+    fn send_ack(&mut self, id: u32, cseq: u32) {
+        let Some(mut ack) = self.dialog_request(id, "ACK", false) else { return };
+        ack.set_header("CSeq", format!("{} ACK", cseq));
+        if let Some(call) = self.calls.get(&id) {
+            let hop = self.next_hop(call);
+            self.send(&ack.to_bytes(), hop);
+        }
+    }
+
+    fn ack_retransmitted_2xx(&mut self, response: &SipMessage) {
+        let call_id = response.call_id().unwrap_or_default();
+        let found = self
+            .calls
+            .iter()
+            .find(|(_, c)| c.call_id == call_id && c.state == CallState::Established)
+            .map(|(&id, _)| id);
+        if let (Some(id), Some((cseq, _))) = (found, response.cseq()) {
+            self.send_ack(id, cseq);
+        }
+    }
+
+    fn send_cancel(&mut self, id: u32) {
+        let Some(call) = self.calls.get(&id) else { return };
+        let Some(invite) = call.invite.as_ref() else { return };
+        let uri = match &invite.start {
+            crate::message::StartLine::Request { uri, .. } => uri.clone(),
+            _ => return,
+        };
+
+        // CANCEL matches the INVITE transaction: same Via, no To tag
+        let mut cancel = SipMessage::request("CANCEL", &uri);
+        for name in ["Via", "From", "To", "Call-ID", "Route"] {
+            for value in invite.headers.iter().filter(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                cancel.add_header(name, value.1.clone());
+            }
+        }
+        let cseq = invite.cseq().map(|(n, _)| n).unwrap_or(1);
+        cancel.add_header("CSeq", format!("{} CANCEL", cseq));
+        cancel.add_header("Max-Forwards", "70");
+        cancel.add_header("User-Agent", USER_AGENT);
+
+        let dest = self
+            .clients
+            .values()
+            .find(|tx| tx.kind == TxKind::Invite(id))
+            .map(|tx| tx.dest)
+            .unwrap_or(call.peer);
+        self.send_request(cancel, dest, TxKind::Cancel, false);
+    }
+
+    fn send_bye(&mut self, id: u32) -> Result<(), String> {
+        if let Some(call) = self.calls.get_mut(&id) {
+            call.local_cseq += 1;
+        }
+        let bye = self.dialog_request(id, "BYE", false).ok_or("No call")?;
+        let hop = self.calls.get(&id).map(|c| self.next_hop(c)).ok_or("No call")?;
+        self.send_request(bye, hop, TxKind::Bye, false);
+        Ok(())
+    }
+
+    /// Report the end of a call and forget it
+    fn end_call(&mut self, id: u32, code: u16) {
+        if let Some(call) = self.calls.remove(&id) {
+            if !call.terminated {
+                self.emit(UaEvent::Ended { call: id, code });
+            }
+        }
+    }
+
+    fn hangup(&mut self, id: u32) -> Result<(), String> {
+        let call = self.calls.get_mut(&id).ok_or("No such call")?;
+        if call.terminated {
+            return Ok(());
+        }
+        call.terminated = true;
+        let state = call.state;
+        self.emit(UaEvent::Ended { call: id, code: 0 });
+
+        match state {
+            // CANCEL needs a provisional response first
+            CallState::Calling => {
+                if let Some(call) = self.calls.get_mut(&id) {
+                    call.cancel_pending = true;
+                }
+            }
+            CallState::Early => self.send_cancel(id),
+            CallState::Incoming => {
+                self.respond_to_invite(id, 603, None);
+                self.calls.remove(&id);
+            }
+            CallState::Established => {
+                let _ = self.send_bye(id);
+                self.calls.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    fn hold(&mut self, id: u32, hold: bool) -> Result<(), String> {
+        let call = self.calls.get_mut(&id).ok_or("No such call")?;
+        if call.state != CallState::Established || call.terminated {
+            return Err("Call is not connected".to_string());
+        }
+        if call.local_hold == hold {
+            return Ok(());
+        }
+        if self.clients.values().any(|tx| tx.kind == TxKind::ReInvite(id)) {
+            return Err("Re-INVITE in progress".to_string());
+        }
+        call.local_hold = hold;
+        call.local_cseq += 1;
+
+        let invite = self.dialog_request(id, "INVITE", true).ok_or("No call")?;
+        let hop = self.calls.get(&id).map(|c| self.next_hop(c)).ok_or("No call")?;
+        self.send_request(invite, hop, TxKind::ReInvite(id), false);
+        Ok(())
+    }
+
+    fn on_reinvite_final(&mut self, id: u32, tx: &ClientTx, response: &SipMessage) {
+        let code = response.status().unwrap_or(500);
+        if !(200..300).contains(&code) {
+            self.send_failure_ack(tx, response);
+            if code == 481 || code == 408 {
+                self.end_call(id, code);
+            } else if let Some(call) = self.calls.get_mut(&id) {
+                // Refused: the hold state stays as it was
+                call.local_hold = !call.local_hold;
+            }
+            return;
+        }
+
+        self.send_ack(id, tx.request.cseq().map(|(n, _)| n).unwrap_or(1));
+        let media = self.media_from_answer(id, response);
+        let Some(call) = self.calls.get_mut(&id) else { return };
+        if let Some(media) = media {
+            call.media = Some(media);
+        }
+        let held = call.local_hold;
+        let active = call.media_active();
+        let media = call.media;
+        if held {
+            self.emit(UaEvent::Held { call: id });
+        } else if let (true, Some(media)) = (active, media) {
+            self.emit(UaEvent::Resumed { call: id, media });
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Incoming requests
+    // ------------------------------------------------------------------------
+
+    fn on_request(&mut self, request: SipMessage, source: SocketAddr) {
+        let method = request.method().unwrap_or_default().to_string();
+
+        // Retransmitted request: repeat our last response
+        if method != "ACK" {
+            let key = format!("{} {}", request.branch().unwrap_or_default(), method);
+            if let Some(served) = self.served.get(&key) {
+                self.send(&served.bytes, source);
+                return;
+            }
+        }
+
+        let call_id = request.call_id().unwrap_or_default().to_string();
+        let dialog = self
+            .calls
+            .iter()
+            .find(|(_, c)| c.call_id == call_id && request.to_tag() == Some(c.local_tag.as_str()))
+            .map(|(&id, _)| id);
+
+        match (method.as_str(), dialog) {
+            ("INVITE", None) if request.to_tag().is_none() => self.on_new_invite(request, source),
+            ("INVITE", Some(id)) => self.on_reinvite(id, request, source),
+            ("ACK", Some(id)) => self.on_ack(id, &request),
+            ("ACK", None) => {}
+            ("CANCEL", _) => self.on_cancel(request, source),
+            ("BYE", Some(id)) => {
+                self.reply(&request, source, 200, None, None);
+                if let Some(call) = self.calls.get_mut(&id) {
+                    call.pending_final = None;
+                }
+                self.end_call(id, 0);
+            }
+            ("OPTIONS", _) => {
+                let mut response = SipMessage::response_to(&request, 200, "OK");
+                response.add_header("Allow", ALLOW);
+                response.add_header("Accept", "application/sdp");
+                self.reply_with(&request, source, response);
+            }
+            ("INFO" | "NOTIFY", Some(_)) => self.reply(&request, source, 200, None, None),
+            (_, None) if request.to_tag().is_some() => self.reply(&request, source, 481, None, None),
+            _ => self.reply(&request, source, 501, None, None),
+        }
+    }
+
+    /// Send a response and remember it for retransmissions of the request
+    fn reply_with(&mut self, request: &SipMessage, source: SocketAddr, response: SipMessage) {
+        let bytes = response.to_bytes();
+        self.send(&bytes, source);
+        let key = format!("{} {}", request.branch().unwrap_or_default(), request.method().unwrap_or_default());
+        self.served.insert(
+            key,
+            Served {
+                bytes,
+                expires: Instant::now() + TRANSACTION_TIMEOUT,
+            },
+        );
+    }
+
+    /// Reply with `code`, adding a To tag if given
+    fn reply(&mut self, request: &SipMessage, source: SocketAddr, code: u16, to_tag: Option<&str>, sdp: Option<String>) {
+        let mut response = SipMessage::response_to(request, code, reason_phrase(code));
+        if let (Some(tag), None) = (to_tag, request.to_tag()) {
+            let to = request.header("To").unwrap_or_default();
+            response.set_header("To", format!("{};tag={}", to, tag));
+        }
+        if let Some(sdp) = sdp {
+            response.add_header("Content-Type", "application/sdp");
+            response.body = sdp.into_bytes();
+        }
+        self.reply_with(request, source, response);
+    }
+
+    fn on_new_invite(&mut self, request: SipMessage, source: SocketAddr) {
+        let offer = std::str::from_utf8(&request.body).ok().and_then(|b| sdp::parse(b).ok());
+        // Offerless INVITEs aren't supported: we can only answer
+        let Some(offer) = offer.filter(|o| sdp::negotiate(o, &self.config.codecs).is_some()) else {
+            self.reply(&request, source, 488, Some(&random_token()), None);
+            return;
+        };
+
+        let local_port = match allocate_rtp_port(self.config.interface) {
+            Ok(port) => port,
+            Err(_) => {
+                self.reply(&request, source, 500, Some(&random_token()), None);
+                return;
+            }
+        };
+
+        let from = request.header("From").unwrap_or_default();
+        let remote_uri = from.split(";tag=").next().unwrap_or(from).to_string();
+        let to = request.header("To").unwrap_or_default().to_string();
+        // The UAS keeps the Record-Route order
+        let route_set: Vec<String> = request.header_values("Record-Route").map(str::to_string).collect();
+
+        let id = NEXT_CALL.fetch_add(1, Ordering::SeqCst);
+        let call = Call {
+            state: CallState::Incoming,
+            terminated: false,
+            call_id: request.call_id().unwrap_or_default().to_string(),
+            local_tag: random_token(),
+            remote_tag: request.from_tag().map(str::to_string),
+            local_uri: to,
+            remote_uri,
+            remote_target: request.header("Contact").map(uri_of).unwrap_or_default().to_string(),
+            route_set,
+            peer: source,
+            local_cseq: 1,
+            local_ip: local_ip_towards(source, self.config.interface),
+            local_port,
+            session_id: random_u64() >> 1,
+            sdp_version: 1,
+            invite: Some(request.clone()),
+            invite_source: Some(source),
+            remote_hold: offer.direction.is_hold(),
+            offer: Some(offer),
+            media: None,
+            local_hold: false,
+            cancel_pending: false,
+            pending_final: None,
+        };
+        let local_tag = call.local_tag.clone();
+        self.calls.insert(id, call);
+
+        let contact = self.contact(self.calls[&id].local_ip);
+        let mut ringing = SipMessage::response_to(&request, 180, "Ringing");
+        ringing.set_header("To", format!("{};tag={}", request.header("To").unwrap_or_default(), local_tag));
+        ringing.add_header("Contact", contact);
+        self.reply_with(&request, source, ringing);
+
+        let from = uri_of(request.header("From").unwrap_or_default()).to_string();
+        self.emit(UaEvent::Incoming { call: id, from });
+    }
+
+    /// Final response to the incoming INVITE of `call` (200 retransmitted
+    /// until ACKed)
+    fn respond_to_invite(&mut self, id: u32, code: u16, sdp: Option<String>) {
+        let Some(call) = self.calls.get(&id) else { return };
+        let (Some(invite), Some(source)) = (call.invite.clone(), call.invite_source) else { return };
+        let contact = self.contact(call.local_ip);
+        let local_tag = call.local_tag.clone();
+
+        let mut response = SipMessage::response_to(&invite, code, reason_phrase(code));
+        if invite.to_tag().is_none() {
+            response.set_header("To", format!("{};tag={}", invite.header("To").unwrap_or_default(), local_tag));
+        }
+        if (200..300).contains(&code) {
+            response.add_header("Contact", contact);
+            response.add_header("Allow", ALLOW);
+            response.add_header("User-Agent", USER_AGENT);
+            for route in invite.header_values("Record-Route") {
+                response.add_header("Record-Route", route.to_string());
+            }
+        }
+        if let Some(sdp) = sdp {
+            response.add_header("Content-Type", "application/sdp");
+            response.body = sdp.into_bytes();
+        }
+
+        let bytes = response.to_bytes();
+        if (200..300).contains(&code) {
+            let now = Instant::now();
+            if let Some(call) = self.calls.get_mut(&id) {
+                call.pending_final = Some(Retransmit {
+                    bytes: bytes.clone(),
+                    dest: source,
+                    cseq: invite.cseq().map(|(n, _)| n).unwrap_or(0),
+                    next: now + T1,
+                    interval: T1,
+                    deadline: now + TRANSACTION_TIMEOUT,
+                });
+            }
+        }
+        self.reply_with(&invite, source, response);
+    }
+
+    fn answer(&mut self, id: u32) -> Result<(), String> {
+        let call = self.calls.get(&id).ok_or("No such call")?;
+        if call.state != CallState::Incoming || call.terminated {
+            return Err("Call is not ringing".to_string());
+        }
+        let offer = call.offer.clone().ok_or("No offer")?;
+        let (codec, payload_codec) = sdp::negotiate(&offer, &self.config.codecs).ok_or("No common codec")?;
+        let media = NegotiatedMedia {
+            local_port: call.local_port,
+            remote: SocketAddrV4::new(offer.addr, offer.port),
+            codec: payload_codec,
+            payload_type: codec.payload_type,
+            channels: codec.channels,
+            ptime: offer.ptime.unwrap_or(self.config.ptime),
+        };
+
+        let call = self.calls.get_mut(&id).ok_or("No such call")?;
+        call.media = Some(media);
+        call.state = CallState::Established;
+        let remote_hold = call.remote_hold;
+        let sdp = self.local_sdp(id, Some(answer_direction(offer.direction, false)));
+        self.respond_to_invite(id, 200, sdp);
+
+        self.emit(UaEvent::Established { call: id, media });
+        if remote_hold {
+            self.emit(UaEvent::Held { call: id });
+        }
+        Ok(())
+    }
+
+    fn on_ack(&mut self, id: u32, request: &SipMessage) {
+        let Some(call) = self.calls.get_mut(&id) else { return };
+        let cseq = request.cseq().map(|(n, _)| n).unwrap_or(0);
+        if call.pending_final.as_ref().is_some_and(|p| p.cseq == cseq) {
+            call.pending_final = None;
+        }
+    }
+
+    fn on_cancel(&mut self, request: SipMessage, source: SocketAddr) {
+        let call_id = request.call_id().unwrap_or_default();
+        let branch = request.branch();
+        let found = self
+            .calls
+            .iter()
+            .find(|(_, c)| {
+                c.state == CallState::Incoming
+                    && c.call_id == call_id
+                    && c.invite.as_ref().and_then(|i| i.branch()) == branch
+            })
+            .map(|(&id, _)| id);
+
+        match found {
+            Some(id) => {
+                self.reply(&request, source, 200, None, None);
+                self.respond_to_invite(id, 487, None);
+                self.end_call(id, 487);
+            }
+            None => self.reply(&request, source, 481, None, None),
+        }
+    }
+
+    fn on_reinvite(&mut self, id: u32, request: SipMessage, source: SocketAddr) {
+        let Some(call) = self.calls.get_mut(&id) else { return };
+        if call.state != CallState::Established {
+            self.reply(&request, source, 491, None, None);
+            return;
+        }
+        if let Some(contact) = request.header("Contact") {
+            call.remote_target = uri_of(contact).to_string();
+        }
+
+        let offer = std::str::from_utf8(&request.body).ok().and_then(|b| sdp::parse(b).ok());
+        let was_active = call.media_active();
+        let old_media = call.media;
+        let mut direction = if call.local_hold { Direction::SendOnly } else { Direction::SendRecv };
+
+        if let Some(offer) = &offer {
+            let codec = old_media.map(|m| vec![m.codec]).unwrap_or_default();
+            match sdp::negotiate(offer, &codec) {
+                Some((sdp_codec, payload_codec)) => {
+                    call.media = Some(NegotiatedMedia {
+                        local_port: call.local_port,
+                        remote: SocketAddrV4::new(offer.addr, offer.port),
+                        codec: payload_codec,
+                        payload_type: sdp_codec.payload_type,
+                        channels: sdp_codec.channels,
+                        ptime: offer.ptime.unwrap_or(self.config.ptime),
+                    });
+                }
+                None => {
+                    self.reply(&request, source, 488, None, None);
+                    return;
+                }
+            }
+            call.remote_hold = offer.direction.is_hold();
+            direction = answer_direction(offer.direction, call.local_hold);
+        }
+
+        call.invite = Some(request.clone());
+        call.invite_source = Some(source);
+        let is_active = call.media_active();
+        let media = call.media;
+
+        let sdp = self.local_sdp(id, Some(direction));
+        self.respond_to_invite(id, 200, sdp);
+
+        match (was_active, is_active, media) {
+            (true, false, _) => self.emit(UaEvent::Held { call: id }),
+            (false, true, Some(media)) => self.emit(UaEvent::Resumed { call: id, media }),
+            (true, true, Some(media)) if Some(media) != old_media => {
+                self.emit(UaEvent::MediaChanged { call: id, media })
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(port: u16) -> UaConfig {
+        UaConfig {
+            interface: Ipv4Addr::LOCALHOST,
+            port,
+            username: format!("user{}", port),
+            codecs: vec![PayloadCodec::G711Ulaw],
+            ..UaConfig::default()
+        }
+    }
+
+    fn next_event(events: &Receiver<UaEvent>) -> UaEvent {
+        events.recv_timeout(Duration::from_secs(5)).expect("no event")
+    }
+
+    #[test]
+    fn test_call_answer_hold_and_hangup() {
+        let (alice, alice_events) = UserAgent::new(config(0)).unwrap();
+        let (bob, bob_events) = UserAgent::new(config(0)).unwrap();
+
+        let target = format!("sip:bob@{}", bob.local_addr());
+        let call = alice.call(&target).unwrap();
+
+        let UaEvent::Incoming { call: incoming, from } = next_event(&bob_events) else { panic!() };
+        assert!(from.starts_with("sip:user0@"));
+        assert_eq!(next_event(&alice_events), UaEvent::Ringing { call });
+
+        bob.answer(incoming).unwrap();
+        let UaEvent::Established { media: bob_media, .. } = next_event(&bob_events) else { panic!() };
+        let UaEvent::Established { media: alice_media, .. } = next_event(&alice_events) else { panic!() };
+        assert_eq!(alice_media.codec, PayloadCodec::G711Ulaw);
+        assert_eq!(alice_media.remote.port(), bob_media.local_port);
+        assert_eq!(bob_media.remote.port(), alice_media.local_port);
+
+        alice.hold(call, true).unwrap();
+        assert_eq!(next_event(&bob_events), UaEvent::Held { call: incoming });
+        assert_eq!(next_event(&alice_events), UaEvent::Held { call });
+        alice.hold(call, false).unwrap();
+        assert!(matches!(next_event(&bob_events), UaEvent::Resumed { .. }));
+        assert!(matches!(next_event(&alice_events), UaEvent::Resumed { .. }));
+
+        bob.hangup(incoming).unwrap();
+        assert_eq!(next_event(&bob_events), UaEvent::Ended { call: incoming, code: 0 });
+        assert_eq!(next_event(&alice_events), UaEvent::Ended { call, code: 0 });
+    }
+
+    #[test]
+    fn test_cancel_while_ringing() {
+        let (alice, alice_events) = UserAgent::new(config(0)).unwrap();
+        let (bob, bob_events) = UserAgent::new(config(0)).unwrap();
+
+        let call = alice.call(&format!("sip:bob@{}", bob.local_addr())).unwrap();
+        let UaEvent::Incoming { call: incoming, .. } = next_event(&bob_events) else { panic!() };
+        assert_eq!(next_event(&alice_events), UaEvent::Ringing { call });
+
+        alice.hangup(call).unwrap();
+        assert_eq!(next_event(&alice_events), UaEvent::Ended { call, code: 0 });
+        assert_eq!(next_event(&bob_events), UaEvent::Ended { call: incoming, code: 487 });
+    }
+
+    /// Registrar stand-in: challenges the first REGISTER, accepts the
+    /// authenticated one
+    #[test]
+    fn test_register_with_digest_auth() {
+        let registrar = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        registrar.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let server = registrar.local_addr().unwrap();
+
+        let stand_in = thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            let mut authorization = None;
+            while authorization.is_none() {
+                let (len, source) = registrar.recv_from(&mut buf).unwrap();
+                let request = SipMessage::parse(&buf[..len]).unwrap();
+                let mut response = match request.header("Authorization") {
+                    None => {
+                        let mut r = SipMessage::response_to(&request, 401, "Unauthorized");
+                        r.add_header("WWW-Authenticate", "Digest realm=\"pbx\", nonce=\"abc\", qop=\"auth\"");
+                        r
+                    }
+                    Some(auth) => {
+                        authorization = Some(auth.to_string());
+                        SipMessage::response_to(&request, 200, "OK")
+                    }
+                };
+                response.add_header("Expires", "120");
+                registrar.send_to(&response.to_bytes(), source).unwrap();
+            }
+            authorization.unwrap()
+        });
+
+        let (ua, events) = UserAgent::new(UaConfig {
+            server: Some(server),
+            password: "secret".to_string(),
+            ..config(0)
+        })
+        .unwrap();
+        ua.register().unwrap();
+
+        assert_eq!(next_event(&events), UaEvent::Registered { expires: 120 });
+        let authorization = stand_in.join().unwrap();
+        assert!(authorization.contains("username=\"user0\""));
+        assert!(authorization.contains("realm=\"pbx\""));
+    }
+}
@@ -4,6 +4,7 @@
 //! Always reports "locked" with 0 ppm correction (nominal rate).
 //! Cross-platform: Windows and Linux.
 
+use std::collections::HashMap;
 use std::ffi::{c_char, c_void};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::OnceLock;
@@ -45,6 +46,12 @@ static STATS_STRING: OnceLock<Mutex<String>> = OnceLock::new();
 /// Timer instance
 static TIMER: OnceLock<Mutex<Option<Timer>>> = OnceLock::new();
 
+/// Timers created with BASS_SYS_CreateTimer, by handle
+static TIMERS: OnceLock<Mutex<HashMap<u32, Timer>>> = OnceLock::new();
+
+/// Next timer handle
+static NEXT_TIMER: AtomicU32 = AtomicU32::new(1);
+
 // ============================================================================
 // C API Functions
 // ============================================================================
@@ -109,13 +116,19 @@ fn force_stop() {
     REF_COUNT.store(0, Ordering::SeqCst);
     RUNNING.store(false, Ordering::SeqCst);
 
-    // Stop timer if running
+    // Stop timers if running
     if let Some(timer_mutex) = TIMER.get() {
         let mut timer_guard = timer_mutex.lock();
         if let Some(timer) = timer_guard.take() {
             timer.stop();
         }
     }
+    if let Some(timers) = TIMERS.get() {
+        let stopped: Vec<Timer> = timers.lock().drain().map(|(_, t)| t).collect();
+        for timer in stopped {
+            timer.stop();
+        }
+    }
 }
 
 /// Check if system clock is running.
@@ -247,14 +260,7 @@ pub unsafe extern "C" fn BASS_SYS_TimerStart(
         return BASS_SYS_ERROR_ALREADY;
     }
 
-    let cb: Option<TimerCallback> = callback.map(|f| {
-        let user_ptr = user as usize;
-        Box::new(move || {
-            f(user_ptr as *mut c_void);
-        }) as TimerCallback
-    });
-
-    match Timer::start(interval_ms, cb) {
+    match Timer::start(interval_ms, timer_callback(callback, user)) {
         Ok(timer) => {
             *timer_guard = Some(timer);
             BASS_SYS_OK
@@ -349,21 +355,124 @@ pub unsafe extern "C" fn BASS_SYS_TimerIsPLLEnabled() -> i32 {
     0
 }
 
+/// Wrap a C timer callback and its user data
+fn timer_callback(callback: Option<BASS_SYS_TimerProc>, user: *mut c_void) -> Option<TimerCallback> {
+    callback.map(|f| {
+        let user_ptr = user as usize;
+        Box::new(move || unsafe {
+            f(user_ptr as *mut c_void);
+        }) as TimerCallback
+    })
+}
+
+fn timers() -> &'static Mutex<HashMap<u32, Timer>> {
+    TIMERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Create an independent precision timer.
+///
+/// Any number of timers can run at once, each with its own interval and
+/// callback. The timer starts ticking immediately.
+///
+/// # Arguments
+/// * `interval_ms` - Timer period in milliseconds (1-1000)
+/// * `callback` - Function to call on each tick (can be NULL)
+/// * `user` - User data passed to callback
+///
+/// # Returns
+/// * Timer handle, or 0 if interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_SYS_CreateTimer(
+    interval_ms: u32,
+    callback: Option<BASS_SYS_TimerProc>,
+    user: *mut c_void,
+) -> u32 {
+    match Timer::start(interval_ms, timer_callback(callback, user)) {
+        Ok(timer) => {
+            let handle = NEXT_TIMER.fetch_add(1, Ordering::SeqCst);
+            timers().lock().insert(handle, timer);
+            handle
+        }
+        Err(_) => 0,
+    }
+}
+
+/// Stop a timer created with BASS_SYS_CreateTimer and free its handle.
+///
+/// Can be called from the timer's own callback.
+///
+/// # Returns
+/// * BASS_SYS_OK on success
+/// * BASS_SYS_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_SYS_DestroyTimer(handle: u32) -> i32 {
+    // Take the timer out first so the lock isn't held while joining
+    let timer = timers().lock().remove(&handle);
+    match timer {
+        Some(timer) => {
+            timer.stop();
+            BASS_SYS_OK
+        }
+        None => BASS_SYS_ERROR_INVALID,
+    }
+}
+
+/// Set a timer's interval (can change while running).
+///
+/// # Returns
+/// * BASS_SYS_OK on success
+/// * BASS_SYS_ERROR_INVALID if handle is unknown or interval is out of range
+#[no_mangle]
+pub unsafe extern "C" fn BASS_SYS_TimerSetIntervalEx(handle: u32, interval_ms: u32) -> i32 {
+    if interval_ms == 0 || interval_ms > 1000 {
+        return BASS_SYS_ERROR_INVALID;
+    }
+
+    match timers().lock().get(&handle) {
+        Some(timer) => {
+            timer.set_interval(interval_ms);
+            BASS_SYS_OK
+        }
+        None => BASS_SYS_ERROR_INVALID,
+    }
+}
+
+/// Get a timer's interval.
+///
+/// # Returns
+/// * Interval in milliseconds, or 0 if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_SYS_TimerGetIntervalEx(handle: u32) -> u32 {
+    timers().lock().get(&handle).map(|t| t.get_interval()).unwrap_or(0)
+}
+
+/// Enable or disable PLL frequency adjustment of a timer.
+///
+/// For system clock, PLL has no effect (always runs at nominal rate).
+///
+/// # Returns
+/// * BASS_SYS_OK on success
+/// * BASS_SYS_ERROR_INVALID if handle is unknown
+#[no_mangle]
+pub unsafe extern "C" fn BASS_SYS_TimerSetPLLEx(handle: u32, _enabled: i32) -> i32 {
+    if timers().lock().contains_key(&handle) { BASS_SYS_OK } else { BASS_SYS_ERROR_INVALID }
+}
+
 // ============================================================================
-// Windows DLL Entry Point
+// Windows DLL Entry Point (left out when linked into a plugin)
 // ============================================================================
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_ATTACH: u32 = 1;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 const DLL_PROCESS_DETACH: u32 = 0;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type BOOL = i32;
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 type DWORD = u32;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "dll-main"))]
 #[no_mangle]
 pub unsafe extern "system" fn DllMain(
     _hinst: *mut c_void,
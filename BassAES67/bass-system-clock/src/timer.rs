@@ -1,6 +1,7 @@
 //! High-precision timer for no-soundcard mode.
 //!
-//! Provides a timer that fires at configurable intervals (default 20ms).
+//! Provides timers that fire at configurable intervals (default 20ms). Each
+//! timer has its own interval, so several can run at once.
 //! Since this is a system clock (free-running), no PLL adjustment is applied.
 //! Cross-platform: Windows and Linux.
 
@@ -11,13 +12,11 @@ use std::thread::{self, JoinHandle};
 /// Timer callback type (boxed closure)
 pub type TimerCallback = Box<dyn Fn() + Send + 'static>;
 
-/// Timer configuration
-static TIMER_INTERVAL_MS: AtomicU32 = AtomicU32::new(20);
-
 /// Timer handle
 pub struct Timer {
     running: Arc<AtomicBool>,
     thread: Option<JoinHandle<()>>,
+    interval_ms: Arc<AtomicU32>,
 }
 
 impl Timer {
@@ -35,7 +34,8 @@ impl Timer {
             return Err("Invalid interval".to_string());
         }
 
-        TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+        let interval = Arc::new(AtomicU32::new(interval_ms));
+        let interval_clone = interval.clone();
 
         let running = Arc::new(AtomicBool::new(true));
         let running_clone = running.clone();
@@ -43,34 +43,40 @@ impl Timer {
         let thread = thread::Builder::new()
             .name("sys-clock-timer".to_string())
             .spawn(move || {
-                timer_thread(running_clone, callback);
+                timer_thread(running_clone, interval_clone, callback);
             })
             .map_err(|e| format!("Failed to spawn timer thread: {}", e))?;
 
         Ok(Timer {
             running,
             thread: Some(thread),
+            interval_ms: interval,
         })
     }
 
     /// Stop the timer.
+    ///
+    /// Waits for the timer thread to finish, unless called from the timer's
+    /// own callback.
     pub fn stop(mut self) {
         self.running.store(false, Ordering::SeqCst);
         if let Some(thread) = self.thread.take() {
-            let _ = thread.join();
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
         }
     }
 
     /// Set timer interval (can change while running).
     pub fn set_interval(&self, interval_ms: u32) {
         if interval_ms > 0 && interval_ms <= 1000 {
-            TIMER_INTERVAL_MS.store(interval_ms, Ordering::SeqCst);
+            self.interval_ms.store(interval_ms, Ordering::SeqCst);
         }
     }
 
     /// Get current timer interval.
     pub fn get_interval(&self) -> u32 {
-        TIMER_INTERVAL_MS.load(Ordering::SeqCst)
+        self.interval_ms.load(Ordering::SeqCst)
     }
 }
 
@@ -83,7 +89,11 @@ impl Drop for Timer {
 
 /// Windows timer thread using waitable timer.
 #[cfg(windows)]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    interval: Arc<AtomicU32>,
+    callback: Option<TimerCallback>,
+) {
     use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0, WAIT_TIMEOUT};
     use windows_sys::Win32::System::Threading::{
         CreateWaitableTimerW, SetWaitableTimer, WaitForSingleObject,
@@ -95,11 +105,11 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
 
     if timer_handle.is_null() || timer_handle == 0 as HANDLE {
         // Fallback to sleep-based timer
-        return timer_thread_fallback(running, callback);
+        return timer_thread_fallback(running, interval, callback);
     }
 
     while running.load(Ordering::SeqCst) {
-        let interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
+        let interval_ms = interval.load(Ordering::SeqCst);
         let interval_100ns = (interval_ms as i64) * 10_000;
 
         // Set timer (negative value = relative time in 100ns units)
@@ -137,13 +147,17 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
 
 /// Windows fallback timer using Sleep.
 #[cfg(windows)]
-fn timer_thread_fallback(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
+fn timer_thread_fallback(
+    running: Arc<AtomicBool>,
+    interval: Arc<AtomicU32>,
+    callback: Option<TimerCallback>,
+) {
     use std::time::{Duration, Instant};
 
     let mut next_tick = Instant::now();
 
     while running.load(Ordering::SeqCst) {
-        let interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
+        let interval_ms = interval.load(Ordering::SeqCst);
         next_tick += Duration::from_millis(interval_ms as u64);
 
         let now = Instant::now();
@@ -165,13 +179,17 @@ fn timer_thread_fallback(running: Arc<AtomicBool>, callback: Option<TimerCallbac
 
 /// Linux/Unix timer thread using nanosleep.
 #[cfg(unix)]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    interval: Arc<AtomicU32>,
+    callback: Option<TimerCallback>,
+) {
     use std::time::{Duration, Instant};
 
     let mut next_tick = Instant::now();
 
     while running.load(Ordering::SeqCst) {
-        let interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
+        let interval_ms = interval.load(Ordering::SeqCst);
         next_tick += Duration::from_millis(interval_ms as u64);
 
         let now = Instant::now();
@@ -193,11 +211,15 @@ fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
 
 /// Fallback for other platforms.
 #[cfg(not(any(windows, unix)))]
-fn timer_thread(running: Arc<AtomicBool>, callback: Option<TimerCallback>) {
+fn timer_thread(
+    running: Arc<AtomicBool>,
+    interval: Arc<AtomicU32>,
+    callback: Option<TimerCallback>,
+) {
     use std::time::Duration;
 
     while running.load(Ordering::SeqCst) {
-        let interval_ms = TIMER_INTERVAL_MS.load(Ordering::SeqCst);
+        let interval_ms = interval.load(Ordering::SeqCst);
         thread::sleep(Duration::from_millis(interval_ms as u64));
 
         if let Some(ref cb) = callback {